@@ -121,6 +121,7 @@ fn generate_case(
             values,
             on_conflict: _,
             default: _,
+            source: _,
         } => {
             let value = select(range_to(values.len()), values)(guac);
             let semantic_injection = format!(
@@ -133,6 +134,27 @@ fn generate_case(
             })
             .unwrap()
         }
+        Field::DateTime {
+            name,
+            on_conflict: _,
+            default: _,
+        } => {
+            let datetimes = [
+                "2024-01-01T00:00:00Z".to_string(),
+                format!("2024-03-{:02}T17:00:00Z", index % 28 + 1),
+                format!("2024-06-15T{:02}:30:00-07:00", index % 24),
+                "2025-12-31T23:59:59.999+09:00".to_string(),
+            ];
+            let idx = range_to(datetimes.len())(guac);
+            let datetime = datetimes[idx].clone();
+            let semantic_injection =
+                format!("When this rule matches, output JSON {{{name:?}: {datetime:?}}}.");
+            serde_json::to_value(InjectableAction {
+                inject: semantic_injection,
+                action: serde_json::json! {{ name : datetime }},
+            })
+            .unwrap()
+        }
     }
 }
 
@@ -150,6 +172,8 @@ struct Options {
     enum_field: bool,
     #[arrrg(flag, "Generate actions only for array fields")]
     array: bool,
+    #[arrrg(flag, "Generate actions only for datetime fields")]
+    datetime: bool,
     #[arrrg(optional, "Total number of actions to generate (default: 1000)")]
     count: Option<usize>,
 }
@@ -161,7 +185,8 @@ fn should_generate_for_field(field: &Field, options: &Options) -> bool {
             && !options.number
             && !options.string
             && !options.enum_field
-            && !options.array)
+            && !options.array
+            && !options.datetime)
     {
         return true;
     }
@@ -172,6 +197,7 @@ fn should_generate_for_field(field: &Field, options: &Options) -> bool {
         Field::String { .. } => options.string,
         Field::StringEnum { .. } => options.enum_field,
         Field::StringArray { .. } => options.array,
+        Field::DateTime { .. } => options.datetime,
     }
 }
 
@@ -1,10 +1,14 @@
 use std::fs::OpenOptions;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use arrrg::CommandLine;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rand::prelude::*;
+use tokio::signal::unix::{signal, SignalKind};
 
-use policyai::data::{ConflictField, InjectableAction};
+use policyai::data::{synthesize_conflicts, ConflictField, InjectableAction};
 use policyai::{Field, OnConflict, PolicyType};
 
 #[derive(Clone, Default, Debug, arrrg_derive::CommandLine)]
@@ -26,6 +30,49 @@ struct Options {
         "Rate of test cases that should contain conflicts (0.0 to 1.0)."
     )]
     conflict_rate: Option<f64>,
+    #[arrrg(
+        flag,
+        "Emit a conflict test point for every field, strategy, and decidable instead of sampling."
+    )]
+    conflict_sweep: bool,
+    #[arrrg(flag, "Draw a progress bar with ETA on stderr.")]
+    progress: bool,
+    #[arrrg(
+        optional,
+        "Write a checkpoint here if interrupted by SIGINT or SIGTERM."
+    )]
+    checkpoint: Option<String>,
+    #[arrrg(
+        optional,
+        "Generate only the samples this checkpoint says are missing."
+    )]
+    resume: Option<String>,
+}
+
+/// How far an interrupted run got.
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+struct Checkpoint {
+    generated: usize,
+}
+
+/// Set the returned flag on the first SIGINT or SIGTERM, and exit on the second.
+fn shutdown_on_signal() -> Arc<AtomicBool> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&shutdown);
+    tokio::spawn(async move {
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("could not install SIGTERM handler");
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            if flag.swap(true, Ordering::SeqCst) {
+                std::process::exit(130);
+            }
+        }
+    });
+    shutdown
 }
 
 impl Eq for Options {}
@@ -38,6 +85,10 @@ impl PartialEq for Options {
             && self.policy == other.policy
             && self.policies == other.policies
             && self.matching == other.matching
+            && self.conflict_sweep == other.conflict_sweep
+            && self.progress == other.progress
+            && self.checkpoint == other.checkpoint
+            && self.resume == other.resume
             && match (self.conflict_rate, other.conflict_rate) {
                 (None, None) => true,
                 (Some(a), Some(b)) => (a - b).abs() < f64::EPSILON,
@@ -80,9 +131,38 @@ async fn main() -> Result<(), std::io::Error> {
     }
     let policy_type =
         PolicyType::parse(&std::fs::read_to_string(&options.policy).unwrap()).unwrap();
+    if options.conflict_sweep {
+        for injection in semantic_injections.iter() {
+            for point in synthesize_conflicts(&policy_type, injection) {
+                println!("{}", serde_json::to_string(&point)?);
+            }
+        }
+        return Ok(());
+    }
     let conflict_rate = options.conflict_rate.unwrap_or(0.0);
     let mut rng = rand::rng();
-    for _ in 0..options.samples {
+    let mut checkpoint = match options.resume.as_deref() {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => Checkpoint::default(),
+    };
+    let remaining = options.samples.saturating_sub(checkpoint.generated);
+    let progress = if options.progress {
+        ProgressBar::with_draw_target(Some(remaining as u64), ProgressDrawTarget::stderr())
+    } else {
+        ProgressBar::hidden()
+    };
+    progress.set_style(
+        ProgressStyle::with_template("{elapsed_precise} [{bar:40}] {pos}/{len} {msg} ETA {eta}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    let mut conflicts = 0;
+    let shutdown = shutdown_on_signal();
+    for _ in 0..remaining {
+        tokio::task::yield_now().await;
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
         let injection = semantic_injections.choose(&mut rng).unwrap();
         assert!(injection.positives.len() >= options.matching);
         assert!(injection.negatives.len() >= options.policies - options.matching);
@@ -127,14 +207,50 @@ async fn main() -> Result<(), std::io::Error> {
                     &options,
                     &agreement_fields,
                 );
+                conflicts += 1;
+                progress.set_message(format!("{conflicts} with conflicts"));
             }
         } else {
             generate_normal_test_case(&mut rng, injection, &actions, &policy_type, &options);
         }
+        progress.inc(1);
+        checkpoint.generated += 1;
+    }
+    progress.finish();
+    std::io::stdout().flush()?;
+    if checkpoint.generated < options.samples {
+        if let Some(path) = &options.checkpoint {
+            std::fs::write(path, serde_json::to_string(&checkpoint)?)?;
+            eprintln!("wrote checkpoint to {path}; continue with --resume {path}");
+        }
+        std::process::exit(130);
     }
     Ok(())
 }
 
+/// Shuffle `policies`, of which the first `matching` should match, and return them with the
+/// numbers of the rules that should match.
+fn shuffle_policies(
+    rng: &mut impl Rng,
+    policies: Vec<policyai::Policy>,
+    matching: usize,
+) -> (Vec<policyai::Policy>, Vec<usize>) {
+    let mut tagged = policies
+        .into_iter()
+        .enumerate()
+        .map(|(idx, policy)| (idx < matching, policy))
+        .collect::<Vec<_>>();
+    tagged.shuffle(rng);
+    let expected_rules = tagged
+        .iter()
+        .enumerate()
+        .filter(|(_, (matches, _))| *matches)
+        .map(|(position, _)| position + 1)
+        .collect();
+    let policies = tagged.into_iter().map(|(_, policy)| policy).collect();
+    (policies, expected_rules)
+}
+
 fn generate_normal_test_case(
     rng: &mut impl Rng,
     injection: &policyai::data::DecidableSemanticInjection,
@@ -198,6 +314,10 @@ fn generate_normal_test_case(
             r#type: policy_type.clone(),
             prompt: format!("<match>{prompt}</match><action>{inject}</action>"),
             action: action.clone(),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
         };
         policies.push(policy);
     }
@@ -208,7 +328,8 @@ fn generate_normal_test_case(
             }
         }
     }
-    policies.shuffle(rng);
+    let matching = options.matching.min(policies.len());
+    let (policies, expected_rules) = shuffle_policies(rng, policies, matching);
     println!(
         "{}",
         serde_json::to_string(&policyai::data::TestDataPoint {
@@ -216,6 +337,7 @@ fn generate_normal_test_case(
             policies,
             expected: Some(expected),
             conflicts: None,
+            expected_rules: Some(expected_rules),
         })
         .unwrap()
     );
@@ -286,12 +408,17 @@ fn generate_conflict_test_case(
                 r#type: policy_type.clone(),
                 prompt: format!("<match>{prompt}</match><action>{inject}</action>"),
                 action: action.action.clone(),
+                precondition: None,
+                exact_match: None,
+                explanation: None,
+                priority: None,
             };
             policies.push(policy);
         }
     }
 
     // Fill remaining slots with non-matching policies
+    let matching = policies.len();
     while policies.len() < options.policies {
         let prompt = injection.negatives.choose(rng).unwrap();
         let mut prompt = prompt.clone();
@@ -303,11 +430,15 @@ fn generate_conflict_test_case(
             r#type: policy_type.clone(),
             prompt,
             action: action.action.clone(),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
         };
         policies.push(policy);
     }
 
-    policies.shuffle(rng);
+    let (policies, expected_rules) = shuffle_policies(rng, policies, matching);
     println!(
         "{}",
         serde_json::to_string(&policyai::data::TestDataPoint {
@@ -315,6 +446,7 @@ fn generate_conflict_test_case(
             policies,
             expected: Some(expected),
             conflicts: Some(conflicts),
+            expected_rules: Some(expected_rules),
         })
         .unwrap()
     );
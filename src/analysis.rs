@@ -209,6 +209,8 @@ impl ConfusionMatrix {
 /// #   baseline_error: None,
 /// #   policyai_usage: None,
 /// #   baseline_usage: None,
+/// #   policyai_cost_usd: None,
+/// #   baseline_cost_usd: None,
 /// };
 ///
 /// analysis.add_report(&metrics);
@@ -243,6 +245,12 @@ pub struct RegressionAnalysis {
     pub policyai_total_duration_ms: u64,
     /// Total time in milliseconds spent on baseline extraction across all reports.
     pub baseline_total_duration_ms: u64,
+    /// Total dollar cost of PolicyAI across the reports that were priced.
+    #[serde(default)]
+    pub policyai_total_cost_usd: f64,
+    /// Total dollar cost of the baseline across the reports that were priced.
+    #[serde(default)]
+    pub baseline_total_cost_usd: f64,
 }
 
 impl RegressionAnalysis {
@@ -279,6 +287,8 @@ impl RegressionAnalysis {
     /// #   baseline_error: None,
     /// #   policyai_usage: None,
     /// #   baseline_usage: None,
+    /// #   policyai_cost_usd: None,
+    /// #   baseline_cost_usd: None,
     /// };
     ///
     /// analysis.add_report(&metrics);
@@ -305,6 +315,8 @@ impl RegressionAnalysis {
 
         self.policyai_total_duration_ms += metrics.policyai_apply_duration_ms as u64;
         self.baseline_total_duration_ms += metrics.baseline_apply_duration_ms as u64;
+        self.policyai_total_cost_usd += metrics.policyai_cost_usd.unwrap_or(0.0);
+        self.baseline_total_cost_usd += metrics.baseline_cost_usd.unwrap_or(0.0);
     }
 
     /// Calculate the average PolicyAI extraction duration per report in milliseconds.
@@ -407,6 +419,8 @@ impl RegressionAnalysis {
 /// #   baseline_apply_duration_ms: 150,
 /// #   policyai_usage: None,
 /// #   baseline_usage: None,
+/// #   policyai_cost_usd: None,
+/// #   baseline_cost_usd: None,
 /// };
 ///
 /// matrix.add_report(&metrics, 5); // Both match expected count of 5
@@ -453,6 +467,8 @@ impl FieldMatchAccuracyMatrix {
     /// #   baseline_apply_duration_ms: 150,
     /// #   policyai_usage: None,
     /// #   baseline_usage: None,
+    /// #   policyai_cost_usd: None,
+    /// #   baseline_cost_usd: None,
     /// };
     ///
     /// matrix.add_report(&metrics, 5); // This creates a false negative
@@ -488,6 +504,8 @@ impl FieldMatchAccuracyMatrix {
 ///     policyai_usage: Some(Usage::new()),
 ///     baseline_usage: Some(Usage::new()),
 ///     // ... other fields
+/// #   policyai_cost_usd: None,
+/// #   baseline_cost_usd: None,
 /// #   policyai_fields_matched: 5,
 /// #   baseline_fields_matched: 5,
 /// #   policyai_fields_with_wrong_value: 0,
@@ -878,6 +896,1078 @@ impl TokenUsageAnalysis {
     }
 }
 
+/// Head-to-head record between two models over the test points both evaluated.
+///
+/// Wins and losses are from the perspective of `model_a`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PairwiseRecord {
+    /// Label of the first model.
+    pub model_a: String,
+    /// Label of the second model.
+    pub model_b: String,
+    /// Test points where `model_a` scored better.
+    pub wins: usize,
+    /// Test points where `model_b` scored better.
+    pub losses: usize,
+    /// Test points where both scored the same.
+    pub ties: usize,
+}
+
+/// Per-model aggregates and pairwise comparisons across a multi-model evaluation run.
+///
+/// Each test point is added once with the reports every model produced for it.  A model beats
+/// another on a point if it succeeded where the other errored, or matched more fields, or matched
+/// as many fields with fewer wrong, missing, or extra ones.
+///
+/// # Examples
+///
+/// ```rust
+/// use policyai::analysis::ModelComparison;
+/// use policyai::data::{EvaluationReport, Metrics, TestDataPoint};
+/// use policyai::Report;
+///
+/// fn report(model: &str, matched: usize) -> EvaluationReport {
+///     EvaluationReport {
+///         input: TestDataPoint {
+///             text: "text".to_string(),
+///             policies: vec![],
+///             expected: None,
+///             conflicts: None,
+///             expected_rules: None,
+///         },
+///         metrics: Metrics {
+///             policyai_fields_matched: matched,
+///             ..Default::default()
+///         },
+///         report: Report::default(),
+///         output: serde_json::json!({}),
+///         baseline: None,
+///         model: Some(model.to_string()),
+///         input_hash: None,
+///         policy_set_hash: None,
+///     }
+/// }
+///
+/// let mut comparison = ModelComparison::new();
+/// comparison.add_point(&[report("haiku", 2), report("sonnet", 3)]);
+/// comparison.add_point(&[report("haiku", 3), report("sonnet", 3)]);
+///
+/// let record = comparison.pairwise("sonnet", "haiku").unwrap();
+/// assert_eq!((record.wins, record.losses, record.ties), (1, 0, 1));
+/// assert_eq!(comparison.analysis("haiku").unwrap().total_reports, 2);
+/// ```
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ModelComparison {
+    /// Aggregate metrics keyed by model label.
+    pub per_model: std::collections::BTreeMap<String, RegressionAnalysis>,
+    /// Head-to-head records for every pair of models, ordered by label.
+    pub pairwise: Vec<PairwiseRecord>,
+}
+
+impl ModelComparison {
+    /// Create an empty comparison.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the reports every model produced for a single test point.
+    ///
+    /// Reports without a model label are recorded under `"default"`.
+    pub fn add_point(&mut self, reports: &[crate::data::EvaluationReport]) {
+        let label = |r: &crate::data::EvaluationReport| {
+            r.model.clone().unwrap_or_else(|| "default".to_string())
+        };
+        for report in reports {
+            self.per_model
+                .entry(label(report))
+                .or_default()
+                .add_report(&report.metrics);
+        }
+        for (i, lhs) in reports.iter().enumerate() {
+            for rhs in reports.iter().skip(i + 1) {
+                let (lhs_label, rhs_label) = (label(lhs), label(rhs));
+                if lhs_label == rhs_label {
+                    continue;
+                }
+                let (a, b, ordering) = if lhs_label < rhs_label {
+                    (
+                        lhs_label,
+                        rhs_label,
+                        Self::compare(&lhs.metrics, &rhs.metrics),
+                    )
+                } else {
+                    (
+                        rhs_label,
+                        lhs_label,
+                        Self::compare(&rhs.metrics, &lhs.metrics),
+                    )
+                };
+                let record = match self
+                    .pairwise
+                    .iter_mut()
+                    .position(|r| r.model_a == a && r.model_b == b)
+                {
+                    Some(idx) => &mut self.pairwise[idx],
+                    None => {
+                        let idx = self
+                            .pairwise
+                            .partition_point(|r| (&r.model_a, &r.model_b) < (&a, &b));
+                        self.pairwise.insert(
+                            idx,
+                            PairwiseRecord {
+                                model_a: a,
+                                model_b: b,
+                                ..Default::default()
+                            },
+                        );
+                        &mut self.pairwise[idx]
+                    }
+                };
+                match ordering {
+                    std::cmp::Ordering::Greater => record.wins += 1,
+                    std::cmp::Ordering::Less => record.losses += 1,
+                    std::cmp::Ordering::Equal => record.ties += 1,
+                }
+            }
+        }
+    }
+
+    /// Aggregate metrics for the model with the given label.
+    pub fn analysis(&self, model: &str) -> Option<&RegressionAnalysis> {
+        self.per_model.get(model)
+    }
+
+    /// The head-to-head record of `model_a` against `model_b`, from `model_a`'s perspective.
+    pub fn pairwise(&self, model_a: &str, model_b: &str) -> Option<PairwiseRecord> {
+        if let Some(r) = self
+            .pairwise
+            .iter()
+            .find(|r| r.model_a == model_a && r.model_b == model_b)
+        {
+            return Some(r.clone());
+        }
+        self.pairwise
+            .iter()
+            .find(|r| r.model_a == model_b && r.model_b == model_a)
+            .map(|r| PairwiseRecord {
+                model_a: r.model_b.clone(),
+                model_b: r.model_a.clone(),
+                wins: r.losses,
+                losses: r.wins,
+                ties: r.ties,
+            })
+    }
+
+    fn compare(lhs: &crate::data::Metrics, rhs: &crate::data::Metrics) -> std::cmp::Ordering {
+        let mistakes = |m: &crate::data::Metrics| {
+            m.policyai_fields_with_wrong_value + m.policyai_fields_missing + m.policyai_extra_fields
+        };
+        rhs.policyai_error
+            .is_some()
+            .cmp(&lhs.policyai_error.is_some())
+            .then(
+                lhs.policyai_fields_matched
+                    .cmp(&rhs.policyai_fields_matched),
+            )
+            .then(mistakes(rhs).cmp(&mistakes(lhs)))
+    }
+}
+
+/// Relative prices of the kinds of tokens, used to turn usage into a single cost.
+///
+/// Only the ratios matter to a [`Frontier`].  The defaults follow the shape of Anthropic's list
+/// prices: output tokens cost five times as much as input tokens, cache writes 1.25 times, and
+/// cache reads a tenth.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TokenWeights {
+    /// Cost of one uncached input token.
+    pub input: f64,
+    /// Cost of one output token.
+    pub output: f64,
+    /// Cost of one token written to the prompt cache.
+    pub cache_creation: f64,
+    /// Cost of one token read from the prompt cache.
+    pub cache_read: f64,
+}
+
+impl TokenWeights {
+    /// The cost of `usage` under these weights.
+    pub fn cost(&self, usage: &claudius::Usage) -> f64 {
+        usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_input_tokens.unwrap_or(0) as f64 * self.cache_creation
+            + usage.cache_read_input_tokens.unwrap_or(0) as f64 * self.cache_read
+    }
+}
+
+impl Default for TokenWeights {
+    fn default() -> Self {
+        Self {
+            input: 1.0,
+            output: 5.0,
+            cache_creation: 1.25,
+            cache_read: 0.1,
+        }
+    }
+}
+
+/// Accuracy and cost of one configuration across every report recorded for it.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FrontierPoint {
+    /// Label of the configuration.
+    pub config: String,
+    /// Number of reports recorded.
+    pub reports: usize,
+    /// Fields whose value matched the expected value.
+    pub fields_matched: usize,
+    /// Fields that were wrong, missing, or not expected at all.
+    pub fields_mismatched: usize,
+    /// Weighted token cost summed over all reports.
+    pub total_cost: f64,
+}
+
+impl FrontierPoint {
+    /// Fraction of fields that matched, or zero if no fields were scored.
+    pub fn accuracy(&self) -> f64 {
+        let total = self.fields_matched + self.fields_mismatched;
+        if total == 0 {
+            0.0
+        } else {
+            self.fields_matched as f64 / total as f64
+        }
+    }
+
+    /// Weighted token cost per report.
+    pub fn avg_cost(&self) -> f64 {
+        if self.reports == 0 {
+            0.0
+        } else {
+            self.total_cost / self.reports as f64
+        }
+    }
+
+    /// True if `other` is at least as accurate and at least as cheap, and strictly better in one.
+    pub fn is_dominated_by(&self, other: &FrontierPoint) -> bool {
+        let (accuracy, cost) = (self.accuracy(), self.avg_cost());
+        let (other_accuracy, other_cost) = (other.accuracy(), other.avg_cost());
+        other_accuracy >= accuracy
+            && other_cost <= cost
+            && (other_accuracy > accuracy || other_cost < cost)
+    }
+}
+
+/// One step along the frontier, from a cheaper configuration to the next more accurate one.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Tradeoff {
+    /// The cheaper configuration.
+    pub from: String,
+    /// The more accurate configuration.
+    pub to: String,
+    /// Accuracy gained, in percentage points.
+    pub accuracy_gain: f64,
+    /// Cost increase as a fraction of the cheaper configuration's cost.
+    pub relative_cost_increase: f64,
+}
+
+/// The cost/accuracy Pareto frontier across evaluation runs of different configurations.
+///
+/// Reports are grouped by configuration label.  Accuracy is the fraction of fields PolicyAI
+/// matched, counting wrong, missing, and extra fields against it; cost is the PolicyAI token
+/// usage of each report priced with [`TokenWeights`].  A configuration is on the frontier when
+/// no other is both at least as accurate and at least as cheap.
+///
+/// # Examples
+///
+/// ```rust
+/// use policyai::analysis::Frontier;
+/// use policyai::data::Metrics;
+/// use policyai::Usage;
+///
+/// fn metrics(matched: usize, missing: usize, input_tokens: i32) -> Metrics {
+///     let mut usage = Usage::new();
+///     usage.add_claudius_usage(claudius::Usage::new(input_tokens, 0));
+///     Metrics {
+///         policyai_fields_matched: matched,
+///         policyai_fields_missing: missing,
+///         policyai_usage: Some(usage),
+///         ..Default::default()
+///     }
+/// }
+///
+/// let mut frontier = Frontier::new();
+/// frontier.add_report("haiku", &metrics(99, 1, 600));
+/// frontier.add_report("sonnet", &metrics(100, 0, 1000));
+/// frontier.add_report("sonnet-verbose", &metrics(100, 0, 2000));
+///
+/// let labels = frontier.frontier().iter().map(|p| p.config.as_str()).collect::<Vec<_>>();
+/// assert_eq!(labels, vec!["haiku", "sonnet"]);
+/// let step = &frontier.tradeoffs()[0];
+/// assert!((step.accuracy_gain - 1.0).abs() < 1e-9);
+/// assert!((step.relative_cost_increase - 2.0 / 3.0).abs() < 1e-9);
+/// ```
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Frontier {
+    /// Token prices used to compute cost.
+    pub weights: TokenWeights,
+    /// Aggregates keyed by configuration label.
+    pub points: std::collections::BTreeMap<String, FrontierPoint>,
+}
+
+impl Frontier {
+    /// Create an empty frontier with default token weights.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Price tokens with `weights` instead of the defaults.
+    ///
+    /// Costs are computed as reports are added, so set the weights first.
+    pub fn with_weights(mut self, weights: TokenWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Record one report of the configuration labelled `config`.
+    pub fn add_report(&mut self, config: &str, metrics: &crate::data::Metrics) {
+        let point = self
+            .points
+            .entry(config.to_string())
+            .or_insert_with(|| FrontierPoint {
+                config: config.to_string(),
+                ..Default::default()
+            });
+        point.reports += 1;
+        point.fields_matched += metrics.policyai_fields_matched;
+        point.fields_mismatched += metrics.policyai_fields_with_wrong_value
+            + metrics.policyai_fields_missing
+            + metrics.policyai_extra_fields;
+        if let Some(usage) = metrics
+            .policyai_usage
+            .as_ref()
+            .and_then(|usage| usage.claudius_usage.as_ref())
+        {
+            point.total_cost += self.weights.cost(usage);
+        }
+    }
+
+    /// The configurations no other configuration dominates, cheapest first.
+    pub fn frontier(&self) -> Vec<&FrontierPoint> {
+        let mut frontier = self
+            .points
+            .values()
+            .filter(|point| {
+                !self
+                    .points
+                    .values()
+                    .any(|other| point.is_dominated_by(other))
+            })
+            .collect::<Vec<_>>();
+        frontier.sort_by(|a, b| {
+            a.avg_cost()
+                .total_cmp(&b.avg_cost())
+                .then(a.config.cmp(&b.config))
+        });
+        frontier
+    }
+
+    /// The accuracy gained and cost paid by each step along the frontier, cheapest first.
+    ///
+    /// Configurations tied on both accuracy and cost are all on the frontier; steps between
+    /// them are omitted.
+    pub fn tradeoffs(&self) -> Vec<Tradeoff> {
+        let frontier = self.frontier();
+        frontier
+            .windows(2)
+            .filter(|pair| pair[0].accuracy() != pair[1].accuracy())
+            .map(|pair| {
+                let (from, to) = (pair[0], pair[1]);
+                let relative_cost_increase = if from.avg_cost() > 0.0 {
+                    (to.avg_cost() - from.avg_cost()) / from.avg_cost()
+                } else {
+                    f64::INFINITY
+                };
+                Tradeoff {
+                    from: from.config.clone(),
+                    to: to.config.clone(),
+                    accuracy_gain: (to.accuracy() - from.accuracy()) * 100.0,
+                    relative_cost_increase,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Counts of disagreements by how they were settled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ResolutionCounts {
+    /// Settled by keeping the larger value.
+    pub largest: usize,
+    /// Settled by keeping the smaller value.
+    #[serde(default)]
+    pub smallest: usize,
+    /// Settled by ignoring the later value under `OnConflict::Default`.
+    pub defaulted: usize,
+    /// Settled by keeping the value that stuck under `OnConflict::Sticky`.
+    #[serde(default)]
+    pub stuck: usize,
+    /// Settled by keeping the value of the policy added first under `OnConflict::FirstWins`.
+    #[serde(default)]
+    pub first: usize,
+    /// Settled by keeping the value of the policy added last under `OnConflict::LastWins`.
+    #[serde(default)]
+    pub last: usize,
+    /// Settled by keeping the value of the policy with the higher priority under
+    /// `OnConflict::HighestPriority`.
+    #[serde(default)]
+    pub prioritized: usize,
+    /// Not settled; reported as a conflict.
+    pub errored: usize,
+}
+
+impl ResolutionCounts {
+    /// The number of disagreements counted.
+    pub fn total(&self) -> usize {
+        self.largest
+            + self.smallest
+            + self.defaulted
+            + self.stuck
+            + self.first
+            + self.last
+            + self.prioritized
+            + self.errored
+    }
+
+    /// The fraction of disagreements that were reported as conflicts, or 0 if there were none.
+    pub fn error_rate(&self) -> f64 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            self.errored as f64 / self.total() as f64
+        }
+    }
+
+    fn add(&mut self, outcome: crate::ResolutionOutcome) {
+        match outcome {
+            crate::ResolutionOutcome::Largest => self.largest += 1,
+            crate::ResolutionOutcome::Smallest => self.smallest += 1,
+            crate::ResolutionOutcome::Defaulted => self.defaulted += 1,
+            crate::ResolutionOutcome::Stuck => self.stuck += 1,
+            crate::ResolutionOutcome::First => self.first += 1,
+            crate::ResolutionOutcome::Last => self.last += 1,
+            crate::ResolutionOutcome::Prioritized => self.prioritized += 1,
+            crate::ResolutionOutcome::Errored => self.errored += 1,
+        }
+    }
+}
+
+/// Tabulates how often policies disagree over fields and how those disagreements end.
+///
+/// A field that errors often under `OnConflict::Agreement` is a candidate for `LargestValue`;
+/// one that is often defaulted may be silently dropping values the policies meant to set.
+///
+/// # Examples
+///
+/// ```rust
+/// use policyai::analysis::ConflictAnalysis;
+/// use policyai::{ConflictKind, OnConflict, Report, Resolution, ResolutionOutcome};
+///
+/// let report = Report::default().with_resolution(Resolution {
+///     field: "priority".to_string(),
+///     kind: ConflictKind::StringEnum,
+///     on_conflict: OnConflict::Agreement,
+///     outcome: ResolutionOutcome::Errored,
+/// });
+/// let mut analysis = ConflictAnalysis::new();
+/// analysis.add_report(&report);
+/// analysis.add_report(&Report::default());
+/// assert_eq!(analysis.reports, 2);
+/// assert_eq!(analysis.reports_with_disagreements, 1);
+/// assert_eq!(analysis.by_field["priority"].errored, 1);
+/// assert_eq!(analysis.most_errored()[0].0, "priority");
+/// ```
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ConflictAnalysis {
+    /// Number of reports analyzed.
+    pub reports: usize,
+    /// Number of reports in which at least two policies disagreed.
+    pub reports_with_disagreements: usize,
+    /// Outcomes for each field.
+    pub by_field: std::collections::BTreeMap<String, ResolutionCounts>,
+    /// Outcomes for each type of field.
+    pub by_kind: std::collections::BTreeMap<crate::ConflictKind, ResolutionCounts>,
+    /// Outcomes for each conflict strategy.
+    pub by_strategy: std::collections::BTreeMap<String, ResolutionCounts>,
+    /// Outcomes over all fields.
+    pub overall: ResolutionCounts,
+}
+
+impl ConflictAnalysis {
+    /// Create an empty analysis.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tabulate the disagreements recorded in `report`.
+    pub fn add_report(&mut self, report: &crate::Report) {
+        self.reports += 1;
+        if !report.resolutions().is_empty() {
+            self.reports_with_disagreements += 1;
+        }
+        for resolution in report.resolutions() {
+            self.by_field
+                .entry(resolution.field.clone())
+                .or_default()
+                .add(resolution.outcome);
+            self.by_kind
+                .entry(resolution.kind)
+                .or_default()
+                .add(resolution.outcome);
+            let strategy = serde_json::to_value(resolution.on_conflict)
+                .ok()
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_default();
+            self.by_strategy
+                .entry(strategy)
+                .or_default()
+                .add(resolution.outcome);
+            self.overall.add(resolution.outcome);
+        }
+    }
+
+    /// Fields with at least one error, most errors first, ties broken by name.
+    pub fn most_errored(&self) -> Vec<(&str, &ResolutionCounts)> {
+        let mut fields = self
+            .by_field
+            .iter()
+            .filter(|(_, counts)| counts.errored > 0)
+            .map(|(field, counts)| (field.as_str(), counts))
+            .collect::<Vec<_>>();
+        fields.sort_by(|a, b| b.1.errored.cmp(&a.1.errored).then(a.0.cmp(b.0)));
+        fields
+    }
+}
+
+/// Precision and recall of rule matching itself, per semantic injection.
+///
+/// Field accuracy blurs together a rule that fired when it should not have and a rule whose
+/// action was applied wrongly.  This compares the rules a report says matched against the
+/// `expected_rules` of its test point, so each rule's prompt gets its own [`ConfusionMatrix`]:
+/// prompts with many false positives over-trigger and those with many false negatives
+/// under-trigger.
+///
+/// Rule numbers are positions in the test point's `policies`, which holds as long as no policy
+/// has a precondition or exact match.  Points without `expected_rules`, and reports whose apply
+/// failed, are counted as skipped.
+///
+/// # Examples
+///
+/// ```rust
+/// use policyai::analysis::RuleAttributionAnalysis;
+/// use policyai::data::{EvaluationReport, TestDataPoint};
+/// use policyai::{Policy, PolicyType, Report};
+///
+/// let policy = |prompt: &str| Policy {
+///     r#type: PolicyType::parse("type T { urgent: bool = false }").unwrap(),
+///     prompt: prompt.to_string(),
+///     action: serde_json::json!({"urgent": true}),
+///     precondition: None,
+///     exact_match: None,
+///     explanation: None,
+///     priority: None,
+/// };
+/// let mut report = Report::default();
+/// report.rules_matched = vec![1, 2];
+/// let evaluation = EvaluationReport {
+///     input: TestDataPoint {
+///         text: "The CEO says hi".to_string(),
+///         policies: vec![policy("from the CEO"), policy("a newsletter")],
+///         expected: None,
+///         conflicts: None,
+///         expected_rules: Some(vec![1]),
+///     },
+///     metrics: Default::default(),
+///     report,
+///     output: serde_json::json!({}),
+///     baseline: None,
+///     model: None,
+///     input_hash: None,
+///     policy_set_hash: None,
+/// };
+/// let mut analysis = RuleAttributionAnalysis::new();
+/// analysis.add_report(&evaluation);
+/// assert_eq!(analysis.by_prompt["from the CEO"].true_positive, 1);
+/// assert_eq!(analysis.over_triggering()[0].0, "a newsletter");
+/// assert!(analysis.under_triggering().is_empty());
+/// ```
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct RuleAttributionAnalysis {
+    /// Number of reports compared.
+    pub reports: usize,
+    /// Number of reports without expected rules or whose apply failed.
+    pub skipped: usize,
+    /// Rule matching for each policy prompt.
+    pub by_prompt: std::collections::BTreeMap<String, ConfusionMatrix>,
+    /// Rule matching over all rules.
+    pub overall: ConfusionMatrix,
+}
+
+impl RuleAttributionAnalysis {
+    /// Create an empty analysis.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare the rules matched in `report` against those its test point expects.
+    pub fn add_report(&mut self, report: &crate::data::EvaluationReport) {
+        let Some(expected) = &report.input.expected_rules else {
+            self.skipped += 1;
+            return;
+        };
+        if report.metrics.policyai_error.is_some() {
+            self.skipped += 1;
+            return;
+        }
+        self.reports += 1;
+        for (position, policy) in report.input.policies.iter().enumerate() {
+            let number = crate::RuleIndex::from_position(position).number();
+            let actual = expected.contains(&number);
+            let predicted = report.report.rules_matched.contains(&number);
+            self.by_prompt
+                .entry(policy.prompt.clone())
+                .or_default()
+                .add_prediction(actual, predicted);
+            self.overall.add_prediction(actual, predicted);
+        }
+    }
+
+    /// Prompts that matched when they should not have, most false positives first, ties broken
+    /// by prompt.
+    pub fn over_triggering(&self) -> Vec<(&str, &ConfusionMatrix)> {
+        self.worst_by(|matrix| matrix.false_positive)
+    }
+
+    /// Prompts that did not match when they should have, most false negatives first, ties
+    /// broken by prompt.
+    pub fn under_triggering(&self) -> Vec<(&str, &ConfusionMatrix)> {
+        self.worst_by(|matrix| matrix.false_negative)
+    }
+
+    fn worst_by(&self, count: impl Fn(&ConfusionMatrix) -> usize) -> Vec<(&str, &ConfusionMatrix)> {
+        let mut prompts = self
+            .by_prompt
+            .iter()
+            .filter(|(_, matrix)| count(matrix) > 0)
+            .map(|(prompt, matrix)| (prompt.as_str(), matrix))
+            .collect::<Vec<_>>();
+        prompts.sort_by(|a, b| count(b.1).cmp(&count(a.1)).then(a.0.cmp(b.0)));
+        prompts
+    }
+}
+
+/// How the values an LLM output for one field's masks measured up to the schema.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IrValueCounts {
+    /// Values of the type the mask expects.
+    pub valid: usize,
+    /// Explicit nulls.
+    pub nulls: usize,
+    /// Values of another type than the mask expects.
+    pub wrong_type: usize,
+    /// Values of the right shape that the schema still rules out: an enum value answered by
+    /// name but not among the field's values, or a timestamp that is not RFC 3339.
+    pub invalid: usize,
+}
+
+impl IrValueCounts {
+    /// The number of values output.
+    pub fn total(&self) -> usize {
+        self.valid + self.nulls + self.wrong_type + self.invalid
+    }
+
+    /// The number of values that were not valid.
+    pub fn violations(&self) -> usize {
+        self.nulls + self.wrong_type + self.invalid
+    }
+
+    /// The fraction of values output that were not valid, or 0 if there were none.
+    pub fn violation_rate(&self) -> f64 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            self.violations() as f64 / self.total() as f64
+        }
+    }
+}
+
+/// Tabulates the values an LLM put in stored reports' IRs against the schema of their masks.
+///
+/// Each mask a report's IR sets is counted under its field and under the kind of mask; masks
+/// the IR leaves out are not counted.  Keys that belong to no mask, other than the `__name__`
+/// bookkeeping keys, are counted as unknown: an LLM that writes a field's real name instead of
+/// its mask shows up there.  A field with a high violation rate is one whose schema or masking
+/// confuses the model.
+///
+/// # Examples
+///
+/// ```rust
+/// use policyai::analysis::IrValueAnalysis;
+/// use policyai::{BoolMask, OnConflict, Report};
+///
+/// let mut report = Report::default();
+/// report.bool_masks.push(BoolMask::new(1, "urgent".to_string(), "m1".to_string(), None, OnConflict::Default));
+/// report.ir = Some(serde_json::json!({"__rule_numbers__": [1], "m1": "yes", "urgent": true}));
+///
+/// let mut analysis = IrValueAnalysis::new();
+/// analysis.add_report(&report);
+/// assert_eq!(analysis.by_field["urgent"].wrong_type, 1);
+/// assert_eq!(analysis.unknown_keys["urgent"], 1);
+/// assert_eq!(analysis.most_confused()[0].0, "urgent");
+/// ```
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct IrValueAnalysis {
+    /// Number of reports analyzed.
+    pub reports: usize,
+    /// Number of reports that carry no IR, such as those stripped for storage.
+    pub reports_without_ir: usize,
+    /// Values for each field.
+    pub by_field: std::collections::BTreeMap<String, IrValueCounts>,
+    /// Values for each kind of mask: `bool`, `number`, `string`, `string_array`,
+    /// `string_enum`, or `datetime`.
+    pub by_kind: std::collections::BTreeMap<String, IrValueCounts>,
+    /// Number of times each key that belongs to no mask appeared.
+    pub unknown_keys: std::collections::BTreeMap<String, usize>,
+}
+
+/// How a value measured up to its mask.
+enum IrValue {
+    Valid,
+    Null,
+    WrongType,
+    Invalid,
+}
+
+impl IrValueAnalysis {
+    /// Create an empty analysis.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tabulate the values in `report`'s IR.
+    pub fn add_report(&mut self, report: &crate::Report) {
+        use serde_json::Value;
+        self.reports += 1;
+        let Some(Value::Object(ir)) = &report.ir else {
+            self.reports_without_ir += 1;
+            return;
+        };
+        fn strings(value: &Value) -> bool {
+            match value {
+                Value::String(_) => true,
+                Value::Array(values) => values.iter().all(strings),
+                _ => false,
+            }
+        }
+        let classify = |value: &Value, valid: bool| match value {
+            Value::Null => IrValue::Null,
+            _ if valid => IrValue::Valid,
+            _ => IrValue::WrongType,
+        };
+        let mut known = std::collections::BTreeSet::new();
+        let mut seen = vec![];
+        for m in report.bool_masks.iter() {
+            if let Some(value) = ir.get(&m.mask) {
+                seen.push((&m.name, "bool", classify(value, value.is_boolean())));
+            }
+            known.insert(&m.mask);
+        }
+        for m in report.number_masks.iter() {
+            if let Some(value) = ir.get(&m.mask) {
+                seen.push((&m.name, "number", classify(value, value.is_number())));
+            }
+            known.insert(&m.mask);
+        }
+        for m in report.string_masks.iter() {
+            if let Some(value) = ir.get(&m.mask) {
+                seen.push((&m.name, "string", classify(value, value.is_string())));
+            }
+            known.insert(&m.mask);
+        }
+        for m in report.string_array_masks.iter() {
+            if let Some(value) = ir.get(&m.mask) {
+                let valid = value.is_array() && strings(value);
+                seen.push((&m.name, "string_array", classify(value, valid)));
+            }
+            known.insert(&m.mask);
+        }
+        for m in report.string_enum_masks.iter() {
+            if let Some(value) = ir.get(&m.mask) {
+                // The mask is a flag, but a model may answer with the value itself.
+                let outcome = match value.as_str() {
+                    Some(answer) if !m.values.iter().any(|v| v == answer) => IrValue::Invalid,
+                    _ => classify(value, value.is_boolean()),
+                };
+                seen.push((&m.name, "string_enum", outcome));
+            }
+            known.insert(&m.mask);
+        }
+        for m in report.datetime_masks.iter() {
+            if let Some(value) = ir.get(&m.mask) {
+                let outcome = match value.as_str() {
+                    Some(s) if crate::compare::rfc3339_instant(s).is_none() => IrValue::Invalid,
+                    _ => classify(value, value.is_string()),
+                };
+                seen.push((&m.name, "datetime", outcome));
+            }
+            known.insert(&m.mask);
+        }
+        for m in report.match_masks.iter() {
+            known.insert(&m.mask);
+        }
+        for (field, kind, outcome) in seen {
+            for counts in [
+                self.by_field.entry(field.clone()).or_default(),
+                self.by_kind.entry(kind.to_string()).or_default(),
+            ] {
+                match outcome {
+                    IrValue::Valid => counts.valid += 1,
+                    IrValue::Null => counts.nulls += 1,
+                    IrValue::WrongType => counts.wrong_type += 1,
+                    IrValue::Invalid => counts.invalid += 1,
+                }
+            }
+        }
+        for key in ir.keys() {
+            let bookkeeping = key.len() > 4 && key.starts_with("__") && key.ends_with("__");
+            if !bookkeeping && !known.contains(key) {
+                *self.unknown_keys.entry(key.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Fields with at least one invalid value, highest violation rate first, ties broken by the
+    /// number of violations and then by name.
+    pub fn most_confused(&self) -> Vec<(&str, &IrValueCounts)> {
+        let mut fields = self
+            .by_field
+            .iter()
+            .filter(|(_, counts)| counts.violations() > 0)
+            .map(|(field, counts)| (field.as_str(), counts))
+            .collect::<Vec<_>>();
+        fields.sort_by(|a, b| {
+            b.1.violation_rate()
+                .total_cmp(&a.1.violation_rate())
+                .then(b.1.violations().cmp(&a.1.violations()))
+                .then(a.0.cmp(b.0))
+        });
+        fields
+    }
+}
+
+/// The 95% Wilson score interval for a proportion of `successes` out of `trials`.
+///
+/// Unlike the normal approximation, the interval stays within zero and one and does not
+/// collapse to a point when every trial succeeds or every trial fails.  With no trials the
+/// proportion could be anything, so the interval is `(0.0, 1.0)`.
+///
+/// # Examples
+///
+/// ```rust
+/// use policyai::analysis::wilson_interval;
+///
+/// let (low, high) = wilson_interval(90, 100);
+/// assert!((low - 0.826).abs() < 1e-3);
+/// assert!((high - 0.945).abs() < 1e-3);
+/// assert_eq!(wilson_interval(0, 0), (0.0, 1.0));
+/// ```
+pub fn wilson_interval(successes: usize, trials: usize) -> (f64, f64) {
+    const Z: f64 = 1.96;
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z * Z;
+    let denominator = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denominator;
+    let half_width = Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+    (
+        (center - half_width).max(0.0),
+        (center + half_width).min(1.0),
+    )
+}
+
+/// Accuracy over the first reports of an evaluation file.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LearningCurvePoint {
+    /// The fraction of the file the prefix was asked to cover.
+    pub fraction: f64,
+    /// Number of reports in the prefix.
+    pub reports: usize,
+    /// Fields whose value matched the expected value.
+    pub fields_matched: usize,
+    /// Fields that were wrong, missing, or not expected at all.
+    pub fields_mismatched: usize,
+    /// Reports that applied without error and got every field right.
+    pub exact_reports: usize,
+}
+
+impl LearningCurvePoint {
+    /// Fraction of fields that matched, or zero if no fields were scored.
+    pub fn accuracy(&self) -> f64 {
+        let total = self.fields_matched + self.fields_mismatched;
+        if total == 0 {
+            0.0
+        } else {
+            self.fields_matched as f64 / total as f64
+        }
+    }
+
+    /// The 95% [`wilson_interval`] around [`LearningCurvePoint::accuracy`].
+    ///
+    /// Fields are treated as independent trials, which they are not quite: fields of the same
+    /// input tend to be right or wrong together, so the true interval is somewhat wider.
+    pub fn accuracy_interval(&self) -> (f64, f64) {
+        wilson_interval(
+            self.fields_matched,
+            self.fields_matched + self.fields_mismatched,
+        )
+    }
+
+    /// Fraction of reports that got every field right, or zero if there are none.
+    pub fn exact_rate(&self) -> f64 {
+        if self.reports == 0 {
+            0.0
+        } else {
+            self.exact_reports as f64 / self.reports as f64
+        }
+    }
+
+    /// The 95% [`wilson_interval`] around [`LearningCurvePoint::exact_rate`].
+    pub fn exact_rate_interval(&self) -> (f64, f64) {
+        wilson_interval(self.exact_reports, self.reports)
+    }
+}
+
+/// Accuracy over growing prefixes of an evaluation file, to judge whether more test data would
+/// still change the conclusions drawn from it.
+///
+/// Reports are taken in the order they are added, which should be the order of the file.  Each
+/// fraction becomes a prefix of at least one report; fractions that round to the same prefix
+/// yield a single point.  When the accuracy of the larger prefixes stays inside the confidence
+/// interval of a smaller one, collecting more data of the same kind is unlikely to move it.
+///
+/// # Examples
+///
+/// ```rust
+/// use policyai::analysis::LearningCurve;
+/// use policyai::data::Metrics;
+///
+/// let mut curve = LearningCurve::new().with_fractions(vec![0.5, 1.0]);
+/// for matched in [3, 2, 3, 3] {
+///     curve.add_report(&Metrics {
+///         policyai_fields_matched: matched,
+///         policyai_fields_with_wrong_value: 3 - matched,
+///         ..Default::default()
+///     });
+/// }
+/// let points = curve.points();
+/// assert_eq!(points.len(), 2);
+/// assert_eq!(points[0].reports, 2);
+/// assert_eq!(points[0].exact_reports, 1);
+/// assert!((points[1].accuracy() - 11.0 / 12.0).abs() < 1e-9);
+/// assert_eq!(curve.settled_at().unwrap().reports, 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LearningCurve {
+    /// Fractions of the file to report on, ascending.
+    pub fractions: Vec<f64>,
+    // Fields matched, fields mismatched, and whether every field was right, for each report.
+    reports: Vec<(usize, usize, bool)>,
+}
+
+impl LearningCurve {
+    /// Fractions used unless [`LearningCurve::with_fractions`] says otherwise.
+    pub const DEFAULT_FRACTIONS: &'static [f64] = &[0.1, 0.25, 0.5, 0.75, 1.0];
+
+    /// Create an empty curve over [`LearningCurve::DEFAULT_FRACTIONS`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report on `fractions` of the file instead of the defaults.
+    ///
+    /// Fractions outside `(0, 1]` are dropped and the rest are sorted.
+    pub fn with_fractions(mut self, mut fractions: Vec<f64>) -> Self {
+        fractions.retain(|fraction| *fraction > 0.0 && *fraction <= 1.0);
+        fractions.sort_by(f64::total_cmp);
+        fractions.dedup();
+        self.fractions = fractions;
+        self
+    }
+
+    /// Record the next report of the file.
+    pub fn add_report(&mut self, metrics: &crate::data::Metrics) {
+        let mismatched = metrics.policyai_fields_with_wrong_value
+            + metrics.policyai_fields_missing
+            + metrics.policyai_extra_fields;
+        let exact = mismatched == 0 && metrics.policyai_error.is_none();
+        self.reports
+            .push((metrics.policyai_fields_matched, mismatched, exact));
+    }
+
+    /// One point per distinct prefix, smallest first.  Empty if no reports were added.
+    pub fn points(&self) -> Vec<LearningCurvePoint> {
+        let mut points: Vec<LearningCurvePoint> = vec![];
+        if self.reports.is_empty() {
+            return points;
+        }
+        for &fraction in self.fractions.iter() {
+            let len = ((fraction * self.reports.len() as f64).ceil() as usize)
+                .clamp(1, self.reports.len());
+            if points.last().is_some_and(|point| point.reports == len) {
+                continue;
+            }
+            let mut point = LearningCurvePoint {
+                fraction,
+                reports: len,
+                ..Default::default()
+            };
+            for &(matched, mismatched, exact) in self.reports[..len].iter() {
+                point.fields_matched += matched;
+                point.fields_mismatched += mismatched;
+                point.exact_reports += usize::from(exact);
+            }
+            points.push(point);
+        }
+        points
+    }
+
+    /// The smallest prefix whose accuracy interval holds the accuracy of every larger prefix.
+    ///
+    /// The largest prefix always qualifies, so this is `None` only when there are no points.
+    /// If it is the largest prefix, the estimate was still moving when the data ran out.
+    pub fn settled_at(&self) -> Option<LearningCurvePoint> {
+        let points = self.points();
+        (0..points.len())
+            .find(|&index| {
+                let (low, high) = points[index].accuracy_interval();
+                points[index + 1..]
+                    .iter()
+                    .all(|later| (low..=high).contains(&later.accuracy()))
+            })
+            .map(|index| points[index].clone())
+    }
+}
+
+impl Default for LearningCurve {
+    fn default() -> Self {
+        Self {
+            fractions: Self::DEFAULT_FRACTIONS.to_vec(),
+            reports: vec![],
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -998,6 +2088,8 @@ mod tests {
             baseline_apply_duration_ms: 150,
             policyai_usage: None,
             baseline_usage: None,
+            policyai_cost_usd: None,
+            baseline_cost_usd: None,
         };
 
         analysis.add_report(&metrics);
@@ -1037,6 +2129,8 @@ mod tests {
             baseline_apply_duration_ms: 300,
             policyai_usage: None,
             baseline_usage: None,
+            policyai_cost_usd: None,
+            baseline_cost_usd: None,
         };
 
         let metrics2 = Metrics {
@@ -1054,6 +2148,8 @@ mod tests {
             baseline_apply_duration_ms: 200,
             policyai_usage: None,
             baseline_usage: None,
+            policyai_cost_usd: None,
+            baseline_cost_usd: None,
         };
 
         analysis.add_report(&metrics1);
@@ -1109,4 +2205,359 @@ mod tests {
         assert_eq!(analysis.policyai_errors, deserialized.policyai_errors);
         assert_eq!(analysis.baseline_errors, deserialized.baseline_errors);
     }
+
+    fn model_report(model: &str, metrics: Metrics) -> crate::data::EvaluationReport {
+        crate::data::EvaluationReport {
+            input: crate::data::TestDataPoint {
+                text: "text".to_string(),
+                policies: vec![],
+                expected: None,
+                conflicts: None,
+                expected_rules: None,
+            },
+            metrics,
+            report: crate::Report::default(),
+            output: serde_json::json!({}),
+            baseline: None,
+            model: Some(model.to_string()),
+            input_hash: None,
+            policy_set_hash: None,
+        }
+    }
+
+    #[test]
+    fn model_comparison_errors_lose_and_mistakes_break_ties() {
+        let mut comparison = ModelComparison::new();
+        comparison.add_point(&[
+            model_report(
+                "b",
+                Metrics {
+                    policyai_fields_matched: 5,
+                    policyai_error: Some("boom".to_string()),
+                    ..Default::default()
+                },
+            ),
+            model_report(
+                "a",
+                Metrics {
+                    policyai_fields_matched: 1,
+                    ..Default::default()
+                },
+            ),
+            model_report(
+                "c",
+                Metrics {
+                    policyai_fields_matched: 1,
+                    policyai_extra_fields: 1,
+                    ..Default::default()
+                },
+            ),
+        ]);
+        assert_eq!(comparison.per_model.len(), 3);
+        let labels: Vec<_> = comparison
+            .pairwise
+            .iter()
+            .map(|r| (r.model_a.as_str(), r.model_b.as_str()))
+            .collect();
+        assert_eq!(labels, vec![("a", "b"), ("a", "c"), ("b", "c")]);
+        assert_eq!(comparison.pairwise("a", "b").unwrap().wins, 1);
+        assert_eq!(comparison.pairwise("b", "a").unwrap().losses, 1);
+        assert_eq!(comparison.pairwise("a", "c").unwrap().wins, 1);
+        assert_eq!(comparison.pairwise("c", "b").unwrap().wins, 1);
+        assert!(comparison.pairwise("a", "z").is_none());
+    }
+
+    fn frontier_metrics(matched: usize, wrong: usize, input: i32, output: i32) -> Metrics {
+        let mut usage = crate::Usage::new();
+        usage.add_claudius_usage(claudius::Usage::new(input, output));
+        Metrics {
+            policyai_fields_matched: matched,
+            policyai_fields_with_wrong_value: wrong,
+            policyai_usage: Some(usage),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn frontier_drops_dominated_configs() {
+        let mut frontier = Frontier::new();
+        frontier.add_report("cheap", &frontier_metrics(8, 2, 100, 10));
+        frontier.add_report("cheap", &frontier_metrics(9, 1, 100, 10));
+        frontier.add_report("mid", &frontier_metrics(9, 1, 200, 20));
+        frontier.add_report("mid", &frontier_metrics(9, 1, 200, 20));
+        frontier.add_report("worse", &frontier_metrics(8, 2, 300, 20));
+        frontier.add_report("worse", &frontier_metrics(8, 2, 300, 20));
+
+        let cheap = &frontier.points["cheap"];
+        assert_eq!(cheap.reports, 2);
+        assert!((cheap.accuracy() - 0.85).abs() < 1e-9);
+        assert!((cheap.avg_cost() - 150.0).abs() < 1e-9);
+        assert!(frontier.points["worse"].is_dominated_by(&frontier.points["mid"]));
+
+        let labels = frontier
+            .frontier()
+            .iter()
+            .map(|p| p.config.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["cheap", "mid"]);
+        let tradeoffs = frontier.tradeoffs();
+        assert_eq!(tradeoffs.len(), 1);
+        assert!((tradeoffs[0].accuracy_gain - 5.0).abs() < 1e-9);
+        assert!((tradeoffs[0].relative_cost_increase - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn frontier_weights_change_the_ranking() {
+        let weights = TokenWeights {
+            input: 1.0,
+            output: 1.0,
+            cache_creation: 1.0,
+            cache_read: 1.0,
+        };
+        let add = |frontier: &mut Frontier| {
+            frontier.add_report("chatty", &frontier_metrics(9, 1, 100, 100));
+            frontier.add_report("long-prompt", &frontier_metrics(9, 1, 250, 10));
+        };
+        let mut default = Frontier::new();
+        add(&mut default);
+        let mut flat = Frontier::new().with_weights(weights);
+        add(&mut flat);
+        assert_eq!(default.frontier()[0].config, "long-prompt");
+        assert_eq!(flat.frontier()[0].config, "chatty");
+        assert_eq!(flat.frontier().len(), 1);
+    }
+
+    #[test]
+    fn frontier_keeps_ties() {
+        let mut frontier = Frontier::new();
+        frontier.add_report("a", &frontier_metrics(1, 0, 10, 0));
+        frontier.add_report("b", &frontier_metrics(1, 0, 10, 0));
+        assert_eq!(frontier.frontier().len(), 2);
+        assert!(frontier.tradeoffs().is_empty());
+    }
+
+    #[test]
+    fn conflict_analysis_tabulates_outcomes() {
+        use crate::{ConflictKind, OnConflict, Report, Resolution, ResolutionOutcome};
+        let resolution = |field: &str, kind, on_conflict, outcome| Resolution {
+            field: field.to_string(),
+            kind,
+            on_conflict,
+            outcome,
+        };
+        let mut analysis = ConflictAnalysis::new();
+        analysis.add_report(
+            &Report::default()
+                .with_resolution(resolution(
+                    "urgent",
+                    ConflictKind::Bool,
+                    OnConflict::LargestValue,
+                    ResolutionOutcome::Largest,
+                ))
+                .with_resolution(resolution(
+                    "title",
+                    ConflictKind::String,
+                    OnConflict::Agreement,
+                    ResolutionOutcome::Errored,
+                )),
+        );
+        analysis.add_report(&Report::default().with_resolution(resolution(
+            "score",
+            ConflictKind::Number,
+            OnConflict::Default,
+            ResolutionOutcome::Defaulted,
+        )));
+        analysis.add_report(&Report::default().with_resolution(resolution(
+            "title",
+            ConflictKind::String,
+            OnConflict::Agreement,
+            ResolutionOutcome::Errored,
+        )));
+        analysis.add_report(&Report::default());
+
+        assert_eq!(analysis.reports, 4);
+        assert_eq!(analysis.reports_with_disagreements, 3);
+        assert_eq!(analysis.overall.total(), 4);
+        assert_eq!(analysis.by_field["title"].errored, 2);
+        assert_eq!(analysis.by_kind[&ConflictKind::Bool].largest, 1);
+        assert_eq!(analysis.by_strategy["agreement"].errored, 2);
+        assert_eq!(analysis.by_strategy["default"].defaulted, 1);
+        assert!((analysis.overall.error_rate() - 0.5).abs() < 1e-9);
+        let errored = analysis.most_errored();
+        assert_eq!(errored.len(), 1);
+        assert_eq!(errored[0].0, "title");
+    }
+
+    #[test]
+    fn conflict_analysis_sees_disagreements_recorded_while_merging() {
+        use crate::{OnConflict, Report};
+        let mut report = Report::default();
+        report.report_bool(1, "urgent", false, OnConflict::LargestValue);
+        report.report_bool(2, "urgent", true, OnConflict::LargestValue);
+        report.report_string(1, "title", "a".to_string(), OnConflict::Agreement);
+        report.report_string(2, "title", "a".to_string(), OnConflict::Agreement);
+        report.report_string(3, "title", "b".to_string(), OnConflict::Agreement);
+        report.report_number(1, "score", 1, OnConflict::Default);
+        report.report_number(2, "score", 2, OnConflict::Default);
+        assert_eq!(report.resolutions().len(), 3);
+        assert_eq!(report.conflicts().len(), 1);
+
+        let mut analysis = ConflictAnalysis::new();
+        analysis.add_report(&report);
+        assert_eq!(analysis.by_field["urgent"].largest, 1);
+        assert_eq!(analysis.by_field["title"].errored, 1);
+        assert_eq!(analysis.by_field["score"].defaulted, 1);
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn rule_attribution_skips_points_without_expectations_and_failed_applies() {
+        let policy = crate::Policy {
+            r#type: crate::PolicyType::parse("type T { urgent: bool = false }").unwrap(),
+            prompt: "from the CEO".to_string(),
+            action: serde_json::json!({"urgent": true}),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        };
+        let mut unlabeled = model_report("m", Metrics::default());
+        unlabeled.input.policies = vec![policy.clone()];
+        let mut failed = unlabeled.clone();
+        failed.input.expected_rules = Some(vec![1]);
+        failed.metrics.policyai_error = Some("too many iterations".to_string());
+        let mut missed = failed.clone();
+        missed.metrics.policyai_error = None;
+
+        let mut analysis = RuleAttributionAnalysis::new();
+        analysis.add_report(&unlabeled);
+        analysis.add_report(&failed);
+        analysis.add_report(&missed);
+        assert_eq!(analysis.reports, 1);
+        assert_eq!(analysis.skipped, 2);
+        assert_eq!(analysis.overall.false_negative, 1);
+        assert_eq!(analysis.under_triggering()[0].0, "from the CEO");
+        assert!(analysis.over_triggering().is_empty());
+    }
+
+    #[test]
+    fn ir_value_analysis_classifies_every_kind_of_mask() {
+        use crate::{
+            DateTimeMask, MatchMask, NumberMask, OnConflict, Report, StringArrayMask,
+            StringEnumMask,
+        };
+        let mut report = Report::default();
+        report.number_masks.push(NumberMask::new(
+            1,
+            "score".to_string(),
+            "n".to_string(),
+            None,
+            None,
+            OnConflict::Default,
+        ));
+        report.string_array_masks.push(StringArrayMask::new(
+            1,
+            "tags".to_string(),
+            "a".to_string(),
+            vec![],
+        ));
+        report.string_enum_masks.push(
+            StringEnumMask::new(
+                1,
+                "priority".to_string(),
+                "e".to_string(),
+                Some("high".to_string()),
+                None,
+                OnConflict::Default,
+            )
+            .with_values(vec!["low".to_string(), "high".to_string()]),
+        );
+        report.string_enum_masks.push(
+            StringEnumMask::new(
+                2,
+                "priority".to_string(),
+                "f".to_string(),
+                Some("low".to_string()),
+                None,
+                OnConflict::Default,
+            )
+            .with_values(vec!["low".to_string(), "high".to_string()]),
+        );
+        report.datetime_masks.push(DateTimeMask::new(
+            1,
+            "due".to_string(),
+            "d".to_string(),
+            None,
+            None,
+            OnConflict::Default,
+        ));
+        report.match_masks.push(MatchMask::new(3, "x".to_string()));
+        report.ir = Some(serde_json::json!({
+            "__rule_numbers__": [1, 3],
+            "n": null,
+            "a": ["ok", ["nested"]],
+            "e": "urgent",
+            "f": "low",
+            "d": "tomorrow",
+            "x": true,
+            "__": 1,
+        }));
+
+        let mut analysis = IrValueAnalysis::new();
+        analysis.add_report(&report);
+        analysis.add_report(&Report::default());
+        assert_eq!(analysis.reports, 2);
+        assert_eq!(analysis.reports_without_ir, 1);
+        assert_eq!(analysis.by_field["score"].nulls, 1);
+        assert_eq!(analysis.by_field["tags"].valid, 1);
+        assert_eq!(analysis.by_field["priority"].invalid, 1);
+        assert_eq!(analysis.by_field["priority"].wrong_type, 1);
+        assert_eq!(analysis.by_field["due"].invalid, 1);
+        assert_eq!(analysis.by_kind["string_enum"].total(), 2);
+        assert_eq!(
+            analysis.unknown_keys,
+            std::collections::BTreeMap::from([("__".to_string(), 1)])
+        );
+        let confused = analysis
+            .most_confused()
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect::<Vec<_>>();
+        assert_eq!(confused, vec!["priority", "due", "score"]);
+    }
+
+    #[test]
+    fn wilson_interval_stays_within_bounds() {
+        let (low, high) = wilson_interval(10, 10);
+        assert!(low > 0.6 && low < 1.0);
+        assert_eq!(high, 1.0);
+        let (low, high) = wilson_interval(0, 10);
+        assert_eq!(low, 0.0);
+        assert!(high > 0.0 && high < 0.4);
+    }
+
+    #[test]
+    fn learning_curve_merges_prefixes_that_round_together() {
+        let mut curve = LearningCurve::new();
+        for (matched, wrong, error) in [(2, 0, false), (1, 1, false), (0, 0, true)] {
+            curve.add_report(&Metrics {
+                policyai_fields_matched: matched,
+                policyai_fields_with_wrong_value: wrong,
+                policyai_error: error.then(|| "timed out".to_string()),
+                ..Default::default()
+            });
+        }
+        let points = curve.points();
+        let sizes = points.iter().map(|p| p.reports).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![1, 2, 3]);
+        assert_eq!(points[0].fraction, 0.1);
+        assert_eq!(points[2].exact_reports, 1);
+        assert!((points[2].exact_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((points[2].accuracy() - 0.75).abs() < 1e-9);
+
+        let unsorted = LearningCurve::new().with_fractions(vec![1.0, 0.0, 0.5, 2.0]);
+        assert_eq!(unsorted.fractions, vec![0.5, 1.0]);
+        assert!(LearningCurve::new().points().is_empty());
+        assert!(LearningCurve::new().settled_at().is_none());
+    }
 }
@@ -0,0 +1,363 @@
+//! Applying policies through Anthropic's message batches API.
+//!
+//! A batch is processed within a day at half the price of the same requests sent one at a time,
+//! which suits offline processing of a corpus.  [`crate::Manager::apply_via_batches`] submits one
+//! request per text, waits for the batch to end, and turns each answer into a report.  The
+//! batch it submitted is recorded in a [`BatchJob`] file so that a process that dies while
+//! waiting picks the same batch up again instead of paying for it twice.
+
+use std::path::Path;
+use std::time::Duration;
+
+use claudius::{Message, MessageCreateParams};
+
+use crate::{ApplyError, Report};
+
+/// The version of the Messages API the batch endpoints are called with.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// A client for the message batches endpoints of the Anthropic API.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use policyai::BatchClient;
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let batches = BatchClient::new(None)?.with_poll_interval(Duration::from_secs(300));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BatchClient {
+    api_key: String,
+    base_url: String,
+    poll_interval: Duration,
+    client: reqwest::Client,
+}
+
+impl BatchClient {
+    /// A client authenticated with `api_key`, or with `CLAUDIUS_API_KEY` or `ANTHROPIC_API_KEY`
+    /// from the environment if it is `None`.
+    pub fn new(api_key: Option<String>) -> Result<Self, claudius::Error> {
+        let api_key = match api_key {
+            Some(api_key) => api_key,
+            None => std::env::var("CLAUDIUS_API_KEY")
+                .or_else(|_| std::env::var("ANTHROPIC_API_KEY"))
+                .map_err(|_| {
+                    claudius::Error::authentication(
+                        "set CLAUDIUS_API_KEY or ANTHROPIC_API_KEY, or pass an API key",
+                    )
+                })?,
+        };
+        Ok(Self {
+            api_key,
+            base_url: "https://api.anthropic.com/v1/".to_string(),
+            poll_interval: Duration::from_secs(60),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Send requests to `base_url`, which ends in a slash, in place of the public API.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Wait `poll_interval` between checks on a batch that has not ended.  The default is a
+    /// minute.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Submit `requests` as one batch and return its id.
+    pub async fn create(&self, requests: &[BatchRequest]) -> Result<String, claudius::Error> {
+        let body = serde_json::json!({ "requests": requests });
+        let req = self
+            .client
+            .post(self.url("messages/batches"))
+            .header("content-type", "application/json")
+            .body(body.to_string());
+        let batch: BatchStatus = self.call(req).await?;
+        Ok(batch.id)
+    }
+
+    /// The current status of the batch `id`.
+    pub async fn status(&self, id: &str) -> Result<BatchStatus, claudius::Error> {
+        self.call(self.client.get(self.url(&format!("messages/batches/{id}"))))
+            .await
+    }
+
+    /// Wait for the batch `id` to end, checking on it every poll interval.
+    pub async fn wait(&self, id: &str) -> Result<BatchStatus, claudius::Error> {
+        loop {
+            let status = self.status(id).await?;
+            if status.has_ended() {
+                return Ok(status);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// The results of the batch `id`, which must have ended, in no particular order.
+    pub async fn results(&self, id: &str) -> Result<Vec<BatchResult>, claudius::Error> {
+        let status = self.status(id).await?;
+        let Some(results_url) = status.results_url else {
+            return Err(claudius::Error::validation(
+                format!(
+                    "batch {id} has no results; it is {}",
+                    status.processing_status
+                ),
+                None,
+            ));
+        };
+        let body = self.send(self.client.get(results_url)).await?;
+        parse_results(&body)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// Send `req` with the API's headers and return the body of a successful response.
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<String, claudius::Error> {
+        let resp = req
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await
+            .map_err(|err| claudius::Error::http_client(err.to_string(), Some(Box::new(err))))?;
+        let status = resp.status();
+        let request_id = resp
+            .headers()
+            .get("request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let body = resp
+            .text()
+            .await
+            .map_err(|err| claudius::Error::http_client(err.to_string(), Some(Box::new(err))))?;
+        if !status.is_success() {
+            return Err(claudius::Error::api(
+                status.as_u16(),
+                None,
+                body,
+                request_id,
+            ));
+        }
+        Ok(body)
+    }
+
+    /// [`BatchClient::send`], parsing the body as JSON.
+    async fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        req: reqwest::RequestBuilder,
+    ) -> Result<T, claudius::Error> {
+        let body = self.send(req).await?;
+        serde_json::from_str(&body)
+            .map_err(|err| claudius::Error::serialization(err.to_string(), Some(Box::new(err))))
+    }
+}
+
+impl std::fmt::Debug for BatchClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchClient")
+            .field("base_url", &self.base_url)
+            .field("poll_interval", &self.poll_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parse the JSONL results of a batch, skipping blank lines.
+fn parse_results(body: &str) -> Result<Vec<BatchResult>, claudius::Error> {
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| claudius::Error::serialization(err.to_string(), Some(Box::new(err))))
+        })
+        .collect()
+}
+
+/// One request of a batch.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct BatchRequest {
+    /// The id that ties the request to its result.
+    pub custom_id: String,
+    /// The request, as it would be sent on its own.
+    pub params: MessageCreateParams,
+}
+
+/// Where a batch is in its processing.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct BatchStatus {
+    /// The batch's id.
+    pub id: String,
+    /// `in_progress`, `canceling`, or `ended`.
+    pub processing_status: String,
+    /// Where the results can be read from, once the batch has ended.
+    #[serde(default)]
+    pub results_url: Option<String>,
+}
+
+impl BatchStatus {
+    /// True if every request of the batch has a result.
+    pub fn has_ended(&self) -> bool {
+        self.processing_status == "ended"
+    }
+}
+
+/// The result of one request of a batch.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct BatchResult {
+    /// The id of the request.
+    pub custom_id: String,
+    /// What became of the request.
+    pub result: BatchOutcome,
+}
+
+/// What became of one request of a batch.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOutcome {
+    /// The request got an answer.
+    Succeeded {
+        /// The answer.
+        message: Message,
+    },
+    /// The request failed.
+    Errored {
+        /// The error, as the API reported it.
+        #[serde(default)]
+        error: serde_json::Value,
+    },
+    /// The batch was canceled before the request was processed.
+    Canceled,
+    /// The batch expired before the request was processed.
+    Expired,
+}
+
+/// A batch submitted by [`crate::Manager::apply_via_batches`], as persisted between runs.
+///
+/// The texts themselves are not persisted; their hashes are, so that a job is only resumed for
+/// the texts and policies it was submitted for.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BatchJob {
+    /// The id of the batch.
+    pub batch_id: String,
+    /// The policy hash of the manager's [`crate::CacheKey`], as 32 hex digits.
+    pub policy_set_hash: String,
+    /// The request submitted for each text, in the order of the texts.
+    pub requests: Vec<BatchJobRequest>,
+}
+
+/// The request [`BatchJob`] submitted for one text.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BatchJobRequest {
+    /// The id of the request within the batch.
+    pub custom_id: String,
+    /// The zero-based position of the text.
+    pub index: usize,
+    /// The text hash of the text's [`crate::CacheKey`], as 32 hex digits.
+    pub text_hash: String,
+}
+
+impl BatchJob {
+    /// Read the job at `path`, or `None` if there is no file there.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Write the job to `path`, replacing whatever is there.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        // Write beside the job and rename, so that a crash never leaves half a job behind.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+/// The outcome of [`crate::Manager::apply_via_batches`]: one result per text, in the order of
+/// the texts.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// The id of the batch, or `None` if no text needed the LLM.
+    pub batch_id: Option<String>,
+    /// The report, or the error, of each text.
+    pub results: Vec<Result<Report, ApplyError>>,
+}
+
+impl BatchReport {
+    /// The reports of the texts that succeeded, with each text's zero-based index.
+    pub fn reports(&self) -> impl Iterator<Item = (usize, &Report)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().ok().map(|report| (index, report)))
+    }
+
+    /// The errors of the texts that failed, with each text's zero-based index.
+    pub fn errors(&self) -> impl Iterator<Item = (usize, &ApplyError)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().err().map(|err| (index, err)))
+    }
+
+    /// True if every text produced a report.
+    pub fn is_complete(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_parse_every_outcome() {
+        let body = r#"{"custom_id":"text-0","result":{"type":"succeeded","message":{"id":"msg_1","type":"message","role":"assistant","model":"claude-haiku-4-5","content":[{"type":"tool_use","id":"toolu_1","name":"output_json","input":{"__rule_numbers__":[1],"urgent":true}}],"stop_reason":"tool_use","stop_sequence":null,"usage":{"input_tokens":10,"output_tokens":5}}}}
+
+{"custom_id":"text-1","result":{"type":"errored","error":{"type":"invalid_request_error","message":"bad"}}}
+{"custom_id":"text-2","result":{"type":"expired"}}
+{"custom_id":"text-3","result":{"type":"canceled"}}
+"#;
+        let results = parse_results(body).unwrap();
+        assert_eq!(results.len(), 4);
+        let BatchOutcome::Succeeded { message } = &results[0].result else {
+            panic!("expected success: {:?}", results[0]);
+        };
+        assert_eq!(message.content.len(), 1);
+        assert!(matches!(results[1].result, BatchOutcome::Errored { .. }));
+        assert!(matches!(results[2].result, BatchOutcome::Expired));
+        assert!(matches!(results[3].result, BatchOutcome::Canceled));
+    }
+
+    #[test]
+    fn job_round_trips_and_is_absent_until_saved() {
+        let path =
+            std::env::temp_dir().join(format!("policyai-batch-{}.json", uuid::Uuid::new_v4()));
+        assert_eq!(BatchJob::load(&path).unwrap(), None);
+        let job = BatchJob {
+            batch_id: "msgbatch_1".to_string(),
+            policy_set_hash: format!("{:032x}", 42),
+            requests: vec![BatchJobRequest {
+                custom_id: "text-3".to_string(),
+                index: 3,
+                text_hash: format!("{:032x}", 7),
+            }],
+        };
+        job.save(&path).unwrap();
+        let loaded = BatchJob::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Some(job));
+    }
+}
@@ -0,0 +1,210 @@
+//! Measure how fast policies are applied as the policy set grows.
+//!
+//! For each policy-set size, synthesizes that many keyword policies and a batch of texts that
+//! each mention a random handful of the keywords, then applies the policies to every text with
+//! bounded concurrency.  One JSON line per size is written to stdout with requests per second,
+//! latency percentiles, tokens per document, and the retry rate.
+//!
+//! With `--mock` no provider is called: each request is assembled as usual, the provider is
+//! stood in for by a sleep of `--mock-latency-ms`, and the output the LLM would give is
+//! synthesized from the keywords.  Mock runs measure the crate's own overhead, and their token
+//! counts are estimated from the size of the request.
+
+use std::time::{Duration, Instant};
+
+use arrrg::CommandLine;
+use claudius::{Anthropic, MessageCreateParams, Model};
+use futures::StreamExt;
+use rand::prelude::*;
+
+use policyai::testing::ReportFixture;
+use policyai::{Manager, Policy, PolicyType, PromptTokens, Usage};
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(optional, "Comma-separated policy-set sizes to run (default 1,10,50)")]
+    sizes: Option<String>,
+    #[arrrg(optional, "Texts to apply each policy set to (default 20)")]
+    docs: Option<usize>,
+    #[arrrg(optional, "Applies in flight at once (default 4)")]
+    concurrency: Option<usize>,
+    #[arrrg(optional, "Model to send requests to (default claude-haiku-4-5)")]
+    model: Option<String>,
+    #[arrrg(flag, "Synthesize responses instead of calling the provider")]
+    mock: bool,
+    #[arrrg(
+        optional,
+        "Latency of each synthesized response in milliseconds (default 0)"
+    )]
+    mock_latency_ms: Option<u64>,
+    #[arrrg(optional, "Seed for the synthetic texts (default 0)")]
+    seed: Option<u64>,
+}
+
+/// The outcome of applying one set of policies to one text.
+struct Sample {
+    latency: Duration,
+    tokens: u64,
+    retries: usize,
+    failed: bool,
+}
+
+fn policy_type() -> PolicyType {
+    PolicyType::parse(
+        r#"type policyai::Bench {
+            urgent: bool = false,
+            score: number @ largest wins = 0,
+            priority: ["low", "medium", "high"] @ highest wins = "low",
+            labels: [string],
+        }"#,
+    )
+    .expect("the benchmark type should parse")
+}
+
+/// The `n` keyword policies, each setting one field when its keyword appears.
+fn policies(n: usize) -> Vec<(String, serde_json::Value)> {
+    (0..n)
+        .map(|i| {
+            let action = match i % 4 {
+                0 => serde_json::json!({"urgent": true}),
+                1 => serde_json::json!({"score": i}),
+                2 => serde_json::json!({"priority": "high"}),
+                _ => serde_json::json!({"labels": [format!("label{i}")]}),
+            };
+            (format!("The text mentions keyword{i}."), action)
+        })
+        .collect()
+}
+
+/// A text that mentions a few of the `n` keywords, and the rule numbers those keywords match.
+fn text(rng: &mut StdRng, n: usize) -> (String, Vec<usize>) {
+    let mut matched = (0..n)
+        .filter(|_| rng.random_bool((3.0 / n as f64).min(1.0)))
+        .collect::<Vec<_>>();
+    matched.shuffle(rng);
+    let mut text = "Hello team,\n\nA quick note about this week's work.".to_string();
+    for i in matched.iter() {
+        text += &format!(" Please keep keyword{i} in mind.");
+    }
+    text += "\n\nThanks,\nAlex";
+    matched.sort();
+    (text, matched.into_iter().map(|i| i + 1).collect())
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index].as_secs_f64() * 1000.0
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(
+        "USAGE: policyai-bench [--sizes 1,10,50] [--docs N] [--concurrency N] [--model MODEL] [--mock [--mock-latency-ms MS]] [--seed N]",
+    );
+    if !free.is_empty() {
+        eprintln!("policyai-bench takes no positional arguments");
+        std::process::exit(1);
+    }
+    let sizes = args
+        .sizes
+        .as_deref()
+        .unwrap_or("1,10,50")
+        .split(',')
+        .map(|size| size.trim().parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let docs = args.docs.unwrap_or(20);
+    let concurrency = args.concurrency.unwrap_or(4).max(1);
+    let mock_latency = Duration::from_millis(args.mock_latency_ms.unwrap_or(0));
+    let client = if args.mock {
+        None
+    } else {
+        Some(Anthropic::new(None)?)
+    };
+    let template = MessageCreateParams {
+        max_tokens: 4096,
+        model: Model::Custom(
+            args.model
+                .clone()
+                .unwrap_or_else(|| "claude-haiku-4-5".to_string()),
+        ),
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(args.seed.unwrap_or(0));
+    for size in sizes {
+        let policy_type = policy_type();
+        let mut manager = Manager::default();
+        let mut fixture = ReportFixture::new(policy_type.clone());
+        for (prompt, action) in policies(size) {
+            manager.add(Policy {
+                r#type: policy_type.clone(),
+                prompt: prompt.clone(),
+                action: action.clone(),
+                precondition: None,
+                exact_match: None,
+                explanation: None,
+                priority: None,
+            });
+            fixture = fixture.with_policy(prompt, action);
+        }
+        let prepared = manager.prepare(template.clone())?;
+        let texts = (0..docs).map(|_| text(&mut rng, size)).collect::<Vec<_>>();
+        let start = Instant::now();
+        let samples = futures::stream::iter(texts.iter())
+            .map(|(text, matched)| {
+                let prepared = &prepared;
+                let fixture = &fixture;
+                let client = client.as_ref();
+                async move {
+                    let started = Instant::now();
+                    let Some(client) = client else {
+                        let (_, req) = prepared.request_for(text);
+                        let request = serde_json::to_string(&req).unwrap_or_default();
+                        tokio::time::sleep(mock_latency).await;
+                        let report = fixture.report(matched);
+                        return Sample {
+                            latency: started.elapsed(),
+                            tokens: PromptTokens::estimate(&request),
+                            retries: 0,
+                            failed: report.has_errors(),
+                        };
+                    };
+                    let mut usage = Usage::new();
+                    let result = prepared.apply(client, text, Some(&mut usage)).await;
+                    Sample {
+                        latency: started.elapsed(),
+                        tokens: usage.input_tokens()
+                            + usage.output_tokens()
+                            + usage.cache_creation_input_tokens()
+                            + usage.cache_read_input_tokens(),
+                        retries: usage.iterations.saturating_sub(1),
+                        failed: result.is_err(),
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        let elapsed = start.elapsed();
+        let mut latencies = samples.iter().map(|s| s.latency).collect::<Vec<_>>();
+        latencies.sort();
+        let count = samples.len().max(1) as f64;
+        let output = serde_json::json!({
+            "policies": size,
+            "docs": samples.len(),
+            "mock": args.mock,
+            "concurrency": concurrency,
+            "elapsed_secs": elapsed.as_secs_f64(),
+            "requests_per_sec": samples.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            "p50_latency_ms": percentile(&latencies, 0.5),
+            "p99_latency_ms": percentile(&latencies, 0.99),
+            "tokens_per_doc": samples.iter().map(|s| s.tokens).sum::<u64>() as f64 / count,
+            "retries_per_doc": samples.iter().map(|s| s.retries).sum::<usize>() as f64 / count,
+            "failures": samples.iter().filter(|s| s.failed).count(),
+        });
+        println!("{output}");
+    }
+    Ok(())
+}
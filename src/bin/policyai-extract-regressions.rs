@@ -5,13 +5,20 @@
 //! 2. The PolicyAI output does not match the expected output (PolicyAI fails)
 //!
 //! This identifies true regressions where the baseline performs better than PolicyAI.
+//!
+//! `--number-format comma|period` and `--date-order mdy|dmy` read numbers and dates written as
+//! strings before comparing them, so that `"1,000"` against `"1000"` is not reported.
 
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
 use arrrg::CommandLine;
+use policyai::compare::{self, CompareOptions, DateOrder, NumberFormat};
 use policyai::data::EvaluationReport;
 
+/// Numbers within 0.1% of the expected value are considered equal.
+const RELATIVE_TOLERANCE: f64 = 0.001;
+
 #[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
 struct Args {
     #[arrrg(flag, "Include entries where both baseline and PolicyAI fail")]
@@ -22,6 +29,15 @@ struct Args {
 
     #[arrrg(flag, "Ignore order in array comparisons")]
     ignore_array_order: bool,
+
+    #[arrrg(
+        optional,
+        "Read numbers in strings grouped by comma (1,000.5) or period (1.000,5)"
+    )]
+    number_format: Option<String>,
+
+    #[arrrg(optional, "Read dates in strings, taking 01/05/2024 as mdy or dmy")]
+    date_order: Option<String>,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -35,6 +51,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
 
+    if let Err(err) = compare_options(&args) {
+        eprintln!("ERROR: {err}");
+        std::process::exit(1);
+    }
+
     for input_file in &free {
         process_file(input_file, &args)?;
     }
@@ -113,104 +134,35 @@ fn is_regression(report: &EvaluationReport, args: &Args) -> bool {
     false
 }
 
-/// Compare two JSON values for semantic equality with configurable matching options.
-fn values_match(actual: &serde_json::Value, expected: &serde_json::Value, args: &Args) -> bool {
-    values_match_recursive(actual, expected, args)
-}
-
-fn values_match_recursive(
-    actual: &serde_json::Value,
-    expected: &serde_json::Value,
-    args: &Args,
-) -> bool {
-    match (actual, expected) {
-        // Numbers: apply 0.1% tolerance for floating point comparisons
-        (serde_json::Value::Number(a), serde_json::Value::Number(b)) => {
-            if let (Some(a_f64), Some(b_f64)) = (a.as_f64(), b.as_f64()) {
-                // Calculate 0.1% tolerance based on the expected value
-                let tolerance = b_f64.abs() * 0.001; // 0.1% = 0.001
-                (a_f64 - b_f64).abs() <= tolerance
-            } else {
-                // For integers or numbers that can't be converted to f64, use exact equality
-                a == b
-            }
-        }
-
-        // Strings: apply whitespace normalization if specified
-        (serde_json::Value::String(a), serde_json::Value::String(b)) => {
-            if args.ignore_whitespace {
-                normalize_whitespace(a) == normalize_whitespace(b)
-            } else {
-                a == b
-            }
+/// The comparison the command line asks for.
+fn compare_options(args: &Args) -> Result<CompareOptions, String> {
+    let mut options = CompareOptions::exact()
+        .with_relative_tolerance(RELATIVE_TOLERANCE)
+        .with_ignore_whitespace(args.ignore_whitespace)
+        .with_ignore_array_order(args.ignore_array_order);
+    match args.number_format.as_deref() {
+        None => {}
+        Some("comma") => options = options.with_number_format(NumberFormat::CommaThousands),
+        Some("period") => options = options.with_number_format(NumberFormat::PeriodThousands),
+        Some(format) => {
+            return Err(format!(
+                "unknown number format {format:?}; use comma or period"
+            ))
         }
-
-        // Arrays: apply order-independent comparison if specified
-        (serde_json::Value::Array(a), serde_json::Value::Array(b)) => {
-            if args.ignore_array_order {
-                arrays_match_unordered(a, b, args)
-            } else {
-                arrays_match_ordered(a, b, args)
-            }
-        }
-
-        // Objects: recursively compare all fields
-        (serde_json::Value::Object(a), serde_json::Value::Object(b)) => {
-            if a.len() != b.len() {
-                return false;
-            }
-            for (key, a_val) in a {
-                match b.get(key) {
-                    Some(b_val) => {
-                        if !values_match_recursive(a_val, b_val, args) {
-                            return false;
-                        }
-                    }
-                    None => return false,
-                }
-            }
-            true
-        }
-
-        // For all other types (null, bool), use exact equality
-        _ => actual == expected,
     }
-}
-
-fn normalize_whitespace(s: &str) -> String {
-    s.split_whitespace().collect::<Vec<_>>().join(" ")
-}
-
-fn arrays_match_ordered(a: &[serde_json::Value], b: &[serde_json::Value], args: &Args) -> bool {
-    if a.len() != b.len() {
-        return false;
+    match args.date_order.as_deref() {
+        None => {}
+        Some("mdy") => options = options.with_date_order(DateOrder::MonthFirst),
+        Some("dmy") => options = options.with_date_order(DateOrder::DayFirst),
+        Some(order) => return Err(format!("unknown date order {order:?}; use mdy or dmy")),
     }
-    a.iter()
-        .zip(b.iter())
-        .all(|(a_val, b_val)| values_match_recursive(a_val, b_val, args))
+    Ok(options)
 }
 
-fn arrays_match_unordered(a: &[serde_json::Value], b: &[serde_json::Value], args: &Args) -> bool {
-    if a.len() != b.len() {
-        return false;
-    }
-
-    // For each element in a, find a matching element in b
-    let mut b_used = vec![false; b.len()];
-    for a_val in a {
-        let mut found_match = false;
-        for (b_idx, b_val) in b.iter().enumerate() {
-            if !b_used[b_idx] && values_match_recursive(a_val, b_val, args) {
-                b_used[b_idx] = true;
-                found_match = true;
-                break;
-            }
-        }
-        if !found_match {
-            return false;
-        }
-    }
-    true
+/// Compare two JSON values for semantic equality with configurable matching options.
+fn values_match(actual: &serde_json::Value, expected: &serde_json::Value, args: &Args) -> bool {
+    let options = compare_options(args).expect("options are checked in main");
+    compare::values_match(actual, expected, &options)
 }
 
 #[cfg(test)]
@@ -230,6 +182,7 @@ mod tests {
                 policies: vec![],
                 expected,
                 conflicts: None,
+                expected_rules: None,
             },
             metrics: Metrics::default(),
             // Report is preserved only for inspection and debugging;
@@ -237,6 +190,9 @@ mod tests {
             report: Report::default(),
             output: policyai_output,
             baseline: baseline_output,
+            model: None,
+            input_hash: None,
+            policy_set_hash: None,
         }
     }
 
@@ -534,4 +490,30 @@ mod tests {
         // Type mismatch should always fail regardless of matching options
         assert!(is_regression(&report, &args));
     }
+
+    #[test]
+    fn formatting_noise_is_not_a_regression() {
+        let expected = serde_json::json!({"amount": "1000", "due": "2024-01-05"});
+        let policyai_output = serde_json::json!({"amount": "1,000", "due": "Jan 5"});
+        let baseline_output = expected.clone();
+
+        let report = create_test_report(Some(expected), policyai_output, Some(baseline_output));
+
+        assert!(is_regression(&report, &Args::default()));
+        let args = Args {
+            number_format: Some("comma".to_string()),
+            date_order: Some("mdy".to_string()),
+            ..Default::default()
+        };
+        assert!(!is_regression(&report, &args));
+    }
+
+    #[test]
+    fn unknown_formats_are_rejected() {
+        let args = Args {
+            number_format: Some("space".to_string()),
+            ..Default::default()
+        };
+        assert!(compare_options(&args).is_err());
+    }
 }
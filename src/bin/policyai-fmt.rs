@@ -0,0 +1,77 @@
+//! Canonicalize policy type files.
+//!
+//! Each positional argument is a file holding one policy type; with none, a type is read from
+//! stdin.  The type is parsed and written back out as [`policyai::PolicyType`] displays it, so
+//! two files that format the same describe the same type.
+//!
+//! With `--write` each file is rewritten in place.  With `--check` nothing is written, and the
+//! exit status is 1 if any file is not already canonical, for use in CI.
+
+use std::io::Read;
+
+use arrrg::CommandLine;
+
+use policyai::PolicyType;
+
+const USAGE: &str = "USAGE: policyai-fmt [--check|--write] [policy_type_file...]";
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(flag, "Exit 1 if any file is not canonical, without writing")]
+    check: bool,
+    #[arrrg(flag, "Rewrite each file in place")]
+    write: bool,
+}
+
+/// The canonical form of the policy type in `input`.
+fn format(name: &str, input: &str) -> Result<String, String> {
+    let policy_type = PolicyType::parse(input).map_err(|err| format!("{name}: {err}"))?;
+    Ok(format!("{policy_type}\n"))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(USAGE);
+    if args.check && args.write {
+        eprintln!("--check and --write cannot be combined");
+        std::process::exit(1);
+    }
+    if free.is_empty() {
+        if args.write {
+            eprintln!("--write needs at least one file");
+            std::process::exit(1);
+        }
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        let formatted = format("<stdin>", &input)?;
+        if args.check {
+            if formatted != input {
+                eprintln!("<stdin> is not formatted");
+                std::process::exit(1);
+            }
+        } else {
+            print!("{formatted}");
+        }
+        return Ok(());
+    }
+    let mut unformatted = 0;
+    for path in free.iter() {
+        let input = std::fs::read_to_string(path)?;
+        let formatted = format(path, &input)?;
+        if args.check {
+            if formatted != input {
+                eprintln!("{path} is not formatted");
+                unformatted += 1;
+            }
+        } else if args.write {
+            if formatted != input {
+                std::fs::write(path, &formatted)?;
+            }
+        } else {
+            print!("{formatted}");
+        }
+    }
+    if unformatted > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Compare the accuracy and cost of evaluation runs from different configurations.
+//!
+//! Each input file holds the evaluation reports of one run.  Files may be given as
+//! `label=path`; otherwise reports are labelled by their model, or by the path when they have
+//! none.  The output lists every configuration, marks the ones on the cost/accuracy frontier,
+//! and describes what each step along the frontier buys.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use arrrg::CommandLine;
+use policyai::analysis::{Frontier, TokenWeights};
+use policyai::data::EvaluationReport;
+
+#[derive(Clone, Default, Debug, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(optional, "Output format (json, text)")]
+    format: Option<String>,
+    #[arrrg(optional, "Price of an output token relative to an input token")]
+    output_weight: Option<f64>,
+}
+
+impl Eq for Args {}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(
+        "USAGE: policyai-frontier-report [OPTIONS] [label=]input_file...",
+    );
+    if free.is_empty() {
+        eprintln!("expected at least one evaluation file");
+        std::process::exit(1);
+    }
+    let mut weights = TokenWeights::default();
+    if let Some(output_weight) = args.output_weight {
+        weights.output = output_weight;
+    }
+    let mut frontier = Frontier::new().with_weights(weights);
+    for arg in free.iter() {
+        let (label, path) = match arg.split_once('=') {
+            Some((label, path)) => (Some(label), path),
+            None => (None, arg.as_str()),
+        };
+        for report in read_reports(path)? {
+            let config = label
+                .map(str::to_string)
+                .or_else(|| report.model.clone())
+                .unwrap_or_else(|| path.to_string());
+            frontier.add_report(&config, &report.metrics);
+        }
+    }
+
+    match args.format.as_deref().unwrap_or("text") {
+        "json" => {
+            let on_frontier = frontier.frontier();
+            let output = serde_json::json!({
+                "weights": frontier.weights,
+                "points": frontier.points.values().map(|point| serde_json::json!({
+                    "config": point.config,
+                    "reports": point.reports,
+                    "accuracy": point.accuracy(),
+                    "avg_cost": point.avg_cost(),
+                    "on_frontier": on_frontier.contains(&point),
+                })).collect::<Vec<_>>(),
+                "tradeoffs": frontier.tradeoffs(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        _ => print_text(&frontier),
+    }
+    Ok(())
+}
+
+fn print_text(frontier: &Frontier) {
+    let on_frontier = frontier.frontier();
+    println!(
+        "{:<32} {:>8} {:>10} {:>12}  frontier",
+        "config", "reports", "accuracy", "avg cost"
+    );
+    let mut points = frontier.points.values().collect::<Vec<_>>();
+    points.sort_by(|a, b| a.avg_cost().total_cmp(&b.avg_cost()));
+    for point in points {
+        println!(
+            "{:<32} {:>8} {:>9.2}% {:>12.1}  {}",
+            point.config,
+            point.reports,
+            point.accuracy() * 100.0,
+            point.avg_cost(),
+            if on_frontier.contains(&point) {
+                "*"
+            } else {
+                ""
+            }
+        );
+    }
+    let tradeoffs = frontier.tradeoffs();
+    if !tradeoffs.is_empty() {
+        println!();
+        for tradeoff in tradeoffs {
+            println!(
+                "{} -> {}: +{:.2} points accuracy for {:+.1}% cost",
+                tradeoff.from,
+                tradeoff.to,
+                tradeoff.accuracy_gain,
+                tradeoff.relative_cost_increase * 100.0
+            );
+        }
+    }
+}
+
+fn read_reports(path: &str) -> Result<Vec<EvaluationReport>, Box<dyn std::error::Error>> {
+    let mut reports = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                eprintln!("Warning: Failed to parse line in {path} as EvaluationReport: {e}");
+            }
+        }
+    }
+    Ok(reports)
+}
@@ -0,0 +1,124 @@
+//! Show how accuracy changes as more of an evaluation file is taken into account.
+//!
+//! Reports are read in file order and scored over growing prefixes of the file, each with a 95%
+//! confidence interval.  The output ends by naming the smallest prefix whose interval holds
+//! the accuracy of every larger one; if that is the whole file, more test data may still change
+//! the result.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use arrrg::CommandLine;
+use policyai::analysis::{LearningCurve, LearningCurvePoint};
+use policyai::data::EvaluationReport;
+
+#[derive(Clone, Default, Debug, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(optional, "Output format (json, text)")]
+    format: Option<String>,
+    #[arrrg(
+        optional,
+        "Comma-separated fractions of the file to score, e.g. 0.1,0.5,1"
+    )]
+    fractions: Option<String>,
+}
+
+impl Eq for Args {}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) =
+        Args::from_command_line_relaxed("USAGE: policyai-learning-curve [OPTIONS] input_file");
+    if free.len() != 1 {
+        eprintln!("expected exactly one evaluation file");
+        std::process::exit(1);
+    }
+    let mut curve = LearningCurve::new();
+    if let Some(fractions) = &args.fractions {
+        let fractions = fractions
+            .split(',')
+            .map(|fraction| fraction.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        curve = curve.with_fractions(fractions);
+    }
+    for report in read_reports(&free[0])? {
+        curve.add_report(&report.metrics);
+    }
+
+    let points = curve.points();
+    let settled = curve.settled_at();
+    match args.format.as_deref().unwrap_or("text") {
+        "json" => {
+            let output = serde_json::json!({
+                "points": points.iter().map(|point| {
+                    let (low, high) = point.accuracy_interval();
+                    let (exact_low, exact_high) = point.exact_rate_interval();
+                    serde_json::json!({
+                        "fraction": point.fraction,
+                        "reports": point.reports,
+                        "accuracy": point.accuracy(),
+                        "accuracy_interval": [low, high],
+                        "exact_rate": point.exact_rate(),
+                        "exact_rate_interval": [exact_low, exact_high],
+                    })
+                }).collect::<Vec<_>>(),
+                "settled_at": settled.as_ref().map(|point| point.reports),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        _ => print_text(&points, settled.as_ref()),
+    }
+    Ok(())
+}
+
+fn print_text(points: &[LearningCurvePoint], settled: Option<&LearningCurvePoint>) {
+    println!(
+        "{:>8} {:>8} {:>10} {:>19} {:>10} {:>19}",
+        "fraction", "reports", "accuracy", "95% interval", "exact", "95% interval"
+    );
+    for point in points {
+        let (low, high) = point.accuracy_interval();
+        let (exact_low, exact_high) = point.exact_rate_interval();
+        println!(
+            "{:>7.0}% {:>8} {:>9.2}% {:>8.2}% - {:>6.2}% {:>9.2}% {:>8.2}% - {:>6.2}%",
+            point.fraction * 100.0,
+            point.reports,
+            point.accuracy() * 100.0,
+            low * 100.0,
+            high * 100.0,
+            point.exact_rate() * 100.0,
+            exact_low * 100.0,
+            exact_high * 100.0,
+        );
+    }
+    match (settled, points.last()) {
+        (Some(settled), Some(last)) if settled.reports < last.reports => {
+            println!();
+            println!(
+                "accuracy settled after {} of {} reports",
+                settled.reports, last.reports
+            );
+        }
+        (Some(_), Some(_)) => {
+            println!();
+            println!("accuracy was still moving at the end of the file");
+        }
+        _ => println!("no reports"),
+    }
+}
+
+fn read_reports(path: &str) -> Result<Vec<EvaluationReport>, Box<dyn std::error::Error>> {
+    let mut reports = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                eprintln!("Warning: Failed to parse line in {path} as EvaluationReport: {e}");
+            }
+        }
+    }
+    Ok(reports)
+}
@@ -0,0 +1,74 @@
+//! Upgrade stored evaluation reports to the current layout.
+//!
+//! This binary reads evaluation reports as JSONL from files or stdin, tolerates fields that older
+//! versions did not emit, and writes the migrated reports as JSONL to stdout.  With
+//! `--strip-messages` the LLM conversation is dropped from each report, which shrinks a store of
+//! reports severalfold.
+//!
+//! With `--dedup` only the first evaluation of each input by each model is written, across every
+//! input file, so that a corpus assembled from overlapping runs does not count an input twice.
+//! Inputs are identified by the hash of their text and policies.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use arrrg::CommandLine;
+use policyai::data::{EvaluationDeduplicator, EvaluationReport};
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(flag, "Skip lines that cannot be migrated instead of stopping")]
+    skip_invalid: bool,
+    #[arrrg(flag, "Drop the LLM conversation from each report")]
+    strip_messages: bool,
+    #[arrrg(flag, "Drop repeated evaluations of an input by the same model")]
+    dedup: bool,
+}
+
+fn migrate_lines(
+    reader: impl BufRead,
+    source: &str,
+    args: &Args,
+    dedup: &mut EvaluationDeduplicator,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let migrated = serde_json::from_str(&line).and_then(EvaluationReport::migrate);
+        match migrated {
+            Ok(report) if args.dedup && !dedup.first_time(&report) => {}
+            Ok(mut report) => {
+                if args.strip_messages {
+                    report.report.strip_messages();
+                }
+                println!("{}", serde_json::to_string(&report)?)
+            }
+            Err(err) if args.skip_invalid => {
+                eprintln!("{source}:{}: skipping: {err}", idx + 1);
+            }
+            Err(err) => return Err(format!("{source}:{}: {err}", idx + 1).into()),
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(
+        "USAGE: policyai-migrate-reports [--skip-invalid] [--strip-messages] [--dedup] [input_file...]",
+    );
+    let mut dedup = EvaluationDeduplicator::default();
+    if free.is_empty() {
+        migrate_lines(io::stdin().lock(), "<stdin>", &args, &mut dedup)?;
+    } else {
+        for path in free.iter() {
+            let file = File::open(path)?;
+            migrate_lines(BufReader::new(file), path, &args, &mut dedup)?;
+        }
+    }
+    if args.dedup {
+        eprintln!("dropped {} repeated evaluations", dedup.duplicates());
+    }
+    Ok(())
+}
@@ -7,7 +7,9 @@ use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read};
 
 use arrrg::CommandLine;
-use policyai::analysis::{ConfusionMatrix, FieldMatchAccuracyMatrix, RegressionAnalysis};
+use policyai::analysis::{
+    ConfusionMatrix, FieldMatchAccuracyMatrix, RegressionAnalysis, RuleAttributionAnalysis,
+};
 use policyai::data::EvaluationReport;
 
 #[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
@@ -61,12 +63,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+fn rule_attribution(reports: &[EvaluationReport]) -> RuleAttributionAnalysis {
+    let mut attribution = RuleAttributionAnalysis::new();
+    for report in reports {
+        attribution.add_report(report);
+    }
+    attribution
+}
+
 fn print_json(
     analysis: &RegressionAnalysis,
     accuracy_matrix: &FieldMatchAccuracyMatrix,
-    _reports: &[EvaluationReport],
+    reports: &[EvaluationReport],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let output = serde_json::json!({
+    let mut output = serde_json::json!({
         "summary": {
             "total_reports": analysis.total_reports,
             "policyai": {
@@ -76,6 +86,7 @@ fn print_json(
                 "total_extra_fields": analysis.policyai_total_extra_fields,
                 "error_rate": analysis.policyai_error_rate(),
                 "avg_duration_ms": analysis.policyai_avg_duration_ms(),
+                "total_cost_usd": analysis.policyai_total_cost_usd,
             },
             "baseline": {
                 "avg_fields_matched": analysis.baseline_avg_fields_matched(),
@@ -84,6 +95,7 @@ fn print_json(
                 "total_extra_fields": analysis.baseline_total_extra_fields,
                 "error_rate": analysis.baseline_error_rate(),
                 "avg_duration_ms": analysis.baseline_avg_duration_ms(),
+                "total_cost_usd": analysis.baseline_total_cost_usd,
             },
             "comparison": {
                 "fields_matched_improvement": analysis.policyai_avg_fields_matched() - analysis.baseline_avg_fields_matched(),
@@ -110,6 +122,10 @@ fn print_json(
             }
         }
     });
+    let attribution = rule_attribution(reports);
+    if attribution.reports > 0 {
+        output["summary"]["rule_attribution"] = serde_json::to_value(&attribution)?;
+    }
     println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
@@ -197,7 +213,7 @@ fn print_csv(
 fn print_text(
     analysis: &RegressionAnalysis,
     accuracy_matrix: &FieldMatchAccuracyMatrix,
-    _reports: &[EvaluationReport],
+    reports: &[EvaluationReport],
     verbose: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("PolicyAI Regression Analysis Report");
@@ -253,6 +269,16 @@ fn print_text(
         let speed_ratio = analysis.baseline_avg_duration_ms() / analysis.policyai_avg_duration_ms();
         println!("  Speed ratio (baseline/policyai): {:.2}x", speed_ratio);
     }
+    if analysis.policyai_total_cost_usd > 0.0 || analysis.baseline_total_cost_usd > 0.0 {
+        println!(
+            "  PolicyAI total cost: ${:.4}",
+            analysis.policyai_total_cost_usd
+        );
+        println!(
+            "  Baseline total cost: ${:.4}",
+            analysis.baseline_total_cost_usd
+        );
+    }
     println!();
 
     println!("Field Quality:");
@@ -283,6 +309,30 @@ fn print_text(
         &accuracy_matrix.confusion_matrix,
     );
 
+    let attribution = rule_attribution(reports);
+    if attribution.reports > 0 {
+        print_confusion_matrix_text(
+            "Rule Attribution (expected vs matched rules)",
+            &attribution.overall,
+        );
+        for (title, prompts) in [
+            ("Over-triggering rules", attribution.over_triggering()),
+            ("Under-triggering rules", attribution.under_triggering()),
+        ] {
+            if prompts.is_empty() {
+                continue;
+            }
+            println!("{title}:");
+            for (prompt, matrix) in prompts.iter().take(10) {
+                println!(
+                    "  {} FP, {} FN: {prompt}",
+                    matrix.false_positive, matrix.false_negative
+                );
+            }
+            println!();
+        }
+    }
+
     if verbose {
         println!("Additional Details:");
         println!("------------------");
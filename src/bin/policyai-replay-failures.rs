@@ -0,0 +1,72 @@
+//! Apply recorded failures again to see which of them are fixed.
+//!
+//! Each input file holds the failures a [`policyai::JsonlFailureStore`] recorded.  Every
+//! failure's policies are applied to its text again, with the original model unless `--model`
+//! overrides it.  One JSON line per failure is written to stdout saying whether it now succeeds.
+//! The exit status is 1 if any failure still fails.
+
+use std::collections::BTreeSet;
+
+use arrrg::CommandLine;
+use claudius::{Anthropic, Model};
+
+use policyai::{JsonlFailureStore, Manager};
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(optional, "Replay with this model instead of the recorded one")]
+    model: Option<String>,
+    #[arrrg(flag, "Replay each distinct text and policy set only once")]
+    dedup: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(
+        "USAGE: policyai-replay-failures [--model MODEL] [--dedup] [failure_file...]",
+    );
+    let client = Anthropic::new(None)?;
+    let mut seen = BTreeSet::new();
+    let mut replayed = 0u64;
+    let mut fixed = 0u64;
+    for path in free.iter() {
+        for failure in JsonlFailureStore::load(path)? {
+            let mut manager = Manager::default();
+            for policy in failure.policies.iter() {
+                manager.add(policy.clone());
+            }
+            let key = manager.cache_key(&failure.text);
+            if args.dedup && !seen.insert(key) {
+                continue;
+            }
+            let mut template = failure.template();
+            if let Some(model) = &args.model {
+                template.model = Model::Custom(model.clone());
+            }
+            replayed += 1;
+            let output = match manager.apply(&client, template, &failure.text, None).await {
+                Ok(report) => {
+                    fixed += 1;
+                    serde_json::json!({
+                        "text_hash": failure.text_hash,
+                        "previous_error": failure.error,
+                        "fixed": true,
+                        "value": report.value(),
+                    })
+                }
+                Err(err) => serde_json::json!({
+                    "text_hash": failure.text_hash,
+                    "previous_error": failure.error,
+                    "fixed": false,
+                    "error": err.to_string(),
+                }),
+            };
+            println!("{output}");
+        }
+    }
+    eprintln!("replayed {replayed} failures: {fixed} fixed");
+    if fixed < replayed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//! Inspect stored reports without writing jq.
+//!
+//! Reads reports as JSONL from files or stdin.  Each line may be a bare `Report` or an
+//! `EvaluationReport`, whose embedded report is used; reports of any vintage are migrated first.
+//! Every line of output starts with `file:line` so that it can be traced back to its report.
+//!
+//! Subcommands:
+//!
+//! - `show`: the value of each report as an indented tree.
+//! - `get FIELD`: the value of one field of each report, as JSON.  `FIELD` may be a dotted path.
+//! - `conflicts`: every unresolved conflict, one per line.
+//! - `errors`: every error recorded while building each report.
+//! - `usage`: the summary counts of each report, then their totals.
+//! - `ir-values`: how often the values in the reports' IRs broke their masks' schema, by field
+//!   and by kind of mask, and the IR keys that belong to no mask.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use arrrg::CommandLine;
+
+use policyai::analysis::IrValueAnalysis;
+use policyai::data::EvaluationReport;
+use policyai::{Report, ReportSummary};
+
+const USAGE: &str = "USAGE: policyai-report-tool [--skip-invalid] <show|get FIELD|conflicts|errors|usage|ir-values> [input_file...]";
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(flag, "Skip lines that are not reports instead of stopping")]
+    skip_invalid: bool,
+}
+
+enum Command {
+    Show,
+    Get(String),
+    Conflicts,
+    Errors,
+    Usage,
+    IrValues,
+}
+
+/// Parse one line as a report, unwrapping evaluation reports.
+fn parse_report(line: &str) -> Result<Report, serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_str(line)?;
+    if value.get("input").is_some() {
+        Ok(EvaluationReport::migrate(value)?.report)
+    } else {
+        Report::migrate(value)
+    }
+}
+
+/// Write `value` as an indented tree, one scalar per line.
+fn print_tree(value: &serde_json::Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match value {
+        serde_json::Value::Object(obj) => {
+            for (key, value) in obj.iter() {
+                if value.is_object() || value.as_array().is_some_and(|a| !a.is_empty()) {
+                    println!("{pad}{key}:");
+                    print_tree(value, indent + 1);
+                } else {
+                    println!("{pad}{key}: {value}");
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values.iter() {
+                if value.is_object() || value.is_array() {
+                    println!("{pad}-");
+                    print_tree(value, indent + 1);
+                } else {
+                    println!("{pad}- {value}");
+                }
+            }
+        }
+        value => println!("{pad}{value}"),
+    }
+}
+
+fn run(
+    command: &Command,
+    report: &Report,
+    location: &str,
+    totals: &mut ReportSummary,
+    reports: &mut usize,
+    ir_values: &mut IrValueAnalysis,
+) {
+    match command {
+        Command::Show => {
+            println!("{location}:");
+            print_tree(&report.value(), 1);
+        }
+        Command::Get(field) => {
+            let value = field
+                .split('.')
+                .try_fold(report.value(), |value, key| value.get(key).cloned());
+            println!("{location}: {}", value.unwrap_or(serde_json::Value::Null));
+        }
+        Command::Conflicts => {
+            for conflict in report.conflicts() {
+                println!("{location}: {conflict}");
+            }
+        }
+        Command::Errors => {
+            for err in report.errors() {
+                // Errors carry a suggestion on a second line; keep one error per line.
+                println!("{location}: {}", err.to_string().replace('\n', "; "));
+            }
+        }
+        Command::Usage => {
+            let summary = report.summary();
+            println!("{location}: {summary}");
+            totals.fields_set += summary.fields_set;
+            totals.fields_defaulted += summary.fields_defaulted;
+            totals.rules_matched += summary.rules_matched;
+            totals.conflicts += summary.conflicts;
+            totals.errors += summary.errors;
+            totals.retries += summary.retries;
+            totals.tokens += summary.tokens;
+            *reports += 1;
+        }
+        Command::IrValues => ir_values.add_report(report),
+    }
+}
+
+/// Write the fields whose IR values most often broke the schema, then the kinds of mask, then
+/// the keys that belong to no mask.
+fn print_ir_values(analysis: &IrValueAnalysis) {
+    println!(
+        "{} reports, {} without an IR",
+        analysis.reports, analysis.reports_without_ir
+    );
+    let line = |name: &str, counts: &policyai::analysis::IrValueCounts| {
+        println!(
+            "{name}: {:.1}% of {} values broke the schema ({} null, {} wrong type, {} invalid)",
+            counts.violation_rate() * 100.0,
+            counts.total(),
+            counts.nulls,
+            counts.wrong_type,
+            counts.invalid
+        );
+    };
+    for (field, counts) in analysis.most_confused() {
+        line(field, counts);
+    }
+    for (kind, counts) in analysis.by_kind.iter() {
+        line(&format!("kind {kind}"), counts);
+    }
+    for (key, count) in analysis.unknown_keys.iter() {
+        println!("unknown key {key:?}: {count} times");
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(USAGE);
+    let mut free = free.into_iter();
+    let command = match free.next().as_deref() {
+        Some("show") => Command::Show,
+        Some("get") => match free.next() {
+            Some(field) => Command::Get(field),
+            None => {
+                eprintln!("{USAGE}");
+                std::process::exit(1);
+            }
+        },
+        Some("conflicts") => Command::Conflicts,
+        Some("errors") => Command::Errors,
+        Some("usage") => Command::Usage,
+        Some("ir-values") => Command::IrValues,
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(1);
+        }
+    };
+    let paths = free.collect::<Vec<_>>();
+    let inputs: Vec<(String, Box<dyn BufRead>)> = if paths.is_empty() {
+        vec![("<stdin>".to_string(), Box::new(io::stdin().lock()))]
+    } else {
+        let mut inputs: Vec<(String, Box<dyn BufRead>)> = vec![];
+        for path in paths {
+            let file = BufReader::new(File::open(&path)?);
+            inputs.push((path, Box::new(file)));
+        }
+        inputs
+    };
+    let mut totals = ReportSummary::default();
+    let mut reports = 0;
+    let mut ir_values = IrValueAnalysis::new();
+    for (source, reader) in inputs {
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let location = format!("{source}:{}", idx + 1);
+            match parse_report(&line) {
+                Ok(report) => run(
+                    &command,
+                    &report,
+                    &location,
+                    &mut totals,
+                    &mut reports,
+                    &mut ir_values,
+                ),
+                Err(err) if args.skip_invalid => eprintln!("{location}: skipping: {err}"),
+                Err(err) => return Err(format!("{location}: {err}").into()),
+            }
+        }
+    }
+    match command {
+        Command::Usage => println!("total ({reports} reports): {totals}"),
+        Command::IrValues => print_ir_values(&ir_values),
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Check that stored policies still mean the same thing under a new model.
+//!
+//! Each input line is a policy.  The policy's prompt is turned into an action again with the
+//! given model and compared with the stored action.  Every policy whose action would change is
+//! written to stdout as a JSON line listing the fields that diverge.  The exit status is 1 if any
+//! policy diverged and 2 if any could not be checked.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use arrrg::CommandLine;
+use claudius::{Anthropic, Model};
+
+use policyai::Policy;
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(required, "Model to derive actions with")]
+    model: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(
+        "USAGE: policyai-revalidate-policies --model MODEL [policy_file...]",
+    );
+    let client = Anthropic::new(None)?;
+    let mut checked = 0u64;
+    let mut diverged = 0u64;
+    let mut failed = 0u64;
+    for path in free.iter() {
+        for (idx, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let policy: Policy = match serde_json::from_str(&line) {
+                Ok(policy) => policy,
+                Err(err) => {
+                    eprintln!("{path}:{}: could not parse policy: {err}", idx + 1);
+                    failed += 1;
+                    continue;
+                }
+            };
+            checked += 1;
+            match policy
+                .revalidate(&client, Model::Custom(args.model.clone()))
+                .await
+            {
+                Ok(divergences) if divergences.is_empty() => {}
+                Ok(divergences) => {
+                    diverged += 1;
+                    let output = serde_json::json!({
+                        "file": path,
+                        "line": idx + 1,
+                        "prompt": policy.prompt,
+                        "divergences": divergences,
+                    });
+                    println!("{output}");
+                }
+                Err(err) => {
+                    eprintln!("{path}:{}: could not revalidate: {err}", idx + 1);
+                    failed += 1;
+                }
+            }
+        }
+    }
+    eprintln!(
+        "checked {checked} policies against {}: {diverged} diverged, {failed} failed",
+        args.model
+    );
+    if failed > 0 {
+        std::process::exit(2);
+    }
+    if diverged > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -3,11 +3,21 @@ use std::io::Read;
 use policyai::PolicyType;
 
 fn main() {
+    let relaxed = std::env::args().skip(1).any(|arg| arg == "--relaxed");
     let mut buf = vec![];
     std::io::stdin()
         .read_to_end(&mut buf)
         .expect("could not read policy type on stdin");
     let buf = String::from_utf8(buf).expect("policy type should be UTF8");
-    let policy_type = PolicyType::parse(&buf).expect("policy type should be valid");
+    let policy_type = if relaxed {
+        let (policy_type, warnings) =
+            PolicyType::parse_relaxed(&buf).expect("policy type should be valid");
+        for warning in warnings {
+            eprintln!("warning: {warning}");
+        }
+        policy_type
+    } else {
+        PolicyType::parse(&buf).expect("policy type should be valid")
+    };
     println!("{}", serde_json::to_value(policy_type).unwrap());
 }
@@ -0,0 +1,129 @@
+//! Roll up the spend recorded in usage logs, for budgeting.
+//!
+//! Reads the JSONL files a [`policyai::JsonlUsageLog`] writes and prints one line per day and
+//! model with the number of applies, how many failed, tokens, dollars, and time spent, then a
+//! total.  `--by day` or `--by model` rolls up along only one of the two.  Days are UTC.
+//!
+//! Applies to models without a price still count toward applies and tokens; the number of them
+//! is reported so that a cost that looks low is not taken at face value.
+
+use std::collections::BTreeMap;
+
+use arrrg::CommandLine;
+
+use policyai::{JsonlUsageLog, UsageRecord};
+
+const USAGE: &str =
+    "USAGE: policyai-usage-summary [--by day|model|day,model] [--since YYYY-MM-DD] [--until YYYY-MM-DD] [--json] usage.jsonl...";
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(optional, "Roll up by day, model, or day,model (default day,model)")]
+    by: Option<String>,
+    #[arrrg(optional, "Skip days before this one, as YYYY-MM-DD")]
+    since: Option<String>,
+    #[arrrg(optional, "Skip days after this one, as YYYY-MM-DD")]
+    until: Option<String>,
+    #[arrrg(flag, "Write JSON lines instead of a table")]
+    json: bool,
+}
+
+/// The totals of one group of records.
+#[derive(Debug, Default)]
+struct Rollup {
+    applies: usize,
+    failures: usize,
+    cache_hits: usize,
+    tokens: u64,
+    cost_usd: f64,
+    unpriced: usize,
+    duration_ms: u64,
+}
+
+impl Rollup {
+    fn add(&mut self, record: &UsageRecord) {
+        self.applies += 1;
+        self.failures += usize::from(!record.succeeded);
+        self.cache_hits += usize::from(record.cache_hit);
+        self.tokens += record.total_tokens();
+        match record.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced += 1,
+        }
+        self.duration_ms += record.duration_ms;
+    }
+
+    fn print(&self, key: &str, json: bool) {
+        if json {
+            let line = serde_json::json!({
+                "key": key,
+                "applies": self.applies,
+                "failures": self.failures,
+                "cache_hits": self.cache_hits,
+                "tokens": self.tokens,
+                "cost_usd": self.cost_usd,
+                "unpriced": self.unpriced,
+                "duration_secs": self.duration_ms as f64 / 1000.0,
+            });
+            println!("{line}");
+        } else {
+            println!(
+                "{key:<40} {:>8} {:>8} {:>12} {:>12.4} {:>8} {:>10.1}",
+                self.applies,
+                self.failures,
+                self.tokens,
+                self.cost_usd,
+                self.unpriced,
+                self.duration_ms as f64 / 1000.0,
+            );
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(USAGE);
+    if free.is_empty() {
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    }
+    let (by_day, by_model) = match args.by.as_deref().unwrap_or("day,model") {
+        "day" => (true, false),
+        "model" => (false, true),
+        "day,model" | "model,day" => (true, true),
+        by => {
+            eprintln!("cannot roll up by {by:?}; use day, model, or day,model");
+            std::process::exit(1);
+        }
+    };
+    let mut groups: BTreeMap<String, Rollup> = BTreeMap::new();
+    let mut total = Rollup::default();
+    for path in free.iter() {
+        for record in JsonlUsageLog::load(path)? {
+            let day = record.day();
+            // Days in YYYY-MM-DD form compare correctly as strings.
+            if args.since.as_ref().is_some_and(|since| day < *since)
+                || args.until.as_ref().is_some_and(|until| day > *until)
+            {
+                continue;
+            }
+            let key = match (by_day, by_model) {
+                (true, true) => format!("{day} {}", record.model),
+                (true, false) => day,
+                _ => record.model.clone(),
+            };
+            groups.entry(key).or_default().add(&record);
+            total.add(&record);
+        }
+    }
+    if !args.json {
+        println!(
+            "{:<40} {:>8} {:>8} {:>12} {:>12} {:>8} {:>10}",
+            "group", "applies", "failed", "tokens", "cost_usd", "unpriced", "secs"
+        );
+    }
+    for (key, rollup) in groups.iter() {
+        rollup.print(key, args.json);
+    }
+    total.print("total", args.json);
+    Ok(())
+}
@@ -0,0 +1,247 @@
+//! Apply policies to every message delivered to a Maildir, or stored in an mbox.
+//!
+//! Each positional argument is a Maildir whose `new/` directory is read, or with `--mbox` an
+//! mbox file.  Every message's raw body is given to a manager holding the policies in
+//! `--policies`, a file of JSON policies one per line, with its From, To, Date, and Subject
+//! headers as metadata.
+//! One report per message is written as a JSON line, annotated with where the message came
+//! from, its Message-ID, and its subject.
+//!
+//! With `--follow` the Maildirs are polled for new deliveries until interrupted.  With
+//! `--mark-seen` each processed message is moved to `cur/` and flagged seen, as a mail client
+//! would.  Bodies are not MIME-decoded; this is a demonstration, not a mail client.
+
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use arrrg::CommandLine;
+use claudius::{Anthropic, MessageCreateParams, Model};
+
+use policyai::{Manager, Metadata, Policy};
+
+#[derive(Clone, Default, Debug, Eq, PartialEq, arrrg_derive::CommandLine)]
+struct Args {
+    #[arrrg(required, "File of JSON policies, one per line")]
+    policies: String,
+    #[arrrg(
+        optional,
+        "Model to apply the policies with (default claude-haiku-4-5)"
+    )]
+    model: Option<String>,
+    #[arrrg(flag, "Read mbox files instead of Maildirs")]
+    mbox: bool,
+    #[arrrg(flag, "Keep polling the Maildirs for new messages")]
+    follow: bool,
+    #[arrrg(optional, "Seconds between polls with --follow (default 5)")]
+    poll_secs: Option<u64>,
+    #[arrrg(flag, "Move each processed message from new/ to cur/ and flag it seen")]
+    mark_seen: bool,
+    #[arrrg(
+        optional,
+        "Append reports to this file instead of writing them to stdout"
+    )]
+    output: Option<String>,
+}
+
+/// The parts of a message the policies see.
+struct Message {
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Message {
+    fn parse(raw: &str) -> Self {
+        let raw = raw.replace("\r\n", "\n");
+        let (head, body) = raw.split_once("\n\n").unwrap_or((&raw, ""));
+        let mut headers: Vec<(String, String)> = vec![];
+        for line in head.lines() {
+            if line.starts_with([' ', '\t']) {
+                // A folded continuation of the previous header.
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        Self {
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The headers given to the manager alongside the body.
+    fn metadata(&self) -> Metadata {
+        let mut metadata = Metadata {
+            sender: self.header("From").map(String::from),
+            subject: self.header("Subject").map(String::from),
+            timestamp: self.header("Date").map(String::from),
+            ..Default::default()
+        };
+        if let Some(to) = self.header("To") {
+            metadata = metadata.with_field("to", to);
+        }
+        metadata
+    }
+}
+
+/// Split an mbox into its raw messages.
+fn read_mbox(path: &str) -> std::io::Result<Vec<String>> {
+    let mut messages = vec![];
+    let mut current: Option<String> = None;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.starts_with("From ") {
+            messages.extend(current.take());
+            current = Some(String::new());
+            continue;
+        }
+        let Some(message) = current.as_mut() else {
+            continue;
+        };
+        // Undo mboxrd quoting of body lines that begin with "From ".
+        let line = match line.strip_prefix('>') {
+            Some(rest) if rest.trim_start_matches('>').starts_with("From ") => rest,
+            _ => &line,
+        };
+        message.push_str(line);
+        message.push('\n');
+    }
+    messages.extend(current);
+    Ok(messages)
+}
+
+/// The messages in `maildir`'s `new/` that have not been processed yet, oldest name first.
+fn new_messages(maildir: &Path, seen: &BTreeSet<PathBuf>) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(maildir.join("new"))? {
+        let path = entry?.path();
+        if path.is_file() && !seen.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Move a processed message into `cur/`, flagged seen.
+fn mark_seen(maildir: &Path, path: &Path) -> std::io::Result<()> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Ok(());
+    };
+    let name = name.split_once(":2,").map_or(name, |(base, _)| base);
+    std::fs::rename(path, maildir.join("cur").join(format!("{name}:2,S")))
+}
+
+struct Watcher {
+    client: Anthropic,
+    manager: Manager,
+    template: MessageCreateParams,
+    output: Box<dyn Write>,
+}
+
+impl Watcher {
+    /// Apply the policies to `raw` and write the annotated report.
+    async fn process(&mut self, source: &str, raw: &str) -> std::io::Result<()> {
+        let message = Message::parse(raw);
+        let mut report = match self
+            .manager
+            .apply_with_metadata(
+                &self.client,
+                self.template.clone(),
+                &message.body,
+                message.metadata(),
+                None,
+            )
+            .await
+        {
+            Ok(report) => report,
+            Err(err) => {
+                eprintln!("{source}: {err}");
+                return Ok(());
+            }
+        };
+        report.annotate("source", source);
+        if let Some(message_id) = message.header("Message-ID") {
+            report.annotate("message_id", message_id);
+        }
+        if let Some(subject) = message.header("Subject") {
+            report.annotate("subject", subject);
+        }
+        writeln!(self.output, "{}", serde_json::to_string(&report)?)?;
+        self.output.flush()
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (args, free) = Args::from_command_line_relaxed(
+        "USAGE: policyai-watch-maildir --policies FILE [--mbox] [--follow] [--mark-seen] [--output FILE] maildir...",
+    );
+    let mut manager = Manager::default();
+    for line in BufReader::new(File::open(&args.policies)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let policy: Policy = serde_json::from_str(&line)?;
+        manager.add(policy);
+    }
+    let output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(OpenOptions::new().create(true).append(true).open(path)?),
+        None => Box::new(std::io::stdout()),
+    };
+    let mut watcher = Watcher {
+        client: Anthropic::new(None)?,
+        manager,
+        template: MessageCreateParams {
+            max_tokens: 4096,
+            model: Model::Custom(
+                args.model
+                    .clone()
+                    .unwrap_or_else(|| "claude-haiku-4-5".to_string()),
+            ),
+            ..Default::default()
+        },
+        output,
+    };
+    if args.mbox {
+        for path in free.iter() {
+            for (index, raw) in read_mbox(path)?.iter().enumerate() {
+                watcher
+                    .process(&format!("{path}#{}", index + 1), raw)
+                    .await?;
+            }
+        }
+        return Ok(());
+    }
+    let poll = Duration::from_secs(args.poll_secs.unwrap_or(5));
+    let mut seen = BTreeSet::new();
+    loop {
+        for maildir in free.iter().map(Path::new) {
+            for path in new_messages(maildir, &seen)? {
+                let raw = String::from_utf8_lossy(&std::fs::read(&path)?).into_owned();
+                watcher.process(&path.display().to_string(), &raw).await?;
+                if args.mark_seen {
+                    mark_seen(maildir, &path)?;
+                }
+                seen.insert(path);
+            }
+        }
+        if !args.follow {
+            return Ok(());
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
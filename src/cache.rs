@@ -0,0 +1,230 @@
+//! Caching of reports for repeated inputs.
+//!
+//! Forwarded and duplicated emails mean the same text is often run through the same policies
+//! more than once.  A [`crate::Manager`] configured with an [`ApplyCache`] looks up each input by
+//! its [`CacheKey`] before calling the LLM and stores every successful report afterwards.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{Policy, Report};
+
+/// The content hash of a policy set and an input text.
+///
+/// Keys are computed with 128-bit FNV-1a over the serialized policies and the raw text, so they
+/// are stable across processes and suitable for persistent caches.  A policy's cached
+/// `explanation` does not contribute to the key.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CacheKey {
+    /// Hash of the policy set, in order
+    pub policies: u128,
+    /// Hash of the input text
+    pub text: u128,
+}
+
+impl CacheKey {
+    /// Compute the key for applying `policies` to `text`.
+    pub fn new(policies: &[Policy], text: &str) -> Self {
+        let mut hasher = Fnv128::default();
+        for policy in policies {
+            let mut content = serde_json::json!({
+                "type": policy.r#type,
+                "prompt": policy.prompt,
+                "action": policy.action,
+                "precondition": policy.precondition,
+            });
+            // Only when set, so that keys for policies without a priority are unchanged.
+            if let Some(priority) = policy.priority {
+                content["priority"] = priority.into();
+            }
+            hasher.write(content.to_string().as_bytes());
+            hasher.write(&[0xff]);
+        }
+        let policies = hasher.finish();
+        let mut hasher = Fnv128::default();
+        hasher.write(text.as_bytes());
+        let text = hasher.finish();
+        Self { policies, text }
+    }
+
+    /// Mix configuration that changes the resulting report, such as conflict overrides, into the
+    /// policy hash.
+    pub fn with_settings(mut self, settings: &str) -> Self {
+        let mut hasher = Fnv128(self.policies);
+        hasher.write(&[0xfe]);
+        hasher.write(settings.as_bytes());
+        self.policies = hasher.finish();
+        self
+    }
+}
+
+impl std::fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:032x}:{:032x}", self.policies, self.text)
+    }
+}
+
+/// A store of reports keyed by [`CacheKey`].
+///
+/// Implementations must be safe to share between the clones of a [`crate::Manager`].
+pub trait ApplyCache: std::fmt::Debug + Send + Sync {
+    /// Return the cached report for `key`, if any.
+    fn get(&self, key: &CacheKey) -> Option<Report>;
+    /// Store `report` under `key`, replacing any previous report.
+    fn put(&self, key: CacheKey, report: Report);
+}
+
+/// An in-memory [`ApplyCache`].
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use policyai::{Manager, MemoryCache};
+///
+/// let cache = Arc::new(MemoryCache::default());
+/// let manager = Manager::default().with_cache(cache.clone());
+/// assert!(cache.is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    reports: Mutex<HashMap<CacheKey, Report>>,
+}
+
+impl MemoryCache {
+    /// The number of cached reports.
+    pub fn len(&self) -> usize {
+        self.reports.lock().unwrap().len()
+    }
+
+    /// True if nothing has been cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cached report.
+    pub fn clear(&self) {
+        self.reports.lock().unwrap().clear();
+    }
+}
+
+impl ApplyCache for MemoryCache {
+    fn get(&self, key: &CacheKey) -> Option<Report> {
+        self.reports.lock().unwrap().get(key).cloned()
+    }
+
+    fn put(&self, key: CacheKey, report: Report) {
+        self.reports.lock().unwrap().insert(key, report);
+    }
+}
+
+//////////////////////////////////////////// Fnv128 ////////////////////////////////////////////
+
+struct Fnv128(u128);
+
+impl Fnv128 {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u128;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u128 {
+        self.0
+    }
+}
+
+impl Default for Fnv128 {
+    fn default() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Field, OnConflict, PolicyType};
+
+    fn policy(prompt: &str) -> Policy {
+        Policy {
+            r#type: PolicyType {
+                name: "T".to_string(),
+                fields: vec![Field::Bool {
+                    name: "urgent".to_string(),
+                    default: Some(false),
+                    on_conflict: OnConflict::Default,
+                }],
+                groups: vec![],
+            },
+            prompt: prompt.to_string(),
+            action: serde_json::json!({"urgent": true}),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn fnv128_known_values() {
+        let mut hasher = Fnv128::default();
+        hasher.write(b"");
+        assert_eq!(hasher.finish(), 0x6c62272e07bb014262b821756295c58d);
+        let mut hasher = Fnv128::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xd228cb696f1a8caf78912b704e4a8964);
+    }
+
+    #[test]
+    fn cache_key_depends_on_policies_and_text() {
+        let policies = vec![policy("from the CEO")];
+        let key = CacheKey::new(&policies, "hello");
+        assert_eq!(key, CacheKey::new(&policies, "hello"));
+        assert_ne!(key.text, CacheKey::new(&policies, "hello!").text);
+        assert_eq!(key.policies, CacheKey::new(&policies, "hello!").policies);
+        let other = vec![policy("from the CFO")];
+        assert_ne!(key.policies, CacheKey::new(&other, "hello").policies);
+        let reordered = vec![policy("from the CFO"), policy("from the CEO")];
+        let ordered = vec![policy("from the CEO"), policy("from the CFO")];
+        assert_ne!(
+            CacheKey::new(&reordered, "hello"),
+            CacheKey::new(&ordered, "hello")
+        );
+    }
+
+    #[test]
+    fn cache_key_ignores_explanation() {
+        let mut explained = policy("from the CEO");
+        explained.explanation = Some("Marks mail from the CEO urgent.".to_string());
+        assert_eq!(
+            CacheKey::new(&[explained], "hello"),
+            CacheKey::new(&[policy("from the CEO")], "hello")
+        );
+    }
+
+    #[test]
+    fn cache_key_settings_change_policy_hash() {
+        let key = CacheKey::new(&[policy("from the CEO")], "hello");
+        let salted = key.with_settings("{}");
+        assert_ne!(key.policies, salted.policies);
+        assert_eq!(key.text, salted.text);
+        assert_eq!(salted, key.with_settings("{}"));
+    }
+
+    #[test]
+    fn memory_cache_round_trip() {
+        let cache = MemoryCache::default();
+        let key = CacheKey::new(&[policy("from the CEO")], "hello");
+        assert!(cache.get(&key).is_none());
+        cache.put(key, Report::default());
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&key).is_some());
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
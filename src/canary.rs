@@ -0,0 +1,475 @@
+//! Shadow comparisons of a candidate configuration against live traffic.
+//!
+//! A [`CanaryRunner`] applies a primary [`Manager`] to every text it is given and, for a sampled
+//! fraction of them, also applies a shadow manager (another model, other prompts, a new policy
+//! set) to the same text.  Callers only ever see the primary's report; the shadow's report and
+//! the fields on which the two disagree come back alongside it as a [`CanaryComparison`], and
+//! [`CanaryStats`] keeps running disagreement rates across every clone of the runner.
+//!
+//! Sampling is decided by a hash of the text, so a text is either always or never shadowed at a
+//! given rate, and repeated inputs do not skew the rates.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use policyai::canary::CanaryRunner;
+//! use policyai::Manager;
+//! # use claudius::{Anthropic, KnownModel, MessageCreateParams, Model};
+//! # let client = Anthropic::new(None)?;
+//! # let manager = Manager::default();
+//! # let template = MessageCreateParams::default();
+//!
+//! let shadow = manager.clone().with_model(Model::Known(KnownModel::ClaudeSonnet40));
+//! let canary = CanaryRunner::new(client, manager, template.clone(), shadow, template)
+//!     .with_sample_rate(0.05);
+//! let outcome = canary.apply("text", None).await;
+//! if let Some(comparison) = &outcome.comparison {
+//!     println!("{}", serde_json::to_string(comparison)?);
+//! }
+//! let report = outcome.result?;
+//! println!("{} ({:.1}% disagreement)", report.value(), canary.stats().disagreement_rate() * 100.0);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use claudius::MessageCreateParams;
+
+use crate::compare::{values_match, CompareOptions};
+use crate::{ApplyError, CacheKey, LlmProvider, Manager, Report, Usage};
+
+///////////////////////////////////////// FieldDisagreement /////////////////////////////////////////
+
+/// A field the primary and shadow reports set differently.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FieldDisagreement {
+    /// The field.
+    pub field: String,
+    /// The primary's value, or `None` if the primary's report lacks the field.
+    pub primary: Option<serde_json::Value>,
+    /// The shadow's value, or `None` if the shadow's report lacks the field.
+    pub shadow: Option<serde_json::Value>,
+}
+
+///////////////////////////////////////// CanaryComparison /////////////////////////////////////////
+
+/// Both reports for a sampled text, and where they differ.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct CanaryComparison {
+    /// The primary's report, as returned to the caller.
+    pub primary: Report,
+    /// The shadow's report, or `None` if the shadow failed.
+    pub shadow: Option<Report>,
+    /// The shadow's error, if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_error: Option<String>,
+    /// Usage of the shadow's application alone.
+    pub shadow_usage: Usage,
+    /// Fields on which the reports' values differ, in field order.  Empty if the shadow failed.
+    pub disagreements: Vec<FieldDisagreement>,
+}
+
+impl CanaryComparison {
+    /// True if the shadow produced a report with the same value as the primary.
+    pub fn agrees(&self) -> bool {
+        self.shadow.is_some() && self.disagreements.is_empty()
+    }
+}
+
+/// The fields of `primary` and `shadow` whose values do not match under `options`.
+fn disagreements(
+    primary: &Report,
+    shadow: &Report,
+    options: &CompareOptions,
+) -> Vec<FieldDisagreement> {
+    let primary = primary.value();
+    let shadow = shadow.value();
+    let empty = serde_json::Map::new();
+    let primary = primary.as_object().unwrap_or(&empty);
+    let shadow = shadow.as_object().unwrap_or(&empty);
+    let mut fields = primary.keys().collect::<Vec<_>>();
+    fields.extend(shadow.keys().filter(|field| !primary.contains_key(*field)));
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let p = primary.get(field);
+            let s = shadow.get(field);
+            let agree = match (p, s) {
+                (Some(p), Some(s)) => values_match(s, p, options),
+                _ => false,
+            };
+            (!agree).then(|| FieldDisagreement {
+                field: field.clone(),
+                primary: p.cloned(),
+                shadow: s.cloned(),
+            })
+        })
+        .collect()
+}
+
+/////////////////////////////////////////// CanaryStats ///////////////////////////////////////////
+
+/// Running totals of a [`CanaryRunner`]'s comparisons.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CanaryStats {
+    /// Texts given to the runner.
+    pub applies: usize,
+    /// Texts sampled for a shadow application.
+    pub sampled: usize,
+    /// Sampled texts for which both the primary and the shadow produced a report.
+    pub compared: usize,
+    /// Sampled texts for which the primary produced a report and the shadow failed.
+    pub shadow_errors: usize,
+    /// Compared texts whose reports differ in at least one field.
+    pub disagreements: usize,
+    /// Compared texts whose reports differ, by field.
+    pub field_disagreements: BTreeMap<String, usize>,
+}
+
+impl CanaryStats {
+    /// The fraction of compared texts whose reports differ, or 0 if none were compared.
+    pub fn disagreement_rate(&self) -> f64 {
+        if self.compared == 0 {
+            0.0
+        } else {
+            self.disagreements as f64 / self.compared as f64
+        }
+    }
+
+    /// The fraction of compared texts whose reports differ on `field`, or 0 if none were
+    /// compared.
+    pub fn field_disagreement_rate(&self, field: &str) -> f64 {
+        if self.compared == 0 {
+            0.0
+        } else {
+            self.field_disagreements.get(field).copied().unwrap_or(0) as f64 / self.compared as f64
+        }
+    }
+
+    /// The fraction of sampled texts the shadow failed on, or 0 if none were sampled.
+    pub fn shadow_error_rate(&self) -> f64 {
+        if self.compared + self.shadow_errors == 0 {
+            0.0
+        } else {
+            self.shadow_errors as f64 / (self.compared + self.shadow_errors) as f64
+        }
+    }
+
+    fn record(&mut self, comparison: &CanaryComparison) {
+        if comparison.shadow.is_none() {
+            self.shadow_errors += 1;
+            return;
+        }
+        self.compared += 1;
+        if !comparison.disagreements.is_empty() {
+            self.disagreements += 1;
+        }
+        for disagreement in comparison.disagreements.iter() {
+            *self
+                .field_disagreements
+                .entry(disagreement.field.clone())
+                .or_default() += 1;
+        }
+    }
+}
+
+////////////////////////////////////////// CanaryOutcome //////////////////////////////////////////
+
+/// The outcome of applying a [`CanaryRunner`] to one text.
+#[derive(Debug)]
+pub struct CanaryOutcome {
+    /// The primary's report or error, exactly as [`Manager::apply`] returned it.
+    pub result: Result<Report, ApplyError>,
+    /// The comparison, if the text was sampled and the primary produced a report.
+    pub comparison: Option<CanaryComparison>,
+}
+
+/////////////////////////////////////////// CanaryRunner ///////////////////////////////////////////
+
+/// Applies a primary [`Manager`] and, for a sample of texts, a shadow one alongside it.
+///
+/// Clones share their [`CanaryStats`].
+#[derive(Clone, Debug)]
+pub struct CanaryRunner {
+    client: Arc<dyn LlmProvider>,
+    shadow_client: Option<Arc<dyn LlmProvider>>,
+    primary: Manager,
+    template: MessageCreateParams,
+    shadow: Manager,
+    shadow_template: MessageCreateParams,
+    sample_rate: f64,
+    compare: CompareOptions,
+    stats: Arc<Mutex<CanaryStats>>,
+}
+
+impl CanaryRunner {
+    /// Sample rate used unless [`CanaryRunner::with_sample_rate`] says otherwise.
+    pub const DEFAULT_SAMPLE_RATE: f64 = 0.01;
+
+    /// Create a runner that answers with `primary` and shadows it with `shadow`, both through
+    /// `client`.
+    pub fn new(
+        client: impl LlmProvider + 'static,
+        primary: Manager,
+        template: MessageCreateParams,
+        shadow: Manager,
+        shadow_template: MessageCreateParams,
+    ) -> Self {
+        Self {
+            client: Arc::new(client),
+            shadow_client: None,
+            primary,
+            template,
+            shadow,
+            shadow_template,
+            sample_rate: Self::DEFAULT_SAMPLE_RATE,
+            compare: CompareOptions::default(),
+            stats: Arc::new(Mutex::new(CanaryStats::default())),
+        }
+    }
+
+    /// Send the shadow's requests through `client` instead, e.g. a gateway to another provider.
+    pub fn with_shadow_client(mut self, client: impl LlmProvider + 'static) -> Self {
+        self.shadow_client = Some(Arc::new(client));
+        self
+    }
+
+    /// Shadow this fraction of texts.  Clamped to `[0, 1]`.
+    pub fn with_sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = if sample_rate.is_nan() {
+            0.0
+        } else {
+            sample_rate.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Compare the reports' values with `compare` instead of [`CompareOptions::default`].
+    pub fn with_compare_options(mut self, compare: CompareOptions) -> Self {
+        self.compare = compare;
+        self
+    }
+
+    /// True if `text` is shadowed at this runner's sample rate.
+    pub fn is_sampled(&self, text: &str) -> bool {
+        // FNV's high bits follow the last bytes of the text too closely to sample texts that
+        // differ only at the end, so fold the hash and mix it before taking a fraction.
+        let hash = CacheKey::new(&[], text).text;
+        let mut mixed = (hash as u64) ^ ((hash >> 64) as u64);
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d049bb133111eb);
+        mixed ^= mixed >> 31;
+        let position = (mixed >> 11) as f64 / (1u64 << 53) as f64;
+        position < self.sample_rate
+    }
+
+    /// A snapshot of the running totals.
+    pub fn stats(&self) -> CanaryStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Apply the primary to `text`, and the shadow too if `text` is sampled.
+    ///
+    /// The two run concurrently.  `usage` accumulates the primary's usage only; the shadow's is
+    /// kept on the comparison.  A failing shadow never fails the primary.
+    pub async fn apply(&self, text: &str, usage: Option<&mut Usage>) -> CanaryOutcome {
+        let mut primary = self.primary.clone();
+        let primary = primary.apply(self.client.as_ref(), self.template.clone(), text, usage);
+        if !self.is_sampled(text) {
+            let result = primary.await;
+            self.stats.lock().unwrap().applies += 1;
+            return CanaryOutcome {
+                result,
+                comparison: None,
+            };
+        }
+        let client = self.shadow_client.as_ref().unwrap_or(&self.client);
+        let mut shadow_usage = Usage::new();
+        let mut shadow = self.shadow.clone();
+        let shadow = shadow.apply(
+            client.as_ref(),
+            self.shadow_template.clone(),
+            text,
+            Some(&mut shadow_usage),
+        );
+        let (result, shadow) = futures::join!(primary, shadow);
+        let comparison = result.as_ref().ok().map(|report| {
+            let (shadow, shadow_error, disagreements) = match shadow {
+                Ok(shadow) => {
+                    let disagreements = disagreements(report, &shadow, &self.compare);
+                    (Some(shadow), None, disagreements)
+                }
+                Err(err) => (None, Some(err.to_string()), vec![]),
+            };
+            CanaryComparison {
+                primary: report.clone(),
+                shadow,
+                shadow_error,
+                shadow_usage,
+                disagreements,
+            }
+        });
+        let mut stats = self.stats.lock().unwrap();
+        stats.applies += 1;
+        stats.sampled += 1;
+        if let Some(comparison) = &comparison {
+            stats.record(comparison);
+        }
+        CanaryOutcome { result, comparison }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claudius::{KnownModel, Message, Model};
+
+    use super::*;
+    use crate::{Field, OnConflict, Policy, PolicyType};
+
+    /// Answers every request with the same tool input.
+    #[derive(Debug)]
+    struct Fixed(serde_json::Value);
+
+    impl LlmProvider for Fixed {
+        fn send(
+            &self,
+            req: MessageCreateParams,
+        ) -> futures::future::BoxFuture<'_, Result<Message, claudius::Error>> {
+            let input = self.0.clone();
+            Box::pin(async move {
+                Ok(crate::tool_use_reply(
+                    "msg_test",
+                    req.model,
+                    Manager::DEFAULT_TOOL_NAME,
+                    input,
+                    claudius::Usage::new(100, 10),
+                ))
+            })
+        }
+    }
+
+    fn manager() -> Manager {
+        let mut manager = Manager::default().with_unmasked_fields(["urgent"]);
+        manager.add(Policy {
+            r#type: PolicyType {
+                name: "T".to_string(),
+                fields: vec![
+                    Field::Bool {
+                        name: "urgent".to_string(),
+                        default: Some(false),
+                        on_conflict: OnConflict::Default,
+                    },
+                    Field::Number {
+                        name: "score".to_string(),
+                        default: Some(crate::t64(0.0)),
+                        on_conflict: OnConflict::Default,
+                    },
+                ],
+                groups: vec![],
+            },
+            prompt: "If the text is urgent".to_string(),
+            action: serde_json::json!({"urgent": true}),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        });
+        manager
+    }
+
+    fn template() -> MessageCreateParams {
+        MessageCreateParams {
+            max_tokens: 1024,
+            model: Model::Known(KnownModel::ClaudeSonnet40),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sampling_is_stable_and_roughly_the_rate() {
+        let canary = CanaryRunner::new(
+            Fixed(serde_json::json!({})),
+            manager(),
+            template(),
+            manager(),
+            template(),
+        );
+        let texts = (0..2000).map(|i| format!("text {i}")).collect::<Vec<_>>();
+        let sampled =
+            |canary: &CanaryRunner| texts.iter().filter(|text| canary.is_sampled(text)).count();
+        assert_eq!(sampled(&canary.clone().with_sample_rate(0.0)), 0);
+        assert_eq!(sampled(&canary.clone().with_sample_rate(f64::NAN)), 0);
+        assert_eq!(sampled(&canary.clone().with_sample_rate(2.0)), texts.len());
+        let quarter = canary.with_sample_rate(0.25);
+        let count = sampled(&quarter);
+        assert!((400..600).contains(&count), "{count}");
+        assert_eq!(sampled(&quarter), count);
+    }
+
+    #[tokio::test]
+    async fn canary_records_both_reports_and_their_disagreements() {
+        let canary = CanaryRunner::new(
+            Fixed(serde_json::json!({"__rule_numbers__": [1], "urgent@1": true})),
+            manager(),
+            template(),
+            manager(),
+            template(),
+        )
+        .with_shadow_client(Fixed(serde_json::json!({"__rule_numbers__": []})))
+        .with_sample_rate(1.0);
+        let mut usage = Usage::new();
+        let outcome = canary.apply("Reply now!", Some(&mut usage)).await;
+        let report = outcome.result.unwrap();
+        assert_eq!(report.value()["urgent"], true);
+        assert_eq!(usage.iterations, 1);
+        let comparison = outcome.comparison.unwrap();
+        assert!(!comparison.agrees());
+        assert_eq!(comparison.shadow.as_ref().unwrap().value()["urgent"], false);
+        assert_eq!(comparison.shadow_usage.iterations, 1);
+        assert_eq!(
+            comparison.disagreements,
+            vec![FieldDisagreement {
+                field: "urgent".to_string(),
+                primary: Some(true.into()),
+                shadow: Some(false.into()),
+            }]
+        );
+
+        // A shadow that fails is counted, and the primary's report is still returned.
+        let failing = CanaryRunner::new(
+            Fixed(serde_json::json!({"__rule_numbers__": []})),
+            manager(),
+            template(),
+            manager(),
+            template(),
+        )
+        .with_shadow_client(Fixed(serde_json::json!({"__rule_numbers__": [7]})))
+        .with_sample_rate(1.0);
+        let outcome = failing.clone().apply("Nothing to see", None).await;
+        assert!(outcome.result.is_ok());
+        let comparison = outcome.comparison.unwrap();
+        assert!(comparison.shadow.is_none());
+        assert!(comparison.shadow_error.is_some());
+
+        let agreeing = canary.clone().with_shadow_client(Fixed(serde_json::json!({
+            "__rule_numbers__": [1],
+            "urgent@1": true,
+        })));
+        assert!(agreeing
+            .apply("Reply now!", None)
+            .await
+            .comparison
+            .unwrap()
+            .agrees());
+        let stats = canary.stats();
+        assert_eq!((stats.applies, stats.sampled, stats.compared), (2, 2, 2));
+        assert_eq!(stats.disagreements, 1);
+        assert_eq!(stats.disagreement_rate(), 0.5);
+        assert_eq!(stats.field_disagreement_rate("urgent"), 0.5);
+        assert_eq!(stats.field_disagreement_rate("score"), 0.0);
+        assert_eq!(failing.stats().shadow_error_rate(), 1.0);
+    }
+}
@@ -0,0 +1,780 @@
+//! Semantic comparison of JSON values.
+//!
+//! Evaluation needs a notion of "the output matches the expected value" that is looser than
+//! `==`: the LLM may write `0` where `0.0` was expected, or a float that differs in the last
+//! digits.  [`values_match`] is the single implementation of that notion; [`CompareOptions`]
+//! selects how loose it is.
+//!
+//! Strings carry formatting noise of their own.  `"1,000"` and `"1000"` name the same number,
+//! and `"Jan 5"` and `"2024-01-05"` the same day; a regression that is only a change of format is
+//! not a regression.  [`CompareOptions::with_number_format`] and
+//! [`CompareOptions::with_date_order`] turn on normalizers that read such strings before
+//! comparing them.  Both are off by default, because whether `"1.000"` is one or a thousand
+//! depends on the locale the data was written in.
+
+/// Options that control how [`values_match`] compares two JSON values.
+///
+/// # Example
+///
+/// ```rust
+/// use policyai::compare::{values_match, CompareOptions};
+/// use serde_json::json;
+///
+/// let options = CompareOptions::default().with_ignore_array_order(true);
+/// assert!(values_match(&json!([1, 2]), &json!([2, 1]), &options));
+/// assert!(!values_match(&json!([1, 2]), &json!([2, 1]), &CompareOptions::default()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CompareOptions {
+    /// Largest allowed difference between numbers, as a fraction of the expected value.
+    /// An expected value of zero only matches zero.
+    pub relative_tolerance: f64,
+    /// Collapse runs of whitespace and trim strings before comparing them.
+    pub ignore_whitespace: bool,
+    /// Compare arrays as multisets rather than sequences.
+    pub ignore_array_order: bool,
+    /// Read strings that are numbers written with digit grouping, such as `"1,000"`, as numbers.
+    /// `None` compares them as strings.
+    pub number_format: Option<NumberFormat>,
+    /// Read strings that are dates, such as `"Jan 5"` or `"2024-01-05"`, as the day they name.
+    /// `None` compares them as strings.
+    pub date_order: Option<DateOrder>,
+}
+
+/// How numbers written as strings group their digits.
+///
+/// Spaces, apostrophes, and underscores group digits in either format.  Groups after the first
+/// must be three digits long, so `"1,2"` is not read as twelve.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NumberFormat {
+    /// `1,234.5`: commas group thousands and a period starts the fraction.
+    CommaThousands,
+    /// `1.234,5`: periods group thousands and a comma starts the fraction.
+    PeriodThousands,
+}
+
+/// How to read all-numeric dates such as `01/05/2024`.
+///
+/// Dates that spell out the month, such as `"5 Jan 2024"`, and ISO 8601 dates and times are
+/// read the same way under either order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DateOrder {
+    /// `01/05/2024` is January 5th.
+    MonthFirst,
+    /// `01/05/2024` is May 1st.
+    DayFirst,
+}
+
+impl CompareOptions {
+    /// The relative tolerance used by [`CompareOptions::default`].
+    pub const DEFAULT_RELATIVE_TOLERANCE: f64 = 0.00001;
+
+    /// Exact comparison except that numbers compare by value, so `0` matches `0.0`.
+    pub fn exact() -> Self {
+        Self {
+            relative_tolerance: 0.0,
+            ignore_whitespace: false,
+            ignore_array_order: false,
+            number_format: None,
+            date_order: None,
+        }
+    }
+
+    /// Set the relative tolerance for numbers.
+    pub fn with_relative_tolerance(mut self, relative_tolerance: f64) -> Self {
+        self.relative_tolerance = relative_tolerance;
+        self
+    }
+
+    /// Set whether whitespace differences in strings are ignored.
+    pub fn with_ignore_whitespace(mut self, ignore_whitespace: bool) -> Self {
+        self.ignore_whitespace = ignore_whitespace;
+        self
+    }
+
+    /// Set whether array order is ignored.
+    pub fn with_ignore_array_order(mut self, ignore_array_order: bool) -> Self {
+        self.ignore_array_order = ignore_array_order;
+        self
+    }
+
+    /// Read strings written in `number_format` as numbers.
+    ///
+    /// A string then matches a number, or another string, that names the same value within the
+    /// relative tolerance.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use policyai::compare::{values_match, CompareOptions, NumberFormat};
+    /// use serde_json::json;
+    ///
+    /// let options = CompareOptions::default().with_number_format(NumberFormat::CommaThousands);
+    /// assert!(values_match(&json!("1,000"), &json!("1000"), &options));
+    /// assert!(values_match(&json!("1,234.5"), &json!(1234.5), &options));
+    /// ```
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = Some(number_format);
+        self
+    }
+
+    /// Read strings that are dates as the day they name, reading all-numeric dates in
+    /// `date_order`.
+    ///
+    /// A date without a year, such as `"Jan 5"`, matches that day in any year.  Times with a UTC
+    /// offset match when they name the same instant; times without one only match times without
+    /// one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use policyai::compare::{values_match, CompareOptions, DateOrder};
+    /// use serde_json::json;
+    ///
+    /// let options = CompareOptions::default().with_date_order(DateOrder::MonthFirst);
+    /// assert!(values_match(&json!("Jan 5"), &json!("2024-01-05"), &options));
+    /// assert!(values_match(&json!("01/05/2024"), &json!("January 5th, 2024"), &options));
+    /// assert!(values_match(
+    ///     &json!("2024-01-05T23:30:00-05:00"),
+    ///     &json!("2024-01-06T04:30:00Z"),
+    ///     &options,
+    /// ));
+    /// ```
+    pub fn with_date_order(mut self, date_order: DateOrder) -> Self {
+        self.date_order = Some(date_order);
+        self
+    }
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self::exact().with_relative_tolerance(Self::DEFAULT_RELATIVE_TOLERANCE)
+    }
+}
+
+/// Compare `actual` against `expected` under `options`.
+///
+/// Numbers match when they are within `relative_tolerance` of the expected value.  Strings,
+/// arrays, and objects are compared recursively; objects must have the same keys.  Strings that
+/// differ are compared again as numbers or dates when `options` turns those normalizers on.
+/// Every other combination, including values of different JSON types, must be equal, except
+/// that a string matches a number it names under [`CompareOptions::number_format`].
+///
+/// # Arguments
+///
+/// * `actual` - The value produced by the system under test
+/// * `expected` - The value it should have produced
+/// * `options` - How loosely to compare
+///
+/// # Example
+///
+/// ```rust
+/// use policyai::compare::{values_match, CompareOptions};
+/// use serde_json::json;
+///
+/// let options = CompareOptions::default();
+/// assert!(values_match(&json!(0), &json!(0.0), &options));
+/// assert!(values_match(&json!(1000.009), &json!(1000.0), &options));
+/// assert!(!values_match(&json!("42"), &json!(42), &options));
+/// ```
+pub fn values_match(
+    actual: &serde_json::Value,
+    expected: &serde_json::Value,
+    options: &CompareOptions,
+) -> bool {
+    use serde_json::Value;
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => numbers_match(a, b, options),
+            _ => a == b,
+        },
+        (Value::String(a), Value::String(b)) => {
+            let equal = if options.ignore_whitespace {
+                normalize_whitespace(a) == normalize_whitespace(b)
+            } else {
+                a == b
+            };
+            equal || formatted_strings_match(a, b, options)
+        }
+        (Value::String(a), Value::Number(b)) => options
+            .number_format
+            .and_then(|format| Some((parse_number(a, format)?, b.as_f64()?)))
+            .is_some_and(|(a, b)| numbers_match(a, b, options)),
+        (Value::Number(a), Value::String(b)) => options
+            .number_format
+            .and_then(|format| Some((a.as_f64()?, parse_number(b, format)?)))
+            .is_some_and(|(a, b)| numbers_match(a, b, options)),
+        (Value::Array(a), Value::Array(b)) => {
+            if a.len() != b.len() {
+                false
+            } else if options.ignore_array_order {
+                arrays_match_unordered(a, b, options)
+            } else {
+                a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| values_match(a, b, options))
+            }
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, a)| {
+                    b.get(key)
+                        .map(|b| values_match(a, b, options))
+                        .unwrap_or(false)
+                })
+        }
+        _ => actual == expected,
+    }
+}
+
+fn numbers_match(actual: f64, expected: f64, options: &CompareOptions) -> bool {
+    (actual - expected).abs() <= expected.abs() * options.relative_tolerance
+}
+
+fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// True if `a` and `b` name the same number or the same date under the enabled normalizers.
+fn formatted_strings_match(a: &str, b: &str, options: &CompareOptions) -> bool {
+    if let Some(format) = options.number_format {
+        if let (Some(a), Some(b)) = (parse_number(a, format), parse_number(b, format)) {
+            return numbers_match(a, b, options);
+        }
+    }
+    if let Some(order) = options.date_order {
+        if let (Some(a), Some(b)) = (parse_date(a, order), parse_date(b, order)) {
+            return a.matches(&b);
+        }
+    }
+    false
+}
+
+/// The value of `s` if it is a number written in `format`.
+fn parse_number(s: &str, format: NumberFormat) -> Option<f64> {
+    let (group, decimal) = match format {
+        NumberFormat::CommaThousands => (',', '.'),
+        NumberFormat::PeriodThousands => ('.', ','),
+    };
+    let s = s.trim();
+    let (sign, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (whole, fraction) = match unsigned.split_once(decimal) {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let groups: Vec<&str> = whole
+        .split([group, ' ', '\u{a0}', '\u{202f}', '\'', '_'])
+        .collect();
+    if !groups.iter().all(|g| is_digits(g)) || !fraction.is_none_or(is_digits) {
+        return None;
+    }
+    if groups.len() > 1 && (groups[0].len() > 3 || groups[1..].iter().any(|g| g.len() != 3)) {
+        return None;
+    }
+    let mut normalized = format!("{sign}{}", groups.concat());
+    if let Some(fraction) = fraction {
+        normalized.push('.');
+        normalized.push_str(fraction);
+    }
+    normalized.parse().ok()
+}
+
+/// What a string that is a date or time names.
+#[derive(Debug, PartialEq)]
+enum Moment {
+    /// A calendar day.  The year is `None` when the text leaves it out, as in `"Jan 5"`.
+    Day {
+        year: Option<i64>,
+        month: u32,
+        day: u32,
+    },
+    /// A time of day, in seconds since the Unix epoch.  When the text has no UTC offset,
+    /// `zoned` is false and the seconds are in whatever local time the text was written in.
+    Instant {
+        seconds: i64,
+        nanos: u32,
+        zoned: bool,
+    },
+}
+
+impl Moment {
+    fn matches(&self, other: &Moment) -> bool {
+        match (self, other) {
+            (
+                Moment::Day { year, month, day },
+                Moment::Day {
+                    year: other_year,
+                    month: other_month,
+                    day: other_day,
+                },
+            ) => {
+                month == other_month
+                    && day == other_day
+                    && (year.is_none() || other_year.is_none() || year == other_year)
+            }
+            (Moment::Instant { .. }, Moment::Instant { .. }) => self == other,
+            _ => false,
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// The date or time `s` names, if it is written in one of the common formats.
+fn parse_date(s: &str, order: DateOrder) -> Option<Moment> {
+    let s = s.trim();
+    parse_iso_instant(s)
+        .or_else(|| parse_numeric_date(s, order))
+        .or_else(|| parse_written_date(s))
+}
+
+/// The instant an RFC 3339 timestamp names, as seconds and nanoseconds since the Unix epoch.
+///
+/// Unlike comparison, which tolerates a missing UTC offset, this requires one:  a
+/// [`crate::Field::DateTime`] value without an offset does not name an instant.
+pub(crate) fn rfc3339_instant(s: &str) -> Option<(i64, u32)> {
+    match parse_iso_instant(s)? {
+        Moment::Instant {
+            seconds,
+            nanos,
+            zoned: true,
+        } => Some((seconds, nanos)),
+        _ => None,
+    }
+}
+
+/// `2024-01-05T10:00:00Z` and friends: an ISO 8601 date, a time, and an optional UTC offset.
+fn parse_iso_instant(s: &str) -> Option<Moment> {
+    if !s.is_char_boundary(10) || !matches!(s.as_bytes().get(10), Some(b'T' | b't' | b' ')) {
+        return None;
+    }
+    let Some(Moment::Day {
+        year: Some(year),
+        month,
+        day,
+    }) = parse_numeric_date(&s[..10], DateOrder::MonthFirst)
+    else {
+        return None;
+    };
+    let time = &s[11..];
+    let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, Some(0))
+    } else if let Some(split) = time.rfind(['+', '-']) {
+        let (time, offset) = time.split_at(split);
+        (time, Some(parse_offset(offset)?))
+    } else {
+        (time, None)
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+    let parts: Vec<&str> = time.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || parts.iter().any(|p| p.len() != 2) {
+        return None;
+    }
+    let hour: i64 = parts[0].parse().ok()?;
+    let minute: i64 = parts[1].parse().ok()?;
+    let second: i64 = parts.get(2).map_or(Some(0), |p| p.parse().ok())?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let nanos = match fraction {
+        Some(fraction)
+            if (1..=9).contains(&fraction.len())
+                && fraction.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            format!("{fraction:0<9}").parse().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+        - offset.unwrap_or(0);
+    Some(Moment::Instant {
+        seconds,
+        nanos,
+        zoned: offset.is_some(),
+    })
+}
+
+/// `+05:30` or `-0800`, in seconds east of UTC.
+fn parse_offset(s: &str) -> Option<i64> {
+    let (sign, rest) = match s.split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let digits = rest.replacen(':', "", 1);
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3_600 + minutes * 60))
+}
+
+/// `2024-01-05`, `2024/01/05`, or `01/05/2024` read in `order`.
+fn parse_numeric_date(s: &str, order: DateOrder) -> Option<Moment> {
+    let separator = s.chars().find(|c| matches!(c, '-' | '/' | '.'))?;
+    let parts: Vec<&str> = s.split(separator).collect();
+    if parts.len() != 3
+        || parts
+            .iter()
+            .any(|p| p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+    let (year, month, day) = if parts[0].len() == 4 {
+        (parts[0], parts[1], parts[2])
+    } else if parts[2].len() == 4 {
+        match order {
+            DateOrder::MonthFirst => (parts[2], parts[0], parts[1]),
+            DateOrder::DayFirst => (parts[2], parts[1], parts[0]),
+        }
+    } else {
+        return None;
+    };
+    if month.len() > 2 || day.len() > 2 {
+        return None;
+    }
+    day_of(
+        Some(year.parse().ok()?),
+        month.parse().ok()?,
+        day.parse().ok()?,
+    )
+}
+
+/// `Jan 5`, `January 5th, 2024`, `Fri, 5 Jan 2024`, and the like.
+fn parse_written_date(s: &str) -> Option<Moment> {
+    let lower = s.to_lowercase().replace(',', " ");
+    let mut words: Vec<&str> = lower
+        .split_whitespace()
+        .map(|w| w.trim_end_matches('.'))
+        .collect();
+    if words.first().and_then(|w| lookup(&WEEKDAYS, w)).is_some() {
+        words.remove(0);
+    }
+    let (month, day, year) = match words.as_slice() {
+        [first, second, rest @ ..] if rest.len() <= 1 => {
+            if let Some(month) = lookup(&MONTHS, first) {
+                (month, parse_day(second)?, rest.first())
+            } else {
+                (lookup(&MONTHS, second)?, parse_day(first)?, rest.first())
+            }
+        }
+        _ => return None,
+    };
+    let year = match year {
+        Some(year) if year.len() == 4 => Some(year.parse().ok()?),
+        Some(_) => return None,
+        None => None,
+    };
+    day_of(year, month, day)
+}
+
+/// The 1-based position in `names` of the name `word` spells or abbreviates to three or more
+/// letters, with `sept` for September.
+fn lookup(names: &[&str], word: &str) -> Option<u32> {
+    if word == "sept" {
+        return (names == MONTHS).then_some(9);
+    }
+    if word.len() < 3 {
+        return None;
+    }
+    names
+        .iter()
+        .position(|name| name.starts_with(word))
+        .map(|idx| idx as u32 + 1)
+}
+
+/// `5`, `05`, or `5th`.
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .unwrap_or(word);
+    if digits.is_empty() || digits.len() > 2 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// The day, if it exists.  February 29th exists when the year is unknown.
+fn day_of(year: Option<i64>, month: u32, day: u32) -> Option<Moment> {
+    let leap = year.is_none_or(|y| y % 4 == 0 && (y % 100 != 0 || y % 400 == 0));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    (1..=days)
+        .contains(&day)
+        .then_some(Moment::Day { year, month, day })
+}
+
+/// Days since 1970-01-01 of a day in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Howard Hinnant's days_from_civil.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (i64::from(month) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn arrays_match_unordered(
+    a: &[serde_json::Value],
+    b: &[serde_json::Value],
+    options: &CompareOptions,
+) -> bool {
+    let mut used = vec![false; b.len()];
+    for a in a {
+        let found = b
+            .iter()
+            .enumerate()
+            .position(|(idx, b)| !used[idx] && values_match(a, b, options));
+        match found {
+            Some(idx) => used[idx] = true,
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn numbers_compare_by_value() {
+        let options = CompareOptions::exact();
+        assert!(values_match(&json!(0), &json!(0.0), &options));
+        assert!(values_match(&json!(0.0), &json!(0), &options));
+        assert!(values_match(&json!(42), &json!(42), &options));
+        assert!(!values_match(&json!(42), &json!(43), &options));
+        assert!(values_match(&json!(u64::MAX), &json!(u64::MAX), &options));
+    }
+
+    #[test]
+    fn numbers_respect_relative_tolerance() {
+        let options = CompareOptions::default();
+        assert!(values_match(&json!(1000.009), &json!(1000.0), &options));
+        assert!(values_match(&json!(999.991), &json!(1000.0), &options));
+        assert!(!values_match(&json!(1000.011), &json!(1000.0), &options));
+        assert!(!values_match(&json!(999.989), &json!(1000.0), &options));
+
+        let loose = CompareOptions::exact().with_relative_tolerance(0.001);
+        assert!(values_match(&json!(100.05), &json!(100.0), &loose));
+        assert!(!values_match(&json!(100.2), &json!(100.0), &loose));
+    }
+
+    #[test]
+    fn zero_only_matches_zero() {
+        let options = CompareOptions::exact().with_relative_tolerance(0.5);
+        assert!(values_match(&json!(0), &json!(0), &options));
+        assert!(!values_match(&json!(0.0001), &json!(0), &options));
+        assert!(!values_match(&json!(0), &json!(0.0001), &options));
+    }
+
+    #[test]
+    fn different_types_never_match() {
+        let options = CompareOptions::default();
+        assert!(!values_match(&json!("42"), &json!(42), &options));
+        assert!(!values_match(&json!(true), &json!(1), &options));
+        assert!(!values_match(&json!(null), &json!(0), &options));
+        assert!(!values_match(&json!([1]), &json!(1), &options));
+    }
+
+    #[test]
+    fn whitespace() {
+        let strict = CompareOptions::default();
+        let relaxed = strict.with_ignore_whitespace(true);
+        let a = json!("hello   world\n");
+        let b = json!(" hello world");
+        assert!(!values_match(&a, &b, &strict));
+        assert!(values_match(&a, &b, &relaxed));
+        assert!(!values_match(&json!("helloworld"), &b, &relaxed));
+    }
+
+    #[test]
+    fn arrays() {
+        let ordered = CompareOptions::default();
+        let unordered = ordered.with_ignore_array_order(true);
+        assert!(values_match(&json!([1, 2, 3]), &json!([1, 2, 3]), &ordered));
+        assert!(!values_match(
+            &json!([1, 2, 3]),
+            &json!([3, 2, 1]),
+            &ordered
+        ));
+        assert!(values_match(
+            &json!([1, 2, 3]),
+            &json!([3, 2, 1]),
+            &unordered
+        ));
+        assert!(!values_match(
+            &json!([1, 1, 2]),
+            &json!([1, 2, 2]),
+            &unordered
+        ));
+        assert!(!values_match(&json!([1, 2]), &json!([1, 2, 3]), &unordered));
+    }
+
+    #[test]
+    fn objects_recurse() {
+        let options = CompareOptions::default().with_ignore_whitespace(true);
+        let actual = json!({"a": 0, "b": {"c": "x  y"}});
+        assert!(values_match(
+            &actual,
+            &json!({"a": 0.0, "b": {"c": "x y"}}),
+            &options
+        ));
+        assert!(!values_match(&actual, &json!({"a": 0}), &options));
+        assert!(!values_match(
+            &actual,
+            &json!({"a": 0, "d": {"c": "x y"}}),
+            &options
+        ));
+    }
+
+    #[test]
+    fn numbers_in_strings() {
+        let plain = CompareOptions::default();
+        let comma = plain.with_number_format(NumberFormat::CommaThousands);
+        let period = plain.with_number_format(NumberFormat::PeriodThousands);
+        assert!(!values_match(&json!("1,000"), &json!("1000"), &plain));
+        assert!(values_match(&json!("1,000"), &json!("1000"), &comma));
+        assert!(values_match(&json!("1,000"), &json!(1000), &comma));
+        assert!(values_match(&json!(1000), &json!("1,000"), &comma));
+        assert!(values_match(
+            &json!("-1,234,567.50"),
+            &json!(-1234567.5),
+            &comma
+        ));
+        assert!(values_match(&json!("1 000"), &json!("1000"), &comma));
+        assert!(values_match(&json!("1.234,5"), &json!(1234.5), &period));
+        assert!(values_match(&json!("1.000"), &json!(1), &comma));
+        assert!(values_match(&json!("1.000"), &json!(1000), &period));
+        assert!(!values_match(&json!("1,2"), &json!(12), &comma));
+        assert!(!values_match(&json!("1,0000"), &json!(10000), &comma));
+        assert!(!values_match(&json!("1,000"), &json!("1001"), &comma));
+        assert!(!values_match(&json!("inf"), &json!("inf "), &comma));
+        assert!(!values_match(&json!("12 apples"), &json!(12), &comma));
+    }
+
+    #[test]
+    fn dates_in_strings() {
+        let plain = CompareOptions::default();
+        let us = plain.with_date_order(DateOrder::MonthFirst);
+        let eu = plain.with_date_order(DateOrder::DayFirst);
+        assert!(!values_match(&json!("Jan 5"), &json!("2024-01-05"), &plain));
+        for written in [
+            "Jan 5",
+            "January 5th, 2024",
+            "5 Jan 2024",
+            "Fri, 5 Jan. 2024",
+            "2024/01/05",
+            "01/05/2024",
+            "1-5-2024",
+        ] {
+            assert!(
+                values_match(&json!(written), &json!("2024-01-05"), &us),
+                "{written}"
+            );
+        }
+        assert!(values_match(
+            &json!("05/01/2024"),
+            &json!("2024-01-05"),
+            &eu
+        ));
+        assert!(!values_match(
+            &json!("05/01/2024"),
+            &json!("2024-01-05"),
+            &us
+        ));
+        assert!(!values_match(
+            &json!("Jan 5, 2023"),
+            &json!("2024-01-05"),
+            &us
+        ));
+        assert!(!values_match(&json!("Feb 29, 2023"), &json!("Feb 29"), &us));
+        assert!(values_match(&json!("Sept 9"), &json!("Sep 9"), &us));
+        assert!(!values_match(&json!("Ma 5"), &json!("Mar 5"), &us));
+    }
+
+    #[test]
+    fn times_in_strings() {
+        let options = CompareOptions::default().with_date_order(DateOrder::MonthFirst);
+        assert!(values_match(
+            &json!("2024-01-05T23:30:00-05:00"),
+            &json!("2024-01-06T04:30:00Z"),
+            &options
+        ));
+        assert!(values_match(
+            &json!("2024-01-05 10:00+0530"),
+            &json!("2024-01-05T04:30:00.000Z"),
+            &options
+        ));
+        assert!(values_match(
+            &json!("2024-01-05T10:00:00"),
+            &json!("2024-01-05T10:00"),
+            &options
+        ));
+        assert!(!values_match(
+            &json!("2024-01-05T10:00:00"),
+            &json!("2024-01-05T10:00:00Z"),
+            &options
+        ));
+        assert!(!values_match(
+            &json!("2024-01-05T10:00:00.5Z"),
+            &json!("2024-01-05T10:00:00Z"),
+            &options
+        ));
+        assert!(!values_match(
+            &json!("2024-01-05T10:00:00Z"),
+            &json!("2024-01-05"),
+            &options
+        ));
+    }
+
+    #[test]
+    fn days_from_civil_matches_known_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+        assert_eq!(days_from_civil(2024, 3, 1), 19_783);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+    }
+}
@@ -4,12 +4,15 @@
 //! and test data generation. It includes utilities for determining policy applicability
 //! and structures for evaluation metrics and test data points.
 
+use std::collections::{BTreeMap, HashSet};
+
 use claudius::{
-    Anthropic, CacheControlEphemeral, ContentBlock, KnownModel, MessageCreateParams, MessageParam,
+    CacheControlEphemeral, ContentBlock, KnownModel, MessageCreateParams, MessageParam,
     MessageParamContent, MessageRole, Model, StopReason, SystemPrompt, TextBlock, ThinkingConfig,
 };
 
-use crate::{Policy, Report, Usage};
+use crate::testing::ReportFixture;
+use crate::{CacheKey, Field, LlmProvider, OnConflict, Policy, PolicyType, Report, Usage};
 
 /// A semantic injection with multiple candidate injections and their rationales.
 ///
@@ -80,7 +83,7 @@ pub struct SemanticInjection {
 /// # }
 /// ```
 pub async fn policy_applies(
-    client: &Anthropic,
+    client: &dyn LlmProvider,
     text: &str,
     semantic_injection: &str,
     k: usize,
@@ -131,7 +134,7 @@ pub async fn policy_applies(
 /// # }
 /// ```
 pub async fn policy_does_not_apply(
-    client: &Anthropic,
+    client: &dyn LlmProvider,
     text: &str,
     semantic_injection: &str,
     k: usize,
@@ -144,7 +147,7 @@ pub async fn policy_does_not_apply(
 }
 
 async fn apply_policy_fractional(
-    client: &Anthropic,
+    client: &dyn LlmProvider,
     text: &str,
     semantic_injection: &str,
     k: usize,
@@ -229,6 +232,93 @@ Output just this one-word answer
     Ok(success)
 }
 
+/// Paraphrase a test data point's text `n` ways while keeping its expected output.
+///
+/// Every variant carries the original's policies, expectations, and expected rules, so a
+/// corpus grows without labeling and a drop in accuracy on the variants measures how much
+/// rule matching depends on the exact wording of the input.  The model is asked to keep
+/// every detail a policy could depend on, but the variants are not verified; spot-check them
+/// or confirm them with [`policy_applies`] before trusting a large batch.
+///
+/// Returns at most `n` variants.  Paraphrases that repeat the original text or one another are
+/// dropped.
+///
+/// # Errors
+///
+/// Returns [`claudius::Error`] if the API call fails or the response is not a JSON array of
+/// strings.
+///
+/// # Examples
+///
+/// ```no_run
+/// use claudius::Anthropic;
+/// use policyai::data::{augment_paraphrases, TestDataPoint};
+///
+/// # async fn example(point: TestDataPoint) -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Anthropic::new(None)?;
+/// let variants = augment_paraphrases(&client, &point, 3).await?;
+/// assert!(variants.iter().all(|v| v.expected == point.expected));
+/// # Ok(())
+/// # }
+/// ```
+pub async fn augment_paraphrases(
+    client: &dyn LlmProvider,
+    point: &TestDataPoint,
+    n: usize,
+) -> Result<Vec<TestDataPoint>, claudius::Error> {
+    if n == 0 {
+        return Ok(vec![]);
+    }
+    let mut content = String::new();
+    for policy in point.policies.iter() {
+        content += &format!("<rule>{}</rule>\n", policy.prompt);
+    }
+    content += &format!("<text>{}</text>\n<count>{n}</count>", point.text);
+    let req = MessageCreateParams {
+        max_tokens: 8192,
+        model: Model::Known(KnownModel::ClaudeSonnet40),
+        messages: vec![MessageParam::new_with_string(content, MessageRole::User)],
+        system: Some(include_str!("../prompts/paraphrase.md").into()),
+        ..Default::default()
+    };
+    let resp = client.send(req).await?;
+    let text = resp
+        .content
+        .iter()
+        .filter_map(|c| match c {
+            ContentBlock::Text(t) => Some(t.text.as_str()),
+            _ => None,
+        })
+        .collect::<String>();
+    let paraphrases = parse_paraphrases(&text).ok_or_else(|| {
+        claudius::Error::validation("paraphrase response was not a JSON array of strings", None)
+    })?;
+    let mut seen = vec![point.text.trim().to_string()];
+    let mut variants = vec![];
+    for paraphrase in paraphrases {
+        let paraphrase = paraphrase.trim().to_string();
+        if paraphrase.is_empty() || seen.contains(&paraphrase) {
+            continue;
+        }
+        seen.push(paraphrase.clone());
+        variants.push(TestDataPoint {
+            text: paraphrase,
+            ..point.clone()
+        });
+        if variants.len() >= n {
+            break;
+        }
+    }
+    Ok(variants)
+}
+
+/// The JSON array of strings in `text`, tolerating prose or a code fence around it.
+fn parse_paraphrases(text: &str) -> Option<Vec<String>> {
+    let start = text.find('[')?;
+    let end = text.rfind(']')?;
+    serde_json::from_str(text.get(start..=end)?).ok()
+}
+
 /// A semantic injection test case with positive and negative examples.
 ///
 /// This structure represents a semantic injection along with sets of text examples
@@ -336,6 +426,7 @@ pub struct ConflictField {
 ///             on_conflict: OnConflict::Default,
 ///         }
 ///     ],
+///     groups: vec![],
 /// };
 ///
 /// let test_point = TestDataPoint {
@@ -344,9 +435,14 @@ pub struct ConflictField {
 ///         r#type: policy_type,
 ///         prompt: "Mark urgent emails".to_string(),
 ///         action: json!({"urgent": true}),
+///         precondition: None,
+///         exact_match: None,
+///         explanation: None,
+///         priority: None,
 ///     }],
 ///     expected: Some(json!({"urgent": true})),
 ///     conflicts: None,
+///     expected_rules: None,
 /// };
 /// ```
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -361,6 +457,139 @@ pub struct TestDataPoint {
     /// Expected conflicts that should occur during policy application.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conflicts: Option<Vec<ConflictField>>,
+    /// The numbers of the rules that should match, counting `policies` from one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_rules: Option<Vec<usize>>,
+}
+
+impl TestDataPoint {
+    /// The [`CacheKey`] of applying the policies to the text, as written by its `Display`.
+    ///
+    /// Points with the same text and policies share it, so it identifies repeated evaluations
+    /// of one input across runs.
+    pub fn input_hash(&self) -> String {
+        CacheKey::new(&self.policies, &self.text).to_string()
+    }
+
+    /// Scrub the input text with `anonymizer`.
+    pub fn anonymize(&mut self, anonymizer: &dyn Anonymizer) {
+        self.text = anonymizer.anonymize(&self.text);
+    }
+}
+
+/// Synthesize test points in which two policies disagree on one field.
+///
+/// For every field of `policy_type` that two policies can disagree on, and for each of the
+/// [`OnConflict::Default`], [`OnConflict::Agreement`], [`OnConflict::LargestValue`],
+/// [`OnConflict::SmallestValue`], and [`OnConflict::Sticky`] strategies, one test point is made:
+/// the field is given that strategy, and two policies whose prompts are drawn from
+/// `injection`'s positives set it to different values.  Both policies should match
+/// `injection`'s text, so the expected output, and the conflicts expected with it, are those the crate's own [`crate::ReportBuilder`] computes when both rules match.
+///
+/// String arrays, which accumulate rather than conflict, dynamic enums, enums with fewer than
+/// two values, and fields in `when` groups are skipped.  Nothing is synthesized if `injection`
+/// has no positives.
+///
+/// # Examples
+///
+/// ```
+/// use policyai::data::{synthesize_conflicts, DecidableSemanticInjection};
+/// use policyai::PolicyType;
+///
+/// let policy_type = PolicyType::parse("type T { urgent: bool = false, score: number }").unwrap();
+/// let injection = DecidableSemanticInjection {
+///     positives: vec!["If the email asks for a reply".to_string()],
+///     negatives: vec![],
+///     text: "Please reply by Friday.".to_string(),
+/// };
+/// let points = synthesize_conflicts(&policy_type, &injection);
+/// assert_eq!(points.len(), 10);
+/// assert_eq!(points[0].expected_rules, Some(vec![1, 2]));
+/// ```
+pub fn synthesize_conflicts(
+    policy_type: &PolicyType,
+    injection: &DecidableSemanticInjection,
+) -> Vec<TestDataPoint> {
+    if injection.positives.is_empty() {
+        return vec![];
+    }
+    let mut points = vec![];
+    for (index, field) in policy_type.fields.iter().enumerate() {
+        let Some((first, second)) = disagreeing_values(field) else {
+            continue;
+        };
+        for strategy in [
+            OnConflict::Default,
+            OnConflict::Agreement,
+            OnConflict::LargestValue,
+            OnConflict::SmallestValue,
+            OnConflict::Sticky,
+        ] {
+            let mut policy_type = policy_type.clone();
+            set_on_conflict(&mut policy_type.fields[index], strategy);
+            let name = field.name();
+            let mut fixture = ReportFixture::new(policy_type);
+            for (n, value) in [&first, &second].into_iter().enumerate() {
+                let positive =
+                    &injection.positives[(points.len() * 2 + n) % injection.positives.len()];
+                fixture = fixture.with_policy(
+                    format!("<match>{positive}</match><action>Set {name} to {value}.</action>"),
+                    serde_json::json!({name: value}),
+                );
+            }
+            let report = fixture.report(&[1, 2]);
+            let conflicts = report
+                .conflicts()
+                .iter()
+                .map(|conflict| ConflictField {
+                    conflict_type: serde_json::to_value(strategy)
+                        .ok()
+                        .and_then(|s| s.as_str().map(String::from))
+                        .unwrap_or_default(),
+                    field_name: conflict.field().to_string(),
+                })
+                .collect::<Vec<_>>();
+            points.push(TestDataPoint {
+                text: injection.text.clone(),
+                policies: fixture.policies().to_vec(),
+                expected: Some(report.value()),
+                conflicts: (!conflicts.is_empty()).then_some(conflicts),
+                expected_rules: Some(vec![1, 2]),
+            });
+        }
+    }
+    points
+}
+
+/// Two different values for `field`, the second the larger, or `None` if policies cannot
+/// disagree on it.
+fn disagreeing_values(field: &Field) -> Option<(serde_json::Value, serde_json::Value)> {
+    match field {
+        Field::Bool { .. } => Some((false.into(), true.into())),
+        Field::Number { default, .. } => {
+            let base = default.map_or(0.0, |d| d.0).floor() + 1.0;
+            Some((base.into(), (base + 1.0).into()))
+        }
+        Field::String { .. } => Some(("alpha".into(), "beta".into())),
+        Field::DateTime { .. } => {
+            Some(("2024-01-01T00:00:00Z".into(), "2024-01-02T00:00:00Z".into()))
+        }
+        Field::StringEnum { values, source, .. } if source.is_static() && values.len() >= 2 => {
+            Some((values[0].clone().into(), values[1].clone().into()))
+        }
+        Field::StringEnum { .. } | Field::StringArray { .. } => None,
+    }
+}
+
+fn set_on_conflict(field: &mut Field, strategy: OnConflict) {
+    match field {
+        Field::Bool { on_conflict, .. }
+        | Field::Number { on_conflict, .. }
+        | Field::String { on_conflict, .. }
+        | Field::StringEnum { on_conflict, .. }
+        | Field::DateTime { on_conflict, .. } => *on_conflict = strategy,
+        Field::StringArray { .. } => {}
+    }
 }
 
 /// Performance and accuracy metrics for policy evaluation.
@@ -386,6 +615,7 @@ pub struct TestDataPoint {
 /// };
 /// ```
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct Metrics {
     /// Number of fields where PolicyAI output exactly matched the expected value.
     pub policyai_fields_matched: usize,
@@ -415,6 +645,12 @@ pub struct Metrics {
     pub policyai_usage: Option<Usage>,
     /// Token and API usage statistics for baseline evaluation.
     pub baseline_usage: Option<Usage>,
+    /// Dollar cost of PolicyAI's usage, when its model has a [`crate::Pricing`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policyai_cost_usd: Option<f64>,
+    /// Dollar cost of the baseline's usage, when its model has a [`crate::Pricing`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline_cost_usd: Option<f64>,
 }
 
 /// A complete evaluation report comparing PolicyAI performance against a baseline.
@@ -436,11 +672,15 @@ pub struct Metrics {
 ///         policies: vec![],
 ///         expected: None,
 ///         conflicts: None,
+///         expected_rules: None,
 ///     },
 ///     metrics: Metrics::default(),
 ///     report: Report::default(),
 ///     output: json!({"processed": true}),
 ///     baseline: Some(json!({"processed": false})),
+///     model: None,
+///     input_hash: None,
+///     policy_set_hash: None,
 /// };
 /// ```
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -448,19 +688,714 @@ pub struct EvaluationReport {
     /// The input test data point that was evaluated.
     pub input: TestDataPoint,
     /// Performance and accuracy metrics from the evaluation.
+    #[serde(default)]
     pub metrics: Metrics,
-    /// The report produced by PolicyAI.
+    /// The report produced by PolicyAI.  Absent from reports written by older evaluators.
+    #[serde(default)]
     pub report: Report,
     /// The structured output produced by PolicyAI.
+    #[serde(default)]
     pub output: serde_json::Value,
     /// The structured output produced by the baseline system, if available.
     pub baseline: Option<serde_json::Value>,
+    /// Label of the model configuration that produced this report, when evaluating several.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// [`TestDataPoint::input_hash`] of `input`, taken before any anonymization.  Absent from
+    /// reports written by older evaluators until [`EvaluationReport::migrate`] fills it in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+    /// [`Report::policy_set_hash`] of the report, so that runs against different policy sets are
+    /// not compared as if they were the same.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_set_hash: Option<String>,
+}
+
+impl EvaluationReport {
+    /// Deserialize a stored evaluation report of any vintage and upgrade its embedded report.
+    ///
+    /// Missing metrics and reports take their defaults.  When `output` is missing it is
+    /// recomputed from the report.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON lacks an `input` or a field has an incompatible type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use policyai::data::EvaluationReport;
+    /// use policyai::Report;
+    ///
+    /// let old = serde_json::json!({
+    ///     "input": {"text": "hello", "policies": []},
+    ///     "output": {"done": true},
+    /// });
+    /// let report = EvaluationReport::migrate(old)?;
+    /// assert_eq!(report.report.schema_version, Report::SCHEMA_VERSION);
+    /// assert_eq!(report.output, serde_json::json!({"done": true}));
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn migrate(mut value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        let report = match value.as_object_mut().and_then(|obj| obj.remove("report")) {
+            Some(report) if !report.is_null() => Report::migrate(report)?,
+            _ => Report::default(),
+        };
+        let output_missing = value.get("output").is_none_or(|v| v.is_null());
+        let mut migrated: EvaluationReport = serde_json::from_value(value)?;
+        if output_missing {
+            migrated.output = report.value();
+        }
+        if migrated.input_hash.is_none() {
+            migrated.input_hash = Some(migrated.input.input_hash());
+        }
+        if migrated.policy_set_hash.is_none() {
+            migrated.policy_set_hash = report.policy_set_hash.clone();
+        }
+        migrated.report = report;
+        Ok(migrated)
+    }
+
+    /// Scrub the input text and every text message stored in the report with `anonymizer`.
+    ///
+    /// Outputs, metrics, and policies are left alone so that the anonymized report still
+    /// scores the same.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use policyai::data::{BasicAnonymizer, EvaluationReport, TestDataPoint};
+    ///
+    /// let mut report = EvaluationReport::migrate(serde_json::json!({
+    ///     "input": {"text": "From: bob@example.com", "policies": []},
+    /// }))?;
+    /// report.anonymize(&BasicAnonymizer::new());
+    /// assert_eq!(report.input.text, "From: [EMAIL]");
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn anonymize(&mut self, anonymizer: &dyn Anonymizer) {
+        self.input.anonymize(anonymizer);
+        let mut messages = self.report.messages();
+        for message in messages.iter_mut() {
+            anonymize_message(message, anonymizer);
+        }
+        self.report = std::mem::take(&mut self.report).with_messages(messages);
+    }
+}
+
+/// Drops repeated evaluations of one input so that corpus statistics count each input once.
+///
+/// Two evaluation reports are repeats if they share an input hash and were produced by the same
+/// model configuration; the first one seen is kept.  Reports without an input hash are hashed
+/// on the spot.
+///
+/// # Examples
+///
+/// ```
+/// use policyai::data::{EvaluationDeduplicator, EvaluationReport};
+///
+/// let report = EvaluationReport::migrate(serde_json::json!({
+///     "input": {"text": "hello", "policies": []},
+/// }))?;
+/// let mut dedup = EvaluationDeduplicator::default();
+/// assert!(dedup.first_time(&report));
+/// assert!(!dedup.first_time(&report));
+/// assert_eq!(dedup.duplicates(), 1);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct EvaluationDeduplicator {
+    seen: HashSet<(String, Option<String>)>,
+    duplicates: usize,
+}
+
+impl EvaluationDeduplicator {
+    /// True if no report of the same input and model has been seen before.
+    pub fn first_time(&mut self, report: &EvaluationReport) -> bool {
+        let hash = report
+            .input_hash
+            .clone()
+            .unwrap_or_else(|| report.input.input_hash());
+        let first = self.seen.insert((hash, report.model.clone()));
+        self.duplicates += usize::from(!first);
+        first
+    }
+
+    /// The number of repeats seen so far.
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+}
+
+/// Keep the first evaluation of each input and model, in order.  See
+/// [`EvaluationDeduplicator`].
+pub fn dedup_evaluations(
+    reports: impl IntoIterator<Item = EvaluationReport>,
+) -> Vec<EvaluationReport> {
+    let mut dedup = EvaluationDeduplicator::default();
+    reports
+        .into_iter()
+        .filter(|report| dedup.first_time(report))
+        .collect()
+}
+
+/// A model to evaluate, as given on the command line.
+///
+/// The specification format is `[label=]model[:max_tokens]`.  The label defaults to the model
+/// name and `max_tokens` defaults to 4096.  Model ids with a provider prefix, such as Bedrock's
+/// `anthropic.claude-v2:1`, keep the version after their first colon, so their `max_tokens`
+/// follows a second colon.
+///
+/// # Examples
+///
+/// ```
+/// use policyai::data::ModelConfig;
+///
+/// let config = ModelConfig::parse("fast=claude-haiku-4-5:2048").unwrap();
+/// assert_eq!(config.label, "fast");
+/// assert_eq!(config.model, "claude-haiku-4-5");
+/// assert_eq!(config.max_tokens, 2048);
+///
+/// let config = ModelConfig::parse("claude-sonnet-4-5").unwrap();
+/// assert_eq!(config.label, "claude-sonnet-4-5");
+/// assert_eq!(config.max_tokens, 4096);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModelConfig {
+    /// Name used to tag reports and metrics produced with this configuration.
+    pub label: String,
+    /// Model identifier sent to the provider.
+    pub model: String,
+    /// Maximum number of output tokens per request.
+    pub max_tokens: u32,
+}
+
+impl ModelConfig {
+    /// Parse a `[label=]model[:max_tokens]` specification.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (label, rest) = match spec.split_once('=') {
+            Some((label, rest)) => (Some(label.trim()), rest.trim()),
+            None => (None, spec),
+        };
+        let version_end = match rest.split_once(':') {
+            Some((id, tail)) if has_provider_prefix(id) => {
+                id.len() + 1 + tail.find(':').unwrap_or(tail.len())
+            }
+            _ => 0,
+        };
+        let (model, max_tokens) = match rest[version_end..].find(':') {
+            Some(colon) => {
+                let (model, max_tokens) = rest.split_at(version_end + colon);
+                let max_tokens = max_tokens[1..]
+                    .parse::<u32>()
+                    .map_err(|err| format!("invalid max_tokens in {spec:?}: {err}"))?;
+                (model.trim(), max_tokens)
+            }
+            None => (rest, 4096),
+        };
+        if model.is_empty() {
+            return Err(format!("missing model in {spec:?}"));
+        }
+        let label = match label {
+            Some("") => return Err(format!("empty label in {spec:?}")),
+            Some(label) => label,
+            None => model,
+        };
+        Ok(Self {
+            label: label.to_string(),
+            model: model.to_string(),
+            max_tokens,
+        })
+    }
+
+    /// Parse a comma-separated list of specifications.
+    pub fn parse_list(specs: &str) -> Result<Vec<Self>, String> {
+        specs
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+}
+
+/// True if `id` starts with a provider, as in `anthropic.claude-v2` or the cross-region
+/// `us.anthropic.claude-3-haiku-20240307-v1`, whose version follows a colon.
+fn has_provider_prefix(id: &str) -> bool {
+    const PROVIDERS: &[&str] = &["ai21", "amazon", "anthropic", "cohere", "meta", "mistral"];
+    let mut parts = id.trim().split('.');
+    parts.next_back();
+    parts.any(|part| PROVIDERS.contains(&part))
+}
+
+/// A collection of test data points with reproducible train/validation splits.
+///
+/// Shuffles and splits are driven by a seed and a generator built into this module, so the same
+/// seed produces the same split on every platform and release.  Splits are stratified by the
+/// number of policies in each point:  a point with five policies is harder than one with a
+/// single policy, and each side of a split gets its share of each.
+///
+/// # Examples
+///
+/// ```
+/// use policyai::data::{Dataset, TestDataPoint};
+///
+/// let dataset = Dataset::new(
+///     (0..10)
+///         .map(|i| TestDataPoint {
+///             text: format!("email {i}"),
+///             policies: vec![],
+///             expected: None,
+///             conflicts: None,
+///             expected_rules: None,
+///         })
+///         .collect(),
+/// );
+/// let split = dataset.split(0.2, 42);
+/// assert_eq!(split.train.len(), 8);
+/// assert_eq!(split.validation.len(), 2);
+/// assert_eq!(dataset.k_folds(5, 42).len(), 5);
+/// ```
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Dataset {
+    /// The points in the dataset, in order.
+    pub points: Vec<TestDataPoint>,
+}
+
+/// One way of dividing a [`Dataset`] into data to tune on and data held out to validate with.
+#[derive(Clone, Debug, Default)]
+pub struct DatasetSplit {
+    /// Points to tune policies and prompts against.
+    pub train: Dataset,
+    /// Held-out points to measure the tuned result on.
+    pub validation: Dataset,
+}
+
+impl Dataset {
+    /// Create a dataset from `points`.
+    pub fn new(points: Vec<TestDataPoint>) -> Self {
+        Self { points }
+    }
+
+    /// Read a dataset from JSONL, one [`TestDataPoint`] per line.  Blank lines are skipped.
+    pub fn from_jsonl(reader: impl std::io::BufRead) -> Result<Self, std::io::Error> {
+        let mut points = vec![];
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let point = serde_json::from_str(&line).map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("line {}: {err}", idx + 1),
+                )
+            })?;
+            points.push(point);
+        }
+        Ok(Self { points })
+    }
+
+    /// Read a dataset from the JSONL file at `path`.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, std::io::Error> {
+        let file = std::fs::File::open(path)?;
+        Self::from_jsonl(std::io::BufReader::new(file))
+    }
+
+    /// The number of points in the dataset.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// True if the dataset has no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The points in a random order determined by `seed`.
+    pub fn shuffled(mut self, seed: u64) -> Self {
+        shuffle(&mut self.points, &mut SplitMix64(seed));
+        self
+    }
+
+    /// Hold out `validation_fraction` of the points, clamped to `[0, 1]`, for validation.
+    ///
+    /// The fraction is applied to each policy count separately and rounded, so the sizes of the
+    /// two sides can differ slightly from the fraction of the whole.
+    pub fn split(&self, validation_fraction: f64, seed: u64) -> DatasetSplit {
+        let validation_fraction = validation_fraction.clamp(0.0, 1.0);
+        let mut split = DatasetSplit::default();
+        for stratum in self.strata(seed) {
+            let held_out = (stratum.len() as f64 * validation_fraction).round() as usize;
+            for (idx, point) in stratum.into_iter().enumerate() {
+                if idx < held_out {
+                    split.validation.points.push(point.clone());
+                } else {
+                    split.train.points.push(point.clone());
+                }
+            }
+        }
+        split
+    }
+
+    /// Divide the points into `k` folds and return one split per fold, in which that fold is the
+    /// validation data and the other folds are the training data.
+    ///
+    /// Every point is in exactly one validation set.  Returns no splits when `k` is zero.
+    pub fn k_folds(&self, k: usize, seed: u64) -> Vec<DatasetSplit> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut folds = vec![vec![]; k];
+        // Carry the position across strata so that small strata do not all land in fold 0.
+        let mut next = 0;
+        for stratum in self.strata(seed) {
+            for point in stratum {
+                folds[next % k].push(point);
+                next += 1;
+            }
+        }
+        (0..k)
+            .map(|held_out| {
+                let mut split = DatasetSplit::default();
+                for (idx, fold) in folds.iter().enumerate() {
+                    let side = if idx == held_out {
+                        &mut split.validation
+                    } else {
+                        &mut split.train
+                    };
+                    side.points
+                        .extend(fold.iter().map(|point| (*point).clone()));
+                }
+                split
+            })
+            .collect()
+    }
+
+    /// The points grouped by policy count in ascending order, each group shuffled by `seed`.
+    fn strata(&self, seed: u64) -> Vec<Vec<&TestDataPoint>> {
+        let mut strata: BTreeMap<usize, Vec<&TestDataPoint>> = BTreeMap::new();
+        for point in self.points.iter() {
+            strata.entry(point.policies.len()).or_default().push(point);
+        }
+        let mut rng = SplitMix64(seed);
+        strata
+            .into_values()
+            .map(|mut stratum| {
+                shuffle(&mut stratum, &mut rng);
+                stratum
+            })
+            .collect()
+    }
+}
+
+/// The SplitMix64 generator.  Small, fast, and fixed, so seeded splits never change.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A value in `0..bound`; the modulo bias is irrelevant at dataset sizes.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Fisher-Yates shuffle of `items`.
+fn shuffle<T>(items: &mut [T], rng: &mut SplitMix64) {
+    for idx in (1..items.len()).rev() {
+        items.swap(idx, rng.below(idx + 1));
+    }
+}
+
+/// Scrubs identifying details from text before evaluation data is shared.
+///
+/// Implementations are applied to [`TestDataPoint::text`] and to the messages stored in
+/// [`EvaluationReport::report`] by [`EvaluationReport::anonymize`].
+pub trait Anonymizer {
+    /// Return `text` with identifying details replaced.
+    fn anonymize(&self, text: &str) -> String;
+}
+
+/// An [`Anonymizer`] that replaces email addresses, digit runs, and a list of known names.
+///
+/// Email addresses become `[EMAIL]`, numbers (including ones written with `-`, `.`, `,`, or
+/// `/` between digit groups, such as phone numbers and dates) become `[NUMBER]`, and each
+/// configured name becomes `[NAME]` wherever it appears as a whole word, ignoring ASCII case.
+///
+/// # Examples
+///
+/// ```
+/// use policyai::data::{Anonymizer, BasicAnonymizer};
+///
+/// let anonymizer = BasicAnonymizer::new().with_names(["Alice"]);
+/// assert_eq!(
+///     anonymizer.anonymize("alice (alice@example.com) called 555-0100 twice"),
+///     "[NAME] ([EMAIL]) called [NUMBER] twice"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct BasicAnonymizer {
+    emails: bool,
+    numbers: bool,
+    names: Vec<String>,
+}
+
+impl BasicAnonymizer {
+    /// Scrub emails and numbers, but no names.
+    pub fn new() -> Self {
+        Self {
+            emails: true,
+            numbers: true,
+            names: vec![],
+        }
+    }
+
+    /// Set whether email addresses are scrubbed.
+    pub fn with_emails(mut self, emails: bool) -> Self {
+        self.emails = emails;
+        self
+    }
+
+    /// Set whether numbers are scrubbed.
+    pub fn with_numbers(mut self, numbers: bool) -> Self {
+        self.numbers = numbers;
+        self
+    }
+
+    /// Add names to scrub.  Empty names are ignored.
+    pub fn with_names<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.names.extend(
+            names
+                .into_iter()
+                .map(Into::into)
+                .filter(|n: &String| !n.trim().is_empty()),
+        );
+        self
+    }
+
+    fn is_email_local(c: char) -> bool {
+        c.is_alphanumeric() || "._%+-".contains(c)
+    }
+
+    fn is_email_domain(c: char) -> bool {
+        c.is_alphanumeric() || ".-".contains(c)
+    }
+
+    fn scrub_emails(text: &str) -> String {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut out = String::with_capacity(text.len());
+        let mut emitted = 0;
+        let mut idx = 0;
+        while idx < chars.len() {
+            if chars[idx] != '@' {
+                idx += 1;
+                continue;
+            }
+            let mut start = idx;
+            while start > emitted && Self::is_email_local(chars[start - 1]) {
+                start -= 1;
+            }
+            let mut end = idx + 1;
+            while end < chars.len() && Self::is_email_domain(chars[end]) {
+                end += 1;
+            }
+            // Sentence punctuation after an address is not part of its domain.
+            while end > idx + 1 && chars[end - 1] == '.' {
+                end -= 1;
+            }
+            let domain = &chars[idx + 1..end];
+            let dot = domain.iter().position(|c| *c == '.');
+            if start < idx && matches!(dot, Some(d) if d > 0) {
+                out.extend(&chars[emitted..start]);
+                out.push_str("[EMAIL]");
+                emitted = end;
+                idx = end;
+            } else {
+                idx += 1;
+            }
+        }
+        out.extend(&chars[emitted..]);
+        out
+    }
+
+    fn scrub_numbers(text: &str) -> String {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut out = String::with_capacity(text.len());
+        let mut idx = 0;
+        while idx < chars.len() {
+            if !chars[idx].is_ascii_digit() {
+                out.push(chars[idx]);
+                idx += 1;
+                continue;
+            }
+            while idx < chars.len() {
+                if chars[idx].is_ascii_digit() {
+                    idx += 1;
+                } else if "-.,/".contains(chars[idx])
+                    && idx + 1 < chars.len()
+                    && chars[idx + 1].is_ascii_digit()
+                {
+                    idx += 2;
+                } else {
+                    break;
+                }
+            }
+            out.push_str("[NUMBER]");
+        }
+        out
+    }
+
+    fn scrub_name(text: &str, name: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        while !rest.is_empty() {
+            let found = rest
+                .char_indices()
+                .map(|(i, _)| i)
+                .filter(|i| {
+                    rest.get(*i..*i + name.len())
+                        .is_some_and(|s| s.eq_ignore_ascii_case(name))
+                })
+                .find(|i| {
+                    !is_word(rest[..*i].chars().next_back())
+                        && !is_word(rest[*i + name.len()..].chars().next())
+                });
+            let Some(i) = found else {
+                break;
+            };
+            out.push_str(&rest[..i]);
+            out.push_str("[NAME]");
+            rest = &rest[i + name.len()..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+impl Default for BasicAnonymizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Anonymizer for BasicAnonymizer {
+    fn anonymize(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        // Emails go first so their digits and names are replaced as a unit.
+        if self.emails {
+            text = Self::scrub_emails(&text);
+        }
+        for name in self.names.iter() {
+            text = Self::scrub_name(&text, name);
+        }
+        if self.numbers {
+            text = Self::scrub_numbers(&text);
+        }
+        text
+    }
+}
+
+fn anonymize_message(message: &mut MessageParam, anonymizer: &dyn Anonymizer) {
+    match &mut message.content {
+        MessageParamContent::String(text) => *text = anonymizer.anonymize(text),
+        MessageParamContent::Array(blocks) => {
+            for block in blocks.iter_mut() {
+                if let ContentBlock::Text(block) = block {
+                    block.text = anonymizer.anonymize(&block.text);
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_paraphrases_tolerates_a_code_fence() {
+        let text = "```json\n[\"first [draft]\", \"second\"]\n```";
+        assert_eq!(
+            parse_paraphrases(text),
+            Some(vec!["first [draft]".to_string(), "second".to_string()])
+        );
+        assert_eq!(parse_paraphrases("no array here"), None);
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn synthesize_conflicts_covers_every_strategy() {
+        let policy_type = PolicyType::parse(
+            r#"type T { score: number = 0, priority: ["low", "high"] = "low", tags: [string] }"#,
+        )
+        .unwrap();
+        let injection = DecidableSemanticInjection {
+            positives: vec!["If it is urgent".to_string(), "If it is late".to_string()],
+            negatives: vec![],
+            text: "Late and urgent.".to_string(),
+        };
+        let points = synthesize_conflicts(&policy_type, &injection);
+        assert_eq!(points.len(), 10);
+        assert_eq!(
+            points[0].policies[0].prompt,
+            "<match>If it is urgent</match><action>Set score to 1.0.</action>"
+        );
+        assert_eq!(points[0].policies[1].prompt.find("If it is late"), Some(7));
+        let strategies = points
+            .iter()
+            .map(|point| {
+                point
+                    .policies
+                    .iter()
+                    .flat_map(|policy| policy.r#type.fields.iter())
+                    .find_map(|field| match field {
+                        Field::Number { on_conflict, .. }
+                        | Field::StringEnum { on_conflict, .. }
+                            if point.policies[0].action.get(field.name()).is_some() =>
+                        {
+                            Some(*on_conflict)
+                        }
+                        _ => None,
+                    })
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            strategies,
+            [
+                OnConflict::Default,
+                OnConflict::Agreement,
+                OnConflict::LargestValue,
+                OnConflict::SmallestValue,
+                OnConflict::Sticky,
+            ]
+            .repeat(2)
+        );
+        let agreement = points[6].conflicts.as_ref().unwrap();
+        assert_eq!(agreement.len(), 1);
+        assert_eq!(agreement[0].conflict_type, "agreement");
+        assert_eq!(agreement[0].field_name, "priority");
+        assert!(points[5].conflicts.is_none());
+        assert_eq!(points[2].expected.as_ref().unwrap()["score"], 2.0);
+        assert_eq!(points[3].expected.as_ref().unwrap()["score"], 1.0);
+        assert_eq!(points[4].expected.as_ref().unwrap()["score"], 1.0);
+        assert_eq!(points[7].expected.as_ref().unwrap()["priority"], "high");
+        // Enums order by length, so "high" cannot displace the shorter "low".
+        assert_eq!(
+            points[8].conflicts.as_ref().unwrap()[0].conflict_type,
+            "smallest"
+        );
+        assert!(points[9].conflicts.is_none());
+    }
+
     #[test]
     fn semantic_injection_default() {
         let injection = SemanticInjection::default();
@@ -570,6 +1505,7 @@ mod tests {
                 default: Some(false),
                 on_conflict: crate::OnConflict::Default,
             }],
+            groups: vec![],
         };
 
         let point = TestDataPoint {
@@ -578,9 +1514,14 @@ mod tests {
                 r#type: policy_type,
                 prompt: "test prompt".to_string(),
                 action: serde_json::json!({"enabled": true}),
+                precondition: None,
+                exact_match: None,
+                explanation: None,
+                priority: None,
             }],
             expected: None,
             conflicts: None,
+            expected_rules: None,
         };
 
         let serialized = serde_json::to_string(&point).unwrap();
@@ -601,6 +1542,7 @@ mod tests {
                 default: None,
                 on_conflict: crate::OnConflict::Agreement,
             }],
+            groups: vec![],
         };
 
         let point = TestDataPoint {
@@ -609,9 +1551,14 @@ mod tests {
                 r#type: policy_type,
                 prompt: "greeting".to_string(),
                 action: serde_json::json!({"message": "hello"}),
+                precondition: None,
+                exact_match: None,
+                explanation: None,
+                priority: None,
             }],
             expected: Some(serde_json::json!({"message": "hello"})),
             conflicts: None,
+            expected_rules: None,
         };
 
         let serialized = serde_json::to_string(&point).unwrap();
@@ -633,6 +1580,7 @@ mod tests {
                 default: Some(crate::t64(0.0)),
                 on_conflict: crate::OnConflict::LargestValue,
             }],
+            groups: vec![],
         };
 
         let point = TestDataPoint {
@@ -642,11 +1590,19 @@ mod tests {
                     r#type: policy_type.clone(),
                     prompt: "first".to_string(),
                     action: serde_json::json!({"count": 10}),
+                    precondition: None,
+                    exact_match: None,
+                    explanation: None,
+                    priority: None,
                 },
                 Policy {
                     r#type: policy_type,
                     prompt: "second".to_string(),
                     action: serde_json::json!({"count": 20}),
+                    precondition: None,
+                    exact_match: None,
+                    explanation: None,
+                    priority: None,
                 },
             ],
             expected: Some(serde_json::json!({"count": 20})),
@@ -654,6 +1610,7 @@ mod tests {
                 conflict_type: "largest".to_string(),
                 field_name: "count".to_string(),
             }]),
+            expected_rules: None,
         };
 
         let serialized = serde_json::to_string(&point).unwrap();
@@ -701,4 +1658,260 @@ mod tests {
         assert_eq!(original.conflict_type, cloned.conflict_type);
         assert_eq!(original.field_name, cloned.field_name);
     }
+
+    #[test]
+    fn model_config_parse_list() {
+        let configs =
+            ModelConfig::parse_list("claude-sonnet-4-5, fast=claude-haiku-4-5:1024,").unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].label, "claude-sonnet-4-5");
+        assert_eq!(configs[1].label, "fast");
+        assert_eq!(configs[1].model, "claude-haiku-4-5");
+        assert_eq!(configs[1].max_tokens, 1024);
+    }
+
+    #[test]
+    fn model_config_parse_errors() {
+        assert!(ModelConfig::parse("").is_err());
+        assert!(ModelConfig::parse("=claude-haiku-4-5").is_err());
+        assert!(ModelConfig::parse("fast=").is_err());
+        assert!(ModelConfig::parse("claude-haiku-4-5:lots").is_err());
+        assert!(ModelConfig::parse("anthropic.claude-v2:1:lots").is_err());
+    }
+
+    #[test]
+    fn model_config_parse_keeps_the_version_of_a_provider_model() {
+        let config = ModelConfig::parse("anthropic.claude-v2:1").unwrap();
+        assert_eq!(config.label, "anthropic.claude-v2:1");
+        assert_eq!(config.model, "anthropic.claude-v2:1");
+        assert_eq!(config.max_tokens, 4096);
+        let config =
+            ModelConfig::parse("bedrock=us.anthropic.claude-3-haiku-20240307-v1:0:1024").unwrap();
+        assert_eq!(config.label, "bedrock");
+        assert_eq!(config.model, "us.anthropic.claude-3-haiku-20240307-v1:0");
+        assert_eq!(config.max_tokens, 1024);
+        let config = ModelConfig::parse("claude-haiku-4-5:1024").unwrap();
+        assert_eq!(config.model, "claude-haiku-4-5");
+        assert_eq!(config.max_tokens, 1024);
+    }
+
+    #[test]
+    fn evaluation_report_migrate_recomputes_missing_output() {
+        let old = serde_json::json!({
+            "input": {"text": "t", "policies": []},
+            "metrics": {"policyai_fields_matched": 2},
+            "report": {"default": {"flag": true}},
+        });
+        let migrated = EvaluationReport::migrate(old).unwrap();
+        assert_eq!(migrated.metrics.policyai_fields_matched, 2);
+        assert_eq!(migrated.output, serde_json::json!({"flag": true}));
+        assert_eq!(migrated.report.schema_version, Report::SCHEMA_VERSION);
+        assert!(EvaluationReport::migrate(serde_json::json!({"output": {}})).is_err());
+    }
+
+    #[test]
+    fn basic_anonymizer_emails() {
+        let anonymizer = BasicAnonymizer::new().with_numbers(false);
+        assert_eq!(
+            anonymizer.anonymize("Write to first.last+tag@mail.example.co.uk."),
+            "Write to [EMAIL]."
+        );
+        assert_eq!(
+            anonymizer.anonymize("a@b and @handle and x@y. stay"),
+            "a@b and @handle and x@y. stay"
+        );
+        assert_eq!(
+            anonymizer.anonymize("<a@x.io>,<b@y.io>"),
+            "<[EMAIL]>,<[EMAIL]>"
+        );
+    }
+
+    #[test]
+    fn basic_anonymizer_numbers() {
+        let anonymizer = BasicAnonymizer::new();
+        assert_eq!(
+            anonymizer.anonymize("Call +1 (555) 010-0199 by 2024/03/01, ref 12,345.67."),
+            "Call +[NUMBER] ([NUMBER]) [NUMBER] by [NUMBER], ref [NUMBER]."
+        );
+        assert_eq!(anonymizer.anonymize("no digits"), "no digits");
+    }
+
+    #[test]
+    fn basic_anonymizer_names_are_whole_words() {
+        let anonymizer = BasicAnonymizer::new().with_names(["Ann", ""]);
+        assert_eq!(
+            anonymizer.anonymize("ANN met Anna and ann_x; ann."),
+            "[NAME] met Anna and ann_x; [NAME]."
+        );
+    }
+
+    #[test]
+    fn dedup_evaluations_keeps_first_per_input_and_model() {
+        let report = |text: &str, model: Option<&str>, hash: bool| {
+            let mut report = EvaluationReport::migrate(serde_json::json!({
+                "input": {"text": text, "policies": []},
+                "model": model,
+            }))
+            .unwrap();
+            if !hash {
+                report.input_hash = None;
+            }
+            report
+        };
+        let kept = dedup_evaluations([
+            report("a", None, true),
+            report("b", None, true),
+            report("a", None, false),
+            report("a", Some("fast"), true),
+            report("b", None, true),
+        ]);
+        let kept = kept
+            .iter()
+            .map(|r| (r.input.text.as_str(), r.model.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(kept, [("a", None), ("b", None), ("a", Some("fast"))]);
+    }
+
+    #[test]
+    fn input_hash_survives_anonymization() {
+        let mut report = EvaluationReport::migrate(serde_json::json!({
+            "input": {"text": "From: bob@example.com", "policies": []},
+        }))
+        .unwrap();
+        let hash = report.input_hash.clone();
+        assert_eq!(hash.as_deref(), Some(report.input.input_hash().as_str()));
+        report.anonymize(&BasicAnonymizer::new());
+        let report = EvaluationReport::migrate(serde_json::to_value(&report).unwrap()).unwrap();
+        assert_eq!(report.input_hash, hash);
+        assert_ne!(report.input.input_hash(), hash.unwrap());
+    }
+
+    #[test]
+    fn evaluation_report_anonymize_scrubs_messages() {
+        let mut report = EvaluationReport {
+            input: TestDataPoint {
+                text: "Hi, it's Carol".to_string(),
+                policies: vec![],
+                expected: None,
+                conflicts: None,
+                expected_rules: None,
+            },
+            metrics: Metrics::default(),
+            report: Report::default(),
+            output: serde_json::json!({"sender": "Carol"}),
+            baseline: None,
+            model: None,
+            input_hash: None,
+            policy_set_hash: None,
+        };
+        report.report = report.report.with_messages(vec![
+            MessageParam::new_with_string(
+                "<text>Hi, it's Carol</text>".to_string(),
+                MessageRole::User,
+            ),
+            MessageParam {
+                role: MessageRole::Assistant,
+                content: MessageParamContent::Array(vec![ContentBlock::Text(TextBlock {
+                    text: "carol@example.com".to_string(),
+                    cache_control: None,
+                    citations: None,
+                })]),
+            },
+        ]);
+        report.anonymize(&BasicAnonymizer::new().with_names(["Carol"]));
+        assert_eq!(report.input.text, "Hi, it's [NAME]");
+        let rendered = serde_json::to_string(&report.report.messages()).unwrap();
+        assert!(!rendered.contains("Carol"));
+        assert!(!rendered.contains("carol"));
+        assert!(rendered.contains("[NAME]"));
+        assert!(rendered.contains("[EMAIL]"));
+        assert_eq!(report.output, serde_json::json!({"sender": "Carol"}));
+    }
+
+    fn dataset() -> Dataset {
+        use crate::{Field, OnConflict, PolicyType};
+        let policy = Policy {
+            r#type: PolicyType {
+                name: "T".to_string(),
+                fields: vec![Field::Bool {
+                    name: "urgent".to_string(),
+                    default: Some(false),
+                    on_conflict: OnConflict::Default,
+                }],
+                groups: vec![],
+            },
+            prompt: "If urgent".to_string(),
+            action: serde_json::json!({"urgent": true}),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        };
+        Dataset::new(
+            (0..20)
+                .map(|idx| TestDataPoint {
+                    text: format!("point {idx}"),
+                    policies: vec![policy.clone(); idx % 2 + 1],
+                    expected: None,
+                    conflicts: None,
+                    expected_rules: None,
+                })
+                .collect(),
+        )
+    }
+
+    fn texts(dataset: &Dataset) -> Vec<String> {
+        dataset.points.iter().map(|p| p.text.clone()).collect()
+    }
+
+    #[test]
+    fn dataset_from_jsonl_skips_blank_lines_and_reports_bad_ones() {
+        let jsonl = "{\"text\":\"a\",\"policies\":[]}\n\n{\"text\":\"b\",\"policies\":[]}\n";
+        let dataset = Dataset::from_jsonl(jsonl.as_bytes()).unwrap();
+        assert_eq!(texts(&dataset), vec!["a", "b"]);
+        let err = Dataset::from_jsonl("{}\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"), "{err}");
+    }
+
+    #[test]
+    fn dataset_shuffle_is_deterministic() {
+        let a = texts(&dataset().shuffled(7));
+        assert_eq!(a, texts(&dataset().shuffled(7)));
+        assert_ne!(a, texts(&dataset().shuffled(8)));
+        let mut sorted = a.clone();
+        sorted.sort();
+        let mut original = texts(&dataset());
+        original.sort();
+        assert_eq!(sorted, original);
+    }
+
+    #[test]
+    fn dataset_split_is_stratified() {
+        let split = dataset().split(0.2, 3);
+        assert_eq!(split.validation.len(), 4);
+        assert_eq!(split.train.len(), 16);
+        let singles = |d: &Dataset| d.points.iter().filter(|p| p.policies.len() == 1).count();
+        assert_eq!(singles(&split.validation), 2);
+        assert_eq!(singles(&split.train), 8);
+        assert_eq!(
+            texts(&split.validation),
+            texts(&dataset().split(0.2, 3).validation)
+        );
+    }
+
+    #[test]
+    fn dataset_k_folds_cover_every_point_once() {
+        let folds = dataset().k_folds(3, 11);
+        assert_eq!(folds.len(), 3);
+        let mut held_out = vec![];
+        for fold in folds.iter() {
+            assert_eq!(fold.train.len() + fold.validation.len(), 20);
+            held_out.extend(texts(&fold.validation));
+        }
+        held_out.sort();
+        let mut all = texts(&dataset());
+        all.sort();
+        assert_eq!(held_out, all);
+        assert!(dataset().k_folds(0, 11).is_empty());
+    }
 }
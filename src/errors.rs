@@ -68,6 +68,66 @@ pub enum PolicyError {
         /// The actual type that was found.
         actual: String,
     },
+    /// A prompt placeholder has no value in the variables supplied at apply time
+    MissingVariable {
+        /// Name of the placeholder.
+        variable: String,
+        /// The prompt containing the placeholder.
+        prompt: String,
+    },
+    /// A prompt contains a `{{` that does not open a valid placeholder
+    MalformedPlaceholder {
+        /// The prompt containing the placeholder.
+        prompt: String,
+        /// Byte offset of the offending `{{`.
+        offset: usize,
+    },
+    /// A field's conflict strategy is from a newer version of the crate and was handled as
+    /// agreement
+    UnknownOnConflict {
+        /// Name of the field.
+        field: String,
+    },
+    /// A policy decided without the LLM leaves a value for the LLM to fill in
+    LocalValueMissing {
+        /// Name of the field whose value is null in the action.
+        field: String,
+    },
+    /// The LLM output more strings for an array field than the configured limit
+    ArrayTruncated {
+        /// Name of the array field.
+        field: String,
+        /// Number of strings the LLM output.
+        length: usize,
+        /// Number of strings kept.
+        limit: usize,
+    },
+    /// A value reported for an enum field is not one of the field's values
+    InvalidEnumValue {
+        /// Name of the enum field.
+        field: String,
+        /// The value that was rejected.
+        value: String,
+        /// The values the field allows.
+        allowed: Vec<String>,
+    },
+    /// A dynamic enum field was used without its values being supplied
+    MissingEnumValues {
+        /// Name of the enum field.
+        field: String,
+    },
+    /// A value reported for a datetime field is not an RFC 3339 timestamp
+    InvalidDateTime {
+        /// Name of the datetime field.
+        field: String,
+        /// The value that was rejected.
+        value: String,
+    },
+    /// A field was asked for that the policy type does not declare
+    UnknownField {
+        /// The name asked for.
+        field: String,
+    },
 }
 
 impl PolicyError {
@@ -177,6 +237,41 @@ impl std::fmt::Display for PolicyError {
             } => {
                 write!(f, "Type check failure at {file}:{line}: {message}\n  Expected: {expected}\n  Actual: {actual}\nSuggestion: Verify that your policy actions match the policy type definition")
             }
+            PolicyError::MissingVariable { variable, prompt } => {
+                write!(f, "No value for variable '{variable}' in prompt: {prompt}\nSuggestion: Supply '{variable}' in the variables passed to the manager")
+            }
+            PolicyError::MalformedPlaceholder { prompt, offset } => {
+                write!(f, "Malformed placeholder at byte {offset} of prompt: {prompt}\nSuggestion: Placeholders look like {{{{name}}}}, where name is letters, digits, and underscores")
+            }
+            PolicyError::UnknownOnConflict { field } => {
+                write!(f, "Field '{field}' uses a conflict strategy this version of policyai does not know; it was resolved by agreement\nSuggestion: Upgrade policyai to the version that wrote the policy")
+            }
+            PolicyError::LocalValueMissing { field } => {
+                write!(f, "Field '{field}' is null in the action of a policy decided without the LLM\nSuggestion: Give '{field}' a value, or remove the policy's exact match so the LLM can fill it in")
+            }
+            PolicyError::ArrayTruncated {
+                field,
+                length,
+                limit,
+            } => {
+                write!(f, "Array field '{field}' had {length} elements; kept the first {limit}\nSuggestion: Raise the array limit if outputs this long are expected")
+            }
+            PolicyError::InvalidEnumValue {
+                field,
+                value,
+                allowed,
+            } => {
+                write!(f, "Value {value:?} is not allowed for enum field '{field}'\nSuggestion: Use one of {}", allowed.join(", "))
+            }
+            PolicyError::MissingEnumValues { field } => {
+                write!(f, "No values were supplied for dynamic enum field '{field}'\nSuggestion: Supply the values of '{field}' in the enum values passed to the manager")
+            }
+            PolicyError::InvalidDateTime { field, value } => {
+                write!(f, "Value {value:?} is not an RFC 3339 datetime for field '{field}'\nSuggestion: Write datetimes with a date, a time, and a UTC offset, as in 2024-03-01T17:00:00Z")
+            }
+            PolicyError::UnknownField { field } => {
+                write!(f, "The policy type declares no field '{field}'\nSuggestion: Ask only for fields the policy type declares")
+            }
         }
     }
 }
@@ -202,9 +297,12 @@ impl std::error::Error for PolicyError {
 ///
 /// let conflict = Conflict::BoolConflict {
 ///     field: "urgent".to_string(),
+///     rule1: 1,
+///     rule2: 3,
 ///     val1: true,
 ///     val2: false,
 /// };
+/// assert_eq!(conflict.to_string(), "urgent: rule 1 set true, rule 3 set false");
 /// ```
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub enum Conflict {
@@ -212,6 +310,12 @@ pub enum Conflict {
     BoolConflict {
         /// Name of the field experiencing the conflict.
         field: String,
+        /// Rule number of the policy that set `val1`, or zero if unknown.
+        #[serde(default)]
+        rule1: usize,
+        /// Rule number of the policy that set `val2`, or zero if unknown.
+        #[serde(default)]
+        rule2: usize,
         /// First boolean value from one policy.
         val1: bool,
         /// Second boolean value from another policy.
@@ -221,6 +325,12 @@ pub enum Conflict {
     NumberConflict {
         /// Name of the field experiencing the conflict.
         field: String,
+        /// Rule number of the policy that set `val1`, or zero if unknown.
+        #[serde(default)]
+        rule1: usize,
+        /// Rule number of the policy that set `val2`, or zero if unknown.
+        #[serde(default)]
+        rule2: usize,
         /// First numeric value from one policy.
         val1: serde_json::Number,
         /// Second numeric value from another policy.
@@ -230,6 +340,12 @@ pub enum Conflict {
     StringConflict {
         /// Name of the field experiencing the conflict.
         field: String,
+        /// Rule number of the policy that set `val1`, or zero if unknown.
+        #[serde(default)]
+        rule1: usize,
+        /// Rule number of the policy that set `val2`, or zero if unknown.
+        #[serde(default)]
+        rule2: usize,
         /// First string value from one policy.
         val1: String,
         /// Second string value from another policy.
@@ -239,6 +355,12 @@ pub enum Conflict {
     Disagree {
         /// Name of the field experiencing the disagreement.
         name: String,
+        /// Rule number of the policy that set `value1`, or zero if unknown.
+        #[serde(default)]
+        rule1: usize,
+        /// Rule number of the policy that set `value2`, or zero if unknown.
+        #[serde(default)]
+        rule2: usize,
         /// First value from one policy.
         value1: serde_json::Value,
         /// Second value from another policy.
@@ -246,6 +368,117 @@ pub enum Conflict {
     },
 }
 
+impl Conflict {
+    /// The name of the field the policies disagreed on.
+    pub fn field(&self) -> &str {
+        match self {
+            Conflict::BoolConflict { field, .. }
+            | Conflict::NumberConflict { field, .. }
+            | Conflict::StringConflict { field, .. } => field,
+            Conflict::Disagree { name, .. } => name,
+        }
+    }
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn rule(number: usize) -> String {
+            if number == 0 {
+                "an unknown rule".to_string()
+            } else {
+                format!("rule {number}")
+            }
+        }
+        let (rule1, rule2, val1, val2) = match self {
+            Conflict::BoolConflict {
+                rule1,
+                rule2,
+                val1,
+                val2,
+                ..
+            } => (rule1, rule2, val1.to_string(), val2.to_string()),
+            Conflict::NumberConflict {
+                rule1,
+                rule2,
+                val1,
+                val2,
+                ..
+            } => (rule1, rule2, val1.to_string(), val2.to_string()),
+            Conflict::StringConflict {
+                rule1,
+                rule2,
+                val1,
+                val2,
+                ..
+            } => (rule1, rule2, format!("{val1:?}"), format!("{val2:?}")),
+            Conflict::Disagree {
+                rule1,
+                rule2,
+                value1,
+                value2,
+                ..
+            } => (rule1, rule2, value1.to_string(), value2.to_string()),
+        };
+        write!(
+            f,
+            "{}: {} set {val1}, {} set {val2}",
+            self.field(),
+            rule(*rule1),
+            rule(*rule2)
+        )
+    }
+}
+
+/////////////////////////////////////////// ClientError ////////////////////////////////////////////
+
+/// An error from the LLM client, from [`ApplyError::Claudius`].
+///
+/// The client's error type is kept behind this opaque wrapper so that [`ApplyError`] is the same
+/// with or without the `client` feature.
+#[derive(Debug)]
+pub struct ClientError(Box<dyn std::error::Error + Send + Sync + 'static>);
+
+impl ClientError {
+    /// Wrap `err`, from an LLM client other than claudius.
+    pub fn new(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self(Box::new(err))
+    }
+
+    /// The claudius error, if the client was claudius.
+    #[cfg(feature = "client")]
+    pub fn as_claudius(&self) -> Option<&claudius::Error> {
+        self.0.downcast_ref()
+    }
+
+    /// True if retrying the same request might succeed.  Only claudius errors are known to be.
+    pub fn is_retryable(&self) -> bool {
+        #[cfg(feature = "client")]
+        if let Some(err) = self.as_claudius() {
+            return err.is_retryable();
+        }
+        false
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<claudius::Error> for ClientError {
+    fn from(err: claudius::Error) -> Self {
+        Self::new(err)
+    }
+}
+
 //////////////////////////////////////////// ApplyError ////////////////////////////////////////////
 
 /// Errors that can occur when applying policies to unstructured data
@@ -254,7 +487,7 @@ pub enum ApplyError {
     /// A policy-specific error occurred
     Policy(PolicyError),
     /// An error occurred while communicating with the LLM
-    Claudius(claudius::Error),
+    Claudius(ClientError),
     /// Policies have conflicting values that cannot be resolved
     Conflict(Conflict),
     /// Too many retry attempts were made to resolve inconsistencies
@@ -264,6 +497,36 @@ pub enum ApplyError {
         /// The error from the final attempt.
         last_error: String,
     },
+    /// More policies would be sent to the LLM in one request than the manager allows
+    TooManyPolicies {
+        /// Number of policies that would have been sent.
+        policies: usize,
+        /// The most the manager sends in one request.
+        max: usize,
+    },
+    /// The request has no usable model: its identifier is empty or not among the models the
+    /// manager allows
+    ModelNotConfigured {
+        /// The model identifier the request carried, possibly empty.
+        model: String,
+        /// Prefixes of the allowed model identifiers, or empty if any model is allowed.
+        allowed: Vec<String>,
+    },
+    /// Applying policies took longer than the time allowed
+    TimedOut {
+        /// The time allowed, in milliseconds.
+        after_ms: u64,
+    },
+    /// The report's value broke a rule checked by the manager's [`crate::Validator`]
+    ValidationFailed {
+        /// Why the value broke the rule, as the validator put it.
+        message: String,
+    },
+    /// A batch job on disk was submitted for other policies or texts than those being applied
+    BatchJobMismatch {
+        /// Where the job is stored.
+        path: String,
+    },
     /// The LLM response was invalid or unexpected
     InvalidResponse {
         /// Description of what made the response invalid.
@@ -282,6 +545,11 @@ impl ApplyError {
         }
     }
 
+    /// Create a TooManyPolicies error
+    pub fn too_many_policies(policies: usize, max: usize) -> Self {
+        Self::TooManyPolicies { policies, max }
+    }
+
     /// Create an InvalidResponse error with a suggestion
     pub fn invalid_response(message: impl Into<String>, suggestion: impl Into<String>) -> Self {
         Self::InvalidResponse {
@@ -289,6 +557,25 @@ impl ApplyError {
             suggestion: suggestion.into(),
         }
     }
+
+    /// True if retrying the same request might succeed.
+    ///
+    /// Transient transport failures (rate limits, timeouts, 5xx) are retryable, as is running out
+    /// of self-correction attempts, because the model is nondeterministic.  Policy and conflict
+    /// errors are properties of the input and are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ApplyError::Claudius(err) => err.is_retryable(),
+            ApplyError::TooManyIterations { .. } | ApplyError::TimedOut { .. } => true,
+            ApplyError::Policy(_)
+            | ApplyError::Conflict(_)
+            | ApplyError::TooManyPolicies { .. }
+            | ApplyError::ModelNotConfigured { .. }
+            | ApplyError::ValidationFailed { .. }
+            | ApplyError::BatchJobMismatch { .. }
+            | ApplyError::InvalidResponse { .. } => false,
+        }
+    }
 }
 
 impl std::fmt::Display for ApplyError {
@@ -296,10 +583,32 @@ impl std::fmt::Display for ApplyError {
         match self {
             ApplyError::Policy(err) => write!(f, "Policy error: {err}"),
             ApplyError::Claudius(err) => write!(f, "LLM communication error: {err}"),
-            ApplyError::Conflict(conflict) => write!(f, "Policy conflict: {conflict:?}\nSuggestion: Review your policies for conflicting rules and adjust their conflict resolution strategies"),
+            ApplyError::Conflict(conflict) => write!(f, "Policy conflict: {conflict}\nSuggestion: Review your policies for conflicting rules and adjust their conflict resolution strategies"),
             ApplyError::TooManyIterations { attempts, last_error } => {
                 write!(f, "Failed to apply policies after {attempts} attempts\nLast error: {last_error}\nSuggestion: Simplify your policies or check for contradictory rules")
             }
+            ApplyError::TooManyPolicies { policies, max } => {
+                write!(f, "Refusing to send {policies} policies in one request; the limit is {max}\nSuggestion: Split the policies across several managers and merge their reports, prune them with preconditions, or apply in two phases with a first pass that picks the relevant policies")
+            }
+            ApplyError::ModelNotConfigured { model, allowed } if model.trim().is_empty() => {
+                write!(f, "No model configured\nSuggestion: Set the model on the template or with Manager::with_model")?;
+                if !allowed.is_empty() {
+                    write!(f, "; allowed models start with {}", allowed.join(", "))?;
+                }
+                Ok(())
+            }
+            ApplyError::ModelNotConfigured { model, allowed } => {
+                write!(f, "Model {model:?} is not allowed\nSuggestion: Use a model starting with {}", allowed.join(", "))
+            }
+            ApplyError::TimedOut { after_ms } => {
+                write!(f, "Gave up applying policies after {after_ms} ms\nSuggestion: Allow more time with Manager::with_chunk_timeout, or split the text into smaller chunks")
+            }
+            ApplyError::ValidationFailed { message } => {
+                write!(f, "Report failed validation: {message}\nSuggestion: Let the LLM correct its output with Manager::with_validation_retry, or revise the policies that produce such values")
+            }
+            ApplyError::BatchJobMismatch { path } => {
+                write!(f, "The batch job at {path} was submitted for other policies or texts\nSuggestion: Apply the texts the job was submitted for, or remove the job to submit a new batch")
+            }
             ApplyError::InvalidResponse { message, suggestion } => {
                 write!(f, "Invalid LLM response: {message}\nSuggestion: {suggestion}")
             }
@@ -323,8 +632,59 @@ impl From<PolicyError> for ApplyError {
     }
 }
 
+#[cfg(feature = "client")]
 impl<T: Into<claudius::Error>> From<T> for ApplyError {
     fn from(err: T) -> Self {
-        Self::Claudius(err.into())
+        Self::Claudius(ClientError::from(err.into()))
     }
 }
+
+///////////////////////////////////////// RepositoryError //////////////////////////////////////////
+
+/// Errors that can occur when storing or loading policies through a
+/// [`crate::PolicyRepository`]
+#[derive(Clone, Debug)]
+pub enum RepositoryError {
+    /// The storage backend failed
+    Backend {
+        /// Description of the failure, as reported by the backend.
+        message: String,
+    },
+    /// A stored policy could not be decoded
+    Corrupt {
+        /// Identifier of the stored policy.
+        id: String,
+        /// Why decoding failed.
+        message: String,
+    },
+    /// A stored policy's type differs from the type of the policies loaded before it
+    TypeMismatch {
+        /// Identifier of the stored policy.
+        id: String,
+    },
+}
+
+impl RepositoryError {
+    /// Create a Backend error from anything that describes the failure
+    pub fn backend(message: impl std::fmt::Display) -> Self {
+        Self::Backend {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositoryError::Backend { message } => write!(f, "Policy storage error: {message}"),
+            RepositoryError::Corrupt { id, message } => {
+                write!(f, "Stored policy '{id}' could not be decoded: {message}\nSuggestion: Re-save the policy or delete it from storage")
+            }
+            RepositoryError::TypeMismatch { id } => {
+                write!(f, "Stored policy '{id}' has a different type than the policies before it\nSuggestion: Keep policies of different types in separate repositories")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
@@ -0,0 +1,172 @@
+//! Persistence of failed applications for replay.
+//!
+//! An apply that exhausts its retries in production is the best regression test there is, but
+//! only if the input and what the LLM said survive the process.  A [`crate::Manager`] configured
+//! with a [`FailureStore`] records a [`Failure`] for every apply that ends in an error after the
+//! LLM was called, and `policyai-replay-failures` runs the recorded inputs again after the code
+//! or prompts change.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use claudius::MessageCreateParams;
+
+use crate::Policy;
+
+/// Everything needed to reproduce one failed apply.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Failure {
+    /// The text hash of the apply's [`crate::CacheKey`], as 32 hex digits.  Recording the same
+    /// input twice yields the same hash, so duplicates can be collapsed.
+    pub text_hash: String,
+    /// The input text.
+    pub text: String,
+    /// The manager's policies, in order.
+    pub policies: Vec<Policy>,
+    /// The first request sent to the LLM.
+    pub request: MessageCreateParams,
+    /// The content of each response, in order.
+    pub attempts: Vec<serde_json::Value>,
+    /// The error the apply returned.
+    pub error: String,
+}
+
+impl Failure {
+    /// A template for applying the policies again with the original model and token limit.
+    pub fn template(&self) -> MessageCreateParams {
+        MessageCreateParams {
+            model: self.request.model.clone(),
+            max_tokens: self.request.max_tokens,
+            ..Default::default()
+        }
+    }
+}
+
+/// A sink for failed applies.
+///
+/// Implementations must be safe to share between the clones of a [`crate::Manager`].
+pub trait FailureStore: std::fmt::Debug + Send + Sync {
+    /// Persist `failure`.
+    ///
+    /// The manager returns the apply's error whether or not this succeeds.
+    fn record(&self, failure: Failure) -> std::io::Result<()>;
+}
+
+/// A [`FailureStore`] that appends one JSON line per failure to a file.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use policyai::{JsonlFailureStore, Manager};
+///
+/// let store = Arc::new(JsonlFailureStore::new("failures.jsonl"));
+/// let manager = Manager::default().with_failure_store(store);
+/// ```
+#[derive(Debug)]
+pub struct JsonlFailureStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonlFailureStore {
+    /// Append failures to the file at `path`, creating it on the first failure.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The file failures are appended to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read every failure recorded in the file at `path`, skipping blank lines.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Vec<Failure>> {
+        let mut failures = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            failures.push(serde_json::from_str(&line)?);
+        }
+        Ok(failures)
+    }
+}
+
+impl FailureStore for JsonlFailureStore {
+    fn record(&self, failure: Failure) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(&failure)?;
+        line.push('\n');
+        let _guard = self.lock.lock().unwrap();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Field, OnConflict, PolicyType};
+
+    fn failure(text: &str) -> Failure {
+        Failure {
+            text_hash: format!("{:032x}", text.len()),
+            text: text.to_string(),
+            policies: vec![Policy {
+                r#type: PolicyType {
+                    name: "T".to_string(),
+                    fields: vec![Field::Bool {
+                        name: "urgent".to_string(),
+                        default: Some(false),
+                        on_conflict: OnConflict::Default,
+                    }],
+                    groups: vec![],
+                },
+                prompt: "from the CEO".to_string(),
+                action: serde_json::json!({"urgent": true}),
+                precondition: None,
+                exact_match: None,
+                explanation: None,
+                priority: None,
+            }],
+            request: MessageCreateParams {
+                max_tokens: 1024,
+                model: claudius::Model::Custom("some-model".to_string()),
+                ..Default::default()
+            },
+            attempts: vec![serde_json::json!([{"type": "text", "text": "no"}])],
+            error: "too many iterations".to_string(),
+        }
+    }
+
+    #[test]
+    fn jsonl_store_appends_and_loads() {
+        let path =
+            std::env::temp_dir().join(format!("policyai-failures-{}.jsonl", uuid::Uuid::new_v4()));
+        let store = JsonlFailureStore::new(&path);
+        store.record(failure("first")).unwrap();
+        store.record(failure("second")).unwrap();
+        let loaded = JsonlFailureStore::load(store.path()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let texts = loaded.iter().map(|f| f.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["first", "second"]);
+        assert_eq!(loaded[1].policies[0].prompt, "from the CEO");
+        assert_eq!(loaded[1].attempts, failure("second").attempts);
+        assert_eq!(loaded[0].template().max_tokens, 1024);
+        assert_eq!(
+            loaded[0].template().model,
+            claudius::Model::Custom("some-model".to_string())
+        );
+        assert!(loaded[0].template().messages.is_empty());
+    }
+}
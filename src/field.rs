@@ -10,7 +10,7 @@ use crate::{t64, OnConflict};
 ///
 /// Fields define the structure of data that policies work with. Each field has:
 /// - A name that identifies it
-/// - A type (bool, number, string, string enum, or string array)
+/// - A type (bool, number, string, string enum, string array, or datetime)
 /// - An optional default value
 /// - A conflict resolution strategy for when multiple policies set the same field
 ///
@@ -58,6 +58,9 @@ pub enum Field {
         default: Option<String>,
         /// Strategy for resolving conflicts when multiple policies set this field.
         on_conflict: OnConflict,
+        /// Whether `values` are declared here or supplied when policies are applied.
+        #[serde(default, skip_serializing_if = "EnumSource::is_static")]
+        source: EnumSource,
     },
     /// An array of strings that policies can append to.
     #[serde(rename = "array")]
@@ -75,6 +78,20 @@ pub enum Field {
         /// Strategy for resolving conflicts when multiple policies set this field.
         on_conflict: OnConflict,
     },
+    /// An RFC 3339 timestamp, such as `2024-03-01T17:00:00Z`.
+    ///
+    /// Values are kept as the strings the policies reported; conflicts compare the instants
+    /// they name, so [`OnConflict::LargestValue`] keeps the latest and
+    /// [`OnConflict::SmallestValue`] the earliest.
+    #[serde(rename = "datetime")]
+    DateTime {
+        /// The name of this field.
+        name: String,
+        /// The default timestamp when no policy sets this field.
+        default: Option<String>,
+        /// Strategy for resolving conflicts when multiple policies set this field.
+        on_conflict: OnConflict,
+    },
 }
 
 impl Field {
@@ -101,8 +118,14 @@ impl Field {
                 values: _,
                 default: _,
                 on_conflict: _,
+                source: _,
             } => name,
             Self::StringArray { name } => name,
+            Self::DateTime {
+                name,
+                default: _,
+                on_conflict: _,
+            } => name,
         }
     }
 
@@ -132,8 +155,106 @@ impl Field {
                 values: _,
                 default,
                 on_conflict: _,
+                source: _,
             } => (*default).clone().into(),
             Self::StringArray { name: _ } => serde_json::json! {[]},
+            Self::DateTime {
+                name: _,
+                default,
+                on_conflict: _,
+            } => (*default).clone().into(),
+        }
+    }
+}
+
+/// Where a [`Field::StringEnum`] gets the values it allows.
+///
+/// Some enums, such as an assignee drawn from a team roster, differ per tenant.  A dynamic enum
+/// is written `[dynamic]` and declares no values; they are supplied when policies are applied,
+/// with [`crate::PolicyType::with_enum_values`] or `Manager::with_enum_values`.
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum EnumSource {
+    /// The values are declared with the field.
+    #[default]
+    #[serde(rename = "static")]
+    Static,
+    /// The values are supplied when policies are applied.
+    #[serde(rename = "dynamic")]
+    Dynamic,
+}
+
+impl EnumSource {
+    /// True for [`EnumSource::Static`].
+    pub fn is_static(&self) -> bool {
+        *self == Self::Static
+    }
+}
+
+/// Words the DSL reserves; a field named after one must be quoted.
+const KEYWORDS: &[&str] = &[
+    "type",
+    "bool",
+    "string",
+    "number",
+    "datetime",
+    "true",
+    "false",
+    "agreement",
+    "sticky",
+    "wins",
+    "last",
+    "highest",
+    "largest",
+    "smallest",
+    "earliest",
+    "latest",
+];
+
+pub(crate) fn is_identifier_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '_'
+}
+
+pub(crate) fn is_identifier_continue(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// True if `name` can be written without quotes:  it lexes as a single identifier and is not a
+/// keyword.
+pub(crate) fn is_bare_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_identifier_start)
+        && chars.all(is_identifier_continue)
+        && !KEYWORDS.contains(&name)
+}
+
+/// Displays a field name as the DSL spells it:  bare when it lexes as an identifier, quoted
+/// otherwise.
+pub(crate) struct FieldName<'a>(pub(crate) &'a str);
+
+impl std::fmt::Display for FieldName<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        if is_bare_identifier(self.0) {
+            write!(f, "{}", self.0)
+        } else {
+            write!(
+                f,
+                "\"{}\"",
+                self.0.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        }
+    }
+}
+
+/// Displays a strategy that picks a policy rather than a value, [`OnConflict::FirstWins`],
+/// [`OnConflict::LastWins`], or [`OnConflict::HighestPriority`], as the DSL spells it.
+struct PolicyWins(OnConflict);
+
+impl std::fmt::Display for PolicyWins {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self.0 {
+            OnConflict::FirstWins => write!(f, "first added wins"),
+            OnConflict::HighestPriority => write!(f, "highest priority wins"),
+            _ => write!(f, "last added wins"),
         }
     }
 }
@@ -147,19 +268,51 @@ impl std::fmt::Display for Field {
                 on_conflict,
             } => match on_conflict {
                 OnConflict::Default => match default {
-                    Some(true) => write!(f, "{name}: bool = true")?,
-                    Some(false) => write!(f, "{name}: bool = false")?,
-                    None => write!(f, "{name}: bool")?,
+                    Some(true) => write!(f, "{}: bool = true", FieldName(name))?,
+                    Some(false) => write!(f, "{}: bool = false", FieldName(name))?,
+                    None => write!(f, "{}: bool", FieldName(name))?,
                 },
-                OnConflict::Agreement => match default {
-                    Some(true) => write!(f, "{name}: bool @ agreement = true")?,
-                    Some(false) => write!(f, "{name}: bool @ agreement = false")?,
-                    None => write!(f, "{name}: bool @ agreement")?,
+                OnConflict::Agreement | OnConflict::Unknown => match default {
+                    Some(true) => write!(f, "{}: bool @ agreement = true", FieldName(name))?,
+                    Some(false) => write!(f, "{}: bool @ agreement = false", FieldName(name))?,
+                    None => write!(f, "{}: bool @ agreement", FieldName(name))?,
                 },
                 OnConflict::LargestValue => match default {
-                    Some(true) => write!(f, "{name}: bool @ sticky = true")?,
-                    Some(false) => write!(f, "{name}: bool @ sticky = false")?,
-                    None => write!(f, "{name}: bool @ sticky")?,
+                    Some(true) => write!(f, "{}: bool @ largest wins = true", FieldName(name))?,
+                    Some(false) => write!(f, "{}: bool @ largest wins = false", FieldName(name))?,
+                    None => write!(f, "{}: bool @ largest wins", FieldName(name))?,
+                },
+                OnConflict::SmallestValue => match default {
+                    Some(true) => write!(f, "{}: bool @ smallest wins = true", FieldName(name))?,
+                    Some(false) => write!(f, "{}: bool @ smallest wins = false", FieldName(name))?,
+                    None => write!(f, "{}: bool @ smallest wins", FieldName(name))?,
+                },
+                OnConflict::FirstWins | OnConflict::LastWins | OnConflict::HighestPriority => {
+                    match default {
+                        Some(true) => write!(
+                            f,
+                            "{}: bool @ {} = true",
+                            FieldName(name),
+                            PolicyWins(*on_conflict)
+                        )?,
+                        Some(false) => write!(
+                            f,
+                            "{}: bool @ {} = false",
+                            FieldName(name),
+                            PolicyWins(*on_conflict)
+                        )?,
+                        None => write!(
+                            f,
+                            "{}: bool @ {}",
+                            FieldName(name),
+                            PolicyWins(*on_conflict)
+                        )?,
+                    }
+                }
+                OnConflict::Sticky => match default {
+                    Some(true) => write!(f, "{}: bool @ sticky = true", FieldName(name))?,
+                    Some(false) => write!(f, "{}: bool @ sticky = false", FieldName(name))?,
+                    None => write!(f, "{}: bool @ sticky", FieldName(name))?,
                 },
             },
             Self::String {
@@ -169,23 +322,49 @@ impl std::fmt::Display for Field {
             } => match on_conflict {
                 OnConflict::Default => {
                     if let Some(default) = default.as_ref() {
-                        write!(f, "{name}: string = {default:?}")?;
+                        write!(f, "{}: string = {default:?}", FieldName(name))?;
                     } else {
-                        write!(f, "{name}: string")?;
+                        write!(f, "{}: string", FieldName(name))?;
                     }
                 }
-                OnConflict::Agreement => {
+                OnConflict::Agreement | OnConflict::Unknown => {
                     if let Some(default) = default.as_ref() {
-                        write!(f, "{name}: string @ agreement = {default:?}")?;
+                        write!(f, "{}: string @ agreement = {default:?}", FieldName(name))?;
                     } else {
-                        write!(f, "{name}: string @ agreement")?;
+                        write!(f, "{}: string @ agreement", FieldName(name))?;
                     }
                 }
                 OnConflict::LargestValue => {
                     if let Some(default) = default.as_ref() {
-                        write!(f, "{name}: string @ last wins = {default:?}")?;
+                        write!(f, "{}: string @ last wins = {default:?}", FieldName(name))?;
+                    } else {
+                        write!(f, "{}: string @ last wins", FieldName(name))?;
+                    }
+                }
+                OnConflict::SmallestValue => {
+                    if let Some(default) = default.as_ref() {
+                        write!(
+                            f,
+                            "{}: string @ smallest wins = {default:?}",
+                            FieldName(name)
+                        )?;
+                    } else {
+                        write!(f, "{}: string @ smallest wins", FieldName(name))?;
+                    }
+                }
+                OnConflict::FirstWins | OnConflict::LastWins | OnConflict::HighestPriority => {
+                    let strategy = PolicyWins(*on_conflict);
+                    if let Some(default) = default.as_ref() {
+                        write!(f, "{}: string @ {strategy} = {default:?}", FieldName(name))?;
+                    } else {
+                        write!(f, "{}: string @ {strategy}", FieldName(name))?;
+                    }
+                }
+                OnConflict::Sticky => {
+                    if let Some(default) = default.as_ref() {
+                        write!(f, "{}: string @ sticky = {default:?}", FieldName(name))?;
                     } else {
-                        write!(f, "{name}: string @ last wins")?;
+                        write!(f, "{}: string @ sticky", FieldName(name))?;
                     }
                 }
             },
@@ -194,38 +373,80 @@ impl std::fmt::Display for Field {
                 values,
                 default,
                 on_conflict,
+                source,
             } => {
-                let values = values
-                    .iter()
-                    .map(|v| format!("{v:?}"))
-                    .collect::<Vec<_>>()
-                    .join(", ");
+                let values = match source {
+                    EnumSource::Static => values
+                        .iter()
+                        .map(|v| format!("{v:?}"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    EnumSource::Dynamic => "dynamic".to_string(),
+                };
                 match on_conflict {
                     OnConflict::Default => {
                         if let Some(default) = default.as_ref() {
-                            write!(f, "{name}: [{values}] = {default:?}")?;
+                            write!(f, "{}: [{values}] = {default:?}", FieldName(name))?;
                         } else {
-                            write!(f, "{name}: [{values}]")?;
+                            write!(f, "{}: [{values}]", FieldName(name))?;
                         }
                     }
-                    OnConflict::Agreement => {
+                    OnConflict::Agreement | OnConflict::Unknown => {
                         if let Some(default) = default.as_ref() {
-                            write!(f, "{name}: [{values}] @ agreement = {default:?}")?;
+                            write!(
+                                f,
+                                "{}: [{values}] @ agreement = {default:?}",
+                                FieldName(name)
+                            )?;
                         } else {
-                            write!(f, "{name}: [{values}] @ agreement")?;
+                            write!(f, "{}: [{values}] @ agreement", FieldName(name))?;
                         }
                     }
                     OnConflict::LargestValue => {
                         if let Some(default) = default.as_ref() {
-                            write!(f, "{name}: [{values}] @ highest wins = {default:?}")?;
+                            write!(
+                                f,
+                                "{}: [{values}] @ highest wins = {default:?}",
+                                FieldName(name)
+                            )?;
+                        } else {
+                            write!(f, "{}: [{values}] @ highest wins", FieldName(name))?;
+                        }
+                    }
+                    OnConflict::SmallestValue => {
+                        if let Some(default) = default.as_ref() {
+                            write!(
+                                f,
+                                "{}: [{values}] @ smallest wins = {default:?}",
+                                FieldName(name)
+                            )?;
+                        } else {
+                            write!(f, "{}: [{values}] @ smallest wins", FieldName(name))?;
+                        }
+                    }
+                    OnConflict::FirstWins | OnConflict::LastWins | OnConflict::HighestPriority => {
+                        let strategy = PolicyWins(*on_conflict);
+                        if let Some(default) = default.as_ref() {
+                            write!(
+                                f,
+                                "{}: [{values}] @ {strategy} = {default:?}",
+                                FieldName(name)
+                            )?;
                         } else {
-                            write!(f, "{name}: [{values}] @ highest wins")?;
+                            write!(f, "{}: [{values}] @ {strategy}", FieldName(name))?;
+                        }
+                    }
+                    OnConflict::Sticky => {
+                        if let Some(default) = default.as_ref() {
+                            write!(f, "{}: [{values}] @ sticky = {default:?}", FieldName(name))?;
+                        } else {
+                            write!(f, "{}: [{values}] @ sticky", FieldName(name))?;
                         }
                     }
                 }
             }
             Self::StringArray { name } => {
-                write!(f, "{name}: [string]")?;
+                write!(f, "{}: [string]", FieldName(name))?;
             }
             Self::Number {
                 name,
@@ -234,26 +455,78 @@ impl std::fmt::Display for Field {
             } => match on_conflict {
                 OnConflict::Default => {
                     if let Some(default) = default.as_ref() {
-                        write!(f, "{name}: number = {}", default.0)?;
+                        write!(f, "{}: number = {}", FieldName(name), default.0)?;
                     } else {
-                        write!(f, "{name}: number")?;
+                        write!(f, "{}: number", FieldName(name))?;
                     }
                 }
-                OnConflict::Agreement => {
+                OnConflict::Agreement | OnConflict::Unknown => {
                     if let Some(default) = default.as_ref() {
-                        write!(f, "{name}: number @ agreement = {}", default.0)?;
+                        write!(f, "{}: number @ agreement = {}", FieldName(name), default.0)?;
                     } else {
-                        write!(f, "{name}: number @ agreement")?;
+                        write!(f, "{}: number @ agreement", FieldName(name))?;
                     }
                 }
                 OnConflict::LargestValue => {
                     if let Some(default) = default.as_ref() {
-                        write!(f, "{name}: number @ last wins = {}", default.0)?;
+                        write!(f, "{}: number @ last wins = {}", FieldName(name), default.0)?;
+                    } else {
+                        write!(f, "{}: number @ last wins", FieldName(name))?;
+                    }
+                }
+                OnConflict::SmallestValue => {
+                    if let Some(default) = default.as_ref() {
+                        write!(
+                            f,
+                            "{}: number @ smallest wins = {}",
+                            FieldName(name),
+                            default.0
+                        )?;
+                    } else {
+                        write!(f, "{}: number @ smallest wins", FieldName(name))?;
+                    }
+                }
+                OnConflict::FirstWins | OnConflict::LastWins | OnConflict::HighestPriority => {
+                    let strategy = PolicyWins(*on_conflict);
+                    if let Some(default) = default.as_ref() {
+                        write!(
+                            f,
+                            "{}: number @ {strategy} = {}",
+                            FieldName(name),
+                            default.0
+                        )?;
                     } else {
-                        write!(f, "{name}: number @ last wins")?;
+                        write!(f, "{}: number @ {strategy}", FieldName(name))?;
+                    }
+                }
+                OnConflict::Sticky => {
+                    if let Some(default) = default.as_ref() {
+                        write!(f, "{}: number @ sticky = {}", FieldName(name), default.0)?;
+                    } else {
+                        write!(f, "{}: number @ sticky", FieldName(name))?;
                     }
                 }
             },
+            Self::DateTime {
+                name,
+                default,
+                on_conflict,
+            } => {
+                write!(f, "{}: datetime", FieldName(name))?;
+                match on_conflict {
+                    OnConflict::Default => {}
+                    OnConflict::Agreement | OnConflict::Unknown => write!(f, " @ agreement")?,
+                    OnConflict::LargestValue => write!(f, " @ latest wins")?,
+                    OnConflict::SmallestValue => write!(f, " @ earliest wins")?,
+                    OnConflict::FirstWins | OnConflict::LastWins | OnConflict::HighestPriority => {
+                        write!(f, " @ {}", PolicyWins(*on_conflict))?
+                    }
+                    OnConflict::Sticky => write!(f, " @ sticky")?,
+                }
+                if let Some(default) = default.as_ref() {
+                    write!(f, " = {default:?}")?;
+                }
+            }
         }
         Ok(())
     }
@@ -284,6 +557,7 @@ mod tests {
             values: vec!["low".to_string(), "high".to_string()],
             default: None,
             on_conflict: OnConflict::LargestValue,
+            source: EnumSource::Static,
         };
         assert_eq!(enum_field.name(), "priority");
 
@@ -328,6 +602,7 @@ mod tests {
             values: vec!["low".to_string(), "high".to_string()],
             default: Some("low".to_string()),
             on_conflict: OnConflict::LargestValue,
+            source: EnumSource::Static,
         };
         assert_eq!(enum_field.default_value(), serde_json::json!("low"));
 
@@ -372,6 +647,13 @@ mod tests {
             default: Some(false),
             on_conflict: OnConflict::LargestValue,
         };
+        assert_eq!(field.to_string(), "is_active: bool @ largest wins = false");
+
+        let field = Field::Bool {
+            name: "is_active".to_string(),
+            default: Some(false),
+            on_conflict: OnConflict::Sticky,
+        };
         assert_eq!(field.to_string(), "is_active: bool @ sticky = false");
     }
 
@@ -409,6 +691,7 @@ mod tests {
             values: vec!["low".to_string(), "medium".to_string(), "high".to_string()],
             default: Some("medium".to_string()),
             on_conflict: OnConflict::Default,
+            source: EnumSource::Static,
         };
         assert_eq!(
             field.to_string(),
@@ -420,11 +703,21 @@ mod tests {
             values: vec!["low".to_string(), "high".to_string()],
             default: None,
             on_conflict: OnConflict::LargestValue,
+            source: EnumSource::Static,
         };
         assert_eq!(
             field.to_string(),
             "priority: [\"low\", \"high\"] @ highest wins"
         );
+
+        let field = Field::StringEnum {
+            name: "assignee".to_string(),
+            values: vec![],
+            default: None,
+            on_conflict: OnConflict::Agreement,
+            source: EnumSource::Dynamic,
+        };
+        assert_eq!(field.to_string(), "assignee: [dynamic] @ agreement");
     }
 
     #[test]
@@ -452,6 +745,27 @@ mod tests {
         assert_eq!(field.to_string(), "score: number @ agreement");
     }
 
+    #[test]
+    fn field_display_datetime() {
+        let field = Field::DateTime {
+            name: "due".to_string(),
+            default: None,
+            on_conflict: OnConflict::LargestValue,
+        };
+        assert_eq!(field.to_string(), "due: datetime @ latest wins");
+        assert_eq!(field.default_value(), serde_json::json!(null));
+
+        let field = Field::DateTime {
+            name: "received".to_string(),
+            default: Some("2024-01-01T00:00:00Z".to_string()),
+            on_conflict: OnConflict::SmallestValue,
+        };
+        assert_eq!(
+            field.to_string(),
+            "received: datetime @ earliest wins = \"2024-01-01T00:00:00Z\""
+        );
+    }
+
     #[test]
     fn field_serialization() {
         let field = Field::Bool {
@@ -0,0 +1,127 @@
+//! Key ordering for structured output.
+//!
+//! This module defines how the keys of a report's output object are ordered, so that output
+//! serializes identically from run to run regardless of which policies happened to fire.
+
+/// Defines the order of keys in the object returned by [`crate::Report::value`].
+///
+/// # Orders
+///
+/// - `Declaration`: Fields appear in the order they are declared in the `PolicyType`
+/// - `Sorted`: Fields appear in lexicographic order by name
+/// - `Insertion`: Defaults first, then extracted values in the order they were reported
+///
+/// Keys that are not part of the declaration (for example, from a hand-built report) are placed
+/// after the declared fields in insertion order.
+///
+/// # Example
+///
+/// ```
+/// use policyai::{FieldOrder, Manager};
+///
+/// let manager = Manager::default().with_field_order(FieldOrder::Sorted);
+/// ```
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum FieldOrder {
+    /// Order fields as they are declared in the policy type
+    #[default]
+    #[serde(rename = "declaration")]
+    Declaration,
+    /// Order fields lexicographically by name
+    #[serde(rename = "sorted")]
+    Sorted,
+    /// Order fields as they were first written
+    #[serde(rename = "insertion")]
+    Insertion,
+}
+
+impl FieldOrder {
+    /// Reorder the keys of `value` according to this order.
+    ///
+    /// `declared` lists field names in declaration order.  Non-object values are returned as-is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::FieldOrder;
+    ///
+    /// let value = serde_json::json!({"b": 1, "c": 2, "a": 3});
+    /// let declared = vec!["c".to_string(), "a".to_string()];
+    /// let ordered = FieldOrder::Declaration.apply(value, &declared);
+    /// let keys: Vec<_> = ordered.as_object().unwrap().keys().cloned().collect();
+    /// assert_eq!(keys, vec!["c", "a", "b"]);
+    /// ```
+    pub fn apply(self, value: serde_json::Value, declared: &[String]) -> serde_json::Value {
+        let serde_json::Value::Object(mut obj) = value else {
+            return value;
+        };
+        let mut ordered = serde_json::Map::new();
+        match self {
+            FieldOrder::Insertion => return serde_json::Value::Object(obj),
+            FieldOrder::Declaration => {
+                for name in declared {
+                    if let Some(v) = obj.shift_remove(name) {
+                        ordered.insert(name.clone(), v);
+                    }
+                }
+                ordered.extend(obj);
+            }
+            FieldOrder::Sorted => {
+                let mut entries = obj.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+                ordered.extend(entries);
+            }
+        }
+        serde_json::Value::Object(ordered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(value: &serde_json::Value) -> Vec<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn field_order_sorted() {
+        let value = serde_json::json!({"b": 1, "c": 2, "a": 3});
+        assert_eq!(
+            keys(&FieldOrder::Sorted.apply(value, &[])),
+            vec!["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn field_order_insertion_is_identity() {
+        let value = serde_json::json!({"b": 1, "c": 2, "a": 3});
+        let declared = vec!["a".to_string()];
+        assert_eq!(
+            keys(&FieldOrder::Insertion.apply(value, &declared)),
+            vec!["b", "c", "a"]
+        );
+    }
+
+    #[test]
+    fn field_order_declaration_ignores_missing_fields() {
+        let value = serde_json::json!({"b": 1});
+        let declared = vec!["a".to_string(), "b".to_string()];
+        let ordered = FieldOrder::Declaration.apply(value, &declared);
+        assert_eq!(ordered, serde_json::json!({"b": 1}));
+    }
+
+    #[test]
+    fn field_order_non_object_passthrough() {
+        let value = serde_json::json!([1, 2, 3]);
+        assert_eq!(FieldOrder::Sorted.apply(value.clone(), &[]), value);
+    }
+
+    #[test]
+    fn field_order_serialization() {
+        let serialized = serde_json::to_string(&FieldOrder::Declaration).unwrap();
+        assert_eq!(serialized, "\"declaration\"");
+        let deserialized: FieldOrder = serde_json::from_str("\"sorted\"").unwrap();
+        assert_eq!(deserialized, FieldOrder::Sorted);
+    }
+}
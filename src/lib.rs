@@ -14,10 +14,31 @@
 //! - **Manager**: Coordinates the application of multiple policies to unstructured data
 //! - **Report**: The result of applying policies, including the structured output
 //!
+//! All of these, and the traits for plugging in caches, repositories, and logs, are in
+//! [`prelude`].
+//!
+//! # Bringing your own LLM
+//!
+//! [`Manager`] sends requests through an [`LlmProvider`], which `claudius::Anthropic` already
+//! is.  To use OpenAI, Bedrock, or a local model instead, implement the trait for a client that
+//! translates the request and builds its reply with [`tool_use_reply`]; retries, verification,
+//! caching, and usage accounting carry over unchanged.
+//!
+//! Code that cannot fit that shape can still use the masks, conflict resolution, and reports:
+//! add policies to a [`ReportBuilder`], send its rules and schema however it likes, and hand the
+//! answer to [`ReportBuilder::consume_ir`].
+//!
+//! # Features
+//!
+//! The LLM client, the policy type parser, data generation, analysis, and the command-line
+//! tools are behind the `client`, `parser`, `data`, `analysis`, and `binaries` features, all on
+//! by default.  With `default-features = false` the crate is left with policy types, masks,
+//! and reports, and depends only on serde, serde_json, and uuid.
+//!
 //! # Example
 //!
 //! ```
-//! use policyai::{PolicyType, Field, OnConflict, Manager};
+//! use policyai::{EnumSource, PolicyType, Field, OnConflict, Manager};
 //!
 //! let policy_type = PolicyType {
 //!     name: "EmailPolicy".to_string(),
@@ -32,42 +53,144 @@
 //!             values: vec!["low".to_string(), "high".to_string()],
 //!             default: None,
 //!             on_conflict: OnConflict::LargestValue,
+//!             source: EnumSource::Static,
 //!         },
 //!     ],
+//!     groups: vec![],
 //! };
 //! ```
 
 use std::cmp::Ordering;
 
 /// Data structures and utilities for test data
+#[cfg(feature = "data")]
 pub mod data;
 
+/// Evaluation of PolicyAI against a baseline
+#[cfg(feature = "data")]
+pub mod eval;
+
 /// Analysis tools for evaluation metrics
+#[cfg(feature = "analysis")]
 pub mod analysis;
 
+/// Bounded, rate-limited bulk processing
+#[cfg(feature = "client")]
+pub mod pipeline;
+
+/// Shadow comparisons of a candidate configuration on live traffic
+#[cfg(feature = "client")]
+pub mod canary;
+
+/// Semantic comparison of JSON values
+pub mod compare;
+
+/// Fixtures and a mock LLM client for testing code that consumes reports
+pub mod testing;
+
+/// The commonly used types and traits, for `use policyai::prelude::*`
+pub mod prelude;
+
+#[cfg(feature = "client")]
+mod batch;
+mod cache;
 mod errors;
+#[cfg(feature = "client")]
+mod failure;
 mod field;
+mod field_order;
+#[cfg(feature = "client")]
 mod manager;
 mod masks;
+mod metadata;
+mod naming;
 mod on_conflict;
+#[cfg(feature = "parser")]
 mod parser;
+#[cfg(feature = "client")]
+mod partial;
+#[cfg(feature = "client")]
+mod plan;
 mod policy;
 mod policy_type;
+mod precondition;
+mod pricing;
+#[cfg(feature = "client")]
+mod prompt_variant;
+#[cfg(feature = "client")]
+mod provider;
 mod report;
 mod report_builder;
+mod repository;
+#[cfg(feature = "client")]
+mod review;
+mod rule_index;
+mod template;
+mod translation;
+#[cfg(feature = "client")]
 mod usage;
-
-pub use errors::{ApplyError, Conflict, PolicyError};
-pub use field::Field;
-pub use manager::Manager;
-pub use masks::{BoolMask, NumberMask, StringArrayMask, StringEnumMask, StringMask};
-pub use on_conflict::OnConflict;
-pub use parser::ParseError;
-pub use policy::Policy;
-pub use policy_type::PolicyType;
-pub use report::Report;
+#[cfg(feature = "client")]
+mod usage_log;
+mod verification;
+
+#[cfg(feature = "client")]
+pub use batch::{
+    BatchClient, BatchJob, BatchJobRequest, BatchOutcome, BatchReport, BatchRequest, BatchResult,
+    BatchStatus,
+};
+pub use cache::{ApplyCache, CacheKey, MemoryCache};
+pub use errors::{ApplyError, ClientError, Conflict, PolicyError, RepositoryError};
+#[cfg(feature = "client")]
+pub use failure::{Failure, FailureStore, JsonlFailureStore};
+pub use field::{EnumSource, Field};
+pub use field_order::FieldOrder;
+#[cfg(feature = "client")]
+pub use manager::{
+    ChunkedReport, HealthCheck, HealthReport, Manager, OnDuplicate, PolicyStats, Prepared,
+};
+pub use masks::{
+    BoolMask, DateTimeMask, MatchMask, NumberMask, StringArrayMask, StringEnumMask, StringMask,
+};
+pub use metadata::Metadata;
+pub use naming::NamingPolicy;
+pub use on_conflict::{
+    ConflictKind, EnumFallback, OnConflict, OnNull, Resolution, ResolutionOutcome,
+};
+#[cfg(feature = "parser")]
+pub use parser::{ParseError, ParseWarning, SyntaxToken, TokenKind};
+#[cfg(feature = "client")]
+pub use partial::PartialJson;
+#[cfg(feature = "client")]
+pub use plan::{PlannedRequest, PromptTokens, RuleTokens};
+#[cfg(feature = "data")]
+pub use policy::PolicyCandidate;
+pub use policy::{ActionDivergence, Policy};
+pub use policy_type::{FieldGroup, PolicyType};
+#[cfg(feature = "client")]
+pub use policy_type::{FieldReference, InjectionFeedback};
+pub use precondition::Precondition;
+pub use pricing::{ModelPrice, Pricing};
+#[cfg(feature = "client")]
+pub use prompt_variant::PromptVariant;
+#[cfg(feature = "client")]
+pub use provider::{tool_use_reply, LlmProvider, MessageEvents};
+pub use report::{
+    RenderOptions, RenderedReport, Report, ReportSize, ReportSummary, SchemaViolation, SkipReason,
+    SkippedPolicy,
+};
 pub use report_builder::ReportBuilder;
+#[cfg(feature = "sqlite")]
+pub use repository::SqliteRepository;
+pub use repository::{MemoryRepository, PolicyRepository};
+#[cfg(feature = "client")]
+pub use review::{DirectoryReviewSink, ReviewSink, ReviewTask, WebhookReviewSink};
+pub use rule_index::RuleIndex;
+pub use translation::Translations;
+#[cfg(feature = "client")]
 pub use usage::Usage;
+#[cfg(feature = "client")]
+pub use usage_log::{JsonlUsageLog, UsageLog, UsageRecord};
+pub use verification::{Contradiction, Validator, Verification};
 
 //////////////////////////////////////////////// t64 ///////////////////////////////////////////////
 
@@ -111,42 +234,125 @@ impl From<t64> for serde_json::Value {
 
 //////////////////////////////////////////// Number Helpers ///////////////////////////////////////
 
-pub(crate) fn number_is_equal(lhs: &serde_json::Number, rhs: &serde_json::Number) -> bool {
-    if lhs.is_f64() && rhs.is_f64() {
-        lhs.as_f64() == rhs.as_f64()
-    } else if lhs.is_u64() && rhs.is_u64() {
-        lhs.as_u64() == rhs.as_u64()
-    } else if lhs.is_i64() && rhs.is_i64() {
-        lhs.as_i64() == rhs.as_i64()
-    } else {
-        // Compare across different number types by converting to f64
-        match (lhs.as_f64(), rhs.as_f64()) {
-            (Some(l), Some(r)) => l == r,
-            _ => false,
+/// How two JSON numbers of possibly different representations are compared.
+///
+/// A JSON number is held as an `i64`, a `u64`, or an `f64`.  Converting everything to `f64`
+/// is simple, but two integers above 2^53 can then compare equal, and a `u64` above
+/// `i64::MAX` loses its magnitude.  Every comparison the crate makes, including
+/// [`OnConflict::LargestValue`] resolution, is [`NumberComparison::Strict`].
+///
+/// # Example
+///
+/// ```
+/// use std::cmp::Ordering;
+///
+/// use policyai::NumberComparison;
+///
+/// let big = serde_json::Number::from(9_007_199_254_740_993u64);
+/// let near = serde_json::Number::from_f64(9_007_199_254_740_992.0).unwrap();
+/// assert!(!NumberComparison::Strict.is_equal(&big, &near));
+/// assert!(NumberComparison::Lossy.is_equal(&big, &near));
+/// assert_eq!(
+///     NumberComparison::Strict.compare(&(-1).into(), &u64::MAX.into()),
+///     Some(Ordering::Less)
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum NumberComparison {
+    /// Compare by exact value, whatever the representation: integers as integers, and an
+    /// integer against a float without rounding either.
+    #[default]
+    #[serde(rename = "strict")]
+    Strict,
+    /// Compare integers of the same signedness exactly, and anything else after converting
+    /// both sides to `f64`, as the crate did before it had [`NumberComparison::Strict`].
+    #[serde(rename = "lossy")]
+    Lossy,
+}
+
+impl NumberComparison {
+    /// How `lhs` orders against `rhs`, or `None` if they cannot be ordered.
+    pub fn compare(self, lhs: &serde_json::Number, rhs: &serde_json::Number) -> Option<Ordering> {
+        match self {
+            Self::Strict => strict_compare(lhs, rhs),
+            Self::Lossy => {
+                if let (Some(l), Some(r)) = (lhs.as_u64(), rhs.as_u64()) {
+                    Some(l.cmp(&r))
+                } else if let (Some(l), Some(r)) = (lhs.as_i64(), rhs.as_i64()) {
+                    Some(l.cmp(&r))
+                } else {
+                    lhs.as_f64()?.partial_cmp(&rhs.as_f64()?)
+                }
+            }
         }
     }
+
+    /// True if `lhs` and `rhs` are the same number.
+    pub fn is_equal(self, lhs: &serde_json::Number, rhs: &serde_json::Number) -> bool {
+        self.compare(lhs, rhs) == Some(Ordering::Equal)
+    }
+}
+
+/// The exact value of `n` if it is an integer.
+fn as_i128(n: &serde_json::Number) -> Option<i128> {
+    n.as_i64()
+        .map(i128::from)
+        .or_else(|| n.as_u64().map(i128::from))
 }
 
-pub(crate) fn number_less_than(lhs: &serde_json::Number, rhs: &serde_json::Number) -> bool {
-    if lhs.is_f64() && rhs.is_f64() {
-        lhs.as_f64() < rhs.as_f64()
-    } else if lhs.is_u64() && rhs.is_u64() {
-        lhs.as_u64() < rhs.as_u64()
-    } else if lhs.is_i64() && rhs.is_i64() {
-        lhs.as_i64() < rhs.as_i64()
-    } else {
-        // Compare across different number types by converting to f64
-        match (lhs.as_f64(), rhs.as_f64()) {
-            (Some(l), Some(r)) => l < r,
-            _ => false,
+/// Order the integer `i` against the float `f` without rounding either.
+fn compare_int_float(i: i128, f: f64) -> Option<Ordering> {
+    if f.is_nan() {
+        return None;
+    }
+    // Every i64 and u64 lies strictly between -2^64 and 2^64, both exact as f64.
+    const TWO_64: f64 = 18_446_744_073_709_551_616.0;
+    if f >= TWO_64 {
+        return Some(Ordering::Less);
+    }
+    if f <= -TWO_64 {
+        return Some(Ordering::Greater);
+    }
+    let floor = f.floor();
+    // |floor| < 2^64, so it converts to i128 exactly.
+    match i.cmp(&(floor as i128)) {
+        Ordering::Equal if f > floor => Some(Ordering::Less),
+        ordering => Some(ordering),
+    }
+}
+
+fn strict_compare(lhs: &serde_json::Number, rhs: &serde_json::Number) -> Option<Ordering> {
+    match (as_i128(lhs), as_i128(rhs)) {
+        (Some(l), Some(r)) => Some(l.cmp(&r)),
+        (Some(l), None) => compare_int_float(l, rhs.as_f64()?),
+        (None, Some(r)) => compare_int_float(r, lhs.as_f64()?).map(Ordering::reverse),
+        (None, None) => lhs.as_f64()?.partial_cmp(&rhs.as_f64()?),
+    }
+}
+
+pub(crate) fn number_is_equal(lhs: &serde_json::Number, rhs: &serde_json::Number) -> bool {
+    NumberComparison::Strict.is_equal(lhs, rhs)
+}
+
+/// The sum of two numbers, exact for integers and in floating point otherwise.  `None` if the
+/// sum is not a finite number.
+pub(crate) fn number_add(
+    lhs: &serde_json::Number,
+    rhs: &serde_json::Number,
+) -> Option<serde_json::Number> {
+    if let (Some(l), Some(r)) = (lhs.as_i64(), rhs.as_i64()) {
+        if let Some(sum) = l.checked_add(r) {
+            return Some(sum.into());
         }
     }
+    serde_json::Number::from_f64(lhs.as_f64()? + rhs.as_f64()?)
 }
 
 /////////////////////////////////////////////// tests //////////////////////////////////////////////
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "client")]
     use claudius::{Anthropic, MessageCreateParams};
 
     use super::*;
@@ -218,17 +424,109 @@ mod tests {
         assert!(!super::number_is_equal(&n1, &n2));
     }
 
+    #[test]
+    fn number_add() {
+        let sum = super::number_add(&5.into(), &(-7).into()).unwrap();
+        assert_eq!(sum.as_i64(), Some(-2));
+        let sum = super::number_add(&1.into(), &serde_json::Number::from_f64(0.5).unwrap());
+        assert_eq!(sum.unwrap().as_f64(), Some(1.5));
+    }
+
     #[test]
     fn number_less_than() {
+        let less = |a: &serde_json::Number, b: &serde_json::Number| {
+            NumberComparison::Strict.compare(a, b) == Some(Ordering::Less)
+        };
         let n1 = serde_json::Number::from(41);
         let n2 = serde_json::Number::from(42);
-        assert!(super::number_less_than(&n1, &n2));
-        assert!(!super::number_less_than(&n2, &n1));
+        assert!(less(&n1, &n2));
+        assert!(!less(&n2, &n1));
 
         let n1 = serde_json::Number::from_f64(3.24).unwrap();
         let n2 = serde_json::Number::from_f64(3.25).unwrap();
-        assert!(super::number_less_than(&n1, &n2));
-        assert!(!super::number_less_than(&n2, &n1));
+        assert!(less(&n1, &n2));
+        assert!(!less(&n2, &n1));
+    }
+
+    #[test]
+    fn strict_comparison_is_exact_near_two_to_the_fifty_third() {
+        let two_53 = serde_json::Number::from(1u64 << 53);
+        let above = serde_json::Number::from((1u64 << 53) + 1);
+        let float = serde_json::Number::from_f64((1u64 << 53) as f64).unwrap();
+        assert!(!super::number_is_equal(&two_53, &above));
+        assert!(super::number_is_equal(&two_53, &float));
+        assert_eq!(
+            NumberComparison::Strict.compare(&above, &float),
+            Some(Ordering::Greater)
+        );
+        assert!(NumberComparison::Lossy.is_equal(&above, &float));
+        let half = serde_json::Number::from_f64(-0.5).unwrap();
+        assert_eq!(
+            NumberComparison::Strict.compare(&half, &0.into()),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            NumberComparison::Strict.compare(&(-1).into(), &u64::MAX.into()),
+            Some(Ordering::Less)
+        );
+    }
+
+    mod number_properties {
+        use proptest::prelude::*;
+
+        use super::super::*;
+
+        fn number() -> impl Strategy<Value = serde_json::Number> {
+            prop_oneof![
+                any::<i64>().prop_map(serde_json::Number::from),
+                any::<u64>().prop_map(serde_json::Number::from),
+                any::<f64>().prop_filter_map("finite", serde_json::Number::from_f64),
+                // Integers near 2^53, where f64 stops representing every integer.
+                (-4i64..4).prop_map(|d| serde_json::Number::from((1i64 << 53) + d)),
+                (-4i64..4).prop_map(|d| {
+                    serde_json::Number::from_f64(((1i64 << 53) + d) as f64).unwrap()
+                }),
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn strict_is_antisymmetric(a in number(), b in number()) {
+                let ab = NumberComparison::Strict.compare(&a, &b);
+                let ba = NumberComparison::Strict.compare(&b, &a);
+                prop_assert_eq!(ab, ba.map(Ordering::reverse));
+                prop_assert!(ab.is_some());
+            }
+
+            #[test]
+            fn strict_is_transitive(a in number(), b in number(), c in number()) {
+                let cmp = |x, y| NumberComparison::Strict.compare(x, y).unwrap();
+                if cmp(&a, &b).is_le() && cmp(&b, &c).is_le() {
+                    prop_assert!(cmp(&a, &c).is_le());
+                }
+            }
+
+            #[test]
+            fn strict_matches_integer_order(a in any::<i64>(), b in any::<u64>()) {
+                let expected = i128::from(a).cmp(&i128::from(b));
+                prop_assert_eq!(
+                    NumberComparison::Strict.compare(&a.into(), &b.into()),
+                    Some(expected)
+                );
+            }
+
+            #[test]
+            fn strict_agrees_with_lossy_on_floats(a in any::<f64>(), b in any::<f64>()) {
+                if let (Some(x), Some(y)) =
+                    (serde_json::Number::from_f64(a), serde_json::Number::from_f64(b))
+                {
+                    prop_assert_eq!(
+                        NumberComparison::Strict.compare(&x, &y),
+                        NumberComparison::Lossy.compare(&x, &y)
+                    );
+                }
+            }
+        }
     }
 
     #[test]
@@ -246,6 +544,7 @@ mod tests {
                     values: vec!["low".to_string(), "medium".to_string(), "high".to_string()],
                     default: None,
                     on_conflict: OnConflict::LargestValue,
+                    source: EnumSource::Static,
                 },
                 Field::StringEnum {
                     name: "category".to_string(),
@@ -256,6 +555,7 @@ mod tests {
                     ],
                     default: Some("other".to_string()),
                     on_conflict: OnConflict::Agreement,
+                    source: EnumSource::Static,
                 },
                 Field::String {
                     name: "template".to_string(),
@@ -266,6 +566,7 @@ mod tests {
                     name: "labels".to_string(),
                 },
             ],
+            groups: vec![],
         };
         assert_eq!(
             r#"type policyai::EmailPolicy {
@@ -279,6 +580,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "client")]
     #[tokio::test]
     async fn with_semantic_injection() {
         let client = Anthropic::new(None).unwrap();
@@ -295,6 +597,7 @@ mod tests {
                     values: vec!["low".to_string(), "medium".to_string(), "high".to_string()],
                     default: None,
                     on_conflict: OnConflict::LargestValue,
+                    source: EnumSource::Static,
                 },
                 Field::StringEnum {
                     name: "category".to_string(),
@@ -305,6 +608,7 @@ mod tests {
                     ],
                     default: Some("other".to_string()),
                     on_conflict: OnConflict::Agreement,
+                    source: EnumSource::Static,
                 },
                 Field::String {
                     name: "template".to_string(),
@@ -315,6 +619,7 @@ mod tests {
                     name: "labels".to_string(),
                 },
             ],
+            groups: vec![],
         };
         let policy = policy
             .with_semantic_injection(
@@ -331,6 +636,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "client")]
     #[tokio::test]
     async fn numeric_semantic_injection() {
         let client = Anthropic::new(None).unwrap();
@@ -341,6 +647,7 @@ mod tests {
                 default: None,
                 on_conflict: OnConflict::Default,
             }],
+            groups: vec![],
         };
         let policy = policy
             .with_semantic_injection(&client, "Assign weight to the email.")
@@ -352,6 +659,7 @@ mod tests {
         ));
     }
 
+    #[cfg(feature = "client")]
     #[tokio::test]
     async fn apply_readme_policy() {
         let client = Anthropic::new(None).unwrap();
@@ -368,6 +676,7 @@ mod tests {
                     values: vec!["low".to_string(), "medium".to_string(), "high".to_string()],
                     default: None,
                     on_conflict: OnConflict::LargestValue,
+                    source: EnumSource::Static,
                 },
                 Field::String {
                     name: "template".to_string(),
@@ -383,11 +692,13 @@ mod tests {
                     ],
                     default: Some("other".to_string()),
                     on_conflict: OnConflict::Agreement,
+                    source: EnumSource::Static,
                 },
                 Field::StringArray {
                     name: "labels".to_string(),
                 },
             ],
+            groups: vec![],
         };
         let policy = policy
             .with_semantic_injection(
@@ -1,11 +1,123 @@
-use std::time::Instant;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use claudius::{
-    push_or_merge_message, Anthropic, ContentBlock, MessageCreateParams, MessageParam,
-    MessageParamContent, MessageRole, SystemPrompt, TextBlock, ToolChoice, ToolResultBlock,
+    push_or_merge_message, CacheControlEphemeral, ContentBlock, Message, MessageCreateParams,
+    MessageParam, MessageParamContent, MessageRole, Model, SystemPrompt, TextBlock, ToolChoice,
+    ToolResultBlock,
 };
+use futures::StreamExt;
 
-use crate::{ApplyError, Policy, Report, ReportBuilder, Usage};
+use crate::batch::{BatchJob, BatchJobRequest, BatchOutcome, BatchRequest};
+use crate::partial::StreamedMessage;
+use crate::prompt_variant::PromptVariants;
+use crate::{
+    ApplyCache, ApplyError, BatchClient, BatchReport, CacheKey, EnumFallback, Failure,
+    FailureStore, FieldOrder, LlmProvider, Metadata, NamingPolicy, OnConflict, OnNull, PartialJson,
+    PlannedRequest, Policy, PolicyError, PolicyRepository, PromptTokens, PromptVariant, Report,
+    ReportBuilder, RepositoryError, ReviewSink, ReviewTask, RuleIndex, RuleTokens, SkipReason,
+    Translations, Usage, UsageLog, UsageRecord, Validator, Verification,
+};
+
+/// What [`Manager::add`] does with a policy whose prompt and action match one it already has.
+///
+/// A duplicate doubles the weight of its rule and, under [`OnConflict::Agreement`], invites the
+/// LLM to report the two copies differently.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum OnDuplicate {
+    /// Keep the duplicate, counting it in [`PolicyStats::duplicates_kept`].
+    #[default]
+    #[serde(rename = "keep")]
+    Keep,
+    /// Drop the duplicate, counting it in [`PolicyStats::duplicates_skipped`].
+    #[serde(rename = "skip")]
+    Skip,
+}
+
+/// Counts that describe the policies of a [`Manager`], from [`Manager::stats`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PolicyStats {
+    /// Policies the manager holds.
+    pub policies: usize,
+    /// Policies held that duplicate an earlier one.
+    pub duplicates_kept: usize,
+    /// Policies dropped by [`Manager::add`] as duplicates.
+    pub duplicates_skipped: usize,
+}
+
+/// The outcome of one of the checks made by [`Manager::health_check`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthCheck {
+    /// The check passed.
+    Passed,
+    /// The check failed.
+    Failed,
+    /// An earlier failure kept the check from being made.
+    NotReached,
+}
+
+/// What [`Manager::health_check`] learned about the LLM behind a manager.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct HealthReport {
+    /// The model the canary was sent to.
+    pub model: String,
+    /// Whether the provider accepted the credentials.
+    pub credentials: HealthCheck,
+    /// Whether the model is allowed by the manager and served by the provider.
+    pub model_available: HealthCheck,
+    /// Whether the model answered with a tool use the manager could turn into a report.
+    pub tool_use: HealthCheck,
+    /// Time taken by the canary apply, in milliseconds.
+    pub latency_ms: u64,
+    /// The error the canary apply failed with, if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    /// True if every check passed.
+    pub fn is_healthy(&self) -> bool {
+        self.credentials == HealthCheck::Passed
+            && self.model_available == HealthCheck::Passed
+            && self.tool_use == HealthCheck::Passed
+    }
+}
+
+/// The outcome of [`Manager::apply_chunked`]: one result per chunk, in the order of the chunks.
+///
+/// A chunk that fails or times out does not take the others down with it, so a long document
+/// still yields reports for every chunk that succeeded.
+#[derive(Debug, Default)]
+pub struct ChunkedReport {
+    /// The report, or the error, of each chunk.
+    pub chunks: Vec<Result<Report, ApplyError>>,
+}
+
+impl ChunkedReport {
+    /// The reports of the chunks that succeeded, with each chunk's zero-based index.
+    pub fn reports(&self) -> impl Iterator<Item = (usize, &Report)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, chunk)| chunk.as_ref().ok().map(|report| (index, report)))
+    }
+
+    /// The errors of the chunks that failed, with each chunk's zero-based index.
+    pub fn errors(&self) -> impl Iterator<Item = (usize, &ApplyError)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, chunk)| chunk.as_ref().err().map(|err| (index, err)))
+    }
+
+    /// True if every chunk produced a report.
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(Result::is_ok)
+    }
+}
 
 /// Manages a collection of policies and applies them to unstructured data.
 ///
@@ -27,6 +139,10 @@ use crate::{ApplyError, Policy, Report, ReportBuilder, Usage};
 /// #     r#type: policy_type,
 /// #     prompt: "Test policy".to_string(),
 /// #     action: serde_json::json!({}),
+/// #     precondition: None,
+/// #     exact_match: None,
+/// #     explanation: None,
+/// #     priority: None,
 /// # };
 /// manager.add(policy);
 ///
@@ -46,14 +162,565 @@ use crate::{ApplyError, Policy, Report, ReportBuilder, Usage};
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Manager {
     policies: Vec<Policy>,
+    settings: ApplySettings,
+    field_order: FieldOrder,
+    naming: NamingPolicy,
+    cache: Option<Arc<dyn ApplyCache>>,
+    tool_name: Option<String>,
+    tool_description: Option<String>,
+    compact_retry: bool,
+    enum_fallbacks: BTreeMap<String, Vec<EnumFallback>>,
+    null_handling: BTreeMap<String, OnNull>,
+    verification: Verification,
+    validator: Option<Validator>,
+    validation_retry: bool,
+    translations: Translations,
+    max_array_len: Option<usize>,
+    prompt_variants: PromptVariants,
+    non_match_reasons: bool,
+    strict_fields: bool,
+    unmasked_fields: BTreeSet<String>,
+    failure_store: Option<Arc<dyn FailureStore>>,
+    usage_log: Option<Arc<dyn UsageLog>>,
+    max_policies: Option<usize>,
+    model: Option<Model>,
+    allowed_models: Vec<String>,
+    review_sink: Option<Arc<dyn ReviewSink>>,
+    review_below_confidence: Option<f64>,
+    on_duplicate: OnDuplicate,
+    chunk_timeout: Option<Duration>,
+    min_input_chars: usize,
+    duplicates_kept: usize,
+    duplicates_skipped: usize,
+}
+
+/// The settings of a [`Manager`] that a single apply can change, such as with
+/// [`Manager::apply_with_overrides`], without touching the manager's own.
+#[derive(Clone, Debug, Default)]
+struct ApplySettings {
+    on_conflict_overrides: BTreeMap<String, OnConflict>,
+    variables: BTreeMap<String, String>,
+    enum_values: BTreeMap<String, Vec<String>>,
+    metadata: Metadata,
+    fields: Option<BTreeSet<String>>,
 }
 
 impl Manager {
+    /// The name of the structured-output tool unless [`Manager::with_tool_name`] overrides it.
+    pub const DEFAULT_TOOL_NAME: &'static str = "output_json";
+    /// The description of the structured-output tool unless overridden.
+    pub const DEFAULT_TOOL_DESCRIPTION: &'static str = "output JSON";
+    /// The most policies sent to the LLM in one request unless [`Manager::with_max_policies`]
+    /// overrides it.
+    pub const DEFAULT_MAX_POLICIES: usize = 500;
+
+    /// Set the key order of every report's output.  Defaults to declaration order.
+    pub fn with_field_order(mut self, field_order: FieldOrder) -> Self {
+        self.field_order = field_order;
+        self
+    }
+
+    /// Set the key casing of every report's output.  Defaults to the names as declared.
+    pub fn with_naming_policy(mut self, naming: NamingPolicy) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Set what [`Manager::add`] does with duplicate policies.  Defaults to
+    /// [`OnDuplicate::Keep`].  Applies to policies added after this call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Manager, OnDuplicate, Policy, PolicyType};
+    /// let policy = Policy {
+    ///     r#type: PolicyType::parse("type T { urgent: bool = false }").unwrap(),
+    ///     prompt: "If the email is from the CEO".to_string(),
+    ///     action: serde_json::json!({"urgent": true}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// let mut manager = Manager::default().with_on_duplicate(OnDuplicate::Skip);
+    /// manager.add(policy.clone());
+    /// manager.add(policy);
+    /// assert_eq!(manager.stats().policies, 1);
+    /// assert_eq!(manager.stats().duplicates_skipped, 1);
+    /// ```
+    pub fn with_on_duplicate(mut self, on_duplicate: OnDuplicate) -> Self {
+        self.on_duplicate = on_duplicate;
+        self
+    }
+
+    /// Give up on a chunk passed to [`Manager::apply_chunked`] once it has taken `timeout`,
+    /// retries included.  The chunk fails with [`ApplyError::TimedOut`] and the other chunks
+    /// carry on.  By default chunks are not timed out.
+    pub fn with_chunk_timeout(mut self, timeout: Duration) -> Self {
+        self.chunk_timeout = Some(timeout);
+        self
+    }
+
+    /// Send every request to `model`, whatever model the template carries.
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// Fail with [`ApplyError::ModelNotConfigured`] instead of sending a request to a model
+    /// whose identifier starts with none of `model_prefixes`.
+    ///
+    /// Guards against a template from elsewhere in a program silently moving an expensive
+    /// workload to a different model.  By default any model is allowed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use claudius::{KnownModel, MessageCreateParams, Model};
+    /// # use policyai::{ApplyError, Manager};
+    /// let manager = Manager::default().with_allowed_models(["claude-haiku-4-5"]);
+    /// let template = MessageCreateParams {
+    ///     model: Model::Known(KnownModel::ClaudeSonnet40),
+    ///     ..Default::default()
+    /// };
+    /// assert!(matches!(
+    ///     manager.prepare(template),
+    ///     Err(ApplyError::ModelNotConfigured { .. })
+    /// ));
+    /// ```
+    pub fn with_allowed_models(
+        mut self,
+        model_prefixes: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.allowed_models = model_prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Fail with [`ApplyError::TooManyPolicies`] instead of sending more than `max_policies`
+    /// policies to the LLM in one request.  Defaults to [`Manager::DEFAULT_MAX_POLICIES`].
+    ///
+    /// Policies pruned by their precondition or decided locally do not count.  Past a few
+    /// hundred rules the request is expensive and the LLM loses track of them, so the limit
+    /// guards against accidentally loading a whole repository into one manager.
+    pub fn with_max_policies(mut self, max_policies: usize) -> Self {
+        self.max_policies = Some(max_policies);
+        self
+    }
+
+    /// Record every apply that fails after calling the LLM in `store`, so the failures can be
+    /// replayed with `policyai-replay-failures`.
+    ///
+    /// The store is shared by every clone of this manager.  Joint applies are not recorded.
+    pub fn with_failure_store(mut self, store: Arc<dyn FailureStore>) -> Self {
+        self.failure_store = Some(store);
+        self
+    }
+
+    /// Write a [`UsageRecord`] to `log` for every apply that goes through [`Manager::apply`] or
+    /// one of its variants, cached or not, successful or not.
+    ///
+    /// The log is shared by every clone of this manager.
+    pub fn with_usage_log(mut self, log: Arc<dyn UsageLog>) -> Self {
+        self.usage_log = Some(log);
+        self
+    }
+
+    /// Submit every report with conflicts or errors to `sink` for manual review, along with
+    /// those below the threshold set by [`Manager::with_review_below_confidence`].
+    ///
+    /// The report is returned to the caller either way.  Reports served from the cache and
+    /// those of joint applies are not submitted.
+    pub fn with_review_sink(mut self, sink: Arc<dyn ReviewSink>) -> Self {
+        self.review_sink = Some(sink);
+        self
+    }
+
+    /// Ask the LLM for its confidence and submit reports below `min_confidence`, in `[0, 1]`,
+    /// to the review sink.
+    ///
+    /// Unlike [`Verification::with_min_confidence`], this does not ask the LLM to double-check.
+    pub fn with_review_below_confidence(mut self, min_confidence: f64) -> Self {
+        self.review_below_confidence = Some(min_confidence);
+        self
+    }
+
+    /// Set the name of the tool the LLM is forced to call.
+    ///
+    /// Gateways that route or observe by tool name can tell policy types apart when each
+    /// manager uses its own name; [`crate::PolicyType::tool_name`] derives one from the type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is empty, longer than 64 characters, or contains characters other than
+    /// ASCII letters, digits, `_`, and `-`.
+    pub fn with_tool_name(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        assert!(
+            !name.is_empty()
+                && name.len() <= 64
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+            "invalid tool name {name:?}"
+        );
+        self.tool_name = Some(name);
+        self
+    }
+
+    /// Set the description of the tool the LLM is forced to call.
+    pub fn with_tool_description(mut self, description: impl Into<String>) -> Self {
+        self.tool_description = Some(description.into());
+        self
+    }
+
+    /// Resolve conflicts on the named fields with the given strategies instead of the ones
+    /// their type declares.  [`Manager::apply_with_overrides`] adds to these for a single call,
+    /// such as a stricter agreement pass.
+    pub fn with_on_conflict_overrides(mut self, overrides: BTreeMap<String, OnConflict>) -> Self {
+        self.settings.on_conflict_overrides = overrides;
+        self
+    }
+
+    /// Settle disagreements on the named enum fields under [`OnConflict::Agreement`] with the
+    /// given fallback orders, so that a conflicted text still gets a usable value.  The
+    /// disagreement is still reported as a conflict.  See [`EnumFallback`].
+    pub fn with_enum_fallbacks(mut self, fallbacks: BTreeMap<String, Vec<EnumFallback>>) -> Self {
+        self.enum_fallbacks = fallbacks;
+        self
+    }
+
+    /// Read an explicit `null` the LLM outputs for each named field as the given [`OnNull`]
+    /// says, instead of failing the field's type check.
+    pub fn with_null_handling(mut self, handling: BTreeMap<String, OnNull>) -> Self {
+        self.null_handling = handling;
+        self
+    }
+
+    /// Fill the `{{placeholders}}` in policy prompts from `variables`.  These are the values
+    /// every call shares; a tenant's own are passed to [`Manager::apply_with_variables`].
+    ///
+    /// Applying fails with [`crate::PolicyError::MissingVariable`] if a prompt uses a placeholder
+    /// that has no value.
+    pub fn with_variables(mut self, variables: BTreeMap<String, String>) -> Self {
+        self.settings.variables = variables;
+        self
+    }
+
+    /// Supply the values of dynamic enum fields, keyed by field name.  Values that change from
+    /// call to call, such as a tenant's own roster, go to [`Manager::apply_with_enum_values`]
+    /// instead.
+    ///
+    /// Applying fails with [`crate::PolicyError::MissingEnumValues`] if the policies' type has a
+    /// dynamic enum without values, and with [`crate::PolicyError::ExpectedString`] if a
+    /// policy's action sets one to a value not among them.
+    pub fn with_enum_values(mut self, enum_values: BTreeMap<String, Vec<String>>) -> Self {
+        self.settings.enum_values = enum_values;
+        self
+    }
+
+    /// Give the LLM `metadata` about the text, in a `<metadata>` block ahead of it, so that
+    /// rules can refer to the sender, subject, or time sent.  [`Manager::apply_joint`] does not
+    /// send it, because its texts would all share it.  Set here, the metadata goes with every
+    /// text; [`Manager::apply_with_metadata`] sends a text's own.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.settings.metadata = metadata;
+        self
+    }
+
+    /// Send compact retries when the LLM's rule numbers disagree with its output.
+    ///
+    /// By default each retry appends the previous answer and its corrections to the growing
+    /// conversation.  A compact retry instead sends the original request plus only the most
+    /// recent answer and its corrections, and the original request carries a prompt-cache
+    /// breakpoint so the rules and text are read from cache on retries.  Compare
+    /// [`Usage::retry_claudius_usage`] with and without this setting to measure the savings.
+    pub fn with_compact_retry(mut self, compact_retry: bool) -> Self {
+        self.compact_retry = compact_retry;
+        self
+    }
+
+    /// Translate output values for consumers that need codes instead of labels.  Reports carry
+    /// the table and apply it in [`Report::value_with_translations`]; [`Report::value`] is
+    /// unaffected.
+    pub fn with_translations(mut self, translations: Translations) -> Self {
+        self.translations = translations;
+        self
+    }
+
+    /// Answer texts with fewer than `min_input_chars` non-whitespace characters without calling
+    /// the LLM.  Off by default; `1` catches empty and whitespace-only texts.
+    ///
+    /// Such a text gets a report that holds only the defaults, with every policy skipped for
+    /// [`SkipReason::EmptyInput`].  Policies decided locally are not evaluated either.
+    pub fn with_min_input_chars(mut self, min_input_chars: usize) -> Self {
+        self.min_input_chars = min_input_chars;
+        self
+    }
+
+    /// Keep at most `max_array_len` strings per rule in array fields, instead of
+    /// [`crate::StringArrayMask::DEFAULT_MAX_LEN`].  Truncation is recorded in the report as
+    /// [`crate::PolicyError::ArrayTruncated`].
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.max_array_len = Some(max_array_len);
+        self
+    }
+
+    /// Ask the LLM why every rule that did not match was passed over, and surface its answers
+    /// through [`Report::non_match_reasons`].
+    ///
+    /// A debugging aid for policy authors; leave it off in production, as the reasons add
+    /// output tokens to every request.
+    pub fn with_non_match_reasons(mut self, non_match_reasons: bool) -> Self {
+        self.non_match_reasons = non_match_reasons;
+        self
+    }
+
+    /// Ask the LLM again when it reports a rule as matched but leaves out some of the fields
+    /// the rule sets, as it does when it reports a rule as matched and sets none of them.
+    ///
+    /// The schema cannot require a rule's fields only when the rule matches, so by default a
+    /// matched rule that sets some of its fields is taken at its word and the rest keep their
+    /// defaults.  Gated fields are exempt, since the LLM is told to leave them out while their
+    /// group is closed.
+    pub fn with_strict_fields(mut self, strict_fields: bool) -> Self {
+        self.strict_fields = strict_fields;
+        self
+    }
+
+    /// Show the LLM the real names of `fields` instead of masks.  See
+    /// [`ReportBuilder::with_unmasked_fields`].
+    ///
+    /// Whether this helps depends on the field; compare with and without using
+    /// `policyai-evaluate-policies --unmasked-fields`.
+    pub fn with_unmasked_fields(
+        mut self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.unmasked_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Ask only for `fields`, leaving every other field of the policies' type out of the schema,
+    /// the masks, and the report.
+    ///
+    /// Each policy is cut down with [`Policy::with_only_fields`], and a policy that sets none of
+    /// `fields` is left out of the request as if its precondition had failed.  When a caller
+    /// needs a couple of fields of a large type, this saves the tokens of every other rule.
+    /// Applying fails with [`crate::PolicyError::UnknownField`] if the type does not declare
+    /// one of `fields`.  This narrows every call; [`Manager::apply_fields`] narrows one.
+    pub fn with_fields(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.settings.fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Word the instructions with `variant` when the request's model identifier starts with
+    /// `model_prefix`.
+    ///
+    /// The built-in instructions were tuned against Claude; models behind other providers can
+    /// need different phrasing.  When several prefixes match, the longest wins.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Manager, PromptVariant};
+    /// # use claudius::Model;
+    /// let variant = PromptVariant::default().with_system("Extract JSON according to the rules.");
+    /// let manager = Manager::default().with_prompt_variant("gpt-", variant.clone());
+    /// assert_eq!(manager.prompt_variant_for(&Model::Custom("gpt-4o".into())), variant);
+    /// assert_eq!(
+    ///     manager.prompt_variant_for(&Model::Custom("claude-sonnet-4-0".into())),
+    ///     PromptVariant::default()
+    /// );
+    /// ```
+    pub fn with_prompt_variant(
+        mut self,
+        model_prefix: impl Into<String>,
+        variant: PromptVariant,
+    ) -> Self {
+        self.prompt_variants.insert(model_prefix.into(), variant);
+        self
+    }
+
+    /// The instructions sent with requests to `model`.
+    pub fn prompt_variant_for(&self, model: &claudius::Model) -> PromptVariant {
+        self.prompt_variants
+            .select(&model.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Double-check reports that `verification` flags as suspicious.
+    ///
+    /// When a consistent report trips any of its heuristics, the LLM is shown its concerns and
+    /// asked to confirm or correct the output, once per call.  The extra round-trip is counted
+    /// in [`Usage::verifications`] and its tokens in [`Usage::retry_claudius_usage`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Contradiction, Manager, Verification};
+    /// let manager = Manager::default().with_verification(
+    ///     Verification::default()
+    ///         .with_contradiction(Contradiction::new("priority", "low", "urgent", true)),
+    /// );
+    /// ```
+    pub fn with_verification(mut self, verification: Verification) -> Self {
+        self.verification = verification;
+        self
+    }
+
+    /// Check the value of every report with `validator`, failing with
+    /// [`ApplyError::ValidationFailed`] instead of returning a report that breaks its rule.
+    ///
+    /// The validator runs after the rule numbers agree with the output and any
+    /// [`Verification`] has passed.  Joint applies are not validated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Manager, Validator};
+    /// let manager = Manager::default()
+    ///     .with_validator(Validator::new(|value| {
+    ///         if value["urgent"] == true && value["priority"] == "low" {
+    ///             Err("an urgent email cannot have low priority".to_string())
+    ///         } else {
+    ///             Ok(())
+    ///         }
+    ///     }))
+    ///     .with_validation_retry(true);
+    /// ```
+    pub fn with_validator(mut self, validator: Validator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// When the validator rejects a report, send its message back to the LLM and ask for a
+    /// corrected output once before failing, as is done when the rule numbers disagree with the
+    /// output.  Corrections are counted in [`Usage::validation_retries`].
+    pub fn with_validation_retry(mut self, validation_retry: bool) -> Self {
+        self.validation_retry = validation_retry;
+        self
+    }
+
+    /// The output of a report in which no rule matched, in this manager's field order and key
+    /// casing.
+    ///
+    /// This is what [`Manager::apply`] returns for text that no policy applies to, so it can be
+    /// shown without calling the LLM.  It is an empty object until a policy is added.
+    pub fn no_match_value(&self) -> serde_json::Value {
+        let Some(policy) = self.policies.first() else {
+            return serde_json::json!({});
+        };
+        let declared = policy
+            .r#type
+            .fields
+            .iter()
+            .map(|f| f.name().to_string())
+            .collect::<Vec<_>>();
+        self.naming.apply(
+            self.field_order
+                .apply(policy.r#type.no_match_value(), &declared),
+        )
+    }
+
+    /// The name of the tool the LLM is forced to call.
+    pub fn tool_name(&self) -> &str {
+        self.tool_name.as_deref().unwrap_or(Self::DEFAULT_TOOL_NAME)
+    }
+
+    /// Serve repeated inputs from `cache` and store every successful report in it.
+    ///
+    /// The cache is shared by every clone of this manager.
+    pub fn with_cache(mut self, cache: Arc<dyn ApplyCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The key under which the report for `text` is cached.
+    pub fn cache_key(&self, text: &str) -> CacheKey {
+        self.cache_key_for(text, &self.settings)
+    }
+
+    /// The key under which the report for `text`, applied with `apply_settings`, is cached.
+    fn cache_key_for(&self, text: &str, apply_settings: &ApplySettings) -> CacheKey {
+        let settings = serde_json::json!({
+            "field_order": self.field_order,
+            "naming": self.naming,
+            "on_conflict_overrides": apply_settings.on_conflict_overrides,
+            "enum_fallbacks": self.enum_fallbacks,
+            "null_handling": self.null_handling,
+            "variables": apply_settings.variables,
+            "enum_values": apply_settings.enum_values,
+            "metadata": apply_settings.metadata,
+            "verification": self.verification,
+            "translations": self.translations,
+            "max_array_len": self.max_array_len,
+            "prompt_variants": self.prompt_variants,
+            "non_match_reasons": self.non_match_reasons,
+            "strict_fields": self.strict_fields,
+            "unmasked_fields": self.unmasked_fields,
+            "fields": apply_settings.fields,
+            "min_input_chars": self.min_input_chars,
+        });
+        CacheKey::new(&self.policies, text).with_settings(&settings.to_string())
+    }
+
+    /// The hash of the policies, in order, and the settings that shape their reports.
+    ///
+    /// It is the policy half of [`Manager::cache_key`], written in hex, and is recorded on every
+    /// report as [`Report::policy_set_hash`], so that a change to the policies can be told apart
+    /// from a change in the model's behavior.
+    pub fn policy_set_hash(&self) -> String {
+        self.policy_set_hash_for(&self.settings)
+    }
+
+    /// The [`Manager::policy_set_hash`] of an apply with `settings`.
+    fn policy_set_hash_for(&self, settings: &ApplySettings) -> String {
+        format!("{:032x}", self.cache_key_for("", settings).policies)
+    }
+
+    /// Load every policy in `repository`, in the order of their identifiers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository fails, a policy cannot be decoded, or the policies do
+    /// not all share one type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::{Manager, MemoryRepository};
+    ///
+    /// let repository = MemoryRepository::default();
+    /// let manager = Manager::from_repository(&repository).unwrap();
+    /// ```
+    pub fn from_repository(repository: &dyn PolicyRepository) -> Result<Self, RepositoryError> {
+        let mut manager = Self::default();
+        for id in repository.list()? {
+            // A policy deleted between listing and loading is skipped rather than an error.
+            let Some(policy) = repository.get(&id)? else {
+                continue;
+            };
+            if manager
+                .policies
+                .last()
+                .is_some_and(|last| last.r#type != policy.r#type)
+            {
+                return Err(RepositoryError::TypeMismatch { id });
+            }
+            manager.add(policy);
+        }
+        Ok(manager)
+    }
+
     /// Add a policy to the manager.
     ///
+    /// A policy with the same prompt and action as one already added is a duplicate, handled
+    /// as set with [`Manager::with_on_duplicate`].
+    ///
     /// # Panics
     ///
     /// Panics if the policy type doesn't match existing policies in the manager.
@@ -61,9 +728,31 @@ impl Manager {
         if let Some(last) = self.policies.last() {
             assert_eq!(last.r#type, policy.r#type);
         }
+        if self
+            .policies
+            .iter()
+            .any(|p| p.prompt == policy.prompt && p.action == policy.action)
+        {
+            match self.on_duplicate {
+                OnDuplicate::Keep => self.duplicates_kept += 1,
+                OnDuplicate::Skip => {
+                    self.duplicates_skipped += 1;
+                    return;
+                }
+            }
+        }
         self.policies.push(policy);
     }
 
+    /// Counts of the policies added to this manager.
+    pub fn stats(&self) -> PolicyStats {
+        PolicyStats {
+            policies: self.policies.len(),
+            duplicates_kept: self.duplicates_kept,
+            duplicates_skipped: self.duplicates_skipped,
+        }
+    }
+
     /// Get the number of policies managed.
     #[cfg(test)]
     pub fn len(&self) -> usize {
@@ -76,11 +765,195 @@ impl Manager {
         self.policies.is_empty()
     }
 
+    /// Build the report the manager would build if the LLM matched the policies as `outputs`
+    /// says, without calling an LLM.
+    ///
+    /// `outputs[i]` is what the rule for the `i`th policy added outputs, keyed by field name
+    /// rather than by mask: an object for a rule that matched, which may be empty for an
+    /// audit-only policy, or `null` for one that did not.  Missing entries do not match.  An
+    /// enum field is set with the policy's value, or `true` as the LLM would set it; any other
+    /// value is passed through as is, to see how a wrong answer is reported.  Preconditions are
+    /// ignored, as there is no text, so every policy takes part; conflicts are then resolved
+    /// exactly as for a real answer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the policies cannot be turned into rules, if there are more outputs
+    /// than policies, or if an output sets a field its policy does not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Manager, Policy, PolicyType};
+    /// let policy_type = PolicyType::parse(r#"type T { priority: ["low", "high"] @ agreement = "low" }"#)?;
+    /// let policy = |prompt: &str, priority: &str| Policy {
+    ///     r#type: policy_type.clone(),
+    ///     prompt: prompt.to_string(),
+    ///     action: serde_json::json!({"priority": priority}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// let mut manager = Manager::default();
+    /// manager.add(policy("If the CEO wrote it", "high"));
+    /// manager.add(policy("If it is a newsletter", "low"));
+    ///
+    /// let report = manager.simulate(&[
+    ///     serde_json::json!({"priority": "high"}),
+    ///     serde_json::json!({"priority": "low"}),
+    /// ])?;
+    /// assert_eq!(report.conflicts().len(), 1);
+    ///
+    /// let report = manager.simulate(&[serde_json::json!({"priority": "high"})])?;
+    /// assert_eq!(report.value()["priority"], "high");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn simulate(&self, outputs: &[serde_json::Value]) -> Result<Report, ApplyError> {
+        if outputs.len() > self.policies.len() {
+            return Err(ApplyError::invalid_response(
+                format!(
+                    "{} outputs given for {} policies",
+                    outputs.len(),
+                    self.policies.len()
+                ),
+                "Give at most one output per policy, in the order the policies were added",
+            ));
+        }
+        let (builder, rule_policies) = self.builder_where(&self.settings, |_| true)?;
+        let mut ir = serde_json::Map::new();
+        let mut rule_numbers = vec![];
+        for (position, policy) in rule_policies.iter().enumerate() {
+            let Some(serde_json::Value::Object(output)) = outputs.get(*policy) else {
+                continue;
+            };
+            let rule = RuleIndex::from_position(position);
+            rule_numbers.push(rule.number());
+            let masks = builder.masks_for_rule(rule).unwrap_or_default();
+            let mut masks_by_field = masks
+                .iter()
+                .filter_map(|mask| Some((builder.field_for_mask(mask)?, mask)))
+                .collect::<BTreeMap<_, _>>();
+            if output.is_empty() {
+                // An audit-only rule matches by setting its match indicator.
+                for mask in masks {
+                    if builder.field_for_mask(mask).is_none() {
+                        ir.insert(mask.clone(), true.into());
+                    }
+                }
+            }
+            for (field, value) in output.iter() {
+                let Some(mask) = masks_by_field.remove(field.as_str()) else {
+                    return Err(ApplyError::invalid_response(
+                        format!("output {policy} sets {field:?}, which its policy does not"),
+                        "Set only the fields in the policy's action",
+                    ));
+                };
+                let flag = builder
+                    .string_enum_value(mask)
+                    .is_some_and(|expected| value.as_str() == Some(expected));
+                let value = if flag { true.into() } else { value.clone() };
+                ir.insert(mask.clone(), value);
+            }
+        }
+        ir.insert("__rule_numbers__".to_string(), rule_numbers.into());
+        ir.insert("__justification__".to_string(), "simulated".into());
+        builder.consume_ir(ir.into())
+    }
+
+    /// Apply one trivial policy to a tiny text, to check that `client` can serve this manager
+    /// before it takes traffic.
+    ///
+    /// The canary goes to the model this manager would use for `template`, with the manager's
+    /// allowed models, tool, and prompt variants, but none of its policies.  It bypasses the
+    /// cache, usage log, and failure store so that probes leave no trace.  Failures are reported
+    /// in the returned [`HealthReport`] rather than as errors, so a readiness probe can serve
+    /// it as is.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use claudius::{Anthropic, KnownModel, MessageCreateParams, Model};
+    /// # use policyai::Manager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// let manager = Manager::default().with_model(Model::Known(KnownModel::ClaudeSonnet40));
+    /// let health = manager
+    ///     .health_check(&client, MessageCreateParams::default())
+    ///     .await;
+    /// if !health.is_healthy() {
+    ///     eprintln!("not ready: {}", health.error.unwrap_or_default());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn health_check(
+        &self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+    ) -> HealthReport {
+        use HealthCheck::{Failed, NotReached, Passed};
+        let model = self.model.as_ref().unwrap_or(&template.model).to_string();
+        let mut canary = Manager {
+            tool_name: self.tool_name.clone(),
+            tool_description: self.tool_description.clone(),
+            prompt_variants: self.prompt_variants.clone(),
+            model: self.model.clone(),
+            allowed_models: self.allowed_models.clone(),
+            ..Default::default()
+        };
+        canary.add(Policy {
+            r#type: crate::PolicyType {
+                name: "policyai::HealthCheck".to_string(),
+                fields: vec![crate::Field::Bool {
+                    name: "ok".to_string(),
+                    default: Some(false),
+                    on_conflict: OnConflict::Default,
+                }],
+                groups: vec![],
+            },
+            prompt: "Always.".to_string(),
+            action: serde_json::json!({"ok": true}),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        });
+        let start_time = Instant::now();
+        let result = canary.apply(client, template, "ping", None).await;
+        let latency_ms = start_time.elapsed().as_millis() as u64;
+        let (credentials, model_available, tool_use) = match &result {
+            Ok(_) => (Passed, Passed, Passed),
+            Err(ApplyError::ModelNotConfigured { .. }) => (NotReached, Failed, NotReached),
+            Err(ApplyError::Claudius(err)) => match err.as_claudius() {
+                Some(err) if err.is_authentication() || err.is_permission() => {
+                    (Failed, NotReached, NotReached)
+                }
+                Some(err) if err.is_not_found() => (Passed, Failed, NotReached),
+                // The model exists, but would not take a request that forces a tool.
+                Some(err) if err.is_bad_request() => (Passed, Passed, Failed),
+                _ => (NotReached, NotReached, NotReached),
+            },
+            Err(_) => (Passed, Passed, Failed),
+        };
+        HealthReport {
+            model,
+            credentials,
+            model_available,
+            tool_use,
+            latency_ms,
+            error: result.err().map(|err| err.to_string()),
+        }
+    }
+
     /// Apply all managed policies to unstructured data.
     ///
     /// This method sends the unstructured data to an LLM along with all policies,
     /// and attempts to extract structured data according to the policy rules.
-    /// It will retry up to 3 times if the LLM's output is inconsistent.
+    /// It will retry up to 4 times, for 5 attempts in all, if the LLM's output is inconsistent.
+    /// When a cache is configured, a cached report for the same policies and text is returned
+    /// without calling the LLM.
     ///
     /// # Arguments
     ///
@@ -94,468 +967,4572 @@ impl Manager {
     /// A `Report` containing the structured output, or an `ApplyError` if processing fails.
     pub async fn apply(
         &mut self,
-        client: &Anthropic,
+        client: &dyn LlmProvider,
         template: MessageCreateParams,
         unstructured_data: &str,
-        mut usage: Option<&mut Usage>,
+        usage: Option<&mut Usage>,
     ) -> Result<Report, ApplyError> {
-        let start_time = Instant::now();
-        let (report, mut req) = self.request_for(template, unstructured_data).await?;
-        let max_attempts = 5;
-        let mut last_error = String::new();
-
-        // Initialize usage tracking if provided
-        if let Some(usage) = &mut usage {
-            **usage = Usage::new();
-        }
+        self.apply_logged(
+            client,
+            template,
+            unstructured_data,
+            &self.settings,
+            usage,
+            None,
+        )
+        .await
+    }
 
-        for attempt in 1..=max_attempts {
-            let resp = client.send(req.clone()).await?;
+    /// Apply all managed policies, streaming the LLM's output as it is generated.
+    ///
+    /// `on_partial` is called with a report built from the output received so far each time it
+    /// gains a complete value, so a UI can show `priority: high` while a long array is still
+    /// arriving.  Later output can still change a partial report's conflicts, and a retry starts
+    /// the stream over, so only the returned report is final.  Cached reports, and reports from
+    /// a provider that cannot stream, are returned without calling `on_partial`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::Manager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let mut manager = Manager::default();
+    /// let report = manager
+    ///     .apply_streaming(&client, MessageCreateParams::default(), "text", None, |partial| {
+    ///         println!("so far: {}", partial.value());
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_streaming(
+        &mut self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        usage: Option<&mut Usage>,
+        mut on_partial: impl FnMut(&Report) + Send,
+    ) -> Result<Report, ApplyError> {
+        self.apply_logged(
+            client,
+            template,
+            unstructured_data,
+            &self.settings,
+            usage,
+            Some(&mut on_partial),
+        )
+        .await
+    }
 
-            // Track usage if provided
-            if let Some(usage) = &mut usage {
-                usage.add_claudius_usage(resp.usage);
-                usage.increment_iterations();
-            }
-            if resp.content.len() != 1 {
-                return Err(ApplyError::invalid_response(
-                    format!(
-                        "Expected exactly 1 content block, got {}",
-                        resp.content.len()
-                    ),
-                    "Check that the LLM is configured correctly and the tool definition is valid",
-                ));
+    /// Apply all managed policies with per-field conflict strategies for this call only.
+    ///
+    /// `overrides` maps field names to the strategy to use in place of the one their type
+    /// declares, on top of any set with [`Manager::with_on_conflict_overrides`].  This makes it
+    /// possible to, say, run a stricter agreement pass without building a second manager.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::collections::BTreeMap;
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::{Manager, OnConflict};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let mut manager = Manager::default();
+    /// let strict = BTreeMap::from([("priority".to_string(), OnConflict::Agreement)]);
+    /// let report = manager
+    ///     .apply_with_overrides(&client, MessageCreateParams::default(), "text", strict, None)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_with_overrides(
+        &mut self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        overrides: BTreeMap<String, OnConflict>,
+        usage: Option<&mut Usage>,
+    ) -> Result<Report, ApplyError> {
+        let mut settings = self.settings.clone();
+        settings.on_conflict_overrides.extend(overrides);
+        self.apply_logged(client, template, unstructured_data, &settings, usage, None)
+            .await
+    }
+
+    /// Apply all managed policies with prompt placeholders filled from `variables`.
+    ///
+    /// `variables` are added to any set with [`Manager::with_variables`], replacing values of
+    /// the same name, so that one set of canonical rules can serve many tenants.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::collections::BTreeMap;
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::Manager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let mut manager = Manager::default();
+    /// let tenant = BTreeMap::from([("customer_name".to_string(), "Acme".to_string())]);
+    /// let report = manager
+    ///     .apply_with_variables(&client, MessageCreateParams::default(), "text", tenant, None)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_with_variables(
+        &mut self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        variables: BTreeMap<String, String>,
+        usage: Option<&mut Usage>,
+    ) -> Result<Report, ApplyError> {
+        let mut settings = self.settings.clone();
+        settings.variables.extend(variables);
+        self.apply_logged(client, template, unstructured_data, &settings, usage, None)
+            .await
+    }
+
+    /// Apply all managed policies with the values of dynamic enum fields supplied for this
+    /// call only.
+    ///
+    /// `enum_values` are added to any set with [`Manager::with_enum_values`], replacing the
+    /// values of the same field, so that one set of policies can draw an assignee from each
+    /// tenant's own roster.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::collections::BTreeMap;
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::Manager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let mut manager = Manager::default();
+    /// let roster = vec!["alice".to_string(), "bob".to_string()];
+    /// let tenant = BTreeMap::from([("assignee".to_string(), roster)]);
+    /// let report = manager
+    ///     .apply_with_enum_values(&client, MessageCreateParams::default(), "text", tenant, None)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_with_enum_values(
+        &mut self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        enum_values: BTreeMap<String, Vec<String>>,
+        usage: Option<&mut Usage>,
+    ) -> Result<Report, ApplyError> {
+        let mut settings = self.settings.clone();
+        settings.enum_values.extend(enum_values);
+        self.apply_logged(client, template, unstructured_data, &settings, usage, None)
+            .await
+    }
+
+    /// Apply all managed policies to a text with `metadata` about it, such as its sender and
+    /// when it was sent.
+    ///
+    /// `metadata` replaces any set with [`Manager::with_metadata`] for this call only.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::{Manager, Metadata};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let mut manager = Manager::default();
+    /// let metadata = Metadata::default()
+    ///     .with_sender("ceo@example.com")
+    ///     .with_timestamp("2025-03-14T17:45:00-07:00");
+    /// let report = manager
+    ///     .apply_with_metadata(&client, MessageCreateParams::default(), "text", metadata, None)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_with_metadata(
+        &mut self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        metadata: Metadata,
+        usage: Option<&mut Usage>,
+    ) -> Result<Report, ApplyError> {
+        let settings = ApplySettings {
+            metadata,
+            ..self.settings.clone()
+        };
+        self.apply_logged(client, template, unstructured_data, &settings, usage, None)
+            .await
+    }
+
+    /// Apply the managed policies for `fields` only, as if they had been set with
+    /// [`Manager::with_fields`] for this call.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::Manager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let mut manager = Manager::default();
+    /// let report = manager
+    ///     .apply_fields(
+    ///         &["priority", "labels"],
+    ///         &client,
+    ///         MessageCreateParams::default(),
+    ///         "Server down in us-east-1!",
+    ///         None,
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_fields(
+        &mut self,
+        fields: &[&str],
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        usage: Option<&mut Usage>,
+    ) -> Result<Report, ApplyError> {
+        let settings = ApplySettings {
+            fields: Some(fields.iter().map(|field| field.to_string()).collect()),
+            ..self.settings.clone()
+        };
+        self.apply_logged(client, template, unstructured_data, &settings, usage, None)
+            .await
+    }
+
+    async fn apply_logged(
+        &self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        settings: &ApplySettings,
+        usage: Option<&mut Usage>,
+        on_partial: Option<&mut (dyn FnMut(&Report) + Send)>,
+    ) -> Result<Report, ApplyError> {
+        let Some(log) = self.usage_log.clone() else {
+            return self
+                .apply_cached(
+                    client,
+                    template,
+                    unstructured_data,
+                    settings,
+                    usage,
+                    on_partial,
+                )
+                .await;
+        };
+        let model = self.model.as_ref().unwrap_or(&template.model).to_string();
+        let mut scratch = Usage::new();
+        let usage = usage.unwrap_or(&mut scratch);
+        let result = self
+            .apply_cached(
+                client,
+                template,
+                unstructured_data,
+                settings,
+                Some(usage),
+                on_partial,
+            )
+            .await;
+        let policy_set_hash = self.policy_set_hash_for(settings);
+        let record = UsageRecord::new(model, policy_set_hash, usage, result.is_ok());
+        // As with failures, the caller gets the apply's result even if it could not be logged.
+        let _ = log.record(record);
+        result
+    }
+
+    async fn apply_cached(
+        &self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        settings: &ApplySettings,
+        mut usage: Option<&mut Usage>,
+        on_partial: Option<&mut (dyn FnMut(&Report) + Send)>,
+    ) -> Result<Report, ApplyError> {
+        let Some(cache) = self.cache.clone() else {
+            return self
+                .apply_uncached(
+                    client,
+                    template,
+                    unstructured_data,
+                    settings,
+                    usage,
+                    on_partial,
+                )
+                .await;
+        };
+        let start_time = Instant::now();
+        let key = self.cache_key_for(unstructured_data, settings);
+        if let Some(report) = cache.get(&key) {
+            if let Some(usage) = &mut usage {
+                **usage = Usage::new();
+                usage.increment_cache_hits();
+                usage.set_wall_clock_time(start_time.elapsed());
             }
-            let ContentBlock::ToolUse(t) = &resp.content[0] else {
-                return Err(ApplyError::invalid_response(
-                    "Expected ToolUse content block",
-                    "The LLM should be using the output_json tool to provide structured output",
-                ));
+            return Ok(report);
+        }
+        let result = self
+            .apply_uncached(
+                client,
+                template,
+                unstructured_data,
+                settings,
+                usage.as_deref_mut(),
+                on_partial,
+            )
+            .await;
+        if let Some(usage) = &mut usage {
+            usage.increment_cache_misses();
+        }
+        if let Ok(report) = &result {
+            cache.put(key, report.clone());
+        }
+        result
+    }
+
+    /// Apply all managed policies to each chunk of a document in turn, isolating failures.
+    ///
+    /// Each chunk is applied as [`Manager::apply`] would apply it, under the timeout set with
+    /// [`Manager::with_chunk_timeout`].  An error in one chunk is recorded in its place in the
+    /// returned [`ChunkedReport`] instead of failing the document, so the caller gets partial
+    /// output plus per-chunk errors.  `usage` covers every chunk.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::Manager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let document = "first section\n\nsecond section";
+    /// let mut manager = Manager::default().with_chunk_timeout(Duration::from_secs(30));
+    /// let chunks = document.split("\n\n").collect::<Vec<_>>();
+    /// let chunked = manager
+    ///     .apply_chunked(&client, MessageCreateParams::default(), &chunks, None)
+    ///     .await;
+    /// for (index, err) in chunked.errors() {
+    ///     eprintln!("chunk {index}: {err}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_chunked(
+        &mut self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        chunks: &[&str],
+        mut usage: Option<&mut Usage>,
+    ) -> ChunkedReport {
+        if let Some(usage) = &mut usage {
+            **usage = Usage::new();
+        }
+        let timeout = self.chunk_timeout;
+        let mut chunked = ChunkedReport::default();
+        for chunk in chunks {
+            let start_time = Instant::now();
+            let mut chunk_usage = Usage::new();
+            let apply = self.apply(client, template.clone(), chunk, Some(&mut chunk_usage));
+            let result = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, apply).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        chunk_usage.set_wall_clock_time(start_time.elapsed());
+                        Err(ApplyError::TimedOut {
+                            after_ms: timeout.as_millis() as u64,
+                        })
+                    }
+                },
+                None => apply.await,
             };
-            let ir = t.input.clone();
-            let Some(reportedly_matched) = ir.get("__rule_numbers__").cloned() else {
+            if let Some(usage) = &mut usage {
+                usage.merge(&chunk_usage);
+            }
+            chunked.chunks.push(result);
+        }
+        chunked
+    }
+
+    /// Apply all managed policies to each of `texts` through the message batches API.
+    ///
+    /// One request per text is submitted as a single batch, which costs half as much as applying
+    /// the texts one at a time but may take up to a day to finish.  The requests share the
+    /// prefix of [`Manager::prepare`], so the rules are also read from the prompt cache.  Texts
+    /// answered by the cache or decided entirely locally are not submitted.
+    ///
+    /// The batch is recorded at `job_path` as soon as it is submitted.  Calling this again with
+    /// the same policies and texts waits on that batch rather than submitting another, so a run
+    /// that is interrupted can simply be restarted.  The job is left in place afterward; remove
+    /// it to submit the texts anew.  A job submitted for other policies or texts is an error.
+    ///
+    /// A batch gets one answer per request and cannot be corrected.  Texts whose answer fails
+    /// the checks of [`Manager::apply`], or that got no answer, are applied with `client`
+    /// instead, retries and all.  `usage` covers the whole call; the usage log is not written,
+    /// as batch prices are not those of [`crate::Pricing`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::{BatchClient, Manager};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let manager = Manager::default();
+    /// # let corpus: Vec<String> = vec![];
+    /// let batches = BatchClient::new(None)?;
+    /// let texts = corpus.iter().map(String::as_str).collect::<Vec<_>>();
+    /// let batched = manager
+    ///     .apply_via_batches(
+    ///         &client,
+    ///         &batches,
+    ///         MessageCreateParams::default(),
+    ///         &texts,
+    ///         "corpus.batch.json",
+    ///         None,
+    ///     )
+    ///     .await?;
+    /// for (index, err) in batched.errors() {
+    ///     eprintln!("text {index}: {err}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_via_batches(
+        &self,
+        client: &dyn LlmProvider,
+        batches: &BatchClient,
+        template: MessageCreateParams,
+        texts: &[&str],
+        job_path: impl AsRef<Path>,
+        mut usage: Option<&mut Usage>,
+    ) -> Result<BatchReport, ApplyError> {
+        let start_time = Instant::now();
+        if let Some(usage) = &mut usage {
+            **usage = Usage::new();
+        }
+        let job_path = job_path.as_ref();
+        let prepared = self.prepare(template)?;
+        let mut results = texts.iter().map(|_| None).collect::<Vec<_>>();
+        let mut requests = vec![];
+        let mut submitted = vec![];
+        for (index, text) in texts.iter().enumerate() {
+            let key = self.cache_key(text);
+            if let Some(report) = self.cache.as_ref().and_then(|cache| cache.get(&key)) {
+                if let Some(usage) = &mut usage {
+                    usage.increment_cache_hits();
+                }
+                results[index] = Some(Ok(report));
                 continue;
-            };
-            let Some(mut reportedly_matched): Option<Vec<usize>> =
-                serde_json::from_value(reportedly_matched).ok()
-            else {
+            }
+            let pruned = prepared.pruned_policies(text);
+            let (builder, params) = prepared.request_for(text);
+            if !prepared.asks_llm(&pruned) {
+                results[index] = Some(
+                    builder
+                        .consume_ir(serde_json::json!({"__rule_numbers__": []}))
+                        .map(|mut report| {
+                            report.record_pruned_policies(pruned);
+                            report
+                        }),
+                );
                 continue;
-            };
-            let report = report.clone().consume_ir(ir.clone())?;
-            let mut empirically_matched = report.rules_matched.clone();
-            empirically_matched.sort();
-            empirically_matched.dedup();
-            reportedly_matched.sort();
-            reportedly_matched.dedup();
-            if *empirically_matched == reportedly_matched {
-                // Set final wall clock time
-                if let Some(usage) = &mut usage {
-                    usage.set_wall_clock_time(start_time.elapsed());
+            }
+            let custom_id = format!("text-{index}");
+            submitted.push(BatchJobRequest {
+                custom_id: custom_id.clone(),
+                index,
+                text_hash: format!("{:032x}", key.text),
+            });
+            requests.push(BatchRequest { custom_id, params });
+        }
+        let policy_set_hash = self.policy_set_hash();
+        let io_error = |err: std::io::Error| {
+            ApplyError::from(claudius::Error::io(
+                format!("batch job {}", job_path.display()),
+                err,
+            ))
+        };
+        let job = match BatchJob::load(job_path).map_err(io_error)? {
+            Some(job) => {
+                if job.policy_set_hash != policy_set_hash || job.requests != submitted {
+                    return Err(ApplyError::BatchJobMismatch {
+                        path: job_path.display().to_string(),
+                    });
                 }
-                return Ok(report);
+                Some(job)
             }
-            let empirical_but_not_reported = empirically_matched
-                .iter()
-                .filter(|x| !reportedly_matched.iter().any(|y| **x == *y))
-                .cloned()
-                .collect::<Vec<_>>();
-            let reported_but_not_empirical = reportedly_matched
-                .iter()
-                .filter(|x| !empirically_matched.iter().any(|y| **x == *y))
-                .cloned()
-                .collect::<Vec<_>>();
-            let mut content =
-                "<instruction>The reported rule numbers do not match the fields that were output.  Re-evaluate your output to resolve the following inconsistencies.</instruction>"
-                    .to_string();
-            if !empirical_but_not_reported.is_empty() {
-                for rule_number in empirical_but_not_reported.into_iter() {
-                    if rule_number > 0 && rule_number <= report.masks_by_index.len() {
-                        for mask in report.masks_by_index[rule_number - 1].iter() {
-                            content += &format!("<inconsistency>{rule_number} was not present in rule numbers, but \"{mask}\" was set.<resolution>Unset \"{mask}\" if the context doesn't match or add {rule_number} to \"__rule_numbers__\" if the rule matches.</resolution></inconsistency>");
-                        }
-                    } else {
-                        content += &format!("<inconsistency>Rule number {rule_number} present in __rule_numbers__, but it doesn't exist in the reported rules.</inconsistency>");
+            None if requests.is_empty() => None,
+            None => {
+                let job = BatchJob {
+                    batch_id: batches.create(&requests).await?,
+                    policy_set_hash,
+                    requests: submitted,
+                };
+                job.save(job_path).map_err(io_error)?;
+                Some(job)
+            }
+        };
+        let mut batched = BatchReport {
+            batch_id: job.as_ref().map(|job| job.batch_id.clone()),
+            results: vec![],
+        };
+        if let Some(job) = job {
+            batches.wait(&job.batch_id).await?;
+            let mut answers = batches
+                .results(&job.batch_id)
+                .await?
+                .into_iter()
+                .map(|result| (result.custom_id, result.result))
+                .collect::<BTreeMap<_, _>>();
+            for request in job.requests {
+                let text = texts[request.index];
+                let pruned = prepared.pruned_policies(text);
+                let (builder, req) = prepared.request_for(text);
+                let discard = prepared.discarded_rules(&pruned);
+                let mut text_usage = Usage::new();
+                let answered = match answers.remove(&request.custom_id) {
+                    Some(BatchOutcome::Succeeded { message }) => {
+                        text_usage.increment_requests();
+                        text_usage.add_message_id(message.id.clone());
+                        text_usage.add_claudius_usage(message.usage);
+                        text_usage.increment_iterations();
+                        self.batch_answer(&builder, &message, &discard)
+                    }
+                    _ => None,
+                };
+                let result = match answered {
+                    Some(report) => Ok(report),
+                    None => {
+                        let mut retry_usage = Usage::new();
+                        let result = self
+                            .send(
+                                client,
+                                &builder,
+                                req,
+                                text,
+                                &discard,
+                                Some(&mut retry_usage),
+                                None,
+                            )
+                            .await;
+                        text_usage.merge(&retry_usage);
+                        result
                     }
+                };
+                let result = result.map(|mut report| {
+                    report.record_pruned_policies(pruned);
+                    report
+                });
+                if let (Ok(report), Some(cache)) = (&result, &self.cache) {
+                    cache.put(self.cache_key(text), report.clone());
                 }
-            }
-            if !reported_but_not_empirical.is_empty() {
-                content += "\n\nYou reported the following rules but did not output their JSON:\n";
-                for rule_number in reported_but_not_empirical.into_iter() {
-                    if rule_number > 0 && rule_number <= report.masks_by_index.len() {
-                        for mask in report.masks_by_index[rule_number - 1].iter() {
-                            content += &format!("<inconsistency>{rule_number} was present in rule numbers, but \"{mask}\" was not set.<resolution>Set \"{mask}\" if the context matches or remove {rule_number} from \"__rule_numbers__\" if the rule does not match.</resolution></inconsistency>");
-                        }
-                    } else {
-                        content += &format!("<inconsistency>Rule number {rule_number} present in __rule_numbers__, but it doesn't exist in the reported rules.</inconsistency>");
+                if let Some(usage) = &mut usage {
+                    usage.merge(&text_usage);
+                    if self.cache.is_some() {
+                        usage.increment_cache_misses();
                     }
                 }
+                results[request.index] = Some(result);
             }
-            last_error = format!("Attempt {attempt}/{max_attempts}: Rule mismatch - empirically matched {empirically_matched:?} but reportedly matched {reportedly_matched:?}");
-            push_or_merge_message(
-                &mut req.messages,
-                MessageParam {
-                    role: MessageRole::Assistant,
-                    content: MessageParamContent::Array(resp.content.clone()),
-                },
-            );
-            push_or_merge_message(
-                &mut req.messages,
-                MessageParam {
-                    role: MessageRole::User,
-                    content: MessageParamContent::Array(vec![ContentBlock::ToolResult(
-                        ToolResultBlock {
-                            tool_use_id: t.id.clone(),
-                            cache_control: None,
-                            is_error: Some(true),
-                            content: Some(
-                                format!("<error-message>{content}</error-message>").into(),
-                            ),
-                        },
-                    )]),
-                },
-            );
         }
-        // Set final wall clock time even on error
+        // Every text was either decided above or is in the job, which matches what would have
+        // been submitted, so none is missing.
+        batched.results = results.into_iter().flatten().collect();
         if let Some(usage) = &mut usage {
             usage.set_wall_clock_time(start_time.elapsed());
         }
-        Err(ApplyError::too_many_iterations(max_attempts, last_error))
+        Ok(batched)
     }
 
-    /// Prepare a request for LLM processing by building the necessary context.
-    ///
-    /// This method constructs the complete request that will be sent to the LLM,
-    /// including system prompts, policy rules, and the input text. It returns
-    /// both a ReportBuilder for processing the response and the configured request.
-    ///
-    /// # Arguments
-    ///
-    /// * `template` - Base message parameters to use for the LLM request
-    /// * `text` - The unstructured text data to analyze
-    ///
-    /// # Returns
-    ///
-    /// A tuple containing:
-    /// - `ReportBuilder`: Used to process the LLM's structured response
-    /// - `MessageCreateParams`: The complete request ready to send to the LLM
+    /// The report for a batch's answer to `builder`'s request, or `None` if the answer would
+    /// have been retried by [`Manager::send`].
+    fn batch_answer(
+        &self,
+        builder: &ReportBuilder,
+        message: &Message,
+        discard: &[RuleIndex],
+    ) -> Option<Report> {
+        let [ContentBlock::ToolUse(tool_use)] = message.content.as_slice() else {
+            return None;
+        };
+        let mut ir = tool_use.input.clone();
+        let mut reportedly_matched = ir
+            .get("__rule_numbers__")
+            .cloned()
+            .and_then(|numbers| serde_json::from_value::<Vec<usize>>(numbers).ok())?;
+        for rule in discard {
+            reportedly_matched.retain(|n| *n != rule.number());
+            if let (Some(masks), serde_json::Value::Object(obj)) =
+                (builder.masks_for_rule(*rule), &mut ir)
+            {
+                for mask in masks {
+                    obj.shift_remove(mask);
+                }
+            }
+        }
+        reportedly_matched.extend(builder.local_matches().iter().map(|rule| rule.number()));
+        let mut report = builder.clone().consume_ir(ir).ok()?;
+        report.message_ids = vec![message.id.clone()];
+        report.tokens_used = tokens_of(&message.usage);
+        report.requests = 1;
+        if rule_number_inconsistencies(builder, &report, reportedly_matched, self.strict_fields)
+            .is_some()
+            || !self.verification.concerns(&report).is_empty()
+            || self
+                .validator
+                .as_ref()
+                .is_some_and(|validator| validator.check(&report.value()).is_err())
+        {
+            return None;
+        }
+        Some(report)
+    }
+
+    /// Apply all managed policies to each of `texts` with a single LLM call.
     ///
-    /// # Errors
+    /// The rules are sent once and every text is tagged with its one-based id, so the fixed
+    /// cost of a request is shared by all texts.  This pays off for many small documents such as
+    /// chat messages.  Each text gets its own report, in the order of `texts`; all reports share
+    /// the message ids, and `usage` covers the whole call.
     ///
-    /// Returns `ApplyError` if policy addition to the report builder fails.
+    /// A policy whose precondition fails for some texts is still sent if it holds for any;
+    /// whatever the LLM outputs for it on the other texts is discarded and the policy is
+    /// recorded in those reports' `pruned_policies`.  The cache and [`Verification`] are not
+    /// used.
     ///
     /// # Example
     ///
     /// ```no_run
+    /// # use claudius::{Anthropic, MessageCreateParams};
     /// # use policyai::Manager;
-    /// # use claudius::MessageCreateParams;
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// let mut manager = Manager::default();
-    /// let template = MessageCreateParams::default();
-    /// let (report_builder, request) = manager.request_for(template, "analyze this text").await?;
+    /// # let client = Anthropic::new(None)?;
+    /// # let mut manager = Manager::default();
+    /// let messages = ["lunch at noon?", "the server is down!", "thanks"];
+    /// let reports = manager
+    ///     .apply_joint(&client, MessageCreateParams::default(), &messages, None)
+    ///     .await?;
+    /// assert_eq!(reports.len(), messages.len());
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn request_for(
+    pub async fn apply_joint(
         &mut self,
+        client: &dyn LlmProvider,
         template: MessageCreateParams,
-        text: &str,
-    ) -> Result<(ReportBuilder, MessageCreateParams), ApplyError> {
-        let mut report = ReportBuilder::default();
-        for policy in self.policies.iter() {
-            report.add_policy(policy)?;
+        texts: &[&str],
+        mut usage: Option<&mut Usage>,
+    ) -> Result<Vec<Report>, ApplyError> {
+        let start_time = Instant::now();
+        if let Some(usage) = &mut usage {
+            **usage = Usage::new();
         }
-        let mut req = template;
-        req.system = Some(SystemPrompt::from_blocks(vec![TextBlock {
-            text: include_str!("../prompts/manager.md").to_string(),
-            cache_control: None,
-            citations: None,
-        }]));
-
-        push_or_merge_message(
-            &mut req.messages,
-            MessageParam::new_with_string(
-                format!(
-                    "<default>Unless specified otherwise, output {}</default>",
-                    serde_json::to_string(report.default_return()).unwrap()
-                ),
-                MessageRole::User,
-            ),
-        );
-        for message in report.messages() {
-            push_or_merge_message(&mut req.messages, message)
+        let (builder, mut req, rule_policies) = self.joint_request(template, texts)?;
+        // The policies whose precondition fails for each text, by position.
+        let pruned = texts
+            .iter()
+            .map(|text| {
+                self.policies
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, policy)| {
+                        policy
+                            .precondition
+                            .as_ref()
+                            .is_some_and(|precondition| !precondition.matches(text))
+                    })
+                    .map(|(index, _)| index)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let finish = |reports: Vec<Report>, usage: &mut Option<&mut Usage>| {
+            if let Some(usage) = usage {
+                usage.set_wall_clock_time(start_time.elapsed());
+            }
+            reports
+                .into_iter()
+                .zip(pruned.iter())
+                .map(|(mut report, pruned)| {
+                    report.record_pruned_policies(pruned.clone());
+                    report
+                })
+                .collect::<Vec<_>>()
+        };
+        // The builder for each text, with the rules decided locally for it marked.
+        let builders = texts
+            .iter()
+            .map(|text| {
+                builder
+                    .clone()
+                    .with_local_matches(self.local_matches(&rule_policies, text))
+                    .with_input_hash(self.cache_key(text).to_string())
+            })
+            .collect::<Vec<_>>();
+        if texts.is_empty() || (!self.policies.is_empty() && builder.has_only_local_rules()) {
+            let mut reports = vec![];
+            for builder in builders {
+                reports.push(builder.consume_ir(serde_json::json!({"__rule_numbers__": []}))?);
+            }
+            return Ok(finish(reports, &mut usage));
         }
-        push_or_merge_message(
-            &mut req.messages,
-            MessageParam::new_with_string(format!("<text>{text}</text>"), MessageRole::User),
-        );
-        push_or_merge_message(
-            &mut req.messages,
-            MessageParam::new_with_string(
-                include_str!("../prompts/manager_suffix.md").to_string(),
-                MessageRole::User,
-            ),
-        );
-        req.tool_choice = Some(ToolChoice::tool("output_json"));
-        req.tools = Some(vec![claudius::ToolUnionParam::CustomTool(
-            claudius::ToolParam {
-                name: "output_json".to_string(),
-                description: Some("output JSON".to_string()),
-                input_schema: report.schema(),
-                cache_control: None,
-            },
-        )]);
-        Ok((report, req))
-    }
-}
-
-#[cfg(test)]
-mod tests {
+        let base = req.clone();
+        let max_attempts = 5;
+        let mut last_error = String::new();
+        let mut message_ids = vec![];
+        let mut tokens_used = 0;
+        for attempt in 1..=max_attempts {
+            if let Some(usage) = &mut usage {
+                usage.increment_requests();
+            }
+            let resp = match client.send(req.clone()).await {
+                Ok(resp) => resp,
+                Err(err) => return Err(err.into()),
+            };
+            message_ids.push(resp.id.clone());
+            tokens_used += tokens_of(&resp.usage);
+            if let Some(usage) = &mut usage {
+                usage.add_message_id(resp.id.clone());
+                usage.add_claudius_usage(resp.usage);
+                if attempt > 1 {
+                    usage.add_retry_claudius_usage(resp.usage);
+                }
+                usage.increment_iterations();
+            }
+            let [ContentBlock::ToolUse(t)] = resp.content.as_slice() else {
+                return Err(ApplyError::invalid_response(
+                    "Expected exactly 1 ToolUse content block",
+                    format!(
+                        "The LLM should be using the {} tool to provide structured output",
+                        self.tool_name()
+                    ),
+                ));
+            };
+            let mut reports = vec![];
+            let mut content = String::new();
+            let mut mismatches = vec![];
+            for (idx, (pruned, builder)) in pruned.iter().zip(builders.iter()).enumerate() {
+                let id = idx + 1;
+                let reportedly_matched = t
+                    .input
+                    .get(id.to_string())
+                    .and_then(|ir| ir.get("__rule_numbers__"))
+                    .cloned()
+                    .and_then(|numbers| serde_json::from_value::<Vec<usize>>(numbers).ok());
+                let Some(mut reportedly_matched) = reportedly_matched else {
+                    content += &format!("<inconsistency>The output for text {id} is missing or has no \"__rule_numbers__\".</inconsistency>");
+                    mismatches.push(format!("text {id} has no rule numbers"));
+                    continue;
+                };
+                let mut ir = t.input[id.to_string()].clone();
+                for (position, policy) in rule_policies.iter().enumerate() {
+                    if !pruned.contains(policy) {
+                        continue;
+                    }
+                    let rule = RuleIndex::from_position(position);
+                    reportedly_matched.retain(|n| *n != rule.number());
+                    if let (Some(masks), serde_json::Value::Object(obj)) =
+                        (builder.masks_for_rule(rule), &mut ir)
+                    {
+                        for mask in masks {
+                            obj.shift_remove(mask);
+                        }
+                    }
+                }
+                reportedly_matched.extend(builder.local_matches().iter().map(|rule| rule.number()));
+                let mut report = builder.clone().consume_ir(ir)?;
+                report.message_ids = message_ids.clone();
+                report.tokens_used = tokens_used;
+                report.requests = attempt;
+                if let Some((inconsistencies, mismatch)) = rule_number_inconsistencies(
+                    builder,
+                    &report,
+                    reportedly_matched,
+                    self.strict_fields,
+                ) {
+                    content += &format!("<text-output id=\"{id}\">{inconsistencies}</text-output>");
+                    mismatches.push(format!("text {id} {mismatch}"));
+                }
+                reports.push(report);
+            }
+            if mismatches.is_empty() {
+                return Ok(finish(reports, &mut usage));
+            }
+            last_error = format!(
+                "Attempt {attempt}/{max_attempts}: Rule mismatch - {}",
+                mismatches.join("; ")
+            );
+            if let (Some(usage), true) = (&mut usage, attempt < max_attempts) {
+                usage.increment_inconsistency_retries();
+            }
+            req = self.retry_request(
+                if self.compact_retry { &base } else { &req },
+                &resp.content,
+                &t.id,
+                format!("<error-message>{RULE_NUMBER_MISMATCH}{content}</error-message>"),
+            );
+        }
+        if let Some(usage) = &mut usage {
+            usage.set_wall_clock_time(start_time.elapsed());
+        }
+        Err(ApplyError::too_many_iterations(max_attempts, last_error))
+    }
+
+    /// Prepare the request [`Manager::apply_joint`] sends for `texts`.
+    ///
+    /// The builder is shared by every text: clone it and consume the object under a text's id
+    /// (`"1"` for the first text) to get that text's report.
+    #[allow(clippy::result_large_err)]
+    pub fn joint_request_for(
+        &self,
+        template: MessageCreateParams,
+        texts: &[&str],
+    ) -> Result<(ReportBuilder, MessageCreateParams), ApplyError> {
+        let (report, req, _) = self.joint_request(template, texts)?;
+        Ok((report, req))
+    }
+
+    /// Like [`Manager::joint_request_for`], also returning the policy position of each rule.
+    #[allow(clippy::result_large_err)]
+    fn joint_request(
+        &self,
+        template: MessageCreateParams,
+        texts: &[&str],
+    ) -> Result<(ReportBuilder, MessageCreateParams, Vec<usize>), ApplyError> {
+        let template = self.with_checked_model(template)?;
+        let (report, rule_policies) = self.builder_for(texts, &self.settings)?;
+        let mut tagged = String::new();
+        let mut properties = serde_json::Map::new();
+        let mut required = vec![];
+        for (idx, text) in texts.iter().enumerate() {
+            let id = (idx + 1).to_string();
+            tagged += &format!("<text id=\"{id}\">{text}</text>\n");
+            properties.insert(id.clone(), report.schema());
+            required.push(id);
+        }
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": required,
+            "properties": properties,
+        });
+        let req = self.assemble(
+            template,
+            &report,
+            tagged,
+            Some(include_str!("../prompts/manager_joint.md")),
+            schema,
+        );
+        Ok((report, req, rule_policies))
+    }
+
+    async fn apply_uncached(
+        &self,
+        client: &dyn LlmProvider,
+        template: MessageCreateParams,
+        unstructured_data: &str,
+        settings: &ApplySettings,
+        mut usage: Option<&mut Usage>,
+        on_partial: Option<&mut (dyn FnMut(&Report) + Send)>,
+    ) -> Result<Report, ApplyError> {
+        let start_time = Instant::now();
+        if self.is_too_short(unstructured_data) {
+            if let Some(usage) = &mut usage {
+                **usage = Usage::new();
+                usage.set_wall_clock_time(start_time.elapsed());
+            }
+            return self.empty_input_report(unstructured_data, settings);
+        }
+        let (report, req) = self.request(template, unstructured_data, settings)?;
+
+        // Every policy failed its precondition or is decided locally, so there is nothing to ask
+        // the LLM.
+        if !self.policies.is_empty() && report.has_only_local_rules() {
+            if let Some(usage) = &mut usage {
+                **usage = Usage::new();
+                usage.set_wall_clock_time(start_time.elapsed());
+            }
+            return report.consume_ir(serde_json::json!({"__rule_numbers__": []}));
+        }
+        self.send(
+            client,
+            &report,
+            req,
+            unstructured_data,
+            &[],
+            usage,
+            on_partial,
+        )
+        .await
+    }
+
+    /// True if `text` has fewer non-whitespace characters than [`Manager::with_min_input_chars`]
+    /// asks for.
+    fn is_too_short(&self, text: &str) -> bool {
+        self.min_input_chars > 0
+            && text.chars().filter(|c| !c.is_whitespace()).count() < self.min_input_chars
+    }
+
+    /// The defaults-only report for a text too short to evaluate.
+    #[allow(clippy::result_large_err)]
+    fn empty_input_report(
+        &self,
+        text: &str,
+        settings: &ApplySettings,
+    ) -> Result<Report, ApplyError> {
+        let (builder, _) = self.builder_where(settings, |_| false)?;
+        let mut report = builder
+            .with_input_hash(self.cache_key_for(text, settings).to_string())
+            .consume_ir(serde_json::json!({"__rule_numbers__": []}))?;
+        report.pruned_policies.clear();
+        for skipped in &mut report.skipped_policies {
+            skipped.reason = SkipReason::EmptyInput;
+        }
+        Ok(report)
+    }
+
+    /// Send `req` and turn the answer into a report with `report`, retrying with corrections
+    /// until the rule numbers agree with the output and verification passes.
+    ///
+    /// Whatever the LLM outputs for the rules in `discard` is dropped before the answer is
+    /// checked, as if those rules had not been sent.  A failure is recorded in the failure store
+    /// under `text`.
+    #[allow(clippy::too_many_arguments)]
+    async fn send(
+        &self,
+        client: &dyn LlmProvider,
+        report: &ReportBuilder,
+        req: MessageCreateParams,
+        text: &str,
+        discard: &[RuleIndex],
+        usage: Option<&mut Usage>,
+        on_partial: Option<&mut (dyn FnMut(&Report) + Send)>,
+    ) -> Result<Report, ApplyError> {
+        let mut attempts = vec![];
+        let result = self
+            .send_attempts(
+                client,
+                report,
+                req.clone(),
+                discard,
+                usage,
+                on_partial,
+                &mut attempts,
+            )
+            .await;
+        if let (Err(err), Some(store)) = (&result, &self.failure_store) {
+            let failure = Failure {
+                text_hash: format!("{:032x}", self.cache_key(text).text),
+                text: text.to_string(),
+                policies: self.policies.clone(),
+                request: req,
+                attempts,
+                error: err.to_string(),
+            };
+            // The apply's error matters more to the caller than a failure to record it.
+            let _ = store.record(failure);
+        }
+        if let (Ok(report), Some(sink)) = (&result, &self.review_sink) {
+            if let Some(task) = self.review_task(text, report) {
+                // As with failures, the report is returned even if it could not be queued.
+                let _ = sink.submit(&task).await;
+            }
+        }
+        result
+    }
+
+    /// The review task for `report`, or `None` if it needs no review.
+    fn review_task(&self, text: &str, report: &Report) -> Option<ReviewTask> {
+        let mut reasons = vec![];
+        if !report.conflicts().is_empty() {
+            reasons.push(format!(
+                "{} conflicts could not be resolved.",
+                report.conflicts().len()
+            ));
+        }
+        if !report.errors().is_empty() {
+            reasons.push(format!("{} errors were recorded.", report.errors().len()));
+        }
+        let confidence = report
+            .ir
+            .as_ref()
+            .and_then(|ir| ir.get(crate::verification::CONFIDENCE_FIELD))
+            .and_then(serde_json::Value::as_f64);
+        if let Some(min_confidence) = self.review_below_confidence {
+            match confidence {
+                Some(confidence) if confidence >= min_confidence => {}
+                Some(confidence) => reasons.push(format!(
+                    "The reported confidence of {confidence} is below {min_confidence}."
+                )),
+                None => reasons.push("No confidence was reported.".to_string()),
+            }
+        }
+        if reasons.is_empty() {
+            return None;
+        }
+        Some(ReviewTask {
+            text_hash: format!("{:032x}", self.cache_key(text).text),
+            text: text.to_string(),
+            report: report.clone(),
+            conflicts: report.conflicts().to_vec(),
+            confidence,
+            reasons,
+        })
+    }
+
+    /// The retry loop of [`Manager::send`].  The content of every response is pushed onto
+    /// `attempts`.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_attempts(
+        &self,
+        client: &dyn LlmProvider,
+        builder: &ReportBuilder,
+        mut req: MessageCreateParams,
+        discard: &[RuleIndex],
+        mut usage: Option<&mut Usage>,
+        mut on_partial: Option<&mut (dyn FnMut(&Report) + Send)>,
+        attempts: &mut Vec<serde_json::Value>,
+    ) -> Result<Report, ApplyError> {
+        let start_time = Instant::now();
+        let base = req.clone();
+        let max_attempts = 5;
+        let mut last_error = String::new();
+        let mut message_ids = vec![];
+        let mut tokens_used = 0;
+        let mut verified = false;
+        let mut corrected = false;
+
+        // Initialize usage tracking if provided
+        if let Some(usage) = &mut usage {
+            **usage = Usage::new();
+        }
+
+        for attempt in 1..=max_attempts {
+            if let Some(usage) = &mut usage {
+                usage.increment_requests();
+            }
+            let resp = match on_partial.as_deref_mut() {
+                Some(on_partial) => stream(client, req.clone(), builder, on_partial).await,
+                None => client.send(req.clone()).await,
+            };
+            let resp = match resp {
+                Ok(resp) => resp,
+                Err(err) => return Err(err.into()),
+            };
+            message_ids.push(resp.id.clone());
+            tokens_used += tokens_of(&resp.usage);
+            attempts.push(serde_json::to_value(&resp.content).unwrap_or_default());
+
+            // Track usage if provided
+            if let Some(usage) = &mut usage {
+                usage.add_message_id(resp.id.clone());
+                usage.add_claudius_usage(resp.usage);
+                if attempt > 1 {
+                    usage.add_retry_claudius_usage(resp.usage);
+                }
+                usage.increment_iterations();
+            }
+            if resp.content.len() != 1 {
+                return Err(ApplyError::invalid_response(
+                    format!(
+                        "Expected exactly 1 content block, got {}",
+                        resp.content.len()
+                    ),
+                    "Check that the LLM is configured correctly and the tool definition is valid",
+                ));
+            }
+            let ContentBlock::ToolUse(t) = &resp.content[0] else {
+                return Err(ApplyError::invalid_response(
+                    "Expected ToolUse content block",
+                    format!(
+                        "The LLM should be using the {} tool to provide structured output",
+                        self.tool_name()
+                    ),
+                ));
+            };
+            let mut ir = t.input.clone();
+            let reportedly_matched = ir
+                .get("__rule_numbers__")
+                .cloned()
+                .and_then(|numbers| serde_json::from_value::<Vec<usize>>(numbers).ok());
+            let Some(mut reportedly_matched) = reportedly_matched else {
+                if let (Some(usage), true) = (&mut usage, attempt < max_attempts) {
+                    usage.increment_inconsistency_retries();
+                }
+                continue;
+            };
+            for rule in discard {
+                reportedly_matched.retain(|n| *n != rule.number());
+                if let (Some(masks), serde_json::Value::Object(obj)) =
+                    (builder.masks_for_rule(*rule), &mut ir)
+                {
+                    for mask in masks {
+                        obj.shift_remove(mask);
+                    }
+                }
+            }
+            reportedly_matched.extend(builder.local_matches().iter().map(|rule| rule.number()));
+            let mut report = builder.clone().consume_ir(ir.clone())?;
+            report.message_ids = message_ids.clone();
+            report.tokens_used = tokens_used;
+            report.requests = attempt;
+            let Some((inconsistencies, mismatch)) = rule_number_inconsistencies(
+                builder,
+                &report,
+                reportedly_matched,
+                self.strict_fields,
+            ) else {
+                let concerns = if verified || attempt == max_attempts {
+                    vec![]
+                } else {
+                    self.verification.concerns(&report)
+                };
+                if concerns.is_empty() {
+                    let violation = self
+                        .validator
+                        .as_ref()
+                        .and_then(|validator| validator.check(&report.value()).err());
+                    let Some(violation) = violation else {
+                        // Set final wall clock time
+                        if let Some(usage) = &mut usage {
+                            usage.set_wall_clock_time(start_time.elapsed());
+                        }
+                        return Ok(report);
+                    };
+                    if !self.validation_retry || corrected || attempt == max_attempts {
+                        if let Some(usage) = &mut usage {
+                            usage.set_wall_clock_time(start_time.elapsed());
+                        }
+                        return Err(ApplyError::ValidationFailed { message: violation });
+                    }
+                    corrected = true;
+                    if let Some(usage) = &mut usage {
+                        usage.increment_validation_retries();
+                    }
+                    let content = format!("<instruction>Your output breaks a rule that every output must follow.  Re-read the rules and the text, then output it again, corrected.</instruction><violation>{violation}</violation>");
+                    req = self.retry_request(
+                        if self.compact_retry { &base } else { &req },
+                        &resp.content,
+                        &t.id,
+                        format!("<error-message>{content}</error-message>"),
+                    );
+                    continue;
+                }
+                verified = true;
+                if let Some(usage) = &mut usage {
+                    usage.increment_verifications();
+                }
+                let mut content = "<instruction>Your output looks suspicious.  Double-check it against the rules and the text, then output it again, corrected if necessary.</instruction>".to_string();
+                for concern in concerns {
+                    content += &format!("<concern>{concern}</concern>");
+                }
+                req = self.retry_request(
+                    if self.compact_retry { &base } else { &req },
+                    &resp.content,
+                    &t.id,
+                    format!("<error-message>{content}</error-message>"),
+                );
+                continue;
+            };
+            let content = format!("{RULE_NUMBER_MISMATCH}{inconsistencies}");
+            last_error = format!("Attempt {attempt}/{max_attempts}: Rule mismatch - {mismatch}");
+            if let (Some(usage), true) = (&mut usage, attempt < max_attempts) {
+                usage.increment_inconsistency_retries();
+            }
+            req = self.retry_request(
+                if self.compact_retry { &base } else { &req },
+                &resp.content,
+                &t.id,
+                format!("<error-message>{content}</error-message>"),
+            );
+        }
+        // Set final wall clock time even on error
+        if let Some(usage) = &mut usage {
+            usage.set_wall_clock_time(start_time.elapsed());
+        }
+        Err(ApplyError::too_many_iterations(max_attempts, last_error))
+    }
+
+    /// Prepare a request for LLM processing by building the necessary context.
+    ///
+    /// This method constructs the complete request that will be sent to the LLM,
+    /// including system prompts, policy rules, and the input text. It returns
+    /// both a ReportBuilder for processing the response and the configured request.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Base message parameters to use for the LLM request
+    /// * `text` - The unstructured text data to analyze
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing:
+    /// - `ReportBuilder`: Used to process the LLM's structured response
+    /// - `MessageCreateParams`: The complete request ready to send to the LLM
+    ///
+    /// # Errors
+    ///
+    /// Returns `ApplyError` if policy addition to the report builder fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use policyai::Manager;
+    /// # use claudius::MessageCreateParams;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = Manager::default();
+    /// let template = MessageCreateParams::default();
+    /// let (report_builder, request) = manager.request_for(template, "analyze this text").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request_for(
+        &mut self,
+        template: MessageCreateParams,
+        text: &str,
+    ) -> Result<(ReportBuilder, MessageCreateParams), ApplyError> {
+        self.request(template, text, &self.settings)
+    }
+
+    /// The builder and request of [`Manager::request_for`] for an apply with `settings`.
+    #[allow(clippy::result_large_err)]
+    fn request(
+        &self,
+        template: MessageCreateParams,
+        text: &str,
+        settings: &ApplySettings,
+    ) -> Result<(ReportBuilder, MessageCreateParams), ApplyError> {
+        let template = self.with_checked_model(template)?;
+        let (report, rule_policies) = self.builder_for(&[text], settings)?;
+        let report = report
+            .with_local_matches(self.local_matches(&rule_policies, text))
+            .with_input_hash(self.cache_key_for(text, settings).to_string());
+        let req = self.assemble(
+            template,
+            &report,
+            self.wrap_text(text, settings),
+            None,
+            report.schema(),
+        );
+        Ok((report, req))
+    }
+
+    /// Assemble the request [`Manager::apply`] would send first for `text`, without sending it,
+    /// and estimate its input tokens by component.
+    ///
+    /// The estimate attributes each rule's prompt and schema to the policy it came from, so the
+    /// policies that make a prompt expensive can be found before a single token is spent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template's model is not allowed or a policy cannot be added to
+    /// the report builder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use claudius::MessageCreateParams;
+    /// # use policyai::Manager;
+    /// let manager = Manager::default();
+    /// let plan = manager.plan(MessageCreateParams::default(), "an email").unwrap();
+    /// assert!(plan.tokens.system > 0);
+    /// println!("{}", plan.tokens);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn plan(
+        &self,
+        template: MessageCreateParams,
+        text: &str,
+    ) -> Result<PlannedRequest, ApplyError> {
+        let template = self.with_checked_model(template)?;
+        let (report, rule_policies) = self.builder_for(&[text], &self.settings)?;
+        let wrapped = self.wrap_text(text, &self.settings);
+        let prompts = self.prompt_variant_for(&template.model);
+        let schema = report.schema();
+        let rules = report
+            .sent_rules()
+            .iter()
+            .zip(rule_policies.iter())
+            .map(|(size, policy)| RuleTokens {
+                rule: size.rule,
+                policy: *policy,
+                prompt: (size.prompt_bytes as u64).div_ceil(PromptTokens::BYTES_PER_TOKEN),
+                schema: (size.schema_bytes as u64).div_ceil(PromptTokens::BYTES_PER_TOKEN),
+            })
+            .collect::<Vec<_>>();
+        let rule_schema_bytes: usize = report.sent_rules().iter().map(|s| s.schema_bytes).sum();
+        let tool_bytes = self.tool_name().len()
+            + self
+                .tool_description
+                .as_deref()
+                .unwrap_or(Self::DEFAULT_TOOL_DESCRIPTION)
+                .len()
+            + serde_json::to_string(&schema).map_or(0, |s| s.len());
+        let tokens = PromptTokens {
+            system: PromptTokens::estimate(&prompts.system),
+            defaults: PromptTokens::estimate(&defaults_message(&report)),
+            rules,
+            tool: (tool_bytes.saturating_sub(rule_schema_bytes) as u64)
+                .div_ceil(PromptTokens::BYTES_PER_TOKEN),
+            text: PromptTokens::estimate(&wrapped),
+            reminder: PromptTokens::estimate(&prompts.suffix),
+        };
+        let report = report.with_local_matches(self.local_matches(&rule_policies, text));
+        let request = self.assemble(template, &report, wrapped, None, schema);
+        Ok(PlannedRequest { request, tokens })
+    }
+
+    /// Build the rules portion of the request once, for applying the same policies to many
+    /// texts.
+    ///
+    /// Every policy becomes a rule regardless of its precondition, so the rules are identical
+    /// from text to text and carry a prompt-cache breakpoint: after the first request, the
+    /// server reads them from cache and only the text is processed anew.  A policy whose
+    /// precondition fails for a text is still sent, but its output is discarded and it is
+    /// recorded in the report's `pruned_policies`.
+    ///
+    /// The handle captures the manager as it is now; policies added later are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a policy cannot be added to the report builder.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use claudius::{Anthropic, MessageCreateParams};
+    /// # use policyai::Manager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Anthropic::new(None)?;
+    /// # let manager = Manager::default();
+    /// let prepared = manager.prepare(MessageCreateParams::default())?;
+    /// for text in ["first email", "second email"] {
+    ///     let report = prepared.apply(&client, text, None).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn prepare(&self, template: MessageCreateParams) -> Result<Prepared, ApplyError> {
+        let template = self.with_checked_model(template)?;
+        let (builder, rule_policies) = self.builder_where(&self.settings, |_| true)?;
+        let mut rules = self.assemble_rules(template, &builder, builder.schema());
+        mark_cache_breakpoint(&mut rules);
+        Ok(Prepared {
+            manager: self.clone(),
+            builder,
+            rule_policies,
+            rules,
+        })
+    }
+
+    /// Build the report builder for a request covering `texts`.
+    ///
+    /// A policy becomes a rule if its precondition holds for at least one of the texts.  Also
+    /// returns, for each rule in order, the position of the policy it came from.
+    #[allow(clippy::result_large_err)]
+    fn builder_for(
+        &self,
+        texts: &[&str],
+        settings: &ApplySettings,
+    ) -> Result<(ReportBuilder, Vec<usize>), ApplyError> {
+        self.builder_where(settings, |policy| {
+            policy
+                .precondition
+                .as_ref()
+                .is_none_or(|precondition| texts.iter().any(|text| precondition.matches(text)))
+        })
+    }
+
+    /// Build a report builder, for an apply with `settings`, whose rules are the policies for
+    /// which `keep` holds.
+    ///
+    /// The rest are recorded as pruned.  Also returns, for each rule in order, the position of
+    /// the policy it came from.
+    #[allow(clippy::result_large_err)]
+    fn builder_where(
+        &self,
+        settings: &ApplySettings,
+        keep: impl Fn(&Policy) -> bool,
+    ) -> Result<(ReportBuilder, Vec<usize>), ApplyError> {
+        let mut report = ReportBuilder::default()
+            .with_field_order(self.field_order)
+            .with_naming_policy(self.naming)
+            .with_translations(self.translations.clone())
+            .with_on_conflict_overrides(settings.on_conflict_overrides.clone())
+            .with_enum_fallbacks(self.enum_fallbacks.clone())
+            .with_null_handling(self.null_handling.clone())
+            .with_unmasked_fields(self.unmasked_fields.iter().cloned())
+            .with_policy_set_hash(self.policy_set_hash_for(settings));
+        if let Some(max_array_len) = self.max_array_len {
+            report = report.with_max_array_len(max_array_len);
+        }
+        if self.verification.min_confidence.is_some() || self.review_below_confidence.is_some() {
+            report = report.with_confidence();
+        }
+        if self.non_match_reasons {
+            report = report.with_non_match_reasons();
+        }
+        let mut rule_policies = vec![];
+        // Policies decided locally become rules after those sent to the LLM, so that the rules
+        // the LLM sees are numbered without gaps.
+        let mut local = vec![];
+        for (index, policy) in self.policies.iter().enumerate() {
+            let scoped = self.scoped(policy, settings)?;
+            // A policy cut down to nothing has nothing to contribute.
+            let sets_nothing = scoped
+                .action
+                .as_object()
+                .is_some_and(|action| action.is_empty());
+            if !keep(policy) {
+                report.add_pruned_policy(index, &scoped);
+            } else if settings.fields.is_some() && sets_nothing {
+                report.add_skipped_policy(index, &scoped, SkipReason::OutOfScope);
+            } else if policy.exact_match.is_some() {
+                local.push(index);
+            } else {
+                report.add_policy(&self.resolve(policy, settings)?)?;
+                rule_policies.push(index);
+            }
+        }
+        let max_policies = self.max_policies.unwrap_or(Self::DEFAULT_MAX_POLICIES);
+        if rule_policies.len() > max_policies {
+            return Err(ApplyError::too_many_policies(
+                rule_policies.len(),
+                max_policies,
+            ));
+        }
+        for index in local {
+            report.add_local_policy(&self.resolve(&self.policies[index], settings)?)?;
+            rule_policies.push(index);
+        }
+        Ok((
+            report.with_rule_policies(rule_policies.clone()),
+            rule_policies,
+        ))
+    }
+
+    /// `policy` cut down to the fields asked for, its placeholders filled from `settings`, and its
+    /// dynamic enums' values supplied.
+    #[allow(clippy::result_large_err)]
+    fn resolve(&self, policy: &Policy, settings: &ApplySettings) -> Result<Policy, PolicyError> {
+        self.scoped(policy, settings)?
+            .with_variables(&settings.variables)?
+            .with_enum_values(&settings.enum_values)
+    }
+
+    /// `policy` cut down to the fields `settings` asks for, if any.
+    #[allow(clippy::result_large_err)]
+    fn scoped(&self, policy: &Policy, settings: &ApplySettings) -> Result<Policy, PolicyError> {
+        match &settings.fields {
+            Some(fields) => policy.with_only_fields(fields),
+            None => Ok(policy.clone()),
+        }
+    }
+
+    /// The rules decided locally that match `text`, given the position of the policy each rule
+    /// came from.  A rule whose policy's precondition fails for `text` does not match.
+    fn local_matches(&self, rule_policies: &[usize], text: &str) -> Vec<RuleIndex> {
+        rule_policies
+            .iter()
+            .enumerate()
+            .filter(|(_, index)| {
+                let policy = &self.policies[**index];
+                policy.exact_match.as_ref().is_some_and(|exact| {
+                    exact.matches(text)
+                        && policy
+                            .precondition
+                            .as_ref()
+                            .is_none_or(|precondition| precondition.matches(text))
+                })
+            })
+            .map(|(position, _)| RuleIndex::from_position(position))
+            .collect()
+    }
+
+    /// `template` with the model set by [`Manager::with_model`], after checking that the model
+    /// is named and allowed.
+    #[allow(clippy::result_large_err)]
+    fn with_checked_model(
+        &self,
+        mut template: MessageCreateParams,
+    ) -> Result<MessageCreateParams, ApplyError> {
+        if let Some(model) = &self.model {
+            template.model = model.clone();
+        }
+        let model = template.model.to_string();
+        let allowed = self.allowed_models.is_empty()
+            || self
+                .allowed_models
+                .iter()
+                .any(|prefix| model.starts_with(prefix.as_str()));
+        if model.trim().is_empty() || !allowed {
+            return Err(ApplyError::ModelNotConfigured {
+                model,
+                allowed: self.allowed_models.clone(),
+            });
+        }
+        Ok(template)
+    }
+
+    /// `text` in its tags, preceded by the block of `settings`' metadata if there is metadata.
+    fn wrap_text(&self, text: &str, settings: &ApplySettings) -> String {
+        format!("{}<text>{text}</text>", settings.metadata.render())
+    }
+
+    /// Assemble the request that asks the LLM to apply `report`'s rules to `texts`, which are
+    /// already wrapped in their tags, and to answer with JSON matching `schema`.
+    fn assemble(
+        &self,
+        template: MessageCreateParams,
+        report: &ReportBuilder,
+        texts: String,
+        instruction: Option<&str>,
+        schema: serde_json::Value,
+    ) -> MessageCreateParams {
+        let req = self.assemble_rules(template, report, schema);
+        self.assemble_texts(req, texts, instruction)
+    }
+
+    /// Assemble the part of a request that does not depend on the texts: the instructions,
+    /// the default, the rules, and the output tool.
+    fn assemble_rules(
+        &self,
+        template: MessageCreateParams,
+        report: &ReportBuilder,
+        schema: serde_json::Value,
+    ) -> MessageCreateParams {
+        let prompts = self.prompt_variant_for(&template.model);
+        let mut req = template;
+        req.system = Some(SystemPrompt::from_blocks(vec![TextBlock {
+            text: prompts.system,
+            cache_control: None,
+            citations: None,
+        }]));
+
+        push_or_merge_message(
+            &mut req.messages,
+            MessageParam::new_with_string(defaults_message(report), MessageRole::User),
+        );
+        for message in report.messages() {
+            push_or_merge_message(&mut req.messages, message)
+        }
+        req.tool_choice = Some(ToolChoice::tool(self.tool_name()));
+        req.tools = Some(vec![claudius::ToolUnionParam::CustomTool(
+            claudius::ToolParam {
+                name: self.tool_name().to_string(),
+                description: Some(
+                    self.tool_description
+                        .as_deref()
+                        .unwrap_or(Self::DEFAULT_TOOL_DESCRIPTION)
+                        .to_string(),
+                ),
+                input_schema: schema,
+                cache_control: None,
+            },
+        )]);
+        req
+    }
+
+    /// Finish a request from [`Manager::assemble_rules`] with `texts`, the optional
+    /// `instruction`, and the closing reminder.
+    fn assemble_texts(
+        &self,
+        mut req: MessageCreateParams,
+        texts: String,
+        instruction: Option<&str>,
+    ) -> MessageCreateParams {
+        push_or_merge_message(
+            &mut req.messages,
+            MessageParam::new_with_string(texts, MessageRole::User),
+        );
+        if let Some(instruction) = instruction {
+            push_or_merge_message(
+                &mut req.messages,
+                MessageParam::new_with_string(instruction.to_string(), MessageRole::User),
+            );
+        }
+        push_or_merge_message(
+            &mut req.messages,
+            MessageParam::new_with_string(
+                self.prompt_variant_for(&req.model).suffix,
+                MessageRole::User,
+            ),
+        );
+        if self.compact_retry {
+            mark_cache_breakpoint(&mut req);
+        }
+        req
+    }
+
+    /// Build the request for the next attempt from `previous`, the answer in `content` whose
+    /// tool use is `tool_use_id`, and the `correction` for it.
+    fn retry_request(
+        &self,
+        previous: &MessageCreateParams,
+        content: &[ContentBlock],
+        tool_use_id: &str,
+        correction: String,
+    ) -> MessageCreateParams {
+        let mut req = previous.clone();
+        push_or_merge_message(
+            &mut req.messages,
+            MessageParam {
+                role: MessageRole::Assistant,
+                content: MessageParamContent::Array(content.to_vec()),
+            },
+        );
+        push_or_merge_message(
+            &mut req.messages,
+            MessageParam {
+                role: MessageRole::User,
+                content: MessageParamContent::Array(vec![ContentBlock::ToolResult(
+                    ToolResultBlock {
+                        tool_use_id: tool_use_id.to_string(),
+                        cache_control: None,
+                        is_error: Some(true),
+                        content: Some(correction.into()),
+                    },
+                )]),
+            },
+        );
+        req
+    }
+}
+
+//////////////////////////////////////////// Prepared ////////////////////////////////////////////
+
+/// A manager's rules, assembled once and reused for every text.
+///
+/// Created by [`Manager::prepare`].  Each request repeats the rules byte for byte ahead of a
+/// prompt-cache breakpoint, so applying the same policies to thousands of texts pays for the
+/// rules once per cache lifetime instead of once per text.
+#[derive(Clone)]
+pub struct Prepared {
+    manager: Manager,
+    builder: ReportBuilder,
+    rule_policies: Vec<usize>,
+    rules: MessageCreateParams,
+}
+
+impl Prepared {
+    /// Apply the prepared policies to `text`.
+    ///
+    /// Behaves like [`Manager::apply`], including the manager's cache and verification.
+    pub async fn apply(
+        &self,
+        client: &dyn LlmProvider,
+        text: &str,
+        mut usage: Option<&mut Usage>,
+    ) -> Result<Report, ApplyError> {
+        let start_time = Instant::now();
+        let cache = self.manager.cache.as_ref();
+        let key = self.manager.cache_key(text);
+        if let Some(report) = cache.and_then(|cache| cache.get(&key)) {
+            if let Some(usage) = &mut usage {
+                **usage = Usage::new();
+                usage.increment_cache_hits();
+                usage.set_wall_clock_time(start_time.elapsed());
+            }
+            return Ok(report);
+        }
+        let pruned = self.pruned_policies(text);
+        let (builder, req) = self.request_for(text);
+        let result = if !self.asks_llm(&pruned) {
+            if let Some(usage) = &mut usage {
+                **usage = Usage::new();
+                usage.set_wall_clock_time(start_time.elapsed());
+            }
+            builder.consume_ir(serde_json::json!({"__rule_numbers__": []}))
+        } else {
+            let discard = self.discarded_rules(&pruned);
+            self.manager
+                .send(
+                    client,
+                    &builder,
+                    req,
+                    text,
+                    &discard,
+                    usage.as_deref_mut(),
+                    None,
+                )
+                .await
+        };
+        let result = result.map(|mut report| {
+            report.record_pruned_policies(pruned);
+            report
+        });
+        if let Some(cache) = cache {
+            if let Some(usage) = &mut usage {
+                usage.increment_cache_misses();
+            }
+            if let Ok(report) = &result {
+                cache.put(key, report.clone());
+            }
+        }
+        result
+    }
+
+    /// The builder and the request [`Prepared::apply`] uses for `text`.
+    ///
+    /// The builder has a rule for every policy, with the rules decided locally for `text`
+    /// marked; the request is the prepared rules followed by the text.
+    pub fn request_for(&self, text: &str) -> (ReportBuilder, MessageCreateParams) {
+        let builder = self
+            .builder
+            .clone()
+            .with_local_matches(self.manager.local_matches(&self.rule_policies, text))
+            .with_input_hash(self.manager.cache_key(text).to_string());
+        let req = self.manager.assemble_texts(
+            self.rules.clone(),
+            self.manager.wrap_text(text, &self.manager.settings),
+            None,
+        );
+        (builder, req)
+    }
+
+    /// True if some policy that survived its precondition, given the positions of those that
+    /// did not, is left to the LLM.  With no policies at all the LLM is still asked.
+    fn asks_llm(&self, pruned: &[usize]) -> bool {
+        self.manager.policies.is_empty()
+            || self.rule_policies.iter().any(|index| {
+                !pruned.contains(index) && self.manager.policies[*index].exact_match.is_none()
+            })
+    }
+
+    /// The rules whose output is dropped because their policy's position is in `pruned`.
+    fn discarded_rules(&self, pruned: &[usize]) -> Vec<RuleIndex> {
+        self.rule_policies
+            .iter()
+            .enumerate()
+            .filter(|(_, policy)| pruned.contains(policy))
+            .map(|(position, _)| RuleIndex::from_position(position))
+            .collect()
+    }
+
+    /// The positions of the policies whose precondition fails for `text`.
+    fn pruned_policies(&self, text: &str) -> Vec<usize> {
+        self.manager
+            .policies
+            .iter()
+            .enumerate()
+            .filter(|(_, policy)| {
+                policy
+                    .precondition
+                    .as_ref()
+                    .is_some_and(|precondition| !precondition.matches(text))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for Prepared {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Prepared")
+            .field("manager", &self.manager)
+            .field("rule_policies", &self.rule_policies)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The message quoting the output to give when no rule says otherwise.
+fn defaults_message(report: &ReportBuilder) -> String {
+    format!(
+        "<default>Unless specified otherwise, output {}</default>",
+        serde_json::to_string(report.default_return()).unwrap()
+    )
+}
+
+/// The longest excerpt of a rule's prompt quoted in a correction, in characters.
+const RULE_EXCERPT_CHARS: usize = 60;
+
+/// `mask` quoted, with the field it stands in for and an excerpt of `rule`'s prompt.
+fn describe_mask(builder: &ReportBuilder, rule: RuleIndex, mask: &str) -> String {
+    let mut described = format!("\"{mask}\"");
+    let field = builder.field_for_mask(mask).filter(|field| *field != mask);
+    let prompt = builder
+        .sent_rules()
+        .iter()
+        .find(|sent| sent.rule == rule)
+        .map(|sent| sent.prompt.trim());
+    match (field, prompt) {
+        (Some(field), Some(prompt)) => {
+            described += &format!(
+                " (field \"{field}\" of rule {rule}, \"{}\")",
+                excerpt(prompt)
+            )
+        }
+        (Some(field), None) => described += &format!(" (field \"{field}\" of rule {rule})"),
+        (None, Some(prompt)) => described += &format!(" (rule {rule}, \"{}\")", excerpt(prompt)),
+        (None, None) => {}
+    }
+    described
+}
+
+/// The start of `prompt`, cut at a word boundary if it is longer than [`RULE_EXCERPT_CHARS`].
+fn excerpt(prompt: &str) -> String {
+    if prompt.chars().count() <= RULE_EXCERPT_CHARS {
+        return prompt.to_string();
+    }
+    let cut: String = prompt.chars().take(RULE_EXCERPT_CHARS).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(space) => &cut[..space],
+        None => &cut,
+    };
+    format!("{}...", cut.trim_end())
+}
+
+/// The instruction that opens the correction sent when rule numbers and output disagree.
+const RULE_NUMBER_MISMATCH: &str = "<instruction>The reported rule numbers do not match the fields that were output.  Re-evaluate your output to resolve the following inconsistencies.</instruction>";
+
+/// Every input and output token of one response.
+fn tokens_of(usage: &claudius::Usage) -> u64 {
+    [
+        Some(usage.input_tokens),
+        Some(usage.output_tokens),
+        usage.cache_creation_input_tokens,
+        usage.cache_read_input_tokens,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|tokens| tokens.max(0) as u64)
+    .sum()
+}
+
+/// Compare the rule numbers the LLM reported with the rules whose fields it set.  When `strict`,
+/// also flag every ungated field of a rule that matched but was left unset.
+///
+/// Returns `None` when they agree.  Otherwise returns the `<inconsistency>` elements to send
+/// back, and a summary of the mismatch for the error returned when retries run out.  Each mask
+/// is named alongside the field it stands in for and an excerpt of its rule, from `builder`,
+/// because a bare mask is easy for the LLM to garble when it answers again.
+fn rule_number_inconsistencies(
+    builder: &ReportBuilder,
+    report: &Report,
+    reportedly_matched: Vec<usize>,
+    strict: bool,
+) -> Option<(String, String)> {
+    let empirically_matched = report.matched_rules();
+    let has_rule_zero = reportedly_matched.contains(&0);
+    let mut reportedly_matched = reportedly_matched
+        .into_iter()
+        .filter_map(RuleIndex::from_number)
+        .collect::<Vec<_>>();
+    reportedly_matched.sort();
+    reportedly_matched.dedup();
+    let mut unset = vec![];
+    if strict {
+        let ir = report.ir.as_ref();
+        for rule in empirically_matched.iter() {
+            if !reportedly_matched.contains(rule) {
+                continue;
+            }
+            for mask in report.masks_for_rule(*rule).unwrap_or_default() {
+                let gated = builder
+                    .field_for_mask(mask)
+                    .is_some_and(|field| builder.is_gated(field));
+                if !gated && ir.and_then(|ir| ir.get(mask)).is_none() {
+                    unset.push((*rule, mask));
+                }
+            }
+        }
+    }
+    if empirically_matched == reportedly_matched && !has_rule_zero && unset.is_empty() {
+        return None;
+    }
+    let empirical_but_not_reported = empirically_matched
+        .iter()
+        .filter(|x| !reportedly_matched.contains(x))
+        .cloned()
+        .collect::<Vec<_>>();
+    let reported_but_not_empirical = reportedly_matched
+        .iter()
+        .filter(|x| !empirically_matched.contains(x))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut content = String::new();
+    if has_rule_zero {
+        content += "<inconsistency>Rule number 0 present in __rule_numbers__, but rules are numbered from 1.</inconsistency>";
+    }
+    for rule_number in empirical_but_not_reported.into_iter() {
+        match report.masks_for_rule(rule_number) {
+            Some(masks) => {
+                for mask in masks.iter() {
+                    let described = describe_mask(builder, rule_number, mask);
+                    content += &format!("<inconsistency>{rule_number} was not present in rule numbers, but {described} was set.<resolution>Unset \"{mask}\" if the context doesn't match or add {rule_number} to \"__rule_numbers__\" if the rule matches.</resolution></inconsistency>");
+                }
+            }
+            None => {
+                content += &format!("<inconsistency>Rule number {rule_number} present in __rule_numbers__, but it doesn't exist in the reported rules.</inconsistency>");
+            }
+        }
+    }
+    if !reported_but_not_empirical.is_empty() {
+        content += "\n\nYou reported the following rules but did not output their JSON:\n";
+        for rule_number in reported_but_not_empirical.into_iter() {
+            match report.masks_for_rule(rule_number) {
+                Some(masks) => {
+                    for mask in masks.iter() {
+                        let described = describe_mask(builder, rule_number, mask);
+                        content += &format!("<inconsistency>{rule_number} was present in rule numbers, but {described} was not set.<resolution>Set \"{mask}\" if the context matches or remove {rule_number} from \"__rule_numbers__\" if the rule does not match.</resolution></inconsistency>");
+                    }
+                }
+                None => {
+                    content += &format!("<inconsistency>Rule number {rule_number} present in __rule_numbers__, but it doesn't exist in the reported rules.</inconsistency>");
+                }
+            }
+        }
+    }
+    for (rule_number, mask) in unset.iter() {
+        let described = describe_mask(builder, *rule_number, mask);
+        content += &format!("<inconsistency>{rule_number} was present in rule numbers, but {described} was not set.<resolution>Set \"{mask}\" as rule {rule_number} says.</resolution></inconsistency>");
+    }
+    let mut mismatch = format!(
+        "empirically matched {empirically_matched:?} but reportedly matched {reportedly_matched:?}"
+    );
+    if !unset.is_empty() {
+        mismatch += &format!(" and left {} of their fields unset", unset.len());
+    }
+    Some((content, mismatch))
+}
+
+/// Put a prompt-cache breakpoint on the last block of the last message, so that everything up
+/// to and including it is cached.
+fn mark_cache_breakpoint(req: &mut MessageCreateParams) {
+    let Some(message) = req.messages.last_mut() else {
+        return;
+    };
+    if let MessageParamContent::String(text) = &message.content {
+        message.content = MessageParamContent::Array(vec![ContentBlock::Text(TextBlock {
+            text: text.clone(),
+            cache_control: None,
+            citations: None,
+        })]);
+    }
+    if let MessageParamContent::Array(blocks) = &mut message.content {
+        if let Some(ContentBlock::Text(block)) = blocks.last_mut() {
+            block.cache_control = Some(CacheControlEphemeral::new());
+        }
+    }
+}
+
+/// Send `req` as a streaming request, calling `on_partial` whenever the tool input gains a value.
+///
+/// A provider that cannot stream is sent the request whole, and `on_partial` is not called.
+async fn stream(
+    client: &dyn LlmProvider,
+    req: MessageCreateParams,
+    builder: &ReportBuilder,
+    on_partial: &mut (dyn FnMut(&Report) + Send),
+) -> Result<Message, claudius::Error> {
+    let Some(mut events) = client.stream(req.clone()).await? else {
+        return client.send(req).await;
+    };
+    let mut streamed = StreamedMessage::default();
+    let mut last = None;
+    while let Some(event) = events.next().await {
+        let Some(ir) = streamed.push(event?).and_then(PartialJson::value) else {
+            continue;
+        };
+        if last.as_ref() != Some(&ir) {
+            if let Ok(report) = builder.clone().consume_ir(ir.clone()) {
+                on_partial(&report);
+            }
+            last = Some(ir);
+        }
+    }
+    streamed.finish()
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
-    use crate::{Field, PolicyType};
-    use claudius::SystemPrompt;
+    use crate::{Field, PolicyError, PolicyType, SkippedPolicy};
+    use claudius::{Anthropic, SystemPrompt};
+
+    fn create_test_policy_type() -> PolicyType {
+        PolicyType {
+            name: "TestPolicy".to_string(),
+            fields: vec![
+                Field::Bool {
+                    name: "is_active".to_string(),
+                    default: Some(false),
+                    on_conflict: crate::OnConflict::Default,
+                },
+                Field::String {
+                    name: "message".to_string(),
+                    default: Some("default".to_string()),
+                    on_conflict: crate::OnConflict::Agreement,
+                },
+                Field::Number {
+                    name: "count".to_string(),
+                    default: Some(crate::t64(0.0)),
+                    on_conflict: crate::OnConflict::LargestValue,
+                },
+            ],
+            groups: vec![],
+        }
+    }
+
+    fn create_test_policy(r#type: PolicyType, prompt: &str, action: serde_json::Value) -> Policy {
+        Policy {
+            r#type,
+            prompt: prompt.to_string(),
+            action,
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn manager_default_is_empty() {
+        let manager = Manager::default();
+        assert!(manager.is_empty());
+        assert_eq!(manager.len(), 0);
+    }
+
+    #[test]
+    fn manager_add_single_policy() {
+        let mut manager = Manager::default();
+        let policy_type = create_test_policy_type();
+        let policy = create_test_policy(
+            policy_type,
+            "test prompt",
+            serde_json::json!({"is_active": true}),
+        );
+
+        manager.add(policy);
+        assert!(!manager.is_empty());
+        assert_eq!(manager.len(), 1);
+    }
+
+    #[test]
+    fn manager_add_multiple_policies_same_type() {
+        let mut manager = Manager::default();
+        let policy_type = create_test_policy_type();
+
+        let policy1 = create_test_policy(
+            policy_type.clone(),
+            "first prompt",
+            serde_json::json!({"is_active": true}),
+        );
+        let policy2 = create_test_policy(
+            policy_type.clone(),
+            "second prompt",
+            serde_json::json!({"message": "hello"}),
+        );
+        let policy3 = create_test_policy(
+            policy_type,
+            "third prompt",
+            serde_json::json!({"count": 42}),
+        );
+
+        manager.add(policy1);
+        manager.add(policy2);
+        manager.add(policy3);
+
+        assert_eq!(manager.len(), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn manager_add_policy_different_type_panics() {
+        let mut manager = Manager::default();
+
+        let type1 = create_test_policy_type();
+        let type2 = PolicyType {
+            name: "DifferentPolicy".to_string(),
+            fields: vec![Field::Bool {
+                name: "enabled".to_string(),
+                default: Some(true),
+                on_conflict: crate::OnConflict::Default,
+            }],
+            groups: vec![],
+        };
+
+        let policy1 = create_test_policy(type1, "first", serde_json::json!({"is_active": true}));
+        let policy2 = create_test_policy(type2, "second", serde_json::json!({"enabled": false}));
+
+        manager.add(policy1);
+        manager.add(policy2); // This should panic
+    }
+
+    #[test]
+    fn manager_counts_and_skips_duplicates() {
+        let policy_type = create_test_policy_type();
+        let urgent = serde_json::json!({"is_active": true});
+        let policies = [
+            create_test_policy(policy_type.clone(), "from the CEO", urgent.clone()),
+            create_test_policy(policy_type.clone(), "from the CEO", urgent.clone()),
+            create_test_policy(
+                policy_type.clone(),
+                "from the CEO",
+                serde_json::json!({"is_active": false}),
+            ),
+            create_test_policy(policy_type, "from the CEO", urgent),
+        ];
+
+        let mut manager = Manager::default();
+        for policy in policies.iter().cloned() {
+            manager.add(policy);
+        }
+        assert_eq!(
+            manager.stats(),
+            PolicyStats {
+                policies: 4,
+                duplicates_kept: 2,
+                duplicates_skipped: 0,
+            }
+        );
+
+        let mut manager = Manager::default().with_on_duplicate(OnDuplicate::Skip);
+        for policy in policies.iter().cloned() {
+            manager.add(policy);
+        }
+        assert_eq!(
+            manager.stats(),
+            PolicyStats {
+                policies: 2,
+                duplicates_kept: 0,
+                duplicates_skipped: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn manager_request_for_empty_manager() {
+        let mut manager = Manager::default();
+        let template = MessageCreateParams::default();
+        let text = "test text";
+
+        let result = manager.request_for(template, text).await;
+        assert!(result.is_ok());
+
+        let (_report, req) = result.unwrap();
+        assert!(!req.messages.is_empty());
+        assert!(req.system.is_some());
+        assert_eq!(req.tool_choice, Some(ToolChoice::tool("output_json")));
+    }
+
+    #[tokio::test]
+    async fn manager_request_for_custom_tool() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default()
+            .with_tool_name(policy_type.tool_name())
+            .with_tool_description("Classify the email");
+        manager.add(create_test_policy(
+            policy_type,
+            "test prompt",
+            serde_json::json!({"is_active": true}),
+        ));
+        let (_report, req) = manager
+            .request_for(MessageCreateParams::default(), "test text")
+            .await
+            .unwrap();
+        assert_eq!(req.tool_choice, Some(ToolChoice::tool("output_TestPolicy")));
+        let Some(tools) = req.tools else {
+            panic!("expected tools");
+        };
+        let claudius::ToolUnionParam::CustomTool(tool) = &tools[0] else {
+            panic!("expected a custom tool");
+        };
+        assert_eq!(tool.name, "output_TestPolicy");
+        assert_eq!(tool.description.as_deref(), Some("Classify the email"));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid tool name")]
+    fn manager_rejects_invalid_tool_name() {
+        let _ = Manager::default().with_tool_name("output json");
+    }
+
+    #[tokio::test]
+    async fn manager_request_for_with_policies() {
+        let mut manager = Manager::default();
+        let policy_type = create_test_policy_type();
+
+        let policy1 = create_test_policy(
+            policy_type.clone(),
+            "if urgent then",
+            serde_json::json!({"is_active": true, "count": 10}),
+        );
+        let policy2 = create_test_policy(
+            policy_type,
+            "if contains hello then",
+            serde_json::json!({"message": "greeting"}),
+        );
+
+        manager.add(policy1);
+        manager.add(policy2);
+
+        let template = MessageCreateParams::default();
+        let text = "urgent hello world";
+
+        let result = manager.request_for(template, text).await;
+        assert!(result.is_ok());
+
+        let (report, req) = result.unwrap();
+        println!("Number of messages: {}", req.messages.len()); // Debug output
+        assert!(!req.messages.is_empty()); // At least one message
+        assert!(req.system.is_some());
+        assert!(req.tools.is_some());
+
+        // Verify the schema includes masked fields and special fields
+        let schema = report.schema();
+        assert!(schema["properties"].as_object().is_some());
+        let properties = schema["properties"].as_object().unwrap();
+
+        // Should have __rule_numbers__ special fields
+        assert!(properties.contains_key("__rule_numbers__"));
+
+        // Should have 3 masked fields (is_active, message, count)
+        // The masked fields will have obfuscated names but correct types
+        let masked_fields = properties.keys().filter(|k| !k.starts_with("__")).count();
+        assert_eq!(masked_fields, 3, "Expected 3 masked fields");
+
+        // Verify the types of the masked fields
+        let mut has_boolean = false;
+        let mut has_string = false;
+        let mut has_number = false;
+
+        for (key, value) in properties.iter() {
+            if !key.starts_with("__") {
+                if let Some(type_val) = value.get("type") {
+                    match type_val.as_str() {
+                        Some("boolean") => has_boolean = true,
+                        Some("string") => has_string = true,
+                        Some("number") | Some("integer") => has_number = true,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        assert!(has_boolean, "Should have a boolean field (is_active)");
+        assert!(has_string, "Should have a string field (message)");
+        assert!(has_number, "Should have a number field (count)");
+    }
+
+    #[tokio::test]
+    async fn manager_request_for_system_prompt() {
+        let mut manager = Manager::default();
+        let template = MessageCreateParams::default();
+        let text = "test";
+
+        let result = manager.request_for(template, text).await;
+        assert!(result.is_ok());
+
+        let (_, req) = result.unwrap();
+        let system = req.system.unwrap();
+        let system_str = match system {
+            SystemPrompt::String(s) => s,
+            SystemPrompt::Blocks(blocks) => {
+                // Extract text from the first SystemTextBlock
+                if let Some(text_block) = blocks.first() {
+                    text_block.block.text.clone()
+                } else {
+                    panic!("Expected text block in system prompt")
+                }
+            }
+        };
+
+        // Verify key parts of the system prompt
+        assert!(system_str.contains("Output JSON"));
+        assert!(system_str.contains("if and only if a rule matches"));
+    }
+
+    #[test]
+    fn manager_debug_format() {
+        let manager = Manager::default();
+        let debug_str = format!("{manager:?}");
+        assert!(debug_str.contains("Manager"));
+        assert!(debug_str.contains("policies"));
+    }
+
+    #[tokio::test]
+    async fn manager_request_includes_text_message() {
+        let mut manager = Manager::default();
+        let template = MessageCreateParams::default();
+        let test_text = "This is my special test text";
+
+        let result = manager.request_for(template, test_text).await;
+        assert!(result.is_ok());
+
+        let (_, req) = result.unwrap();
+
+        // Find the message containing our text
+        let mut found_text = false;
+        for message in &req.messages {
+            if let MessageParamContent::String(content) = &message.content {
+                if content.contains(test_text) {
+                    found_text = true;
+                    break;
+                }
+            }
+        }
+        assert!(found_text, "Request should include the input text");
+    }
+
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_field_order_applies_to_report_value() {
+        let policy_type = PolicyType::parse(
+            "type Ordered { label: string, flag: bool = false, count: number = 0 }",
+        )
+        .unwrap();
+        let policy = create_test_policy(
+            policy_type,
+            "Always apply",
+            serde_json::json!({"label": "x"}),
+        );
+        for (order, expected) in [
+            (FieldOrder::Declaration, vec!["label", "flag", "count"]),
+            (FieldOrder::Sorted, vec!["count", "flag", "label"]),
+            (FieldOrder::Insertion, vec!["flag", "count", "label"]),
+        ] {
+            let mut manager = Manager::default().with_field_order(order);
+            manager.add(policy.clone());
+            let (builder, _) = manager
+                .request_for(MessageCreateParams::default(), "text")
+                .await
+                .unwrap();
+            let mask = builder
+                .clone()
+                .consume_ir(serde_json::json!({}))
+                .unwrap()
+                .string_masks[0]
+                .mask
+                .clone();
+            let report = builder
+                .consume_ir(serde_json::json!({"__rule_numbers__": [1], mask: "x"}))
+                .unwrap();
+            let value = report.value();
+            let keys: Vec<_> = value.as_object().unwrap().keys().cloned().collect();
+            assert_eq!(keys, expected, "{order:?}");
+        }
+    }
+
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_naming_policy_recases_output_keys() {
+        let policy_type = PolicyType::parse(
+            r#"type Named { is_meeting: bool = false, when is_meeting { meeting_place: string = "office" } }"#,
+        )
+        .unwrap();
+        let mut manager = Manager::default().with_naming_policy(crate::NamingPolicy::CamelCase);
+        manager.add(create_test_policy(
+            policy_type,
+            "if it is an invitation",
+            serde_json::json!({"is_meeting": true}),
+        ));
+        assert_eq!(
+            manager.no_match_value(),
+            serde_json::json!({"isMeeting": false})
+        );
+        assert_ne!(
+            manager.cache_key("text"),
+            manager
+                .clone()
+                .with_naming_policy(crate::NamingPolicy::Preserve)
+                .cache_key("text")
+        );
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .unwrap();
+        let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+        let mut ir = serde_json::json!({"__rule_numbers__": [1]});
+        ir[mask] = true.into();
+        let report = builder.consume_ir(ir).unwrap();
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"isMeeting": true, "meetingPlace": "office"})
+        );
+        assert_eq!(
+            report.declared_value(),
+            serde_json::json!({"is_meeting": true, "meeting_place": "office"})
+        );
+    }
+
+    #[tokio::test]
+    async fn manager_prunes_policies_whose_precondition_fails() {
+        let policy_type = create_test_policy_type();
+        let mut relevant = create_test_policy(
+            policy_type.clone(),
+            "if it mentions consensus then",
+            serde_json::json!({"is_active": true}),
+        );
+        relevant.precondition = Some(crate::Precondition::any_of(["paxos", "raft"]));
+        let mut irrelevant = create_test_policy(
+            policy_type.clone(),
+            "if it mentions invoices then",
+            serde_json::json!({"count": 1}),
+        );
+        irrelevant.precondition = Some(crate::Precondition::contains("invoice"));
+        let unconditional =
+            create_test_policy(policy_type, "always", serde_json::json!({"message": "hi"}));
+        let mut manager = Manager::default();
+        manager.add(relevant);
+        manager.add(irrelevant);
+        manager.add(unconditional);
+
+        let (builder, req) = manager
+            .request_for(MessageCreateParams::default(), "a note on Raft")
+            .await
+            .unwrap();
+        assert_eq!(builder.pruned_policies(), &[1]);
+        let rendered = format!("{:?}", req.messages);
+        assert!(rendered.contains("consensus"));
+        assert!(!rendered.contains("invoices"));
+        assert!(rendered.contains("rule index=\\\"2\\\""));
+        let report = builder.consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(report.pruned_policies, vec![1]);
+        assert_eq!(report.masks_by_index.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn manager_asks_only_for_the_fields_requested() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "if it mentions consensus then",
+            serde_json::json!({"is_active": true, "count": 3}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            "if it mentions invoices then",
+            serde_json::json!({"message": "billing"}),
+        ));
+        let mut scoped = manager.clone().with_fields(["is_active"]);
+        let (builder, req) = scoped
+            .request_for(MessageCreateParams::default(), "a note on Raft")
+            .await
+            .unwrap();
+        assert_eq!(builder.pruned_policies(), &[1]);
+        let rendered = format!("{:?}", req.messages);
+        assert!(rendered.contains("consensus"));
+        assert!(!rendered.contains("invoices"));
+        let report = builder.consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(report.value(), serde_json::json!({"is_active": false}));
+        assert_ne!(scoped.cache_key("text"), manager.cache_key("text"));
+
+        let client = crate::testing::MockClient::replaying(serde_json::json!({
+            "__rule_numbers__": [],
+        }));
+        let report = manager
+            .apply_fields(
+                &["is_active"],
+                &client,
+                MessageCreateParams::default(),
+                "a note on Raft",
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.value(), serde_json::json!({"is_active": false}));
+        assert!(!format!("{:?}", client.requests()[0].messages).contains("invoices"));
+        assert!(manager.settings.fields.is_none());
+
+        let result = manager
+            .with_fields(["nope"])
+            .request_for(MessageCreateParams::default(), "text")
+            .await;
+        assert!(matches!(
+            result,
+            Err(ApplyError::Policy(PolicyError::UnknownField { field })) if field == "nope"
+        ));
+    }
+
+    #[tokio::test]
+    async fn manager_skips_llm_when_everything_is_pruned() {
+        let mut policy = create_test_policy(
+            create_test_policy_type(),
+            "if it mentions paxos then",
+            serde_json::json!({"is_active": true}),
+        );
+        policy.precondition = Some(crate::Precondition::contains("paxos"));
+        let mut manager = Manager::default();
+        manager.add(policy);
+        // No network is available in tests; an LLM call here would fail.
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let mut usage = Usage::new();
+        let report = manager
+            .apply(
+                &client,
+                MessageCreateParams::default(),
+                "nothing relevant",
+                Some(&mut usage),
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.pruned_policies, vec![0]);
+        assert_eq!(usage.iterations, 0);
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"is_active": false, "message": "default", "count": 0.0})
+        );
+    }
 
-    fn create_test_policy_type() -> PolicyType {
-        PolicyType {
-            name: "TestPolicy".to_string(),
-            fields: vec![
-                Field::Bool {
-                    name: "is_active".to_string(),
-                    default: Some(false),
-                    on_conflict: crate::OnConflict::Default,
-                },
-                Field::String {
-                    name: "message".to_string(),
-                    default: Some("default".to_string()),
-                    on_conflict: crate::OnConflict::Agreement,
-                },
-                Field::Number {
-                    name: "count".to_string(),
-                    default: Some(crate::t64(0.0)),
-                    on_conflict: crate::OnConflict::LargestValue,
-                },
-            ],
+    #[tokio::test]
+    async fn manager_isolates_chunks_that_time_out() {
+        let mut policy = create_test_policy(
+            create_test_policy_type(),
+            "if it mentions paxos then",
+            serde_json::json!({"is_active": true}),
+        );
+        policy.precondition = Some(crate::Precondition::contains("paxos"));
+        let mut manager = Manager::default().with_chunk_timeout(Duration::ZERO);
+        manager.add(policy);
+        // The middle chunk needs the LLM, which cannot answer within no time at all; the others
+        // are pruned and decided without it.
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let mut usage = Usage::new();
+        let chunked = manager
+            .apply_chunked(
+                &client,
+                MessageCreateParams::default(),
+                &["nothing relevant", "all about paxos", "still nothing"],
+                Some(&mut usage),
+            )
+            .await;
+        assert!(!chunked.is_complete());
+        assert_eq!(
+            chunked
+                .reports()
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        let errors = chunked.errors().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0],
+            (1, ApplyError::TimedOut { after_ms: 0 })
+        ));
+        assert!(errors[0].1.is_retryable());
+        assert_eq!(usage.iterations, 0);
+    }
+
+    #[tokio::test]
+    async fn manager_batches_nothing_when_every_text_is_decided_locally() {
+        let mut policy = create_test_policy(
+            create_test_policy_type(),
+            "if it mentions paxos then",
+            serde_json::json!({"is_active": true}),
+        );
+        policy.precondition = Some(crate::Precondition::contains("paxos"));
+        let mut manager =
+            Manager::default().with_model(Model::Custom("claude-haiku-4-5".to_string()));
+        manager.add(policy);
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let batches = BatchClient::new(Some("sk-ant-test".to_string())).unwrap();
+        let job_path =
+            std::env::temp_dir().join(format!("policyai-batch-{}.json", uuid::Uuid::new_v4()));
+        let batched = manager
+            .apply_via_batches(
+                &client,
+                &batches,
+                MessageCreateParams::default(),
+                &["nothing relevant", "still nothing"],
+                &job_path,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(batched.is_complete());
+        assert_eq!(batched.batch_id, None);
+        assert_eq!(batched.results.len(), 2);
+        assert!(!job_path.exists());
+    }
+
+    #[tokio::test]
+    async fn manager_refuses_a_batch_job_for_other_texts() {
+        let mut policy = create_test_policy(
+            create_test_policy_type(),
+            "if it mentions paxos then",
+            serde_json::json!({"is_active": true}),
+        );
+        policy.precondition = Some(crate::Precondition::contains("paxos"));
+        let mut manager =
+            Manager::default().with_model(Model::Custom("claude-haiku-4-5".to_string()));
+        manager.add(policy);
+        let job_path =
+            std::env::temp_dir().join(format!("policyai-batch-{}.json", uuid::Uuid::new_v4()));
+        let job = BatchJob {
+            batch_id: "msgbatch_1".to_string(),
+            policy_set_hash: manager.policy_set_hash(),
+            requests: vec![BatchJobRequest {
+                custom_id: "text-0".to_string(),
+                index: 0,
+                text_hash: format!("{:032x}", manager.cache_key("some other paxos").text),
+            }],
+        };
+        job.save(&job_path).unwrap();
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let batches = BatchClient::new(Some("sk-ant-test".to_string())).unwrap();
+        // The job is checked before anything is sent, so this never reaches the network.
+        let err = manager
+            .apply_via_batches(
+                &client,
+                &batches,
+                MessageCreateParams::default(),
+                &["all about paxos"],
+                &job_path,
+                None,
+            )
+            .await
+            .unwrap_err();
+        std::fs::remove_file(&job_path).unwrap();
+        assert!(matches!(err, ApplyError::BatchJobMismatch { .. }));
+        assert!(!err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn manager_sends_metadata_ahead_of_the_text() {
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "if it was sent after 5pm",
+            serde_json::json!({"is_active": true}),
+        ));
+        let before = manager.cache_key("hello");
+        let (_, plain) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        assert!(!format!("{:?}", plain.messages).contains("<metadata>"));
+
+        let metadata = Metadata::default()
+            .with_sender("ann@example.com")
+            .with_timestamp("2025-03-14T17:45:00-07:00");
+        let mut manager = manager.with_metadata(metadata);
+        assert_ne!(manager.cache_key("hello"), before);
+        let (_, req) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let rendered = format!("{:?}", req.messages);
+        let metadata = rendered.find("<timestamp>2025-03-14T17:45:00-07:00</timestamp>");
+        let text = rendered.find("<text>hello</text>");
+        assert!(metadata.is_some() && text.is_some());
+        assert!(metadata < text);
+
+        let prepared = manager.prepare(MessageCreateParams::default()).unwrap();
+        let (_, req) = prepared.request_for("hello");
+        assert!(format!("{:?}", req.messages).contains("<sender>ann@example.com</sender>"));
+
+        // A text's own metadata replaces the manager's for its call only.
+        let client = crate::testing::MockClient::replaying(serde_json::json!({
+            "__rule_numbers__": [],
+        }));
+        let own = Metadata::default().with_sender("bob@example.com");
+        manager
+            .apply_with_metadata(&client, MessageCreateParams::default(), "hello", own, None)
+            .await
+            .unwrap();
+        let rendered = format!("{:?}", client.requests()[0].messages);
+        assert!(rendered.contains("<sender>bob@example.com</sender>"));
+        assert!(!rendered.contains("ann@example.com"));
+        assert_eq!(
+            manager.settings.metadata.sender.as_deref(),
+            Some("ann@example.com")
+        );
+    }
+
+    #[tokio::test]
+    async fn manager_decides_exact_match_policies_locally() {
+        let policy_type = create_test_policy_type();
+        let mut internal = create_test_policy(
+            policy_type.clone(),
+            "if it is from a colleague",
+            serde_json::json!({"message": "internal", "count": 2}),
+        );
+        internal.exact_match = Some(crate::Precondition::contains("@example.com"));
+        let semantic = create_test_policy(
+            policy_type,
+            "if it is urgent",
+            serde_json::json!({"is_active": true}),
+        );
+        let mut manager = Manager::default();
+        manager.add(internal.clone());
+        manager.add(semantic);
+
+        let (builder, req) = manager
+            .request_for(
+                MessageCreateParams::default(),
+                "From: ann@example.com\nASAP!",
+            )
+            .await
+            .unwrap();
+        let rendered = format!("{:?}", req.messages);
+        assert!(!rendered.contains("colleague"));
+        assert!(rendered.contains("rule index=\\\"1\\\""));
+        assert!(!rendered.contains("rule index=\\\"2\\\""));
+        assert_eq!(builder.local_matches(), &[RuleIndex::FIRST.next()]);
+        let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+        let mut ir = serde_json::json!({"__rule_numbers__": [1]});
+        ir[mask] = true.into();
+        let report = builder.consume_ir(ir).unwrap();
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"is_active": true, "message": "internal", "count": 2})
+        );
+        assert_eq!(
+            report.matched_rules(),
+            vec![RuleIndex::FIRST, RuleIndex::FIRST.next()]
+        );
+
+        // With only local policies there is nothing to ask the LLM, which is unreachable here.
+        let mut manager = Manager::default();
+        manager.add(internal);
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let mut usage = Usage::new();
+        let report = manager
+            .apply(
+                &client,
+                MessageCreateParams::default(),
+                "From: bob@example.com",
+                Some(&mut usage),
+            )
+            .await
+            .unwrap();
+        assert_eq!(usage.iterations, 0);
+        assert_eq!(report.value()["message"], "internal");
+    }
+
+    #[tokio::test]
+    async fn manager_logs_usage_of_every_apply() {
+        let mut policy = create_test_policy(
+            create_test_policy_type(),
+            "if it mentions paxos then",
+            serde_json::json!({"is_active": true}),
+        );
+        policy.precondition = Some(crate::Precondition::contains("paxos"));
+        let path =
+            std::env::temp_dir().join(format!("policyai-usage-{}.jsonl", uuid::Uuid::new_v4()));
+        let log = Arc::new(crate::JsonlUsageLog::new(&path));
+        let mut manager = Manager::default()
+            .with_model(Model::Custom("claude-haiku-4-5".to_string()))
+            .with_usage_log(log);
+        manager.add(policy);
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        for text in ["nothing relevant", "still nothing"] {
+            manager
+                .apply(&client, MessageCreateParams::default(), text, None)
+                .await
+                .unwrap();
         }
+        let records = crate::JsonlUsageLog::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].model, "claude-haiku-4-5");
+        assert_eq!(records[0].policy_set_hash, records[1].policy_set_hash);
+        assert_eq!(
+            records[0].policy_set_hash,
+            format!("{:032x}", manager.cache_key("").policies)
+        );
+        assert_eq!(records[0].total_tokens(), 0);
+        assert_eq!(records[0].cost_usd, Some(0.0));
+        assert!(records.iter().all(|r| r.succeeded && !r.cache_hit));
     }
 
-    fn create_test_policy(r#type: PolicyType, prompt: &str, action: serde_json::Value) -> Policy {
-        Policy {
-            r#type,
-            prompt: prompt.to_string(),
-            action,
+    #[tokio::test]
+    async fn manager_serves_repeated_input_from_cache() {
+        let mut policy = create_test_policy(
+            create_test_policy_type(),
+            "if it mentions paxos then",
+            serde_json::json!({"is_active": true}),
+        );
+        policy.precondition = Some(crate::Precondition::contains("paxos"));
+        let cache = Arc::new(crate::MemoryCache::default());
+        let mut manager = Manager::default().with_cache(cache.clone());
+        manager.add(policy);
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let mut usage = Usage::new();
+        manager
+            .apply(
+                &client,
+                MessageCreateParams::default(),
+                "nothing relevant",
+                Some(&mut usage),
+            )
+            .await
+            .unwrap();
+        assert_eq!((usage.cache_hits, usage.cache_misses), (0, 1));
+        assert_eq!(cache.len(), 1);
+
+        let mut clone = manager.clone();
+        let report = clone
+            .apply(
+                &client,
+                MessageCreateParams::default(),
+                "nothing relevant",
+                Some(&mut usage),
+            )
+            .await
+            .unwrap();
+        assert_eq!((usage.cache_hits, usage.cache_misses), (1, 0));
+        assert_eq!(report.pruned_policies, vec![0]);
+        assert!(cache.get(&manager.cache_key("nothing relevant")).is_some());
+        assert!(cache.get(&manager.cache_key("something else")).is_none());
+    }
+
+    #[tokio::test]
+    async fn manager_rule_numbers_agree_across_prompt_masks_and_report() {
+        let policy_type = create_test_policy_type();
+        let mut pruned = create_test_policy(
+            policy_type.clone(),
+            "if it mentions invoices then",
+            serde_json::json!({"count": 1}),
+        );
+        pruned.precondition = Some(crate::Precondition::contains("invoice"));
+        let policies = [
+            create_test_policy(
+                policy_type.clone(),
+                "first rule",
+                serde_json::json!({"is_active": true}),
+            ),
+            pruned,
+            create_test_policy(
+                policy_type.clone(),
+                "second rule",
+                serde_json::json!({"message": "hi"}),
+            ),
+            create_test_policy(policy_type, "third rule", serde_json::json!({"count": 3})),
+        ];
+        let mut manager = Manager::default();
+        for policy in policies {
+            manager.add(policy);
+        }
+        let (builder, req) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        assert_eq!(builder.next_rule(), RuleIndex::from_number(4).unwrap());
+
+        // The prompt numbers rules from one, skipping the pruned policy.
+        let rendered = format!("{:?}", req.messages);
+        for (number, prompt) in [(1, "first"), (2, "second"), (3, "third")] {
+            assert!(rendered.contains(&format!("rule index=\\\"{number}\\\">{prompt} rule")));
         }
+        assert!(!rendered.contains("rule index=\\\"4\\\""));
+
+        // Every mask is listed under the rule it records.
+        let shape = builder.clone().consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(shape.masks_by_index.len(), 3);
+        let mut ir = serde_json::json!({});
+        let masks = shape
+            .bool_masks
+            .iter()
+            .map(|m| (m.policy_index, m.mask.clone(), serde_json::json!(true)))
+            .chain(
+                shape
+                    .string_masks
+                    .iter()
+                    .map(|m| (m.policy_index, m.mask.clone(), serde_json::json!("hi"))),
+            )
+            .chain(
+                shape
+                    .number_masks
+                    .iter()
+                    .map(|m| (m.policy_index, m.mask.clone(), serde_json::json!(3))),
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(masks.len(), 3);
+        for (number, mask, value) in masks.iter() {
+            let rule = RuleIndex::from_number(*number).unwrap();
+            assert!(shape.masks_for_rule(rule).unwrap().contains(mask));
+            ir[mask] = value.clone();
+        }
+        assert_eq!(
+            masks.iter().map(|(n, _, _)| *n).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        // Setting a rule's masks reports exactly that rule as matched.
+        let report = builder.clone().consume_ir(ir).unwrap();
+        assert_eq!(
+            report.matched_rules(),
+            (1..=3)
+                .map(|n| RuleIndex::from_number(n).unwrap())
+                .collect::<Vec<_>>()
+        );
+        let (_, second, value) = &masks[1];
+        let report = builder
+            .consume_ir(serde_json::json!({second: value}))
+            .unwrap();
+        assert_eq!(report.matched_rules(), vec![RuleIndex::FIRST.next()]);
+    }
+
+    #[tokio::test]
+    async fn manager_compact_retry_resends_only_the_latest_correction() {
+        let mut manager = Manager::default().with_compact_retry(true);
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "test prompt",
+            serde_json::json!({"is_active": true}),
+        ));
+        let (_report, base) = manager
+            .request_for(MessageCreateParams::default(), "test text")
+            .await
+            .unwrap();
+        let MessageParamContent::Array(blocks) = &base.messages.last().unwrap().content else {
+            panic!("expected the breakpoint to convert the last message to blocks");
+        };
+        let Some(ContentBlock::Text(last)) = blocks.last() else {
+            panic!("expected a text block");
+        };
+        assert!(last.cache_control.is_some());
+
+        let answer = |id: &str| {
+            vec![ContentBlock::ToolUse(claudius::ToolUseBlock::new(
+                id,
+                manager.tool_name(),
+                serde_json::json!({}),
+            ))]
+        };
+        let first = manager.retry_request(&base, &answer("a"), "a", "fix a".to_string());
+        assert_eq!(first.messages.len(), base.messages.len() + 2);
+        let compact = manager.retry_request(&base, &answer("b"), "b", "fix b".to_string());
+        assert_eq!(compact.messages.len(), base.messages.len() + 2);
+        let rendered = format!("{:?}", compact.messages);
+        assert!(rendered.contains("fix b"));
+        assert!(!rendered.contains("fix a"));
+        let full = manager.retry_request(&first, &answer("b"), "b", "fix b".to_string());
+        assert_eq!(full.messages.len(), base.messages.len() + 4);
+    }
+
+    #[tokio::test]
+    async fn manager_default_request_has_no_cache_breakpoint() {
+        let mut manager = Manager::default();
+        let (_report, req) = manager
+            .request_for(MessageCreateParams::default(), "test text")
+            .await
+            .unwrap();
+        assert!(!format!("{:?}", req.messages).contains("Ephemeral"));
+    }
+
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_field_groups_are_gated() {
+        let policy_type = PolicyType::parse(
+            r#"type Email {
+                is_meeting_request: bool = false,
+                when is_meeting_request {
+                    meeting_place: string = "office",
+                },
+            }"#,
+        )
+        .unwrap();
+        let gate = create_test_policy(
+            policy_type.clone(),
+            "if it asks to meet",
+            serde_json::json!({"is_meeting_request": true}),
+        );
+        let place = create_test_policy(
+            policy_type.clone(),
+            "if it names a place",
+            serde_json::json!({"meeting_place": null}),
+        );
+
+        // Without a rule that can set the gate, the group's fields are not offered.
+        let mut manager = Manager::default();
+        manager.add(place.clone());
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .unwrap();
+        let properties = builder.schema()["properties"].as_object().unwrap().clone();
+        assert_eq!(properties.len(), 2);
+        let report = builder.consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"is_meeting_request": false})
+        );
+
+        let mut manager = Manager::default();
+        manager.add(gate);
+        manager.add(place);
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .unwrap();
+        let shape = builder.clone().consume_ir(serde_json::json!({})).unwrap();
+        let gate_mask = shape.masks_by_index[0][0].clone();
+        let place_mask = shape.masks_by_index[1][0].clone();
+        let schema = builder.schema();
+        assert!(schema["properties"][&place_mask]["description"]
+            .as_str()
+            .unwrap()
+            .contains(&gate_mask));
+
+        // Gate open: the grouped field is applied.
+        let report = builder
+            .clone()
+            .consume_ir(serde_json::json!({&gate_mask: true, &place_mask: "cafe"}))
+            .unwrap();
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"is_meeting_request": true, "meeting_place": "cafe"})
+        );
+        assert_eq!(report.rules_matched, vec![1, 2]);
+
+        // Gate closed: the grouped field and its default are left out, but the rule still
+        // counts as matched.
+        let report = builder
+            .consume_ir(serde_json::json!({&gate_mask: false, &place_mask: "cafe"}))
+            .unwrap();
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"is_meeting_request": false})
+        );
+        assert_eq!(
+            report.matched_rules(),
+            vec![RuleIndex::FIRST, RuleIndex::FIRST.next()]
+        );
+    }
+
+    #[tokio::test]
+    async fn manager_on_conflict_overrides_replace_declared_strategy() {
+        let policy_type = create_test_policy_type();
+        let policies = [
+            create_test_policy(policy_type.clone(), "one", serde_json::json!({"count": 1})),
+            create_test_policy(policy_type, "two", serde_json::json!({"count": 2})),
+        ];
+        let policies = &policies;
+        let run = |manager: Manager| async move {
+            let mut manager = manager;
+            for policy in policies.iter().cloned() {
+                manager.add(policy);
+            }
+            let key = manager.cache_key("text");
+            let (builder, _) = manager
+                .request_for(MessageCreateParams::default(), "text")
+                .await
+                .unwrap();
+            let shape = builder.clone().consume_ir(serde_json::json!({})).unwrap();
+            let ir = serde_json::json!({
+                &shape.masks_by_index[0][0]: 1,
+                &shape.masks_by_index[1][0]: 2,
+            });
+            (builder.consume_ir(ir).unwrap(), key)
+        };
+
+        // count is declared LargestValue.
+        let (report, declared_key) = run(Manager::default()).await;
+        assert!(report.conflicts().is_empty());
+        assert_eq!(report.value()["count"], serde_json::json!(2));
+
+        let strict = BTreeMap::from([("count".to_string(), OnConflict::Agreement)]);
+        let (report, strict_key) = run(Manager::default().with_on_conflict_overrides(strict)).await;
+        assert_eq!(report.conflicts().len(), 1);
+        assert_ne!(declared_key, strict_key);
+    }
+
+    #[tokio::test]
+    async fn manager_applies_overrides_for_one_call() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default().with_unmasked_fields(["count"]);
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "one",
+            serde_json::json!({"count": 1}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            "two",
+            serde_json::json!({"count": 2}),
+        ));
+        let client = crate::testing::MockClient::replaying(serde_json::json!({
+            "__rule_numbers__": [1, 2],
+            "count@1": 1,
+            "count@2": 2,
+        }));
+
+        let strict = BTreeMap::from([("count".to_string(), OnConflict::Agreement)]);
+        let report = manager
+            .apply_with_overrides(
+                &client,
+                MessageCreateParams::default(),
+                "text",
+                strict,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.conflicts().len(), 1);
+
+        // The overrides were for that call only.
+        let report = manager
+            .apply(&client, MessageCreateParams::default(), "text", None)
+            .await
+            .unwrap();
+        assert!(report.conflicts().is_empty());
+        assert_eq!(report.value()["count"], serde_json::json!(2));
     }
 
     #[test]
-    fn manager_default_is_empty() {
+    fn manager_simulates_conflicts_without_an_llm() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "if urgent",
+            serde_json::json!({"count": 3, "message": "urgent"}),
+        ));
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "if late",
+            serde_json::json!({"count": 5, "message": "late"}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            "if audited",
+            serde_json::json!({}),
+        ));
+
+        let report = manager
+            .simulate(&[
+                serde_json::json!({"count": 3, "message": "urgent"}),
+                serde_json::json!({"count": 5, "message": "late"}),
+                serde_json::json!({}),
+            ])
+            .unwrap();
+        assert_eq!(report.value()["count"], 5);
+        assert_eq!(report.conflicts().len(), 1);
+        assert_eq!(report.matched_rules().len(), 3);
+
+        let report = manager
+            .simulate(&[serde_json::Value::Null, serde_json::json!({"count": 5})])
+            .unwrap();
+        assert_eq!(report.value()["count"], 5);
+        assert_eq!(report.value()["message"], "default");
+        assert!(report.conflicts().is_empty());
+        assert_eq!(report.skip_reason(0), Some(SkipReason::NotMatched));
+
+        assert!(matches!(
+            manager.simulate(&[serde_json::json!({"is_active": true})]),
+            Err(ApplyError::InvalidResponse { .. })
+        ));
+        assert!(matches!(
+            manager.simulate(&vec![serde_json::Value::Null; 4]),
+            Err(ApplyError::InvalidResponse { .. })
+        ));
+    }
+
+    /// A provider that reports every rule as not matching.
+    #[derive(Debug)]
+    struct NoMatches;
+
+    impl LlmProvider for NoMatches {
+        fn send(
+            &self,
+            req: MessageCreateParams,
+        ) -> futures::future::BoxFuture<'_, Result<Message, claudius::Error>> {
+            Box::pin(async move {
+                Ok(crate::tool_use_reply(
+                    "msg_test",
+                    req.model,
+                    Manager::DEFAULT_TOOL_NAME,
+                    serde_json::json!({"__rule_numbers__": [], "__justification__": "none"}),
+                    claudius::Usage::new(100, 10),
+                ))
+            })
+        }
+    }
+
+    /// A provider that answers with each of its replies in turn.
+    #[derive(Debug)]
+    struct Scripted(std::sync::Mutex<Vec<serde_json::Value>>);
+
+    impl LlmProvider for Scripted {
+        fn send(
+            &self,
+            req: MessageCreateParams,
+        ) -> futures::future::BoxFuture<'_, Result<Message, claudius::Error>> {
+            let input = self.0.lock().unwrap().remove(0);
+            Box::pin(async move {
+                Ok(crate::tool_use_reply(
+                    "msg_test",
+                    req.model,
+                    Manager::DEFAULT_TOOL_NAME,
+                    input,
+                    claudius::Usage::new(100, 10),
+                ))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn manager_strict_fields_asks_for_fields_a_matched_rule_left_unset() {
+        let run = |strict_fields: bool| async move {
+            let mut manager = Manager::default()
+                .with_unmasked_fields(["is_active", "count"])
+                .with_strict_fields(strict_fields);
+            manager.add(create_test_policy(
+                create_test_policy_type(),
+                "if urgent",
+                serde_json::json!({"is_active": true, "count": 3}),
+            ));
+            let provider = Scripted(std::sync::Mutex::new(vec![
+                serde_json::json!({"__rule_numbers__": [1], "is_active@1": true}),
+                serde_json::json!({"__rule_numbers__": [1], "is_active@1": true, "count@1": 3}),
+            ]));
+            let template = MessageCreateParams {
+                max_tokens: 1024,
+                model: Model::Known(claudius::KnownModel::ClaudeSonnet40),
+                ..Default::default()
+            };
+            let mut usage = Usage::new();
+            let report = manager
+                .apply(&provider, template, "hello", Some(&mut usage))
+                .await
+                .unwrap();
+            (report, usage.inconsistency_retries)
+        };
+        let (report, retries) = run(false).await;
+        assert_eq!(retries, 0);
+        assert_eq!(report.value()["count"], 0.0);
+        let (report, retries) = run(true).await;
+        assert_eq!(retries, 1);
+        assert_eq!(report.value()["count"], 3);
+    }
+
+    /// A provider that rejects every request's credentials.
+    #[derive(Debug)]
+    struct BadKey;
+
+    impl LlmProvider for BadKey {
+        fn send(
+            &self,
+            _: MessageCreateParams,
+        ) -> futures::future::BoxFuture<'_, Result<Message, claudius::Error>> {
+            Box::pin(async { Err(claudius::Error::authentication("invalid x-api-key")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn manager_answers_short_inputs_with_defaults() {
+        let template = MessageCreateParams {
+            max_tokens: 1024,
+            model: Model::Known(claudius::KnownModel::ClaudeSonnet40),
+            ..Default::default()
+        };
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default().with_min_input_chars(3);
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "if urgent",
+            serde_json::json!({"message": "urgent"}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            "if late",
+            serde_json::json!({"count": 5}),
+        ));
+
+        for text in ["", " \n\t ", "ok"] {
+            let mut usage = Usage::new();
+            let report = manager
+                .apply(&BadKey, template.clone(), text, Some(&mut usage))
+                .await
+                .unwrap();
+            assert_eq!(report.value()["message"], "default");
+            assert!(report.matched_rules().is_empty());
+            assert!(report.pruned_policies.is_empty());
+            assert_eq!(report.skip_reason(0), Some(SkipReason::EmptyInput));
+            assert_eq!(report.skip_reason(1), Some(SkipReason::EmptyInput));
+            assert_eq!(usage.iterations, 0);
+        }
+        assert!(manager
+            .apply(&BadKey, template.clone(), "o k !", None)
+            .await
+            .is_err());
+        assert!(Manager::default()
+            .apply(&BadKey, template, "", None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn manager_health_check_reports_each_check() {
+        let template = MessageCreateParams {
+            max_tokens: 1024,
+            model: Model::Known(claudius::KnownModel::ClaudeSonnet40),
+            ..Default::default()
+        };
         let manager = Manager::default();
-        assert!(manager.is_empty());
-        assert_eq!(manager.len(), 0);
+        let health = manager.health_check(&NoMatches, template.clone()).await;
+        assert!(health.is_healthy(), "{health:?}");
+        assert_eq!(
+            health.model,
+            Model::Known(claudius::KnownModel::ClaudeSonnet40).to_string()
+        );
+        assert_eq!(health.error, None);
+
+        let health = manager.health_check(&BadKey, template.clone()).await;
+        assert_eq!(health.credentials, HealthCheck::Failed);
+        assert_eq!(health.model_available, HealthCheck::NotReached);
+        assert!(health.error.unwrap().contains("invalid x-api-key"));
+
+        let pinned = Manager::default().with_allowed_models(["claude-haiku"]);
+        let health = pinned.health_check(&NoMatches, template).await;
+        assert!(!health.is_healthy());
+        assert_eq!(health.credentials, HealthCheck::NotReached);
+        assert_eq!(health.model_available, HealthCheck::Failed);
+    }
+
+    #[tokio::test]
+    async fn manager_applies_through_any_provider() {
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "one",
+            serde_json::json!({"count": 1}),
+        ));
+        let mut usage = Usage::new();
+        let report = manager
+            .apply(
+                &NoMatches,
+                MessageCreateParams::default(),
+                "text",
+                Some(&mut usage),
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.message_ids, ["msg_test"]);
+        assert!(report.rules_matched.is_empty());
+        assert_eq!(usage.iterations, 1);
+        assert_eq!(usage.input_tokens(), 100);
+
+        // NoMatches cannot stream, so the request is sent whole and there are no partials.
+        let mut partials = 0;
+        let report = manager
+            .apply_streaming(
+                &NoMatches,
+                MessageCreateParams::default(),
+                "text",
+                None,
+                |_| partials += 1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(partials, 0);
+        assert_eq!(report.message_ids, ["msg_test"]);
+    }
+
+    #[tokio::test]
+    async fn manager_reports_carry_the_input_hash() {
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "one",
+            serde_json::json!({"count": 1}),
+        ));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .unwrap();
+        let report = builder.consume_ir(serde_json::json!({})).unwrap();
+        let hash = manager.cache_key("text").to_string();
+        assert_eq!(report.input_hash.as_deref(), Some(hash.as_str()));
+        let (builder, _) = manager
+            .prepare(MessageCreateParams::default())
+            .unwrap()
+            .request_for("text");
+        let report = builder.consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(report.input_hash, Some(hash));
+        let report = Report::migrate(serde_json::to_value(&report).unwrap()).unwrap();
+        assert!(report.input_hash.is_some());
+    }
+
+    #[tokio::test]
+    async fn manager_reports_carry_the_policy_set_hash() {
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "one",
+            serde_json::json!({"count": 1}),
+        ));
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "two",
+            serde_json::json!({"count": 2}),
+        ));
+        let hash = manager.policy_set_hash();
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .unwrap();
+        let report = builder.consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(report.policy_set_hash.as_deref(), Some(hash.as_str()));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "other text")
+            .await
+            .unwrap();
+        let report = builder.consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(report.policy_set_hash.as_deref(), Some(hash.as_str()));
+        let report = Report::migrate(serde_json::to_value(&report).unwrap()).unwrap();
+        assert_eq!(report.policy_set_hash.as_deref(), Some(hash.as_str()));
+
+        // The same policies in another order are another policy set.
+        let mut reordered = Manager::default();
+        reordered.add(create_test_policy(
+            create_test_policy_type(),
+            "two",
+            serde_json::json!({"count": 2}),
+        ));
+        reordered.add(create_test_policy(
+            create_test_policy_type(),
+            "one",
+            serde_json::json!({"count": 1}),
+        ));
+        assert_ne!(reordered.policy_set_hash(), hash);
+        let mut edited = Manager::default();
+        edited.add(create_test_policy(
+            create_test_policy_type(),
+            "one",
+            serde_json::json!({"count": 1}),
+        ));
+        edited.add(create_test_policy(
+            create_test_policy_type(),
+            "two, edited",
+            serde_json::json!({"count": 2}),
+        ));
+        assert_ne!(edited.policy_set_hash(), hash);
+    }
+
+    #[tokio::test]
+    async fn manager_settles_highest_priority_by_policy_not_rule_number() {
+        let mut manager = Manager::default()
+            .with_unmasked_fields(["message"])
+            .with_on_conflict_overrides(BTreeMap::from([(
+                "message".to_string(),
+                OnConflict::HighestPriority,
+            )]));
+        // Decided locally, so it becomes the last rule even though it was added first.
+        let mut escalate = create_test_policy(
+            create_test_policy_type(),
+            "from the CEO",
+            serde_json::json!({"message": "escalate"}),
+        );
+        escalate.exact_match = Some(crate::Precondition::contains("CEO"));
+        escalate.priority = Some(10);
+        manager.add(escalate);
+        let mut reply = create_test_policy(
+            create_test_policy_type(),
+            "if urgent",
+            serde_json::json!({"message": "reply"}),
+        );
+        reply.priority = Some(1);
+        manager.add(reply);
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "The CEO says it is urgent")
+            .await
+            .unwrap();
+        let report = builder
+            .consume_ir(serde_json::json!({"__rule_numbers__": [1], "message@1": "reply"}))
+            .unwrap();
+        assert_eq!(report.value()["message"], "escalate");
+        assert_eq!(report.sources["message"], 2);
+        assert_eq!(report.policy_priorities, BTreeMap::from([(1, 1), (2, 10)]));
+        assert!(report.conflicts().is_empty());
+        assert_eq!(
+            report.resolutions()[0].outcome,
+            crate::ResolutionOutcome::Prioritized
+        );
+    }
+
+    #[tokio::test]
+    async fn manager_settles_first_and_last_added_by_policy_not_rule_number() {
+        for (on_conflict, expected) in [
+            (OnConflict::FirstWins, "escalate"),
+            (OnConflict::LastWins, "reply"),
+        ] {
+            let mut manager = Manager::default()
+                .with_unmasked_fields(["message"])
+                .with_on_conflict_overrides(BTreeMap::from([("message".to_string(), on_conflict)]));
+            // Decided locally, so it becomes the last rule even though it was added first.
+            let mut escalate = create_test_policy(
+                create_test_policy_type(),
+                "from the CEO",
+                serde_json::json!({"message": "escalate"}),
+            );
+            escalate.exact_match = Some(crate::Precondition::contains("CEO"));
+            manager.add(escalate);
+            manager.add(create_test_policy(
+                create_test_policy_type(),
+                "if urgent",
+                serde_json::json!({"message": "reply"}),
+            ));
+            let (builder, _) = manager
+                .request_for(MessageCreateParams::default(), "The CEO says it is urgent")
+                .await
+                .unwrap();
+            let report = builder
+                .consume_ir(serde_json::json!({"__rule_numbers__": [1], "message@1": "reply"}))
+                .unwrap();
+            assert_eq!(report.value()["message"], expected, "{on_conflict:?}");
+            assert_eq!(report.policy_positions, BTreeMap::from([(1, 1), (2, 0)]));
+            assert!(report.conflicts().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn manager_reads_nulls_as_configured() {
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "if urgent",
+            serde_json::json!({"is_active": true, "count": 3}),
+        ));
+        let mut manager = manager.with_null_handling(BTreeMap::from([
+            ("is_active".to_string(), OnNull::Unset),
+            ("count".to_string(), OnNull::Absent),
+        ]));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let masks = builder.masks_for_rule(RuleIndex::FIRST).unwrap().to_vec();
+        let ir = masks
+            .iter()
+            .map(|mask| (mask.clone(), serde_json::Value::Null))
+            .collect::<serde_json::Map<_, _>>();
+        let report = builder.consume_ir(ir.into()).unwrap();
+        assert!(report.errors().is_empty(), "{:?}", report.errors());
+        assert_eq!(report.matched_rules(), vec![RuleIndex::FIRST]);
+    }
+
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_enum_fallbacks_settle_failed_agreement() {
+        let policy_type = PolicyType::parse(
+            r#"type T {
+                severity: ["low", "high", "critical"] @ agreement,
+                queue: ["triage", "billing", "legal"] @ agreement = "triage",
+            }"#,
+        )
+        .unwrap();
+        let policies = [
+            create_test_policy(
+                policy_type.clone(),
+                "one",
+                serde_json::json!({"severity": "critical", "queue": "billing"}),
+            ),
+            create_test_policy(
+                policy_type,
+                "two",
+                serde_json::json!({"severity": "high", "queue": "legal"}),
+            ),
+        ];
+        let policies = &policies;
+        let run = |manager: Manager| async move {
+            let mut manager = manager;
+            for policy in policies.iter().cloned() {
+                manager.add(policy);
+            }
+            let key = manager.cache_key("text");
+            let (builder, _) = manager
+                .request_for(MessageCreateParams::default(), "text")
+                .await
+                .unwrap();
+            let shape = builder.clone().consume_ir(serde_json::json!({})).unwrap();
+            let mut ir = serde_json::Map::new();
+            for masks in shape.masks_by_index.iter() {
+                for mask in masks.iter() {
+                    ir.insert(mask.clone(), true.into());
+                }
+            }
+            (builder.consume_ir(ir.into()).unwrap(), key)
+        };
+
+        let (report, plain_key) = run(Manager::default()).await;
+        assert_eq!(report.conflicts().len(), 2);
+        assert_eq!(report.value()["severity"], "critical");
+        assert_eq!(report.value()["queue"], "billing");
+
+        let order = vec![EnumFallback::Default, EnumFallback::LastDeclared];
+        let fallbacks = BTreeMap::from([
+            ("severity".to_string(), order.clone()),
+            ("queue".to_string(), order),
+        ]);
+        let (report, fallback_key) = run(Manager::default().with_enum_fallbacks(fallbacks)).await;
+        assert_eq!(report.conflicts().len(), 2);
+        // severity declares no default, so the value declared last wins.
+        assert_eq!(report.value()["severity"], "critical");
+        assert_eq!(report.value()["queue"], "triage");
+        assert_ne!(plain_key, fallback_key);
+    }
+
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_extracts_the_latest_datetime() {
+        let policy_type = PolicyType::parse("type T { due: datetime @ latest wins }").unwrap();
+        let mut manager = Manager::default();
+        for prompt in ["the deadline", "the follow-up date"] {
+            manager.add(create_test_policy(
+                policy_type.clone(),
+                prompt,
+                serde_json::json!({"due": null}),
+            ));
+        }
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .unwrap();
+        let shape = builder.clone().consume_ir(serde_json::json!({})).unwrap();
+        assert_eq!(shape.datetime_masks.len(), 2);
+        let mut ir = serde_json::Map::new();
+        for (mask, value) in shape
+            .datetime_masks
+            .iter()
+            .zip(["2024-03-08T17:00:00Z", "2024-03-09T09:00:00+01:00"])
+        {
+            ir.insert(mask.mask.clone(), value.into());
+        }
+        let report = builder.consume_ir(ir.into()).unwrap();
+        assert_eq!(report.value()["due"], "2024-03-09T09:00:00+01:00");
+        assert!(report.conflicts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn manager_partial_ir_yields_fields_as_they_complete() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "first rule",
+            serde_json::json!({"message": "urgent"}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            "second rule",
+            serde_json::json!({"count": 7}),
+        ));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let shape = builder.clone().consume_ir(serde_json::json!({})).unwrap();
+        let document = serde_json::json!({
+            "__rule_numbers__": [1, 2],
+            &shape.masks_by_index[0][0]: "urgent",
+            &shape.masks_by_index[1][0]: 7,
+        })
+        .to_string();
+        let mut partial = PartialJson::default();
+        let mut messages = vec![];
+        for c in document.chars() {
+            partial.push(&c.to_string());
+            let report = builder
+                .clone()
+                .consume_ir(partial.value().unwrap())
+                .unwrap();
+            let value = report.value();
+            if messages.last() != Some(&value) {
+                messages.push(value);
+            }
+        }
+        assert_eq!(
+            messages,
+            vec![
+                serde_json::json!({"is_active": false, "message": "default", "count": 0.0}),
+                serde_json::json!({"is_active": false, "message": "urgent", "count": 0.0}),
+                serde_json::json!({"is_active": false, "message": "urgent", "count": 7}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn manager_reports_matches_of_audit_only_rules() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "if the email is urgent",
+            serde_json::json!({"is_active": true}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            "if the email mentions a competitor",
+            serde_json::json!({}),
+        ));
+        let (builder, req) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let shape = builder.clone().consume_ir(serde_json::json!({})).unwrap();
+        let indicator = shape.masks_by_index[1][0].clone();
+        assert!(format!("{:?}", req.messages).contains(&indicator));
+        assert!(shape.rules_matched.is_empty());
+
+        let report = builder
+            .consume_ir(serde_json::json!({&indicator: true}))
+            .unwrap();
+        assert_eq!(report.matched_rules(), vec![RuleIndex::from_position(1)]);
+        assert_eq!(
+            report.matched_audit_rules(),
+            vec![RuleIndex::from_position(1)]
+        );
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"is_active": false, "message": "default", "count": 0.0})
+        );
     }
 
-    #[test]
-    fn manager_add_single_policy() {
+    #[tokio::test]
+    async fn manager_asks_for_confidence_only_when_verifying_it() {
         let mut manager = Manager::default();
-        let policy_type = create_test_policy_type();
-        let policy = create_test_policy(
-            policy_type,
-            "test prompt",
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "if urgent",
             serde_json::json!({"is_active": true}),
+        ));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        assert!(builder.schema()["properties"]
+            .get("__confidence__")
+            .is_none());
+
+        let mut verifying = manager
+            .clone()
+            .with_verification(Verification::default().with_min_confidence(0.8));
+        let (builder, _) = verifying
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let schema = builder.schema();
+        assert!(schema["properties"]["__confidence__"].is_object());
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("__confidence__")));
+        assert_ne!(verifying.cache_key("hello"), manager.cache_key("hello"));
+    }
+
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_translations_reach_the_report() {
+        let policy_type =
+            PolicyType::parse(r#"type T { priority: ["low", "high"] = "low", tags: [string] }"#)
+                .unwrap();
+        let mut manager = Manager::default().with_translations(
+            Translations::default()
+                .with_translation("priority", "low", 1)
+                .with_translation("priority", "high", 3),
+        );
+        manager.add(create_test_policy(
+            policy_type,
+            "if urgent",
+            serde_json::json!({"priority": "high"}),
+        ));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let mask = builder
+            .clone()
+            .consume_ir(serde_json::json!({}))
+            .unwrap()
+            .masks_by_index[0][0]
+            .clone();
+        let report = builder
+            .consume_ir(serde_json::json!({"__rule_numbers__": [1], &mask: true}))
+            .unwrap();
+        assert_eq!(report.value(), serde_json::json!({"priority": "high"}));
+        assert_eq!(
+            report.value_with_translations(),
+            serde_json::json!({"priority": 3})
         );
+        assert_ne!(
+            manager.cache_key("hello"),
+            manager
+                .clone()
+                .with_translations(Translations::default())
+                .cache_key("hello")
+        );
+    }
 
-        manager.add(policy);
-        assert!(!manager.is_empty());
-        assert_eq!(manager.len(), 1);
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_no_match_value_is_what_apply_returns_without_matches() {
+        let policy_type = PolicyType::parse(
+            r#"type T {
+                meeting: bool = false,
+                when meeting { place: string = "office" },
+                label: string,
+                score: number = 3,
+                tags: [string],
+            }"#,
+        )
+        .unwrap();
+        let mut manager = Manager::default().with_field_order(FieldOrder::Sorted);
+        assert_eq!(manager.no_match_value(), serde_json::json!({}));
+        manager.add(create_test_policy(
+            policy_type,
+            "if it is a meeting",
+            serde_json::json!({"meeting": true, "place": "cafe", "tags": ["x"]}),
+        ));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let report = builder
+            .consume_ir(serde_json::json!({"__rule_numbers__": []}))
+            .unwrap();
+        assert_eq!(report.value(), manager.no_match_value());
+        assert_eq!(
+            serde_json::to_string(&manager.no_match_value()).unwrap(),
+            r#"{"meeting":false,"score":3.0}"#
+        );
     }
 
     #[test]
-    fn manager_add_multiple_policies_same_type() {
-        let mut manager = Manager::default();
+    fn manager_joint_request_tags_texts_and_nests_outputs() {
         let policy_type = create_test_policy_type();
-
-        let policy1 = create_test_policy(
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
             policy_type.clone(),
-            "first prompt",
+            "if urgent",
             serde_json::json!({"is_active": true}),
-        );
-        let policy2 = create_test_policy(
-            policy_type.clone(),
-            "second prompt",
-            serde_json::json!({"message": "hello"}),
-        );
-        let policy3 = create_test_policy(
+        ));
+        let mut pruned = create_test_policy(
             policy_type,
-            "third prompt",
-            serde_json::json!({"count": 42}),
+            "if it mentions invoices",
+            serde_json::json!({"count": 2}),
         );
+        pruned.precondition = Some(crate::Precondition::contains("nowhere"));
+        manager.add(pruned);
+        let (builder, req) = manager
+            .joint_request_for(MessageCreateParams::default(), &["first", "second"])
+            .unwrap();
+        let rendered = format!("{:?}", req.messages);
+        assert!(rendered.contains(r#"<text id=\"1\">first</text>"#));
+        assert!(rendered.contains(r#"<text id=\"2\">second</text>"#));
+        assert_eq!(builder.pruned_policies(), &[1]);
+        let Some(claudius::ToolUnionParam::CustomTool(tool)) =
+            req.tools.as_ref().and_then(|tools| tools.first())
+        else {
+            panic!("expected the output tool");
+        };
+        assert_eq!(tool.input_schema["required"], serde_json::json!(["1", "2"]));
+        assert_eq!(tool.input_schema["properties"]["2"], builder.schema());
 
-        manager.add(policy1);
-        manager.add(policy2);
-        manager.add(policy3);
-
-        assert_eq!(manager.len(), 3);
+        let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+        let report = builder
+            .consume_ir(serde_json::json!({"__rule_numbers__": [1], &mask: true}))
+            .unwrap();
+        assert_eq!(report.value()["is_active"], serde_json::json!(true));
     }
 
-    #[test]
-    #[should_panic]
-    fn manager_add_policy_different_type_panics() {
+    #[tokio::test]
+    async fn manager_joint_apply_without_rules_skips_the_llm() {
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let mut policy = create_test_policy(
+            create_test_policy_type(),
+            "if it mentions invoices",
+            serde_json::json!({"count": 2}),
+        );
+        policy.precondition = Some(crate::Precondition::contains("invoice"));
         let mut manager = Manager::default();
+        manager.add(policy);
+        let mut usage = Usage::default();
+        let reports = manager
+            .apply_joint(
+                &client,
+                MessageCreateParams::default(),
+                &["hello", "goodbye"],
+                Some(&mut usage),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reports.len(), 2);
+        for report in reports {
+            assert_eq!(report.pruned_policies, vec![0]);
+            assert_eq!(report.value(), manager.no_match_value());
+        }
+        assert_eq!(usage.iterations, 0);
+        assert!(manager
+            .apply_joint(&client, MessageCreateParams::default(), &[], None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
 
-        let type1 = create_test_policy_type();
-        let type2 = PolicyType {
-            name: "DifferentPolicy".to_string(),
-            fields: vec![Field::Bool {
-                name: "enabled".to_string(),
-                default: Some(true),
-                on_conflict: crate::OnConflict::Default,
-            }],
-        };
-
-        let policy1 = create_test_policy(type1, "first", serde_json::json!({"is_active": true}));
-        let policy2 = create_test_policy(type2, "second", serde_json::json!({"enabled": false}));
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_truncates_oversized_arrays() {
+        let policy_type = PolicyType::parse("type T { tags: [string] }").unwrap();
+        let mut manager = Manager::default().with_max_array_len(3);
+        manager.add(create_test_policy(
+            policy_type,
+            "if urgent",
+            serde_json::json!({"tags": ["urgent"]}),
+        ));
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let mask = builder
+            .clone()
+            .consume_ir(serde_json::json!({}))
+            .unwrap()
+            .masks_by_index[0][0]
+            .clone();
+        let report = builder
+            .clone()
+            .consume_ir(serde_json::json!({
+                "__rule_numbers__": [1],
+                &mask: ["a", ["b", ["c", "d"]], "e"],
+            }))
+            .unwrap();
+        assert_eq!(report.value(), serde_json::json!({"tags": ["a", "b", "c"]}));
+        assert_eq!(report.errors().len(), 1);
+        assert!(matches!(
+            &report.errors()[0],
+            PolicyError::ArrayTruncated { field, length: 5, limit: 3 } if field == "tags"
+        ));
+        let report = builder
+            .consume_ir(serde_json::json!({"__rule_numbers__": [1], &mask: ["a", "b", "c"]}))
+            .unwrap();
+        assert!(report.errors().is_empty());
+        assert_ne!(
+            manager.cache_key("hello"),
+            manager.clone().with_max_array_len(4).cache_key("hello")
+        );
+    }
 
-        manager.add(policy1);
-        manager.add(policy2); // This should panic
+    #[tokio::test]
+    async fn manager_words_instructions_for_the_requested_model() {
+        let mut plain = Manager::default();
+        plain.add(create_test_policy(
+            create_test_policy_type(),
+            "if urgent",
+            serde_json::json!({"is_active": true}),
+        ));
+        let mut manager = plain.clone().with_prompt_variant(
+            "gpt-",
+            PromptVariant::default()
+                .with_system("GPT SYSTEM")
+                .with_suffix("GPT SUFFIX"),
+        );
+        let system_and_user = |req: MessageCreateParams| {
+            let Some(SystemPrompt::Blocks(blocks)) = req.system else {
+                panic!("expected system blocks");
+            };
+            let last = match &req.messages.last().unwrap().content {
+                MessageParamContent::String(text) => text.clone(),
+                MessageParamContent::Array(content) => match content.last() {
+                    Some(ContentBlock::Text(text)) => text.text.clone(),
+                    _ => panic!("expected text"),
+                },
+            };
+            (blocks[0].block.text.clone(), last)
+        };
+        let gpt = MessageCreateParams {
+            model: claudius::Model::Custom("gpt-4o".to_string()),
+            ..Default::default()
+        };
+        let (_, req) = manager.request_for(gpt, "hello").await.unwrap();
+        let (system, user) = system_and_user(req);
+        assert_eq!(system, "GPT SYSTEM");
+        assert!(user.ends_with("<text>hello</text>GPT SUFFIX"), "{user}");
+        let (_, req) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let builtin = PromptVariant::default();
+        let (system, user) = system_and_user(req);
+        assert_eq!(system, builtin.system);
+        assert!(user.ends_with(&builtin.suffix), "{user}");
+        assert_ne!(manager.cache_key("hello"), plain.cache_key("hello"));
     }
 
     #[tokio::test]
-    async fn manager_request_for_empty_manager() {
+    async fn manager_explains_unmatched_rules_on_request() {
         let mut manager = Manager::default();
-        let template = MessageCreateParams::default();
-        let text = "test text";
-
-        let result = manager.request_for(template, text).await;
-        assert!(result.is_ok());
+        for prompt in ["if urgent", "if spam"] {
+            manager.add(create_test_policy(
+                create_test_policy_type(),
+                prompt,
+                serde_json::json!({"is_active": true}),
+            ));
+        }
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        assert!(builder.schema()["properties"]
+            .get("__non_match_reasons__")
+            .is_none());
 
-        let (_report, req) = result.unwrap();
-        assert!(!req.messages.is_empty());
-        assert!(req.system.is_some());
-        assert_eq!(req.tool_choice, Some(ToolChoice::tool("output_json")));
+        let mut debugging = manager.clone().with_non_match_reasons(true);
+        let (builder, _) = debugging
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        assert!(builder.schema()["properties"]["__non_match_reasons__"].is_object());
+        assert_ne!(debugging.cache_key("hello"), manager.cache_key("hello"));
+        let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+        let report = builder
+            .consume_ir(serde_json::json!({
+                "__rule_numbers__": [1],
+                &mask: true,
+                "__non_match_reasons__": {
+                    "1": "It is urgent.",
+                    "2": "Nothing is being sold.",
+                    "3": "There is no rule 3.",
+                },
+            }))
+            .unwrap();
+        assert_eq!(
+            report.non_match_reasons(),
+            BTreeMap::from([(
+                RuleIndex::FIRST.next(),
+                "Nothing is being sold.".to_string()
+            )])
+        );
     }
 
     #[tokio::test]
-    async fn manager_request_for_with_policies() {
-        let mut manager = Manager::default();
+    async fn manager_records_why_policies_did_not_fire() {
         let policy_type = create_test_policy_type();
-
-        let policy1 = create_test_policy(
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
             policy_type.clone(),
-            "if urgent then",
-            serde_json::json!({"is_active": true, "count": 10}),
+            "if urgent",
+            serde_json::json!({"is_active": true}),
+        ));
+        let mut invoices = create_test_policy(
+            policy_type.clone(),
+            "if it mentions invoices then",
+            serde_json::json!({"is_active": true}),
         );
-        let policy2 = create_test_policy(
+        invoices.precondition = Some(crate::Precondition::contains("invoice"));
+        manager.add(invoices);
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "if spam",
+            serde_json::json!({"is_active": true}),
+        ));
+        manager.add(create_test_policy(
             policy_type,
-            "if contains hello then",
-            serde_json::json!({"message": "greeting"}),
+            "always",
+            serde_json::json!({"message": "hi"}),
+        ));
+        let mut scoped = manager
+            .with_fields(["is_active"])
+            .with_non_match_reasons(true);
+        let (builder, _) = scoped
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+        let report = builder
+            .consume_ir(serde_json::json!({
+                "__rule_numbers__": [1],
+                &mask: true,
+                "__non_match_reasons__": {"2": "Nothing is being sold."},
+            }))
+            .unwrap();
+        assert_eq!(
+            report.skipped_policies,
+            vec![
+                SkippedPolicy {
+                    policy: 1,
+                    reason: SkipReason::PreconditionFailed,
+                    explanation: None,
+                },
+                SkippedPolicy {
+                    policy: 2,
+                    reason: SkipReason::NotMatched,
+                    explanation: Some("Nothing is being sold.".to_string()),
+                },
+                SkippedPolicy {
+                    policy: 3,
+                    reason: SkipReason::OutOfScope,
+                    explanation: None,
+                },
+            ]
         );
+        assert_eq!(report.skip_reason(0), None);
+        assert_eq!(report.pruned_policies, vec![1, 3]);
+    }
 
-        manager.add(policy1);
-        manager.add(policy2);
-
-        let template = MessageCreateParams::default();
-        let text = "urgent hello world";
-
-        let result = manager.request_for(template, text).await;
-        assert!(result.is_ok());
+    #[test]
+    fn manager_plan_attributes_tokens_to_policies() {
+        let policy_type = create_test_policy_type();
+        let mut invoices = create_test_policy(
+            policy_type.clone(),
+            "if it mentions invoices then",
+            serde_json::json!({"count": 1}),
+        );
+        invoices.precondition = Some(crate::Precondition::contains("invoice"));
+        let mut manager = Manager::default();
+        manager.add(invoices);
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "if short",
+            serde_json::json!({"is_active": true}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            &"if the sender is asking about anything at all ".repeat(20),
+            serde_json::json!({"message": "hi"}),
+        ));
 
-        let (report, req) = result.unwrap();
-        println!("Number of messages: {}", req.messages.len()); // Debug output
-        assert!(!req.messages.is_empty()); // At least one message
-        assert!(req.system.is_some());
-        assert!(req.tools.is_some());
+        let plan = manager
+            .plan(MessageCreateParams::default(), "lunch?")
+            .unwrap();
+        let tokens = &plan.tokens;
+        // The invoice policy is pruned and costs nothing.
+        assert_eq!(
+            tokens.rules.iter().map(|r| r.policy).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(tokens.rules[0].rule, RuleIndex::FIRST);
+        assert_eq!(tokens.rules_by_cost()[0].policy, 2);
+        assert!(tokens.rules.iter().all(|r| r.prompt > 0 && r.schema > 0));
+        assert_eq!(tokens.text, PromptTokens::estimate("<text>lunch?</text>"));
+        assert!(tokens.system > 0 && tokens.defaults > 0 && tokens.tool > 0);
+        assert!(tokens.reminder > 0);
+        let request_bytes = serde_json::to_string(&plan.request).unwrap().len() as u64;
+        // Every byte but the JSON framing of the request is accounted for.
+        assert!(tokens.total() * PromptTokens::BYTES_PER_TOKEN <= request_bytes + 16);
+        assert!(tokens.total() * PromptTokens::BYTES_PER_TOKEN * 10 >= request_bytes * 9);
+        let table = tokens.to_string();
+        assert!(table.contains("rule 2 (policy 2)"), "{table}");
+        assert!(table.lines().last().unwrap().starts_with("total"));
+    }
 
-        // Verify the schema includes masked fields and special fields
-        let schema = report.schema();
-        assert!(schema["properties"].as_object().is_some());
-        let properties = schema["properties"].as_object().unwrap();
+    #[test]
+    fn inconsistencies_name_the_field_and_rule_behind_each_mask() {
+        let mut builder = ReportBuilder::default();
+        builder
+            .add_policy(&create_test_policy(
+                create_test_policy_type(),
+                "If the message is from the billing department and mentions an overdue invoice",
+                serde_json::json!({"is_active": true}),
+            ))
+            .unwrap();
+        let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+        let report = builder
+            .clone()
+            .consume_ir(serde_json::json!({"__rule_numbers__": [], &mask: true}))
+            .unwrap();
+        let (content, _) = rule_number_inconsistencies(&builder, &report, vec![], false).unwrap();
+        assert!(
+            content.contains(&format!(
+                "\"{mask}\" (field \"is_active\" of rule 1, \"If the message is from the billing department and mentions...\") was set"
+            )),
+            "{content}"
+        );
+        assert!(content.contains(&format!("Unset \"{mask}\"")), "{content}");
+    }
 
-        // Should have __rule_numbers__ special fields
-        assert!(properties.contains_key("__rule_numbers__"));
+    #[test]
+    fn excerpts_cut_long_prompts_at_a_word() {
+        assert_eq!(excerpt("if urgent"), "if urgent");
+        assert_eq!(excerpt(&"a".repeat(100)), format!("{}...", "a".repeat(60)));
+        assert_eq!(
+            excerpt(&"word ".repeat(20)),
+            format!("{}...", "word ".repeat(12).trim_end())
+        );
+    }
 
-        // Should have 3 masked fields (is_active, message, count)
-        // The masked fields will have obfuscated names but correct types
-        let masked_fields = properties.keys().filter(|k| !k.starts_with("__")).count();
-        assert_eq!(masked_fields, 3, "Expected 3 masked fields");
+    #[test]
+    fn prepared_requests_share_the_rules_ahead_of_a_cache_breakpoint() {
+        let policy_type = create_test_policy_type();
+        let mut invoices = create_test_policy(
+            policy_type.clone(),
+            "if it mentions invoices then",
+            serde_json::json!({"count": 1}),
+        );
+        invoices.precondition = Some(crate::Precondition::contains("invoice"));
+        let mut manager = Manager::default();
+        manager.add(invoices);
+        manager.add(create_test_policy(
+            policy_type,
+            "always",
+            serde_json::json!({"message": "hi"}),
+        ));
+        let prepared = manager.prepare(MessageCreateParams::default()).unwrap();
 
-        // Verify the types of the masked fields
-        let mut has_boolean = false;
-        let mut has_string = false;
-        let mut has_number = false;
+        let (builder, first) = prepared.request_for("an invoice is attached");
+        let (_, second) = prepared.request_for("lunch?");
+        assert!(builder.masks_for_rule(RuleIndex::FIRST.next()).is_some());
+        assert!(builder.pruned_policies().is_empty());
+        assert_eq!(first.system, second.system);
+        assert_eq!(first.tools, second.tools);
+        let blocks = |req: &MessageCreateParams| {
+            let MessageParamContent::Array(blocks) = &req.messages[0].content else {
+                panic!("expected the breakpoint to convert the rules to blocks");
+            };
+            blocks.clone()
+        };
+        let (first, second) = (blocks(&first), blocks(&second));
+        let breakpoint = first
+            .iter()
+            .position(|block| matches!(block, ContentBlock::Text(t) if t.cache_control.is_some()))
+            .unwrap();
+        assert_eq!(first[..=breakpoint], second[..=breakpoint]);
+        assert!(format!("{:?}", &first[..=breakpoint]).contains("invoices"));
+        assert!(format!("{:?}", &first[breakpoint + 1..]).contains("an invoice is attached"));
+        assert!(format!("{:?}", &second[breakpoint + 1..]).contains("lunch?"));
+    }
 
-        for (key, value) in properties.iter() {
-            if !key.starts_with("__") {
-                if let Some(type_val) = value.get("type") {
-                    match type_val.as_str() {
-                        Some("boolean") => has_boolean = true,
-                        Some("string") => has_string = true,
-                        Some("number") | Some("integer") => has_number = true,
-                        _ => {}
-                    }
-                }
+    #[tokio::test]
+    async fn manager_refuses_more_policies_than_its_limit() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default().with_max_policies(2);
+        for prompt in ["if it is urgent", "if it is a reply", "if it is spam"] {
+            manager.add(create_test_policy(
+                policy_type.clone(),
+                prompt,
+                serde_json::json!({"is_active": true}),
+            ));
+        }
+        let Err(err) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+        else {
+            panic!("three policies should exceed a limit of two");
+        };
+        assert!(matches!(
+            err,
+            ApplyError::TooManyPolicies {
+                policies: 3,
+                max: 2
             }
+        ));
+        assert!(!err.is_retryable());
+
+        // Policies decided locally are never sent, so they do not count.
+        let mut local = create_test_policy(
+            policy_type.clone(),
+            "if it is from a colleague",
+            serde_json::json!({"message": "internal"}),
+        );
+        local.exact_match = Some(crate::Precondition::contains("@example.com"));
+        let mut manager = Manager::default().with_max_policies(2);
+        manager.add(local);
+        for prompt in ["if it is urgent", "if it is a reply"] {
+            manager.add(create_test_policy(
+                policy_type.clone(),
+                prompt,
+                serde_json::json!({"is_active": true}),
+            ));
         }
+        assert!(manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .is_ok());
+    }
 
-        assert!(has_boolean, "Should have a boolean field (is_active)");
-        assert!(has_string, "Should have a string field (message)");
-        assert!(has_number, "Should have a number field (count)");
+    #[test]
+    fn manager_flags_doubtful_reports_for_review() {
+        let manager = Manager::default().with_review_below_confidence(0.8);
+        let confident = |confidence: f64| {
+            let mut report = Report::from_value(
+                serde_json::json!({"is_active": true}),
+                serde_json::json!({"is_active": false}),
+            );
+            report.ir = Some(serde_json::json!({"__confidence__": confidence}));
+            report
+        };
+        assert!(manager.review_task("text", &confident(0.9)).is_none());
+        let task = manager.review_task("text", &confident(0.5)).unwrap();
+        assert_eq!(task.confidence, Some(0.5));
+        assert_eq!(task.reasons.len(), 1);
+
+        let conflicted = confident(0.9).with_conflict(crate::Conflict::BoolConflict {
+            field: "is_active".to_string(),
+            rule1: 1,
+            rule2: 2,
+            val1: true,
+            val2: false,
+        });
+        let task = manager.review_task("text", &conflicted).unwrap();
+        assert_eq!(task.conflicts.len(), 1);
+        assert_eq!(
+            task.text_hash,
+            format!("{:032x}", manager.cache_key("text").text)
+        );
+        assert!(Manager::default()
+            .review_task("text", &confident(0.1))
+            .is_none());
     }
 
     #[tokio::test]
-    async fn manager_request_for_system_prompt() {
+    async fn manager_validates_the_model_it_sends_to() {
         let mut manager = Manager::default();
-        let template = MessageCreateParams::default();
-        let text = "test";
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "if it is urgent",
+            serde_json::json!({"is_active": true}),
+        ));
+        let unset = MessageCreateParams {
+            model: Model::Custom(String::new()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            manager.request_for(unset.clone(), "text").await,
+            Err(ApplyError::ModelNotConfigured { .. })
+        ));
 
-        let result = manager.request_for(template, text).await;
-        assert!(result.is_ok());
+        let mut manager = manager
+            .with_model(Model::Custom("claude-haiku-4-5".to_string()))
+            .with_allowed_models(["claude-haiku"]);
+        let (_, request) = manager.request_for(unset, "text").await.unwrap();
+        assert_eq!(request.model, Model::Custom("claude-haiku-4-5".to_string()));
 
-        let (_, req) = result.unwrap();
-        let system = req.system.unwrap();
-        let system_str = match system {
-            SystemPrompt::String(s) => s,
-            SystemPrompt::Blocks(blocks) => {
-                // Extract text from the first SystemTextBlock
-                if let Some(text_block) = blocks.first() {
-                    text_block.block.text.clone()
-                } else {
-                    panic!("Expected text block in system prompt")
-                }
-            }
+        let manager = manager.with_allowed_models(["claude-opus"]);
+        let Err(err) = manager.prepare(MessageCreateParams::default()) else {
+            panic!("a disallowed model was accepted");
         };
+        assert!(!err.is_retryable());
+        assert!(err.to_string().contains("claude-haiku-4-5"));
+    }
 
-        // Verify key parts of the system prompt
-        assert!(system_str.contains("Output JSON"));
-        assert!(system_str.contains("if and only if a rule matches"));
+    #[tokio::test]
+    async fn manager_unmasked_fields_use_real_names() {
+        let policy_type = create_test_policy_type();
+        let mut manager = Manager::default().with_unmasked_fields(["message"]);
+        manager.add(create_test_policy(
+            policy_type.clone(),
+            "If the text is urgent",
+            serde_json::json!({"is_active": true, "message": "urgent"}),
+        ));
+        manager.add(create_test_policy(
+            policy_type,
+            "If the text is a reply",
+            serde_json::json!({"message": "reply"}),
+        ));
+        assert_ne!(
+            manager.cache_key("text"),
+            Manager {
+                unmasked_fields: BTreeSet::new(),
+                ..manager.clone()
+            }
+            .cache_key("text")
+        );
+        let (builder, _) = manager
+            .request_for(MessageCreateParams::default(), "text")
+            .await
+            .unwrap();
+        let properties = builder.schema()["properties"].clone();
+        assert!(properties["message@1"].is_object());
+        assert!(properties["message@2"].is_object());
+        let masked = builder.masks_for_rule(RuleIndex::FIRST).unwrap();
+        assert!(masked
+            .iter()
+            .any(|mask| uuid::Uuid::parse_str(mask).is_ok()));
+        let report = builder
+            .consume_ir(serde_json::json!({"__rule_numbers__": [2], "message@2": "reply"}))
+            .unwrap();
+        assert_eq!(report.value()["message"], "reply");
+        assert_eq!(report.value()["is_active"], false);
     }
 
     #[test]
-    fn manager_debug_format() {
-        let manager = Manager::default();
-        let debug_str = format!("{manager:?}");
-        assert!(debug_str.contains("Manager"));
-        assert!(debug_str.contains("policies"));
+    fn manager_apply_streaming_is_send() {
+        fn assert_send<T: Send>(_: T) {}
+        let client = Anthropic::new(Some("sk-ant-test".to_string())).unwrap();
+        let mut manager = Manager::default();
+        assert_send(manager.apply_streaming(
+            &client,
+            MessageCreateParams::default(),
+            "text",
+            None,
+            |_| {},
+        ));
+        let prepared = manager.prepare(MessageCreateParams::default()).unwrap();
+        assert_send(prepared.apply(&client, "text", None));
     }
 
     #[tokio::test]
-    async fn manager_request_includes_text_message() {
+    async fn manager_fills_prompt_variables() {
         let mut manager = Manager::default();
-        let template = MessageCreateParams::default();
-        let test_text = "This is my special test text";
+        manager.add(create_test_policy(
+            create_test_policy_type(),
+            "if the email is from {{customer_name}}",
+            serde_json::json!({"is_active": true}),
+        ));
+        let result = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await;
+        assert!(matches!(
+            result,
+            Err(ApplyError::Policy(
+                crate::PolicyError::MissingVariable { .. }
+            ))
+        ));
 
-        let result = manager.request_for(template, test_text).await;
-        assert!(result.is_ok());
+        let acme = BTreeMap::from([("customer_name".to_string(), "Acme".to_string())]);
+        let globex = BTreeMap::from([("customer_name".to_string(), "Globex".to_string())]);
+        let mut acme_manager = manager.clone().with_variables(acme);
+        let (_, req) = acme_manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let rendered = format!("{:?}", req.messages);
+        assert!(rendered.contains("if the email is from Acme"));
+        assert!(!rendered.contains("{{customer_name}}"));
+        assert_ne!(
+            acme_manager.cache_key("hello"),
+            manager
+                .clone()
+                .with_variables(globex.clone())
+                .cache_key("hello")
+        );
 
-        let (_, req) = result.unwrap();
+        let client = crate::testing::MockClient::replaying(serde_json::json!({
+            "__rule_numbers__": [],
+        }));
+        acme_manager
+            .apply_with_variables(
+                &client,
+                MessageCreateParams::default(),
+                "hello",
+                globex,
+                None,
+            )
+            .await
+            .unwrap();
+        let rendered = format!("{:?}", client.requests()[0].messages);
+        assert!(rendered.contains("if the email is from Globex"));
+        // The call's variables did not stick.
+        let (_, req) = acme_manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        assert!(format!("{:?}", req.messages).contains("if the email is from Acme"));
+    }
 
-        // Find the message containing our text
-        let mut found_text = false;
-        for message in &req.messages {
-            if let MessageParamContent::String(content) = &message.content {
-                if content.contains(test_text) {
-                    found_text = true;
-                    break;
-                }
-            }
+    #[cfg(feature = "parser")]
+    #[tokio::test]
+    async fn manager_supplies_dynamic_enum_values() {
+        let policy_type = PolicyType::parse("type T { assignee: [dynamic] = \"nobody\" }").unwrap();
+        let mut manager = Manager::default();
+        manager.add(create_test_policy(
+            policy_type,
+            "if the email is about billing",
+            serde_json::json!({"assignee": "alice"}),
+        ));
+        let result = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await;
+        assert!(matches!(
+            result,
+            Err(ApplyError::Policy(
+                crate::PolicyError::MissingEnumValues { .. }
+            ))
+        ));
+
+        let roster = |names: &[&str]| {
+            BTreeMap::from([(
+                "assignee".to_string(),
+                names.iter().map(|n| n.to_string()).collect(),
+            )])
+        };
+        let mut acme = manager.clone().with_enum_values(roster(&["alice", "bob"]));
+        let (builder, _) = acme
+            .request_for(MessageCreateParams::default(), "hello")
+            .await
+            .unwrap();
+        let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+        let mut ir = serde_json::json!({"__rule_numbers__": [1]});
+        ir[mask] = true.into();
+        let report = builder.consume_ir(ir).unwrap();
+        assert_eq!(report.value(), serde_json::json!({"assignee": "alice"}));
+
+        let mut globex = manager.clone().with_enum_values(roster(&["carol"]));
+        let result = globex
+            .request_for(MessageCreateParams::default(), "hello")
+            .await;
+        assert!(matches!(
+            result,
+            Err(ApplyError::Policy(
+                crate::PolicyError::ExpectedString { .. }
+            ))
+        ));
+        assert_ne!(acme.cache_key("hello"), globex.cache_key("hello"));
+
+        // Supplied for one call, the values do not stick.
+        let client = crate::testing::MockClient::replaying(serde_json::json!({
+            "__rule_numbers__": [],
+        }));
+        let report = manager
+            .apply_with_enum_values(
+                &client,
+                MessageCreateParams::default(),
+                "hello",
+                roster(&["alice", "bob"]),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.value(), serde_json::json!({"assignee": "nobody"}));
+        let result = manager
+            .request_for(MessageCreateParams::default(), "hello")
+            .await;
+        assert!(matches!(
+            result,
+            Err(ApplyError::Policy(
+                crate::PolicyError::MissingEnumValues { .. }
+            ))
+        ));
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn manager_from_repository_loads_in_id_order() {
+        let repository = crate::MemoryRepository::default();
+        let policy_type = create_test_policy_type();
+        for (id, prompt) in [("b", "second"), ("a", "first")] {
+            let policy = create_test_policy(
+                policy_type.clone(),
+                prompt,
+                serde_json::json!({"is_active": true}),
+            );
+            repository.put(id, &policy).unwrap();
         }
-        assert!(found_text, "Request should include the input text");
+        let manager = Manager::from_repository(&repository).unwrap();
+        let prompts = manager
+            .policies
+            .iter()
+            .map(|p| p.prompt.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(prompts, vec!["first", "second"]);
+
+        let other = PolicyType::parse("type Other { flag: bool = false }").unwrap();
+        repository
+            .put(
+                "c",
+                &create_test_policy(other, "third", serde_json::json!({"flag": true})),
+            )
+            .unwrap();
+        assert!(matches!(
+            Manager::from_repository(&repository),
+            Err(RepositoryError::TypeMismatch { id }) if id == "c"
+        ));
     }
 }
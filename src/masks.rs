@@ -1,4 +1,14 @@
-use crate::{number_is_equal, t64, OnConflict, Report};
+use crate::{number_is_equal, t64, EnumFallback, OnConflict, OnNull, Report};
+
+/// `mask`'s value in `ir`, leaving out a `null` that `on_null` treats as absent.
+fn lookup<'a>(
+    ir: &'a serde_json::Value,
+    mask: &str,
+    on_null: OnNull,
+) -> Option<&'a serde_json::Value> {
+    ir.get(mask)
+        .filter(|value| !(value.is_null() && on_null == OnNull::Absent))
+}
 
 ///////////////////////////////////////////// BoolMask /////////////////////////////////////////////
 
@@ -16,8 +26,14 @@ pub struct BoolMask {
     pub mask: String,
     /// Default value when the field is not present
     pub default: Option<bool>,
+    /// Expected boolean value for this policy rule
+    #[serde(default)]
+    pub value: Option<bool>,
     /// Strategy for resolving conflicts when multiple policies set different values
     pub on_conflict: OnConflict,
+    /// What an explicit `null` for the field means
+    #[serde(default)]
+    pub on_null: OnNull,
 }
 
 impl BoolMask {
@@ -55,14 +71,40 @@ impl BoolMask {
             name,
             mask,
             default,
+            value: None,
             on_conflict,
+            on_null: OnNull::Error,
         }
     }
 
+    /// Read an explicit `null` for the field as `on_null` says.  See [`OnNull`].
+    pub fn with_on_null(mut self, on_null: OnNull) -> Self {
+        self.on_null = on_null;
+        self
+    }
+
+    /// Expect the rule to output `value`; any other output is reported as a conflict.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{BoolMask, Conflict, OnConflict, Report};
+    /// let mask = BoolMask::new(1, "unread".to_string(), "field_abc".to_string(), Some(true), OnConflict::Default)
+    ///     .with_value(false);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// mask.apply_to(&serde_json::json!({"field_abc": true}), &mut report);
+    /// assert!(matches!(report.conflicts(), [Conflict::BoolConflict { .. }]));
+    /// ```
+    pub fn with_value(mut self, value: bool) -> Self {
+        self.value = Some(value);
+        self
+    }
+
     /// Apply this boolean mask to intermediate representation data.
     ///
     /// Extracts the boolean value from the IR and reports it to the given Report
-    /// if it matches the expected value, otherwise reports the default.
+    /// if it matches the expected value, otherwise reports a conflict.  Reports the default
+    /// when the IR does not set the mask.
     ///
     /// # Arguments
     ///
@@ -75,13 +117,27 @@ impl BoolMask {
     /// # use policyai::{BoolMask, OnConflict, Report};
     /// let mask = BoolMask::new(1, "urgent".to_string(), "field_abc".to_string(), None, OnConflict::Default);
     /// let ir = serde_json::json!({"field_abc": true});
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// mask.apply_to(&ir, &mut report);
     /// ```
     pub fn apply_to(&self, ir: &serde_json::Value, report: &mut Report) {
-        match ir.get(&self.mask) {
-            Some(serde_json::Value::Bool(ret)) => {
-                report.report_bool(self.policy_index, &self.name, *ret, self.on_conflict);
+        match lookup(ir, &self.mask, self.on_null) {
+            Some(serde_json::Value::Bool(ret)) => match self.value {
+                Some(expected_value) if expected_value != *ret => {
+                    report.report_policy_index(self.policy_index);
+                    report.report_bool_conflict(
+                        &self.name,
+                        (self.policy_index, self.policy_index),
+                        *ret,
+                        expected_value,
+                    );
+                }
+                _ => {
+                    report.report_bool(self.policy_index, &self.name, *ret, self.on_conflict);
+                }
+            },
+            Some(serde_json::Value::Null) if self.on_null == OnNull::Unset => {
+                report.report_policy_index(self.policy_index);
             }
             Some(_) => {
                 report.report_type_check_failure(
@@ -119,6 +175,12 @@ pub struct NumberMask {
     pub value: Option<serde_json::Number>,
     /// Strategy for resolving conflicts when multiple policies set different values
     pub on_conflict: OnConflict,
+    /// Whether `value` is added to the field instead of replacing it
+    #[serde(default)]
+    pub increment: bool,
+    /// What an explicit `null` for the field means
+    #[serde(default)]
+    pub on_null: OnNull,
 }
 
 impl NumberMask {
@@ -161,9 +223,24 @@ impl NumberMask {
             default,
             value,
             on_conflict,
+            increment: false,
+            on_null: OnNull::Error,
         }
     }
 
+    /// Read an explicit `null` for the field as `on_null` says.  See [`OnNull`].
+    pub fn with_on_null(mut self, on_null: OnNull) -> Self {
+        self.on_null = on_null;
+        self
+    }
+
+    /// Add this mask's value to the field instead of replacing it, as for an action of
+    /// `{"field": {"$add": value}}`.
+    pub fn with_increment(mut self) -> Self {
+        self.increment = true;
+        self
+    }
+
     /// Apply this numeric mask to intermediate representation data.
     ///
     /// Extracts the numeric value from the IR and reports it to the given Report,
@@ -181,14 +258,28 @@ impl NumberMask {
     /// # use claudius::MessageParam;
     /// let mask = NumberMask::new(1, "score".to_string(), "field_num".to_string(), Some(t64(0.0)), Some(serde_json::Number::from(42)), OnConflict::Default);
     /// let ir = serde_json::json!({"field_num": 42});
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// mask.apply_to(&ir, &mut report);
     /// ```
     pub fn apply_to(&self, ir: &serde_json::Value, report: &mut Report) {
-        match ir.get(&self.mask) {
+        match lookup(ir, &self.mask, self.on_null) {
             Some(serde_json::Value::Number(value)) => {
                 if let Some(expected_value) = &self.value {
-                    if number_is_equal(value, expected_value) {
+                    if number_is_equal(value, expected_value) && self.increment {
+                        let base = self.default.and_then(|d| {
+                            if d.0.fract() == 0.0 && d.0.abs() < i64::MAX as f64 {
+                                Some((d.0 as i64).into())
+                            } else {
+                                serde_json::Number::from_f64(d.0)
+                            }
+                        });
+                        report.report_number_increment(
+                            self.policy_index,
+                            &self.name,
+                            expected_value.clone(),
+                            base,
+                        );
+                    } else if number_is_equal(value, expected_value) {
                         report.report_number(
                             self.policy_index,
                             &self.name,
@@ -199,6 +290,7 @@ impl NumberMask {
                         report.report_policy_index(self.policy_index);
                         report.report_number_conflict(
                             &self.name,
+                            (self.policy_index, self.policy_index),
                             value.clone(),
                             expected_value.clone(),
                         );
@@ -212,6 +304,9 @@ impl NumberMask {
                     );
                 }
             }
+            Some(serde_json::Value::Null) if self.on_null == OnNull::Unset => {
+                report.report_policy_index(self.policy_index);
+            }
             Some(_) => {
                 report.report_type_check_failure(
                     file!(),
@@ -256,6 +351,9 @@ pub struct StringMask {
     pub value: Option<String>,
     /// Strategy for resolving conflicts when multiple policies set different values
     pub on_conflict: OnConflict,
+    /// What an explicit `null` for the field means
+    #[serde(default)]
+    pub on_null: OnNull,
 }
 
 impl StringMask {
@@ -298,9 +396,16 @@ impl StringMask {
             default,
             value,
             on_conflict,
+            on_null: OnNull::Error,
         }
     }
 
+    /// Read an explicit `null` for the field as `on_null` says.  See [`OnNull`].
+    pub fn with_on_null(mut self, on_null: OnNull) -> Self {
+        self.on_null = on_null;
+        self
+    }
+
     /// Apply this string mask to intermediate representation data.
     ///
     /// Extracts the string value from the IR and reports it to the given Report,
@@ -318,11 +423,11 @@ impl StringMask {
     /// # use claudius::MessageParam;
     /// let mask = StringMask::new(1, "title".to_string(), "field_str".to_string(), None, Some("important".to_string()), OnConflict::Default);
     /// let ir = serde_json::json!({"field_str": "important"});
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// mask.apply_to(&ir, &mut report);
     /// ```
     pub fn apply_to(&self, ir: &serde_json::Value, report: &mut Report) {
-        match ir.get(&self.mask) {
+        match lookup(ir, &self.mask, self.on_null) {
             Some(serde_json::Value::String(value)) => {
                 if let Some(expected_value) = &self.value {
                     if value == expected_value {
@@ -336,6 +441,7 @@ impl StringMask {
                         report.report_policy_index(self.policy_index);
                         report.report_string_conflict(
                             &self.name,
+                            (self.policy_index, self.policy_index),
                             value.clone(),
                             expected_value.clone(),
                         );
@@ -349,6 +455,9 @@ impl StringMask {
                     );
                 }
             }
+            Some(serde_json::Value::Null) if self.on_null == OnNull::Unset => {
+                report.report_policy_index(self.policy_index);
+            }
             Some(_) => {
                 report.report_type_check_failure(
                     file!(),
@@ -379,9 +488,23 @@ pub struct StringArrayMask {
     pub name: String,
     /// Masked field name unlikely to be in LLM training data
     pub mask: String,
+    /// Most strings kept from the IR; the rest are dropped and the truncation is reported
+    #[serde(default = "StringArrayMask::default_max_len")]
+    pub max_len: usize,
+    /// What an explicit `null` for the field means
+    #[serde(default)]
+    pub on_null: OnNull,
 }
 
 impl StringArrayMask {
+    /// The number of strings kept per rule unless [`StringArrayMask::with_max_len`] says
+    /// otherwise.
+    pub const DEFAULT_MAX_LEN: usize = 1024;
+
+    fn default_max_len() -> usize {
+        Self::DEFAULT_MAX_LEN
+    }
+
     /// Create a new StringArrayMask with the specified parameters.
     ///
     /// # Arguments
@@ -407,13 +530,47 @@ impl StringArrayMask {
             policy_index,
             name,
             mask,
+            max_len: Self::DEFAULT_MAX_LEN,
+            on_null: OnNull::Error,
         }
     }
 
+    /// Read an explicit `null` for the field as `on_null` says.  See [`OnNull`].
+    pub fn with_on_null(mut self, on_null: OnNull) -> Self {
+        self.on_null = on_null;
+        self
+    }
+
+    /// Keep at most `max_len` strings from the IR.
+    ///
+    /// A degenerate IR could otherwise carry an array large enough to exhaust memory or the
+    /// token budget of whatever consumes the report.  Longer arrays are truncated and
+    /// [`crate::PolicyError::ArrayTruncated`] is recorded in the report.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{PolicyError, Report, StringArrayMask};
+    /// let mask = StringArrayMask::new(1, "tags".to_string(), "field_arr".to_string(), vec![])
+    ///     .with_max_len(2);
+    /// let ir = serde_json::json!({"field_arr": ["a", "b", "c"]});
+    /// let mut report = Report::default();
+    /// mask.apply_to(&ir, &mut report);
+    /// assert_eq!(report.value(), serde_json::json!({"tags": ["a", "b"]}));
+    /// assert!(matches!(
+    ///     report.errors()[0],
+    ///     PolicyError::ArrayTruncated { length: 3, limit: 2, .. }
+    /// ));
+    /// ```
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
     /// Apply this string array mask to intermediate representation data.
     ///
     /// Extracts string arrays from the IR (supporting nested arrays) and reports
-    /// each individual string to the given Report.
+    /// each individual string to the given Report, up to [`StringArrayMask::max_len`] of them.
     ///
     /// # Arguments
     ///
@@ -427,28 +584,44 @@ impl StringArrayMask {
     /// # use claudius::MessageParam;
     /// let mask = StringArrayMask::new(1, "tags".to_string(), "field_arr".to_string(), vec![]);
     /// let ir = serde_json::json!({"field_arr": ["tag1", "tag2"]});
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// mask.apply_to(&ir, &mut report);
     /// ```
     pub fn apply_to(&self, ir: &serde_json::Value, report: &mut Report) {
-        fn extract_strings(value: &serde_json::Value, depth: usize) -> Option<Vec<String>> {
+        // Counts every string but only copies the first `max_len`, so an oversized array costs
+        // a walk rather than an allocation.
+        fn extract_strings(
+            value: &serde_json::Value,
+            depth: usize,
+            max_len: usize,
+            strings: &mut Vec<String>,
+            count: &mut usize,
+        ) -> Option<()> {
             if depth == 0 {
                 None
             } else if let serde_json::Value::String(s) = value {
-                Some(vec![s.clone()])
+                if *count < max_len {
+                    strings.push(s.clone());
+                }
+                *count += 1;
+                Some(())
             } else if let serde_json::Value::Array(a) = value {
-                let mut all = vec![];
                 for v in a {
-                    all.extend(extract_strings(v, depth - 1)?.into_iter());
+                    extract_strings(v, depth - 1, max_len, strings, count)?;
                 }
-                Some(all)
+                Some(())
             } else {
                 None
             }
         }
-        if let Some(reported) = ir.get(&self.mask) {
-            match extract_strings(reported, 128) {
-                Some(strings) => {
+        let reported = lookup(ir, &self.mask, self.on_null);
+        if reported.is_some_and(serde_json::Value::is_null) && self.on_null == OnNull::Unset {
+            report.report_policy_index(self.policy_index);
+        } else if let Some(reported) = reported {
+            let mut strings = vec![];
+            let mut count = 0;
+            match extract_strings(reported, 128, self.max_len, &mut strings, &mut count) {
+                Some(()) => {
                     if strings.is_empty() {
                         report.init_empty_string_array(self.policy_index, &self.name);
                     } else {
@@ -456,6 +629,9 @@ impl StringArrayMask {
                             report.report_string_array(self.policy_index, &self.name, s);
                         }
                     }
+                    if count > self.max_len {
+                        report.report_array_truncated(&self.name, count, self.max_len);
+                    }
                 }
                 None => {
                     report.report_type_check_failure(
@@ -489,6 +665,17 @@ pub struct StringEnumMask {
     pub default: Option<String>,
     /// Strategy for resolving conflicts when multiple policies set different values
     pub on_conflict: OnConflict,
+    /// Every value the field allows; empty for masks that predate recording them, which then
+    /// allow only `value`
+    #[serde(default)]
+    pub values: Vec<String>,
+    /// The order that picks a value when policies fail to agree under
+    /// [`OnConflict::Agreement`]; empty to keep the first value
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<EnumFallback>,
+    /// What an explicit `null` for the field means
+    #[serde(default)]
+    pub on_null: OnNull,
 }
 
 impl StringEnumMask {
@@ -531,9 +718,31 @@ impl StringEnumMask {
             value,
             default,
             on_conflict,
+            values: vec![],
+            fallback: vec![],
+            on_null: OnNull::Error,
         }
     }
 
+    /// Read an explicit `null` for the field as `on_null` says.  See [`OnNull`].
+    pub fn with_on_null(mut self, on_null: OnNull) -> Self {
+        self.on_null = on_null;
+        self
+    }
+
+    /// Record every value the field allows, so that a `value` outside them is rejected.
+    pub fn with_values(mut self, values: Vec<String>) -> Self {
+        self.values = values;
+        self
+    }
+
+    /// Settle disagreements under [`OnConflict::Agreement`] with `fallback`.  See
+    /// [`EnumFallback`].
+    pub fn with_fallback(mut self, fallback: Vec<EnumFallback>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
     /// Apply this string enum mask to intermediate representation data.
     ///
     /// Checks for a boolean flag in the IR and if true, reports the associated
@@ -552,24 +761,33 @@ impl StringEnumMask {
     /// # use claudius::MessageParam;
     /// let mask = StringEnumMask::new(1, "priority".to_string(), "field_enum".to_string(), Some("high".to_string()), None, OnConflict::Default);
     /// let ir = serde_json::json!({"field_enum": true});
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// mask.apply_to(&ir, &mut report);
     /// ```
     pub fn apply_to(&self, ir: &serde_json::Value, report: &mut Report) {
-        match ir.get(&self.mask) {
+        match lookup(ir, &self.mask, self.on_null) {
             Some(serde_json::Value::Bool(value)) => {
                 if *value {
                     if let Some(enum_value) = &self.value {
-                        report.report_string_enum(
+                        let allowed = if self.values.is_empty() {
+                            std::slice::from_ref(enum_value)
+                        } else {
+                            &self.values
+                        };
+                        report.report_string_enum_with_fallback(
                             self.policy_index,
                             &self.name,
                             enum_value.clone(),
+                            allowed,
                             self.on_conflict,
+                            self.default.as_deref(),
+                            &self.fallback,
                         );
                     } else {
                         report.report_policy_index(self.policy_index);
                         report.report_string_enum_conflict(
                             &self.name,
+                            (self.policy_index, self.policy_index),
                             value.to_string(),
                             "null".to_string(),
                         );
@@ -578,6 +796,9 @@ impl StringEnumMask {
                     report.report_string_default(&self.name, default);
                 }
             }
+            Some(serde_json::Value::Null) if self.on_null == OnNull::Unset => {
+                report.report_policy_index(self.policy_index);
+            }
             Some(_) => {
                 report.report_type_check_failure(
                     file!(),
@@ -593,3 +814,188 @@ impl StringEnumMask {
         }
     }
 }
+
+/////////////////////////////////////////// DateTimeMask ///////////////////////////////////////////
+
+/// Represents a datetime field mask for policy application.
+///
+/// A DateTimeMask handles the extraction and conflict resolution of RFC 3339 timestamps from
+/// unstructured data based on policy rules.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct DateTimeMask {
+    /// Index of the policy this mask belongs to
+    pub policy_index: usize,
+    /// Original field name from the policy definition
+    pub name: String,
+    /// Masked field name unlikely to be in LLM training data
+    pub mask: String,
+    /// Default timestamp when the field is not present
+    pub default: Option<String>,
+    /// Expected timestamp for this policy rule
+    pub value: Option<String>,
+    /// Strategy for resolving conflicts when multiple policies set different values
+    pub on_conflict: OnConflict,
+    /// What an explicit `null` for the field means
+    #[serde(default)]
+    pub on_null: OnNull,
+}
+
+impl DateTimeMask {
+    /// Create a new DateTimeMask with the specified parameters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::{DateTimeMask, OnConflict};
+    /// let mask = DateTimeMask::new(
+    ///     1,
+    ///     "due".to_string(),
+    ///     "field_dt123".to_string(),
+    ///     None,
+    ///     None,
+    ///     OnConflict::SmallestValue,
+    /// );
+    /// ```
+    pub fn new(
+        policy_index: usize,
+        name: String,
+        mask: String,
+        default: Option<String>,
+        value: Option<String>,
+        on_conflict: OnConflict,
+    ) -> Self {
+        Self {
+            policy_index,
+            name,
+            mask,
+            default,
+            value,
+            on_conflict,
+            on_null: OnNull::Error,
+        }
+    }
+
+    /// Read an explicit `null` for the field as `on_null` says.  See [`OnNull`].
+    pub fn with_on_null(mut self, on_null: OnNull) -> Self {
+        self.on_null = on_null;
+        self
+    }
+
+    /// Apply this datetime mask to intermediate representation data.
+    ///
+    /// A value that names a different instant than the policy's expected value is reported as
+    /// a conflict, as is done for strings; one that is not RFC 3339 at all is reported as
+    /// [`crate::PolicyError::InvalidDateTime`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{DateTimeMask, OnConflict, Report};
+    /// let mask = DateTimeMask::new(1, "due".to_string(), "field_dt".to_string(), None, None, OnConflict::LargestValue);
+    /// let ir = serde_json::json!({"field_dt": "2024-03-01T17:00:00Z"});
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// mask.apply_to(&ir, &mut report);
+    /// assert_eq!(report.value()["due"], "2024-03-01T17:00:00Z");
+    /// ```
+    pub fn apply_to(&self, ir: &serde_json::Value, report: &mut Report) {
+        match lookup(ir, &self.mask, self.on_null) {
+            Some(serde_json::Value::String(value)) => {
+                let instant = crate::compare::rfc3339_instant(value);
+                let expected = self.value.as_ref().filter(|expected| {
+                    instant.is_some() && crate::compare::rfc3339_instant(expected) != instant
+                });
+                if let Some(expected) = expected {
+                    report.report_policy_index(self.policy_index);
+                    report.report_string_conflict(
+                        &self.name,
+                        (self.policy_index, self.policy_index),
+                        value.clone(),
+                        expected.clone(),
+                    );
+                } else {
+                    report.report_datetime(
+                        self.policy_index,
+                        &self.name,
+                        value.clone(),
+                        self.on_conflict,
+                    );
+                }
+            }
+            Some(serde_json::Value::Null) if self.on_null == OnNull::Unset => {
+                report.report_policy_index(self.policy_index);
+            }
+            Some(_) => {
+                report.report_type_check_failure(
+                    file!(),
+                    line!(),
+                    &format!("expected datetime string for {}", self.name),
+                );
+            }
+            _ => {
+                if let Some(default) = self.default.as_ref() {
+                    report.report_datetime_default(&self.name, default);
+                }
+            }
+        }
+    }
+}
+
+//////////////////////////////////////////// MatchMask /////////////////////////////////////////////
+
+/// Records that a rule whose action sets no fields matched.
+///
+/// Audit-only policies intentionally match without changing the output.  Without a mask of its
+/// own such a rule could never show up in `rules_matched`, so the builder gives it a boolean
+/// indicator that the LLM sets to `true` when the rule applies.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct MatchMask {
+    /// Index of the policy this mask belongs to
+    pub policy_index: usize,
+    /// Masked field name unlikely to be in LLM training data
+    pub mask: String,
+}
+
+impl MatchMask {
+    /// Create a new MatchMask for the given rule.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::MatchMask;
+    /// let mask = MatchMask::new(1, "field_match789".to_string());
+    /// ```
+    pub fn new(policy_index: usize, mask: String) -> Self {
+        Self { policy_index, mask }
+    }
+
+    /// Apply this match mask to intermediate representation data.
+    ///
+    /// Reports the rule as matched if the indicator is `true`; the output is never changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{MatchMask, Report};
+    /// let mask = MatchMask::new(1, "field_match".to_string());
+    /// let ir = serde_json::json!({"field_match": true});
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// mask.apply_to(&ir, &mut report);
+    /// assert_eq!(report.rules_matched, vec![1]);
+    /// ```
+    pub fn apply_to(&self, ir: &serde_json::Value, report: &mut Report) {
+        match ir.get(&self.mask) {
+            Some(serde_json::Value::Bool(true)) => report.report_policy_index(self.policy_index),
+            Some(serde_json::Value::Bool(false)) | None => {}
+            Some(_) => {
+                report.report_type_check_failure(
+                    file!(),
+                    line!(),
+                    &format!(
+                        "expected boolean for match indicator of rule {}",
+                        self.policy_index
+                    ),
+                );
+            }
+        }
+    }
+}
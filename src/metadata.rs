@@ -0,0 +1,103 @@
+//! Structured context that travels with the text.
+//!
+//! Much of what a rule needs to know about an email is not in its body: who sent it, its
+//! subject, and when it was sent.  Pasting headers into the text works, but the LLM then has to
+//! find them again.  [`Metadata`] carries them as typed fields that are rendered in a
+//! `<metadata>` block ahead of the text, so that a rule like "if it was sent after 5pm" has
+//! something unambiguous to refer to.
+
+use std::collections::BTreeMap;
+
+/// Structured facts about a text, given to the LLM alongside it.
+///
+/// # Example
+///
+/// ```
+/// use policyai::Metadata;
+///
+/// let metadata = Metadata::default()
+///     .with_sender("ceo@example.com")
+///     .with_subject("Quarterly numbers")
+///     .with_timestamp("2025-03-14T17:45:00-07:00")
+///     .with_field("mailbox", "finance");
+/// assert_eq!(
+///     metadata.render(),
+///     "<metadata>\n\
+///      <sender>ceo@example.com</sender>\n\
+///      <subject>Quarterly numbers</subject>\n\
+///      <timestamp>2025-03-14T17:45:00-07:00</timestamp>\n\
+///      <field name=\"mailbox\">finance</field>\n\
+///      </metadata>\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Metadata {
+    /// Who sent the text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// The subject or title of the text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// When the text was sent.  RFC 3339 with the sender's UTC offset, such as
+    /// `2025-03-14T17:45:00-07:00`, lets rules about the time of day be decided correctly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    /// Any other facts, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Set who sent the text.
+    pub fn with_sender(mut self, sender: impl Into<String>) -> Self {
+        self.sender = Some(sender.into());
+        self
+    }
+
+    /// Set the subject of the text.
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// Set when the text was sent.
+    pub fn with_timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = Some(timestamp.into());
+        self
+    }
+
+    /// Add a fact named `name`, replacing any fact of the same name.
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.insert(name.into(), value.into());
+        self
+    }
+
+    /// True if there is nothing to render.
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_none()
+            && self.subject.is_none()
+            && self.timestamp.is_none()
+            && self.fields.is_empty()
+    }
+
+    /// The `<metadata>` block given to the LLM, or the empty string if there is no metadata.
+    pub fn render(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut block = "<metadata>\n".to_string();
+        for (tag, value) in [
+            ("sender", &self.sender),
+            ("subject", &self.subject),
+            ("timestamp", &self.timestamp),
+        ] {
+            if let Some(value) = value {
+                block += &format!("<{tag}>{value}</{tag}>\n");
+            }
+        }
+        for (name, value) in self.fields.iter() {
+            block += &format!("<field name=\"{name}\">{value}</field>\n");
+        }
+        block + "</metadata>\n"
+    }
+}
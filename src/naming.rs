@@ -0,0 +1,158 @@
+//! Key casing for structured output.
+//!
+//! Field names in the DSL are whatever the policy author wrote.  A [`NamingPolicy`] rewrites
+//! the keys of a report's output so that consumers see one casing convention no matter how the
+//! fields were declared.
+
+/// Defines the casing of keys in the object returned by [`crate::Report::value`].
+///
+/// Names are split into words at underscores, hyphens, whitespace, and lower-to-upper case
+/// changes, so `due_date`, `dueDate`, `due-date`, and `"due date"` all name the same words.
+/// Only the top-level keys are rewritten.  If two fields map to the same key, the one later in
+/// the output wins.
+///
+/// # Example
+///
+/// ```
+/// use policyai::{Manager, NamingPolicy};
+///
+/// let manager = Manager::default().with_naming_policy(NamingPolicy::CamelCase);
+/// ```
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum NamingPolicy {
+    /// Keep field names as they are declared in the policy type
+    #[default]
+    #[serde(rename = "preserve")]
+    Preserve,
+    /// Lowercase words joined by underscores, e.g. `due_date`
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+    /// Words joined with every word but the first capitalized, e.g. `dueDate`
+    #[serde(rename = "camelCase")]
+    CamelCase,
+    /// Lowercase words joined by hyphens, e.g. `due-date`
+    #[serde(rename = "kebab-case")]
+    KebabCase,
+}
+
+impl NamingPolicy {
+    /// The key under which the field `name` appears in the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::NamingPolicy;
+    ///
+    /// assert_eq!(NamingPolicy::SnakeCase.rename("dueDate"), "due_date");
+    /// assert_eq!(NamingPolicy::CamelCase.rename("due date"), "dueDate");
+    /// assert_eq!(NamingPolicy::KebabCase.rename("HTTPStatus"), "http-status");
+    /// assert_eq!(NamingPolicy::Preserve.rename("Due Date"), "Due Date");
+    /// ```
+    pub fn rename(self, name: &str) -> String {
+        let words = words(name);
+        match self {
+            NamingPolicy::Preserve => name.to_string(),
+            NamingPolicy::SnakeCase => words.join("_"),
+            NamingPolicy::KebabCase => words.join("-"),
+            NamingPolicy::CamelCase => {
+                let mut renamed = String::new();
+                for (idx, word) in words.iter().enumerate() {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) if idx > 0 => {
+                            renamed.extend(first.to_uppercase());
+                            renamed.push_str(chars.as_str());
+                        }
+                        _ => renamed.push_str(word),
+                    }
+                }
+                renamed
+            }
+        }
+    }
+
+    /// Rename the keys of `value` according to this policy.  Non-object values are returned
+    /// as-is, and key order is kept.
+    pub fn apply(self, value: serde_json::Value) -> serde_json::Value {
+        match (self, value) {
+            (NamingPolicy::Preserve, value) => value,
+            (_, serde_json::Value::Object(obj)) => serde_json::Value::Object(
+                obj.into_iter()
+                    .map(|(key, value)| (self.rename(&key), value))
+                    .collect(),
+            ),
+            (_, value) => value,
+        }
+    }
+}
+
+/// Split `name` into lowercase words.
+fn words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (idx, c) in chars.iter().enumerate() {
+        if *c == '_' || *c == '-' || c.is_whitespace() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars.get(idx + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn naming_policy_splits_every_convention_into_the_same_words() {
+        for name in [
+            "due_date", "dueDate", "DueDate", "due-date", "due date", "DUE_DATE",
+        ] {
+            assert_eq!(NamingPolicy::SnakeCase.rename(name), "due_date", "{name}");
+            assert_eq!(NamingPolicy::CamelCase.rename(name), "dueDate", "{name}");
+            assert_eq!(NamingPolicy::KebabCase.rename(name), "due-date", "{name}");
+        }
+        assert_eq!(
+            NamingPolicy::SnakeCase.rename("parseHTTPResponse2"),
+            "parse_http_response2"
+        );
+    }
+
+    #[test]
+    fn naming_policy_renames_top_level_keys_in_order() {
+        let value =
+            serde_json::json!({"is_urgent": true, "tagList": ["a_b"], "x": {"inner_key": 1}});
+        let renamed = NamingPolicy::KebabCase.apply(value);
+        let keys = renamed
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["is-urgent", "tag-list", "x"]);
+        assert_eq!(renamed["tag-list"], serde_json::json!(["a_b"]));
+        assert_eq!(renamed["x"], serde_json::json!({"inner_key": 1}));
+    }
+
+    #[test]
+    fn naming_policy_serialization() {
+        let serialized = serde_json::to_string(&NamingPolicy::CamelCase).unwrap();
+        assert_eq!(serialized, "\"camelCase\"");
+        let deserialized: NamingPolicy = serde_json::from_str("\"kebab-case\"").unwrap();
+        assert_eq!(deserialized, NamingPolicy::KebabCase);
+    }
+}
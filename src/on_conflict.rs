@@ -12,18 +12,38 @@
 ///
 /// - `Default`: Use the field's default value, ignoring policy values
 /// - `Agreement`: All policies must agree on the value, or a conflict is reported
-/// - `LargestValue`: The largest value wins (true > false for bools, longer strings win, etc.)
+/// - `LargestValue`: The largest value wins (true > false for bools, longer strings win, the
+///   latest datetime wins, etc.)
+/// - `SmallestValue`: The smallest value wins (false < true for bools, shorter strings win, the
+///   earliest datetime wins, etc.)
+/// - `Sticky`: Once true, a bool stays true; once set, any other field stays set to its first
+///   value
+/// - `FirstWins`: The value of the policy added first wins, whatever it is
+/// - `LastWins`: The value of the policy added last wins, whatever it is
+/// - `HighestPriority`: The value of the policy with the highest [`crate::Policy::priority`]
+///   wins; policies of equal priority must agree, as under `Agreement`
+/// - `Unknown`: A strategy this version of the crate does not know
+///
+/// # Compatibility
+///
+/// Policy files written by a newer version of the crate can name strategies this version
+/// lacks.  They deserialize as `Unknown` instead of failing, so the rest of the file stays
+/// usable.  `Unknown` settles disagreements the way `Agreement` does, the most conservative
+/// choice, and the report records [`crate::PolicyError::UnknownOnConflict`] to say that an
+/// upgrade is needed for the intended behavior.  The original name is not kept, so `Unknown`
+/// serializes as `"unknown"` and displays as agreement in the policy type language.
 ///
 /// # Example
 ///
 /// ```
-/// use policyai::{Field, OnConflict};
+/// use policyai::{EnumSource, Field, OnConflict};
 ///
 /// let field = Field::StringEnum {
 ///     name: "priority".to_string(),
 ///     values: vec!["low".to_string(), "high".to_string()],
 ///     default: None,
 ///     on_conflict: OnConflict::LargestValue, // "high" would win over "low"
+///     source: EnumSource::Static,
 /// };
 /// ```
 #[derive(Copy, Clone, Default, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -38,6 +58,198 @@ pub enum OnConflict {
     /// The largest value wins
     #[serde(rename = "largest")]
     LargestValue,
+    /// The smallest value wins
+    #[serde(rename = "smallest")]
+    SmallestValue,
+    /// A bool that is true stays true; any other field keeps the first value set
+    #[serde(rename = "sticky")]
+    Sticky,
+    /// The policy added first owns the field
+    #[serde(rename = "first")]
+    FirstWins,
+    /// The policy added last owns the field
+    #[serde(rename = "last")]
+    LastWins,
+    /// The policy with the highest priority owns the field
+    #[serde(rename = "priority")]
+    HighestPriority,
+    /// A strategy from a newer version of the crate, handled like `Agreement`
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// A step in the order that picks a value for an enum field whose policies failed to agree.
+///
+/// Under [`OnConflict::Agreement`] a disagreement on an enum leaves the first value in place,
+/// which is rarely the one a downstream system should act on.  A fallback order configured per
+/// field, with [`crate::Manager::with_enum_fallbacks`], replaces it with a deliberate choice.
+/// The steps are tried in order and the first that yields a value wins; if none does, the first
+/// value stays.  Either way the disagreement is still reported as a conflict.
+///
+/// # Example
+///
+/// ```
+/// use policyai::{EnumFallback, OnConflict, Report};
+///
+/// let allowed = ["low".to_string(), "high".to_string(), "critical".to_string()];
+/// let order = [EnumFallback::Default, EnumFallback::LastDeclared];
+/// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+/// for (policy, value) in [(1, "high"), (2, "critical")] {
+///     report.report_string_enum_with_fallback(
+///         policy,
+///         "severity",
+///         value.to_string(),
+///         &allowed,
+///         OnConflict::Agreement,
+///         None,
+///         &order,
+///     );
+/// }
+/// // No default is declared, so the value declared last wins.
+/// assert_eq!(report.value()["severity"], "critical");
+/// assert_eq!(report.conflicts().len(), 1);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum EnumFallback {
+    /// The field's default, if its type declares one
+    #[serde(rename = "default")]
+    Default,
+    /// Of the values in conflict, the one declared first
+    #[serde(rename = "first_declared")]
+    FirstDeclared,
+    /// Of the values in conflict, the one declared last; with values declared from least to
+    /// most severe, the most severe
+    #[serde(rename = "last_declared")]
+    LastDeclared,
+}
+
+impl EnumFallback {
+    /// The value `order` picks when `current` and `value` disagree, or `None` to keep `current`.
+    pub(crate) fn choose(
+        order: &[EnumFallback],
+        default: Option<&str>,
+        allowed: &[String],
+        current: &str,
+        value: &str,
+    ) -> Option<String> {
+        let in_conflict = |candidate: &&String| *candidate == current || *candidate == value;
+        order.iter().find_map(|step| match step {
+            EnumFallback::Default => default.map(str::to_string),
+            EnumFallback::FirstDeclared => allowed.iter().find(in_conflict).cloned(),
+            EnumFallback::LastDeclared => allowed.iter().rfind(in_conflict).cloned(),
+        })
+    }
+}
+
+/// What a mask makes of an explicit `null` the LLM output for its field.
+///
+/// LLMs sometimes answer `null` for a field they were told to leave out.  Which reading is
+/// right depends on the field, so it is configured per field with
+/// [`crate::ReportBuilder::with_null_handling`] or [`crate::Manager::with_null_handling`].
+///
+/// # Example
+///
+/// ```
+/// # use policyai::{BoolMask, OnConflict, OnNull, Report};
+/// let ir = serde_json::json!({"field_abc": null});
+/// let mask = BoolMask::new(1, "urgent".to_string(), "field_abc".to_string(), Some(false), OnConflict::Default);
+///
+/// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+/// mask.clone().apply_to(&ir, &mut report);
+/// assert_eq!(report.errors().len(), 1);
+///
+/// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+/// mask.clone().with_on_null(OnNull::Absent).apply_to(&ir, &mut report);
+/// assert!(report.errors().is_empty());
+/// assert!(report.rules_matched.is_empty());
+///
+/// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+/// mask.with_on_null(OnNull::Unset).apply_to(&ir, &mut report);
+/// assert_eq!(report.rules_matched, vec![1]);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum OnNull {
+    /// Report a type-check failure, as for any other value of the wrong type
+    #[default]
+    #[serde(rename = "error")]
+    Error,
+    /// Treat the field as left out: the rule did not set it
+    #[serde(rename = "absent")]
+    Absent,
+    /// The rule matched but sets no value, so the field keeps its default unless another rule
+    /// sets it
+    #[serde(rename = "unset")]
+    Unset,
+}
+
+/// The type of value two policies disagreed on.
+#[derive(
+    Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
+pub enum ConflictKind {
+    /// A boolean field
+    #[serde(rename = "bool")]
+    Bool,
+    /// A numeric field
+    #[serde(rename = "number")]
+    Number,
+    /// A free-form string field
+    #[serde(rename = "string")]
+    String,
+    /// A string enum field
+    #[serde(rename = "string_enum")]
+    StringEnum,
+    /// An RFC 3339 datetime field
+    #[serde(rename = "datetime")]
+    DateTime,
+}
+
+/// What the field's [`OnConflict`] strategy did with a disagreement.
+#[derive(
+    Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
+pub enum ResolutionOutcome {
+    /// The larger value was kept
+    #[serde(rename = "largest")]
+    Largest,
+    /// The smaller value was kept
+    #[serde(rename = "smallest")]
+    Smallest,
+    /// The later value was ignored under [`OnConflict::Default`]
+    #[serde(rename = "defaulted")]
+    Defaulted,
+    /// The value that stuck under [`OnConflict::Sticky`] was kept
+    #[serde(rename = "sticky")]
+    Stuck,
+    /// The value of the policy added first was kept under [`OnConflict::FirstWins`]
+    #[serde(rename = "first")]
+    First,
+    /// The value of the policy added last was kept under [`OnConflict::LastWins`]
+    #[serde(rename = "last")]
+    Last,
+    /// The value of the policy with the higher priority was kept under
+    /// [`OnConflict::HighestPriority`]
+    #[serde(rename = "priority")]
+    Prioritized,
+    /// The disagreement could not be settled and was reported as a conflict
+    #[serde(rename = "errored")]
+    Errored,
+}
+
+/// A record of two policies setting one field to different values, and how it was settled.
+///
+/// Reports keep one of these for every disagreement, including the ones that resolved cleanly,
+/// so that analysis can show which strategies are doing real work.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Resolution {
+    /// The field the policies disagreed on.
+    pub field: String,
+    /// The type of the field.
+    pub kind: ConflictKind,
+    /// The strategy in effect for the field.
+    pub on_conflict: OnConflict,
+    /// What the strategy did.
+    pub outcome: ResolutionOutcome,
 }
 
 #[cfg(test)]
@@ -86,6 +298,63 @@ mod tests {
         assert_eq!(serialized, "\"largest\"");
         let deserialized: OnConflict = serde_json::from_str(&serialized).unwrap();
         assert_eq!(conflict, deserialized);
+
+        let conflict = OnConflict::SmallestValue;
+        let serialized = serde_json::to_string(&conflict).unwrap();
+        assert_eq!(serialized, "\"smallest\"");
+        let deserialized: OnConflict = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(conflict, deserialized);
+
+        let conflict = OnConflict::Sticky;
+        let serialized = serde_json::to_string(&conflict).unwrap();
+        assert_eq!(serialized, "\"sticky\"");
+        let deserialized: OnConflict = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(conflict, deserialized);
+
+        let conflict = OnConflict::FirstWins;
+        let serialized = serde_json::to_string(&conflict).unwrap();
+        assert_eq!(serialized, "\"first\"");
+        let deserialized: OnConflict = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(conflict, deserialized);
+
+        let conflict = OnConflict::LastWins;
+        let serialized = serde_json::to_string(&conflict).unwrap();
+        assert_eq!(serialized, "\"last\"");
+        let deserialized: OnConflict = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(conflict, deserialized);
+
+        let conflict = OnConflict::HighestPriority;
+        let serialized = serde_json::to_string(&conflict).unwrap();
+        assert_eq!(serialized, "\"priority\"");
+        let deserialized: OnConflict = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(conflict, deserialized);
+    }
+
+    #[test]
+    fn on_conflict_from_a_newer_version_is_unknown() {
+        let deserialized: OnConflict = serde_json::from_str("\"weighted\"").unwrap();
+        assert_eq!(deserialized, OnConflict::Unknown);
+        let field: crate::Field = serde_json::from_value(serde_json::json!({
+            "number": {"name": "score", "default": 0.0, "on_conflict": "weighted"}
+        }))
+        .unwrap();
+        assert_eq!(
+            field.to_string(),
+            "score: number @ agreement = 0",
+            "unknown strategies render as the agreement they fall back to"
+        );
+
+        let mut report = crate::Report::default();
+        report.report_bool(1, "urgent", true, OnConflict::Unknown);
+        report.report_bool(2, "urgent", false, OnConflict::Unknown);
+        report.report_bool(3, "urgent", false, OnConflict::Unknown);
+        assert_eq!(report.conflicts().len(), 2);
+        let unknown = report
+            .errors()
+            .iter()
+            .filter(|err| matches!(err, crate::PolicyError::UnknownOnConflict { field } if field == "urgent"))
+            .count();
+        assert_eq!(unknown, 1);
     }
 
     #[test]
@@ -94,4 +363,30 @@ mod tests {
         assert_eq!(format!("{:?}", OnConflict::Agreement), "Agreement");
         assert_eq!(format!("{:?}", OnConflict::LargestValue), "LargestValue");
     }
+
+    #[test]
+    fn enum_fallback_tries_each_step_in_order() {
+        let allowed = [
+            "low".to_string(),
+            "high".to_string(),
+            "critical".to_string(),
+        ];
+        let choose = |order: &[EnumFallback], default| {
+            EnumFallback::choose(order, default, &allowed, "high", "low")
+        };
+        assert_eq!(choose(&[], Some("low")), None);
+        assert_eq!(choose(&[EnumFallback::Default], None), None);
+        assert_eq!(
+            choose(&[EnumFallback::Default], Some("critical")).as_deref(),
+            Some("critical")
+        );
+        assert_eq!(
+            choose(&[EnumFallback::Default, EnumFallback::LastDeclared], None).as_deref(),
+            Some("high")
+        );
+        assert_eq!(
+            choose(&[EnumFallback::FirstDeclared], Some("critical")).as_deref(),
+            Some("low")
+        );
+    }
 }
@@ -6,7 +6,8 @@
 
 use std::fmt;
 
-use crate::{t64, Field, OnConflict, PolicyType};
+use crate::field::{is_identifier_continue, is_identifier_start};
+use crate::{t64, EnumSource, Field, FieldGroup, OnConflict, PolicyType};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Position {
@@ -141,6 +142,27 @@ impl fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+/// A syntax slip that relaxed parsing accepted instead of rejecting.
+///
+/// See [`crate::PolicyType::parse_relaxed`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    /// What was wrong and how it was read
+    pub message: String,
+    /// The position in the input where the slip occurred
+    pub position: Position,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "at line {}:{}: {}",
+            self.position.line, self.position.column, self.message
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     // Keywords
@@ -148,6 +170,7 @@ pub enum Token {
     Bool,
     String,
     Number,
+    DateTime,
     True,
     False,
 
@@ -174,6 +197,9 @@ pub enum Token {
     Last,
     Highest,
     Largest,
+    Smallest,
+    Earliest,
+    Latest,
 }
 
 impl fmt::Display for Token {
@@ -183,6 +209,7 @@ impl fmt::Display for Token {
             Token::Bool => write!(f, "bool"),
             Token::String => write!(f, "string"),
             Token::Number => write!(f, "number"),
+            Token::DateTime => write!(f, "datetime"),
             Token::True => write!(f, "true"),
             Token::False => write!(f, "false"),
             Token::Identifier(s) => write!(f, "{s}"),
@@ -203,10 +230,21 @@ impl fmt::Display for Token {
             Token::Last => write!(f, "last"),
             Token::Highest => write!(f, "highest"),
             Token::Largest => write!(f, "largest"),
+            Token::Smallest => write!(f, "smallest"),
+            Token::Earliest => write!(f, "earliest"),
+            Token::Latest => write!(f, "latest"),
         }
     }
 }
 
+/// Field names that the manager reserves for its own bookkeeping in the LLM output.
+const RESERVED_FIELD_NAMES: &[&str] = &[
+    "__rule_numbers__",
+    "__justification__",
+    "__confidence__",
+    "__non_match_reasons__",
+];
+
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
@@ -260,7 +298,7 @@ impl Lexer {
     fn read_identifier(&mut self) -> String {
         let mut ident = String::new();
         while let Some(ch) = self.peek() {
-            if ch.is_alphanumeric() || ch == '_' {
+            if is_identifier_continue(ch) {
                 ident.push(ch);
                 self.advance();
             } else {
@@ -316,96 +354,175 @@ impl Lexer {
         }
     }
 
+    /// Read a run of digits with optional `_` separators into `out`, returning how many digits
+    /// were read.  A separator must sit between two digits.
+    fn read_digits(&mut self, out: &mut String, what: &str) -> Result<usize, ParseError> {
+        let mut digits = 0;
+        let mut last_was_separator = false;
+        while let Some(ch) = self.peek() {
+            if ch.is_ascii_digit() {
+                out.push(ch);
+                digits += 1;
+                last_was_separator = false;
+            } else if ch == '_' {
+                if digits == 0 || last_was_separator {
+                    return Err(ParseError::InvalidNumber {
+                        reason: format!("digit separator '_' must follow a digit in the {what}"),
+                        position: self.current_position(),
+                    });
+                }
+                last_was_separator = true;
+            } else {
+                break;
+            }
+            self.advance();
+        }
+        if last_was_separator {
+            return Err(ParseError::InvalidNumber {
+                reason: format!("digit separator '_' must be followed by a digit in the {what}"),
+                position: self.current_position(),
+            });
+        }
+        Ok(digits)
+    }
+
     fn read_number(&mut self) -> Result<f64, ParseError> {
         let start_pos = self.current_position();
         let mut num_str = String::new();
 
-        // Handle negative sign
-        if self.peek() == Some('-') {
-            num_str.push('-');
+        // Handle sign
+        match self.peek() {
+            Some('-') => {
+                num_str.push('-');
+                self.advance();
+            }
+            Some('+') => {
+                self.advance();
+            }
+            _ => {}
+        }
+
+        // Integer part, then an optional fraction
+        let mut digits = self.read_digits(&mut num_str, "integer part")?;
+        if self.peek() == Some('.') {
+            num_str.push('.');
             self.advance();
+            digits += self.read_digits(&mut num_str, "fraction")?;
+        }
+        if digits == 0 {
+            return Err(ParseError::InvalidNumber {
+                reason: "expected at least one digit".to_string(),
+                position: start_pos,
+            });
         }
 
-        // Read digits and decimal point
-        while let Some(ch) = self.peek() {
-            if ch.is_ascii_digit() || (ch == '.' && !num_str.contains('.')) {
-                num_str.push(ch);
+        // Optional exponent
+        if let Some(e @ ('e' | 'E')) = self.peek() {
+            num_str.push(e);
+            self.advance();
+            if let Some(sign @ ('+' | '-')) = self.peek() {
+                num_str.push(sign);
                 self.advance();
-            } else {
-                break;
+            }
+            let exponent_pos = self.current_position();
+            if self.read_digits(&mut num_str, "exponent")? == 0 {
+                return Err(ParseError::InvalidNumber {
+                    reason: "exponent has no digits".to_string(),
+                    position: exponent_pos,
+                });
             }
         }
 
-        num_str
+        let value = num_str
             .parse::<f64>()
             .map_err(|_| ParseError::InvalidNumber {
                 reason: format!("'{num_str}' is not a valid number"),
+                position: start_pos.clone(),
+            })?;
+        if !value.is_finite() {
+            return Err(ParseError::InvalidNumber {
+                reason: format!("'{num_str}' is out of range"),
                 position: start_pos,
-            })
+            });
+        }
+        Ok(value)
     }
 
     pub fn tokenize(&mut self) -> Result<Vec<(Token, Position)>, ParseError> {
+        Ok(self
+            .tokenize_with_spans()?
+            .into_iter()
+            .map(|(token, pos, _)| (token, pos))
+            .collect())
+    }
+
+    /// Tokenize, recording with each token the range of character offsets it was read from.
+    fn tokenize_with_spans(
+        &mut self,
+    ) -> Result<Vec<(Token, Position, std::ops::Range<usize>)>, ParseError> {
         let mut tokens = Vec::new();
 
         loop {
             self.skip_whitespace();
 
             let pos = self.current_position();
+            let start = self.position;
 
             match self.peek() {
                 None => break,
                 Some('"') => {
                     let string_lit = self.read_string_literal()?;
-                    tokens.push((Token::StringLiteral(string_lit), pos));
+                    tokens.push((Token::StringLiteral(string_lit), pos, start..self.position));
                 }
-                Some('-') | Some('0'..='9') => {
+                Some('-') | Some('+') | Some('0'..='9') => {
                     let num = self.read_number()?;
-                    tokens.push((Token::NumberLiteral(num), pos));
+                    tokens.push((Token::NumberLiteral(num), pos, start..self.position));
                 }
                 Some('{') => {
                     self.advance();
-                    tokens.push((Token::LeftBrace, pos));
+                    tokens.push((Token::LeftBrace, pos, start..self.position));
                 }
                 Some('}') => {
                     self.advance();
-                    tokens.push((Token::RightBrace, pos));
+                    tokens.push((Token::RightBrace, pos, start..self.position));
                 }
                 Some('[') => {
                     self.advance();
-                    tokens.push((Token::LeftBracket, pos));
+                    tokens.push((Token::LeftBracket, pos, start..self.position));
                 }
                 Some(']') => {
                     self.advance();
-                    tokens.push((Token::RightBracket, pos));
+                    tokens.push((Token::RightBracket, pos, start..self.position));
                 }
                 Some(':') => {
                     self.advance();
                     if self.peek() == Some(':') {
                         self.advance();
-                        tokens.push((Token::DoubleColon, pos));
+                        tokens.push((Token::DoubleColon, pos, start..self.position));
                     } else {
-                        tokens.push((Token::Colon, pos));
+                        tokens.push((Token::Colon, pos, start..self.position));
                     }
                 }
                 Some(',') => {
                     self.advance();
-                    tokens.push((Token::Comma, pos));
+                    tokens.push((Token::Comma, pos, start..self.position));
                 }
                 Some('=') => {
                     self.advance();
-                    tokens.push((Token::Equals, pos));
+                    tokens.push((Token::Equals, pos, start..self.position));
                 }
                 Some('@') => {
                     self.advance();
-                    tokens.push((Token::At, pos));
+                    tokens.push((Token::At, pos, start..self.position));
                 }
-                Some(ch) if ch.is_alphabetic() || ch == '_' => {
+                Some(ch) if is_identifier_start(ch) => {
                     let ident = self.read_identifier();
                     let token = match ident.as_str() {
                         "type" => Token::Type,
                         "bool" => Token::Bool,
                         "string" => Token::String,
                         "number" => Token::Number,
+                        "datetime" => Token::DateTime,
                         "true" => Token::True,
                         "false" => Token::False,
                         "agreement" => Token::Agreement,
@@ -414,9 +531,12 @@ impl Lexer {
                         "last" => Token::Last,
                         "highest" => Token::Highest,
                         "largest" => Token::Largest,
+                        "smallest" => Token::Smallest,
+                        "earliest" => Token::Earliest,
+                        "latest" => Token::Latest,
                         _ => Token::Identifier(ident),
                     };
-                    tokens.push((token, pos));
+                    tokens.push((token, pos, start..self.position));
                 }
                 Some(ch) => {
                     return Err(ParseError::Custom {
@@ -434,6 +554,8 @@ impl Lexer {
 pub struct Parser {
     tokens: Vec<(Token, Position)>,
     position: usize,
+    relaxed: bool,
+    warnings: Vec<ParseWarning>,
 }
 
 impl Parser {
@@ -441,6 +563,65 @@ impl Parser {
         Self {
             tokens,
             position: 0,
+            relaxed: false,
+            warnings: vec![],
+        }
+    }
+
+    /// Tolerate common slips in hand-written types, recording a warning for each.
+    pub fn relaxed(mut self) -> Self {
+        self.relaxed = true;
+        self
+    }
+
+    fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(ParseWarning {
+            message: message.into(),
+            position: self.current_position(),
+        });
+    }
+
+    /// The `:` between a field's name and its type.  Relaxed parsing also accepts `=`.
+    fn expect_colon(&mut self) -> Result<(), ParseError> {
+        if self.relaxed && self.peek() == Some(&Token::Equals) {
+            self.warn("expected ':' after field name, found '='");
+            self.advance();
+            return Ok(());
+        }
+        self.expect(Token::Colon)
+    }
+
+    /// Consume the `=` that introduces a default, returning whether there was one.  Relaxed
+    /// parsing also accepts `:`.
+    fn at_default(&mut self) -> bool {
+        match self.peek() {
+            Some(Token::Equals) => {}
+            Some(Token::Colon) if self.relaxed => {
+                self.warn("expected '=' before default value, found ':'");
+            }
+            _ => return false,
+        }
+        self.advance();
+        true
+    }
+
+    /// The `,` after a field or group.  It is optional before the closing brace; relaxed
+    /// parsing also lets it be left out between definitions.
+    fn field_separator(&mut self) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(Token::Comma) => {
+                self.advance();
+                Ok(())
+            }
+            Some(Token::RightBrace) => Ok(()),
+            Some(_) if self.relaxed => {
+                self.warn("missing ',' after field definition");
+                Ok(())
+            }
+            _ => Err(ParseError::Custom {
+                message: "expected ',' or '}' after field definition".to_string(),
+                position: self.current_position(),
+            }),
         }
     }
 
@@ -505,6 +686,29 @@ impl Parser {
         }
     }
 
+    /// A field name is an identifier or, for names that are not valid identifiers (spaces,
+    /// punctuation, keywords), a quoted string.
+    fn parse_field_name(&mut self) -> Result<String, ParseError> {
+        let pos = self.current_position();
+        let name = match self.peek() {
+            Some(Token::StringLiteral(_)) => self.parse_string_literal()?,
+            _ => self.parse_identifier()?,
+        };
+        if name.trim().is_empty() {
+            return Err(ParseError::InvalidIdentifier {
+                reason: "field name must not be empty or all whitespace".to_string(),
+                position: pos,
+            });
+        }
+        if RESERVED_FIELD_NAMES.contains(&name.as_str()) {
+            return Err(ParseError::InvalidIdentifier {
+                reason: format!("'{name}' is reserved"),
+                position: pos,
+            });
+        }
+        Ok(name)
+    }
+
     fn parse_string_literal(&mut self) -> Result<String, ParseError> {
         let pos = self.current_position();
         match self.advance() {
@@ -537,14 +741,55 @@ impl Parser {
         }
     }
 
+    /// `first added wins`, `last added wins`, or `highest priority wins`, which every field with
+    /// a strategy accepts.  `first`, `added`, and `priority` are not keywords, so fields may
+    /// still be named after them.
+    fn parse_policy_wins(&mut self) -> Result<Option<OnConflict>, ParseError> {
+        let added = Token::Identifier("added".to_string());
+        let priority = Token::Identifier("priority".to_string());
+        let second = self.tokens.get(self.position + 1).map(|(t, _)| t);
+        if self.peek() == Some(&Token::Highest) && second == Some(&priority) {
+            self.advance();
+            self.advance();
+            self.expect(Token::Wins)?;
+            return Ok(Some(OnConflict::HighestPriority));
+        }
+        let strategy = match self.peek() {
+            Some(Token::Identifier(first)) if first == "first" => OnConflict::FirstWins,
+            Some(Token::Last)
+                if self.tokens.get(self.position + 1).map(|(t, _)| t) == Some(&added) =>
+            {
+                OnConflict::LastWins
+            }
+            _ => return Ok(None),
+        };
+        self.advance();
+        self.expect(added)?;
+        self.expect(Token::Wins)?;
+        Ok(Some(strategy))
+    }
+
     fn parse_bool_conflict(&mut self) -> Result<OnConflict, ParseError> {
         if self.peek() == Some(&Token::At) {
             self.advance();
+            if let Some(strategy) = self.parse_policy_wins()? {
+                return Ok(strategy);
+            }
             match self.peek() {
                 Some(Token::Sticky) => {
                     self.advance();
+                    Ok(OnConflict::Sticky)
+                }
+                Some(Token::Largest) => {
+                    self.advance();
+                    self.expect(Token::Wins)?;
                     Ok(OnConflict::LargestValue)
                 }
+                Some(Token::Smallest) => {
+                    self.advance();
+                    self.expect(Token::Wins)?;
+                    Ok(OnConflict::SmallestValue)
+                }
                 Some(Token::Agreement) => {
                     self.advance();
                     Ok(OnConflict::Agreement)
@@ -552,7 +797,8 @@ impl Parser {
                 _ => {
                     let pos = self.current_position();
                     Err(ParseError::Custom {
-                        message: "expected 'sticky' or 'agreement' after '@'".to_string(),
+                        message: "expected 'sticky', 'largest wins', 'smallest wins', 'first added wins', 'last added wins', 'highest priority wins', or 'agreement' after '@'"
+                            .to_string(),
                         position: pos,
                     })
                 }
@@ -565,17 +811,29 @@ impl Parser {
     fn parse_string_conflict(&mut self) -> Result<OnConflict, ParseError> {
         if self.peek() == Some(&Token::At) {
             self.advance();
+            if let Some(strategy) = self.parse_policy_wins()? {
+                return Ok(strategy);
+            }
             if self.peek() == Some(&Token::Last) {
                 self.advance();
                 self.expect(Token::Wins)?;
                 Ok(OnConflict::LargestValue)
+            } else if self.peek() == Some(&Token::Smallest) {
+                self.advance();
+                self.expect(Token::Wins)?;
+                Ok(OnConflict::SmallestValue)
+            } else if self.peek() == Some(&Token::Sticky) {
+                self.advance();
+                Ok(OnConflict::Sticky)
             } else if self.peek() == Some(&Token::Agreement) {
                 self.advance();
                 Ok(OnConflict::Agreement)
             } else {
                 let pos = self.current_position();
                 Err(ParseError::Custom {
-                    message: "expected 'last wins' or 'agreement' after '@'".to_string(),
+                    message:
+                        "expected 'last wins', 'smallest wins', 'first added wins', 'last added wins', 'highest priority wins', 'sticky', or 'agreement' after '@'"
+                            .to_string(),
                     position: pos,
                 })
             }
@@ -587,17 +845,29 @@ impl Parser {
     fn parse_string_enum_conflict(&mut self) -> Result<OnConflict, ParseError> {
         if self.peek() == Some(&Token::At) {
             self.advance();
+            if let Some(strategy) = self.parse_policy_wins()? {
+                return Ok(strategy);
+            }
             if self.peek() == Some(&Token::Highest) {
                 self.advance();
                 self.expect(Token::Wins)?;
                 Ok(OnConflict::LargestValue)
+            } else if self.peek() == Some(&Token::Smallest) {
+                self.advance();
+                self.expect(Token::Wins)?;
+                Ok(OnConflict::SmallestValue)
+            } else if self.peek() == Some(&Token::Sticky) {
+                self.advance();
+                Ok(OnConflict::Sticky)
             } else if self.peek() == Some(&Token::Agreement) {
                 self.advance();
                 Ok(OnConflict::Agreement)
             } else {
                 let pos = self.current_position();
                 Err(ParseError::Custom {
-                    message: "expected 'highest wins' or 'agreement' after '@'".to_string(),
+                    message:
+                        "expected 'highest wins', 'smallest wins', 'first added wins', 'last added wins', 'highest priority wins', 'sticky', or 'agreement' after '@'"
+                            .to_string(),
                     position: pos,
                 })
             }
@@ -609,17 +879,27 @@ impl Parser {
     fn parse_number_conflict(&mut self) -> Result<OnConflict, ParseError> {
         if self.peek() == Some(&Token::At) {
             self.advance();
+            if let Some(strategy) = self.parse_policy_wins()? {
+                return Ok(strategy);
+            }
             if matches!(self.peek(), Some(&Token::Last) | Some(&Token::Largest)) {
                 self.advance();
                 self.expect(Token::Wins)?;
                 Ok(OnConflict::LargestValue)
+            } else if self.peek() == Some(&Token::Smallest) {
+                self.advance();
+                self.expect(Token::Wins)?;
+                Ok(OnConflict::SmallestValue)
+            } else if self.peek() == Some(&Token::Sticky) {
+                self.advance();
+                Ok(OnConflict::Sticky)
             } else if self.peek() == Some(&Token::Agreement) {
                 self.advance();
                 Ok(OnConflict::Agreement)
             } else {
                 let pos = self.current_position();
                 Err(ParseError::Custom {
-                    message: "expected 'last wins', 'largest wins', or 'agreement' after '@'"
+                    message: "expected 'last wins', 'largest wins', 'smallest wins', 'first added wins', 'last added wins', 'highest priority wins', 'sticky', or 'agreement' after '@'"
                         .to_string(),
                     position: pos,
                 })
@@ -629,16 +909,54 @@ impl Parser {
         }
     }
 
+    fn parse_datetime_conflict(&mut self) -> Result<OnConflict, ParseError> {
+        if self.peek() == Some(&Token::At) {
+            self.advance();
+            if let Some(strategy) = self.parse_policy_wins()? {
+                return Ok(strategy);
+            }
+            match self.peek() {
+                Some(Token::Latest) => {
+                    self.advance();
+                    self.expect(Token::Wins)?;
+                    Ok(OnConflict::LargestValue)
+                }
+                Some(Token::Earliest) => {
+                    self.advance();
+                    self.expect(Token::Wins)?;
+                    Ok(OnConflict::SmallestValue)
+                }
+                Some(Token::Sticky) => {
+                    self.advance();
+                    Ok(OnConflict::Sticky)
+                }
+                Some(Token::Agreement) => {
+                    self.advance();
+                    Ok(OnConflict::Agreement)
+                }
+                _ => {
+                    let pos = self.current_position();
+                    Err(ParseError::Custom {
+                        message: "expected 'latest wins', 'earliest wins', 'first added wins', 'last added wins', 'highest priority wins', 'sticky', or 'agreement' after '@'"
+                            .to_string(),
+                        position: pos,
+                    })
+                }
+            }
+        } else {
+            Ok(OnConflict::Default)
+        }
+    }
+
     fn parse_field(&mut self) -> Result<Field, ParseError> {
-        let name = self.parse_identifier()?;
-        self.expect(Token::Colon)?;
+        let name = self.parse_field_name()?;
+        self.expect_colon()?;
 
         match self.peek() {
             Some(Token::Bool) => {
                 self.advance();
                 let on_conflict = self.parse_bool_conflict()?;
-                let default = if self.peek() == Some(&Token::Equals) {
-                    self.advance();
+                let default = if self.at_default() {
                     match self.advance() {
                         Some(Token::True) => Some(true),
                         Some(Token::False) => Some(false),
@@ -661,8 +979,7 @@ impl Parser {
             Some(Token::String) => {
                 self.advance();
                 let on_conflict = self.parse_string_conflict()?;
-                let default = if self.peek() == Some(&Token::Equals) {
-                    self.advance();
+                let default = if self.at_default() {
                     Some(self.parse_string_literal()?)
                 } else {
                     None
@@ -676,8 +993,7 @@ impl Parser {
             Some(Token::Number) => {
                 self.advance();
                 let on_conflict = self.parse_number_conflict()?;
-                let default = if self.peek() == Some(&Token::Equals) {
-                    self.advance();
+                let default = if self.at_default() {
                     Some(t64(self.parse_number_literal()?))
                 } else {
                     None
@@ -688,6 +1004,28 @@ impl Parser {
                     default,
                 })
             }
+            Some(Token::DateTime) => {
+                self.advance();
+                let on_conflict = self.parse_datetime_conflict()?;
+                let default = if self.at_default() {
+                    let position = self.current_position();
+                    let default = self.parse_string_literal()?;
+                    if crate::compare::rfc3339_instant(&default).is_none() {
+                        return Err(ParseError::Custom {
+                            message: format!("expected an RFC 3339 datetime, found {default:?}"),
+                            position,
+                        });
+                    }
+                    Some(default)
+                } else {
+                    None
+                };
+                Ok(Field::DateTime {
+                    name,
+                    on_conflict,
+                    default,
+                })
+            }
             Some(Token::LeftBracket) => {
                 self.advance();
                 if self.peek() == Some(&Token::String) {
@@ -695,16 +1033,27 @@ impl Parser {
                     self.expect(Token::RightBracket)?;
                     Ok(Field::StringArray { name })
                 } else {
-                    // String enum
-                    let mut values = vec![self.parse_string_literal()?];
-                    while self.peek() == Some(&Token::Comma) {
+                    // String enum, with its values listed or, for `[dynamic]`, supplied when
+                    // policies are applied.
+                    let (values, source) = if matches!(self.peek(), Some(Token::Identifier(d)) if d == "dynamic")
+                    {
                         self.advance();
-                        values.push(self.parse_string_literal()?);
-                    }
+                        (vec![], EnumSource::Dynamic)
+                    } else {
+                        let mut values = vec![self.parse_string_literal()?];
+                        while self.peek() == Some(&Token::Comma) {
+                            self.advance();
+                            if self.relaxed && self.peek() == Some(&Token::RightBracket) {
+                                self.warn("trailing ',' in enum values");
+                                break;
+                            }
+                            values.push(self.parse_string_literal()?);
+                        }
+                        (values, EnumSource::Static)
+                    };
                     self.expect(Token::RightBracket)?;
                     let on_conflict = self.parse_string_enum_conflict()?;
-                    let default = if self.peek() == Some(&Token::Equals) {
-                        self.advance();
+                    let default = if self.at_default() {
                         Some(self.parse_string_literal()?)
                     } else {
                         None
@@ -714,13 +1063,15 @@ impl Parser {
                         values,
                         on_conflict,
                         default,
+                        source,
                     })
                 }
             }
             _ => {
                 let pos = self.current_position();
                 Err(ParseError::Custom {
-                    message: "expected field type (bool, string, number, or [...)".to_string(),
+                    message: "expected field type (bool, string, number, datetime, or [...)"
+                        .to_string(),
                     position: pos,
                 })
             }
@@ -741,45 +1092,257 @@ impl Parser {
         self.expect(Token::LeftBrace)?;
 
         let mut fields = Vec::new();
+        let mut groups = Vec::new();
         let mut field_names = std::collections::HashSet::new();
 
-        // Parse fields
+        // Parse fields and groups
         while self.peek() != Some(&Token::RightBrace) && self.peek().is_some() {
-            let field = self.parse_field()?;
-
-            // Check for duplicate field names
-            let field_name = match &field {
-                Field::Bool { name, .. }
-                | Field::String { name, .. }
-                | Field::StringEnum { name, .. }
-                | Field::StringArray { name }
-                | Field::Number { name, .. } => name.clone(),
-            };
-
-            if !field_names.insert(field_name.clone()) {
-                return Err(ParseError::DuplicateFieldName {
-                    name: field_name,
-                    position: self.current_position(),
-                });
+            if self.at_group() {
+                let group = self.parse_group(&mut fields, &mut field_names)?;
+                groups.push(group);
+            } else {
+                let field = self.parse_field()?;
+                self.push_field(field, &mut fields, &mut field_names)?;
             }
+            self.field_separator()?;
+        }
 
-            fields.push(field);
+        self.expect(Token::RightBrace)?;
 
-            // Handle optional comma
-            if self.peek() == Some(&Token::Comma) {
-                self.advance();
-            } else if self.peek() != Some(&Token::RightBrace) {
+        for (group, position) in groups.iter() {
+            check_gate(&fields, &groups, group, position)?;
+        }
+        let groups = groups.into_iter().map(|(group, _)| group).collect();
+        Ok(PolicyType {
+            name,
+            fields,
+            groups,
+        })
+    }
+
+    /// `when` starts a group unless it is a field named `when`.
+    fn at_group(&self) -> bool {
+        matches!(self.peek(), Some(Token::Identifier(when)) if when == "when")
+            && self.tokens.get(self.position + 1).map(|(t, _)| t) != Some(&Token::Colon)
+    }
+
+    fn push_field(
+        &mut self,
+        field: Field,
+        fields: &mut Vec<Field>,
+        field_names: &mut std::collections::HashSet<String>,
+    ) -> Result<(), ParseError> {
+        // Check for duplicate field names
+        let field_name = field.name().to_string();
+        if !field_names.insert(field_name.clone()) {
+            return Err(ParseError::DuplicateFieldName {
+                name: field_name,
+                position: self.current_position(),
+            });
+        }
+        fields.push(field);
+        Ok(())
+    }
+
+    /// Parse `when gate { field, ... }`, adding the group's fields to `fields`.
+    fn parse_group(
+        &mut self,
+        fields: &mut Vec<Field>,
+        field_names: &mut std::collections::HashSet<String>,
+    ) -> Result<(FieldGroup, Position), ParseError> {
+        self.advance();
+        let position = self.current_position();
+        let when = self.parse_field_name()?;
+        self.expect(Token::LeftBrace)?;
+        let mut group = FieldGroup {
+            when,
+            fields: vec![],
+        };
+        while self.peek() != Some(&Token::RightBrace) && self.peek().is_some() {
+            if self.at_group() {
                 return Err(ParseError::Custom {
-                    message: "expected ',' or '}' after field definition".to_string(),
+                    message: "groups cannot be nested".to_string(),
                     position: self.current_position(),
                 });
             }
+            let field = self.parse_field()?;
+            group.fields.push(field.name().to_string());
+            self.push_field(field, fields, field_names)?;
+            self.field_separator()?;
         }
-
         self.expect(Token::RightBrace)?;
+        if group.fields.is_empty() {
+            return Err(ParseError::Custom {
+                message: format!("group for '{}' has no fields", group.when),
+                position,
+            });
+        }
+        Ok((group, position))
+    }
+}
+
+/// A gate must name a bool field that is not itself grouped.
+fn check_gate(
+    fields: &[Field],
+    groups: &[(FieldGroup, Position)],
+    group: &FieldGroup,
+    position: &Position,
+) -> Result<(), ParseError> {
+    let gate = &group.when;
+    let message = match fields.iter().find(|f| f.name() == gate) {
+        None => format!("group gate '{gate}' is not a field"),
+        Some(Field::Bool { .. }) => {
+            if groups.iter().any(|(g, _)| g.fields.contains(gate)) {
+                format!("group gate '{gate}' is itself in a group")
+            } else {
+                return Ok(());
+            }
+        }
+        Some(_) => format!("group gate '{gate}' must be a bool field"),
+    };
+    Err(ParseError::Custom {
+        message,
+        position: position.clone(),
+    })
+}
+
+/// The grammar of the policy type language, in ISO EBNF.
+pub const GRAMMAR: &str = r#"(* The policy type language.  Whitespace may separate any two tokens. *)
+type        = "type" , name , "{" , [ member , { "," , member } , [ "," ] ] , "}" ;
+name        = identifier , { "::" , identifier } ;
+member      = group | field ;
+(* "when" begins a group unless it is a field name followed by ":". *)
+group       = "when" , field name , "{" , field , { "," , field } , [ "," ] , "}" ;
+field       = field name , ":" , field type ;
+field name  = identifier | string ;
+field type  = bool type | string type | number type | datetime type | enum type | array type ;
+bool type   = "bool" , [ "@" , ( ( "largest" | "smallest" ) , "wins" | policy wins | "sticky" | "agreement" ) ] ,
+              [ "=" , ( "true" | "false" ) ] ;
+string type = "string" , [ "@" , ( ( "last" | "smallest" ) , "wins" | policy wins | "sticky" | "agreement" ) ] ,
+              [ "=" , string ] ;
+number type = "number" ,
+              [ "@" , ( ( "last" | "largest" | "smallest" ) , "wins" | policy wins | "sticky" | "agreement" ) ] ,
+              [ "=" , number ] ;
+(* A datetime default is an RFC 3339 timestamp with a UTC offset. *)
+datetime type = "datetime" ,
+              [ "@" , ( ( "latest" | "earliest" ) , "wins" | policy wins | "sticky" | "agreement" ) ] ,
+              [ "=" , string ] ;
+enum type   = "[" , ( string , { "," , string } | "dynamic" ) , "]" ,
+              [ "@" , ( ( "highest" | "smallest" ) , "wins" | policy wins | "sticky" | "agreement" ) ] ,
+              [ "=" , string ] ;
+array type  = "[" , "string" , "]" ;
+(* "first", "added", and "priority" are not keywords; fields may be named after them. *)
+policy wins = ( ( "first" | "last" ) , "added" | "highest" , "priority" ) , "wins" ;
+(* A field name that is a keyword must be written as a string. *)
+keyword     = "type" | "bool" | "string" | "number" | "datetime" | "true" | "false"
+            | "agreement" | "sticky" | "wins" | "last" | "highest" | "largest" | "smallest"
+            | "earliest" | "latest" ;
+identifier  = ( letter | "_" ) , { letter | digit | "_" } ;
+string      = '"' , { character - ( '"' | "\" ) | "\" , ( '"' | "\" ) } , '"' ;
+number      = [ "+" | "-" ] , ( digits , [ "." , [ digits ] ] | "." , digits ) ,
+              [ ( "e" | "E" ) , [ "+" | "-" ] , digits ] ;
+digits      = digit , { [ "_" ] , digit } ;
+"#;
+
+/// What a [`SyntaxToken`] is, for syntax highlighting.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    /// `type`, and `when` and `dynamic` where they begin a group or a dynamic enum
+    Keyword,
+    /// `bool`, `string`, `number`, and `datetime`
+    FieldType,
+    /// `true` and `false`
+    Boolean,
+    /// `agreement`, `sticky`, `wins`, `last`, `highest`, `largest`, `smallest`, `earliest`, and
+    /// `latest`, which name conflict strategies, and `first`, `added`, and `priority` inside one
+    Strategy,
+    /// A type or field name
+    Identifier,
+    /// A quoted string: an enum value, a default, or a quoted field name
+    StringLiteral,
+    /// A number
+    NumberLiteral,
+    /// Braces, brackets, `:`, `::`, `,`, `=`, and `@`
+    Punctuation,
+}
 
-        Ok(PolicyType { name, fields })
+/// One token of a policy type, with where it appears in the source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyntaxToken {
+    /// What the token is
+    pub kind: TokenKind,
+    /// The source text of the token, quotes and escapes included
+    pub text: String,
+    /// The one-based line the token starts on
+    pub line: usize,
+    /// The one-based column, in characters, the token starts at
+    pub column: usize,
+    /// The offset, in characters, of the token's start from the start of the input
+    pub offset: usize,
+}
+
+/// Split `input` into classified tokens.  Unlike [`parse`], this accepts any sequence of valid
+/// tokens, so that an editor can highlight a type while it is being written.
+pub fn tokens(input: &str) -> Result<Vec<SyntaxToken>, ParseError> {
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize_with_spans()?;
+    let mut syntax = Vec::with_capacity(tokens.len());
+    for (i, (token, position, span)) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1).map(|(t, _, _)| t);
+        let prev = i.checked_sub(1).map(|i| &tokens[i].0);
+        let kind = match token {
+            Token::Type => TokenKind::Keyword,
+            Token::Bool | Token::String | Token::Number | Token::DateTime => TokenKind::FieldType,
+            Token::True | Token::False => TokenKind::Boolean,
+            Token::Agreement
+            | Token::Sticky
+            | Token::Wins
+            | Token::Last
+            | Token::Highest
+            | Token::Largest
+            | Token::Smallest
+            | Token::Earliest
+            | Token::Latest => TokenKind::Strategy,
+            Token::Identifier(ident) if ident == "when" && next != Some(&Token::Colon) => {
+                TokenKind::Keyword
+            }
+            Token::Identifier(ident) if ident == "dynamic" && prev == Some(&Token::LeftBracket) => {
+                TokenKind::Keyword
+            }
+            Token::Identifier(ident)
+                if (ident == "first" && prev == Some(&Token::At))
+                    || (ident == "added"
+                        && (prev == Some(&Token::Last)
+                            || matches!(prev, Some(Token::Identifier(first)) if first == "first"))
+                        && next == Some(&Token::Wins))
+                    || (ident == "priority"
+                        && prev == Some(&Token::Highest)
+                        && next == Some(&Token::Wins)) =>
+            {
+                TokenKind::Strategy
+            }
+            Token::Identifier(_) => TokenKind::Identifier,
+            Token::StringLiteral(_) => TokenKind::StringLiteral,
+            Token::NumberLiteral(_) => TokenKind::NumberLiteral,
+            Token::LeftBrace
+            | Token::RightBrace
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::Colon
+            | Token::Comma
+            | Token::Equals
+            | Token::At
+            | Token::DoubleColon => TokenKind::Punctuation,
+        };
+        syntax.push(SyntaxToken {
+            kind,
+            text: lexer.input[span.clone()].iter().collect(),
+            line: position.line,
+            column: position.column,
+            offset: span.start,
+        });
     }
+    Ok(syntax)
 }
 
 pub fn parse(input: &str) -> Result<PolicyType, ParseError> {
@@ -789,6 +1352,14 @@ pub fn parse(input: &str) -> Result<PolicyType, ParseError> {
     parser.parse_policy_type()
 }
 
+pub fn parse_relaxed(input: &str) -> Result<(PolicyType, Vec<ParseWarning>), ParseError> {
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize()?;
+    let mut parser = Parser::new(tokens).relaxed();
+    let policy_type = parser.parse_policy_type()?;
+    Ok((policy_type, parser.warnings))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -828,6 +1399,114 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_dynamic_enum_field() {
+        let policy_type =
+            parse("type Test { assignee: [dynamic] @ agreement = \"nobody\" }").unwrap();
+        match &policy_type.fields[0] {
+            Field::StringEnum {
+                values,
+                default,
+                on_conflict,
+                source,
+                ..
+            } => {
+                assert!(values.is_empty());
+                assert_eq!(*default, Some("nobody".to_string()));
+                assert_eq!(*on_conflict, OnConflict::Agreement);
+                assert_eq!(*source, EnumSource::Dynamic);
+            }
+            _ => panic!("Expected enum field"),
+        }
+        assert_eq!(parse(&policy_type.to_string()).unwrap(), policy_type);
+    }
+
+    #[test]
+    fn test_parse_datetime_field() {
+        let policy_type = parse(
+            r#"type Email {
+                due: datetime @ latest wins,
+                received: datetime @ earliest wins = "2024-01-01T00:00:00+01:00",
+                seen: datetime,
+                score: number @ smallest wins,
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            policy_type.fields,
+            vec![
+                Field::DateTime {
+                    name: "due".to_string(),
+                    default: None,
+                    on_conflict: OnConflict::LargestValue,
+                },
+                Field::DateTime {
+                    name: "received".to_string(),
+                    default: Some("2024-01-01T00:00:00+01:00".to_string()),
+                    on_conflict: OnConflict::SmallestValue,
+                },
+                Field::DateTime {
+                    name: "seen".to_string(),
+                    default: None,
+                    on_conflict: OnConflict::Default,
+                },
+                Field::Number {
+                    name: "score".to_string(),
+                    default: None,
+                    on_conflict: OnConflict::SmallestValue,
+                },
+            ]
+        );
+        assert_eq!(parse(&policy_type.to_string()).unwrap(), policy_type);
+
+        // A default must name an instant, so it needs a UTC offset.
+        assert!(parse(r#"type T { due: datetime = "2024-01-01T00:00:00" }"#).is_err());
+        assert!(parse(r#"type T { due: datetime = "next tuesday" }"#).is_err());
+        assert!(parse("type T { due: datetime @ largest wins }").is_err());
+    }
+
+    #[test]
+    fn test_tokens_classify_every_token() {
+        let input =
+            "type T {\n  when \"has tag\" { n: number @ largest wins = 1_0 },\n  a: [dynamic],\n}";
+        let tokens = tokens(input).unwrap();
+        let text = tokens
+            .iter()
+            .map(|t| (t.kind, t.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            text,
+            vec![
+                (TokenKind::Keyword, "type"),
+                (TokenKind::Identifier, "T"),
+                (TokenKind::Punctuation, "{"),
+                (TokenKind::Keyword, "when"),
+                (TokenKind::StringLiteral, "\"has tag\""),
+                (TokenKind::Punctuation, "{"),
+                (TokenKind::Identifier, "n"),
+                (TokenKind::Punctuation, ":"),
+                (TokenKind::FieldType, "number"),
+                (TokenKind::Punctuation, "@"),
+                (TokenKind::Strategy, "largest"),
+                (TokenKind::Strategy, "wins"),
+                (TokenKind::Punctuation, "="),
+                (TokenKind::NumberLiteral, "1_0"),
+                (TokenKind::Punctuation, "}"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Identifier, "a"),
+                (TokenKind::Punctuation, ":"),
+                (TokenKind::Punctuation, "["),
+                (TokenKind::Keyword, "dynamic"),
+                (TokenKind::Punctuation, "]"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Punctuation, "}"),
+            ]
+        );
+        let a = &tokens[16];
+        assert_eq!((a.line, a.column), (3, 3));
+        assert_eq!(input.chars().nth(a.offset), Some('a'));
+    }
+
     #[test]
     fn test_parse_data_policy_file() {
         const POLICY_CONTENT: &str = include_str!("../data/policy");
@@ -841,4 +1520,211 @@ mod tests {
             }
         }
     }
+
+    fn lex_number(input: &str) -> Result<f64, ParseError> {
+        let tokens = Lexer::new(input).tokenize()?;
+        match &tokens[0].0 {
+            Token::NumberLiteral(n) => Ok(*n),
+            other => panic!("expected number, got {other}"),
+        }
+    }
+
+    #[test]
+    fn test_lexer_numbers() {
+        assert_eq!(lex_number("42").unwrap(), 42.0);
+        assert_eq!(lex_number("-1.5").unwrap(), -1.5);
+        assert_eq!(lex_number("+3").unwrap(), 3.0);
+        assert_eq!(lex_number("1e6").unwrap(), 1e6);
+        assert_eq!(lex_number("2.5E-3").unwrap(), 2.5e-3);
+        assert_eq!(lex_number("1_000_000").unwrap(), 1_000_000.0);
+        assert_eq!(lex_number("1_000.000_1e+1_0").unwrap(), 1_000.000_1e10);
+    }
+
+    #[test]
+    fn test_lexer_invalid_numbers() {
+        for (input, column) in [
+            ("1__0", 3),
+            ("10_", 4),
+            ("1._5", 3),
+            ("1e", 3),
+            ("1e+", 4),
+            ("-", 1),
+            ("1e999", 1),
+        ] {
+            match lex_number(input) {
+                Err(ParseError::InvalidNumber { position, .. }) => {
+                    assert_eq!(position.column, column, "{input}");
+                }
+                other => panic!("{input}: expected InvalidNumber, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_scientific_default() {
+        let policy_type = parse("type Test { threshold: number = 1e6 }").unwrap();
+        match &policy_type.fields[0] {
+            Field::Number { default, .. } => assert_eq!(*default, Some(t64(1e6))),
+            _ => panic!("Expected number field"),
+        }
+    }
+
+    #[test]
+    fn test_parse_unicode_and_quoted_field_names() {
+        let policy_type = parse(
+            r#"type Test {
+                größe: number,
+                "due date": string,
+                "last": bool = false,
+                "say \"hi\"": [string],
+            }"#,
+        )
+        .unwrap();
+        let names: Vec<_> = policy_type.fields.iter().map(|f| f.name()).collect();
+        assert_eq!(names, vec!["größe", "due date", "last", "say \"hi\""]);
+        // Display quotes exactly the names that need it, so the output parses back.
+        let rendered = policy_type.to_string();
+        assert!(rendered.contains("    größe: number,"));
+        assert!(rendered.contains("    \"due date\": string,"));
+        assert!(rendered.contains("    \"last\": bool = false,"));
+        assert_eq!(parse(&rendered).unwrap(), policy_type);
+    }
+
+    #[test]
+    fn test_parse_rejects_reserved_and_empty_field_names() {
+        for input in [
+            r#"type Test { "__rule_numbers__": bool }"#,
+            "type Test { __justification__: string }",
+            r#"type Test { "  ": string }"#,
+        ] {
+            match parse(input) {
+                Err(ParseError::InvalidIdentifier { .. }) => {}
+                other => panic!("{input}: expected InvalidIdentifier, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let policy_type = parse(
+            r#"type Email {
+                is_meeting_request: bool = false,
+                when is_meeting_request {
+                    meeting_time: string,
+                    meeting_place: string = "office",
+                },
+                when: bool,
+                "urgent": bool,
+            }"#,
+        )
+        .unwrap();
+        let names: Vec<_> = policy_type.fields.iter().map(|f| f.name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "is_meeting_request",
+                "meeting_time",
+                "meeting_place",
+                "when",
+                "urgent"
+            ]
+        );
+        assert_eq!(
+            policy_type.groups,
+            vec![FieldGroup {
+                when: "is_meeting_request".to_string(),
+                fields: vec!["meeting_time".to_string(), "meeting_place".to_string()],
+            }]
+        );
+        let rendered = policy_type.to_string();
+        assert!(rendered.contains("    when is_meeting_request {\n        meeting_time: string,\n"));
+        assert_eq!(parse(&rendered).unwrap(), policy_type);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_groups() {
+        for (input, message) in [
+            ("type T { when missing { a: bool } }", "is not a field"),
+            (
+                "type T { n: number, when n { a: bool } }",
+                "must be a bool field",
+            ),
+            (
+                "type T { g: bool, when g { h: bool }, when h { a: bool } }",
+                "is itself in a group",
+            ),
+            ("type T { g: bool, when g { } }", "has no fields"),
+            (
+                "type T { g: bool, when g { when g { a: bool } } }",
+                "cannot be nested",
+            ),
+        ] {
+            match parse(input) {
+                Err(ParseError::Custom { message: m, .. }) => {
+                    assert!(m.contains(message), "{input}: {m}")
+                }
+                other => panic!("{input}: expected Custom, got {other:?}"),
+            }
+        }
+        assert!(matches!(
+            parse("type T { g: bool, when g { g: bool } }"),
+            Err(ParseError::DuplicateFieldName { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_trailing_commas_and_whitespace() {
+        let expected =
+            parse(r#"type T { a: bool = true, b: ["x", "y"] = "x", c: number }"#).unwrap();
+        for input in [
+            r#"type T { a: bool = true, b: ["x", "y"] = "x", c: number, }"#,
+            "type T{a:bool=true,b:[\"x\",\"y\"]=\"x\",c:number}",
+            "type T {\n\ta : bool\t= true ,\r\n  b :[ \"x\" , \"y\" ]= \"x\",\n\n c\n:\nnumber\n,\n}",
+        ] {
+            assert_eq!(parse(input).unwrap(), expected, "{input}");
+            let (relaxed, warnings) = parse_relaxed(input).unwrap();
+            assert_eq!(relaxed, expected, "{input}");
+            assert!(warnings.is_empty(), "{input}: {warnings:?}");
+        }
+        for input in [
+            "type T { a: bool,, }",
+            "type T { , }",
+            r#"type T { b: ["x",] }"#,
+            "type T { a: bool b: bool }",
+            "type T { a = bool }",
+            "type T { a: bool: true }",
+        ] {
+            assert!(parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_relaxed_corrects_slips_with_warnings() {
+        let expected = parse(
+            r#"type T { a: bool = true, b: ["x", "y"] = "x", c: number = 1, when a { d: string } }"#,
+        )
+        .unwrap();
+        let (relaxed, warnings) = parse_relaxed(
+            "type T {\n  a = bool: true\n  b: [\"x\", \"y\",] = \"x\"\n  c: number = 1,\n  when a { d: string }\n}",
+        )
+        .unwrap();
+        assert_eq!(relaxed, expected);
+        let messages = warnings
+            .iter()
+            .map(|w| (w.position.line, w.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (2, "expected ':' after field name, found '='"),
+                (2, "expected '=' before default value, found ':'"),
+                (3, "missing ',' after field definition"),
+                (3, "trailing ',' in enum values"),
+                (4, "missing ',' after field definition"),
+            ]
+        );
+        // Relaxed parsing is not a license for anything.
+        assert!(parse_relaxed("type T { a: bool,, }").is_err());
+        assert!(parse_relaxed("type T { a: bool = maybe }").is_err());
+    }
 }
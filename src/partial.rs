@@ -0,0 +1,469 @@
+//! Incremental parsing of streamed tool input.
+//!
+//! When a request is streamed, the tool-use JSON arrives as a sequence of fragments that are
+//! not valid JSON until the last one.  [`PartialJson`] accumulates those fragments and recovers
+//! the values that are already complete, so a caller can act on `"priority": "high"` long
+//! before the array that follows it has finished.
+
+use claudius::{ContentBlock, ContentBlockDelta, Message, MessageStreamEvent};
+
+/// An accumulator for a JSON document that arrives in fragments.
+///
+/// [`PartialJson::value`] returns the document as if it ended after the last complete value.
+/// Strings, numbers, and literals are only included once they are complete, so a value never
+/// changes after it first appears.  Arrays and objects are included as soon as they open and
+/// grow as their elements complete.
+///
+/// # Example
+///
+/// ```
+/// use policyai::PartialJson;
+/// use serde_json::json;
+///
+/// let mut json = PartialJson::default();
+/// json.push(r#"{"priority": "hi"#);
+/// assert_eq!(json.value(), Some(json!({})));
+/// json.push(r#"gh", "labels": ["work", "ur"#);
+/// assert_eq!(json.value(), Some(json!({"priority": "high", "labels": ["work"]})));
+/// json.push(r#"gent"]}"#);
+/// assert!(json.is_complete());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PartialJson {
+    buffer: String,
+}
+
+impl PartialJson {
+    /// Append the next fragment of the document.
+    pub fn push(&mut self, fragment: &str) {
+        self.buffer.push_str(fragment);
+    }
+
+    /// Everything received so far.
+    pub fn as_str(&self) -> &str {
+        &self.buffer
+    }
+
+    /// True once the received text is a complete JSON document.
+    pub fn is_complete(&self) -> bool {
+        serde_json::from_str::<serde_json::Value>(&self.buffer).is_ok()
+    }
+
+    /// The complete prefix of the document, or `None` if not even its first value has started.
+    ///
+    /// The whole buffer is re-parsed on every call, which is cheap for tool inputs of a few
+    /// kilobytes.
+    pub fn value(&self) -> Option<serde_json::Value> {
+        let mut parser = PrefixParser {
+            bytes: self.buffer.as_bytes(),
+            offset: 0,
+        };
+        match parser.parse_value() {
+            Parsed::Complete(value) => Some(value),
+            Parsed::Partial(value) => value,
+        }
+    }
+}
+
+//////////////////////////////////////////// Parsed ////////////////////////////////////////////
+
+enum Parsed {
+    Complete(serde_json::Value),
+    Partial(Option<serde_json::Value>),
+}
+
+///////////////////////////////////////// PrefixParser /////////////////////////////////////////
+
+struct PrefixParser<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl PrefixParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.offset)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.offset += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.offset).copied()
+    }
+
+    fn parse_value(&mut self) -> Parsed {
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => self.parse_string(),
+            Some(_) => self.parse_scalar(),
+            None => Parsed::Partial(None),
+        }
+    }
+
+    fn parse_object(&mut self) -> Parsed {
+        self.offset += 1;
+        let mut object = serde_json::Map::new();
+        loop {
+            match self.peek() {
+                Some(b'}') => {
+                    self.offset += 1;
+                    return Parsed::Complete(object.into());
+                }
+                Some(b',') => {
+                    self.offset += 1;
+                    continue;
+                }
+                Some(b'"') => {}
+                _ => return Parsed::Partial(Some(object.into())),
+            }
+            let Parsed::Complete(serde_json::Value::String(key)) = self.parse_string() else {
+                return Parsed::Partial(Some(object.into()));
+            };
+            if self.peek() != Some(b':') {
+                return Parsed::Partial(Some(object.into()));
+            }
+            self.offset += 1;
+            match self.parse_value() {
+                Parsed::Complete(value) => {
+                    object.insert(key, value);
+                }
+                Parsed::Partial(value) => {
+                    if let Some(value) = value {
+                        object.insert(key, value);
+                    }
+                    return Parsed::Partial(Some(object.into()));
+                }
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Parsed {
+        self.offset += 1;
+        let mut array = vec![];
+        loop {
+            match self.peek() {
+                Some(b']') => {
+                    self.offset += 1;
+                    return Parsed::Complete(array.into());
+                }
+                Some(b',') => {
+                    self.offset += 1;
+                    continue;
+                }
+                None => return Parsed::Partial(Some(array.into())),
+                _ => {}
+            }
+            match self.parse_value() {
+                Parsed::Complete(value) => array.push(value),
+                Parsed::Partial(value) => {
+                    array.extend(value);
+                    return Parsed::Partial(Some(array.into()));
+                }
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Parsed {
+        let start = self.offset;
+        let mut offset = start + 1;
+        while let Some(b) = self.bytes.get(offset) {
+            match b {
+                b'\\' => offset += 2,
+                b'"' => {
+                    self.offset = offset + 1;
+                    return match serde_json::from_slice(&self.bytes[start..self.offset]) {
+                        Ok(value) => Parsed::Complete(value),
+                        Err(_) => Parsed::Partial(None),
+                    };
+                }
+                _ => offset += 1,
+            }
+        }
+        self.offset = self.bytes.len();
+        Parsed::Partial(None)
+    }
+
+    /// Numbers and literals.  A scalar that runs to the end of the buffer may still grow, so it
+    /// only counts once a delimiter follows it.
+    fn parse_scalar(&mut self) -> Parsed {
+        let start = self.offset;
+        while self
+            .bytes
+            .get(self.offset)
+            .is_some_and(|b| !matches!(b, b',' | b']' | b'}') && !b.is_ascii_whitespace())
+        {
+            self.offset += 1;
+        }
+        if self.offset == self.bytes.len() {
+            return Parsed::Partial(None);
+        }
+        match serde_json::from_slice(&self.bytes[start..self.offset]) {
+            Ok(value) => Parsed::Complete(value),
+            Err(_) => {
+                self.offset = self.bytes.len();
+                Parsed::Partial(None)
+            }
+        }
+    }
+}
+
+/////////////////////////////////////// StreamedMessage ////////////////////////////////////////
+
+/// Reassembles a [`Message`] from the events of a streamed response.
+#[derive(Debug, Default)]
+pub(crate) struct StreamedMessage {
+    message: Option<Message>,
+    inputs: Vec<Option<PartialJson>>,
+}
+
+impl StreamedMessage {
+    /// Fold `event` into the message.  Returns the partial input of the tool-use block that the
+    /// event extended, if any.
+    pub(crate) fn push(&mut self, event: MessageStreamEvent) -> Option<&PartialJson> {
+        match event {
+            MessageStreamEvent::MessageStart(start) => {
+                self.message = Some(start.message);
+                self.inputs.clear();
+            }
+            MessageStreamEvent::ContentBlockStart(start) => {
+                let message = self.message.as_mut()?;
+                let input = matches!(start.content_block, ContentBlock::ToolUse(_))
+                    .then(PartialJson::default);
+                if start.index >= message.content.len() {
+                    message.content.resize(start.index + 1, start.content_block);
+                    self.inputs.resize(start.index + 1, None);
+                } else {
+                    message.content[start.index] = start.content_block;
+                }
+                self.inputs[start.index] = input;
+            }
+            MessageStreamEvent::ContentBlockDelta(delta) => {
+                let message = self.message.as_mut()?;
+                match (delta.delta, message.content.get_mut(delta.index)) {
+                    (ContentBlockDelta::TextDelta(text), Some(ContentBlock::Text(block))) => {
+                        block.text.push_str(&text.text);
+                    }
+                    (ContentBlockDelta::InputJsonDelta(json), Some(ContentBlock::ToolUse(_))) => {
+                        let input = self.inputs.get_mut(delta.index)?.as_mut()?;
+                        input.push(&json.partial_json);
+                        return Some(input);
+                    }
+                    _ => {}
+                }
+            }
+            MessageStreamEvent::ContentBlockStop(stop) => {
+                let message = self.message.as_mut()?;
+                let input = self.inputs.get(stop.index)?.as_ref()?;
+                if let Some(ContentBlock::ToolUse(block)) = message.content.get_mut(stop.index) {
+                    if !input.as_str().trim().is_empty() {
+                        if let Ok(value) = serde_json::from_str(input.as_str()) {
+                            block.input = value;
+                        }
+                    }
+                }
+            }
+            MessageStreamEvent::MessageDelta(delta) => {
+                let message = self.message.as_mut()?;
+                message.stop_reason = delta.delta.stop_reason;
+                message.stop_sequence = delta.delta.stop_sequence;
+                message.usage.output_tokens = delta.usage.output_tokens;
+                if let Some(input_tokens) = delta.usage.input_tokens {
+                    message.usage.input_tokens = input_tokens;
+                }
+                if delta.usage.cache_creation_input_tokens.is_some() {
+                    message.usage.cache_creation_input_tokens =
+                        delta.usage.cache_creation_input_tokens;
+                }
+                if delta.usage.cache_read_input_tokens.is_some() {
+                    message.usage.cache_read_input_tokens = delta.usage.cache_read_input_tokens;
+                }
+            }
+            MessageStreamEvent::Ping | MessageStreamEvent::MessageStop(_) => {}
+        }
+        None
+    }
+
+    /// The assembled message, or an error if the stream never started one.
+    pub(crate) fn finish(self) -> Result<Message, claudius::Error> {
+        self.message
+            .ok_or_else(|| claudius::Error::streaming("stream ended without a message", None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claudius::{
+        ContentBlockDeltaEvent, ContentBlockStartEvent, ContentBlockStopEvent, InputJsonDelta,
+        KnownModel, MessageDelta, MessageDeltaEvent, MessageDeltaUsage, MessageStartEvent, Model,
+        StopReason, ToolUseBlock, Usage,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn prefixes(document: &str) -> Vec<Option<serde_json::Value>> {
+        (0..=document.len())
+            .filter(|idx| document.is_char_boundary(*idx))
+            .map(|idx| {
+                let mut json = PartialJson::default();
+                json.push(&document[..idx]);
+                json.value()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn empty_and_whitespace() {
+        assert_eq!(PartialJson::default().value(), None);
+        let mut json = PartialJson::default();
+        json.push("  ");
+        assert_eq!(json.value(), None);
+        json.push("{");
+        assert_eq!(json.value(), Some(json!({})));
+    }
+
+    #[test]
+    fn scalars_wait_for_a_delimiter() {
+        let mut json = PartialJson::default();
+        json.push(r#"{"count": 12"#);
+        assert_eq!(json.value(), Some(json!({})));
+        json.push("3");
+        assert_eq!(json.value(), Some(json!({})));
+        json.push(r#", "urgent": tru"#);
+        assert_eq!(json.value(), Some(json!({"count": 123})));
+        json.push("e}");
+        assert_eq!(json.value(), Some(json!({"count": 123, "urgent": true})));
+        assert!(json.is_complete());
+    }
+
+    #[test]
+    fn strings_wait_for_their_closing_quote() {
+        let mut json = PartialJson::default();
+        json.push(r#"{"a": "say \"hi"#);
+        assert_eq!(json.value(), Some(json!({})));
+        json.push(r#"\"", "b"#);
+        assert_eq!(json.value(), Some(json!({"a": "say \"hi\""})));
+        json.push(r#"": "é"}"#);
+        assert_eq!(json.value(), Some(json!({"a": "say \"hi\"", "b": "é"})));
+    }
+
+    #[test]
+    fn arrays_grow_element_by_element() {
+        let mut json = PartialJson::default();
+        json.push(r#"{"labels": ["#);
+        assert_eq!(json.value(), Some(json!({"labels": []})));
+        json.push(r#""a", "b"#);
+        assert_eq!(json.value(), Some(json!({"labels": ["a"]})));
+        json.push(r#"", [1, 2"#);
+        assert_eq!(json.value(), Some(json!({"labels": ["a", "b", [1]]})));
+        json.push("]]}");
+        assert_eq!(json.value(), Some(json!({"labels": ["a", "b", [1, 2]]})));
+    }
+
+    #[test]
+    fn values_never_change_once_seen() {
+        let document = r#"{"__rule_numbers__": [1, 3], "priority": "high", "count": -1.5e3, "ok": false, "labels": ["x", "y\n"], "none": null}"#;
+        let expected: serde_json::Value = serde_json::from_str(document).unwrap();
+        let mut last = serde_json::Map::new();
+        for value in prefixes(document).into_iter().flatten() {
+            let object = value.as_object().unwrap().clone();
+            for (key, value) in last.iter() {
+                let now = &object[key];
+                match (value, now) {
+                    (serde_json::Value::Array(before), serde_json::Value::Array(after)) => {
+                        assert!(after.starts_with(before), "{key}: {before:?} -> {after:?}");
+                    }
+                    _ => assert_eq!(value, now, "{key}"),
+                }
+            }
+            last = object;
+        }
+        assert_eq!(serde_json::Value::Object(last), expected);
+    }
+
+    #[test]
+    fn malformed_input_keeps_the_valid_prefix() {
+        let mut json = PartialJson::default();
+        json.push(r#"{"a": 1, "b": nope, "c": 2}"#);
+        assert_eq!(json.value(), Some(json!({"a": 1})));
+        assert!(!json.is_complete());
+    }
+
+    #[test]
+    fn streamed_message_reassembles_tool_use() {
+        let mut streamed = StreamedMessage::default();
+        assert!(streamed
+            .push(MessageStreamEvent::MessageStart(MessageStartEvent::new(
+                Message::new(
+                    "msg_1".to_string(),
+                    vec![],
+                    Model::Known(KnownModel::ClaudeSonnet40),
+                    Usage::new(10, 1),
+                )
+            )))
+            .is_none());
+        streamed.push(MessageStreamEvent::ContentBlockStart(
+            ContentBlockStartEvent::new(
+                ContentBlock::ToolUse(ToolUseBlock::new("toolu_1", "output_json", json!({}))),
+                0,
+            ),
+        ));
+        let mut seen = vec![];
+        for fragment in [r#"{"prio"#, r#"rity": "high", "#, r#""labels": ["a"]}"#] {
+            let partial = streamed
+                .push(MessageStreamEvent::ContentBlockDelta(
+                    ContentBlockDeltaEvent::new(
+                        ContentBlockDelta::InputJsonDelta(InputJsonDelta::new(
+                            fragment.to_string(),
+                        )),
+                        0,
+                    ),
+                ))
+                .unwrap();
+            seen.push(partial.value().unwrap());
+        }
+        assert_eq!(
+            seen,
+            vec![
+                json!({}),
+                json!({"priority": "high"}),
+                json!({"priority": "high", "labels": ["a"]}),
+            ]
+        );
+        streamed.push(MessageStreamEvent::ContentBlockStop(
+            ContentBlockStopEvent { index: 0 },
+        ));
+        streamed.push(MessageStreamEvent::MessageDelta(MessageDeltaEvent::new(
+            MessageDelta {
+                stop_reason: Some(StopReason::ToolUse),
+                stop_sequence: None,
+            },
+            MessageDeltaUsage {
+                cache_creation_input_tokens: None,
+                cache_read_input_tokens: None,
+                input_tokens: None,
+                output_tokens: 42,
+                server_tool_use: None,
+            },
+        )));
+        let message = streamed.finish().unwrap();
+        assert_eq!(message.id, "msg_1");
+        assert_eq!(message.usage.input_tokens, 10);
+        assert_eq!(message.usage.output_tokens, 42);
+        assert_eq!(message.stop_reason, Some(StopReason::ToolUse));
+        let [ContentBlock::ToolUse(block)] = message.content.as_slice() else {
+            panic!("expected one tool use block: {:?}", message.content);
+        };
+        assert_eq!(block.input, json!({"priority": "high", "labels": ["a"]}));
+    }
+
+    #[test]
+    fn streamed_message_requires_a_start() {
+        assert!(StreamedMessage::default().finish().is_err());
+    }
+}
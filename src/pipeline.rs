@@ -0,0 +1,445 @@
+//! Bounded, rate-limited bulk application of a [`Manager`] to many texts.
+//!
+//! A [`Pipeline`] pulls texts from an iterator only as fast as there is room to process them:  at
+//! most `max_in_flight` texts are being applied (or waiting for the consumer to take their result)
+//! at any moment, request starts are spaced by an optional minimum interval, and retryable
+//! failures are retried with exponential backoff.  Results arrive on a [`PipelineStream`] in
+//! completion order; each carries the index of the text that produced it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use policyai::pipeline::Pipeline;
+//! use policyai::Manager;
+//! # use claudius::{Anthropic, MessageCreateParams};
+//! # let client = Anthropic::new(None)?;
+//! # let manager = Manager::default();
+//! # let template = MessageCreateParams::default();
+//!
+//! let texts = vec!["first email".to_string(), "second email".to_string()];
+//! let mut stream = Pipeline::new(client, manager, template)
+//!     .with_max_in_flight(8)
+//!     .run(texts);
+//! while let Some(output) = stream.next().await {
+//!     match output.result {
+//!         Ok(report) => println!("{}: {}", output.index, report.value()),
+//!         Err(err) => eprintln!("{}: {err}", output.index),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use claudius::MessageCreateParams;
+use tokio::sync::{mpsc, watch, Mutex, Semaphore};
+use tokio::time::Instant;
+
+use crate::{ApplyError, LlmProvider, Manager, Report, Usage};
+
+/////////////////////////////////////////// RetryPolicy ///////////////////////////////////////////
+
+/// How a pipeline retries a text whose application failed with a retryable error.
+///
+/// [`ApplyError::TooManyIterations`] is not retried:  the manager has already spent its own
+/// attempts correcting the LLM, and retrying here would multiply them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt.  Zero disables retries.
+    pub max_retries: usize,
+    /// Delay before the first retry; each subsequent retry doubles it.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// The delay to wait before retry number `retry` (zero-based).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use policyai::pipeline::RetryPolicy;
+    /// let policy = RetryPolicy::default();
+    /// assert_eq!(policy.backoff(0), Duration::from_secs(1));
+    /// assert_eq!(policy.backoff(1), Duration::from_secs(2));
+    /// ```
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let factor = 1u32.checked_shl(retry as u32).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+////////////////////////////////////////// PipelineOutput //////////////////////////////////////////
+
+/// The outcome of applying the pipeline's policies to one text.
+#[derive(Debug)]
+pub struct PipelineOutput {
+    /// Position of the text in the input iterator.
+    pub index: usize,
+    /// Number of times the text was submitted, including the first attempt.
+    pub attempts: usize,
+    /// Usage accumulated across every attempt.
+    pub usage: Usage,
+    /// The report, or the error from the final attempt.
+    pub result: Result<Report, ApplyError>,
+}
+
+////////////////////////////////////////// ShutdownHandle //////////////////////////////////////////
+
+/// A cloneable handle that asks a running pipeline to stop.
+///
+/// After shutdown no new texts are started and no further retries are attempted.  Texts already
+/// in flight run to completion and their outputs are still delivered.
+#[derive(Clone, Debug)]
+pub struct ShutdownHandle {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    /// Request a graceful shutdown.
+    pub fn shutdown(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// True if shutdown has been requested.
+    pub fn is_shutdown(&self) -> bool {
+        *self.tx.borrow()
+    }
+}
+
+////////////////////////////////////////// PipelineStream //////////////////////////////////////////
+
+/// The receiving end of a running pipeline.
+#[derive(Debug)]
+pub struct PipelineStream {
+    rx: mpsc::Receiver<PipelineOutput>,
+    shutdown: ShutdownHandle,
+}
+
+impl PipelineStream {
+    /// Wait for the next output.  Returns `None` once every started text has been delivered.
+    pub async fn next(&mut self) -> Option<PipelineOutput> {
+        self.rx.recv().await
+    }
+
+    /// Request a graceful shutdown.  See [`ShutdownHandle`].
+    pub fn shutdown(&self) {
+        self.shutdown.shutdown();
+    }
+
+    /// A handle that can request shutdown from elsewhere, e.g. a signal handler.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+}
+
+///////////////////////////////////////////// Pipeline /////////////////////////////////////////////
+
+/// Applies one [`Manager`]'s policies to a stream of texts with bounded concurrency.
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    client: Arc<dyn LlmProvider>,
+    manager: Manager,
+    template: MessageCreateParams,
+    max_in_flight: usize,
+    min_interval: Duration,
+    retry: RetryPolicy,
+}
+
+impl Pipeline {
+    /// Create a pipeline that applies `manager` using `client` and `template`.
+    ///
+    /// Defaults to four texts in flight, no rate limit, and [`RetryPolicy::default`].
+    pub fn new(
+        client: impl LlmProvider + 'static,
+        manager: Manager,
+        template: MessageCreateParams,
+    ) -> Self {
+        Self {
+            client: Arc::new(client),
+            manager,
+            template,
+            max_in_flight: 4,
+            min_interval: Duration::ZERO,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Bound the number of texts being processed or awaiting consumption.  Clamped to at least 1.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Space the start of consecutive requests (including retries) by at least `min_interval`.
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Limit request starts to `requests_per_minute`.  Zero removes the limit.
+    pub fn with_requests_per_minute(self, requests_per_minute: u32) -> Self {
+        if requests_per_minute == 0 {
+            self.with_min_interval(Duration::ZERO)
+        } else {
+            self.with_min_interval(Duration::from_secs(60) / requests_per_minute)
+        }
+    }
+
+    /// Set the per-text retry policy.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Start processing `texts` on the current tokio runtime.
+    ///
+    /// The iterator is consumed lazily; it is only advanced when a slot frees up.
+    pub fn run<I>(self, texts: I) -> PipelineStream
+    where
+        I: IntoIterator<Item = String>,
+        I::IntoIter: Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(self.max_in_flight);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let shutdown = ShutdownHandle {
+            tx: Arc::new(shutdown_tx),
+        };
+        let texts = texts.into_iter();
+        tokio::spawn(self.drive(texts, tx, shutdown_rx));
+        PipelineStream { rx, shutdown }
+    }
+
+    async fn drive<I>(
+        self,
+        texts: I,
+        tx: mpsc::Sender<PipelineOutput>,
+        mut shutdown: watch::Receiver<bool>,
+    ) where
+        I: Iterator<Item = String>,
+    {
+        let semaphore = Arc::new(Semaphore::new(self.max_in_flight));
+        let limiter = Arc::new(RateLimiter::new(self.min_interval));
+        let this = Arc::new(self);
+        for (index, text) in texts.enumerate() {
+            if *shutdown.borrow() {
+                break;
+            }
+            let permit = tokio::select! {
+                permit = semaphore.clone().acquire_owned() => permit,
+                _ = shutdown.wait_for(|stop| *stop) => break,
+            };
+            let Ok(permit) = permit else {
+                break;
+            };
+            let this = Arc::clone(&this);
+            let limiter = Arc::clone(&limiter);
+            let tx = tx.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                let output = this.process(index, &text, &limiter, &shutdown).await;
+                // The receiver going away is how a consumer abandons the stream; nothing to do.
+                let _ = tx.send(output).await;
+                drop(permit);
+            });
+        }
+    }
+
+    async fn process(
+        &self,
+        index: usize,
+        text: &str,
+        limiter: &RateLimiter,
+        shutdown: &watch::Receiver<bool>,
+    ) -> PipelineOutput {
+        let mut manager = self.manager.clone();
+        let mut total = Usage::new();
+        let mut attempts = 0;
+        loop {
+            limiter.wait().await;
+            attempts += 1;
+            let mut usage = Usage::new();
+            let result = manager
+                .apply(
+                    self.client.as_ref(),
+                    self.template.clone(),
+                    text,
+                    Some(&mut usage),
+                )
+                .await;
+            total.merge(&usage);
+            let retry = attempts - 1;
+            let retryable = match &result {
+                Ok(_) | Err(ApplyError::TooManyIterations { .. }) => false,
+                Err(err) => err.is_retryable(),
+            };
+            if !retryable || retry >= self.retry.max_retries || *shutdown.borrow() {
+                return PipelineOutput {
+                    index,
+                    attempts,
+                    usage: total,
+                    result,
+                };
+            }
+            // All that is left to retry is a failed transport or a timeout.
+            total.increment_transport_retries();
+            tokio::time::sleep(self.retry.backoff(retry)).await;
+        }
+    }
+}
+
+/////////////////////////////////////////// RateLimiter ///////////////////////////////////////////
+
+struct RateLimiter {
+    min_interval: Duration,
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            next_start: Mutex::new(None),
+        }
+    }
+
+    async fn wait(&self) {
+        if self.min_interval.is_zero() {
+            return;
+        }
+        let mut next_start = self.next_start.lock().await;
+        let now = Instant::now();
+        let start = match *next_start {
+            Some(when) if when > now => when,
+            _ => now,
+        };
+        *next_start = Some(start + self.min_interval);
+        drop(next_start);
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClient;
+
+    fn pipeline() -> Pipeline {
+        Pipeline::new(
+            MockClient::new(),
+            Manager::default(),
+            MessageCreateParams::default(),
+        )
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let retry = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(retry.backoff(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff(1), Duration::from_millis(200));
+        assert_eq!(retry.backoff(2), Duration::from_millis(400));
+        assert_eq!(retry.backoff(3), Duration::from_millis(500));
+        assert_eq!(retry.backoff(64), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn builder_clamps_and_converts() {
+        let p = pipeline()
+            .with_max_in_flight(0)
+            .with_requests_per_minute(120);
+        assert_eq!(p.max_in_flight, 1);
+        assert_eq!(p.min_interval, Duration::from_millis(500));
+        let p = p.with_requests_per_minute(0);
+        assert_eq!(p.min_interval, Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn empty_input_closes_stream() {
+        let mut stream = pipeline().run(Vec::<String>::new());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn shutdown_before_start_processes_nothing() {
+        let p = pipeline();
+        let (tx, mut rx) = mpsc::channel(1);
+        let (shutdown_tx, shutdown_rx) = watch::channel(true);
+        p.drive(vec!["text".to_string()].into_iter(), tx, shutdown_rx)
+            .await;
+        drop(shutdown_tx);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn retries_transport_failures_but_not_exhausted_corrections() {
+        let retry = RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        };
+        let client = MockClient::new()
+            .with_error(claudius::Error::rate_limit("slow down", None))
+            .with_reply(serde_json::json!({"__rule_numbers__": []}));
+        let mut stream = Pipeline::new(client, Manager::default(), MessageCreateParams::default())
+            .with_retry_policy(retry)
+            .run(vec!["text".to_string()]);
+        let output = stream.next().await.unwrap();
+        assert!(output.result.is_ok());
+        assert_eq!(output.attempts, 2);
+        assert_eq!(output.usage.transport_retries, 1);
+        assert_eq!(output.usage.inconsistency_retries, 0);
+
+        // Rule 1 does not exist, so every answer is corrected until the manager gives up.
+        let client = MockClient::replaying(serde_json::json!({"__rule_numbers__": [1]}));
+        let mut stream = Pipeline::new(client, Manager::default(), MessageCreateParams::default())
+            .with_retry_policy(retry)
+            .run(vec!["text".to_string()]);
+        let output = stream.next().await.unwrap();
+        assert!(matches!(
+            output.result,
+            Err(ApplyError::TooManyIterations { .. })
+        ));
+        assert_eq!(output.attempts, 1);
+        assert_eq!(output.usage.transport_retries, 0);
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_spaces_starts() {
+        let limiter = RateLimiter::new(Duration::from_millis(20));
+        let start = Instant::now();
+        limiter.wait().await;
+        limiter.wait().await;
+        limiter.wait().await;
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+}
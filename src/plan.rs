@@ -0,0 +1,139 @@
+//! Estimates of what a request costs before it is sent.
+//!
+//! A request is the system prompt, the default output, one `<rule>` per policy, the output tool
+//! and its schema, the text, and a closing reminder.  The rules grow with the policy set and are
+//! usually most of the prompt, but which rules is not obvious from the policies themselves.
+//! [`crate::Manager::plan`] assembles a request without sending it and estimates the tokens of
+//! each of those components, so that the policies worth compressing can be found by reading a
+//! table instead of by bisecting the policy set.
+
+use claudius::MessageCreateParams;
+
+use crate::RuleIndex;
+
+/// A request assembled but not sent, with an estimate of its input tokens.
+///
+/// Created by [`crate::Manager::plan`].
+#[derive(Clone, Debug)]
+pub struct PlannedRequest {
+    /// The request [`crate::Manager::apply`] would send first.
+    pub request: MessageCreateParams,
+    /// The estimated input tokens of the request, by component.
+    pub tokens: PromptTokens,
+}
+
+/// Estimated input tokens of one request, by component.
+///
+/// Estimates are the size in bytes divided by [`PromptTokens::BYTES_PER_TOKEN`].  They are good
+/// for comparing components with one another, not for predicting a bill; the usage the API
+/// reports is authoritative.
+///
+/// # Example
+///
+/// ```
+/// use policyai::PromptTokens;
+///
+/// let tokens = PromptTokens {
+///     system: 100,
+///     text: 20,
+///     ..PromptTokens::default()
+/// };
+/// assert_eq!(tokens.total(), 120);
+/// assert_eq!(PromptTokens::estimate("four"), 1);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PromptTokens {
+    /// The system prompt.
+    pub system: u64,
+    /// The message quoting the default output.
+    pub defaults: u64,
+    /// Each rule sent to the LLM, in rule order.  Rules decided locally are not sent and are not
+    /// listed.
+    pub rules: Vec<RuleTokens>,
+    /// The output tool's name, description, and the part of its schema no rule added.
+    pub tool: u64,
+    /// The text and its metadata.
+    pub text: u64,
+    /// The reminder that closes the request.
+    pub reminder: u64,
+}
+
+impl PromptTokens {
+    /// The bytes per token assumed by [`PromptTokens::estimate`].
+    pub const BYTES_PER_TOKEN: u64 = 4;
+
+    /// The estimated tokens of `text`, rounded up.
+    pub fn estimate(text: &str) -> u64 {
+        (text.len() as u64).div_ceil(Self::BYTES_PER_TOKEN)
+    }
+
+    /// The estimated tokens of the rules, prompts and schema both.
+    pub fn rules_total(&self) -> u64 {
+        self.rules.iter().map(RuleTokens::total).sum()
+    }
+
+    /// The estimated tokens of the whole request.
+    pub fn total(&self) -> u64 {
+        self.system + self.defaults + self.rules_total() + self.tool + self.text + self.reminder
+    }
+
+    /// The rules, most expensive first.  Rules of equal cost stay in rule order.
+    pub fn rules_by_cost(&self) -> Vec<&RuleTokens> {
+        let mut rules = self.rules.iter().collect::<Vec<_>>();
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.total()));
+        rules
+    }
+}
+
+impl std::fmt::Display for PromptTokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>8} {:>8} {:>8}",
+            "component", "tokens", "prompt", "schema"
+        )?;
+        for (name, tokens) in [("system", self.system), ("defaults", self.defaults)] {
+            writeln!(f, "{name:<24} {tokens:>8}")?;
+        }
+        for rule in self.rules.iter() {
+            writeln!(
+                f,
+                "{:<24} {:>8} {:>8} {:>8}",
+                format!("rule {} (policy {})", rule.rule, rule.policy),
+                rule.total(),
+                rule.prompt,
+                rule.schema,
+            )?;
+        }
+        for (name, tokens) in [
+            ("tool", self.tool),
+            ("text", self.text),
+            ("reminder", self.reminder),
+            ("total", self.total()),
+        ] {
+            writeln!(f, "{name:<24} {tokens:>8}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Estimated input tokens of one rule.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RuleTokens {
+    /// The rule's number in the request.
+    pub rule: RuleIndex,
+    /// The position of the policy the rule came from, in the order the manager's policies were
+    /// added.
+    pub policy: usize,
+    /// The `<rule>` text.
+    pub prompt: u64,
+    /// The properties the rule adds to the output tool's schema.
+    pub schema: u64,
+}
+
+impl RuleTokens {
+    /// The estimated tokens of the rule, prompt and schema both.
+    pub fn total(&self) -> u64 {
+        self.prompt + self.schema
+    }
+}
@@ -1,4 +1,11 @@
-use crate::PolicyType;
+#[cfg(feature = "client")]
+use claudius::{ContentBlock, KnownModel, MessageCreateParams, MessageParam, MessageRole, Model};
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "client")]
+use crate::LlmProvider;
+use crate::{number_is_equal, PolicyError, PolicyType, Precondition};
 
 /// Represents a policy with its type definition, prompt, and resulting action.
 ///
@@ -12,4 +19,383 @@ pub struct Policy {
     pub prompt: String,
     /// The structured action data that conforms to the policy type schema
     pub action: serde_json::Value,
+    /// An optional local check on the input text; when it fails the policy is left out of the
+    /// request entirely
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precondition: Option<Precondition>,
+    /// An optional local test that decides the policy without the LLM: the policy matches
+    /// exactly when the test holds on the input text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact_match: Option<Precondition>,
+    /// A cached human-readable summary of this policy, filled in by [`Policy::explain`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    /// The policy's weight under [`crate::OnConflict::HighestPriority`]; `None` counts as 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+}
+
+/// A field on which a policy's stored action disagrees with the action derived from its prompt.
+///
+/// See [`Policy::revalidate`].
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ActionDivergence {
+    /// The field whose value differs
+    pub field: String,
+    /// The value in the stored action, if it sets the field
+    pub stored: Option<serde_json::Value>,
+    /// The value in the derived action, if it sets the field
+    pub derived: Option<serde_json::Value>,
+}
+
+/// A policy inferred from labeled examples by [`Policy::from_examples`].
+#[cfg(feature = "data")]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PolicyCandidate {
+    /// The inferred policy.
+    pub policy: Policy,
+    /// The fraction of the examples the policy reproduces, from 0 to 1.
+    pub confidence: f64,
+    /// The zero-based positions of the examples the policy does not reproduce.
+    pub misses: Vec<usize>,
+}
+
+impl Policy {
+    /// The names of the `{{placeholders}}` in this policy's prompt, in order of first appearance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PolicyError::MalformedPlaceholder`] if the prompt contains a `{{` that does not
+    /// open a valid placeholder.
+    #[allow(clippy::result_large_err)]
+    pub fn variables(&self) -> Result<Vec<String>, PolicyError> {
+        crate::template::variables(&self.prompt)
+    }
+
+    /// This policy with every `{{placeholder}}` in its prompt replaced by its value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PolicyError::MissingVariable`] if a placeholder has no value and
+    /// [`PolicyError::MalformedPlaceholder`] if the prompt is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use policyai::{Policy, PolicyType};
+    /// let policy = Policy {
+    ///     r#type: PolicyType::parse("type T { urgent: bool = false }").unwrap(),
+    ///     prompt: "If the email is from {{customer_name}}".to_string(),
+    ///     action: serde_json::json!({"urgent": true}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// let variables = BTreeMap::from([("customer_name".to_string(), "Acme".to_string())]);
+    /// assert_eq!(policy.with_variables(&variables).unwrap().prompt, "If the email is from Acme");
+    /// assert!(policy.with_variables(&BTreeMap::new()).is_err());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn with_variables(
+        &self,
+        variables: &BTreeMap<String, String>,
+    ) -> Result<Policy, PolicyError> {
+        let mut policy = self.clone();
+        policy.prompt = crate::template::render(&self.prompt, variables)?;
+        Ok(policy)
+    }
+
+    /// This policy with the values of its type's dynamic enums supplied from `values`.  See
+    /// [`PolicyType::with_enum_values`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PolicyError::MissingEnumValues`] if a dynamic enum has no entry in `values`.
+    #[allow(clippy::result_large_err)]
+    pub fn with_enum_values(
+        &self,
+        values: &BTreeMap<String, Vec<String>>,
+    ) -> Result<Policy, PolicyError> {
+        let mut policy = self.clone();
+        policy.r#type = self.r#type.with_enum_values(values)?;
+        Ok(policy)
+    }
+
+    /// This policy cut down to `fields`: its type as [`PolicyType::with_only_fields`] cuts it,
+    /// and its action without the fields that are gone.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PolicyError::UnknownField`] if the type does not declare one of `fields`.
+    #[allow(clippy::result_large_err)]
+    pub fn with_only_fields(
+        &self,
+        fields: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<Policy, PolicyError> {
+        let mut policy = self.clone();
+        policy.r#type = self.r#type.with_only_fields(fields)?;
+        if let serde_json::Value::Object(action) = &mut policy.action {
+            action.retain(|name, _| policy.r#type.fields.iter().any(|f| f.name() == name));
+        }
+        Ok(policy)
+    }
+
+    /// Produce a one-paragraph, human-readable summary of what this policy does.
+    ///
+    /// The summary covers the condition under which the policy applies and every field its
+    /// action sets.  The first call asks the LLM and caches the result in `explanation`, which is
+    /// serialized with the policy; later calls return the cached text.  Set `explanation` to
+    /// `None` after editing the prompt or action to force a fresh summary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response contains no text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use claudius::Anthropic;
+    /// # use policyai::{Policy, PolicyType};
+    /// # let client = Anthropic::new(None)?;
+    /// let mut policy = Policy {
+    ///     r#type: PolicyType::parse("type T { urgent: bool = false }")?,
+    ///     prompt: "If the email is from the CEO".to_string(),
+    ///     action: serde_json::json!({"urgent": true}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// println!("{}", policy.explain(&client).await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "client")]
+    pub async fn explain(&mut self, client: &dyn LlmProvider) -> Result<&str, claudius::Error> {
+        if self.explanation.is_none() {
+            let req = MessageCreateParams {
+                max_tokens: 1024,
+                model: Model::Known(KnownModel::ClaudeSonnet40),
+                messages: vec![MessageParam::new_with_string(
+                    format!(
+                        "<condition>{}</condition>\n<action>{}</action>",
+                        self.prompt, self.action
+                    ),
+                    MessageRole::User,
+                )],
+                system: Some(include_str!("../prompts/explain-policy.md").into()),
+                ..Default::default()
+            };
+            let resp = client.send(req).await?;
+            let text = resp
+                .content
+                .iter()
+                .filter_map(|c| match c {
+                    ContentBlock::Text(t) => Some(t.text.as_str()),
+                    _ => None,
+                })
+                .collect::<String>();
+            let text = text.trim();
+            if text.is_empty() {
+                return Err(claudius::Error::validation(
+                    "explanation response contained no text",
+                    None,
+                ));
+            }
+            self.explanation = Some(text.to_string());
+        }
+        Ok(self.explanation.as_deref().unwrap_or_default())
+    }
+
+    /// Infer a policy of type `r#type` from labeled examples.
+    ///
+    /// Each example is a text and the output expected for it: the fields the policy sets when it
+    /// applies to the text, or `{}` (or `null`) when it does not.  The LLM writes a semantic
+    /// injection and an action that account for the examples; fields the type does not declare
+    /// are dropped from the action.  The candidate is then checked against every example with
+    /// [`crate::data::policy_applies`], two votes out of three, and an applying example is only
+    /// reproduced if the action agrees with every field it expects.  The candidate is returned
+    /// however well it does; judge it by `confidence` and `misses`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no examples, an expected output is neither an object nor
+    /// null, a request fails, or the response is not a rule.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use claudius::Anthropic;
+    /// # use policyai::{Policy, PolicyType};
+    /// # let client = Anthropic::new(None)?;
+    /// let policy_type = PolicyType::parse("type T { urgent: bool = false }")?;
+    /// let examples = [
+    ///     ("The build is broken and customers are down.", serde_json::json!({"urgent": true})),
+    ///     ("Production database unreachable!", serde_json::json!({"urgent": true})),
+    ///     ("Lunch on Friday?", serde_json::json!({})),
+    /// ];
+    /// let candidate = Policy::from_examples(&client, &policy_type, &examples).await?;
+    /// println!("{} ({:.0}%)", candidate.policy.prompt, candidate.confidence * 100.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "data")]
+    pub async fn from_examples(
+        client: &dyn LlmProvider,
+        r#type: &PolicyType,
+        examples: &[(&str, serde_json::Value)],
+    ) -> Result<PolicyCandidate, claudius::Error> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            injection: String,
+            #[serde(default)]
+            action: serde_json::Map<String, serde_json::Value>,
+        }
+        if examples.is_empty() {
+            return Err(claudius::Error::validation(
+                "at least one example is needed",
+                None,
+            ));
+        }
+        let mut content = format!("<type>{}</type>\n", r#type);
+        for (text, expected) in examples.iter() {
+            if !expected.is_object() && !expected.is_null() {
+                return Err(claudius::Error::validation(
+                    format!("expected output {expected} is not a JSON object"),
+                    None,
+                ));
+            }
+            let expected = if expected.is_null() {
+                serde_json::json!({})
+            } else {
+                expected.clone()
+            };
+            content +=
+                &format!("<example><text>{text}</text><output>{expected}</output></example>\n");
+        }
+        let req = MessageCreateParams {
+            max_tokens: 2048,
+            model: Model::Known(KnownModel::ClaudeSonnet40),
+            messages: vec![MessageParam::new_with_string(content, MessageRole::User)],
+            system: Some(include_str!("../prompts/policy-from-examples.md").into()),
+            ..Default::default()
+        };
+        let resp = client.send(req).await?;
+        let raw_response = resp
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                ContentBlock::Text(t) => Some(t.text.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        let json_content = match (raw_response.find('{'), raw_response.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &raw_response[start..=end],
+            _ => raw_response.trim(),
+        };
+        let mut response: Response = serde_json::from_str(json_content)?;
+        response
+            .action
+            .retain(|name, _| r#type.fields.iter().any(|field| field.name() == name));
+        let policy = Policy {
+            r#type: r#type.clone(),
+            prompt: response.injection,
+            action: response.action.into(),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        };
+        let mut misses = vec![];
+        for (index, (text, expected)) in examples.iter().enumerate() {
+            let expects_match = expected.as_object().is_some_and(|obj| !obj.is_empty());
+            let applies = crate::data::policy_applies(client, text, &policy.prompt, 2, 3).await?;
+            let agrees = !expects_match
+                || policy
+                    .divergences(expected)
+                    .iter()
+                    .all(|divergence| divergence.derived.is_none());
+            if applies != expects_match || !agrees {
+                misses.push(index);
+            }
+        }
+        let confidence = (examples.len() - misses.len()) as f64 / examples.len() as f64;
+        Ok(PolicyCandidate {
+            policy,
+            confidence,
+            misses,
+        })
+    }
+
+    /// Derive this policy's action from its prompt again with `model` and report every field
+    /// on which the result differs from the stored action.
+    ///
+    /// An empty result means the model still reads the prompt the way it was read when the
+    /// policy was saved.  Run this over stored policies before switching models.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a JSON action.
+    #[cfg(feature = "client")]
+    pub async fn revalidate(
+        &self,
+        client: &dyn LlmProvider,
+        model: Model,
+    ) -> Result<Vec<ActionDivergence>, claudius::Error> {
+        let derived = self
+            .r#type
+            .with_semantic_injection_using(client, model, &self.prompt)
+            .await?;
+        Ok(self.divergences(&derived.action))
+    }
+
+    /// The fields on which `action` differs from this policy's action, in declaration order.
+    ///
+    /// Numbers compare by value, so `1` and `1.0` agree.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Policy, PolicyType};
+    /// let policy = Policy {
+    ///     r#type: PolicyType::parse("type T { urgent: bool = false, score: number }").unwrap(),
+    ///     prompt: "If the email is from the CEO".to_string(),
+    ///     action: serde_json::json!({"urgent": true, "score": 1}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// assert!(policy.divergences(&serde_json::json!({"urgent": true, "score": 1.0})).is_empty());
+    /// let divergences = policy.divergences(&serde_json::json!({"score": 1}));
+    /// assert_eq!(divergences[0].field, "urgent");
+    /// assert_eq!(divergences[0].derived, None);
+    /// ```
+    pub fn divergences(&self, action: &serde_json::Value) -> Vec<ActionDivergence> {
+        self.r#type
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let name = field.name();
+                let stored = self.action.get(name);
+                let derived = action.get(name);
+                let same = match (stored, derived) {
+                    (
+                        Some(serde_json::Value::Number(lhs)),
+                        Some(serde_json::Value::Number(rhs)),
+                    ) => number_is_equal(lhs, rhs),
+                    (lhs, rhs) => lhs == rhs,
+                };
+                (!same).then(|| ActionDivergence {
+                    field: name.to_string(),
+                    stored: stored.cloned(),
+                    derived: derived.cloned(),
+                })
+            })
+            .collect()
+    }
 }
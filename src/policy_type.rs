@@ -1,9 +1,19 @@
+#[cfg(feature = "client")]
 use claudius::{
-    Anthropic, ContentBlock, JsonSchema, KnownModel, MessageCreateParams, MessageParam,
-    MessageRole, Model, ThinkingConfig,
+    ContentBlock, JsonSchema, KnownModel, MessageCreateParams, MessageParam, MessageRole, Model,
+    ThinkingConfig,
 };
 
-use crate::{parser, Field, ParseError, Policy};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::field::FieldName;
+#[cfg(feature = "client")]
+use crate::LlmProvider;
+#[cfg(feature = "client")]
+use crate::Policy;
+#[cfg(feature = "parser")]
+use crate::{parser, ParseError, ParseWarning, SyntaxToken};
+use crate::{EnumSource, Field, PolicyError};
 
 /// Represents a policy type definition with a name and a set of typed fields.
 ///
@@ -15,6 +25,73 @@ pub struct PolicyType {
     pub name: String,
     /// The fields that make up this policy type
     pub fields: Vec<Field>,
+    /// Sets of fields that only apply when a gating bool field is true
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<FieldGroup>,
+}
+
+/// A set of fields that only apply when a gating field is set.
+///
+/// In the DSL a group is written `when gate { field, ... }` inside the type body.  The gate must
+/// be a bool field of the same type outside any group.  Grouped fields are only offered to the
+/// LLM when some rule can set the gate, and they are left out of the output unless the gate
+/// ends up true.
+///
+/// # Example
+///
+/// ```
+/// use policyai::PolicyType;
+///
+/// let policy_type = PolicyType::parse(r#"type Email {
+///     is_meeting_request: bool = false,
+///     when is_meeting_request {
+///         meeting_time: string,
+///         meeting_place: string,
+///     },
+/// }"#).unwrap();
+/// assert_eq!(policy_type.gate_for("meeting_time"), Some("is_meeting_request"));
+/// assert_eq!(policy_type.gate_for("is_meeting_request"), None);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FieldGroup {
+    /// The bool field that must be true for the group to apply
+    pub when: String,
+    /// Names of the fields in the group, in declaration order
+    pub fields: Vec<String>,
+}
+
+/// A field an injection asks to set, and the value it asks for.
+#[cfg(feature = "client")]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FieldReference {
+    /// The field name as the injection refers to it.
+    pub field: String,
+    /// The value to set, or null for a value to extract from the text.
+    pub value: serde_json::Value,
+}
+
+/// What [`PolicyType::validate_injection`] found when checking an injection against a type.
+#[cfg(feature = "client")]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct InjectionFeedback {
+    /// Every field the injection asks to set.
+    pub references: Vec<FieldReference>,
+    /// Referenced fields the type does not declare.
+    pub unknown_fields: Vec<String>,
+    /// References whose value the field cannot hold, such as a value outside an enum.
+    pub invalid_values: Vec<FieldReference>,
+    /// Parts of the injection, quoted, that no field can express.
+    pub unexpressible: Vec<String>,
+}
+
+#[cfg(feature = "client")]
+impl InjectionFeedback {
+    /// True if the type can express everything the injection asks for.
+    pub fn is_expressible(&self) -> bool {
+        self.unknown_fields.is_empty()
+            && self.invalid_values.is_empty()
+            && self.unexpressible.is_empty()
+    }
 }
 
 impl PolicyType {
@@ -25,15 +102,81 @@ impl PolicyType {
     /// use policyai::PolicyType;
     /// let policy_type = PolicyType::parse("type MyPolicy { unread: bool = true }").unwrap();
     /// ```
+    #[cfg(feature = "parser")]
     pub fn parse(input: &str) -> Result<Self, ParseError> {
         parser::parse(input.trim())
     }
 
+    /// Parse a PolicyType, tolerating the slips common in hand-edited files.
+    ///
+    /// Relaxed parsing accepts a trailing comma in enum values, a missing comma between
+    /// fields, and `=` and `:` used in each other's place.  Each correction is returned as a
+    /// warning; anything else that [`PolicyType::parse`] rejects is still an error.
+    ///
+    /// # Example
+    /// ```
+    /// use policyai::PolicyType;
+    /// let (policy_type, warnings) = PolicyType::parse_relaxed(
+    ///     "type MyPolicy { unread = bool: true priority: [\"low\", \"high\",] }",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(policy_type.fields.len(), 2);
+    /// assert_eq!(warnings.len(), 4);
+    /// assert!(PolicyType::parse("type MyPolicy { unread = bool }").is_err());
+    /// ```
+    #[cfg(feature = "parser")]
+    pub fn parse_relaxed(input: &str) -> Result<(Self, Vec<ParseWarning>), ParseError> {
+        parser::parse_relaxed(input.trim())
+    }
+
+    /// The grammar [`PolicyType::parse`] accepts, in ISO EBNF, for editors and documentation.
+    #[cfg(feature = "parser")]
+    pub const GRAMMAR: &'static str = parser::GRAMMAR;
+
+    /// Split `input` into classified tokens, for syntax highlighting and completion.
+    ///
+    /// Any sequence of valid tokens is accepted, even one that does not parse as a type, so an
+    /// editor can highlight a definition while it is being written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error at the first character that cannot start a token, or at a malformed
+    /// string or number.
+    ///
+    /// # Example
+    /// ```
+    /// use policyai::{PolicyType, TokenKind};
+    /// let tokens = PolicyType::tokens("type T { urgent: bool @ sticky").unwrap();
+    /// let kinds = tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+    /// assert_eq!(kinds[0], TokenKind::Keyword);
+    /// assert_eq!(kinds[5], TokenKind::FieldType);
+    /// assert_eq!(kinds[7], TokenKind::Strategy);
+    /// assert_eq!((tokens[3].text.as_str(), tokens[3].column), ("urgent", 10));
+    /// ```
+    #[cfg(feature = "parser")]
+    pub fn tokens(input: &str) -> Result<Vec<SyntaxToken>, ParseError> {
+        parser::tokens(input)
+    }
+
     /// Get the default value for this policy type.
     ///
-    /// Returns a JSON object where each field name maps to its default value.
-    /// Fields without defaults will have null values (for String, Number, StringEnum)
-    /// or their type-specific defaults (bool fields always have a default, arrays default to []).
+    /// Returns a JSON object mapping each field to its [`Field::default_value`], leaving out
+    /// fields whose default is null or empty.  This is the object the LLM is told to output
+    /// unless a rule says otherwise, and the base every [`crate::Report`] overlays its extracted
+    /// values on.  See [`PolicyType::no_match_value`] for the output when no rule matches.
+    ///
+    /// # Example
+    /// ```
+    /// use policyai::PolicyType;
+    /// let policy_type = PolicyType::parse(
+    ///     r#"type T { urgent: bool = false, label: string, tags: [string], score: number = 1 }"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     policy_type.default_value(),
+    ///     serde_json::json!({"urgent": false, "score": 1.0}),
+    /// );
+    /// ```
     pub fn default_value(&self) -> serde_json::Value {
         let mut defaults = serde_json::Map::new();
         for field in self.fields.iter() {
@@ -57,13 +200,203 @@ impl PolicyType {
         serde_json::Value::Object(defaults)
     }
 
+    /// The output of a report in which no rule matched.
+    ///
+    /// This is [`PolicyType::default_value`] without the fields of groups whose gate does not
+    /// default to true, because a closed group's fields are left out of the output.
+    ///
+    /// # Example
+    /// ```
+    /// use policyai::PolicyType;
+    /// let policy_type = PolicyType::parse(
+    ///     r#"type T { meeting: bool = false, when meeting { place: string = "office" } }"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     policy_type.default_value(),
+    ///     serde_json::json!({"meeting": false, "place": "office"}),
+    /// );
+    /// assert_eq!(policy_type.no_match_value(), serde_json::json!({"meeting": false}));
+    /// ```
+    pub fn no_match_value(&self) -> serde_json::Value {
+        let mut value = self.default_value();
+        if let serde_json::Value::Object(obj) = &mut value {
+            let open = |gate: &str| obj.get(gate) == Some(&serde_json::Value::Bool(true));
+            let closed = self
+                .groups
+                .iter()
+                .filter(|group| !open(&group.when))
+                .flat_map(|group| group.fields.iter().cloned())
+                .collect::<Vec<_>>();
+            for field in closed {
+                obj.shift_remove(&field);
+            }
+        }
+        value
+    }
+
+    /// The gating field of the group containing `field`, if it is grouped.
+    pub fn gate_for(&self, field: &str) -> Option<&str> {
+        self.groups
+            .iter()
+            .find(|g| g.fields.iter().any(|f| f == field))
+            .map(|g| g.when.as_str())
+    }
+
+    /// This type cut down to `fields`, in declaration order.
+    ///
+    /// A grouped field keeps the bool field that gates its group, so that the group still opens
+    /// and closes as declared; the groups keep only the fields that remain.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PolicyError::UnknownField`] if this type does not declare one of `fields`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::PolicyType;
+    ///
+    /// let policy_type = PolicyType::parse(
+    ///     "type T { urgent: bool = false, escalate: bool = false, when escalate { team: string } }",
+    /// )
+    /// .unwrap();
+    /// let subset = policy_type.with_only_fields(["team"]).unwrap();
+    /// assert_eq!(
+    ///     subset.to_string(),
+    ///     "type T {\n    escalate: bool = false,\n    when escalate {\n        team: string,\n    },\n}",
+    /// );
+    /// assert!(policy_type.with_only_fields(["nope"]).is_err());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn with_only_fields(
+        &self,
+        fields: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<PolicyType, PolicyError> {
+        let mut keep = BTreeSet::new();
+        for field in fields {
+            let field = field.as_ref();
+            if !self.fields.iter().any(|f| f.name() == field) {
+                return Err(PolicyError::UnknownField {
+                    field: field.to_string(),
+                });
+            }
+            keep.insert(field.to_string());
+            if let Some(gate) = self.gate_for(field) {
+                keep.insert(gate.to_string());
+            }
+        }
+        let mut policy_type = self.clone();
+        policy_type.fields.retain(|f| keep.contains(f.name()));
+        for group in policy_type.groups.iter_mut() {
+            group.fields.retain(|f| keep.contains(f));
+        }
+        policy_type
+            .groups
+            .retain(|g| keep.contains(&g.when) && !g.fields.is_empty());
+        Ok(policy_type)
+    }
+
+    /// This type with the values of each dynamic enum supplied from `values`, keyed by field
+    /// name.  The enums become static, so that policies of the returned type are checked
+    /// against the supplied values like any other enum.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PolicyError::MissingEnumValues`] if a dynamic enum has no entry in `values`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// use policyai::PolicyType;
+    ///
+    /// let policy_type = PolicyType::parse("type T { assignee: [dynamic] = \"nobody\" }").unwrap();
+    /// let roster = BTreeMap::from([(
+    ///     "assignee".to_string(),
+    ///     vec!["alice".to_string(), "bob".to_string()],
+    /// )]);
+    /// let resolved = policy_type.with_enum_values(&roster).unwrap();
+    /// assert_eq!(
+    ///     resolved.to_string(),
+    ///     "type T {\n    assignee: [\"alice\", \"bob\"] = \"nobody\",\n}",
+    /// );
+    /// assert!(policy_type.with_enum_values(&BTreeMap::new()).is_err());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn with_enum_values(
+        &self,
+        values: &BTreeMap<String, Vec<String>>,
+    ) -> Result<PolicyType, PolicyError> {
+        let mut policy_type = self.clone();
+        for field in policy_type.fields.iter_mut() {
+            if let Field::StringEnum {
+                name,
+                values: declared,
+                source: source @ EnumSource::Dynamic,
+                ..
+            } = field
+            {
+                let Some(supplied) = values.get(name.as_str()) else {
+                    return Err(PolicyError::MissingEnumValues {
+                        field: name.clone(),
+                    });
+                };
+                *declared = supplied.clone();
+                *source = EnumSource::Static;
+            }
+        }
+        Ok(policy_type)
+    }
+
+    /// A tool name unique to this policy type, for use with [`crate::Manager::with_tool_name`].
+    ///
+    /// The name is `output_` followed by the type name with every character a tool name cannot
+    /// contain replaced by `_`, truncated to the 64 characters a tool name allows.
+    ///
+    /// # Example
+    /// ```
+    /// use policyai::PolicyType;
+    /// let policy_type = PolicyType::parse("type policyai::EmailPolicy { unread: bool = true }").unwrap();
+    /// assert_eq!(policy_type.tool_name(), "output_policyai__EmailPolicy");
+    /// ```
+    pub fn tool_name(&self) -> String {
+        let mut name = "output_".to_string();
+        name.extend(self.name.chars().map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        }));
+        name.truncate(64);
+        name
+    }
+
     /// Create a new Policy by applying a semantic injection to this PolicyType.
     ///
     /// The semantic injection is a natural language description that gets converted
     /// into structured actions that conform to this PolicyType's schema.
+    #[cfg(feature = "client")]
     pub async fn with_semantic_injection(
         &self,
-        client: &Anthropic,
+        client: &dyn LlmProvider,
+        injection: &str,
+    ) -> Result<Policy, claudius::Error> {
+        self.with_semantic_injection_using(
+            client,
+            Model::Known(KnownModel::ClaudeSonnet40),
+            injection,
+        )
+        .await
+    }
+
+    /// Like [`PolicyType::with_semantic_injection`], but derive the action with `model`.
+    #[cfg(feature = "client")]
+    pub async fn with_semantic_injection_using(
+        &self,
+        client: &dyn LlmProvider,
+        model: Model,
         injection: &str,
     ) -> Result<Policy, claudius::Error> {
         let mut schema = serde_json::json! {{}};
@@ -90,12 +423,25 @@ impl PolicyType {
                     values,
                     default: _,
                     on_conflict: _,
+                    source,
                 } => {
+                    // The values of a dynamic enum are not known until policies are applied.
                     let mut schema = String::json_schema();
-                    schema["enum"] = values.clone().into();
+                    if source.is_static() {
+                        schema["enum"] = values.clone().into();
+                    }
                     (name.clone(), schema)
                 }
                 Field::StringArray { name } => (name.clone(), Vec::<String>::json_schema()),
+                Field::DateTime {
+                    name,
+                    default: _,
+                    on_conflict: _,
+                } => {
+                    let mut schema = String::json_schema();
+                    schema["format"] = "date-time".into();
+                    (name.clone(), schema)
+                }
             };
             properties[name] = schema;
         }
@@ -105,7 +451,7 @@ impl PolicyType {
         let system = include_str!("../prompts/generate-semantic-injection.md").to_string();
         let req = MessageCreateParams {
             max_tokens: 2048,
-            model: Model::Known(KnownModel::ClaudeSonnet40),
+            model,
             messages: vec![MessageParam::new_with_string(
                 format!("<ask>{injection}</ask>"),
                 MessageRole::User,
@@ -157,21 +503,148 @@ impl PolicyType {
             r#type: self.clone(),
             prompt,
             action,
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
         })
     }
+
+    /// Check, before creating a policy, that `injection` only asks for what this type can hold.
+    ///
+    /// The LLM lists the fields the injection sets and the parts it cannot express; every listed
+    /// field and value is then checked against the declared fields and enum values, so a typo'd
+    /// field or a priority of "urgent" in a low/high enum is caught whatever the LLM says.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use claudius::Anthropic;
+    /// use policyai::PolicyType;
+    ///
+    /// let client = Anthropic::new(None)?;
+    /// let policy_type = PolicyType::parse(r#"type T { priority: ["low", "high"] = "low" }"#)?;
+    /// let feedback = policy_type
+    ///     .validate_injection(&client, "If the CEO writes, set priority to urgent.")
+    ///     .await?;
+    /// assert!(!feedback.is_expressible());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "client")]
+    pub async fn validate_injection(
+        &self,
+        client: &dyn LlmProvider,
+        injection: &str,
+    ) -> Result<InjectionFeedback, claudius::Error> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            #[serde(default)]
+            references: Vec<FieldReference>,
+            #[serde(default)]
+            unexpressible: Vec<String>,
+        }
+        let req = MessageCreateParams {
+            max_tokens: 2048,
+            model: Model::Known(KnownModel::ClaudeSonnet40),
+            messages: vec![MessageParam::new_with_string(
+                format!("<type>{self}</type>\n<injection>{injection}</injection>"),
+                MessageRole::User,
+            )],
+            system: Some(include_str!("../prompts/validate-injection.md").into()),
+            ..Default::default()
+        };
+        let resp = client.send(req).await?;
+        let raw_response = resp
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                ContentBlock::Text(t) => Some(t.text.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        let json_content = match (raw_response.find('{'), raw_response.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &raw_response[start..=end],
+            _ => raw_response.trim(),
+        };
+        let response: Response = serde_json::from_str(json_content)?;
+        Ok(self.check_references(response.references, response.unexpressible))
+    }
+
+    /// Sort `references` into those this type can and cannot hold.
+    #[cfg(feature = "client")]
+    fn check_references(
+        &self,
+        references: Vec<FieldReference>,
+        unexpressible: Vec<String>,
+    ) -> InjectionFeedback {
+        let mut feedback = InjectionFeedback {
+            unexpressible,
+            ..Default::default()
+        };
+        for reference in references.iter() {
+            let Some(field) = self.fields.iter().find(|f| f.name() == reference.field) else {
+                feedback.unknown_fields.push(reference.field.clone());
+                continue;
+            };
+            let value = &reference.value;
+            let fits = value.is_null()
+                || match field {
+                    Field::Bool { .. } => value.is_boolean(),
+                    Field::Number { .. } => value.is_number(),
+                    Field::String { .. } => value.is_string(),
+                    Field::DateTime { .. } => value
+                        .as_str()
+                        .is_some_and(|v| crate::compare::rfc3339_instant(v).is_some()),
+                    Field::StringEnum { values, source, .. } => value
+                        .as_str()
+                        .is_some_and(|v| !source.is_static() || values.iter().any(|x| x == v)),
+                    Field::StringArray { .. } => {
+                        value.is_string()
+                            || value
+                                .as_array()
+                                .is_some_and(|vs| vs.iter().all(serde_json::Value::is_string))
+                    }
+                };
+            if !fits {
+                feedback.invalid_values.push(reference.clone());
+            }
+        }
+        feedback.references = references;
+        feedback
+    }
 }
 
 impl std::fmt::Display for PolicyType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         writeln!(f, "type {} {{", self.name)?;
         for field in self.fields.iter() {
-            writeln!(f, "    {field},")?;
+            let Some(group) = self
+                .groups
+                .iter()
+                .find(|g| g.fields.iter().any(|f| f == field.name()))
+            else {
+                writeln!(f, "    {field},")?;
+                continue;
+            };
+            // A group is written out in full where its first field is declared.
+            if group.fields.first().map(String::as_str) != Some(field.name()) {
+                continue;
+            }
+            writeln!(f, "    when {} {{", FieldName(&group.when))?;
+            for name in group.fields.iter() {
+                if let Some(field) = self.fields.iter().find(|f| f.name() == name) {
+                    writeln!(f, "        {field},")?;
+                }
+            }
+            writeln!(f, "    }},")?;
         }
         write!(f, "}}")
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "parser"))]
 mod tests {
     use super::*;
     use crate::OnConflict;
@@ -195,6 +668,7 @@ mod tests {
                     values: vec!["low".to_string(), "medium".to_string(), "high".to_string()],
                     default: Some("low".to_string()),
                     on_conflict: OnConflict::LargestValue,
+                    source: EnumSource::Static,
                 },
                 Field::StringArray {
                     name: "tags".to_string(),
@@ -205,6 +679,7 @@ mod tests {
                     on_conflict: OnConflict::LargestValue,
                 },
             ],
+            groups: vec![],
         }
     }
 
@@ -215,6 +690,35 @@ mod tests {
         assert_eq!(policy_type.fields.len(), 5);
     }
 
+    #[cfg(feature = "client")]
+    #[test]
+    fn check_references_flags_unknown_fields_and_values() {
+        let reference = |field: &str, value: serde_json::Value| FieldReference {
+            field: field.to_string(),
+            value,
+        };
+        let feedback = create_test_policy_type().check_references(
+            vec![
+                reference("priority", serde_json::json!("high")),
+                reference("title", serde_json::Value::Null),
+                reference("tags", serde_json::json!("vip")),
+                reference("priority", serde_json::json!("urgent")),
+                reference("urgency", serde_json::json!(true)),
+            ],
+            vec![],
+        );
+        assert_eq!(feedback.references.len(), 5);
+        assert_eq!(feedback.unknown_fields, vec!["urgency".to_string()]);
+        assert_eq!(
+            feedback.invalid_values,
+            vec![reference("priority", serde_json::json!("urgent"))]
+        );
+        assert!(!feedback.is_expressible());
+        let feedback = create_test_policy_type()
+            .check_references(vec![reference("active", serde_json::json!(false))], vec![]);
+        assert!(feedback.is_expressible());
+    }
+
     #[test]
     fn policy_type_parse_simple() {
         let input = "type SimplePolicy { active: bool = true }";
@@ -368,6 +872,7 @@ mod tests {
                     on_conflict: OnConflict::Agreement,
                 },
             ],
+            groups: vec![],
         };
 
         let display_str = format!("{policy_type}");
@@ -386,6 +891,7 @@ mod tests {
                 default: Some(true),
                 on_conflict: OnConflict::Default,
             }],
+            groups: vec![],
         };
 
         let type2 = PolicyType {
@@ -395,6 +901,7 @@ mod tests {
                 default: Some(true),
                 on_conflict: OnConflict::Default,
             }],
+            groups: vec![],
         };
 
         let type3 = PolicyType {
@@ -404,6 +911,7 @@ mod tests {
                 default: Some(true),
                 on_conflict: OnConflict::Default,
             }],
+            groups: vec![],
         };
 
         assert_eq!(type1, type2);
@@ -425,6 +933,7 @@ mod tests {
         let policy_type = PolicyType {
             name: "DebugTest".to_string(),
             fields: vec![],
+            groups: vec![],
         };
 
         let debug_str = format!("{policy_type:?}");
@@ -442,6 +951,7 @@ mod tests {
                 default: Some(true),
                 on_conflict: OnConflict::Default,
             }],
+            groups: vec![],
         };
 
         let serialized = serde_json::to_string(&policy_type).unwrap();
@@ -461,6 +971,7 @@ mod tests {
                 default: Some(true),
                 on_conflict: OnConflict::Default,
             }],
+            groups: vec![],
         };
 
         let displayed = format!("{original}");
@@ -494,11 +1005,13 @@ mod tests {
                     values: vec!["low".to_string(), "medium".to_string(), "high".to_string()],
                     default: Some("medium".to_string()),
                     on_conflict: OnConflict::LargestValue,
+                    source: EnumSource::Static,
                 },
                 Field::StringArray {
                     name: "tags".to_string(),
                 },
             ],
+            groups: vec![],
         };
 
         let displayed = format!("{original}");
@@ -528,6 +1041,7 @@ mod tests {
                     on_conflict: OnConflict::LargestValue,
                 },
             ],
+            groups: vec![],
         };
 
         let displayed = format!("{original}");
@@ -535,6 +1049,95 @@ mod tests {
         assert_eq!(original, parsed);
     }
 
+    #[test]
+    fn policy_type_display_parse_roundtrip_strategies_that_pick_a_policy() {
+        let original = PolicyType::parse(
+            r#"type Owned {
+                escalated: bool @ first added wins = false,
+                owner: string @ last added wins,
+                score: number @ first added wins = 0,
+                priority: ["low", "high"] @ last added wins = "low",
+                due: datetime @ first added wins,
+                first: string @ last wins,
+                added: number @ last added wins,
+                level: ["low", "high"] @ highest priority wins,
+                note: string @ highest priority wins,
+            }"#,
+        )
+        .unwrap();
+        let strategies = original
+            .fields
+            .iter()
+            .map(|field| match field {
+                Field::Bool { on_conflict, .. }
+                | Field::String { on_conflict, .. }
+                | Field::Number { on_conflict, .. }
+                | Field::StringEnum { on_conflict, .. }
+                | Field::DateTime { on_conflict, .. } => *on_conflict,
+                Field::StringArray { .. } => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            strategies,
+            vec![
+                OnConflict::FirstWins,
+                OnConflict::LastWins,
+                OnConflict::FirstWins,
+                OnConflict::LastWins,
+                OnConflict::FirstWins,
+                OnConflict::LargestValue,
+                OnConflict::LastWins,
+                OnConflict::HighestPriority,
+                OnConflict::HighestPriority,
+            ]
+        );
+        let displayed = original.to_string();
+        assert!(displayed.contains("escalated: bool @ first added wins = false"));
+        assert!(displayed.contains("owner: string @ last added wins"));
+        assert!(displayed.contains("note: string @ highest priority wins"));
+        let parsed = PolicyType::parse(&displayed).unwrap();
+        assert_eq!(original, parsed);
+        assert!(PolicyType::parse("type T { a: bool @ first wins }").is_err());
+    }
+
+    #[test]
+    fn policy_type_display_parse_roundtrip_sticky_apart_from_largest() {
+        let original = PolicyType::parse(
+            r#"type Sticky {
+                escalated: bool @ sticky = false,
+                urgent: bool @ largest wins = false,
+                owner: string @ sticky,
+                score: number @ sticky = 0,
+                priority: ["low", "high"] @ sticky = "low",
+            }"#,
+        )
+        .unwrap();
+        let strategies = original
+            .fields
+            .iter()
+            .map(|field| match field {
+                Field::Bool { on_conflict, .. }
+                | Field::String { on_conflict, .. }
+                | Field::Number { on_conflict, .. }
+                | Field::StringEnum { on_conflict, .. }
+                | Field::DateTime { on_conflict, .. } => *on_conflict,
+                Field::StringArray { .. } => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            strategies,
+            vec![
+                OnConflict::Sticky,
+                OnConflict::LargestValue,
+                OnConflict::Sticky,
+                OnConflict::Sticky,
+                OnConflict::Sticky,
+            ]
+        );
+        let parsed = PolicyType::parse(&original.to_string()).unwrap();
+        assert_eq!(original, parsed);
+    }
+
     #[test]
     fn debug_parse_simple_with_default() {
         let input = r#"type Test {
@@ -566,6 +1169,7 @@ mod tests {
         let original = PolicyType {
             name: "EmptyFieldsRoundTrip".to_string(),
             fields: vec![],
+            groups: vec![],
         };
 
         let displayed = format!("{original}");
@@ -593,8 +1197,10 @@ mod tests {
                     values: vec!["a".to_string(), "b".to_string()],
                     default: None,
                     on_conflict: OnConflict::LargestValue,
+                    source: EnumSource::Static,
                 },
             ],
+            groups: vec![],
         };
 
         let displayed = format!("{original}");
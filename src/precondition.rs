@@ -0,0 +1,123 @@
+//! Local quick-reject checks for policies.
+//!
+//! A [`Precondition`] is a cheap, deterministic test on the input text.  The [`crate::Manager`]
+//! evaluates preconditions before building a request and leaves out every policy whose
+//! precondition fails, so obviously irrelevant rules never reach the LLM.
+
+/// A keyword test that must pass for a policy to be considered.
+///
+/// Keyword matching is substring matching; it is case-insensitive unless `case_sensitive` is set.
+///
+/// # Example
+///
+/// ```
+/// use policyai::Precondition;
+///
+/// let precondition = Precondition::any_of(["paxos", "raft"]);
+/// assert!(precondition.matches("Notes on Multi-Paxos"));
+/// assert!(!precondition.matches("Notes on gossip"));
+///
+/// let precondition = Precondition::Not(Box::new(Precondition::contains("unsubscribe")));
+/// assert!(!precondition.matches("Click to Unsubscribe"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Precondition {
+    /// The text contains the keyword
+    #[serde(rename = "contains")]
+    Contains {
+        /// The keyword to look for
+        keyword: String,
+        /// Match case exactly
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// At least one of the preconditions holds
+    #[serde(rename = "any_of")]
+    AnyOf(Vec<Precondition>),
+    /// Every one of the preconditions holds
+    #[serde(rename = "all_of")]
+    AllOf(Vec<Precondition>),
+    /// The precondition does not hold
+    #[serde(rename = "not")]
+    Not(Box<Precondition>),
+}
+
+impl Precondition {
+    /// A case-insensitive keyword test.
+    pub fn contains(keyword: impl Into<String>) -> Self {
+        Self::Contains {
+            keyword: keyword.into(),
+            case_sensitive: false,
+        }
+    }
+
+    /// Holds if the text contains any of the keywords, case-insensitively.
+    pub fn any_of<S: Into<String>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self::AnyOf(keywords.into_iter().map(Self::contains).collect())
+    }
+
+    /// Holds if the text contains all of the keywords, case-insensitively.
+    pub fn all_of<S: Into<String>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self::AllOf(keywords.into_iter().map(Self::contains).collect())
+    }
+
+    /// Evaluate this precondition against `text`.
+    pub fn matches(&self, text: &str) -> bool {
+        match self {
+            Precondition::Contains {
+                keyword,
+                case_sensitive: true,
+            } => text.contains(keyword.as_str()),
+            Precondition::Contains {
+                keyword,
+                case_sensitive: false,
+            } => text.to_lowercase().contains(&keyword.to_lowercase()),
+            Precondition::AnyOf(preconditions) => preconditions.iter().any(|p| p.matches(text)),
+            Precondition::AllOf(preconditions) => preconditions.iter().all(|p| p.matches(text)),
+            Precondition::Not(precondition) => !precondition.matches(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precondition_case_sensitivity() {
+        let insensitive = Precondition::contains("Paxos");
+        assert!(insensitive.matches("multi-paxos"));
+        let sensitive = Precondition::Contains {
+            keyword: "Paxos".to_string(),
+            case_sensitive: true,
+        };
+        assert!(!sensitive.matches("multi-paxos"));
+        assert!(sensitive.matches("Multi-Paxos"));
+    }
+
+    #[test]
+    fn precondition_empty_combinators() {
+        assert!(!Precondition::AnyOf(vec![]).matches("anything"));
+        assert!(Precondition::AllOf(vec![]).matches("anything"));
+    }
+
+    #[test]
+    fn precondition_all_of() {
+        let p = Precondition::all_of(["invoice", "overdue"]);
+        assert!(p.matches("Your INVOICE is overdue"));
+        assert!(!p.matches("Your invoice is attached"));
+    }
+
+    #[test]
+    fn precondition_serialization() {
+        let p = Precondition::any_of(["paxos"]);
+        let json = serde_json::to_value(&p).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"any_of": [{"contains": {"keyword": "paxos", "case_sensitive": false}}]})
+        );
+        let q: Precondition =
+            serde_json::from_value(serde_json::json!({"contains": {"keyword": "raft"}})).unwrap();
+        assert_eq!(q, Precondition::contains("raft"));
+    }
+}
@@ -0,0 +1,28 @@
+//! The types most programs that apply policies need, for importing with a glob.
+//!
+//! ```
+//! use policyai::prelude::*;
+//!
+//! let policy_type = PolicyType::parse("type Email { unread: bool = true }").unwrap();
+//! let mut manager = Manager::default();
+//! manager.add(Policy {
+//!     r#type: policy_type,
+//!     prompt: "Mark newsletters as read.".to_string(),
+//!     action: serde_json::json!({"unread": false}),
+//!     precondition: None,
+//!     exact_match: None,
+//!     explanation: None,
+//!     priority: None,
+//! });
+//! ```
+//!
+//! Besides the types, the prelude brings the extension traits into scope, so that a custom
+//! cache, repository, or log can be written without naming their modules.  Everything else is
+//! imported from the crate root as usual.
+
+pub use crate::{
+    ApplyCache, ApplyError, EnumSource, Field, Metadata, OnConflict, Policy, PolicyError,
+    PolicyRepository, PolicyType, Precondition, Report, RuleIndex,
+};
+#[cfg(feature = "client")]
+pub use crate::{FailureStore, LlmProvider, Manager, Prepared, ReviewSink, Usage, UsageLog};
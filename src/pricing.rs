@@ -0,0 +1,164 @@
+//! Dollar prices of tokens, by model.
+//!
+//! Token counts compare runs of one model, but not a cheap model against an expensive one.
+//! [`Pricing`] maps model identifiers to [`ModelPrice`]s so that [`crate::Usage::cost_usd`] can
+//! put a dollar figure on any usage.  A process-wide registry starts with Anthropic's list
+//! prices and can be overridden for negotiated rates or models served through a gateway.
+
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+/// The price of each kind of token, in US dollars per million tokens.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModelPrice {
+    /// Price of uncached input tokens.
+    pub input: f64,
+    /// Price of output tokens, thinking included.
+    pub output: f64,
+    /// Price of input tokens written to the prompt cache.
+    pub cache_creation: f64,
+    /// Price of input tokens read from the prompt cache.
+    pub cache_read: f64,
+}
+
+impl ModelPrice {
+    /// A price with Anthropic's cache multipliers: writes cost 1.25 times and reads a tenth of
+    /// the input price.
+    pub fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_creation: input * 1.25,
+            cache_read: input * 0.1,
+        }
+    }
+
+    /// The cost in dollars of the given token counts.
+    pub fn cost(&self, input: u64, output: u64, cache_creation: u64, cache_read: u64) -> f64 {
+        (input as f64 * self.input
+            + output as f64 * self.output
+            + cache_creation as f64 * self.cache_creation
+            + cache_read as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Prices keyed by model prefix.  The longest prefix of a model's identifier wins, so
+/// `claude-sonnet-4` covers every dated snapshot of that model.
+///
+/// # Example
+///
+/// ```
+/// use policyai::{ModelPrice, Pricing};
+///
+/// let pricing = Pricing::default().with_price("my-gateway/", ModelPrice::new(1.0, 2.0));
+/// assert_eq!(pricing.price("my-gateway/fast").unwrap().output, 2.0);
+/// assert_eq!(pricing.price("claude-sonnet-4-5-20250929").unwrap().input, 3.0);
+/// assert!(pricing.price("unknown-model").is_none());
+/// ```
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Pricing {
+    prices: BTreeMap<String, ModelPrice>,
+}
+
+impl Pricing {
+    /// A registry without any prices.
+    pub fn empty() -> Self {
+        Self {
+            prices: BTreeMap::new(),
+        }
+    }
+
+    /// Price models whose identifier starts with `model_prefix` at `price`, replacing any
+    /// price for the same prefix.
+    pub fn with_price(mut self, model_prefix: impl Into<String>, price: ModelPrice) -> Self {
+        self.set_price(model_prefix, price);
+        self
+    }
+
+    /// Price models whose identifier starts with `model_prefix` at `price`.
+    pub fn set_price(&mut self, model_prefix: impl Into<String>, price: ModelPrice) {
+        self.prices.insert(model_prefix.into(), price);
+    }
+
+    /// The price of `model`, if any prefix of it is priced.
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        self.prices
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, price)| *price)
+    }
+
+    /// The price of `model` in the process-wide registry.
+    pub fn global_price(model: &str) -> Option<ModelPrice> {
+        Self::registry().read().unwrap().price(model)
+    }
+
+    /// Price models whose identifier starts with `model_prefix` at `price` in the process-wide
+    /// registry.
+    pub fn set_global_price(model_prefix: impl Into<String>, price: ModelPrice) {
+        Self::registry()
+            .write()
+            .unwrap()
+            .set_price(model_prefix, price);
+    }
+
+    /// Replace the process-wide registry with `pricing`.
+    pub fn set_global(pricing: Pricing) {
+        *Self::registry().write().unwrap() = pricing;
+    }
+
+    fn registry() -> &'static RwLock<Pricing> {
+        static REGISTRY: OnceLock<RwLock<Pricing>> = OnceLock::new();
+        REGISTRY.get_or_init(|| RwLock::new(Pricing::default()))
+    }
+}
+
+impl Default for Pricing {
+    /// Anthropic's list prices.
+    fn default() -> Self {
+        Self::empty()
+            .with_price("claude-3-haiku", ModelPrice::new(0.25, 1.25))
+            .with_price("claude-3-5-haiku", ModelPrice::new(0.8, 4.0))
+            .with_price("claude-haiku-4-5", ModelPrice::new(1.0, 5.0))
+            .with_price("claude-3-5-sonnet", ModelPrice::new(3.0, 15.0))
+            .with_price("claude-3-7-sonnet", ModelPrice::new(3.0, 15.0))
+            .with_price("claude-sonnet-4", ModelPrice::new(3.0, 15.0))
+            .with_price("claude-3-opus", ModelPrice::new(15.0, 75.0))
+            .with_price("claude-opus-4", ModelPrice::new(15.0, 75.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_prefix_wins() {
+        let pricing =
+            Pricing::default().with_price("claude-sonnet-4-5", ModelPrice::new(2.0, 10.0));
+        assert_eq!(
+            pricing.price("claude-sonnet-4-5-20250929").unwrap().input,
+            2.0
+        );
+        assert_eq!(pricing.price("claude-sonnet-4-0").unwrap().input, 3.0);
+    }
+
+    #[test]
+    fn cost_is_per_million_tokens() {
+        let price = ModelPrice::new(3.0, 15.0);
+        assert!((price.cost(1_000_000, 0, 0, 0) - 3.0).abs() < 1e-9);
+        assert!((price.cost(0, 100_000, 0, 0) - 1.5).abs() < 1e-9);
+        assert!((price.cost(0, 0, 1_000_000, 1_000_000) - 4.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn global_overrides_are_seen_by_every_thread() {
+        Pricing::set_global_price("test-global-model", ModelPrice::new(7.0, 8.0));
+        let seen = std::thread::spawn(|| Pricing::global_price("test-global-model-1"))
+            .join()
+            .unwrap();
+        assert_eq!(seen, Some(ModelPrice::new(7.0, 8.0)));
+    }
+}
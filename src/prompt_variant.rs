@@ -0,0 +1,90 @@
+//! Model-specific wording of the manager's instructions.
+//!
+//! The instructions in `prompts/manager.md` were tuned against Claude.  Other models, reached
+//! through gateways that speak the same API, can do better with different phrasing; a
+//! [`PromptVariant`] registered with [`crate::Manager::with_prompt_variant`] replaces the
+//! built-in wording for every model whose identifier starts with a given prefix.
+
+/// The instructions the manager wraps around the rules and the text.
+///
+/// # Example
+///
+/// ```
+/// # use policyai::PromptVariant;
+/// let variant = PromptVariant::default()
+///     .with_system("Extract JSON.  Apply every rule that matches; otherwise use the default.");
+/// assert_ne!(variant.system, PromptVariant::default().system);
+/// assert_eq!(variant.suffix, PromptVariant::default().suffix);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PromptVariant {
+    /// The system prompt.
+    pub system: String,
+    /// The reminder sent after the text.
+    pub suffix: String,
+}
+
+impl PromptVariant {
+    /// Replace the system prompt.
+    pub fn with_system(mut self, system: impl Into<String>) -> Self {
+        self.system = system.into();
+        self
+    }
+
+    /// Replace the reminder sent after the text.
+    pub fn with_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+}
+
+impl Default for PromptVariant {
+    /// The built-in wording.
+    fn default() -> Self {
+        Self {
+            system: include_str!("../prompts/manager.md").to_string(),
+            suffix: include_str!("../prompts/manager_suffix.md").to_string(),
+        }
+    }
+}
+
+/// Variants keyed by model prefix.  The longest prefix of the model's identifier wins.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub(crate) struct PromptVariants {
+    variants: std::collections::BTreeMap<String, PromptVariant>,
+}
+
+impl PromptVariants {
+    pub(crate) fn insert(&mut self, model_prefix: String, variant: PromptVariant) {
+        self.variants.insert(model_prefix, variant);
+    }
+
+    pub(crate) fn select(&self, model: &str) -> Option<&PromptVariant> {
+        self.variants
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, variant)| variant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_prefix_wins() {
+        let mut variants = PromptVariants::default();
+        variants.insert(
+            "gpt".to_string(),
+            PromptVariant::default().with_system("gpt"),
+        );
+        variants.insert(
+            "gpt-4o".to_string(),
+            PromptVariant::default().with_system("gpt-4o"),
+        );
+        assert_eq!(variants.select("gpt-4o-mini").unwrap().system, "gpt-4o");
+        assert_eq!(variants.select("gpt-5").unwrap().system, "gpt");
+        assert!(variants.select("claude-sonnet-4-0").is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! The LLM behind a [`crate::Manager`].
+//!
+//! Everything that talks to an LLM takes a `&dyn LlmProvider`.  Requests and replies are
+//! claudius's Anthropic-shaped [`MessageCreateParams`] and [`Message`] regardless of the backend,
+//! because the manager reads only a handful of their parts: the system prompt, messages, and
+//! output tool it sends, and the single tool use, id, and usage it gets back.  A provider for
+//! OpenAI, Bedrock, or a local vLLM server translates those parts to and from its own API and
+//! builds its reply with [`tool_use_reply`].
+//!
+//! [`Anthropic`] is a provider as is, so existing callers keep passing `&client`.
+
+use claudius::{
+    Anthropic, ContentBlock, Message, MessageCreateParams, MessageStreamEvent, Model, ToolUseBlock,
+};
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt};
+
+/// The events of a streamed reply, as returned by [`LlmProvider::stream`].
+pub type MessageEvents<'a> = BoxStream<'a, Result<MessageStreamEvent, claudius::Error>>;
+
+/// An LLM that answers requests by calling the tool they offer.
+///
+/// Implementations must be safe to share between the clones of a [`crate::Manager`] and the
+/// tasks of a [`crate::pipeline::Pipeline`].
+///
+/// # Example
+///
+/// ```
+/// use claudius::{Message, MessageCreateParams, Usage};
+/// use futures::future::BoxFuture;
+/// use policyai::{tool_use_reply, LlmProvider};
+///
+/// /// A provider that matches no rules, as a stand-in for a real backend.
+/// #[derive(Debug)]
+/// struct MatchNothing;
+///
+/// impl LlmProvider for MatchNothing {
+///     fn send(&self, req: MessageCreateParams) -> BoxFuture<'_, Result<Message, claudius::Error>> {
+///         Box::pin(async move {
+///             let input = serde_json::json!({"__rule_numbers__": []});
+///             Ok(tool_use_reply("msg_1", req.model, "output_json", input, Usage::new(10, 5)))
+///         })
+///     }
+/// }
+/// ```
+pub trait LlmProvider: std::fmt::Debug + Send + Sync {
+    /// Send `req` and return the reply.
+    ///
+    /// A reply to a request that forces a tool must consist of exactly one tool use whose input
+    /// is the tool's arguments as JSON.  The reply's id is recorded in [`crate::Report`] and
+    /// [`crate::Usage`] as the message id of the attempt.
+    fn send(&self, req: MessageCreateParams) -> BoxFuture<'_, Result<Message, claudius::Error>>;
+
+    /// Send `req` as a streaming request, or return `None` if the provider cannot stream.
+    ///
+    /// The manager sends a request whole with [`LlmProvider::send`] when the provider cannot
+    /// stream it, so callers of [`crate::Manager::apply_streaming`] see the finished report
+    /// without any partial ones.
+    fn stream(
+        &self,
+        req: MessageCreateParams,
+    ) -> BoxFuture<'_, Result<Option<MessageEvents<'_>>, claudius::Error>> {
+        let _ = req;
+        Box::pin(async { Ok(None) })
+    }
+}
+
+impl LlmProvider for Anthropic {
+    fn send(&self, req: MessageCreateParams) -> BoxFuture<'_, Result<Message, claudius::Error>> {
+        Anthropic::send(self, req).boxed()
+    }
+
+    fn stream(
+        &self,
+        req: MessageCreateParams,
+    ) -> BoxFuture<'_, Result<Option<MessageEvents<'_>>, claudius::Error>> {
+        async move {
+            let events = Anthropic::stream(self, req).await?;
+            Ok(Some(events.boxed()))
+        }
+        .boxed()
+    }
+}
+
+impl<P: LlmProvider + ?Sized> LlmProvider for std::sync::Arc<P> {
+    fn send(&self, req: MessageCreateParams) -> BoxFuture<'_, Result<Message, claudius::Error>> {
+        (**self).send(req)
+    }
+
+    fn stream(
+        &self,
+        req: MessageCreateParams,
+    ) -> BoxFuture<'_, Result<Option<MessageEvents<'_>>, claudius::Error>> {
+        (**self).stream(req)
+    }
+}
+
+/// A reply that calls `tool` with `input`, for providers that translate another API's reply.
+///
+/// `id` is the backend's identifier for the reply, which reports record so that an incident can
+/// be traced to the backend's logs.
+///
+/// # Example
+///
+/// ```
+/// use claudius::{ContentBlock, Model, Usage};
+/// use policyai::tool_use_reply;
+///
+/// let reply = tool_use_reply(
+///     "chatcmpl-123",
+///     Model::Custom("gpt-4o".to_string()),
+///     "output_json",
+///     serde_json::json!({"__rule_numbers__": [1]}),
+///     Usage::new(120, 30),
+/// );
+/// assert!(matches!(reply.content.as_slice(), [ContentBlock::ToolUse(_)]));
+/// ```
+pub fn tool_use_reply(
+    id: impl Into<String>,
+    model: Model,
+    tool: impl Into<String>,
+    input: serde_json::Value,
+    usage: claudius::Usage,
+) -> Message {
+    let id = id.into();
+    let block = ToolUseBlock::new(format!("toolu_{id}"), tool, input);
+    Message::new(id, vec![ContentBlock::ToolUse(block)], model, usage)
+}
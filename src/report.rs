@@ -1,48 +1,250 @@
-use claudius::MessageParam;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+#[cfg(feature = "client")]
+use claudius::{
+    ContentBlock, MessageParam, MessageParamContent, MessageRole, TextBlock, ToolResultBlock,
+};
 
 use crate::{
-    number_is_equal, number_less_than, BoolMask, Conflict, NumberMask, OnConflict, PolicyError,
-    StringArrayMask, StringEnumMask, StringMask,
+    number_add, number_is_equal, BoolMask, Conflict, ConflictKind, DateTimeMask, EnumFallback,
+    Field, FieldOrder, MatchMask, NamingPolicy, NumberComparison, NumberMask, OnConflict,
+    PolicyError, PolicyType, Resolution, ResolutionOutcome, RuleIndex, StringArrayMask,
+    StringEnumMask, StringMask, Translations,
 };
 
+/// The property the LLM fills with why rules did not match when asked to.
+pub(crate) const NON_MATCH_REASONS_FIELD: &str = "__non_match_reasons__";
+
+/// A way in which a report's output does not conform to a policy type.
+///
+/// See [`Report::validate_against`].
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum SchemaViolation {
+    /// The output is not a JSON object
+    NotAnObject {
+        /// The output.
+        actual: serde_json::Value,
+    },
+    /// The output has a key the type does not declare
+    UnknownField {
+        /// The undeclared key.
+        field: String,
+    },
+    /// A field with a default is absent from the output
+    MissingField {
+        /// The absent field.
+        field: String,
+    },
+    /// A field holds a value of the wrong JSON type
+    WrongType {
+        /// The field.
+        field: String,
+        /// The type the field declares, as written in the DSL.
+        expected: String,
+        /// The value found.
+        actual: serde_json::Value,
+    },
+    /// An enum field holds a string outside its declared values
+    NotInEnum {
+        /// The field.
+        field: String,
+        /// The value found.
+        value: String,
+        /// The declared values.
+        values: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaViolation::NotAnObject { actual } => {
+                write!(f, "output is not an object: {actual}")
+            }
+            SchemaViolation::UnknownField { field } => {
+                write!(f, "field '{field}' is not declared by the type")
+            }
+            SchemaViolation::MissingField { field } => {
+                write!(f, "field '{field}' has a default but is missing")
+            }
+            SchemaViolation::WrongType {
+                field,
+                expected,
+                actual,
+            } => write!(f, "field '{field}' should be {expected} but is {actual}"),
+            SchemaViolation::NotInEnum {
+                field,
+                value,
+                values,
+            } => write!(
+                f,
+                "field '{field}' is {value:?}, which is not one of {values:?}"
+            ),
+        }
+    }
+}
+
+/// Why a policy did not fire.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum SkipReason {
+    /// The policy's precondition failed for the text, so it was left out of the request
+    #[serde(rename = "precondition_failed")]
+    PreconditionFailed,
+    /// The policy sets none of the fields asked for, so it was left out of the request
+    #[serde(rename = "out_of_scope")]
+    OutOfScope,
+    /// The policy was evaluated, by the LLM or locally, and did not match
+    #[serde(rename = "not_matched")]
+    NotMatched,
+    /// The text was empty or too short to evaluate, so nothing was asked of the LLM; see
+    /// [`crate::Manager::with_min_input_chars`]
+    #[serde(rename = "empty_input")]
+    EmptyInput,
+}
+
+/// A policy that did not fire, and why, from [`Report::skipped_policies`].
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SkippedPolicy {
+    /// Zero-based position of the policy, in the order policies were added to the manager
+    pub policy: usize,
+    /// Why the policy did not fire
+    pub reason: SkipReason,
+    /// The LLM's explanation of a [`SkipReason::NotMatched`], when asked for with
+    /// [`crate::ReportBuilder::with_non_match_reasons`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+}
+
 /// Contains the result of applying policies to unstructured data.
 ///
 /// A Report tracks which rules matched, what values were extracted,
 /// and any conflicts or errors that occurred during policy application.
-#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+///
+/// Reports are meant to be stored.  Every field deserializes with a default when absent, and
+/// `schema_version` records the layout that wrote the report so [`Report::migrate`] can upgrade
+/// older snapshots.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 pub struct Report {
-    /// Messages that were used in the LLM conversation
-    pub messages: Vec<MessageParam>,
+    /// Layout version this report was written with; 0 for reports that predate versioning
+    #[serde(default)]
+    pub schema_version: u32,
     /// Boolean field masks that were applied during processing
+    #[serde(default)]
     pub bool_masks: Vec<BoolMask>,
     /// Numeric field masks that were applied during processing
+    #[serde(default)]
     pub number_masks: Vec<NumberMask>,
     /// String field masks that were applied during processing
+    #[serde(default)]
     pub string_masks: Vec<StringMask>,
     /// String array field masks that were applied during processing
+    #[serde(default)]
     pub string_array_masks: Vec<StringArrayMask>,
     /// String enum field masks that were applied during processing
+    #[serde(default)]
     pub string_enum_masks: Vec<StringEnumMask>,
-    /// Mapping of policy indices to their associated field names
+    /// Datetime field masks that were applied during processing
+    #[serde(default)]
+    pub datetime_masks: Vec<DateTimeMask>,
+    /// Match indicators of rules whose action sets no fields
+    #[serde(default)]
+    pub match_masks: Vec<MatchMask>,
+    /// Masked field names set by each rule; entry `i` belongs to the rule at
+    /// [`RuleIndex::position`] `i`
+    #[serde(default)]
     pub masks_by_index: Vec<Vec<String>>,
-    /// List of policy rule indices that were matched during processing
+    /// Rule numbers ([`RuleIndex::number`]) that were matched during processing
+    #[serde(default)]
     pub rules_matched: Vec<usize>,
     /// The intermediate representation JSON received from the LLM
+    #[serde(default)]
     pub ir: Option<serde_json::Value>,
     /// Default values for all fields in the report
+    #[serde(default)]
     pub default: Option<serde_json::Value>,
+    /// Ids of the messages the provider returned while producing this report, one per attempt,
+    /// in attempt order.  Quote these when correlating an incident with the provider's logs.
+    /// They are message ids, not the request ids of the provider's response headers; a request
+    /// that fails returns no message, and its request id is on the error instead.
+    #[serde(default)]
+    pub message_ids: Vec<String>,
+    /// Input and output tokens, cached or not, of the attempts in `message_ids`.  The reports of
+    /// one joint apply share their requests and so each carry the same count.
+    #[serde(default)]
+    pub tokens_used: u64,
+    /// Requests sent to the LLM for this report, failed ones included.  Zero when every rule was
+    /// decided locally.
+    #[serde(default)]
+    pub requests: usize,
+    /// Key order applied by [`Report::value`]
+    #[serde(default)]
+    pub field_order: FieldOrder,
+    /// Key casing applied by [`Report::value`]
+    #[serde(default)]
+    pub naming: NamingPolicy,
+    /// Field names in `PolicyType` declaration order
+    #[serde(default)]
+    pub declared_fields: Vec<String>,
+    /// Zero-based positions (in the order they were added to the manager) of policies whose
+    /// precondition failed and that were left out of the request
+    #[serde(default)]
+    pub pruned_policies: Vec<usize>,
+    /// Every policy that did not fire, with the reason, ordered by position.  Together with
+    /// [`Report::matched_rules`] this accounts for every policy:  what fired and why the rest
+    /// did not.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_policies: Vec<SkippedPolicy>,
+    /// Value translations applied by [`Report::value_with_translations`]
+    #[serde(default, skip_serializing_if = "Translations::is_empty")]
+    pub translations: Translations,
+    /// The [`crate::CacheKey`] of the policies, settings, and text that produced this report, as
+    /// written by its `Display`; `None` for reports not produced by a manager.  Reports of the
+    /// same input share it, even across runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+    /// [`crate::Manager::policy_set_hash`] of the manager that produced this report; `None` for
+    /// reports not produced by a manager.  Reports that share it came from the same policies, in
+    /// the same order, under the same settings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_set_hash: Option<String>,
+    /// The rule whose value each field holds, by rule number.  Fields no rule set, and string
+    /// arrays and increments, which combine the values of several rules, are absent.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sources: BTreeMap<String, usize>,
+    /// The priority of each rule whose policy has one, by rule number.  See
+    /// [`OnConflict::HighestPriority`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub policy_priorities: BTreeMap<usize, i64>,
+    /// The zero-based position, in the order they were added to the manager, of the policy
+    /// behind each rule, by rule number.  See [`OnConflict::FirstWins`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub policy_positions: BTreeMap<usize, usize>,
 
+    /// The LLM conversation, as JSON so that reports keep it with or without the `client`
+    /// feature.
+    #[serde(default)]
+    messages: Vec<serde_json::Value>,
+    #[serde(default)]
     value: Option<serde_json::Value>,
+    #[serde(default)]
     errors: Vec<PolicyError>,
+    #[serde(default)]
     conflicts: Vec<Conflict>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    resolutions: Vec<Resolution>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, serde_json::Value>,
 }
 
 impl Report {
+    /// The layout version written by this version of the crate.
+    pub const SCHEMA_VERSION: u32 = 1;
+
     /// Create a new Report with the specified masks and configuration.
     ///
     /// # Arguments
     ///
-    /// * `messages` - Messages to be included in the LLM conversation
     /// * `bool_masks` - Boolean field masks for policy application
     /// * `number_masks` - Numeric field masks for policy application
     /// * `string_masks` - String field masks for policy application
@@ -54,11 +256,9 @@ impl Report {
     ///
     /// ```
     /// use policyai::Report;
-    /// use claudius::MessageParam;
-    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// ```
     pub fn new(
-        messages: Vec<MessageParam>,
         bool_masks: Vec<BoolMask>,
         number_masks: Vec<NumberMask>,
         string_masks: Vec<StringMask>,
@@ -67,44 +267,312 @@ impl Report {
         masks_by_index: Vec<Vec<String>>,
     ) -> Self {
         Self {
-            messages,
+            schema_version: Self::SCHEMA_VERSION,
+            messages: vec![],
             bool_masks,
             number_masks,
             string_masks,
             string_array_masks,
             string_enum_masks,
+            datetime_masks: vec![],
+            match_masks: vec![],
             masks_by_index,
             rules_matched: vec![],
             ir: None,
             default: None,
+            message_ids: vec![],
+            tokens_used: 0,
+            requests: 0,
+            field_order: FieldOrder::default(),
+            naming: NamingPolicy::default(),
+            declared_fields: vec![],
+            pruned_policies: vec![],
+            skipped_policies: vec![],
+            translations: Translations::default(),
+            input_hash: None,
+            policy_set_hash: None,
+            sources: BTreeMap::new(),
+            policy_priorities: BTreeMap::new(),
+            policy_positions: BTreeMap::new(),
             value: None,
             errors: vec![],
             conflicts: vec![],
+            resolutions: vec![],
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    /// Fabricate a report whose output is `defaults` overlaid with the extracted `value`.
+    ///
+    /// Intended for tests that need a realistic report without running an LLM.  Both arguments
+    /// should be JSON objects; the declared field order is the keys of `defaults` followed by
+    /// any keys only in `value`.  Combine with the `with_*` helpers to add matched rules,
+    /// conflicts, and errors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::{Conflict, Report};
+    /// use serde_json::json;
+    ///
+    /// let report = Report::from_value(json!({"urgent": true}), json!({"urgent": false, "tag": "x"}))
+    ///     .with_rules_matched([1])
+    ///     .with_conflict(Conflict::StringConflict {
+    ///         field: "tag".to_string(),
+    ///         rule1: 1,
+    ///         rule2: 2,
+    ///         val1: "a".to_string(),
+    ///         val2: "b".to_string(),
+    ///     });
+    /// assert_eq!(report.value(), json!({"urgent": true, "tag": "x"}));
+    /// assert_eq!(report.rules_matched, vec![1]);
+    /// assert!(report.has_errors());
+    /// ```
+    pub fn from_value(value: serde_json::Value, defaults: serde_json::Value) -> Self {
+        let mut report = Self::default();
+        let mut declared_fields = vec![];
+        for obj in [&defaults, &value]
+            .into_iter()
+            .filter_map(|v| v.as_object())
+        {
+            for key in obj.keys() {
+                if !declared_fields.contains(key) {
+                    declared_fields.push(key.clone());
+                }
+            }
+        }
+        report.declared_fields = declared_fields;
+        report.default = Some(defaults);
+        report.value = Some(value);
+        report
+    }
+
+    /// Set the extracted value of one field.
+    pub fn with_field(mut self, field: impl Into<String>, value: serde_json::Value) -> Self {
+        let field = field.into();
+        if !self.declared_fields.contains(&field) {
+            self.declared_fields.push(field.clone());
+        }
+        let build = self.value.get_or_insert_with(|| serde_json::json! {{}});
+        build[field] = value;
+        self
+    }
+
+    /// Record the given rule numbers as matched.
+    pub fn with_rules_matched(mut self, rules: impl IntoIterator<Item = usize>) -> Self {
+        self.rules_matched.extend(rules);
+        self
+    }
+
+    /// Record a conflict.
+    pub fn with_conflict(mut self, conflict: Conflict) -> Self {
+        self.conflicts.push(conflict);
+        self
+    }
+
+    /// Record how a disagreement between policies was settled.
+    pub fn with_resolution(mut self, resolution: Resolution) -> Self {
+        self.resolutions.push(resolution);
+        self
+    }
+
+    /// Record an error.
+    pub fn with_error(mut self, error: PolicyError) -> Self {
+        self.errors.push(error);
+        self
+    }
+
+    /// Deserialize a stored report of any schema version and upgrade it to the current one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is not an object or a field has an incompatible type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// // A report written before versioning, with most fields missing.
+    /// let old = serde_json::json!({"default": {"flag": false, "label": "x"}, "rules_matched": [1]});
+    /// let report = Report::migrate(old)?;
+    /// assert_eq!(report.schema_version, Report::SCHEMA_VERSION);
+    /// assert_eq!(report.rules_matched, vec![1]);
+    /// assert_eq!(report.declared_fields, vec!["flag", "label"]);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn migrate(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        let mut report: Report = serde_json::from_value(value)?;
+        if report.schema_version < 1 {
+            // Version 0 did not record declaration order; the defaults object is the best
+            // approximation because it is built by walking the declared fields.
+            if report.declared_fields.is_empty() {
+                if let Some(serde_json::Value::Object(defaults)) = &report.default {
+                    report.declared_fields = defaults.keys().cloned().collect();
+                }
+            }
+        }
+        report.schema_version = Self::SCHEMA_VERSION;
+        Ok(report)
+    }
+
+    /// Drop the LLM conversation, which is most of a stored report's size.
+    ///
+    /// Everything [`Report::value`], [`Report::conflicts`], and [`Report::errors`] depend on is
+    /// kept, so a stripped report still answers every question about its output; only the
+    /// ability to replay the request is lost.
+    pub fn strip_messages(&mut self) {
+        self.messages.clear();
+    }
+
+    /// Record `messages` as the LLM conversation that produced this report.
+    #[cfg(feature = "client")]
+    pub fn with_messages(mut self, messages: Vec<MessageParam>) -> Self {
+        self.messages = messages
+            .iter()
+            .map(|message| serde_json::to_value(message).expect("messages serialize to JSON"))
+            .collect();
+        self
+    }
+
+    /// The LLM conversation that produced this report.
+    #[cfg(feature = "client")]
+    pub fn messages(&self) -> Vec<MessageParam> {
+        self.messages
+            .iter()
+            .filter_map(|message| serde_json::from_value(message.clone()).ok())
+            .collect()
+    }
+
+    /// The bytes this report takes as JSON, in total and for its messages and IR.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// let report = Report::from_value(
+    ///     serde_json::json!({"urgent": true}),
+    ///     serde_json::json!({"urgent": false}),
+    /// );
+    /// let mut report = report.with_messages(vec![claudius::MessageParam::new_with_string(
+    ///     "a long transcript".repeat(100),
+    ///     claudius::MessageRole::User,
+    /// )]);
+    /// let before = report.size();
+    /// report.strip_messages();
+    /// let after = report.size();
+    /// assert_eq!(after.messages, 2);
+    /// assert_eq!(before.total - after.total, before.messages - after.messages);
+    /// ```
+    pub fn size(&self) -> ReportSize {
+        ReportSize {
+            total: json_len(self),
+            messages: json_len(&self.messages),
+            ir: self.ir.as_ref().map_or(0, json_len),
         }
     }
 
+    /// This report as zstd-compressed JSON, for storing in bulk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the report cannot be serialized or compressed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// let report = Report::from_value(
+    ///     serde_json::json!({"urgent": true}),
+    ///     serde_json::json!({"urgent": false}),
+    /// );
+    /// let bytes = report.to_bytes().unwrap();
+    /// let restored = Report::from_bytes(&bytes).unwrap();
+    /// assert_eq!(restored.value(), report.value());
+    /// ```
+    #[cfg(feature = "zstd")]
+    pub fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
+        let json = serde_json::to_vec(self)?;
+        zstd::encode_all(json.as_slice(), Self::ZSTD_LEVEL)
+    }
+
+    /// Read a report written by [`Report::to_bytes`], migrating it as [`Report::migrate`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` are not a compressed report.
+    #[cfg(feature = "zstd")]
+    pub fn from_bytes(bytes: &[u8]) -> std::io::Result<Self> {
+        let json = zstd::decode_all(bytes)?;
+        let value = serde_json::from_slice(&json)?;
+        Ok(Self::migrate(value)?)
+    }
+
+    /// The zstd level of [`Report::to_bytes`]; zstd's default, which compresses JSON well
+    /// without being slow.
+    #[cfg(feature = "zstd")]
+    const ZSTD_LEVEL: i32 = 3;
+
     /// Get the final structured output value combining defaults and extracted values.
     ///
     /// Returns a JSON object that merges the default values with any values
     /// that were successfully extracted and reported during policy application.
+    /// Keys are ordered according to `field_order` and cased according to `naming`.
     ///
     /// # Example
     ///
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// let output = report.value();
     /// assert!(output.is_object());
     /// ```
     pub fn value(&self) -> serde_json::Value {
+        self.naming.apply(self.declared_value())
+    }
+
+    /// Like [`Report::value`], but keyed by the field names as declared, whatever `naming`
+    /// says.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{NamingPolicy, Report};
+    /// let mut report = Report::from_value(
+    ///     serde_json::json!({"is_urgent": true}),
+    ///     serde_json::json!({"is_urgent": false}),
+    /// );
+    /// report.naming = NamingPolicy::CamelCase;
+    /// assert_eq!(report.value(), serde_json::json!({"isUrgent": true}));
+    /// assert_eq!(report.declared_value(), serde_json::json!({"is_urgent": true}));
+    /// ```
+    pub fn declared_value(&self) -> serde_json::Value {
         let mut value = self.default.clone().unwrap_or(serde_json::json! {{}});
         if let Some(serde_json::Value::Object(obj)) = self.value.as_ref() {
             for (k, v) in obj.iter() {
                 value[k.clone()] = v.clone();
             }
         }
-        value
+        self.field_order.apply(value, &self.declared_fields)
+    }
+
+    /// The output with every value that has a declared translation replaced by it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Report, Translations};
+    /// let mut report = Report::from_value(
+    ///     serde_json::json!({"priority": "high"}),
+    ///     serde_json::json!({"priority": "low"}),
+    /// );
+    /// report.translations = Translations::default().with_translation("priority", "high", 3);
+    /// assert_eq!(report.value_with_translations(), serde_json::json!({"priority": 3}));
+    /// assert_eq!(report.value(), serde_json::json!({"priority": "high"}));
+    /// ```
+    pub fn value_with_translations(&self) -> serde_json::Value {
+        self.naming
+            .apply(self.translations.apply(self.declared_value()))
     }
 
     /// Get all policy errors that occurred during processing.
@@ -117,7 +585,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// let errors = report.errors();
     /// assert!(errors.is_empty());
     /// ```
@@ -135,7 +603,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// let conflicts = report.conflicts();
     /// assert!(conflicts.is_empty());
     /// ```
@@ -143,6 +611,38 @@ impl Report {
         &self.conflicts
     }
 
+    /// Every disagreement between policies over a field's value, in the order they were seen,
+    /// with how each was settled.  Those that could not be settled are also in
+    /// [`Report::conflicts`].
+    pub fn resolutions(&self) -> &[Resolution] {
+        &self.resolutions
+    }
+
+    /// Attach `value` to the report under `key`, replacing any previous value.
+    ///
+    /// Annotations are for the caller's own metadata, such as where the text came from or when
+    /// it was ingested.  They serialize with the report but play no part in its output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// let mut report = Report::default();
+    /// report.annotate("mailbox", "support@example.com");
+    /// report.annotate("ingested_at", 1_700_000_000);
+    /// let stored: Report = serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+    /// assert_eq!(stored.annotations()["mailbox"], "support@example.com");
+    /// assert_eq!(stored.annotations().len(), 2);
+    /// ```
+    pub fn annotate(&mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) {
+        self.annotations.insert(key.into(), value.into());
+    }
+
+    /// Every annotation attached with [`Report::annotate`], by key.
+    pub fn annotations(&self) -> &BTreeMap<String, serde_json::Value> {
+        &self.annotations
+    }
+
     /// Check if the report contains any errors or conflicts.
     ///
     /// Returns true if there are any policy errors or conflicts that occurred
@@ -153,13 +653,151 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// assert!(!report.has_errors());
     /// ```
     pub fn has_errors(&self) -> bool {
         !self.errors.is_empty() || !self.conflicts.is_empty()
     }
 
+    /// Format this report according to `options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{RenderOptions, Report};
+    /// let report = Report::from_value(
+    ///     serde_json::json!({"urgent": true, "summary": "Call Alice at 555-0100"}),
+    ///     serde_json::json!({"urgent": false}),
+    /// );
+    /// assert_eq!(
+    ///     report.render(RenderOptions::production()).to_string(),
+    ///     "so: {\"urgent\":true,\"summary\":\"[redacted]\"}\n"
+    /// );
+    /// ```
+    pub fn render(&self, options: RenderOptions) -> RenderedReport<'_> {
+        RenderedReport {
+            report: self,
+            options,
+        }
+    }
+
+    /// Counts that describe this report at a glance, for logging one line per document.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// let report = Report::from_value(
+    ///     serde_json::json!({"urgent": true}),
+    ///     serde_json::json!({"urgent": false, "tag": "x"}),
+    /// )
+    /// .with_rules_matched([1]);
+    /// let summary = report.summary();
+    /// assert_eq!(summary.fields_set, 1);
+    /// assert_eq!(summary.fields_defaulted, 1);
+    /// assert_eq!(summary.rules_matched, 1);
+    /// assert_eq!(
+    ///     summary.to_string(),
+    ///     "fields_set=1 fields_defaulted=1 rules_matched=1 conflicts=0 errors=0 retries=0 tokens=0"
+    /// );
+    /// ```
+    pub fn summary(&self) -> ReportSummary {
+        let set = match &self.value {
+            Some(serde_json::Value::Object(obj)) => obj.keys().collect(),
+            _ => vec![],
+        };
+        let fields_defaulted = match &self.default {
+            Some(serde_json::Value::Object(obj)) => {
+                obj.keys().filter(|key| !set.contains(key)).count()
+            }
+            _ => 0,
+        };
+        ReportSummary {
+            fields_set: set.len(),
+            fields_defaulted,
+            rules_matched: self.matched_rules().len(),
+            conflicts: self.conflicts.len(),
+            errors: self.errors.len(),
+            retries: self.requests.saturating_sub(1),
+            tokens: self.tokens_used,
+        }
+    }
+
+    /// Convert this report into the result of a tool call.
+    ///
+    /// Use this when policy application is exposed as a tool to a larger agent.  The first text
+    /// block holds the structured value as JSON.  If there were errors or conflicts, a second
+    /// text block lists them inside `<policy-errors>` and the result is flagged `is_error`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_use_id` - The id of the tool use this result answers
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// let report = Report::default();
+    /// let result = report.to_tool_result("toolu_123");
+    /// assert_eq!(result.tool_use_id, "toolu_123");
+    /// assert_eq!(result.is_error, Some(false));
+    ///
+    /// let mut report = Report::default();
+    /// report.report_invariant_violation(file!(), line!(), "bad mask");
+    /// let result = report.to_tool_result("toolu_456");
+    /// assert_eq!(result.is_error, Some(true));
+    /// let json = serde_json::to_string(&result).unwrap();
+    /// assert!(json.contains("<policy-errors>"));
+    /// ```
+    #[cfg(feature = "client")]
+    pub fn to_tool_result(&self, tool_use_id: impl Into<String>) -> ToolResultBlock {
+        let value = serde_json::to_string(&self.value()).unwrap_or_else(|_| "{}".to_string());
+        let mut result = ToolResultBlock::new(tool_use_id.into())
+            .with_text_content(TextBlock::new(value))
+            .with_error(self.has_errors());
+        if self.has_errors() {
+            let mut annotations = "<policy-errors>".to_string();
+            for error in self.errors.iter() {
+                annotations += &format!("<error>{error}</error>");
+            }
+            for conflict in self.conflicts.iter() {
+                annotations += &format!("<conflict>{conflict}</conflict>");
+            }
+            annotations += "</policy-errors>";
+            result = result.with_text_content(TextBlock::new(annotations));
+        }
+        result
+    }
+
+    /// Convert this report into a tool-result content block.
+    ///
+    /// Equivalent to wrapping [`Report::to_tool_result`] in `ContentBlock::ToolResult`.
+    #[cfg(feature = "client")]
+    pub fn to_content_block(&self, tool_use_id: impl Into<String>) -> ContentBlock {
+        ContentBlock::ToolResult(self.to_tool_result(tool_use_id))
+    }
+
+    /// Convert this report into a user message answering a tool call.
+    ///
+    /// The message can be pushed directly onto the conversation of a claudius-based agent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// # use claudius::MessageRole;
+    /// let message = Report::default().to_tool_result_message("toolu_123");
+    /// assert_eq!(message.role, MessageRole::User);
+    /// ```
+    #[cfg(feature = "client")]
+    pub fn to_tool_result_message(&self, tool_use_id: impl Into<String>) -> MessageParam {
+        MessageParam {
+            role: MessageRole::User,
+            content: MessageParamContent::Array(vec![self.to_content_block(tool_use_id)]),
+        }
+    }
+
     /// Report a default boolean value for a field.
     ///
     /// Sets or validates the default value for a boolean field. If a default
@@ -175,7 +813,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_bool_default("active", true);
     /// ```
     pub fn report_bool_default(&mut self, field: &str, default: bool) {
@@ -214,7 +852,7 @@ impl Report {
     /// ```
     /// # use policyai::{Report, OnConflict};
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_bool(1, "urgent", true, OnConflict::Agreement);
     /// ```
     pub fn report_bool(
@@ -225,6 +863,9 @@ impl Report {
         on_conflict: OnConflict,
     ) {
         self.report_policy_index(policy_index);
+        let precedence = self.precedence(field, policy_index, on_conflict);
+        let before = self.field_value(field);
+        let mut outcome = None;
         let build = self.value.get_or_insert_with(|| {
             serde_json::json! {{}}
         });
@@ -234,12 +875,56 @@ impl Report {
                 serde_json::Value::Bool(b) => {
                     if *b != value {
                         match on_conflict {
-                            OnConflict::Default => {}
-                            OnConflict::Agreement => {
+                            OnConflict::Default => {
+                                outcome = Some(ResolutionOutcome::Defaulted);
+                            }
+                            OnConflict::Agreement | OnConflict::Unknown => {
+                                outcome = Some(ResolutionOutcome::Errored);
                                 let b = *b;
-                                self.report_bool_conflict(field, b, value);
+                                let rules = self.conflicting_rules(field, policy_index);
+                                self.report_bool_conflict(field, rules, b, value);
                             }
                             OnConflict::LargestValue => {
+                                outcome = Some(ResolutionOutcome::Largest);
+                                if value {
+                                    *b = value;
+                                }
+                            }
+                            OnConflict::SmallestValue => {
+                                outcome = Some(ResolutionOutcome::Smallest);
+                                if !value {
+                                    *b = value;
+                                }
+                            }
+                            OnConflict::HighestPriority => match precedence {
+                                Some(Ordering::Greater) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                    *b = value;
+                                }
+                                Some(Ordering::Less) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                }
+                                Some(Ordering::Equal) | None => {
+                                    outcome = Some(ResolutionOutcome::Errored);
+                                    let b = *b;
+                                    let rules = self.conflicting_rules(field, policy_index);
+                                    self.report_bool_conflict(field, rules, b, value);
+                                }
+                            },
+                            OnConflict::FirstWins => {
+                                outcome = Some(ResolutionOutcome::First);
+                                if precedence == Some(Ordering::Greater) {
+                                    *b = value;
+                                }
+                            }
+                            OnConflict::LastWins => {
+                                outcome = Some(ResolutionOutcome::Last);
+                                if precedence != Some(Ordering::Less) {
+                                    *b = value;
+                                }
+                            }
+                            OnConflict::Sticky => {
+                                outcome = Some(ResolutionOutcome::Stuck);
                                 if value {
                                     *b = value;
                                 }
@@ -275,6 +960,8 @@ impl Report {
         } else {
             build[field] = value.into();
         }
+        self.report_source(field, policy_index, before, on_conflict, precedence);
+        self.report_resolution(field, ConflictKind::Bool, on_conflict, outcome);
     }
 
     /// Report a default numeric value for a field.
@@ -292,7 +979,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_number_default("score", 0);
     /// ```
     pub fn report_number_default(&mut self, field: &str, default: impl Into<serde_json::Number>) {
@@ -332,7 +1019,7 @@ impl Report {
     /// ```
     /// # use policyai::{Report, OnConflict};
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_number(1, "priority", 10, OnConflict::LargestValue);
     /// ```
     pub fn report_number(
@@ -348,6 +1035,9 @@ impl Report {
         let mut conflict_to_report = None;
         let mut error_to_report = None;
 
+        let precedence = self.precedence(field, policy_index, on_conflict);
+        let before = self.field_value(field);
+        let mut outcome = None;
         let build = self.value.get_or_insert_with(|| {
             serde_json::json! {{}}
         });
@@ -357,18 +1047,81 @@ impl Report {
                 serde_json::Value::Number(existing) => {
                     if !number_is_equal(existing, &value) {
                         match on_conflict {
-                            OnConflict::Default => {}
-                            OnConflict::Agreement => {
+                            OnConflict::Default => {
+                                outcome = Some(ResolutionOutcome::Defaulted);
+                            }
+                            OnConflict::Agreement | OnConflict::Unknown => {
+                                outcome = Some(ResolutionOutcome::Errored);
                                 conflict_to_report =
                                     Some((field.to_string(), existing.clone(), value.clone()));
                             }
                             OnConflict::LargestValue => {
-                                if number_less_than(existing, &value) {
+                                match NumberComparison::Strict.compare(existing, &value) {
+                                    Some(Ordering::Less) => {
+                                        outcome = Some(ResolutionOutcome::Largest);
+                                        *existing = value;
+                                    }
+                                    Some(Ordering::Greater) => {
+                                        outcome = Some(ResolutionOutcome::Largest);
+                                    }
+                                    // Numbers that cannot be ordered have no largest.
+                                    Some(Ordering::Equal) | None => {
+                                        outcome = Some(ResolutionOutcome::Errored);
+                                        conflict_to_report = Some((
+                                            field.to_string(),
+                                            existing.clone(),
+                                            value.clone(),
+                                        ));
+                                    }
+                                }
+                            }
+                            OnConflict::SmallestValue => {
+                                match NumberComparison::Strict.compare(existing, &value) {
+                                    Some(Ordering::Greater) => {
+                                        outcome = Some(ResolutionOutcome::Smallest);
+                                        *existing = value;
+                                    }
+                                    Some(Ordering::Less) => {
+                                        outcome = Some(ResolutionOutcome::Smallest);
+                                    }
+                                    Some(Ordering::Equal) | None => {
+                                        outcome = Some(ResolutionOutcome::Errored);
+                                        conflict_to_report = Some((
+                                            field.to_string(),
+                                            existing.clone(),
+                                            value.clone(),
+                                        ));
+                                    }
+                                }
+                            }
+                            OnConflict::HighestPriority => match precedence {
+                                Some(Ordering::Greater) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
                                     *existing = value;
-                                } else {
+                                }
+                                Some(Ordering::Less) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                }
+                                Some(Ordering::Equal) | None => {
+                                    outcome = Some(ResolutionOutcome::Errored);
                                     conflict_to_report =
                                         Some((field.to_string(), existing.clone(), value.clone()));
                                 }
+                            },
+                            OnConflict::FirstWins => {
+                                outcome = Some(ResolutionOutcome::First);
+                                if precedence == Some(Ordering::Greater) {
+                                    *existing = value;
+                                }
+                            }
+                            OnConflict::LastWins => {
+                                outcome = Some(ResolutionOutcome::Last);
+                                if precedence != Some(Ordering::Less) {
+                                    *existing = value;
+                                }
+                            }
+                            OnConflict::Sticky => {
+                                outcome = Some(ResolutionOutcome::Stuck);
                             }
                         }
                     }
@@ -391,11 +1144,59 @@ impl Report {
         }
 
         if let Some((field_name, old_val, new_val)) = conflict_to_report {
-            self.report_number_conflict(&field_name, old_val, new_val);
+            let rules = self.conflicting_rules(field, policy_index);
+            self.report_number_conflict(&field_name, rules, old_val, new_val);
         }
         if let Some(error_msg) = error_to_report {
             self.report_invariant_violation(file!(), line!(), &error_msg);
         }
+        self.report_source(field, policy_index, before, on_conflict, precedence);
+        self.report_resolution(field, ConflictKind::Number, on_conflict, outcome);
+    }
+
+    /// Report an increment to a numeric field from a policy application.
+    ///
+    /// Adds `delta` to the field's current value, or to `base` if no policy has set the field
+    /// yet.  Increments compose with one another instead of conflicting, so every matching rule
+    /// contributes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{OnConflict, Report};
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// report.report_number_increment(1, "score", 10, Some(5.into()));
+    /// report.report_number_increment(2, "score", -3, Some(5.into()));
+    /// assert_eq!(report.value()["score"], 12);
+    /// ```
+    pub fn report_number_increment(
+        &mut self,
+        policy_index: usize,
+        field: &str,
+        delta: impl Into<serde_json::Number>,
+        base: Option<serde_json::Number>,
+    ) {
+        self.report_policy_index(policy_index);
+        let delta = delta.into();
+        let build = self.value.get_or_insert_with(|| {
+            serde_json::json! {{}}
+        });
+        let current = match build.get(field) {
+            None | Some(serde_json::Value::Null) => base.unwrap_or_else(|| 0.into()),
+            Some(serde_json::Value::Number(existing)) => existing.clone(),
+            Some(_) => {
+                self.report_invariant_violation(
+                    file!(),
+                    line!(),
+                    "non-number found in place of number",
+                );
+                return;
+            }
+        };
+        match number_add(&current, &delta) {
+            Some(sum) => build[field] = sum.into(),
+            None => self.report_invariant_violation(file!(), line!(), "increment overflowed"),
+        }
     }
 
     /// Report a default string value for a field.
@@ -413,7 +1214,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_string_default("category", "unknown");
     /// ```
     pub fn report_string_default(&mut self, field: &str, default: impl Into<String>) {
@@ -453,7 +1254,7 @@ impl Report {
     /// ```
     /// # use policyai::{Report, OnConflict};
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_string(1, "title", "Important Message".to_string(), OnConflict::Agreement);
     /// ```
     pub fn report_string(
@@ -468,6 +1269,9 @@ impl Report {
         let mut conflict_to_report = None;
         let mut error_to_report = None;
 
+        let precedence = self.precedence(field, policy_index, on_conflict);
+        let before = self.field_value(field);
+        let mut outcome = None;
         let build = self.value.get_or_insert_with(|| {
             serde_json::json! {{}}
         });
@@ -477,16 +1281,55 @@ impl Report {
                 serde_json::Value::String(existing) => {
                     if *existing != value {
                         match on_conflict {
-                            OnConflict::Default => {}
-                            OnConflict::Agreement => {
+                            OnConflict::Default => {
+                                outcome = Some(ResolutionOutcome::Defaulted);
+                            }
+                            OnConflict::Agreement | OnConflict::Unknown => {
+                                outcome = Some(ResolutionOutcome::Errored);
                                 conflict_to_report =
                                     Some((field.to_string(), existing.clone(), value.clone()));
                             }
                             OnConflict::LargestValue => {
+                                outcome = Some(ResolutionOutcome::Largest);
                                 if value.len() > existing.len() {
                                     *v = value.into();
                                 }
                             }
+                            OnConflict::SmallestValue => {
+                                outcome = Some(ResolutionOutcome::Smallest);
+                                if value.len() < existing.len() {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::HighestPriority => match precedence {
+                                Some(Ordering::Greater) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                    *v = value.into();
+                                }
+                                Some(Ordering::Less) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                }
+                                Some(Ordering::Equal) | None => {
+                                    outcome = Some(ResolutionOutcome::Errored);
+                                    conflict_to_report =
+                                        Some((field.to_string(), existing.clone(), value.clone()));
+                                }
+                            },
+                            OnConflict::FirstWins => {
+                                outcome = Some(ResolutionOutcome::First);
+                                if precedence == Some(Ordering::Greater) {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::LastWins => {
+                                outcome = Some(ResolutionOutcome::Last);
+                                if precedence != Some(Ordering::Less) {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::Sticky => {
+                                outcome = Some(ResolutionOutcome::Stuck);
+                            }
                         }
                     }
                 }
@@ -508,41 +1351,90 @@ impl Report {
         }
 
         if let Some((field_name, old_val, new_val)) = conflict_to_report {
-            self.report_string_conflict(&field_name, old_val, new_val);
+            let rules = self.conflicting_rules(field, policy_index);
+            self.report_string_conflict(&field_name, rules, old_val, new_val);
         }
         if let Some(error_msg) = error_to_report {
             self.report_invariant_violation(file!(), line!(), &error_msg);
         }
+        self.report_source(field, policy_index, before, on_conflict, precedence);
+        self.report_resolution(field, ConflictKind::String, on_conflict, outcome);
     }
 
     /// Report a string enum value from a policy application.
     ///
     /// Records a string enum value extracted by a policy and handles conflicts
-    /// according to the specified conflict resolution strategy.
+    /// according to the specified conflict resolution strategy.  A value outside `allowed` is
+    /// not recorded; [`PolicyError::InvalidEnumValue`] is reported instead.
     ///
     /// # Arguments
     ///
     /// * `policy_index` - The index of the policy reporting this value
     /// * `field` - The name of the field being reported
     /// * `value` - The enum value to report
+    /// * `allowed` - The values the field allows
     /// * `on_conflict` - Strategy for handling conflicts with existing values
     ///
     /// # Example
     ///
     /// ```
-    /// # use policyai::{Report, OnConflict};
+    /// # use policyai::{Report, OnConflict, PolicyError};
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
-    /// report.report_string_enum(1, "status", "active".to_string(), OnConflict::LargestValue);
+    /// let allowed = ["active".to_string(), "inactive".to_string()];
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// report.report_string_enum(1, "status", "active".to_string(), &allowed, OnConflict::LargestValue);
+    /// report.report_string_enum(2, "status", "paused".to_string(), &allowed, OnConflict::LargestValue);
+    /// assert_eq!(report.value()["status"], "active");
+    /// assert!(matches!(report.errors(), [PolicyError::InvalidEnumValue { .. }]));
     /// ```
     pub fn report_string_enum(
         &mut self,
         policy_index: usize,
         field: &str,
         value: String,
+        allowed: &[String],
+        on_conflict: OnConflict,
+    ) {
+        self.report_string_enum_with_fallback(
+            policy_index,
+            field,
+            value,
+            allowed,
+            on_conflict,
+            None,
+            &[],
+        );
+    }
+
+    /// Report a string enum value, as [`Report::report_string_enum`] does, and settle a
+    /// disagreement under [`OnConflict::Agreement`] with `fallback`.
+    ///
+    /// The disagreement is reported as a conflict either way; `fallback` only decides which
+    /// value the field holds afterward.  `default` is the field's declared default, used by
+    /// [`EnumFallback::Default`].  See [`EnumFallback`] for an example.
+    #[allow(clippy::too_many_arguments)]
+    pub fn report_string_enum_with_fallback(
+        &mut self,
+        policy_index: usize,
+        field: &str,
+        value: String,
+        allowed: &[String],
         on_conflict: OnConflict,
+        default: Option<&str>,
+        fallback: &[EnumFallback],
     ) {
         self.report_policy_index(policy_index);
+        if !allowed.contains(&value) {
+            self.errors.push(PolicyError::InvalidEnumValue {
+                field: field.to_string(),
+                value,
+                allowed: allowed.to_vec(),
+            });
+            return;
+        }
+        let precedence = self.precedence(field, policy_index, on_conflict);
+        let before = self.field_value(field);
+        let mut outcome = None;
         let build = self.value.get_or_insert_with(|| {
             serde_json::json! {{}}
         });
@@ -552,19 +1444,72 @@ impl Report {
                 serde_json::Value::String(s) => {
                     if *s != value {
                         match on_conflict {
-                            OnConflict::Default => {}
-                            OnConflict::Agreement => {
+                            OnConflict::Default => {
+                                outcome = Some(ResolutionOutcome::Defaulted);
+                            }
+                            OnConflict::Agreement | OnConflict::Unknown => {
+                                outcome = Some(ResolutionOutcome::Errored);
                                 let s = s.clone();
-                                self.report_string_conflict(field, s, value);
+                                if let Some(chosen) =
+                                    EnumFallback::choose(fallback, default, allowed, &s, &value)
+                                {
+                                    *v = chosen.into();
+                                }
+                                let rules = self.conflicting_rules(field, policy_index);
+                                self.report_string_conflict(field, rules, s, value);
                             }
                             OnConflict::LargestValue => {
                                 if value.len() > s.len() {
+                                    outcome = Some(ResolutionOutcome::Largest);
+                                    *v = value.into();
+                                } else {
+                                    outcome = Some(ResolutionOutcome::Errored);
+                                    let s = s.clone();
+                                    let rules = self.conflicting_rules(field, policy_index);
+                                    self.report_string_conflict(field, rules, s, value);
+                                }
+                            }
+                            OnConflict::SmallestValue => {
+                                if value.len() < s.len() {
+                                    outcome = Some(ResolutionOutcome::Smallest);
                                     *v = value.into();
                                 } else {
+                                    outcome = Some(ResolutionOutcome::Errored);
+                                    let s = s.clone();
+                                    let rules = self.conflicting_rules(field, policy_index);
+                                    self.report_string_conflict(field, rules, s, value);
+                                }
+                            }
+                            OnConflict::HighestPriority => match precedence {
+                                Some(Ordering::Greater) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                    *v = value.into();
+                                }
+                                Some(Ordering::Less) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                }
+                                Some(Ordering::Equal) | None => {
+                                    outcome = Some(ResolutionOutcome::Errored);
                                     let s = s.clone();
-                                    self.report_string_conflict(field, s, value);
+                                    let rules = self.conflicting_rules(field, policy_index);
+                                    self.report_string_conflict(field, rules, s, value);
+                                }
+                            },
+                            OnConflict::FirstWins => {
+                                outcome = Some(ResolutionOutcome::First);
+                                if precedence == Some(Ordering::Greater) {
+                                    *v = value.into();
                                 }
                             }
+                            OnConflict::LastWins => {
+                                outcome = Some(ResolutionOutcome::Last);
+                                if precedence != Some(Ordering::Less) {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::Sticky => {
+                                outcome = Some(ResolutionOutcome::Stuck);
+                            }
                         }
                     }
                 }
@@ -596,6 +1541,162 @@ impl Report {
         } else {
             build[field] = value.into();
         }
+        self.report_source(field, policy_index, before, on_conflict, precedence);
+        self.report_resolution(field, ConflictKind::StringEnum, on_conflict, outcome);
+    }
+
+    /// Report a default datetime value for a field.
+    ///
+    /// Sets or validates the default value for a datetime field. If a default
+    /// already exists and differs, reports a default conflict error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Report;
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// report.report_datetime_default("due", "2024-12-31T23:59:59Z");
+    /// ```
+    pub fn report_datetime_default(&mut self, field: &str, default: impl Into<String>) {
+        self.report_string_default(field, default);
+    }
+
+    /// Report an RFC 3339 datetime value from a policy application.
+    ///
+    /// Values that name the same instant, such as `2024-03-01T17:00:00Z` and
+    /// `2024-03-01T12:00:00-05:00`, agree; the first one reported is kept.  Otherwise
+    /// [`OnConflict::LargestValue`] keeps the latest and [`OnConflict::SmallestValue`] the
+    /// earliest.  A value that is not RFC 3339, including one without a UTC offset, is not
+    /// recorded; [`PolicyError::InvalidDateTime`] is reported instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy_index` - The index of the policy reporting this value
+    /// * `field` - The name of the field being reported
+    /// * `value` - The timestamp to report
+    /// * `on_conflict` - Strategy for handling conflicts with existing values
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{OnConflict, Report};
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// report.report_datetime(1, "due", "2024-03-08T17:00:00Z".to_string(), OnConflict::SmallestValue);
+    /// report.report_datetime(2, "due", "2024-03-01T09:00:00-08:00".to_string(), OnConflict::SmallestValue);
+    /// assert_eq!(report.value()["due"], "2024-03-01T09:00:00-08:00");
+    /// ```
+    pub fn report_datetime(
+        &mut self,
+        policy_index: usize,
+        field: &str,
+        value: String,
+        on_conflict: OnConflict,
+    ) {
+        self.report_policy_index(policy_index);
+        let Some(instant) = crate::compare::rfc3339_instant(&value) else {
+            self.errors.push(PolicyError::InvalidDateTime {
+                field: field.to_string(),
+                value,
+            });
+            return;
+        };
+
+        let mut conflict_to_report = None;
+        let mut error_to_report = None;
+
+        let precedence = self.precedence(field, policy_index, on_conflict);
+        let before = self.field_value(field);
+        let mut outcome = None;
+        let build = self.value.get_or_insert_with(|| {
+            serde_json::json! {{}}
+        });
+        if let Some(v) = build.get_mut(field) {
+            match v {
+                serde_json::Value::Null => *v = value.into(),
+                serde_json::Value::String(existing) => {
+                    // A value set by other means, as with `Report::with_field`, may not be
+                    // RFC 3339; it gives way to any instant.
+                    let existing_instant = crate::compare::rfc3339_instant(existing);
+                    if existing_instant != Some(instant) {
+                        match on_conflict {
+                            OnConflict::Default => {
+                                outcome = Some(ResolutionOutcome::Defaulted);
+                            }
+                            OnConflict::Agreement | OnConflict::Unknown => {
+                                outcome = Some(ResolutionOutcome::Errored);
+                                conflict_to_report =
+                                    Some((field.to_string(), existing.clone(), value.clone()));
+                            }
+                            OnConflict::LargestValue => {
+                                outcome = Some(ResolutionOutcome::Largest);
+                                if existing_instant < Some(instant) {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::SmallestValue => {
+                                outcome = Some(ResolutionOutcome::Smallest);
+                                if existing_instant.is_none_or(|e| instant < e) {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::HighestPriority => match precedence {
+                                Some(Ordering::Greater) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                    *v = value.into();
+                                }
+                                Some(Ordering::Less) => {
+                                    outcome = Some(ResolutionOutcome::Prioritized);
+                                }
+                                Some(Ordering::Equal) | None => {
+                                    outcome = Some(ResolutionOutcome::Errored);
+                                    conflict_to_report =
+                                        Some((field.to_string(), existing.clone(), value.clone()));
+                                }
+                            },
+                            OnConflict::FirstWins => {
+                                outcome = Some(ResolutionOutcome::First);
+                                if precedence == Some(Ordering::Greater) {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::LastWins => {
+                                outcome = Some(ResolutionOutcome::Last);
+                                if precedence != Some(Ordering::Less) {
+                                    *v = value.into();
+                                }
+                            }
+                            OnConflict::Sticky => {
+                                outcome = Some(ResolutionOutcome::Stuck);
+                            }
+                        }
+                    }
+                }
+                serde_json::Value::Bool(_) => {
+                    error_to_report = Some("bool found in place of datetime".to_string());
+                }
+                serde_json::Value::Number(_) => {
+                    error_to_report = Some("number found in place of datetime".to_string());
+                }
+                serde_json::Value::Array(_) => {
+                    error_to_report = Some("array found in place of datetime".to_string());
+                }
+                serde_json::Value::Object(_) => {
+                    error_to_report = Some("found an object".to_string());
+                }
+            }
+        } else {
+            build[field] = value.into();
+        }
+
+        if let Some((field_name, old_val, new_val)) = conflict_to_report {
+            let rules = self.conflicting_rules(field, policy_index);
+            self.report_string_conflict(&field_name, rules, old_val, new_val);
+        }
+        if let Some(error_msg) = error_to_report {
+            self.report_invariant_violation(file!(), line!(), &error_msg);
+        }
+        self.report_source(field, policy_index, before, on_conflict, precedence);
+        self.report_resolution(field, ConflictKind::DateTime, on_conflict, outcome);
     }
 
     /// Report a string array element from a policy application.
@@ -614,7 +1715,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_string_array(1, "tags", "urgent".to_string());
     /// report.report_string_array(1, "tags", "important".to_string());
     /// ```
@@ -646,6 +1747,324 @@ impl Report {
             .or_insert_with(|| serde_json::Value::Array(vec![]));
     }
 
+    /// The masked field names that `rule` sets, or `None` if no such rule was sent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Report, RuleIndex};
+    /// let report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![vec!["field_abc".to_string()]]);
+    /// assert_eq!(report.masks_for_rule(RuleIndex::FIRST), Some(&["field_abc".to_string()][..]));
+    /// assert_eq!(report.masks_for_rule(RuleIndex::FIRST.next()), None);
+    /// ```
+    pub fn masks_for_rule(&self, rule: RuleIndex) -> Option<&[String]> {
+        self.masks_by_index.get(rule.position()).map(Vec::as_slice)
+    }
+
+    /// The distinct rules that were matched, in ascending order.
+    pub fn matched_rules(&self) -> Vec<RuleIndex> {
+        let mut rules = self
+            .rules_matched
+            .iter()
+            .filter_map(|n| RuleIndex::from_number(*n))
+            .collect::<Vec<_>>();
+        rules.sort();
+        rules.dedup();
+        rules
+    }
+
+    /// Why the policy at zero-based `position` did not fire, or `None` if it fired or is not
+    /// accounted for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Report, SkipReason, SkippedPolicy};
+    /// let mut report = Report::default();
+    /// report.skipped_policies.push(SkippedPolicy {
+    ///     policy: 2,
+    ///     reason: SkipReason::PreconditionFailed,
+    ///     explanation: None,
+    /// });
+    /// assert_eq!(report.skip_reason(2), Some(SkipReason::PreconditionFailed));
+    /// assert_eq!(report.skip_reason(0), None);
+    /// ```
+    pub fn skip_reason(&self, position: usize) -> Option<SkipReason> {
+        self.skipped_policies
+            .iter()
+            .find(|skipped| skipped.policy == position)
+            .map(|skipped| skipped.reason)
+    }
+
+    /// Record the policies at `positions` as pruned because their precondition failed,
+    /// replacing whatever was recorded for them.
+    #[cfg(feature = "client")]
+    pub(crate) fn record_pruned_policies(&mut self, positions: Vec<usize>) {
+        self.skipped_policies
+            .retain(|skipped| !positions.contains(&skipped.policy));
+        self.skipped_policies
+            .extend(positions.iter().map(|&policy| SkippedPolicy {
+                policy,
+                reason: SkipReason::PreconditionFailed,
+                explanation: None,
+            }));
+        self.skipped_policies.sort_by_key(|skipped| skipped.policy);
+        self.pruned_policies = positions;
+    }
+
+    /// The matched rules whose action sets no fields, in ascending order.
+    ///
+    /// These are the audit-only rules that fired without changing the output; they are also
+    /// included in [`Report::matched_rules`].
+    pub fn matched_audit_rules(&self) -> Vec<RuleIndex> {
+        self.matched_rules()
+            .into_iter()
+            .filter(|rule| {
+                self.match_masks
+                    .iter()
+                    .any(|m| m.policy_index == rule.number())
+            })
+            .collect()
+    }
+
+    /// Why each rule that did not match was passed over, in the LLM's words.
+    ///
+    /// Only populated for reports built with [`crate::ReportBuilder::with_non_match_reasons`].
+    /// Reasons given for rules that did match, or for rules that do not exist, are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{ReportBuilder, RuleIndex};
+    /// let report = ReportBuilder::default()
+    ///     .with_non_match_reasons()
+    ///     .consume_ir(serde_json::json!({
+    ///         "__rule_numbers__": [],
+    ///         "__non_match_reasons__": {"1": "The sender is not the CEO."},
+    ///     }))
+    ///     .unwrap();
+    /// // The default builder has no rules, so the reason is dropped.
+    /// assert!(report.non_match_reasons().is_empty());
+    /// ```
+    pub fn non_match_reasons(&self) -> BTreeMap<RuleIndex, String> {
+        let Some(serde_json::Value::Object(reasons)) = self
+            .ir
+            .as_ref()
+            .and_then(|ir| ir.get(NON_MATCH_REASONS_FIELD))
+        else {
+            return BTreeMap::new();
+        };
+        let matched = self.matched_rules();
+        reasons
+            .iter()
+            .filter_map(|(number, reason)| {
+                let rule = RuleIndex::from_number(number.trim().parse().ok()?)?;
+                let reason = reason.as_str()?;
+                (rule.position() < self.masks_by_index.len() && !matched.contains(&rule))
+                    .then(|| (rule, reason.to_string()))
+            })
+            .collect()
+    }
+
+    /// Check that [`Report::declared_value`] strictly conforms to `policy_type`.
+    ///
+    /// Every key must be a declared field holding a value of the declared type, enum values
+    /// must be among the declared values, and every field with a default must be present
+    /// unless it belongs to a group whose gate is not true.  Returns the violations in the
+    /// order of the output's keys followed by the missing fields; an empty list means the
+    /// value is safe to store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{PolicyType, Report, SchemaViolation};
+    /// let policy_type =
+    ///     PolicyType::parse(r#"type T { urgent: bool = false, priority: ["low", "high"] }"#)
+    ///         .unwrap();
+    /// let report = Report::from_value(
+    ///     serde_json::json!({"urgent": true, "priority": "medium"}),
+    ///     serde_json::json!({"urgent": false}),
+    /// );
+    /// assert_eq!(
+    ///     report.validate_against(&policy_type),
+    ///     vec![SchemaViolation::NotInEnum {
+    ///         field: "priority".to_string(),
+    ///         value: "medium".to_string(),
+    ///         values: vec!["low".to_string(), "high".to_string()],
+    ///     }],
+    /// );
+    /// ```
+    pub fn validate_against(&self, policy_type: &PolicyType) -> Vec<SchemaViolation> {
+        let value = self.declared_value();
+        let serde_json::Value::Object(obj) = &value else {
+            return vec![SchemaViolation::NotAnObject { actual: value }];
+        };
+        let mut violations = vec![];
+        for (name, actual) in obj.iter() {
+            let Some(field) = policy_type.fields.iter().find(|f| f.name() == name) else {
+                violations.push(SchemaViolation::UnknownField {
+                    field: name.clone(),
+                });
+                continue;
+            };
+            let wrong_type = |expected: &str| SchemaViolation::WrongType {
+                field: name.clone(),
+                expected: expected.to_string(),
+                actual: actual.clone(),
+            };
+            match (field, actual) {
+                (Field::Bool { .. }, serde_json::Value::Bool(_))
+                | (Field::Number { .. }, serde_json::Value::Number(_))
+                | (Field::String { .. }, serde_json::Value::String(_)) => {}
+                (Field::StringEnum { values, source, .. }, serde_json::Value::String(s)) => {
+                    if source.is_static() && !values.contains(s) {
+                        violations.push(SchemaViolation::NotInEnum {
+                            field: name.clone(),
+                            value: s.clone(),
+                            values: values.clone(),
+                        });
+                    }
+                }
+                (Field::StringArray { .. }, serde_json::Value::Array(elems))
+                    if elems.iter().all(serde_json::Value::is_string) => {}
+                (Field::DateTime { .. }, serde_json::Value::String(s))
+                    if crate::compare::rfc3339_instant(s).is_some() => {}
+                (Field::Bool { .. }, _) => violations.push(wrong_type("bool")),
+                (Field::Number { .. }, _) => violations.push(wrong_type("number")),
+                (Field::String { .. }, _) => violations.push(wrong_type("string")),
+                (Field::StringEnum { values, .. }, _) => {
+                    violations.push(wrong_type(&format!("{values:?}")))
+                }
+                (Field::StringArray { .. }, _) => violations.push(wrong_type("[string]")),
+                (Field::DateTime { .. }, _) => violations.push(wrong_type("datetime")),
+            }
+        }
+        let open = |gate: &str| obj.get(gate) == Some(&serde_json::Value::Bool(true));
+        if let serde_json::Value::Object(defaults) = policy_type.default_value() {
+            for name in defaults.keys() {
+                let expected = policy_type.gate_for(name).is_none_or(open);
+                if expected && !obj.contains_key(name) {
+                    violations.push(SchemaViolation::MissingField {
+                        field: name.clone(),
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    /// Remove `field` from the output, including its default.
+    ///
+    /// Used for the fields of a [`crate::FieldGroup`] whose gate is not set.
+    pub fn clear_field(&mut self, field: &str) {
+        for value in [self.value.as_mut(), self.default.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            if let serde_json::Value::Object(obj) = value {
+                obj.shift_remove(field);
+            }
+        }
+        self.sources.remove(field);
+    }
+
+    /// Record the priority of the policy behind rule `policy_index`.  Rules without one have
+    /// priority 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{OnConflict, Report};
+    /// let mut report = Report::default();
+    /// report.report_policy_priority(2, 10);
+    /// report.report_string(1, "queue", "support".to_string(), OnConflict::HighestPriority);
+    /// report.report_string(2, "queue", "billing".to_string(), OnConflict::HighestPriority);
+    /// assert_eq!(report.value()["queue"], "billing");
+    /// assert_eq!(report.sources["queue"], 2);
+    /// ```
+    pub fn report_policy_priority(&mut self, policy_index: usize, priority: i64) {
+        self.policy_priorities.insert(policy_index, priority);
+    }
+
+    /// Record that the policy behind rule `policy_index` was added at zero-based `position`.
+    /// Rules without one are ordered by rule number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{OnConflict, Report};
+    /// let mut report = Report::default();
+    /// report.report_policy_position(1, 1);
+    /// report.report_policy_position(2, 0);
+    /// report.report_string(1, "queue", "support".to_string(), OnConflict::FirstWins);
+    /// report.report_string(2, "queue", "billing".to_string(), OnConflict::FirstWins);
+    /// assert_eq!(report.value()["queue"], "billing");
+    /// ```
+    pub fn report_policy_position(&mut self, policy_index: usize, position: usize) {
+        self.policy_positions.insert(policy_index, position);
+    }
+
+    /// How rule `policy_index` ranks against the rule whose value `field` holds under
+    /// `on_conflict`, or `None` if no rule's value is held.  Greater means it takes the field.
+    ///
+    /// Rules are ranked by the order their policies were added under [`OnConflict::FirstWins`]
+    /// and [`OnConflict::LastWins`], and by priority otherwise.
+    fn precedence(
+        &self,
+        field: &str,
+        policy_index: usize,
+        on_conflict: OnConflict,
+    ) -> Option<Ordering> {
+        let source = *self.sources.get(field)?;
+        let added = |index| self.policy_positions.get(&index).copied().unwrap_or(index);
+        let priority = |index| self.policy_priorities.get(&index).copied().unwrap_or(0);
+        match on_conflict {
+            OnConflict::FirstWins => Some(added(source).cmp(&added(policy_index))),
+            OnConflict::LastWins => Some(added(policy_index).cmp(&added(source))),
+            _ => Some(priority(policy_index).cmp(&priority(source))),
+        }
+    }
+
+    fn field_value(&self, field: &str) -> Option<serde_json::Value> {
+        self.value.as_ref()?.get(field).cloned()
+    }
+
+    /// Credit rule `policy_index` with `field` if reporting its value changed the field from
+    /// `before`.
+    ///
+    /// Under the strategies that rank rules, a rule that agrees with the value also takes the
+    /// credit when it ranks at least as high as the source, so that the source is always the
+    /// highest-ranked rule backing the value.
+    fn report_source(
+        &mut self,
+        field: &str,
+        policy_index: usize,
+        before: Option<serde_json::Value>,
+        on_conflict: OnConflict,
+        precedence: Option<Ordering>,
+    ) {
+        let after = self.value.as_ref().and_then(|value| value.get(field));
+        let Some(after) = after.filter(|after| !after.is_null()) else {
+            return;
+        };
+        let changed = Some(after) != before.as_ref();
+        let ranked = matches!(
+            on_conflict,
+            OnConflict::FirstWins | OnConflict::LastWins | OnConflict::HighestPriority
+        );
+        let outranks = ranked && precedence.is_some_and(|precedence| precedence != Ordering::Less);
+        if changed || outranks {
+            self.sources.insert(field.to_string(), policy_index);
+        }
+    }
+
+    /// The rule that set `field`'s current value, and `policy_index`, which disagrees with it.
+    /// The first is zero if no rule is known to have set the value.
+    fn conflicting_rules(&self, field: &str, policy_index: usize) -> (usize, usize) {
+        let source = self.sources.get(field).copied().unwrap_or_default();
+        (source, policy_index)
+    }
+
     /// Record that a policy was matched.
     ///
     /// This is called internally when a mask is applied and matches the input data,
@@ -674,7 +2093,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_invariant_violation(file!(), line!(), "unexpected null value");
     /// ```
     pub fn report_invariant_violation(&mut self, file: &str, line: u32, message: &str) {
@@ -701,7 +2120,7 @@ impl Report {
     /// ```
     /// # use policyai::Report;
     /// # use claudius::MessageParam;
-    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![]);
+    /// let mut report = Report::new(vec![], vec![], vec![], vec![], vec![], vec![]);
     /// report.report_type_check_failure(file!(), line!(), "expected boolean, got string");
     /// ```
     pub fn report_type_check_failure(&mut self, file: &str, line: u32, message: &str) {
@@ -714,9 +2133,62 @@ impl Report {
         });
     }
 
-    fn report_bool_conflict(&mut self, field: &str, val1: bool, val2: bool) {
+    /// Report that an array field was cut down to `limit` of its `length` elements.
+    pub fn report_array_truncated(&mut self, field: &str, length: usize, limit: usize) {
+        self.errors.push(PolicyError::ArrayTruncated {
+            field: field.to_string(),
+            length,
+            limit,
+        });
+    }
+
+    fn report_resolution(
+        &mut self,
+        field: &str,
+        kind: ConflictKind,
+        on_conflict: OnConflict,
+        outcome: Option<ResolutionOutcome>,
+    ) {
+        let Some(outcome) = outcome else {
+            return;
+        };
+        if on_conflict == OnConflict::Unknown
+            && !self
+                .errors
+                .iter()
+                .any(|err| matches!(err, PolicyError::UnknownOnConflict { field: f } if f == field))
+        {
+            self.errors.push(PolicyError::UnknownOnConflict {
+                field: field.to_string(),
+            });
+        }
+        self.resolutions.push(Resolution {
+            field: field.to_string(),
+            kind,
+            on_conflict,
+            outcome,
+        });
+    }
+
+    /// Report a conflict between two boolean values for the same field.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The name of the field experiencing the conflict
+    /// * `rules` - The rule numbers of the policies that set `val1` and `val2`
+    /// * `val1` - The first conflicting boolean value
+    /// * `val2` - The second conflicting boolean value
+    pub fn report_bool_conflict(
+        &mut self,
+        field: &str,
+        rules: (usize, usize),
+        val1: bool,
+        val2: bool,
+    ) {
         self.conflicts.push(Conflict::BoolConflict {
             field: field.to_string(),
+            rule1: rules.0,
+            rule2: rules.1,
             val1,
             val2,
         });
@@ -730,16 +2202,20 @@ impl Report {
     /// # Arguments
     ///
     /// * `field` - The name of the field experiencing the conflict
+    /// * `rules` - The rule numbers of the policies that set `val1` and `val2`
     /// * `val1` - The first conflicting numeric value
     /// * `val2` - The second conflicting numeric value
     pub fn report_number_conflict(
         &mut self,
         field: &str,
+        rules: (usize, usize),
         val1: serde_json::Number,
         val2: serde_json::Number,
     ) {
         self.conflicts.push(Conflict::NumberConflict {
             field: field.to_string(),
+            rule1: rules.0,
+            rule2: rules.1,
             val1,
             val2,
         });
@@ -753,11 +2229,20 @@ impl Report {
     /// # Arguments
     ///
     /// * `field` - The name of the field experiencing the conflict
+    /// * `rules` - The rule numbers of the policies that set `val1` and `val2`
     /// * `val1` - The first conflicting string value
     /// * `val2` - The second conflicting string value
-    pub fn report_string_conflict(&mut self, field: &str, val1: String, val2: String) {
+    pub fn report_string_conflict(
+        &mut self,
+        field: &str,
+        rules: (usize, usize),
+        val1: String,
+        val2: String,
+    ) {
         self.conflicts.push(Conflict::StringConflict {
             field: field.to_string(),
+            rule1: rules.0,
+            rule2: rules.1,
             val1,
             val2,
         });
@@ -772,36 +2257,698 @@ impl Report {
     /// # Arguments
     ///
     /// * `field` - The name of the field experiencing the conflict
+    /// * `rules` - The rule numbers of the policies that set `val1` and `val2`
     /// * `val1` - The existing string value from the report
     /// * `val2` - The expected enum string value
-    pub fn report_string_enum_conflict(&mut self, field: &str, val1: String, val2: String) {
+    pub fn report_string_enum_conflict(
+        &mut self,
+        field: &str,
+        rules: (usize, usize),
+        val1: String,
+        val2: String,
+    ) {
         self.conflicts.push(Conflict::StringConflict {
             field: field.to_string(),
+            rule1: rules.0,
+            rule2: rules.1,
             val1,
             val2,
         });
     }
 }
 
-impl std::fmt::Display for Report {
+/// Counts that describe a report, from [`Report::summary`].
+///
+/// Displays as `key=value` pairs on one line.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ReportSummary {
+    /// Fields a matched rule set.
+    pub fields_set: usize,
+    /// Fields left at their default.
+    pub fields_defaulted: usize,
+    /// Distinct rules that matched.
+    pub rules_matched: usize,
+    /// Conflicts between policies that could not be resolved.
+    pub conflicts: usize,
+    /// Errors recorded while building the report.
+    pub errors: usize,
+    /// Requests made after the first.
+    pub retries: usize,
+    /// Tokens consumed by every request.
+    pub tokens: u64,
+}
+
+impl std::fmt::Display for ReportSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        writeln!(
+        write!(
             f,
-            "ir: {}",
-            serde_json::to_string_pretty(self.ir.as_ref().unwrap_or(&serde_json::json! {{}}))
-                .unwrap()
-        )?;
-        writeln!(
-            f,
-            "so: {}",
-            serde_json::to_string_pretty(self.value.as_ref().unwrap_or(&serde_json::json! {{}}))
-                .unwrap()
+            "fields_set={} fields_defaulted={} rules_matched={} conflicts={} errors={} retries={} tokens={}",
+            self.fields_set,
+            self.fields_defaulted,
+            self.rules_matched,
+            self.conflicts,
+            self.errors,
+            self.retries,
+            self.tokens
         )
     }
 }
 
+/// The bytes a report takes as JSON, from [`Report::size`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ReportSize {
+    /// The whole report.
+    pub total: usize,
+    /// The messages, which [`Report::strip_messages`] drops.
+    pub messages: usize,
+    /// The IR received from the LLM.
+    pub ir: usize,
+}
+
+/// The length of `value` as JSON.
+fn json_len(value: &impl serde::Serialize) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self::new(vec![], vec![], vec![], vec![], vec![], vec![])
+    }
+}
+
+/// How [`Report::render`] formats a report.
+///
+/// The default matches the report's `Display`: the IR and the extracted values, pretty-printed
+/// and unredacted.  [`RenderOptions::production`] is meant for logs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RenderOptions {
+    /// Print each value on one line instead of pretty-printing it.
+    pub compact: bool,
+    /// Replace every string that came from the text with `"[redacted]"`.  Enum values, which
+    /// come from the policy type, are kept.
+    pub redact: bool,
+    /// Print the IR the LLM output.
+    pub include_ir: bool,
+}
+
+impl RenderOptions {
+    /// Compact, redacted, and without the IR.
+    pub fn production() -> Self {
+        Self {
+            compact: true,
+            redact: true,
+            include_ir: false,
+        }
+    }
+
+    /// Print each value on one line.
+    pub fn with_compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Redact the strings that came from the text.
+    pub fn with_redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// Print the IR the LLM output.
+    pub fn with_include_ir(mut self, include_ir: bool) -> Self {
+        self.include_ir = include_ir;
+        self
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            compact: false,
+            redact: false,
+            include_ir: true,
+        }
+    }
+}
+
+/// A report formatted according to [`RenderOptions`], from [`Report::render`].
+pub struct RenderedReport<'a> {
+    report: &'a Report,
+    options: RenderOptions,
+}
+
+impl RenderedReport<'_> {
+    fn write_value(
+        &self,
+        f: &mut std::fmt::Formatter,
+        label: &str,
+        value: &serde_json::Value,
+    ) -> std::fmt::Result {
+        let value = if self.options.redact {
+            let enums = self
+                .report
+                .string_enum_masks
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>();
+            redact(value, &enums)
+        } else {
+            value.clone()
+        };
+        let value = if self.options.compact {
+            serde_json::to_string(&value)
+        } else {
+            serde_json::to_string_pretty(&value)
+        };
+        writeln!(f, "{label}: {}", value.map_err(|_| std::fmt::Error)?)
+    }
+}
+
+impl std::fmt::Display for RenderedReport<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let empty = serde_json::json! {{}};
+        if self.options.include_ir {
+            self.write_value(f, "ir", self.report.ir.as_ref().unwrap_or(&empty))?;
+        }
+        self.write_value(f, "so", self.report.value.as_ref().unwrap_or(&empty))
+    }
+}
+
+/// `value` with every string replaced by `"[redacted]"`, except the top-level fields named in
+/// `keep`.
+fn redact(value: &serde_json::Value, keep: &[&str]) -> serde_json::Value {
+    fn redact_all(value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::String(_) => "[redacted]".into(),
+            serde_json::Value::Array(values) => values.iter().map(redact_all).collect(),
+            serde_json::Value::Object(obj) => obj
+                .iter()
+                .map(|(k, v)| (k.clone(), redact_all(v)))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+            _ => value.clone(),
+        }
+    }
+    match value {
+        serde_json::Value::Object(obj) => obj
+            .iter()
+            .map(|(k, v)| {
+                let v = if keep.contains(&k.as_str()) {
+                    v.clone()
+                } else {
+                    redact_all(v)
+                };
+                (k.clone(), v)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        _ => redact_all(value),
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.render(RenderOptions::default()).fmt(f)
+    }
+}
+
 impl std::fmt::Debug for Report {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         f.debug_struct("Report").finish_non_exhaustive()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{t64, OnNull};
+
+    #[test]
+    fn render_redacts_text_but_keeps_enum_values() {
+        let mut report = Report::from_value(
+            serde_json::json!({"priority": "high", "tags": ["alice"], "score": 3}),
+            serde_json::json!({"priority": "low"}),
+        );
+        report.ir = Some(serde_json::json!({"mask": "alice@example.com"}));
+        report.string_enum_masks.push(StringEnumMask::new(
+            1,
+            "priority".to_string(),
+            "mask".to_string(),
+            Some("high".to_string()),
+            Some("low".to_string()),
+            OnConflict::Default,
+        ));
+        let options = RenderOptions::default()
+            .with_compact(true)
+            .with_redact(true);
+        assert_eq!(
+            report.render(options).to_string(),
+            "ir: {\"mask\":\"[redacted]\"}\nso: {\"priority\":\"high\",\"tags\":[\"[redacted]\"],\"score\":3}\n"
+        );
+        assert_eq!(
+            report.to_string(),
+            report.render(RenderOptions::default()).to_string()
+        );
+    }
+
+    #[test]
+    fn largest_number_wins_in_either_order_and_exactly() {
+        let big = (1u64 << 53) + 1;
+        for values in [[big, big - 1], [big - 1, big]] {
+            let mut report = Report::default();
+            for (index, value) in values.into_iter().enumerate() {
+                report.report_number(index + 1, "score", value, OnConflict::LargestValue);
+            }
+            assert!(report.conflicts().is_empty());
+            assert_eq!(report.value()["score"], big);
+        }
+        let mut report = Report::default();
+        report.report_number(1, "score", -1, OnConflict::LargestValue);
+        report.report_number(2, "score", u64::MAX, OnConflict::LargestValue);
+        assert_eq!(report.value()["score"], u64::MAX);
+    }
+
+    #[test]
+    fn datetimes_compare_as_instants() {
+        let morning = "2024-03-01T09:00:00-08:00".to_string();
+        let noon_utc = "2024-03-01T12:00:00Z".to_string();
+        for (on_conflict, kept, outcome) in [
+            (
+                OnConflict::LargestValue,
+                &morning,
+                ResolutionOutcome::Largest,
+            ),
+            (
+                OnConflict::SmallestValue,
+                &noon_utc,
+                ResolutionOutcome::Smallest,
+            ),
+        ] {
+            for values in [[&morning, &noon_utc], [&noon_utc, &morning]] {
+                let mut report = Report::default();
+                for (index, value) in values.into_iter().enumerate() {
+                    report.report_datetime(index + 1, "due", value.clone(), on_conflict);
+                }
+                assert_eq!(report.value()["due"], *kept);
+                assert!(report.conflicts().is_empty());
+                assert_eq!(report.resolutions()[0].outcome, outcome);
+                assert_eq!(report.resolutions()[0].kind, ConflictKind::DateTime);
+            }
+        }
+
+        // The same instant in two offsets agrees; different instants do not.
+        let mut report = Report::default();
+        report.report_datetime(1, "due", noon_utc.clone(), OnConflict::Agreement);
+        report.report_datetime(
+            2,
+            "due",
+            "2024-03-01T07:00:00-05:00".to_string(),
+            OnConflict::Agreement,
+        );
+        assert!(report.conflicts().is_empty());
+        report.report_datetime(3, "due", morning.clone(), OnConflict::Agreement);
+        assert_eq!(report.conflicts().len(), 1);
+        assert_eq!(report.value()["due"], noon_utc);
+
+        let mut report = Report::default();
+        report.report_datetime(1, "due", "tomorrow".to_string(), OnConflict::Agreement);
+        assert!(matches!(
+            report.errors(),
+            [PolicyError::InvalidDateTime { field, value }] if field == "due" && value == "tomorrow"
+        ));
+        assert_eq!(report.value(), serde_json::json!({}));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn tool_result_names_the_rules_of_each_conflict() {
+        let mut report = Report::default();
+        report.report_bool(2, "urgent", true, OnConflict::Agreement);
+        report.report_bool(5, "urgent", false, OnConflict::Agreement);
+        report.report_string(1, "owner", "ana".to_string(), OnConflict::Agreement);
+        report.report_string(3, "owner", "bo".to_string(), OnConflict::Agreement);
+        let rendered = report
+            .conflicts()
+            .iter()
+            .map(|conflict| conflict.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rendered,
+            [
+                "urgent: rule 2 set true, rule 5 set false",
+                "owner: rule 1 set \"ana\", rule 3 set \"bo\"",
+            ]
+        );
+        let result = serde_json::to_value(report.to_tool_result("toolu_1")).unwrap();
+        let text = result["content"][1]["text"].as_str().unwrap();
+        assert!(text.contains("<conflict>urgent: rule 2 set true, rule 5 set false</conflict>"));
+    }
+
+    #[test]
+    fn smallest_values_win() {
+        let mut report = Report::default();
+        report.report_bool(1, "urgent", true, OnConflict::SmallestValue);
+        report.report_bool(2, "urgent", false, OnConflict::SmallestValue);
+        report.report_number(1, "score", 9, OnConflict::SmallestValue);
+        report.report_number(2, "score", -3, OnConflict::SmallestValue);
+        report.report_number(3, "score", 4, OnConflict::SmallestValue);
+        report.report_string(
+            1,
+            "owner",
+            "bartholomew".to_string(),
+            OnConflict::SmallestValue,
+        );
+        report.report_string(2, "owner", "ana".to_string(), OnConflict::SmallestValue);
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"urgent": false, "score": -3, "owner": "ana"})
+        );
+        assert!(report.conflicts().is_empty());
+        assert!(report
+            .resolutions()
+            .iter()
+            .all(|r| r.outcome == ResolutionOutcome::Smallest));
+    }
+
+    #[test]
+    fn null_handling_covers_every_mask_type() {
+        type Apply = Box<dyn Fn(OnNull, &serde_json::Value, &mut Report)>;
+        let name = |n: &str| n.to_string();
+        let masks: Vec<(&str, Option<serde_json::Value>, Apply)> = vec![
+            (
+                "urgent",
+                Some(false.into()),
+                Box::new(move |on_null, ir, report| {
+                    let mask = BoolMask::new(
+                        1,
+                        name("urgent"),
+                        name("m"),
+                        Some(false),
+                        OnConflict::Default,
+                    );
+                    mask.with_on_null(on_null).apply_to(ir, report);
+                }),
+            ),
+            (
+                "score",
+                Some(0.0.into()),
+                Box::new(move |on_null, ir, report| {
+                    let mask = NumberMask::new(
+                        1,
+                        name("score"),
+                        name("m"),
+                        Some(t64(0.0)),
+                        None,
+                        OnConflict::Default,
+                    );
+                    mask.with_on_null(on_null).apply_to(ir, report);
+                }),
+            ),
+            (
+                "title",
+                Some("none".into()),
+                Box::new(move |on_null, ir, report| {
+                    let mask = StringMask::new(
+                        1,
+                        name("title"),
+                        name("m"),
+                        Some(name("none")),
+                        None,
+                        OnConflict::Default,
+                    );
+                    mask.with_on_null(on_null).apply_to(ir, report);
+                }),
+            ),
+            (
+                "tags",
+                None,
+                Box::new(move |on_null, ir, report| {
+                    let mask = StringArrayMask::new(1, name("tags"), name("m"), vec![]);
+                    mask.with_on_null(on_null).apply_to(ir, report);
+                }),
+            ),
+            (
+                "priority",
+                Some("low".into()),
+                Box::new(move |on_null, ir, report| {
+                    let mask = StringEnumMask::new(
+                        1,
+                        name("priority"),
+                        name("m"),
+                        Some(name("high")),
+                        Some(name("low")),
+                        OnConflict::Default,
+                    );
+                    mask.with_on_null(on_null).apply_to(ir, report);
+                }),
+            ),
+            (
+                "due",
+                Some("2024-03-01T00:00:00Z".into()),
+                Box::new(move |on_null, ir, report| {
+                    let mask = DateTimeMask::new(
+                        1,
+                        name("due"),
+                        name("m"),
+                        Some(name("2024-03-01T00:00:00Z")),
+                        None,
+                        OnConflict::Default,
+                    );
+                    mask.with_on_null(on_null).apply_to(ir, report);
+                }),
+            ),
+        ];
+        let ir = serde_json::json!({"m": null});
+        for (field, default, apply) in masks.iter() {
+            let mut report = Report::default();
+            apply(OnNull::Error, &ir, &mut report);
+            assert_eq!(report.errors().len(), 1, "{field}");
+            assert!(report.rules_matched.is_empty(), "{field}");
+
+            let mut report = Report::default();
+            apply(OnNull::Absent, &ir, &mut report);
+            assert!(report.errors().is_empty(), "{field}");
+            assert!(report.rules_matched.is_empty(), "{field}");
+            assert_eq!(report.value().get(field), default.as_ref(), "{field}");
+
+            let mut report = Report::default();
+            apply(OnNull::Unset, &ir, &mut report);
+            assert!(report.errors().is_empty(), "{field}");
+            assert_eq!(report.rules_matched, vec![1], "{field}");
+            assert_eq!(report.value().get(field), None, "{field}");
+        }
+    }
+
+    #[test]
+    fn sticky_values_stay_set() {
+        for values in [[false, true], [true, false]] {
+            let mut report = Report::default();
+            for (index, value) in values.into_iter().enumerate() {
+                report.report_bool(index + 1, "escalated", value, OnConflict::Sticky);
+            }
+            assert_eq!(report.value()["escalated"], true);
+            assert!(report.conflicts().is_empty());
+        }
+        let mut report = Report::default();
+        report.report_number(1, "score", 5, OnConflict::Sticky);
+        report.report_number(2, "score", 9, OnConflict::Sticky);
+        report.report_string(1, "owner", "ana".to_string(), OnConflict::Sticky);
+        report.report_string(2, "owner", "bartholomew".to_string(), OnConflict::Sticky);
+        assert_eq!(report.value()["score"], 5);
+        assert_eq!(report.value()["owner"], "ana");
+        assert!(report.conflicts().is_empty());
+        assert!(report
+            .resolutions()
+            .iter()
+            .all(|r| r.on_conflict == OnConflict::Sticky && r.outcome == ResolutionOutcome::Stuck));
+        assert_eq!(report.resolutions().len(), 2);
+    }
+
+    #[test]
+    fn first_and_last_added_win_whatever_their_values() {
+        let allowed = ["low".to_string(), "high".to_string()];
+        for (on_conflict, outcome, expected) in [
+            (
+                OnConflict::FirstWins,
+                ResolutionOutcome::First,
+                serde_json::json!({
+                    "urgent": true,
+                    "score": 9,
+                    "owner": "bartholomew",
+                    "priority": "high",
+                    "due": "2024-03-08T00:00:00Z",
+                }),
+            ),
+            (
+                OnConflict::LastWins,
+                ResolutionOutcome::Last,
+                serde_json::json!({
+                    "urgent": false,
+                    "score": 4,
+                    "owner": "ana",
+                    "priority": "low",
+                    "due": "2024-03-01T00:00:00Z",
+                }),
+            ),
+        ] {
+            let mut report = Report::default();
+            report.report_bool(1, "urgent", true, on_conflict);
+            report.report_bool(2, "urgent", false, on_conflict);
+            report.report_number(1, "score", 9, on_conflict);
+            report.report_number(2, "score", -3, on_conflict);
+            report.report_number(3, "score", 4, on_conflict);
+            report.report_string(1, "owner", "bartholomew".to_string(), on_conflict);
+            report.report_string(2, "owner", "ana".to_string(), on_conflict);
+            report.report_string_enum(1, "priority", "high".to_string(), &allowed, on_conflict);
+            report.report_string_enum(2, "priority", "low".to_string(), &allowed, on_conflict);
+            report.report_datetime(1, "due", "2024-03-08T00:00:00Z".to_string(), on_conflict);
+            report.report_datetime(2, "due", "2024-03-01T00:00:00Z".to_string(), on_conflict);
+            assert_eq!(report.value(), expected, "{on_conflict:?}");
+            assert!(report.conflicts().is_empty());
+            assert_eq!(report.resolutions().len(), 6);
+            assert!(report
+                .resolutions()
+                .iter()
+                .all(|r| r.on_conflict == on_conflict && r.outcome == outcome));
+        }
+    }
+
+    #[test]
+    fn highest_priority_wins_and_ties_conflict() {
+        let allowed = ["low".to_string(), "high".to_string()];
+        let mut report = Report::default();
+        report.report_policy_priority(1, 5);
+        report.report_policy_priority(3, 5);
+        let on_conflict = OnConflict::HighestPriority;
+        // Rule 2 has the default priority of 0, so rule 1 keeps every field.
+        report.report_bool(1, "urgent", true, on_conflict);
+        report.report_bool(2, "urgent", false, on_conflict);
+        report.report_number(2, "score", 1, on_conflict);
+        report.report_number(1, "score", 9, on_conflict);
+        report.report_string_enum(1, "priority", "high".to_string(), &allowed, on_conflict);
+        report.report_string_enum(2, "priority", "low".to_string(), &allowed, on_conflict);
+        report.report_datetime(2, "due", "2024-03-01T00:00:00Z".to_string(), on_conflict);
+        report.report_datetime(1, "due", "2024-03-08T00:00:00Z".to_string(), on_conflict);
+        assert_eq!(
+            report.value(),
+            serde_json::json!({
+                "urgent": true,
+                "score": 9,
+                "priority": "high",
+                "due": "2024-03-08T00:00:00Z",
+            })
+        );
+        assert!(report.sources.values().all(|source| *source == 1));
+        assert!(report.conflicts().is_empty());
+        assert!(report
+            .resolutions()
+            .iter()
+            .all(|r| r.outcome == ResolutionOutcome::Prioritized));
+
+        // Rules 1 and 3 share a priority, so their disagreement is a conflict.
+        report.report_string(1, "owner", "ana".to_string(), on_conflict);
+        report.report_string(3, "owner", "bartholomew".to_string(), on_conflict);
+        assert_eq!(report.value()["owner"], "ana");
+        assert_eq!(report.conflicts().len(), 1);
+        report.clear_field("owner");
+        assert!(!report.sources.contains_key("owner"));
+    }
+
+    #[test]
+    fn highest_priority_credits_the_highest_rule_that_agrees() {
+        let mut report = Report::default();
+        report.report_policy_priority(2, 10);
+        report.report_policy_priority(3, 5);
+        let on_conflict = OnConflict::HighestPriority;
+        report.report_bool(1, "urgent", true, on_conflict);
+        report.report_bool(2, "urgent", true, on_conflict);
+        report.report_bool(3, "urgent", false, on_conflict);
+        assert_eq!(report.value()["urgent"], true);
+        assert_eq!(report.sources["urgent"], 2);
+        assert!(report.conflicts().is_empty());
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn validate_against_flags_every_kind_of_violation() {
+        let policy_type = PolicyType::parse(
+            r#"type T {
+                meeting: bool = false,
+                score: number = 0,
+                tags: [string],
+                when meeting { place: string = "office" },
+            }"#,
+        )
+        .unwrap();
+        let conforming = Report::from_value(
+            serde_json::json!({"meeting": true, "place": "home", "tags": ["a"]}),
+            serde_json::json!({"meeting": false, "score": 0, "place": "office"}),
+        );
+        assert_eq!(conforming.validate_against(&policy_type), vec![]);
+
+        let report = Report::from_value(
+            serde_json::json!({"meeting": true, "score": "7", "tags": ["a", 1], "extra": 1}),
+            serde_json::json!({}),
+        );
+        assert_eq!(
+            report.validate_against(&policy_type),
+            vec![
+                SchemaViolation::WrongType {
+                    field: "score".to_string(),
+                    expected: "number".to_string(),
+                    actual: serde_json::json!("7"),
+                },
+                SchemaViolation::WrongType {
+                    field: "tags".to_string(),
+                    expected: "[string]".to_string(),
+                    actual: serde_json::json!(["a", 1]),
+                },
+                SchemaViolation::UnknownField {
+                    field: "extra".to_string(),
+                },
+                SchemaViolation::MissingField {
+                    field: "place".to_string(),
+                },
+            ]
+        );
+
+        let closed = Report::from_value(serde_json::json!({}), serde_json::json!({"score": 0}));
+        assert_eq!(
+            closed.validate_against(&policy_type),
+            vec![SchemaViolation::MissingField {
+                field: "meeting".to_string(),
+            }]
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn stripped_report_compresses_and_round_trips() {
+        let mut report = Report::from_value(
+            serde_json::json!({"urgent": true, "tags": ["a", "b"]}),
+            serde_json::json!({"urgent": false}),
+        )
+        .with_conflict(Conflict::BoolConflict {
+            field: "urgent".to_string(),
+            rule1: 1,
+            rule2: 2,
+            val1: true,
+            val2: false,
+        });
+        report = report.with_messages(vec![MessageParam::new_with_string(
+            "the same transcript again and again ".repeat(200),
+            claudius::MessageRole::User,
+        )]);
+        let bytes = report.to_bytes().unwrap();
+        assert!(bytes.len() < report.size().total / 10);
+        let restored = Report::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.value(), report.value());
+        assert_eq!(restored.conflicts().len(), 1);
+        assert_eq!(restored.messages().len(), 1);
+
+        report.strip_messages();
+        let restored = Report::from_bytes(&report.to_bytes().unwrap()).unwrap();
+        assert!(restored.messages().is_empty());
+        assert_eq!(restored.value(), report.value());
+        assert!(Report::from_bytes(b"not zstd").is_err());
+    }
+}
@@ -1,11 +1,60 @@
-use claudius::{push_or_merge_message, JsonSchema, MessageParam, MessageRole};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "client")]
+use claudius::{push_or_merge_message, MessageParam, MessageRole};
 use uuid::Uuid;
 
 use crate::{
-    ApplyError, BoolMask, Field, NumberMask, Policy, PolicyError, Report, StringArrayMask,
-    StringEnumMask, StringMask,
+    ApplyError, BoolMask, DateTimeMask, EnumFallback, Field, FieldGroup, FieldOrder, MatchMask,
+    NamingPolicy, NumberMask, OnConflict, OnNull, Policy, PolicyError, Report, RuleIndex,
+    SkipReason, SkippedPolicy, StringArrayMask, StringEnumMask, StringMask, Translations,
 };
 
+/// A rule sent to the LLM, and how much it adds to a request in bytes.
+#[cfg(feature = "client")]
+#[derive(Clone, Debug)]
+pub(crate) struct SentRule {
+    pub(crate) rule: RuleIndex,
+    /// The prompt of the policy the rule came from, before its fields were masked.
+    pub(crate) prompt: String,
+    /// The `<rule>` text.
+    pub(crate) prompt_bytes: usize,
+    /// The properties the rule adds to the output schema.
+    pub(crate) schema_bytes: usize,
+}
+
+/// The JSON schema of a value of JSON type `ty`.
+fn scalar_schema(ty: &str) -> serde_json::Value {
+    serde_json::json! {{ "type": ty }}
+}
+
+/// The JSON schema of an array whose elements are of JSON type `ty`.
+fn array_schema(ty: &str) -> serde_json::Value {
+    serde_json::json! {{ "type": "array", "items": scalar_schema(ty) }}
+}
+
+/// The operand of an action value of the form `{"$op": operand}`, the only form of operator an
+/// action may use in place of a literal value.
+fn operand<'a>(value: &'a serde_json::Value, op: &str) -> Option<&'a serde_json::Value> {
+    match value {
+        serde_json::Value::Object(obj) if obj.len() == 1 => obj.get(op),
+        _ => None,
+    }
+}
+
+/// What the LLM outputs for an action value when its rule matches: the value itself, the
+/// amount of an `$add`, or the elements of an `$append`.
+pub(crate) fn action_output(value: &serde_json::Value) -> serde_json::Value {
+    if let Some(delta) = operand(value, "$add") {
+        return delta.clone();
+    }
+    match operand(value, "$append") {
+        Some(serde_json::Value::String(v)) => serde_json::json!([v]),
+        Some(v) => v.clone(),
+        None => value.clone(),
+    }
+}
+
 /// Builder for constructing Reports from policy definitions.
 ///
 /// A ReportBuilder accumulates policy configurations and creates the necessary
@@ -20,15 +69,239 @@ pub struct ReportBuilder {
     string_masks: Vec<StringMask>,
     string_array_masks: Vec<StringArrayMask>,
     string_enum_masks: Vec<StringEnumMask>,
+    datetime_masks: Vec<DateTimeMask>,
+    match_masks: Vec<MatchMask>,
     masks_by_index: Vec<Vec<String>>,
     default_return: serde_json::Value,
+    #[cfg(feature = "client")]
     messages: Vec<MessageParam>,
-    policy_index: usize,
+    #[cfg(feature = "client")]
+    sent_rules: Vec<SentRule>,
+    policy_index: RuleIndex,
     required: Vec<String>,
     properties: serde_json::Value,
+    field_order: FieldOrder,
+    naming: NamingPolicy,
+    translations: Translations,
+    max_array_len: usize,
+    declared_fields: Vec<String>,
+    groups: Vec<FieldGroup>,
+    on_conflict_overrides: BTreeMap<String, OnConflict>,
+    enum_fallbacks: BTreeMap<String, Vec<EnumFallback>>,
+    null_handling: BTreeMap<String, OnNull>,
+    pruned_policies: Vec<usize>,
+    skipped_policies: Vec<SkippedPolicy>,
+    rule_policies: Option<Vec<usize>>,
+    local_rules: Vec<(RuleIndex, serde_json::Map<String, serde_json::Value>)>,
+    local_matches: Vec<RuleIndex>,
+    unmasked_fields: BTreeSet<String>,
+    input_hash: Option<String>,
+    policy_set_hash: Option<String>,
+    policy_priorities: BTreeMap<usize, i64>,
 }
 
 impl ReportBuilder {
+    /// Set the key order of the resulting report's output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{FieldOrder, ReportBuilder};
+    /// let builder = ReportBuilder::default().with_field_order(FieldOrder::Sorted);
+    /// ```
+    pub fn with_field_order(mut self, field_order: FieldOrder) -> Self {
+        self.field_order = field_order;
+        self
+    }
+
+    /// Set the key casing of the resulting report's output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{NamingPolicy, ReportBuilder};
+    /// let builder = ReportBuilder::default().with_naming_policy(NamingPolicy::SnakeCase);
+    /// ```
+    pub fn with_naming_policy(mut self, naming: NamingPolicy) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Set the value translations of the resulting report.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{ReportBuilder, Translations};
+    /// let translations = Translations::default().with_translation("priority", "high", 3);
+    /// let builder = ReportBuilder::default().with_translations(translations);
+    /// ```
+    pub fn with_translations(mut self, translations: Translations) -> Self {
+        self.translations = translations;
+        self
+    }
+
+    /// Keep at most `max_array_len` strings per rule in array fields.  See
+    /// [`StringArrayMask::with_max_len`].
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.max_array_len = max_array_len;
+        self
+    }
+
+    /// Resolve conflicts on the named fields with the given strategies instead of the ones
+    /// their type declares.  Applies to policies added after this call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use policyai::{OnConflict, ReportBuilder};
+    /// let overrides = BTreeMap::from([("priority".to_string(), OnConflict::Agreement)]);
+    /// let builder = ReportBuilder::default().with_on_conflict_overrides(overrides);
+    /// ```
+    pub fn with_on_conflict_overrides(mut self, overrides: BTreeMap<String, OnConflict>) -> Self {
+        self.on_conflict_overrides = overrides;
+        self
+    }
+
+    /// Settle disagreements on the named enum fields under [`OnConflict::Agreement`] with the
+    /// given fallback orders.  Applies to policies added after this call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use policyai::{EnumFallback, ReportBuilder};
+    /// let fallbacks = BTreeMap::from([(
+    ///     "priority".to_string(),
+    ///     vec![EnumFallback::Default, EnumFallback::LastDeclared],
+    /// )]);
+    /// let builder = ReportBuilder::default().with_enum_fallbacks(fallbacks);
+    /// ```
+    pub fn with_enum_fallbacks(mut self, fallbacks: BTreeMap<String, Vec<EnumFallback>>) -> Self {
+        self.enum_fallbacks = fallbacks;
+        self
+    }
+
+    /// Read an explicit `null` for each named field as the given [`OnNull`] says; other fields
+    /// treat it as an error.  Applies to policies added after this call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use policyai::{OnNull, ReportBuilder};
+    /// let handling = BTreeMap::from([("priority".to_string(), OnNull::Absent)]);
+    /// let builder = ReportBuilder::default().with_null_handling(handling);
+    /// ```
+    pub fn with_null_handling(mut self, handling: BTreeMap<String, OnNull>) -> Self {
+        self.null_handling = handling;
+        self
+    }
+
+    /// Ask the LLM to report how confident it is in its output, as a number in `[0, 1]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::ReportBuilder;
+    /// let builder = ReportBuilder::default().with_confidence();
+    /// assert!(builder.schema()["properties"]["__confidence__"].is_object());
+    /// ```
+    pub fn with_confidence(mut self) -> Self {
+        let field = crate::verification::CONFIDENCE_FIELD;
+        if !self.required.iter().any(|r| r == field) {
+            self.required.push(field.to_string());
+        }
+        self.properties[field] = serde_json::json!({
+            "type": "number",
+            "minimum": 0,
+            "maximum": 1,
+            "description": "How confident you are that this output is correct",
+        });
+        self
+    }
+
+    /// Ask the LLM to say, for every rule that does not match, why not.
+    ///
+    /// Meant for policy authors debugging a rule that should have fired on a sample; the
+    /// reasons cost output tokens on every request.  Read them back with
+    /// [`Report::non_match_reasons`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::ReportBuilder;
+    /// let builder = ReportBuilder::default().with_non_match_reasons();
+    /// assert!(builder.schema()["properties"]["__non_match_reasons__"].is_object());
+    /// ```
+    pub fn with_non_match_reasons(mut self) -> Self {
+        let field = crate::report::NON_MATCH_REASONS_FIELD;
+        if !self.required.iter().any(|r| r == field) {
+            self.required.push(field.to_string());
+        }
+        self.properties[field] = serde_json::json!({
+            "type": "object",
+            "additionalProperties": {"type": "string"},
+            "description": "For each rule number not in __rule_numbers__, one sentence on why the rule does not match",
+        });
+        self
+    }
+
+    /// Show the LLM the real names of `fields` instead of masking them.  Applies to policies
+    /// added after this call.
+    ///
+    /// Each rule still outputs its own key, named for the field and the rule number, so that
+    /// rules that disagree can be told apart.  Masking keeps the LLM from answering from the
+    /// field name rather than the rule; for names like "priority" that carry the meaning of
+    /// the field, the LLM may do better seeing them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Policy, PolicyType, ReportBuilder};
+    /// let policy_type = PolicyType::parse("type T { priority: string = \"low\" }").unwrap();
+    /// let policy = Policy {
+    ///     r#type: policy_type,
+    ///     prompt: "If the email is from the CEO".to_string(),
+    ///     action: serde_json::json!({"priority": "high"}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// let mut builder = ReportBuilder::default().with_unmasked_fields(["priority"]);
+    /// builder.add_policy(&policy).unwrap();
+    /// assert!(builder.schema()["properties"]["priority@1"].is_object());
+    /// ```
+    pub fn with_unmasked_fields(
+        mut self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.unmasked_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The key under which the current rule outputs `field`.
+    fn mask_for(&self, field: &str) -> String {
+        if self.unmasked_fields.contains(field) {
+            format!("{field}@{}", self.policy_index.number())
+        } else {
+            Uuid::new_v4().to_string()
+        }
+    }
+
+    fn on_null_for(&self, field: &str) -> OnNull {
+        self.null_handling.get(field).copied().unwrap_or_default()
+    }
+
+    fn on_conflict_for(&self, field: &str, declared: OnConflict) -> OnConflict {
+        self.on_conflict_overrides
+            .get(field)
+            .copied()
+            .unwrap_or(declared)
+    }
+
     /// Add a policy to this report builder.
     ///
     /// Processes the policy definition and creates the necessary masks for each field
@@ -61,12 +334,90 @@ impl ReportBuilder {
     /// #     r#type: policy_type,
     /// #     prompt: "test".to_string(),
     /// #     action: serde_json::json!({"active": true}),
+    /// #     precondition: None,
+    /// #     exact_match: None,
+    /// #     explanation: None,
+    /// #     priority: None,
     /// # };
     /// builder.add_policy(&policy)?;
     /// # Ok::<(), policyai::PolicyError>(())
     /// ```
     #[allow(clippy::result_large_err)]
     pub fn add_policy(&mut self, policy: &Policy) -> Result<(), PolicyError> {
+        self.add_rule(policy, false)
+    }
+
+    /// Add a policy that is decided locally instead of by the LLM.
+    ///
+    /// The policy becomes a rule with masks like any other, so its output takes part in
+    /// conflict resolution, but it is left out of the messages and the schema.  Mark the rules
+    /// that matched with [`ReportBuilder::with_local_matches`]; [`ReportBuilder::consume_ir`]
+    /// then merges their actions into the IR as if the LLM had output them.
+    ///
+    /// # Errors
+    ///
+    /// As [`ReportBuilder::add_policy`], and also returns [`PolicyError::LocalValueMissing`] if
+    /// the action leaves a value for the LLM to fill in.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Policy, PolicyType, ReportBuilder, RuleIndex};
+    /// let policy = Policy {
+    ///     r#type: PolicyType::parse("type T { internal: bool = false }").unwrap(),
+    ///     prompt: "Mail from example.com".to_string(),
+    ///     action: serde_json::json!({"internal": true}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// let mut builder = ReportBuilder::default();
+    /// builder.add_local_policy(&policy)?;
+    /// assert!(builder.has_only_local_rules());
+    /// let report = builder
+    ///     .with_local_matches(vec![RuleIndex::FIRST])
+    ///     .consume_ir(serde_json::json!({"__rule_numbers__": []}))
+    ///     .unwrap();
+    /// assert_eq!(report.value(), serde_json::json!({"internal": true}));
+    /// # Ok::<(), policyai::PolicyError>(())
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn add_local_policy(&mut self, policy: &Policy) -> Result<(), PolicyError> {
+        self.add_rule(policy, true)
+    }
+
+    /// Mark which of the rules added with [`ReportBuilder::add_local_policy`] matched.
+    pub fn with_local_matches(mut self, rules: Vec<RuleIndex>) -> Self {
+        self.local_matches = rules;
+        self
+    }
+
+    /// Record the hash of the input the report is for in [`Report::input_hash`].
+    pub fn with_input_hash(mut self, input_hash: impl Into<String>) -> Self {
+        self.input_hash = Some(input_hash.into());
+        self
+    }
+
+    /// Record the hash of the policy set the report is for in [`Report::policy_set_hash`].
+    pub fn with_policy_set_hash(mut self, policy_set_hash: impl Into<String>) -> Self {
+        self.policy_set_hash = Some(policy_set_hash.into());
+        self
+    }
+
+    /// The local rules marked as matched.
+    pub fn local_matches(&self) -> &[RuleIndex] {
+        &self.local_matches
+    }
+
+    /// True if every rule was added with [`ReportBuilder::add_local_policy`], so there is
+    /// nothing to ask the LLM.  Also true when there are no rules at all.
+    pub fn has_only_local_rules(&self) -> bool {
+        self.local_rules.len() == self.masks_by_index.len()
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn add_rule(&mut self, policy: &Policy, local: bool) -> Result<(), PolicyError> {
         // Assume default=0, so we increment mask_index here (in case we throw out parts of it) and
         // increment policy_index at the end when we "commit".
         self.mask_index += 1;
@@ -78,61 +429,88 @@ impl ReportBuilder {
         let mut new_string_masks = Vec::new();
         let mut new_string_array_masks = Vec::new();
         let mut new_string_enum_masks = Vec::new();
+        let mut new_datetime_masks = Vec::new();
         let mut new_required = Vec::new();
         let mut new_properties = serde_json::Map::new();
         let mut new_masks = Vec::new();
+        // What the LLM would output under each mask if the rule matched, for local rules.
+        let mut local_outputs = serde_json::Map::new();
         self.default_return = policy.r#type.default_value();
+        self.declared_fields = policy
+            .r#type
+            .fields
+            .iter()
+            .map(|f| f.name().to_string())
+            .collect();
+        self.groups = policy.r#type.groups.clone();
         for field in policy.r#type.fields.iter() {
             let Some(value) = policy.action.get(field.name()) else {
                 continue;
             };
+            if local && value.is_null() {
+                return Err(PolicyError::LocalValueMissing {
+                    field: field.name().to_string(),
+                });
+            }
             match field {
                 Field::Bool {
                     name,
                     default,
                     on_conflict,
                 } => {
-                    let serde_json::Value::Bool(_) = value else {
+                    let serde_json::Value::Bool(bool_value) = value else {
                         return Err(PolicyError::expected_bool(name.clone(), value));
                     };
-                    let mask = Uuid::new_v4().to_string();
+                    let mask = self.mask_for(name);
                     new_masks.push(mask.clone());
-                    new_bool_masks.push(BoolMask::new(
-                        self.policy_index,
-                        name.clone(),
-                        mask.clone(),
-                        *default,
-                        *on_conflict,
-                    ));
+                    new_bool_masks.push(
+                        BoolMask::new(
+                            self.policy_index.number(),
+                            name.clone(),
+                            mask.clone(),
+                            *default,
+                            self.on_conflict_for(name, *on_conflict),
+                        )
+                        .with_value(*bool_value)
+                        .with_on_null(self.on_null_for(name)),
+                    );
                     content = content.replace(&format!("{name:?}"), &format!("{mask:?}"));
+                    local_outputs.insert(mask.clone(), value.clone());
                     new_required.push(mask.clone());
-                    new_properties.insert(mask, bool::json_schema());
+                    new_properties.insert(mask, scalar_schema("boolean"));
                 }
                 Field::Number {
                     name,
                     default,
                     on_conflict,
                 } => {
-                    let number_value = match value {
+                    // {"$add": delta} raises the field by delta rather than setting it.
+                    let increment = operand(value, "$add");
+                    let number_value = match increment.unwrap_or(value) {
                         serde_json::Value::Number(v) => Some(v.clone()),
-                        serde_json::Value::Null => None,
+                        serde_json::Value::Null if increment.is_none() => None,
                         _ => return Err(PolicyError::expected_number(name.clone(), value)),
                     };
-                    let mask = Uuid::new_v4().to_string();
+                    let mask = self.mask_for(name);
                     new_masks.push(mask.clone());
-                    new_number_masks.push(NumberMask::new(
-                        self.policy_index,
+                    let mut number_mask = NumberMask::new(
+                        self.policy_index.number(),
                         name.clone(),
                         mask.clone(),
                         *default,
                         number_value.clone(),
-                        *on_conflict,
-                    ));
+                        self.on_conflict_for(name, *on_conflict),
+                    );
+                    if increment.is_some() {
+                        number_mask = number_mask.with_increment();
+                    }
+                    new_number_masks.push(number_mask.with_on_null(self.on_null_for(name)));
                     content = content.replace(&format!("{name:?}"), &format!("{mask:?}"));
+                    local_outputs.insert(mask.clone(), action_output(value));
                     if default.is_some() {
                         new_required.push(mask.clone());
                     }
-                    new_properties.insert(mask, f64::json_schema());
+                    new_properties.insert(mask, scalar_schema("number"));
                 }
                 Field::String {
                     name,
@@ -144,23 +522,29 @@ impl ReportBuilder {
                         serde_json::Value::Null => None,
                         _ => return Err(PolicyError::expected_string(name.clone(), value)),
                     };
-                    let mask = Uuid::new_v4().to_string();
+                    let mask = self.mask_for(name);
                     new_masks.push(mask.clone());
-                    new_string_masks.push(StringMask::new(
-                        self.policy_index,
-                        name.clone(),
-                        mask.clone(),
-                        default.clone(),
-                        string_value.clone(),
-                        *on_conflict,
-                    ));
+                    new_string_masks.push(
+                        StringMask::new(
+                            self.policy_index.number(),
+                            name.clone(),
+                            mask.clone(),
+                            default.clone(),
+                            string_value.clone(),
+                            self.on_conflict_for(name, *on_conflict),
+                        )
+                        .with_on_null(self.on_null_for(name)),
+                    );
                     content = content.replace(&format!("{name:?}"), &format!("{mask:?}"));
+                    local_outputs.insert(mask.clone(), value.clone());
                     if default.is_some() {
                         new_required.push(mask.clone());
                     }
-                    new_properties.insert(mask, String::json_schema());
+                    new_properties.insert(mask, scalar_schema("string"));
                 }
                 Field::StringArray { name } => {
+                    // Arrays always accumulate, so {"$append": ...} only spares the brackets.
+                    let value = &action_output(value);
                     let serde_json::Value::Array(v) = value else {
                         return Err(PolicyError::expected_string(name.clone(), value));
                     };
@@ -172,23 +556,34 @@ impl ReportBuilder {
                             return Err(PolicyError::expected_string(name.clone(), v));
                         }
                     }
-                    let mask = Uuid::new_v4().to_string();
+                    let mask = self.mask_for(name);
                     new_masks.push(mask.clone());
-                    new_string_array_masks.push(StringArrayMask::new(
-                        self.policy_index,
-                        name.clone(),
-                        mask.clone(),
-                        strings,
-                    ));
+                    new_string_array_masks.push(
+                        StringArrayMask::new(
+                            self.policy_index.number(),
+                            name.clone(),
+                            mask.clone(),
+                            strings,
+                        )
+                        .with_max_len(self.max_array_len)
+                        .with_on_null(self.on_null_for(name)),
+                    );
                     content = content.replace(&format!("{name:?}"), &format!("{mask:?}"));
-                    new_properties.insert(mask, Vec::<String>::json_schema());
+                    local_outputs.insert(mask.clone(), value.clone());
+                    new_properties.insert(mask, array_schema("string"));
                 }
                 Field::StringEnum {
                     name,
                     values,
                     default,
                     on_conflict,
+                    source,
                 } => {
+                    if !source.is_static() {
+                        return Err(PolicyError::MissingEnumValues {
+                            field: name.clone(),
+                        });
+                    }
                     let enum_value = match value {
                         serde_json::Value::Null => None,
                         v => {
@@ -198,110 +593,468 @@ impl ReportBuilder {
                             Some(found_value.clone())
                         }
                     };
-                    let mask = Uuid::new_v4().to_string();
+                    let mask = self.mask_for(name);
                     new_masks.push(mask.clone());
-                    new_string_enum_masks.push(StringEnumMask::new(
-                        self.policy_index,
-                        name.clone(),
-                        mask.clone(),
-                        enum_value.clone(),
-                        default.clone(),
-                        *on_conflict,
-                    ));
+                    new_string_enum_masks.push(
+                        StringEnumMask::new(
+                            self.policy_index.number(),
+                            name.clone(),
+                            mask.clone(),
+                            enum_value.clone(),
+                            default.clone(),
+                            self.on_conflict_for(name, *on_conflict),
+                        )
+                        .with_values(values.clone())
+                        .with_fallback(self.enum_fallbacks.get(name).cloned().unwrap_or_default())
+                        .with_on_null(self.on_null_for(name)),
+                    );
                     content = content.replace(&format!("{name:?}"), &format!("{mask:?}"));
                     if let Some(v) = &enum_value {
                         content = content.replace(&format!("{v:?}"), "true");
                     }
+                    local_outputs.insert(mask.clone(), true.into());
+                    if default.is_some() {
+                        new_required.push(mask.clone());
+                    }
+                    new_properties.insert(mask, scalar_schema("boolean"));
+                }
+                Field::DateTime {
+                    name,
+                    default,
+                    on_conflict,
+                } => {
+                    let datetime_value = match value {
+                        serde_json::Value::String(v) => {
+                            if crate::compare::rfc3339_instant(v).is_none() {
+                                return Err(PolicyError::InvalidDateTime {
+                                    field: name.clone(),
+                                    value: v.clone(),
+                                });
+                            }
+                            Some(v.clone())
+                        }
+                        serde_json::Value::Null => None,
+                        _ => return Err(PolicyError::expected_string(name.clone(), value)),
+                    };
+                    let mask = self.mask_for(name);
+                    new_masks.push(mask.clone());
+                    new_datetime_masks.push(
+                        DateTimeMask::new(
+                            self.policy_index.number(),
+                            name.clone(),
+                            mask.clone(),
+                            default.clone(),
+                            datetime_value,
+                            self.on_conflict_for(name, *on_conflict),
+                        )
+                        .with_on_null(self.on_null_for(name)),
+                    );
+                    content = content.replace(&format!("{name:?}"), &format!("{mask:?}"));
+                    local_outputs.insert(mask.clone(), value.clone());
                     if default.is_some() {
                         new_required.push(mask.clone());
                     }
-                    new_properties.insert(mask, bool::json_schema());
+                    let mut schema = scalar_schema("string");
+                    schema["format"] = "date-time".into();
+                    new_properties.insert(mask, schema);
                 }
             }
         }
+        // A rule that sets nothing still needs something to output when it matches, or it
+        // could never be reported as matched.
+        let mut new_match_mask = None;
+        if new_masks.is_empty() {
+            let mask = Uuid::new_v4().to_string();
+            content += &format!(" When this rule matches, output JSON {{{mask:?}: true}}.");
+            new_masks.push(mask.clone());
+            local_outputs.insert(mask.clone(), true.into());
+            new_properties.insert(mask.clone(), scalar_schema("boolean"));
+            new_match_mask = Some(MatchMask::new(self.policy_index.number(), mask));
+        }
         // Commit all changes atomically
-        push_or_merge_message(
-            &mut self.messages,
-            MessageParam {
-                role: MessageRole::User,
-                content: format!("<rule index=\"{}\">{content}</rule>", self.policy_index).into(),
-            },
-        );
+        if local {
+            self.local_rules.push((self.policy_index, local_outputs));
+        } else {
+            #[cfg(not(feature = "client"))]
+            let _ = content;
+            #[cfg(feature = "client")]
+            {
+                let content = format!("<rule index=\"{}\">{content}</rule>", self.policy_index);
+                self.sent_rules.push(SentRule {
+                    rule: self.policy_index,
+                    prompt: policy.prompt.clone(),
+                    prompt_bytes: content.len(),
+                    schema_bytes: serde_json::to_string(&new_properties).map_or(0, |s| s.len()),
+                });
+                push_or_merge_message(
+                    &mut self.messages,
+                    MessageParam {
+                        role: MessageRole::User,
+                        content: content.into(),
+                    },
+                );
+            }
 
-        // Extend collections instead of replacing
-        //self.required.extend(new_required);
-        if let serde_json::Value::Object(props) = &mut self.properties {
-            props.extend(new_properties);
+            // Extend collections instead of replacing
+            //self.required.extend(new_required);
+            if let serde_json::Value::Object(props) = &mut self.properties {
+                props.extend(new_properties);
+            }
         }
         self.bool_masks.extend(new_bool_masks);
         self.number_masks.extend(new_number_masks);
         self.string_masks.extend(new_string_masks);
         self.string_array_masks.extend(new_string_array_masks);
         self.string_enum_masks.extend(new_string_enum_masks);
+        self.datetime_masks.extend(new_datetime_masks);
+        self.match_masks.extend(new_match_mask);
+        debug_assert_eq!(self.masks_by_index.len(), self.policy_index.position());
         self.masks_by_index.push(new_masks);
+        if let Some(priority) = policy.priority {
+            self.policy_priorities
+                .insert(self.policy_index.number(), priority);
+        }
 
-        self.policy_index += 1;
+        self.policy_index = self.policy_index.next();
         Ok(())
     }
 
-    /// Convert intermediate representation into a final Report.
+    /// Record that a policy was left out because its precondition failed.
     ///
-    /// Takes the JSON output from an LLM and applies all configured masks to extract
-    /// structured data according to the policies that were added to this builder.
+    /// The policy contributes no rule, but its type still supplies the defaults and field
+    /// declaration order, so a report built only from pruned policies has the right shape.
     ///
     /// # Arguments
     ///
-    /// * `ir` - The intermediate representation JSON from the LLM
+    /// * `index` - Zero-based position of the policy among all candidate policies
+    /// * `policy` - The policy that was pruned
+    pub fn add_pruned_policy(&mut self, index: usize, policy: &Policy) {
+        self.add_skipped_policy(index, policy, SkipReason::PreconditionFailed);
+    }
+
+    /// Record that a policy was left out for `reason`, as [`ReportBuilder::add_pruned_policy`]
+    /// does for a failed precondition.
     ///
-    /// # Returns
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Policy, PolicyType, ReportBuilder, SkipReason};
+    /// let policy = Policy {
+    ///     r#type: PolicyType::parse("type T { urgent: bool = false }").unwrap(),
+    ///     prompt: "If the email is from the CEO".to_string(),
+    ///     action: serde_json::json!({}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// let mut builder = ReportBuilder::default();
+    /// builder.add_skipped_policy(0, &policy, SkipReason::OutOfScope);
+    /// let report = builder.consume_ir(serde_json::json!({})).unwrap();
+    /// assert_eq!(report.skip_reason(0), Some(SkipReason::OutOfScope));
+    /// assert_eq!(report.pruned_policies, vec![0]);
+    /// ```
+    pub fn add_skipped_policy(&mut self, index: usize, policy: &Policy, reason: SkipReason) {
+        if self.has_no_rules() {
+            self.default_return = policy.r#type.default_value();
+            self.declared_fields = policy
+                .r#type
+                .fields
+                .iter()
+                .map(|f| f.name().to_string())
+                .collect();
+            self.groups = policy.r#type.groups.clone();
+        }
+        self.pruned_policies.push(index);
+        self.skipped_policies.push(SkippedPolicy {
+            policy: index,
+            reason,
+            explanation: None,
+        });
+    }
+
+    /// Name the zero-based position of the policy each rule came from, in rule order, so that
+    /// reports also record the policies whose rules did not match.
+    ///
+    /// Without it, [`Report::skipped_policies`] holds only the policies left out with
+    /// [`ReportBuilder::add_skipped_policy`].
+    pub fn with_rule_policies(mut self, positions: Vec<usize>) -> Self {
+        self.rule_policies = Some(positions);
+        self
+    }
+
+    /// Positions of the policies recorded with [`ReportBuilder::add_pruned_policy`].
+    pub fn pruned_policies(&self) -> &[usize] {
+        &self.pruned_policies
+    }
+
+    /// The rule the next call to [`ReportBuilder::add_policy`] will create.
+    pub fn next_rule(&self) -> RuleIndex {
+        self.policy_index
+    }
+
+    /// The masked field names set by `rule`, if the rule exists.
+    pub fn masks_for_rule(&self, rule: RuleIndex) -> Option<&[String]> {
+        self.masks_by_index.get(rule.position()).map(Vec::as_slice)
+    }
+
+    /// True if no policy has been added as a rule.
+    pub fn has_no_rules(&self) -> bool {
+        self.policy_index == RuleIndex::FIRST
+    }
+
+    /// Turn the LLM's output for this builder's rules, the intermediate representation, into a
+    /// [`Report`].
     ///
-    /// Returns a `Report` containing the extracted structured data, or an `ApplyError`
-    /// if there were issues processing the intermediate representation.
+    /// This is the last step of applying policies and the only one that needs no LLM, so code
+    /// with its own LLM plumbing can use the rest of the crate end to end:
+    ///
+    /// 1. Add policies with [`ReportBuilder::add_policy`].
+    /// 2. Send the LLM the rules in [`ReportBuilder::messages`] and the text, and have it answer
+    ///    with JSON matching [`ReportBuilder::schema`], for instance as the input of a forced
+    ///    tool call.
+    /// 3. Pass the answer here.
+    ///
+    /// The IR is keyed by masks, the opaque names that stand in for fields in the rules and the
+    /// schema.  Each mask's value is credited to its field and rule, disagreements between rules
+    /// are settled by the field's [`crate::OnConflict`], the results are laid over the defaults,
+    /// and fields of closed `when` groups are dropped.  Rules decided locally and marked with
+    /// [`ReportBuilder::with_local_matches`] are merged in as if the LLM had output them.
+    ///
+    /// The `__rule_numbers__` the LLM reports are kept in [`Report::ir`] but not checked:
+    /// [`Report::matched_rules`] lists the rules whose masks were set, and a caller that wants
+    /// to ask the LLM again when the two disagree, as [`crate::Manager`] does, compares them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApplyError::InvalidResponse`] if `ir` is not a JSON object.
+    ///
+    /// Problems with individual values are not errors here.  A value of the wrong type, a
+    /// disagreement that the field's strategy cannot settle, or a mask that belongs to no rule is
+    /// recorded on the report, and shows up in [`Report::errors`] and [`Report::conflicts`], so
+    /// that one bad field does not cost the rest of the output.  Check
+    /// [`Report::has_errors`] before trusting the result.
     ///
     /// # Example
     ///
     /// ```
-    /// # use policyai::ReportBuilder;
-    /// let builder = ReportBuilder::default();
-    /// let ir = serde_json::json!({"field_abc": true});
-    /// let report = builder.consume_ir(ir)?;
-    /// # Ok::<(), policyai::ApplyError>(())
+    /// use policyai::{Policy, PolicyType, ReportBuilder, RuleIndex};
+    /// use serde_json::json;
+    ///
+    /// let policy = Policy {
+    ///     r#type: PolicyType::parse("type Email { urgent: bool = false }")?,
+    ///     prompt: "If the email asks for a reply today".to_string(),
+    ///     action: json!({"urgent": true}),
+    ///     precondition: None,
+    ///     exact_match: None,
+    ///     explanation: None,
+    ///     priority: None,
+    /// };
+    /// let mut builder = ReportBuilder::default();
+    /// builder.add_policy(&policy)?;
+    ///
+    /// // The rules and the schema name the field by its mask, and so does the LLM's answer.
+    /// let mask = builder.masks_for_rule(RuleIndex::FIRST).unwrap()[0].clone();
+    /// assert!(builder.schema()["properties"].get(&mask).is_some());
+    /// let answer = json!({"__rule_numbers__": [1], &mask: true});
+    ///
+    /// let report = builder.clone().consume_ir(answer)?;
+    /// assert_eq!(report.value(), json!({"urgent": true}));
+    /// assert_eq!(report.matched_rules(), vec![RuleIndex::FIRST]);
+    /// assert!(!report.has_errors());
+    ///
+    /// let wrong_type = builder.clone().consume_ir(json!({&mask: "yes"}))?;
+    /// assert!(wrong_type.has_errors());
+    /// assert!(builder.consume_ir(json!(["not", "an", "object"])).is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     #[allow(clippy::result_large_err)]
     pub fn consume_ir(self, ir: serde_json::Value) -> Result<Report, ApplyError> {
+        if !ir.is_object() {
+            return Err(ApplyError::invalid_response(
+                format!("Expected a JSON object keyed by masks, got {ir}"),
+                "Ask the LLM to answer with JSON matching the builder's schema",
+            ));
+        }
+        let ir = self.merge_local_matches(ir);
         let mut report = Report::new(
-            self.messages,
-            self.bool_masks,
-            self.number_masks,
-            self.string_masks,
-            self.string_array_masks,
-            self.string_enum_masks,
-            self.masks_by_index,
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            self.masks_by_index.clone(),
         );
+        #[cfg(feature = "client")]
+        {
+            report = report.with_messages(self.messages.clone());
+        }
         report.ir = Some(ir.clone());
-        report.default = Some(self.default_return);
-        for m in report.bool_masks.clone().into_iter() {
-            m.apply_to(&ir, &mut report);
+        report.default = Some(self.default_return.clone());
+        report.field_order = self.field_order;
+        report.naming = self.naming;
+        report.translations = self.translations.clone();
+        report.declared_fields = self.declared_fields.clone();
+        report.pruned_policies = self.pruned_policies.clone();
+        report.input_hash = self.input_hash.clone();
+        report.policy_set_hash = self.policy_set_hash.clone();
+        report.policy_priorities = self.policy_priorities.clone();
+        if let Some(rule_policies) = &self.rule_policies {
+            for (position, &policy) in rule_policies.iter().enumerate() {
+                report.report_policy_position(RuleIndex::from_position(position).number(), policy);
+            }
         }
-        for m in report.number_masks.clone().into_iter() {
+        // Ungrouped fields first, so that every gate has its final value before the fields it
+        // gates are considered.
+        let ungrouped = |name: &str| self.gate_for(name).is_none();
+        self.apply_masks(&ir, &mut report, ungrouped);
+        for m in self.match_masks.iter() {
             m.apply_to(&ir, &mut report);
+            report.match_masks.push(m.clone());
         }
-        for m in report.string_masks.clone().into_iter() {
-            m.apply_to(&ir, &mut report);
+        let value = report.declared_value();
+        let open = |name: &str| {
+            self.gate_for(name)
+                .is_some_and(|gate| value.get(gate) == Some(&serde_json::Value::Bool(true)))
+        };
+        self.apply_masks(&ir, &mut report, open);
+        // A closed group's fields stay out of the output, but a rule that set them still counts
+        // as matched so that the rule numbers the LLM reports are not flagged as inconsistent.
+        for (name, mask, policy_index) in self.all_masks() {
+            if self.gate_for(name).is_some() && !open(name) && ir.get(mask).is_some() {
+                report.report_policy_index(policy_index);
+            }
         }
-        for m in report.string_array_masks.clone().into_iter() {
-            m.apply_to(&ir, &mut report);
+        for group in self.groups.iter() {
+            if value.get(&group.when) != Some(&serde_json::Value::Bool(true)) {
+                for field in group.fields.iter() {
+                    report.clear_field(field);
+                }
+            }
         }
-        for m in report.string_enum_masks.clone().into_iter() {
-            m.apply_to(&ir, &mut report);
+        report.skipped_policies = self.skipped_policies.clone();
+        if let Some(rule_policies) = &self.rule_policies {
+            let matched = report.matched_rules();
+            let mut reasons = report.non_match_reasons();
+            for (position, &policy) in rule_policies.iter().enumerate() {
+                let rule = RuleIndex::from_position(position);
+                if !matched.contains(&rule) {
+                    report.skipped_policies.push(SkippedPolicy {
+                        policy,
+                        reason: SkipReason::NotMatched,
+                        explanation: reasons.remove(&rule),
+                    });
+                }
+            }
         }
+        report
+            .skipped_policies
+            .sort_by_key(|skipped| skipped.policy);
         Ok(report)
     }
 
+    /// Add the outputs and rule numbers of the local rules that matched to `ir`.
+    fn merge_local_matches(&self, mut ir: serde_json::Value) -> serde_json::Value {
+        let serde_json::Value::Object(obj) = &mut ir else {
+            return ir;
+        };
+        if self.local_matches.is_empty() {
+            return ir;
+        }
+        let mut numbers = match obj.get("__rule_numbers__") {
+            Some(serde_json::Value::Array(numbers)) => numbers.clone(),
+            _ => vec![],
+        };
+        for (rule, outputs) in self.local_rules.iter() {
+            if self.local_matches.contains(rule) {
+                numbers.push(rule.number().into());
+                obj.extend(outputs.clone());
+            }
+        }
+        obj.insert("__rule_numbers__".to_string(), numbers.into());
+        ir
+    }
+
+    /// Apply every mask whose field satisfies `select`, and record it on the report.
+    fn apply_masks(
+        &self,
+        ir: &serde_json::Value,
+        report: &mut Report,
+        select: impl Fn(&str) -> bool,
+    ) {
+        for m in self.bool_masks.iter().filter(|m| select(&m.name)) {
+            m.apply_to(ir, report);
+            report.bool_masks.push(m.clone());
+        }
+        for m in self.number_masks.iter().filter(|m| select(&m.name)) {
+            m.apply_to(ir, report);
+            report.number_masks.push(m.clone());
+        }
+        for m in self.string_masks.iter().filter(|m| select(&m.name)) {
+            m.apply_to(ir, report);
+            report.string_masks.push(m.clone());
+        }
+        for m in self.string_array_masks.iter().filter(|m| select(&m.name)) {
+            m.apply_to(ir, report);
+            report.string_array_masks.push(m.clone());
+        }
+        for m in self.string_enum_masks.iter().filter(|m| select(&m.name)) {
+            m.apply_to(ir, report);
+            report.string_enum_masks.push(m.clone());
+        }
+        for m in self.datetime_masks.iter().filter(|m| select(&m.name)) {
+            m.apply_to(ir, report);
+            report.datetime_masks.push(m.clone());
+        }
+    }
+
+    /// The field name, mask, and rule number of every mask.
+    fn all_masks(&self) -> impl Iterator<Item = (&str, &str, usize)> {
+        let bool_masks = self
+            .bool_masks
+            .iter()
+            .map(|m| (&m.name, &m.mask, m.policy_index));
+        let number_masks = self
+            .number_masks
+            .iter()
+            .map(|m| (&m.name, &m.mask, m.policy_index));
+        let string_masks = self
+            .string_masks
+            .iter()
+            .map(|m| (&m.name, &m.mask, m.policy_index));
+        let string_array_masks = self
+            .string_array_masks
+            .iter()
+            .map(|m| (&m.name, &m.mask, m.policy_index));
+        let string_enum_masks = self
+            .string_enum_masks
+            .iter()
+            .map(|m| (&m.name, &m.mask, m.policy_index));
+        let datetime_masks = self
+            .datetime_masks
+            .iter()
+            .map(|m| (&m.name, &m.mask, m.policy_index));
+        bool_masks
+            .chain(number_masks)
+            .chain(string_masks)
+            .chain(string_array_masks)
+            .chain(string_enum_masks)
+            .chain(datetime_masks)
+            .map(|(name, mask, policy_index)| (name.as_str(), mask.as_str(), policy_index))
+    }
+
+    fn gate_for(&self, field: &str) -> Option<&str> {
+        self.groups
+            .iter()
+            .find(|g| g.fields.iter().any(|f| f == field))
+            .map(|g| g.when.as_str())
+    }
+
     /// Get the default return value structure.
     ///
-    /// Returns the JSON object that represents the default values for all fields,
-    /// which is used when the LLM doesn't provide specific values.
+    /// Returns the [`crate::PolicyType::default_value`] of the policies added so far.  The
+    /// manager quotes it in the prompt and every report built from this builder overlays its
+    /// extracted values on it.
     ///
     /// # Example
     ///
@@ -328,10 +1081,40 @@ impl ReportBuilder {
     /// let messages = builder.messages();
     /// // Messages will be empty for a default builder with no policies
     /// ```
+    #[cfg(feature = "client")]
     pub fn messages(&self) -> Vec<MessageParam> {
         self.messages.clone()
     }
 
+    /// The rules sent to the LLM, in rule order.
+    #[cfg(feature = "client")]
+    pub(crate) fn sent_rules(&self) -> &[SentRule] {
+        &self.sent_rules
+    }
+
+    /// The enum value the rule behind `mask` sets, if `mask` is an enum field's.
+    #[cfg(feature = "client")]
+    pub(crate) fn string_enum_value(&self, mask: &str) -> Option<&str> {
+        self.string_enum_masks
+            .iter()
+            .find(|m| m.mask == mask)
+            .and_then(|m| m.value.as_deref())
+    }
+
+    /// True if `field` belongs to a `when` group, and so is only output while its gate is open.
+    #[cfg(feature = "client")]
+    pub(crate) fn is_gated(&self, field: &str) -> bool {
+        self.gate_for(field).is_some()
+    }
+
+    /// The name of the field that `mask` stands in for, or `None` if no field has that mask.
+    #[cfg(feature = "client")]
+    pub(crate) fn field_for_mask(&self, mask: &str) -> Option<&str> {
+        self.all_masks()
+            .find(|(_, m, _)| *m == mask)
+            .map(|(name, _, _)| name)
+    }
+
     /// Get the JSON schema for the expected LLM output.
     ///
     /// Returns a JSON schema object that describes the structure and types
@@ -347,10 +1130,33 @@ impl ReportBuilder {
     /// assert!(schema["properties"].is_object());
     /// ```
     pub fn schema(&self) -> serde_json::Value {
+        let mut required = self.required.clone();
+        let mut properties = self.properties.clone();
+        for (name, mask, _) in self.all_masks() {
+            let Some(gate) = self.gate_for(name) else {
+                continue;
+            };
+            let gate_masks = self
+                .bool_masks
+                .iter()
+                .filter(|m| m.name == gate)
+                .map(|m| format!("{:?}", m.mask))
+                .collect::<Vec<_>>();
+            if gate_masks.is_empty() {
+                // No rule can open the group, so its fields are not offered at all.
+                required.retain(|r| r != mask);
+                if let serde_json::Value::Object(props) = &mut properties {
+                    props.shift_remove(mask);
+                }
+            } else if let Some(prop) = properties.get_mut(mask) {
+                prop["description"] =
+                    format!("Only set when {} is true", gate_masks.join(" or ")).into();
+            }
+        }
         let mut schema = serde_json::json! {{}};
         schema["type"] = "object".into();
-        schema["required"] = self.required.clone().into();
-        schema["properties"] = self.properties.clone();
+        schema["required"] = required.into();
+        schema["properties"] = properties;
         schema
     }
 }
@@ -364,18 +1170,41 @@ impl Default for ReportBuilder {
             string_masks: vec![],
             string_array_masks: vec![],
             string_enum_masks: vec![],
+            datetime_masks: vec![],
+            match_masks: vec![],
             masks_by_index: vec![],
             default_return: serde_json::json! {{}},
+            #[cfg(feature = "client")]
             messages: vec![],
-            policy_index: 1,
+            #[cfg(feature = "client")]
+            sent_rules: vec![],
+            policy_index: RuleIndex::FIRST,
             required: vec![
                 "__rule_numbers__".to_string(),
                 "__justification__".to_string(),
             ],
             properties: serde_json::json! {{
-                "__rule_numbers__": Vec::<u64>::json_schema(),
-                "__justification__": String::json_schema(),
+                "__rule_numbers__": array_schema("integer"),
+                "__justification__": scalar_schema("string"),
             }},
+            field_order: FieldOrder::default(),
+            naming: NamingPolicy::default(),
+            translations: Translations::default(),
+            max_array_len: StringArrayMask::DEFAULT_MAX_LEN,
+            declared_fields: vec![],
+            groups: vec![],
+            on_conflict_overrides: BTreeMap::new(),
+            enum_fallbacks: BTreeMap::new(),
+            null_handling: BTreeMap::new(),
+            pruned_policies: vec![],
+            skipped_policies: vec![],
+            rule_policies: None,
+            local_rules: vec![],
+            local_matches: vec![],
+            unmasked_fields: BTreeSet::new(),
+            input_hash: None,
+            policy_set_hash: None,
+            policy_priorities: BTreeMap::new(),
         }
     }
 }
@@ -0,0 +1,286 @@
+//! Storage of policies outside the process.
+//!
+//! Services that manage policies for many tenants keep them in a database rather than in files.
+//! A [`PolicyRepository`] stores policies under string identifiers, and
+//! [`crate::Manager::from_repository`] hydrates a manager from one.  [`MemoryRepository`] is
+//! always available; `SqliteRepository` requires the `sqlite` feature.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::{Policy, RepositoryError};
+
+/// A store of policies keyed by identifier.
+///
+/// Identifiers are chosen by the caller and are opaque to the repository.  Implementations must
+/// be safe to share between threads.
+pub trait PolicyRepository: std::fmt::Debug + Send + Sync {
+    /// The identifiers of every stored policy, in ascending order.
+    fn list(&self) -> Result<Vec<String>, RepositoryError>;
+    /// The policy stored under `id`, if any.
+    fn get(&self, id: &str) -> Result<Option<Policy>, RepositoryError>;
+    /// Store `policy` under `id`, replacing any policy already there.
+    fn put(&self, id: &str, policy: &Policy) -> Result<(), RepositoryError>;
+    /// Remove the policy stored under `id`.  Returns true if there was one.
+    fn delete(&self, id: &str) -> Result<bool, RepositoryError>;
+}
+
+/// An in-memory [`PolicyRepository`], useful for tests and as a reference implementation.
+///
+/// # Example
+///
+/// ```
+/// use policyai::{MemoryRepository, Policy, PolicyRepository, PolicyType};
+///
+/// let repository = MemoryRepository::default();
+/// let policy = Policy {
+///     r#type: PolicyType::parse("type T { urgent: bool = false }").unwrap(),
+///     prompt: "If the email is from the CEO".to_string(),
+///     action: serde_json::json!({"urgent": true}),
+///     precondition: None,
+///     exact_match: None,
+///     explanation: None,
+///     priority: None,
+/// };
+/// repository.put("ceo", &policy).unwrap();
+/// assert_eq!(repository.list().unwrap(), vec!["ceo".to_string()]);
+/// assert!(repository.delete("ceo").unwrap());
+/// assert!(repository.get("ceo").unwrap().is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryRepository {
+    policies: Mutex<BTreeMap<String, Policy>>,
+}
+
+impl PolicyRepository for MemoryRepository {
+    fn list(&self) -> Result<Vec<String>, RepositoryError> {
+        Ok(self.policies.lock().unwrap().keys().cloned().collect())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Policy>, RepositoryError> {
+        Ok(self.policies.lock().unwrap().get(id).cloned())
+    }
+
+    fn put(&self, id: &str, policy: &Policy) -> Result<(), RepositoryError> {
+        self.policies
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), policy.clone());
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, RepositoryError> {
+        Ok(self.policies.lock().unwrap().remove(id).is_some())
+    }
+}
+
+/////////////////////////////////////// SqliteRepository ///////////////////////////////////////
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteRepository;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use rusqlite::{params, Connection, OptionalExtension};
+
+    use super::PolicyRepository;
+    use crate::{Policy, RepositoryError};
+
+    /// Schema changes, applied in order.  The database's `user_version` records how many have
+    /// been applied; append to this list and never edit an entry that has shipped.
+    const MIGRATIONS: &[&str] = &["CREATE TABLE policies (
+            id TEXT PRIMARY KEY NOT NULL,
+            policy TEXT NOT NULL,
+            updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );"];
+
+    /// A [`PolicyRepository`] backed by an SQLite database.
+    ///
+    /// Each policy is stored as JSON in the `policies` table.  Opening a database brings its
+    /// schema up to date, so a file written by an older release can be opened by a newer one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::{PolicyRepository, SqliteRepository};
+    ///
+    /// let repository = SqliteRepository::open_in_memory().unwrap();
+    /// assert!(repository.list().unwrap().is_empty());
+    /// ```
+    #[derive(Debug)]
+    pub struct SqliteRepository {
+        conn: Mutex<Connection>,
+    }
+
+    impl SqliteRepository {
+        /// Open, creating if necessary, the database at `path`.
+        pub fn open(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+            Self::migrate(Connection::open(path).map_err(RepositoryError::backend)?)
+        }
+
+        /// Open a private database that lives only as long as the repository.
+        pub fn open_in_memory() -> Result<Self, RepositoryError> {
+            Self::migrate(Connection::open_in_memory().map_err(RepositoryError::backend)?)
+        }
+
+        /// The number of migrations applied to the database.
+        pub fn schema_version(&self) -> Result<usize, RepositoryError> {
+            let conn = self.conn.lock().unwrap();
+            Self::user_version(&conn)
+        }
+
+        fn user_version(conn: &Connection) -> Result<usize, RepositoryError> {
+            conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+                .map(|version| version as usize)
+                .map_err(RepositoryError::backend)
+        }
+
+        fn migrate(mut conn: Connection) -> Result<Self, RepositoryError> {
+            let version = Self::user_version(&conn)?;
+            if version > MIGRATIONS.len() {
+                return Err(RepositoryError::backend(format!(
+                    "database schema version {version} is newer than this release supports ({})",
+                    MIGRATIONS.len()
+                )));
+            }
+            for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+                let tx = conn.transaction().map_err(RepositoryError::backend)?;
+                tx.execute_batch(migration)
+                    .map_err(RepositoryError::backend)?;
+                tx.pragma_update(None, "user_version", (idx + 1) as i64)
+                    .map_err(RepositoryError::backend)?;
+                tx.commit().map_err(RepositoryError::backend)?;
+            }
+            Ok(Self {
+                conn: Mutex::new(conn),
+            })
+        }
+    }
+
+    impl PolicyRepository for SqliteRepository {
+        fn list(&self) -> Result<Vec<String>, RepositoryError> {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn
+                .prepare("SELECT id FROM policies ORDER BY id")
+                .map_err(RepositoryError::backend)?;
+            let ids = stmt
+                .query_map([], |row| row.get(0))
+                .map_err(RepositoryError::backend)?;
+            ids.collect::<Result<Vec<String>, _>>()
+                .map_err(RepositoryError::backend)
+        }
+
+        fn get(&self, id: &str) -> Result<Option<Policy>, RepositoryError> {
+            let conn = self.conn.lock().unwrap();
+            let json: Option<String> = conn
+                .query_row(
+                    "SELECT policy FROM policies WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(RepositoryError::backend)?;
+            json.map(|json| {
+                serde_json::from_str(&json).map_err(|err| RepositoryError::Corrupt {
+                    id: id.to_string(),
+                    message: err.to_string(),
+                })
+            })
+            .transpose()
+        }
+
+        fn put(&self, id: &str, policy: &Policy) -> Result<(), RepositoryError> {
+            let json = serde_json::to_string(policy).map_err(RepositoryError::backend)?;
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO policies (id, policy) VALUES (?1, ?2)
+                 ON CONFLICT (id) DO UPDATE SET
+                    policy = excluded.policy,
+                    updated_at = strftime('%s', 'now')",
+                params![id, json],
+            )
+            .map_err(RepositoryError::backend)?;
+            Ok(())
+        }
+
+        fn delete(&self, id: &str) -> Result<bool, RepositoryError> {
+            let conn = self.conn.lock().unwrap();
+            let deleted = conn
+                .execute("DELETE FROM policies WHERE id = ?1", params![id])
+                .map_err(RepositoryError::backend)?;
+            Ok(deleted > 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Field, OnConflict, PolicyType};
+
+    fn policy(prompt: &str) -> Policy {
+        Policy {
+            r#type: PolicyType {
+                name: "T".to_string(),
+                fields: vec![Field::Bool {
+                    name: "urgent".to_string(),
+                    default: Some(false),
+                    on_conflict: OnConflict::Default,
+                }],
+                groups: vec![],
+            },
+            prompt: prompt.to_string(),
+            action: serde_json::json!({"urgent": true}),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        }
+    }
+
+    fn exercise(repository: &dyn PolicyRepository) {
+        assert!(repository.list().unwrap().is_empty());
+        assert!(repository.get("b").unwrap().is_none());
+        repository.put("b", &policy("from the CFO")).unwrap();
+        repository.put("a", &policy("from the CEO")).unwrap();
+        assert_eq!(
+            repository.list().unwrap(),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        repository.put("b", &policy("from the CTO")).unwrap();
+        assert_eq!(repository.get("b").unwrap().unwrap().prompt, "from the CTO");
+        assert!(repository.delete("a").unwrap());
+        assert!(!repository.delete("a").unwrap());
+        assert_eq!(repository.list().unwrap(), vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn memory_repository() {
+        exercise(&MemoryRepository::default());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_repository() {
+        exercise(&SqliteRepository::open_in_memory().unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_repository_persists_and_migrates_once() {
+        let path = std::env::temp_dir().join(format!("policyai-{}.sqlite", uuid::Uuid::new_v4()));
+        {
+            let repository = SqliteRepository::open(&path).unwrap();
+            assert_eq!(repository.schema_version().unwrap(), 1);
+            repository.put("a", &policy("from the CEO")).unwrap();
+        }
+        let repository = SqliteRepository::open(&path).unwrap();
+        assert_eq!(repository.schema_version().unwrap(), 1);
+        assert_eq!(repository.get("a").unwrap().unwrap().prompt, "from the CEO");
+        drop(repository);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
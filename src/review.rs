@@ -0,0 +1,189 @@
+//! Routing of doubtful reports to people.
+//!
+//! Some extractions should not be acted on without a second pair of eyes: those with conflicts
+//! or errors, and those the LLM was unsure of.  A [`crate::Manager`] configured with a
+//! [`ReviewSink`] packages each such report into a [`ReviewTask`] and submits it, while still
+//! returning the report to the caller.
+
+use std::path::{Path, PathBuf};
+
+use futures::future::BoxFuture;
+
+use crate::{Conflict, Report};
+
+/// One report awaiting manual review.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ReviewTask {
+    /// The text hash of the apply's [`crate::CacheKey`], as 32 hex digits.
+    pub text_hash: String,
+    /// The input text.
+    pub text: String,
+    /// The report the manager returned.
+    pub report: Report,
+    /// The report's unresolved conflicts, copied out for reviewers.
+    pub conflicts: Vec<Conflict>,
+    /// The confidence the LLM reported, if it was asked for one.
+    pub confidence: Option<f64>,
+    /// Why the report was flagged, one sentence per reason.
+    pub reasons: Vec<String>,
+}
+
+/// A destination for reports that need manual review.
+///
+/// Implementations must be safe to share between the clones of a [`crate::Manager`].
+pub trait ReviewSink: std::fmt::Debug + Send + Sync {
+    /// Hand `task` to reviewers.
+    ///
+    /// The manager returns the report whether or not this succeeds.
+    fn submit<'a>(&'a self, task: &'a ReviewTask) -> BoxFuture<'a, std::io::Result<()>>;
+}
+
+/// A [`ReviewSink`] that writes each task to its own JSON file in a directory.
+///
+/// A file per task lets reviewers, or a script, claim and delete tasks independently.  Files are
+/// written with `tokio::fs`, so tasks must be submitted on a tokio runtime.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use policyai::{DirectoryReviewSink, Manager};
+///
+/// let sink = Arc::new(DirectoryReviewSink::new("review-queue"));
+/// let manager = Manager::default().with_review_sink(sink);
+/// ```
+#[derive(Debug)]
+pub struct DirectoryReviewSink {
+    dir: PathBuf,
+}
+
+impl DirectoryReviewSink {
+    /// Write tasks into `dir`, creating it on the first task.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The directory tasks are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read every task in the directory at `dir`, oldest file name first.
+    pub fn load(dir: impl AsRef<Path>) -> std::io::Result<Vec<ReviewTask>> {
+        let mut paths = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let mut tasks = vec![];
+        for path in paths {
+            tasks.push(serde_json::from_slice(&std::fs::read(path)?)?);
+        }
+        Ok(tasks)
+    }
+}
+
+impl ReviewSink for DirectoryReviewSink {
+    fn submit<'a>(&'a self, task: &'a ReviewTask) -> BoxFuture<'a, std::io::Result<()>> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            // Timestamp first so that file names sort in submission order.
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            let name = format!("{nanos:024}-{}.json", uuid::Uuid::new_v4());
+            tokio::fs::write(self.dir.join(name), serde_json::to_vec_pretty(task)?).await
+        })
+    }
+}
+
+/// A [`ReviewSink`] that POSTs each task as JSON to a URL.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use policyai::{Manager, WebhookReviewSink};
+///
+/// let sink = WebhookReviewSink::new("https://review.example.com/tasks")
+///     .with_header("Authorization", "Bearer secret");
+/// let manager = Manager::default().with_review_sink(Arc::new(sink));
+/// ```
+#[derive(Debug)]
+pub struct WebhookReviewSink {
+    url: String,
+    headers: Vec<(String, String)>,
+    client: reqwest::Client,
+}
+
+impl WebhookReviewSink {
+    /// POST tasks to `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: vec![],
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send `name: value` with every request, for example to authenticate.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl ReviewSink for WebhookReviewSink {
+    fn submit<'a>(&'a self, task: &'a ReviewTask) -> BoxFuture<'a, std::io::Result<()>> {
+        Box::pin(async move {
+            let mut req = self
+                .client
+                .post(&self.url)
+                .header("content-type", "application/json")
+                .body(serde_json::to_vec(task)?);
+            for (name, value) in self.headers.iter() {
+                req = req.header(name, value);
+            }
+            let resp = req.send().await.map_err(std::io::Error::other)?;
+            resp.error_for_status()
+                .map(|_| ())
+                .map_err(std::io::Error::other)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn directory_sink_writes_one_file_per_task() {
+        let dir = std::env::temp_dir().join(format!("policyai-review-{}", uuid::Uuid::new_v4()));
+        let sink = DirectoryReviewSink::new(&dir);
+        for text in ["first", "second"] {
+            let task = ReviewTask {
+                text_hash: format!("{:032x}", text.len()),
+                text: text.to_string(),
+                report: Report::from_value(
+                    serde_json::json!({"urgent": true}),
+                    serde_json::json!({"urgent": false}),
+                ),
+                conflicts: vec![],
+                confidence: Some(0.5),
+                reasons: vec!["low confidence".to_string()],
+            };
+            sink.submit(&task).await.unwrap();
+        }
+        let tasks = DirectoryReviewSink::load(sink.dir()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let texts = tasks.iter().map(|t| t.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["first", "second"]);
+        assert_eq!(tasks[0].report.value(), serde_json::json!({"urgent": true}));
+    }
+}
@@ -0,0 +1,67 @@
+//! Rule numbering shared by prompts, masks, and reports.
+//!
+//! Each policy added to a [`crate::ReportBuilder`] becomes one rule.  The LLM sees rules as
+//! `<rule index="N">` with N counting from one, reports the rules it applied in
+//! `__rule_numbers__` using the same numbers, and masks record the number of the rule they
+//! belong to.  [`RuleIndex`] is the single place that converts between that one-based number
+//! and the zero-based position used to index `masks_by_index`.
+
+/// The number of a rule, counting from one in the order policies were added.
+///
+/// # Example
+///
+/// ```
+/// use policyai::RuleIndex;
+///
+/// let rule = RuleIndex::FIRST.next();
+/// assert_eq!(rule.number(), 2);
+/// assert_eq!(rule.position(), 1);
+/// assert_eq!(RuleIndex::from_number(2), Some(rule));
+/// assert_eq!(RuleIndex::from_number(0), None);
+/// assert_eq!(rule.to_string(), "2");
+/// ```
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
+#[serde(transparent)]
+pub struct RuleIndex(usize);
+
+impl RuleIndex {
+    /// The rule of the first policy added.
+    pub const FIRST: RuleIndex = RuleIndex(1);
+
+    /// The rule with the given one-based number, or `None` for zero.
+    pub fn from_number(number: usize) -> Option<Self> {
+        if number == 0 {
+            None
+        } else {
+            Some(Self(number))
+        }
+    }
+
+    /// The rule at the given zero-based position.
+    pub fn from_position(position: usize) -> Self {
+        Self(position + 1)
+    }
+
+    /// The one-based number shown to the LLM and recorded in masks and `rules_matched`.
+    pub fn number(self) -> usize {
+        self.0
+    }
+
+    /// The zero-based position of this rule's entry in `masks_by_index`.
+    pub fn position(self) -> usize {
+        self.0 - 1
+    }
+
+    /// The rule after this one.
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+
+impl std::fmt::Display for RuleIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,145 @@
+//! Placeholders in policy prompts.
+//!
+//! A prompt may contain placeholders such as `{{customer_name}}` that are filled in from a map
+//! of variables each time policies are applied, so one canonical rule can serve many tenants.
+//! Every placeholder must have a value; there is no fallback and no escape syntax.
+
+use std::collections::BTreeMap;
+
+use crate::PolicyError;
+
+/// A piece of a parsed prompt.
+enum Piece<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+#[allow(clippy::result_large_err)]
+fn parse(prompt: &str) -> Result<Vec<Piece<'_>>, PolicyError> {
+    let mut pieces = vec![];
+    let mut rest = prompt;
+    while let Some(open) = rest.find("{{") {
+        let offset = prompt.len() - rest.len() + open;
+        let malformed = || PolicyError::MalformedPlaceholder {
+            prompt: prompt.to_string(),
+            offset,
+        };
+        pieces.push(Piece::Text(&rest[..open]));
+        let after = &rest[open + 2..];
+        let close = after.find("}}").ok_or_else(malformed)?;
+        let name = after[..close].trim();
+        let mut chars = name.chars();
+        let valid = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(malformed());
+        }
+        pieces.push(Piece::Variable(name));
+        rest = &after[close + 2..];
+    }
+    pieces.push(Piece::Text(rest));
+    Ok(pieces)
+}
+
+/// The distinct placeholder names in `prompt`, in order of first appearance.
+#[allow(clippy::result_large_err)]
+pub(crate) fn variables(prompt: &str) -> Result<Vec<String>, PolicyError> {
+    let mut names: Vec<String> = vec![];
+    for piece in parse(prompt)? {
+        if let Piece::Variable(name) = piece {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Substitute every placeholder in `prompt` with its value from `values`.
+#[allow(clippy::result_large_err)]
+pub(crate) fn render(
+    prompt: &str,
+    values: &BTreeMap<String, String>,
+) -> Result<String, PolicyError> {
+    let mut rendered = String::with_capacity(prompt.len());
+    for piece in parse(prompt)? {
+        match piece {
+            Piece::Text(text) => rendered.push_str(text),
+            Piece::Variable(name) => match values.get(name) {
+                Some(value) => rendered.push_str(value),
+                None => {
+                    return Err(PolicyError::MissingVariable {
+                        variable: name.to_string(),
+                        prompt: prompt.to_string(),
+                    })
+                }
+            },
+        }
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn prompts_without_placeholders_are_unchanged() {
+        let prompt = "If the email mentions {braces} or }} alone";
+        assert_eq!(render(prompt, &BTreeMap::new()).unwrap(), prompt);
+        assert!(variables(prompt).unwrap().is_empty());
+    }
+
+    #[test]
+    fn placeholders_are_substituted() {
+        let prompt =
+            "If the email is from {{customer_name}} about {{ product }} or {{customer_name}}";
+        assert_eq!(
+            variables(prompt).unwrap(),
+            vec!["customer_name".to_string(), "product".to_string()]
+        );
+        assert_eq!(
+            render(
+                prompt,
+                &values(&[("customer_name", "Acme"), ("product", "{{widgets}}")])
+            )
+            .unwrap(),
+            "If the email is from Acme about {{widgets}} or Acme"
+        );
+    }
+
+    #[test]
+    fn missing_variables_are_errors() {
+        let err = render("from {{customer_name}}", &values(&[("other", "x")])).unwrap_err();
+        assert!(matches!(
+            err,
+            PolicyError::MissingVariable { ref variable, .. } if variable == "customer_name"
+        ));
+    }
+
+    #[test]
+    fn malformed_placeholders_are_errors() {
+        for (prompt, at) in [
+            ("from {{customer_name", 5),
+            ("x {{}} y", 2),
+            ("{{two words}}", 0),
+            ("ok {{a}} then {{1st}}", 14),
+        ] {
+            match render(prompt, &values(&[("a", "b")])) {
+                Err(PolicyError::MalformedPlaceholder { offset, .. }) => {
+                    assert_eq!(offset, at, "{prompt}")
+                }
+                other => panic!("{prompt}: {other:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,575 @@
+//! Fixtures for testing code that consumes reports.
+//!
+//! Masks are random UUIDs, so a test that wants to feed a [`ReportBuilder`] the IR an LLM would
+//! produce has to build the builder, look up which mask stands for which field, and assemble
+//! the JSON by hand.  [`ReportFixture`] keeps the builder and its policies together, and
+//! [`IrFixture`] writes the IR for any combination of matched rules.
+//!
+//! Code that drives a [`crate::Manager`] can be tested without the network by sending its
+//! requests to a `MockClient`, which replays canned IR.
+//!
+//! # Example
+//!
+//! ```
+//! use policyai::testing::ReportFixture;
+//! use policyai::PolicyType;
+//!
+//! let policy_type =
+//!     PolicyType::parse(r#"type T { urgent: bool = false, priority: ["low", "high"] = "low" }"#)
+//!         .unwrap();
+//! let fixture = ReportFixture::new(policy_type)
+//!     .with_policy("If the email is from the CEO", serde_json::json!({"urgent": true}))
+//!     .with_policy("If the email is a newsletter", serde_json::json!({"priority": "low"}));
+//! let report = fixture.report(&[1]);
+//! assert_eq!(
+//!     report.value(),
+//!     serde_json::json!({"urgent": true, "priority": "low"})
+//! );
+//! ```
+
+use std::collections::BTreeSet;
+#[cfg(feature = "client")]
+use std::collections::VecDeque;
+#[cfg(feature = "client")]
+use std::sync::Mutex;
+
+#[cfg(feature = "client")]
+use claudius::{Message, MessageCreateParams, ToolChoice};
+#[cfg(feature = "client")]
+use futures::future::BoxFuture;
+
+use crate::report_builder::action_output;
+#[cfg(feature = "client")]
+use crate::{tool_use_reply, LlmProvider, Manager};
+use crate::{Policy, PolicyType, Report, ReportBuilder, RuleIndex};
+
+/// A [`ReportBuilder`] together with the policies that were added to it.
+#[derive(Clone)]
+pub struct ReportFixture {
+    policy_type: PolicyType,
+    policies: Vec<Policy>,
+    builder: ReportBuilder,
+}
+
+impl ReportFixture {
+    /// Start a fixture for policies of `policy_type`.
+    pub fn new(policy_type: PolicyType) -> Self {
+        Self {
+            policy_type,
+            policies: vec![],
+            builder: ReportBuilder::default(),
+        }
+    }
+
+    /// Add a policy with `prompt` and `action` as the next rule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `action` does not fit the policy type.
+    pub fn with_policy(mut self, prompt: impl Into<String>, action: serde_json::Value) -> Self {
+        let policy = Policy {
+            r#type: self.policy_type.clone(),
+            prompt: prompt.into(),
+            action,
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        };
+        if let Err(err) = self.builder.add_policy(&policy) {
+            panic!("action does not fit {}: {err}", self.policy_type.name);
+        }
+        self.policies.push(policy);
+        self
+    }
+
+    /// The policies, in rule order.
+    pub fn policies(&self) -> &[Policy] {
+        &self.policies
+    }
+
+    /// A copy of the builder every policy was added to.
+    pub fn builder(&self) -> ReportBuilder {
+        self.builder.clone()
+    }
+
+    /// An IR in which no rule matched, ready to have rules added.
+    pub fn ir(&self) -> IrFixture {
+        let shape = self
+            .builder
+            .clone()
+            .consume_ir(serde_json::json!({}))
+            .expect("an empty IR should always be consumable");
+        let field_of = |mask: &str| {
+            shape
+                .bool_masks
+                .iter()
+                .map(|m| (&m.mask, &m.name))
+                .chain(shape.number_masks.iter().map(|m| (&m.mask, &m.name)))
+                .chain(shape.string_masks.iter().map(|m| (&m.mask, &m.name)))
+                .chain(shape.string_array_masks.iter().map(|m| (&m.mask, &m.name)))
+                .chain(shape.string_enum_masks.iter().map(|m| (&m.mask, &m.name)))
+                .chain(shape.datetime_masks.iter().map(|m| (&m.mask, &m.name)))
+                .find(|(m, _)| m.as_str() == mask)
+                .map(|(_, name)| name.clone())
+        };
+        let mut rules = vec![];
+        for (position, policy) in self.policies.iter().enumerate() {
+            let masks = self
+                .builder
+                .masks_for_rule(RuleIndex::from_position(position))
+                .unwrap_or_default();
+            let mut outputs = vec![];
+            for mask in masks {
+                let output = match field_of(mask) {
+                    Some(field) => {
+                        let is_enum = shape.string_enum_masks.iter().any(|m| &m.mask == mask);
+                        let value = match policy.action.get(&field) {
+                            Some(serde_json::Value::String(_)) if is_enum => true.into(),
+                            Some(value) => action_output(value),
+                            None => serde_json::Value::Null,
+                        };
+                        RuleOutput {
+                            field: Some(field),
+                            mask: mask.clone(),
+                            value,
+                        }
+                    }
+                    // Only an audit rule's indicator mask belongs to no field.
+                    None => RuleOutput {
+                        field: None,
+                        mask: mask.clone(),
+                        value: true.into(),
+                    },
+                };
+                outputs.push(output);
+            }
+            rules.push(outputs);
+        }
+        IrFixture {
+            rules,
+            matched: BTreeSet::new(),
+            rule_numbers: true,
+            justification: None,
+            overrides: vec![],
+        }
+    }
+
+    /// The report for an IR in which exactly the rules numbered in `matched` matched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a rule number is out of range or the builder rejects the IR.
+    pub fn report(&self, matched: &[usize]) -> Report {
+        let ir = matched
+            .iter()
+            .fold(self.ir(), |ir, rule| ir.with_rule(*rule))
+            .build();
+        self.builder()
+            .consume_ir(ir)
+            .expect("the fixture's IR should be consumable")
+    }
+}
+
+/// What the LLM outputs under one mask when the rule owning it matches.
+#[derive(Clone, Debug)]
+struct RuleOutput {
+    field: Option<String>,
+    mask: String,
+    value: serde_json::Value,
+}
+
+/// The IR an LLM would output for a [`ReportFixture`], built up rule by rule.
+///
+/// Deviations from a well-behaved LLM, such as a wrong value or missing rule numbers, can be
+/// layered on top to exercise error handling.
+#[derive(Clone, Debug)]
+pub struct IrFixture {
+    rules: Vec<Vec<RuleOutput>>,
+    matched: BTreeSet<usize>,
+    rule_numbers: bool,
+    justification: Option<String>,
+    overrides: Vec<(usize, String, serde_json::Value)>,
+}
+
+impl IrFixture {
+    /// Mark rule number `rule` as matched, outputting the values of its action.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no rule numbered `rule`.
+    pub fn with_rule(mut self, rule: usize) -> Self {
+        assert!(
+            (1..=self.rules.len()).contains(&rule),
+            "no rule numbered {rule}"
+        );
+        self.matched.insert(rule);
+        self
+    }
+
+    /// Output `value` for `field` under rule number `rule`, instead of what its action says.
+    ///
+    /// The value is output even if the rule is not marked as matched, which is how an LLM
+    /// whose output disagrees with its rule numbers looks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if rule `rule` does not set `field`.
+    pub fn with_value(
+        mut self,
+        rule: usize,
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        let field = field.into();
+        let sets_field = rule >= 1
+            && self.rules.get(rule - 1).is_some_and(|outputs| {
+                outputs
+                    .iter()
+                    .any(|output| output.field.as_deref() == Some(field.as_str()))
+            });
+        assert!(sets_field, "rule {rule} does not set {field:?}");
+        self.overrides.push((rule, field, value.into()));
+        self
+    }
+
+    /// Leave `__rule_numbers__` out of the IR.
+    pub fn without_rule_numbers(mut self) -> Self {
+        self.rule_numbers = false;
+        self
+    }
+
+    /// Set `__justification__`.
+    pub fn with_justification(mut self, justification: impl Into<String>) -> Self {
+        self.justification = Some(justification.into());
+        self
+    }
+
+    /// The IR as JSON.
+    pub fn build(&self) -> serde_json::Value {
+        let mut ir = serde_json::Map::new();
+        if self.rule_numbers {
+            ir.insert(
+                "__rule_numbers__".to_string(),
+                self.matched.iter().copied().collect::<Vec<_>>().into(),
+            );
+        }
+        if let Some(justification) = &self.justification {
+            ir.insert(
+                "__justification__".to_string(),
+                justification.clone().into(),
+            );
+        }
+        for rule in self.matched.iter() {
+            for output in self.rules[rule - 1].iter() {
+                ir.insert(output.mask.clone(), output.value.clone());
+            }
+        }
+        for (rule, field, value) in self.overrides.iter() {
+            for output in self.rules[rule - 1].iter() {
+                if output.field.as_deref() == Some(field.as_str()) {
+                    ir.insert(output.mask.clone(), value.clone());
+                }
+            }
+        }
+        serde_json::Value::Object(ir)
+    }
+}
+
+/// An [`LlmProvider`] that answers from a script instead of the network.
+///
+/// Each request takes the next scripted reply, so a script of a bad IR followed by a good one
+/// drives [`Manager::apply`] through a retry.  Once the script runs out, requests get the IR
+/// given to [`MockClient::replaying`], or fail if there is none.  Every reply is answered with
+/// the tool the request forces and reports the same token usage.
+///
+/// Masks are random, so a manager under test should show the LLM the real names of the fields
+/// the script sets with [`Manager::with_unmasked_fields`].  The value of `field` under rule
+/// number `n` is then keyed `"field@n"`.
+///
+/// # Example
+///
+/// ```
+/// # use claudius::{KnownModel, MessageCreateParams, Model};
+/// # use policyai::testing::MockClient;
+/// # use policyai::{Manager, Policy, PolicyType, Usage};
+/// # futures::executor::block_on(async {
+/// let policy_type = PolicyType::parse("type T { urgent: bool = false }").unwrap();
+/// let mut manager = Manager::default().with_unmasked_fields(["urgent"]);
+/// manager.add(Policy {
+///     r#type: policy_type,
+///     prompt: "If the email is from the CEO".to_string(),
+///     action: serde_json::json!({"urgent": true}),
+///     precondition: None,
+///     exact_match: None,
+///     explanation: None,
+///     priority: None,
+/// });
+/// // The first answer claims rule 1 matched without setting its field.
+/// let client = MockClient::new()
+///     .with_reply(serde_json::json!({"__rule_numbers__": [1]}))
+///     .with_reply(serde_json::json!({"__rule_numbers__": [1], "urgent@1": true}));
+/// let template = MessageCreateParams {
+///     max_tokens: 1024,
+///     model: Model::Known(KnownModel::ClaudeSonnet40),
+///     ..Default::default()
+/// };
+/// let mut usage = Usage::new();
+/// let report = manager
+///     .apply(&client, template, "From: ceo@example.com", Some(&mut usage))
+///     .await
+///     .unwrap();
+/// assert_eq!(report.value()["urgent"], true);
+/// assert_eq!(usage.inconsistency_retries, 1);
+/// assert_eq!(client.requests().len(), 2);
+/// # });
+/// ```
+#[cfg(feature = "client")]
+#[derive(Debug)]
+pub struct MockClient {
+    script: Mutex<VecDeque<Result<serde_json::Value, claudius::Error>>>,
+    replay: Option<serde_json::Value>,
+    usage: claudius::Usage,
+    requests: Mutex<Vec<MessageCreateParams>>,
+}
+
+#[cfg(feature = "client")]
+impl MockClient {
+    /// A client with an empty script, which fails every request until replies are added.
+    pub fn new() -> Self {
+        Self {
+            script: Mutex::new(VecDeque::new()),
+            replay: None,
+            usage: claudius::Usage::new(100, 10),
+            requests: Mutex::new(vec![]),
+        }
+    }
+
+    /// A client that answers every request with `ir` once its script runs out.
+    pub fn replaying(ir: serde_json::Value) -> Self {
+        Self {
+            replay: Some(ir),
+            ..Self::new()
+        }
+    }
+
+    /// Answer the next unanswered request with `ir`.
+    pub fn with_reply(self, ir: serde_json::Value) -> Self {
+        self.script.lock().unwrap().push_back(Ok(ir));
+        self
+    }
+
+    /// Fail the next unanswered request with `err`, as a rate limit or an outage would.
+    pub fn with_error(self, err: claudius::Error) -> Self {
+        self.script.lock().unwrap().push_back(Err(err));
+        self
+    }
+
+    /// Report `usage` on every reply.  Defaults to 100 input and 10 output tokens.
+    pub fn with_usage(mut self, usage: claudius::Usage) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// The requests received so far, in order, including the corrections sent on retries.
+    pub fn requests(&self) -> Vec<MessageCreateParams> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The number of scripted replies not yet sent.
+    pub fn remaining(&self) -> usize {
+        self.script.lock().unwrap().len()
+    }
+}
+
+#[cfg(feature = "client")]
+impl Default for MockClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "client")]
+impl LlmProvider for MockClient {
+    fn send(&self, req: MessageCreateParams) -> BoxFuture<'_, Result<Message, claudius::Error>> {
+        let reply = {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(req.clone());
+            let id = format!("msg_mock_{}", requests.len());
+            let tool = match &req.tool_choice {
+                Some(ToolChoice::Tool { name, .. }) => name.clone(),
+                _ => Manager::DEFAULT_TOOL_NAME.to_string(),
+            };
+            match self.script.lock().unwrap().pop_front() {
+                Some(Ok(ir)) => Ok(tool_use_reply(id, req.model, tool, ir, self.usage)),
+                Some(Err(err)) => Err(err),
+                None => match &self.replay {
+                    Some(ir) => Ok(tool_use_reply(id, req.model, tool, ir.clone(), self.usage)),
+                    None => Err(claudius::Error::unknown(format!(
+                        "the mock client's script ran out at request {}",
+                        requests.len()
+                    ))),
+                },
+            }
+        };
+        Box::pin(async move { reply })
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod tests {
+    use super::*;
+    use crate::Conflict;
+
+    fn fixture() -> ReportFixture {
+        let policy_type = PolicyType::parse(
+            r#"type T {
+                urgent: bool = false,
+                score: number @ largest wins = 0,
+                priority: ["low", "high"] @ agreement = "low",
+                tags: [string],
+            }"#,
+        )
+        .unwrap();
+        ReportFixture::new(policy_type)
+            .with_policy(
+                "from the CEO",
+                serde_json::json!({"urgent": true, "score": 3}),
+            )
+            .with_policy(
+                "mentions an outage",
+                serde_json::json!({"priority": "high", "tags": ["ops"]}),
+            )
+            .with_policy("mentions a lawsuit", serde_json::json!({}))
+    }
+
+    #[test]
+    fn report_for_matched_rules() {
+        let fixture = fixture();
+        assert_eq!(fixture.policies().len(), 3);
+        let report = fixture.report(&[]);
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"urgent": false, "score": 0.0, "priority": "low"})
+        );
+        let report = fixture.report(&[1, 2, 3]);
+        assert_eq!(
+            report.value(),
+            serde_json::json!({"urgent": true, "score": 3, "priority": "high", "tags": ["ops"]})
+        );
+        assert_eq!(
+            report.matched_audit_rules(),
+            vec![RuleIndex::from_position(2)]
+        );
+    }
+
+    #[test]
+    fn ir_deviations() {
+        let fixture = fixture();
+        let ir = fixture
+            .ir()
+            .with_rule(1)
+            .with_value(1, "score", 7)
+            .with_justification("because")
+            .build();
+        assert_eq!(ir["__rule_numbers__"], serde_json::json!([1]));
+        assert_eq!(ir["__justification__"], "because");
+        let report = fixture.builder().consume_ir(ir).unwrap();
+        assert!(matches!(
+            report.conflicts(),
+            [Conflict::NumberConflict { field, .. }] if field == "score"
+        ));
+
+        let ir = fixture.ir().with_rule(2).without_rule_numbers().build();
+        assert!(ir.get("__rule_numbers__").is_none());
+    }
+
+    #[test]
+    fn operators_accumulate_across_rules() {
+        let policy_type =
+            PolicyType::parse("type T { score: number = 1, tags: [string] }").unwrap();
+        let fixture = ReportFixture::new(policy_type)
+            .with_policy("from the CEO", serde_json::json!({"score": {"$add": 10}}))
+            .with_policy(
+                "mentions an outage",
+                serde_json::json!({"score": {"$add": 5}}),
+            )
+            .with_policy(
+                "is a customer",
+                serde_json::json!({"tags": {"$append": "vip"}}),
+            );
+        assert_eq!(
+            fixture.report(&[1, 2, 3]).value(),
+            serde_json::json!({"score": 16, "tags": ["vip"]})
+        );
+        assert_eq!(fixture.report(&[2]).value()["score"], 6);
+        assert_eq!(fixture.report(&[]).value()["score"], 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "no rule numbered 4")]
+    fn unknown_rule_panics() {
+        fixture().ir().with_rule(4);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn mock_client_drives_retries_and_usage() {
+        use claudius::{KnownModel, Model};
+
+        let policy_type = PolicyType::parse("type T { urgent: bool = false }").unwrap();
+        let mut manager = Manager::default()
+            .with_unmasked_fields(["urgent"])
+            .with_tool_name("output_T");
+        manager.add(Policy {
+            r#type: policy_type,
+            prompt: "from the CEO".to_string(),
+            action: serde_json::json!({"urgent": true}),
+            precondition: None,
+            exact_match: None,
+            explanation: None,
+            priority: None,
+        });
+        let template = MessageCreateParams {
+            max_tokens: 1024,
+            model: Model::Known(KnownModel::ClaudeSonnet40),
+            ..Default::default()
+        };
+
+        let client = MockClient::replaying(serde_json::json!({"__rule_numbers__": []}))
+            .with_reply(serde_json::json!({"__rule_numbers__": [], "urgent@1": true}))
+            .with_usage(claudius::Usage::new(50, 5));
+        let mut usage = crate::Usage::new();
+        let report = manager
+            .apply(&client, template.clone(), "hello", Some(&mut usage))
+            .await
+            .unwrap();
+        assert_eq!(report.value()["urgent"], false);
+        assert_eq!(report.requests, 2);
+        assert_eq!(report.summary().retries, 1);
+        assert_eq!(client.remaining(), 0);
+        assert_eq!(usage.iterations, 2);
+        assert_eq!(usage.requests, 2);
+        assert_eq!(usage.inconsistency_retries, 1);
+        assert_eq!(usage.message_ids, vec!["msg_mock_1", "msg_mock_2"]);
+        assert_eq!(usage.claudius_usage, Some(claudius::Usage::new(100, 10)));
+        assert_eq!(
+            usage.retry_claudius_usage,
+            Some(claudius::Usage::new(50, 5))
+        );
+        let requests = client.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].messages.len() > requests[0].messages.len());
+
+        let client = MockClient::new().with_error(claudius::Error::authentication("bad key"));
+        assert!(manager
+            .apply(&client, template.clone(), "hello", None)
+            .await
+            .is_err());
+        assert!(manager
+            .apply(&client, template, "hello", None)
+            .await
+            .is_err());
+        assert_eq!(client.requests().len(), 2);
+    }
+}
@@ -0,0 +1,128 @@
+//! Output translation tables.
+//!
+//! Downstream systems sometimes want codes rather than the labels a policy type is written in,
+//! e.g. `3` where the type says `"high"`.  [`Translations`] maps individual field values to
+//! replacements that [`crate::Report::value_with_translations`] applies on the way out, leaving
+//! [`crate::Report::value`] untouched.
+
+use std::collections::BTreeMap;
+
+/// Per-field tables that map output values to their translations.
+///
+/// Only string values are translated, including the elements of string arrays.  Values without
+/// an entry, and fields without a table, are output as they are.
+///
+/// # Example
+///
+/// ```
+/// # use policyai::Translations;
+/// let translations = Translations::default()
+///     .with_translation("priority", "low", 1)
+///     .with_translation("priority", "high", 3);
+/// assert_eq!(
+///     translations.apply(serde_json::json!({"priority": "high", "label": "high"})),
+///     serde_json::json!({"priority": 3, "label": "high"}),
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct Translations {
+    fields: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+}
+
+impl Translations {
+    /// Output `to` wherever `field` would be `value`.
+    pub fn with_translation(
+        mut self,
+        field: impl Into<String>,
+        value: impl Into<String>,
+        to: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.fields
+            .entry(field.into())
+            .or_default()
+            .insert(value.into(), to.into());
+        self
+    }
+
+    /// True if no translation has been declared.
+    pub fn is_empty(&self) -> bool {
+        self.fields.values().all(BTreeMap::is_empty)
+    }
+
+    /// The translation of `value` for `field`, if one was declared.
+    pub fn get(&self, field: &str, value: &str) -> Option<&serde_json::Value> {
+        self.fields.get(field)?.get(value)
+    }
+
+    /// Translate the fields of `output`, an object of field values.
+    pub fn apply(&self, mut output: serde_json::Value) -> serde_json::Value {
+        let serde_json::Value::Object(obj) = &mut output else {
+            return output;
+        };
+        for (field, table) in self.fields.iter() {
+            let Some(value) = obj.get_mut(field) else {
+                continue;
+            };
+            match value {
+                serde_json::Value::String(s) => {
+                    if let Some(to) = table.get(s.as_str()) {
+                        *value = to.clone();
+                    }
+                }
+                serde_json::Value::Array(elements) => {
+                    for element in elements.iter_mut() {
+                        if let Some(to) = element.as_str().and_then(|s| table.get(s)) {
+                            *element = to.clone();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_scalars_and_array_elements() {
+        let translations = Translations::default()
+            .with_translation("priority", "high", 3)
+            .with_translation("tags", "spam", "SP")
+            .with_translation("missing", "x", "y");
+        assert_eq!(
+            translations.apply(serde_json::json!({
+                "priority": "high",
+                "tags": ["spam", "ham"],
+                "other": "high",
+                "count": 3,
+            })),
+            serde_json::json!({
+                "priority": 3,
+                "tags": ["SP", "ham"],
+                "other": "high",
+                "count": 3,
+            })
+        );
+        assert_eq!(
+            translations.apply(serde_json::json!({"priority": "low"})),
+            serde_json::json!({"priority": "low"})
+        );
+    }
+
+    #[test]
+    fn round_trips_through_serde() {
+        let translations = Translations::default().with_translation("priority", "high", 3);
+        let json = serde_json::to_value(&translations).unwrap();
+        assert_eq!(json, serde_json::json!({"priority": {"high": 3}}));
+        assert_eq!(
+            serde_json::from_value::<Translations>(json).unwrap(),
+            translations
+        );
+        assert!(Translations::default().is_empty());
+    }
+}
@@ -19,6 +19,38 @@ pub struct Usage {
     pub wall_clock_time: Duration,
     /// Number of iterations needed (for retry logic)
     pub iterations: usize,
+    /// Number of API calls made, whether or not they returned a message
+    #[serde(default)]
+    pub requests: usize,
+    /// Ids of the messages returned by each API call, in the order the calls were made
+    #[serde(default)]
+    pub message_ids: Vec<String>,
+    /// Token usage of retries alone, i.e. every API call after the first
+    #[serde(default)]
+    pub retry_claudius_usage: Option<ClaudiusUsage>,
+    /// Number of reports served from an [`crate::ApplyCache`]
+    #[serde(default)]
+    pub cache_hits: usize,
+    /// Number of cache lookups that had to go to the LLM
+    #[serde(default)]
+    pub cache_misses: usize,
+    /// Number of extra round-trips made because a [`crate::Verification`] heuristic fired
+    #[serde(default)]
+    pub verifications: usize,
+    /// Number of extra round-trips made because a [`crate::Validator`] rejected the output
+    #[serde(default)]
+    pub validation_retries: usize,
+    /// Number of round-trips retried because the output was inconsistent, such as rule numbers
+    /// that disagree with the values output
+    #[serde(default)]
+    pub inconsistency_retries: usize,
+    /// Number of applies retried because the transport failed, such as a rate limit or timeout
+    #[serde(default)]
+    pub transport_retries: usize,
+    /// Output tokens spent on extended thinking.  The API counts these as output tokens without
+    /// breaking them out, so this is only set by callers that can attribute them.
+    #[serde(default)]
+    pub thinking_tokens: u64,
 }
 
 impl Usage {
@@ -35,13 +67,214 @@ impl Usage {
         });
     }
 
+    /// Add claudius usage of a retry.  Retries also count toward the total, which the caller
+    /// adds separately with [`Usage::add_claudius_usage`].
+    pub fn add_retry_claudius_usage(&mut self, usage: ClaudiusUsage) {
+        self.retry_claudius_usage = Some(match self.retry_claudius_usage {
+            Some(existing) => existing + usage,
+            None => usage,
+        });
+    }
+
     /// Increment the iteration counter
     pub fn increment_iterations(&mut self) {
         self.iterations += 1;
     }
 
+    /// Record one API call
+    pub fn increment_requests(&mut self) {
+        self.requests += 1;
+    }
+
+    /// Record the id of the message one API call returned
+    pub fn add_message_id(&mut self, message_id: impl Into<String>) {
+        self.message_ids.push(message_id.into());
+    }
+
+    /// Record a cache hit
+    pub fn increment_cache_hits(&mut self) {
+        self.cache_hits += 1;
+    }
+
+    /// Record a cache miss
+    pub fn increment_cache_misses(&mut self) {
+        self.cache_misses += 1;
+    }
+
+    /// Record a double-check requested by a [`crate::Verification`] heuristic
+    pub fn increment_verifications(&mut self) {
+        self.verifications += 1;
+    }
+
+    /// Record a correction requested because a [`crate::Validator`] rejected the output
+    pub fn increment_validation_retries(&mut self) {
+        self.validation_retries += 1;
+    }
+
+    /// Record a round-trip retried because the output was inconsistent
+    pub fn increment_inconsistency_retries(&mut self) {
+        self.inconsistency_retries += 1;
+    }
+
+    /// Record an apply retried because the transport failed
+    pub fn increment_transport_retries(&mut self) {
+        self.transport_retries += 1;
+    }
+
+    /// Add output tokens spent on extended thinking
+    pub fn add_thinking_tokens(&mut self, tokens: u64) {
+        self.thinking_tokens += tokens;
+    }
+
     /// Set the wall clock time
     pub fn set_wall_clock_time(&mut self, duration: Duration) {
         self.wall_clock_time = duration;
     }
+
+    /// Uncached input tokens across all API calls
+    pub fn input_tokens(&self) -> u64 {
+        self.claudius_usage
+            .map_or(0, |usage| usage.input_tokens.max(0) as u64)
+    }
+
+    /// Output tokens across all API calls, thinking included
+    pub fn output_tokens(&self) -> u64 {
+        self.claudius_usage
+            .map_or(0, |usage| usage.output_tokens.max(0) as u64)
+    }
+
+    /// Input tokens written to the prompt cache across all API calls
+    pub fn cache_creation_input_tokens(&self) -> u64 {
+        self.claudius_usage
+            .and_then(|usage| usage.cache_creation_input_tokens)
+            .map_or(0, |tokens| tokens.max(0) as u64)
+    }
+
+    /// Input tokens read from the prompt cache across all API calls
+    pub fn cache_read_input_tokens(&self) -> u64 {
+        self.claudius_usage
+            .and_then(|usage| usage.cache_read_input_tokens)
+            .map_or(0, |tokens| tokens.max(0) as u64)
+    }
+
+    /// The cost in dollars of this usage when made against `model`, priced with the
+    /// process-wide [`crate::Pricing`] registry.  `None` if the model has no price.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::Usage;
+    ///
+    /// let mut usage = Usage::new();
+    /// usage.add_claudius_usage(claudius::Usage::new(1_000_000, 0));
+    /// assert_eq!(usage.cost_usd("claude-sonnet-4-5"), Some(3.0));
+    /// assert_eq!(usage.cost_usd("unknown-model"), None);
+    /// ```
+    pub fn cost_usd(&self, model: &str) -> Option<f64> {
+        let price = crate::Pricing::global_price(model)?;
+        Some(price.cost(
+            self.input_tokens(),
+            self.output_tokens(),
+            self.cache_creation_input_tokens(),
+            self.cache_read_input_tokens(),
+        ))
+    }
+
+    /// Fold `other` into this usage.  Counters and tokens add up, as does wall clock time, so
+    /// merging concurrent operations overstates their elapsed time.
+    pub fn merge(&mut self, other: &Usage) {
+        if let Some(usage) = other.claudius_usage {
+            self.add_claudius_usage(usage);
+        }
+        if let Some(usage) = other.retry_claudius_usage {
+            self.add_retry_claudius_usage(usage);
+        }
+        self.wall_clock_time += other.wall_clock_time;
+        self.iterations += other.iterations;
+        self.requests += other.requests;
+        self.message_ids.extend(other.message_ids.iter().cloned());
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        self.verifications += other.verifications;
+        self.validation_retries += other.validation_retries;
+        self.inconsistency_retries += other.inconsistency_retries;
+        self.transport_retries += other.transport_retries;
+        self.thinking_tokens += other.thinking_tokens;
+    }
+
+    /// A flat JSON object of every dimension, for logs and dashboards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use policyai::Usage;
+    ///
+    /// let mut usage = Usage::new();
+    /// usage.add_claudius_usage(claudius::Usage::new(100, 20).with_cache_read_input_tokens(900));
+    /// usage.increment_inconsistency_retries();
+    /// let summary = usage.to_json_summary();
+    /// assert_eq!(summary["input_tokens"], 100);
+    /// assert_eq!(summary["cache_read_input_tokens"], 900);
+    /// assert_eq!(summary["inconsistency_retries"], 1);
+    /// ```
+    pub fn to_json_summary(&self) -> serde_json::Value {
+        let retry = self.retry_claudius_usage;
+        serde_json::json!({
+            "input_tokens": self.input_tokens(),
+            "output_tokens": self.output_tokens(),
+            "cache_creation_input_tokens": self.cache_creation_input_tokens(),
+            "cache_read_input_tokens": self.cache_read_input_tokens(),
+            "thinking_tokens": self.thinking_tokens,
+            "retry_input_tokens": retry.map_or(0, |usage| usage.input_tokens.max(0) as u64),
+            "retry_output_tokens": retry.map_or(0, |usage| usage.output_tokens.max(0) as u64),
+            "requests": self.requests,
+            "iterations": self.iterations,
+            "inconsistency_retries": self.inconsistency_retries,
+            "transport_retries": self.transport_retries,
+            "verifications": self.verifications,
+            "validation_retries": self.validation_retries,
+            "cache_hits": self.cache_hits,
+            "cache_misses": self.cache_misses,
+            "wall_clock_ms": self.wall_clock_time.as_millis() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_adds_every_dimension() {
+        let mut a = Usage::new();
+        a.add_claudius_usage(ClaudiusUsage::new(10, 1).with_cache_creation_input_tokens(50));
+        a.increment_iterations();
+        a.increment_requests();
+        a.add_message_id("a");
+        a.increment_transport_retries();
+        let mut b = Usage::new();
+        b.add_claudius_usage(ClaudiusUsage::new(20, 2));
+        b.add_retry_claudius_usage(ClaudiusUsage::new(20, 2));
+        b.increment_iterations();
+        b.increment_requests();
+        b.increment_requests();
+        b.add_message_id("b");
+        b.increment_inconsistency_retries();
+        b.add_thinking_tokens(7);
+        b.set_wall_clock_time(Duration::from_millis(40));
+        a.merge(&b);
+        assert_eq!(a.input_tokens(), 30);
+        assert_eq!(a.output_tokens(), 3);
+        assert_eq!(a.cache_creation_input_tokens(), 50);
+        assert_eq!(a.cache_read_input_tokens(), 0);
+        assert_eq!(a.message_ids, vec!["a", "b"]);
+        let summary = a.to_json_summary();
+        assert_eq!(summary["iterations"], 2);
+        assert_eq!(summary["requests"], 3, "b's failed request counts too");
+        assert_eq!(summary["retry_input_tokens"], 20);
+        assert_eq!(summary["transport_retries"], 1);
+        assert_eq!(summary["inconsistency_retries"], 1);
+        assert_eq!(summary["thinking_tokens"], 7);
+        assert_eq!(summary["wall_clock_ms"], 40);
+    }
 }
@@ -0,0 +1,215 @@
+//! Persistence of per-apply usage for budgeting.
+//!
+//! A [`Usage`] describes one apply and is gone when the caller drops it.  A [`crate::Manager`]
+//! configured with a [`UsageLog`] also writes a [`UsageRecord`] for every apply, so that spend
+//! can be rolled up by day and model long after the process exits; `policyai-usage-summary`
+//! does exactly that for a [`JsonlUsageLog`].
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::Usage;
+
+/// What one apply consumed.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct UsageRecord {
+    /// When the apply finished, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The model the apply was sent to.
+    pub model: String,
+    /// The policy hash of the apply's [`crate::CacheKey`], as 32 hex digits.  Applies of the same
+    /// policies with the same settings share it.
+    pub policy_set_hash: String,
+    /// Uncached input tokens.
+    pub input_tokens: u64,
+    /// Output tokens.
+    pub output_tokens: u64,
+    /// Input tokens written to the prompt cache.
+    pub cache_creation_input_tokens: u64,
+    /// Input tokens read from the prompt cache.
+    pub cache_read_input_tokens: u64,
+    /// The cost in US dollars, or `None` if the model has no price in [`crate::Pricing`].
+    pub cost_usd: Option<f64>,
+    /// How long the apply took, in milliseconds.
+    pub duration_ms: u64,
+    /// Requests sent to the LLM, retries included.
+    pub iterations: usize,
+    /// True if the report came from the manager's cache.
+    pub cache_hit: bool,
+    /// True if the apply returned a report.
+    pub succeeded: bool,
+}
+
+impl UsageRecord {
+    /// A record of `usage`, stamped with the current time.
+    pub fn new(
+        model: impl Into<String>,
+        policy_set_hash: impl Into<String>,
+        usage: &Usage,
+        succeeded: bool,
+    ) -> Self {
+        let model = model.into();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            timestamp,
+            cost_usd: usage.cost_usd(&model),
+            model,
+            policy_set_hash: policy_set_hash.into(),
+            input_tokens: usage.input_tokens(),
+            output_tokens: usage.output_tokens(),
+            cache_creation_input_tokens: usage.cache_creation_input_tokens(),
+            cache_read_input_tokens: usage.cache_read_input_tokens(),
+            duration_ms: usage.wall_clock_time.as_millis() as u64,
+            iterations: usage.iterations,
+            cache_hit: usage.cache_hits > 0,
+            succeeded,
+        }
+    }
+
+    /// The UTC day of the timestamp, as `YYYY-MM-DD`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Usage, UsageRecord};
+    /// let mut record = UsageRecord::new("claude-haiku-4-5", "", &Usage::new(), true);
+    /// record.timestamp = 1_700_000_000;
+    /// assert_eq!(record.day(), "2023-11-14");
+    /// ```
+    pub fn day(&self) -> String {
+        // Howard Hinnant's civil_from_days, on days since 1970-01-01.
+        let days = (self.timestamp / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let doe = days.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+
+    /// Every token the apply consumed, of any kind.
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+}
+
+/// A sink for per-apply usage.
+///
+/// Implementations must be safe to share between the clones of a [`crate::Manager`].
+pub trait UsageLog: std::fmt::Debug + Send + Sync {
+    /// Persist `record`.
+    ///
+    /// The manager returns the apply's result whether or not this succeeds.
+    fn record(&self, record: UsageRecord) -> std::io::Result<()>;
+}
+
+/// A [`UsageLog`] that appends one JSON line per apply to a file.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use policyai::{JsonlUsageLog, Manager};
+///
+/// let log = Arc::new(JsonlUsageLog::new("usage.jsonl"));
+/// let manager = Manager::default().with_usage_log(log);
+/// ```
+#[derive(Debug)]
+pub struct JsonlUsageLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonlUsageLog {
+    /// Append records to the file at `path`, creating it on the first apply.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The file records are appended to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read every record in the file at `path`, skipping blank lines.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Vec<UsageRecord>> {
+        let mut records = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push(serde_json::from_str(&line)?);
+        }
+        Ok(records)
+    }
+}
+
+impl UsageLog for JsonlUsageLog {
+    fn record(&self, record: UsageRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        let _guard = self.lock.lock().unwrap();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_handles_epoch_and_leap_days() {
+        let mut record = UsageRecord::new("m", "", &Usage::new(), true);
+        for (timestamp, day) in [
+            (0, "1970-01-01"),
+            (951_782_400, "2000-02-29"),
+            (1_709_251_199, "2024-02-29"),
+            (1_709_251_200, "2024-03-01"),
+        ] {
+            record.timestamp = timestamp;
+            assert_eq!(record.day(), day);
+        }
+    }
+
+    #[test]
+    fn jsonl_log_appends_and_loads() {
+        let path =
+            std::env::temp_dir().join(format!("policyai-usage-{}.jsonl", uuid::Uuid::new_v4()));
+        let log = JsonlUsageLog::new(&path);
+        let mut usage = Usage::new();
+        usage.add_claudius_usage(claudius::Usage::new(1_000_000, 100));
+        usage.increment_iterations();
+        log.record(UsageRecord::new("claude-sonnet-4-5", "ab", &usage, true))
+            .unwrap();
+        log.record(UsageRecord::new("unpriced", "ab", &Usage::new(), false))
+            .unwrap();
+        let loaded = JsonlUsageLog::load(log.path()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].total_tokens(), 1_000_100);
+        assert_eq!(loaded[0].iterations, 1);
+        assert!((loaded[0].cost_usd.unwrap() - 3.0015).abs() < 1e-9);
+        assert_eq!(loaded[1].cost_usd, None);
+        assert!(!loaded[1].succeeded);
+    }
+}
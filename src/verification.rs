@@ -0,0 +1,283 @@
+//! A second look at reports that are probably wrong.
+//!
+//! A report can be internally consistent, with every matched rule accounted for, and still be a
+//! silent mis-extraction.  [`Verification`] holds heuristics that flag such reports; when one
+//! fires, the [`crate::Manager`] asks the LLM to double-check its output once before accepting
+//! it.
+
+use std::sync::Arc;
+
+use crate::Report;
+
+/// The name of the property the LLM fills with its confidence when a threshold is set.
+pub(crate) const CONFIDENCE_FIELD: &str = "__confidence__";
+
+/// Two field values that should never appear in the same output.
+///
+/// For example, `priority = "low"` alongside `urgent = true` usually means one of the two was
+/// extracted wrongly.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Contradiction {
+    /// The first field.
+    pub field: String,
+    /// The value of the first field.
+    pub value: serde_json::Value,
+    /// The second field.
+    pub conflicts_with: String,
+    /// The value of the second field that contradicts the first.
+    pub conflicting_value: serde_json::Value,
+}
+
+impl Contradiction {
+    /// Flag outputs where `field` is `value` and `conflicts_with` is `conflicting_value`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::Contradiction;
+    /// let contradiction = Contradiction::new("priority", "low", "urgent", true);
+    /// ```
+    pub fn new(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+        conflicts_with: impl Into<String>,
+        conflicting_value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            value: value.into(),
+            conflicts_with: conflicts_with.into(),
+            conflicting_value: conflicting_value.into(),
+        }
+    }
+
+    fn holds(&self, output: &serde_json::Value) -> bool {
+        output.get(&self.field) == Some(&self.value)
+            && output.get(&self.conflicts_with) == Some(&self.conflicting_value)
+    }
+}
+
+/// Heuristics that trigger a double-check of a report.
+///
+/// Every heuristic is off by default.  At most one extra round-trip is made per call, and it is
+/// counted in [`crate::Usage::verifications`].
+///
+/// # Example
+///
+/// ```
+/// # use policyai::{Contradiction, Verification};
+/// let verification = Verification::default()
+///     .with_all_defaults()
+///     .with_contradiction(Contradiction::new("priority", "low", "urgent", true))
+///     .with_min_confidence(0.8);
+/// assert!(verification.is_enabled());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Verification {
+    /// Double-check outputs in which every field holds its default.
+    pub all_defaults: bool,
+    /// Double-check outputs that contain any of these pairs of values.
+    pub contradictions: Vec<Contradiction>,
+    /// Ask the LLM for its confidence and double-check outputs below this value in `[0, 1]`.
+    pub min_confidence: Option<f64>,
+}
+
+impl Verification {
+    /// Double-check outputs in which every field holds its default.
+    pub fn with_all_defaults(mut self) -> Self {
+        self.all_defaults = true;
+        self
+    }
+
+    /// Double-check outputs that contain `contradiction`.
+    pub fn with_contradiction(mut self, contradiction: Contradiction) -> Self {
+        self.contradictions.push(contradiction);
+        self
+    }
+
+    /// Double-check outputs whose self-reported confidence is below `min_confidence`.
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = Some(min_confidence);
+        self
+    }
+
+    /// True if any heuristic is turned on.
+    pub fn is_enabled(&self) -> bool {
+        self.all_defaults || !self.contradictions.is_empty() || self.min_confidence.is_some()
+    }
+
+    /// Describe, for the LLM, each reason `report` looks wrong.  Empty if nothing is suspicious.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use policyai::{Report, Verification};
+    /// let report = Report::from_value(
+    ///     serde_json::json!({"urgent": false}),
+    ///     serde_json::json!({"urgent": false}),
+    /// );
+    /// assert!(Verification::default().concerns(&report).is_empty());
+    /// assert_eq!(Verification::default().with_all_defaults().concerns(&report).len(), 1);
+    /// ```
+    pub fn concerns(&self, report: &Report) -> Vec<String> {
+        let mut concerns = vec![];
+        let output = report.declared_value();
+        if self.all_defaults
+            && report.default.as_ref().is_some_and(|default| {
+                output.as_object().is_some_and(|fields| !fields.is_empty()) && *default == output
+            })
+        {
+            concerns.push(
+                "Every field holds its default value.  Check whether a rule matches after all."
+                    .to_string(),
+            );
+        }
+        for contradiction in self.contradictions.iter() {
+            if contradiction.holds(&output) {
+                concerns.push(format!(
+                    "{:?} is {} while {:?} is {}, which should not happen together.",
+                    contradiction.field,
+                    contradiction.value,
+                    contradiction.conflicts_with,
+                    contradiction.conflicting_value
+                ));
+            }
+        }
+        if let Some(min_confidence) = self.min_confidence {
+            let confidence = report
+                .ir
+                .as_ref()
+                .and_then(|ir| ir.get(CONFIDENCE_FIELD))
+                .and_then(serde_json::Value::as_f64);
+            match confidence {
+                Some(confidence) if confidence >= min_confidence => {}
+                Some(confidence) => concerns.push(format!(
+                    "You reported a confidence of {confidence}.  Re-read the rules and the text."
+                )),
+                None => concerns.push(format!("{CONFIDENCE_FIELD:?} was not set.")),
+            }
+        }
+        concerns
+    }
+}
+
+/// The closure behind a [`Validator`].
+type Check = dyn Fn(&serde_json::Value) -> Result<(), String> + Send + Sync;
+
+/// A business rule the final value of every report must satisfy.
+///
+/// Wraps a closure that returns `Err` with a message, written for the LLM, when the value breaks
+/// the rule.  Where [`Verification`] flags reports that are probably wrong, a validator rejects
+/// reports that are certainly wrong: a [`crate::Manager`] returns
+/// [`crate::ApplyError::ValidationFailed`] instead of such a report.
+///
+/// # Example
+///
+/// ```
+/// # use policyai::Validator;
+/// let validator = Validator::new(|value| match value["refund"].as_f64() {
+///     Some(refund) if refund > 500.0 => Err(format!("refund {refund} exceeds the limit of 500")),
+///     _ => Ok(()),
+/// });
+/// assert!(validator.check(&serde_json::json!({"refund": 20})).is_ok());
+/// assert!(validator.check(&serde_json::json!({"refund": 900})).is_err());
+/// ```
+#[derive(Clone)]
+pub struct Validator {
+    check: Arc<Check>,
+}
+
+impl Validator {
+    /// A validator that runs `check` on each report's value.
+    pub fn new(
+        check: impl Fn(&serde_json::Value) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            check: Arc::new(check),
+        }
+    }
+
+    /// Check `value`, returning the reason it breaks the rule if it does.
+    pub fn check(&self, value: &serde_json::Value) -> Result<(), String> {
+        (self.check)(value)
+    }
+}
+
+impl std::fmt::Debug for Validator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Validator").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(output: serde_json::Value, ir: serde_json::Value) -> Report {
+        let mut report = Report::from_value(
+            output,
+            serde_json::json!({"priority": "medium", "urgent": false}),
+        );
+        report.ir = Some(ir);
+        report
+    }
+
+    #[test]
+    fn nothing_is_suspicious_by_default() {
+        let report = report(
+            serde_json::json!({"priority": "medium", "urgent": false}),
+            serde_json::json!({}),
+        );
+        assert!(!Verification::default().is_enabled());
+        assert!(Verification::default().concerns(&report).is_empty());
+    }
+
+    #[test]
+    fn all_defaults_are_suspicious() {
+        let verification = Verification::default().with_all_defaults();
+        let defaults = report(
+            serde_json::json!({"priority": "medium", "urgent": false}),
+            serde_json::json!({}),
+        );
+        assert_eq!(verification.concerns(&defaults).len(), 1);
+        let changed = report(
+            serde_json::json!({"priority": "high", "urgent": false}),
+            serde_json::json!({}),
+        );
+        assert!(verification.concerns(&changed).is_empty());
+    }
+
+    #[test]
+    fn contradictions_are_suspicious() {
+        let verification = Verification::default()
+            .with_contradiction(Contradiction::new("priority", "low", "urgent", true));
+        let contradictory = report(
+            serde_json::json!({"priority": "low", "urgent": true}),
+            serde_json::json!({}),
+        );
+        assert_eq!(verification.concerns(&contradictory).len(), 1);
+        let consistent = report(
+            serde_json::json!({"priority": "high", "urgent": true}),
+            serde_json::json!({}),
+        );
+        assert!(verification.concerns(&consistent).is_empty());
+    }
+
+    #[test]
+    fn low_or_missing_confidence_is_suspicious() {
+        let verification = Verification::default().with_min_confidence(0.8);
+        let output = serde_json::json!({"priority": "high", "urgent": true});
+        for (ir, suspicious) in [
+            (serde_json::json!({"__confidence__": 0.9}), false),
+            (serde_json::json!({"__confidence__": 0.5}), true),
+            (serde_json::json!({}), true),
+        ] {
+            let report = report(output.clone(), ir.clone());
+            assert_eq!(
+                !verification.concerns(&report).is_empty(),
+                suspicious,
+                "{ir}"
+            );
+        }
+    }
+}